@@ -0,0 +1,196 @@
+//! Detect→sign hot path: each group times a real builder against the straightforward version
+//! of it in `temp::core::baseline`, which produces the same instructions.
+//!
+//! Run with `cargo bench --bench hot_path --features bench`; compare the `baseline` and `real`
+//! lines of a group.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::{json, Value};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::get_associated_token_address;
+use temp::{
+    core::{
+        baseline,
+        tx::{budgeted_instructions, TxConfig},
+    },
+    dex::{
+        pump::sell_instruction,
+        raydium_clmm::{swap_v2_instruction, ClmmPool},
+    },
+    engine::{signal::parse_trade_signal, sniper::BuyTemplate},
+};
+
+const TARGETS: usize = 5;
+
+fn swap_instructions() -> Vec<Instruction> {
+    (0..4)
+        .map(|i| system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), i))
+        .collect()
+}
+
+/// A pump.fun buy by `target` in `transactionSubscribe` shape
+fn notification(target: &str) -> Value {
+    let keys = (0..24)
+        .map(|i| match i {
+            0 => json!({ "pubkey": target }),
+            _ => json!({ "pubkey": Pubkey::new_unique().to_string() }),
+        })
+        .collect::<Vec<_>>();
+    let logs = vec!["Program log: Instruction: Buy"; 40];
+    let balance = |amount: &str| {
+        json!([{ "owner": target, "mint": "Mint1111111111111111111111111111111111pump",
+            "uiTokenAmount": { "amount": amount, "decimals": 6 } }])
+    };
+    json!({ "params": { "result": {
+        "signature": "5".repeat(88),
+        "slot": 300_000_000u64,
+        "transaction": {
+            "transaction": { "message": { "accountKeys": keys, "instructions": [] } },
+            "meta": {
+                "err": null,
+                "fee": 5000,
+                "preBalances": [2_000_000_000u64],
+                "postBalances": [1_000_000_000u64],
+                "preTokenBalances": balance("0"),
+                "postTokenBalances": balance("35000000000"),
+                "logMessages": logs,
+            }
+        }
+    }}})
+}
+
+fn bench_pump(c: &mut Criterion) {
+    let (user, mint, curve) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let template = BuyTemplate::new(user);
+    let mut group = c.benchmark_group("pump_buy");
+    group.bench_function("baseline", |b| {
+        b.iter(|| baseline::pump_buy_instructions(&user, black_box(&mint), &curve, 1, 2))
+    });
+    group.bench_function("real", |b| {
+        b.iter(|| template.instructions(black_box(&mint), &curve, 1, 2))
+    });
+    group.finish();
+
+    let curve_ata = get_associated_token_address(&curve, &mint);
+    let mut group = c.benchmark_group("pump_sell");
+    group.bench_function("baseline", |b| {
+        b.iter(|| {
+            baseline::pump_sell_instruction(&user, black_box(&mint), &curve, &curve_ata, 1, 2)
+        })
+    });
+    group.bench_function("real", |b| {
+        b.iter(|| sell_instruction(&user, black_box(&mint), &curve, &curve_ata, 1, 2))
+    });
+    group.finish();
+}
+
+fn bench_clmm(c: &mut Criterion) {
+    let pool = ClmmPool {
+        id: Pubkey::new_unique(),
+        amm_config: Pubkey::new_unique(),
+        mint_0: spl_token::native_mint::ID,
+        mint_1: Pubkey::new_unique(),
+        vault_0: Pubkey::new_unique(),
+        vault_1: Pubkey::new_unique(),
+        observation: Pubkey::new_unique(),
+        decimals_0: 9,
+        decimals_1: 6,
+        tick_spacing: 60,
+        liquidity: 1 << 40,
+        sqrt_price_x64: 1 << 64,
+        tick_current: 0,
+    };
+    let tick_arrays = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let (payer, input, output) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let mut group = c.benchmark_group("clmm_swap_v2");
+    group.bench_function("baseline", |b| {
+        b.iter(|| {
+            baseline::clmm_swap_v2_instruction(
+                black_box(&pool),
+                &payer,
+                true,
+                &input,
+                &output,
+                &tick_arrays,
+                1_000_000,
+                1,
+            )
+        })
+    });
+    group.bench_function("real", |b| {
+        b.iter(|| {
+            swap_v2_instruction(
+                black_box(&pool),
+                &payer,
+                true,
+                &input,
+                &output,
+                &tick_arrays,
+                1_000_000,
+                1,
+            )
+        })
+    });
+    group.finish();
+}
+
+fn bench_compute_budget(c: &mut Criterion) {
+    let config = TxConfig::default();
+    let tip = system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+    let mut group = c.benchmark_group("compute_budget");
+    group.bench_function("baseline", |b| {
+        b.iter_batched(
+            swap_instructions,
+            |instructions| {
+                baseline::budgeted_instructions(instructions, &config, Some(tip.clone()))
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("real", |b| {
+        b.iter_batched(
+            swap_instructions,
+            |instructions| budgeted_instructions(instructions, &config, Some(tip.clone())),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_signal_dispatch(c: &mut Criterion) {
+    let target = Pubkey::new_unique().to_string();
+    let json = notification(&target);
+    let targets = (0..TARGETS)
+        .map(|i| match i {
+            0 => target.clone(),
+            _ => Pubkey::new_unique().to_string(),
+        })
+        .collect::<Vec<_>>();
+    // One notification checked against every copy target, as the event loop does
+    c.bench_function("signal_dispatch", |b| {
+        b.iter(|| {
+            for target in &targets {
+                black_box(parse_trade_signal(black_box(&json), target));
+            }
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_pump,
+    bench_clmm,
+    bench_compute_budget,
+    bench_signal_dispatch
+);
+criterion_main!(benches);
@@ -0,0 +1,248 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::common::storage::data_dir;
+
+const MAGIC: &[u8] = b"CTBSTATE";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+const CONFIG_FILES: [&str; 2] = [".env", "config.toml"];
+const JOURNAL_FILE: &str = "./src/log.txt";
+/// `.env` entries left out of archives: the wallet key and the signing service's tokens
+const SECRET_VARS: [&str; 3] = ["WALLET_PRIVATE_KEY", "WALLET_SIGNER_TOKEN", "SIGNER_TOKEN"];
+
+/// Everything needed to bring the bot up on another machine, minus the wallet key and signer
+/// tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateBundle {
+    pub version: u8,
+    pub created_at: DateTime<Utc>,
+    /// Archive path (`config/...`, `data/...`, `journal/...`) to base64 file contents
+    pub files: BTreeMap<String, String>,
+}
+
+/// `.env` contents without the lines setting one of `SECRET_VARS`, commented out or not
+fn strip_secrets(env_file: &str) -> String {
+    env_file
+        .lines()
+        .filter(|line| {
+            let name = line.trim_start().trim_start_matches('#').trim_start();
+            let name = name.strip_prefix("export ").unwrap_or(name);
+            let name = name.split('=').next().unwrap_or_default().trim();
+            !SECRET_VARS.contains(&name)
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Gathers the config, every file in the data directory and the journal
+pub fn collect_state() -> Result<StateBundle> {
+    let mut files = BTreeMap::new();
+    for config_file in CONFIG_FILES {
+        let Ok(bytes) = fs::read(config_file) else {
+            continue;
+        };
+        let contents = match config_file {
+            ".env" => strip_secrets(&String::from_utf8_lossy(&bytes)).into_bytes(),
+            _ => bytes,
+        };
+        files.insert(format!("config/{}", config_file), base64::encode(contents));
+    }
+    if let Ok(bytes) = fs::read(JOURNAL_FILE) {
+        files.insert("journal/log.txt".to_string(), base64::encode(bytes));
+    }
+
+    let root = data_dir();
+    let mut pending = vec![root.clone()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path
+                .strip_prefix(&root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.insert(
+                format!("data/{}", relative),
+                base64::encode(fs::read(&path)?),
+            );
+        }
+    }
+
+    Ok(StateBundle {
+        version: FORMAT_VERSION,
+        created_at: Utc::now(),
+        files,
+    })
+}
+
+pub(crate) fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts a bundle as `MAGIC | salt | nonce | AES-256-GCM ciphertext`
+pub fn seal(bundle: &StateBundle, passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt)?)
+        .map_err(|_| anyhow!("Invalid archive key length"))?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            serde_json::to_vec(bundle)?.as_ref(),
+        )
+        .map_err(|_| anyhow!("Failed to encrypt state archive"))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypts an archive produced by `seal`
+pub fn open(sealed: &[u8], passphrase: &str) -> Result<StateBundle> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if sealed.len() < header || !sealed.starts_with(MAGIC) {
+        return Err(anyhow!("Not a state archive"));
+    }
+    let salt = &sealed[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &sealed[MAGIC.len() + SALT_LEN..header];
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, salt)?)
+        .map_err(|_| anyhow!("Invalid archive key length"))?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), &sealed[header..])
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted archive"))?;
+
+    let bundle: StateBundle = serde_json::from_slice(&plaintext)?;
+    if bundle.version > FORMAT_VERSION {
+        return Err(anyhow!(
+            "Archive format {} is newer than this build supports",
+            bundle.version
+        ));
+    }
+    Ok(bundle)
+}
+
+/// Where an archive entry is restored to; rejects anything escaping its directory
+fn restore_path(name: &str) -> Result<PathBuf> {
+    let (section, rest) = name
+        .split_once('/')
+        .ok_or_else(|| anyhow!("Invalid archive entry: '{}'", name))?;
+    let relative = Path::new(rest);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(anyhow!("Unsafe archive entry: '{}'", name));
+    }
+    match section {
+        "config" => Ok(PathBuf::from(".").join(relative)),
+        "data" => Ok(data_dir().join(relative)),
+        "journal" => Ok(PathBuf::from(JOURNAL_FILE)),
+        _ => Err(anyhow!("Unknown archive section: '{}'", section)),
+    }
+}
+
+/// Writes an encrypted archive of the current state; returns how many files it holds
+pub fn export_state(path: &Path, passphrase: &str) -> Result<usize> {
+    let bundle = collect_state()?;
+    fs::write(path, seal(&bundle, passphrase)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(bundle.files.len())
+}
+
+/// Restores an archive; refuses to overwrite existing files unless `force` is set
+pub fn import_state(path: &Path, passphrase: &str, force: bool) -> Result<usize> {
+    let sealed = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let bundle = open(&sealed, passphrase)?;
+
+    let targets = bundle
+        .files
+        .iter()
+        .map(|(name, contents)| Ok((restore_path(name)?, base64::decode(contents)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if !force {
+        if let Some((existing, _)) = targets.iter().find(|(target, _)| target.exists()) {
+            return Err(anyhow!(
+                "{} already exists; rerun with --force to overwrite",
+                existing.display()
+            ));
+        }
+    }
+
+    for (target, contents) in &targets {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, contents)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+    Ok(targets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> StateBundle {
+        StateBundle {
+            version: FORMAT_VERSION,
+            created_at: Utc::now(),
+            files: BTreeMap::from([("data/positions.json".to_string(), base64::encode("{}"))]),
+        }
+    }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let sealed = seal(&bundle(), "correct horse").unwrap();
+        let opened = open(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.files, bundle().files);
+        assert!(open(&sealed, "wrong horse").is_err());
+    }
+
+    #[test]
+    fn test_strips_secrets_from_env() {
+        let env_file = "RPC_ENDPOINT=http://rpc\nWALLET_PRIVATE_KEY=abc\n\
+            export SIGNER_TOKEN = xyz\n# WALLET_SIGNER_TOKEN=old\nWALLET_SIGNER_TOKEN=t\n";
+        assert_eq!(strip_secrets(env_file), "RPC_ENDPOINT=http://rpc\n");
+    }
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        assert!(restore_path("data/positions.json").is_ok());
+        assert!(restore_path("data/../../etc/passwd").is_err());
+        assert!(restore_path("data//etc/passwd").is_err());
+        assert!(restore_path("wallet/key.txt").is_err());
+    }
+}
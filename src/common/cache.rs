@@ -0,0 +1,226 @@
+use std::{collections::HashMap, env, hash::Hash, str::FromStr, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::services::metrics::METRICS;
+
+/// Size and age limits of one cache
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheConfig {
+    pub max_entries: usize,
+    /// Entries older than this are treated as missing; `None` keeps them until evicted
+    pub ttl: Option<Duration>,
+}
+
+impl CacheConfig {
+    /// Reads `CACHE_<NAME>_MAX_ENTRIES` and `CACHE_<NAME>_TTL_SECS` (0 disables the TTL),
+    /// falling back to the given defaults
+    pub fn from_env(name: &str, max_entries: usize, ttl: Option<Duration>) -> Self {
+        let prefix = format!("CACHE_{}", name.to_uppercase());
+        let max_entries = env::var(format!("{}_MAX_ENTRIES", prefix))
+            .ok()
+            .and_then(|v| usize::from_str(&v).ok())
+            .unwrap_or(max_entries)
+            .max(1);
+        let ttl = match env::var(format!("{}_TTL_SECS", prefix))
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+        {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => ttl,
+        };
+        Self { max_entries, ttl }
+    }
+}
+
+struct Entry<V> {
+    value: V,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+struct Entries<K, V> {
+    map: HashMap<K, Entry<V>>,
+    /// Logical clock for least-recently-used ordering
+    tick: u64,
+}
+
+/// A map capped at `max_entries` that evicts the least recently used entry when full and
+/// drops entries past their TTL. Hits, misses, evictions and size are exported per `name`.
+pub struct BoundedCache<K, V> {
+    name: &'static str,
+    config: CacheConfig,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> BoundedCache<K, V> {
+    pub fn new(name: &'static str, config: CacheConfig) -> Self {
+        Self {
+            name,
+            config,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    /// A cache sized from the `CACHE_<NAME>_*` env vars, see [`CacheConfig::from_env`]
+    pub fn from_env(name: &'static str, max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self::new(name, CacheConfig::from_env(name, max_entries, ttl))
+    }
+
+    fn expired(&self, entry: &Entry<V>) -> bool {
+        self.config
+            .ttl
+            .is_some_and(|ttl| entry.inserted_at.elapsed() >= ttl)
+    }
+
+    pub async fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().await;
+        entries.tick += 1;
+        let tick = entries.tick;
+        let value = match entries.map.get_mut(key) {
+            Some(entry) if !self.expired(entry) => {
+                entry.last_used = tick;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.map.remove(key);
+                METRICS
+                    .cache_evictions
+                    .with_label_values(&[self.name])
+                    .inc();
+                None
+            }
+            None => None,
+        };
+        match value {
+            Some(_) => METRICS.cache_hits.with_label_values(&[self.name]).inc(),
+            None => METRICS.cache_misses.with_label_values(&[self.name]).inc(),
+        }
+        self.record_size(entries.map.len());
+        value
+    }
+
+    /// Stores `value`, returning the previous live value for `key`
+    pub async fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut entries = self.entries.lock().await;
+        entries.tick += 1;
+        let entry = Entry {
+            value,
+            inserted_at: Instant::now(),
+            last_used: entries.tick,
+        };
+        if !entries.map.contains_key(&key) && entries.map.len() >= self.config.max_entries {
+            self.make_room(&mut entries.map);
+        }
+        let previous = entries
+            .map
+            .insert(key, entry)
+            .filter(|previous| !self.expired(previous))
+            .map(|previous| previous.value);
+        self.record_size(entries.map.len());
+        previous
+    }
+
+    pub async fn remove(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().await;
+        let removed = entries.map.remove(key).map(|entry| entry.value);
+        self.record_size(entries.map.len());
+        removed
+    }
+
+    /// Removes `key` only while `predicate` holds for its value
+    pub async fn remove_if(&self, key: &K, predicate: impl FnOnce(&V) -> bool) -> bool {
+        let mut entries = self.entries.lock().await;
+        if !entries
+            .map
+            .get(key)
+            .is_some_and(|entry| predicate(&entry.value))
+        {
+            return false;
+        }
+        entries.map.remove(key);
+        self.record_size(entries.map.len());
+        true
+    }
+
+    /// Drops every entry, counting them as evictions
+    pub async fn clear(&self) {
+        let mut entries = self.entries.lock().await;
+        METRICS
+            .cache_evictions
+            .with_label_values(&[self.name])
+            .inc_by(entries.map.len() as u64);
+        entries.map.clear();
+        self.record_size(0);
+    }
+
+    pub async fn len(&self) -> usize {
+        self.entries.lock().await.map.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Drops expired entries, or failing that the least recently used one
+    fn make_room(&self, map: &mut HashMap<K, Entry<V>>) {
+        let before = map.len();
+        map.retain(|_, entry| !self.expired(entry));
+        if map.len() == before {
+            let oldest = map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = oldest {
+                map.remove(&key);
+            }
+        }
+        METRICS
+            .cache_evictions
+            .with_label_values(&[self.name])
+            .inc_by((before - map.len()) as u64);
+    }
+
+    fn record_size(&self, len: usize) {
+        METRICS
+            .cache_entries
+            .with_label_values(&[self.name])
+            .set(len as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize, ttl: Option<Duration>) -> BoundedCache<&'static str, u32> {
+        BoundedCache::new("test", CacheConfig { max_entries, ttl })
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let cache = cache(2, None);
+        cache.insert("a", 1).await;
+        cache.insert("b", 2).await;
+        assert_eq!(cache.get(&"a").await, Some(1));
+        cache.insert("c", 3).await;
+        assert_eq!(cache.len().await, 2);
+        assert_eq!(cache.get(&"b").await, None);
+        assert_eq!(cache.get(&"a").await, Some(1));
+        assert_eq!(cache.get(&"c").await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_expires_after_ttl() {
+        let cache = cache(8, Some(Duration::from_millis(50)));
+        cache.insert("a", 1).await;
+        assert_eq!(cache.get(&"a").await, Some(1));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(cache.get(&"a").await, None);
+        assert!(cache.is_empty().await);
+    }
+}
@@ -0,0 +1,732 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    fs,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use tokio::{sync::watch, time::sleep};
+use toml_edit::{value, Array, DocumentMut};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::{
+        dual_control::{submit, ControlAction},
+        sizing::CopySizing,
+        wallets::WalletMode,
+    },
+    services::{jito::region_url, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const DEFAULT_WALLET_PATH: &str = "./key.txt";
+const MAX_UNIT_LIMIT: u32 = 1_400_000;
+const DEFAULT_SLIPPAGE_BPS: u64 = 10_000;
+const DEFAULT_WATCH_SECS: u64 = 2;
+
+/// RPC endpoints
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub endpoint: Option<String>,
+    pub websocket_endpoint: Option<String>,
+    /// Extra endpoints transactions are broadcast through
+    pub broadcast_endpoints: Vec<String>,
+    /// Endpoints reads fail over to when `endpoint` is down, slow or behind
+    pub fallback_endpoints: Vec<String>,
+}
+
+/// Where the signing key lives
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WalletConfig {
+    pub key_path: Option<String>,
+    /// More key files to rotate or split copy buys across
+    pub extra_key_paths: Vec<String>,
+    /// `rotate` or `split:<wallets>`
+    pub mode: Option<String>,
+}
+
+/// Who to copy and how much to spend
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CopyConfig {
+    /// Wallets whose trades are copied; reloaded live
+    pub targets: Vec<String>,
+    /// Wallet whose transactions are ignored in the stream (the Jupiter fee account)
+    pub ignored_pubkey: Option<String>,
+    /// `fixed:<sol>`, `usd:<amount>`, `balance:<pct>` or `proportional:<pct>`
+    pub sizing: Option<String>,
+    pub min_sol: Option<f64>,
+    pub max_sol: Option<f64>,
+}
+
+/// Pre-trade checks and automatic exits
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    /// `reject` or `flag`
+    pub safety_mode: Option<String>,
+    pub max_creator_pct: Option<f64>,
+    pub min_lp_burned_pct: Option<f64>,
+    /// e.g. `15m`
+    pub max_hold_time: Option<String>,
+    pub approval_min_sol: Option<f64>,
+    /// Slippage on copied swaps; reloaded live
+    pub slippage_bps: Option<u64>,
+    /// Refuse buys that would move the pool's price further than this
+    pub max_price_impact_bps: Option<u64>,
+    /// Sell a position once its price falls this far below the first price seen; reloaded live
+    pub stop_loss_pct: Option<f64>,
+}
+
+/// Jito block engine settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JitoConfig {
+    pub block_engine_url: Option<String>,
+    /// Block engine regions (`ny`, `amsterdam`, `frankfurt`, `tokyo`, ...) or URLs, most
+    /// preferred first; replaces `block_engine_url` when set
+    pub regions: Vec<String>,
+    /// Send each bundle to every region at once, counting whichever lands it first
+    pub fan_out: Option<bool>,
+    pub tip_stream_url: Option<String>,
+    pub tip_percentile: Option<String>,
+}
+
+/// Priority fee settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComputeConfig {
+    pub unit_price: Option<u64>,
+    pub unit_limit: Option<u32>,
+}
+
+/// Typed bot settings from `config.toml`, with every field overridable by its env var
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rpc: RpcConfig,
+    pub wallet: WalletConfig,
+    #[serde(rename = "copy")]
+    pub copy_trading: CopyConfig,
+    pub risk: RiskConfig,
+    pub jito: JitoConfig,
+    pub compute: ComputeConfig,
+}
+
+/// Splits a comma-separated env list, dropping blanks
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Replaces `slot` with the parsed override `key`, when set
+fn override_from<T>(
+    overrides: &HashMap<String, String>,
+    slot: &mut Option<T>,
+    key: &str,
+) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(value) = overrides.get(key) {
+        let parsed = T::from_str(&value).map_err(|e| anyhow!("Invalid {}: {}", key, e))?;
+        *slot = Some(parsed);
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Parses TOML settings without applying env overrides
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).context("Invalid config file")
+    }
+
+    /// Config file from `CONFIG_PATH`, defaulting to `config.toml`
+    pub fn path() -> String {
+        env::var("CONFIG_PATH").unwrap_or(DEFAULT_CONFIG_PATH.to_string())
+    }
+
+    /// Loads the config file (a missing file means env vars only), applies the process
+    /// environment as overrides and validates the result
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path(), &env::vars().collect())
+    }
+
+    /// Like `load`, with an explicit file and override set
+    pub fn load_from(path: &str, overrides: &HashMap<String, String>) -> Result<Self> {
+        let mut config = match fs::read_to_string(path) {
+            Ok(toml) => {
+                Self::from_toml(&toml).with_context(|| format!("Failed to load {}", path))?
+            }
+            Err(_) if !Path::new(path).exists() => Self::default(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+        };
+        config.apply_overrides(overrides)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Env vars win over the file
+    pub fn apply_overrides(&mut self, env: &HashMap<String, String>) -> Result<()> {
+        override_from(env, &mut self.rpc.endpoint, "RPC_ENDPOINT")?;
+        override_from(
+            env,
+            &mut self.rpc.websocket_endpoint,
+            "RPC_WEBSOCKET_ENDPOINT",
+        )?;
+        if let Some(endpoints) = env.get("RPC_BROADCAST_ENDPOINTS") {
+            self.rpc.broadcast_endpoints = split_list(endpoints);
+        }
+        if let Some(endpoints) = env.get("RPC_FALLBACK_ENDPOINTS") {
+            self.rpc.fallback_endpoints = split_list(endpoints);
+        }
+        override_from(env, &mut self.wallet.key_path, "WALLET_PATH")?;
+        if let Some(paths) = env.get("WALLET_PATHS") {
+            self.wallet.extra_key_paths = split_list(paths);
+        }
+        override_from(env, &mut self.wallet.mode, "WALLET_MODE")?;
+
+        let copy = &mut self.copy_trading;
+        if let Some(targets) = env.get("TARGET_PUBKEY") {
+            copy.targets = split_list(targets);
+        }
+        override_from(env, &mut copy.ignored_pubkey, "JUP_PUBKEY")?;
+        override_from(env, &mut copy.sizing, "COPY_SIZING")?;
+        override_from(env, &mut copy.min_sol, "COPY_MIN_SOL")?;
+        override_from(env, &mut copy.max_sol, "COPY_MAX_SOL")?;
+
+        let risk = &mut self.risk;
+        override_from(env, &mut risk.safety_mode, "SAFETY_MODE")?;
+        override_from(env, &mut risk.max_creator_pct, "SAFETY_MAX_CREATOR_PCT")?;
+        override_from(env, &mut risk.min_lp_burned_pct, "SAFETY_MIN_LP_BURNED_PCT")?;
+        override_from(env, &mut risk.max_hold_time, "MAX_HOLD_TIME")?;
+        override_from(env, &mut risk.approval_min_sol, "APPROVAL_MIN_SOL")?;
+        override_from(env, &mut risk.slippage_bps, "COPY_SLIPPAGE_BPS")?;
+        override_from(env, &mut risk.max_price_impact_bps, "MAX_PRICE_IMPACT_BPS")?;
+        override_from(env, &mut risk.stop_loss_pct, "STOP_LOSS_PCT")?;
+
+        override_from(
+            env,
+            &mut self.jito.block_engine_url,
+            "JITO_BLOCK_ENGINE_URL",
+        )?;
+        if let Some(regions) = env.get("JITO_REGIONS") {
+            self.jito.regions = split_list(regions);
+        }
+        override_from(env, &mut self.jito.fan_out, "JITO_FANOUT")?;
+        override_from(env, &mut self.jito.tip_stream_url, "JITO_TIP_STREAM_URL")?;
+        override_from(env, &mut self.jito.tip_percentile, "JITO_TIP_PERCENTILE")?;
+
+        override_from(env, &mut self.compute.unit_price, "UNIT_PRICE")?;
+        override_from(env, &mut self.compute.unit_limit, "UNIT_LIMIT")?;
+        Ok(())
+    }
+
+    /// Rejects missing required settings and malformed values, naming every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        let mut require = |value: &Option<String>, name: &str| {
+            if value.as_deref().map_or(true, str::is_empty) {
+                problems.push(format!("{} is required", name));
+            }
+        };
+        require(&self.rpc.endpoint, "rpc.endpoint");
+        require(&self.rpc.websocket_endpoint, "rpc.websocket_endpoint");
+        require(&self.copy_trading.ignored_pubkey, "copy.ignored_pubkey");
+        if self.jito.regions.is_empty() {
+            require(&self.jito.block_engine_url, "jito.block_engine_url");
+        }
+        require(&self.jito.tip_stream_url, "jito.tip_stream_url");
+        require(&self.jito.tip_percentile, "jito.tip_percentile");
+        if self.copy_trading.targets.is_empty() {
+            problems.push("copy.targets needs at least one wallet".to_string());
+        }
+
+        let http_endpoints = self
+            .rpc
+            .endpoint
+            .iter()
+            .chain(&self.rpc.broadcast_endpoints)
+            .chain(&self.rpc.fallback_endpoints);
+        for endpoint in http_endpoints {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                problems.push(format!("RPC endpoint '{}' must be http(s)", endpoint));
+            }
+        }
+        if let Some(ws) = &self.rpc.websocket_endpoint {
+            if !ws.starts_with("ws://") && !ws.starts_with("wss://") {
+                problems.push(format!("rpc.websocket_endpoint '{}' must be ws(s)", ws));
+            }
+        }
+        for region in &self.jito.regions {
+            if let Err(e) = region_url(region) {
+                problems.push(format!("jito.regions: {}", e));
+            }
+        }
+        let pubkeys = self
+            .copy_trading
+            .targets
+            .iter()
+            .map(|target| ("copy.targets", target.as_str()))
+            .chain(
+                self.copy_trading
+                    .ignored_pubkey
+                    .as_deref()
+                    .filter(|p| !p.is_empty())
+                    .map(|pubkey| ("copy.ignored_pubkey", pubkey)),
+            );
+        for (name, pubkey) in pubkeys {
+            if Pubkey::from_str(pubkey).is_err() {
+                problems.push(format!("{} '{}' is not a valid pubkey", name, pubkey));
+            }
+        }
+        // A key given inline in WALLET_PRIVATE_KEY or held by a device replaces the primary key file
+        let keyless = env::var_os("WALLET_PRIVATE_KEY").is_some()
+            || env::var("WALLET_SIGNER").is_ok_and(|signer| signer != "keypair");
+        let primary = (!keyless).then(|| self.wallet_path());
+        let key_paths = primary
+            .into_iter()
+            .chain(self.wallet.extra_key_paths.iter().map(String::as_str));
+        for path in key_paths {
+            if !Path::new(path).exists() {
+                problems.push(format!("wallet key file {} not found", path));
+            }
+        }
+        if let Some(mode) = &self.wallet.mode {
+            if let Err(e) = WalletMode::from_str(mode) {
+                problems.push(format!("wallet.mode: {}", e));
+            }
+        }
+
+        let copy = &self.copy_trading;
+        if let Some(sizing) = &copy.sizing {
+            if let Err(e) = CopySizing::from_str(sizing) {
+                problems.push(format!("copy.sizing: {}", e));
+            }
+        }
+        if let (Some(min), Some(max)) = (copy.min_sol, copy.max_sol) {
+            if min > max {
+                problems.push(format!("copy.min_sol {} exceeds copy.max_sol {}", min, max));
+            }
+        }
+        if let Some(mode) = &self.risk.safety_mode {
+            if mode != "reject" && mode != "flag" {
+                problems.push(format!(
+                    "risk.safety_mode '{}' must be reject or flag",
+                    mode
+                ));
+            }
+        }
+        for (name, pct) in [
+            ("risk.max_creator_pct", self.risk.max_creator_pct),
+            ("risk.min_lp_burned_pct", self.risk.min_lp_burned_pct),
+            ("risk.stop_loss_pct", self.risk.stop_loss_pct),
+        ] {
+            if pct.is_some_and(|pct| !(0.0..=100.0).contains(&pct)) {
+                problems.push(format!("{} must be between 0 and 100", name));
+            }
+        }
+        if self.risk.slippage_bps.is_some_and(|bps| bps > 10_000) {
+            problems.push("risk.slippage_bps exceeds 10000".to_string());
+        }
+        if self.risk.max_price_impact_bps.is_some_and(|bps| bps > 10_000) {
+            problems.push("risk.max_price_impact_bps exceeds 10000".to_string());
+        }
+        if self
+            .compute
+            .unit_limit
+            .is_some_and(|limit| limit > MAX_UNIT_LIMIT)
+        {
+            problems.push(format!("compute.unit_limit exceeds {}", MAX_UNIT_LIMIT));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Invalid configuration:\n  {}",
+                problems.join("\n  ")
+            ))
+        }
+    }
+
+    pub fn wallet_path(&self) -> &str {
+        self.wallet
+            .key_path
+            .as_deref()
+            .unwrap_or(DEFAULT_WALLET_PATH)
+    }
+
+    /// Settings as the env vars the rest of the bot reads them from
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = Vec::new();
+        let mut push = |key: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                vars.push((key, value));
+            }
+        };
+        push("RPC_ENDPOINT", self.rpc.endpoint.clone());
+        push(
+            "RPC_WEBSOCKET_ENDPOINT",
+            self.rpc.websocket_endpoint.clone(),
+        );
+        if !self.rpc.broadcast_endpoints.is_empty() {
+            push(
+                "RPC_BROADCAST_ENDPOINTS",
+                Some(self.rpc.broadcast_endpoints.join(",")),
+            );
+        }
+        if !self.rpc.fallback_endpoints.is_empty() {
+            push(
+                "RPC_FALLBACK_ENDPOINTS",
+                Some(self.rpc.fallback_endpoints.join(",")),
+            );
+        }
+        push("WALLET_PATH", Some(self.wallet_path().to_string()));
+        if !self.wallet.extra_key_paths.is_empty() {
+            push("WALLET_PATHS", Some(self.wallet.extra_key_paths.join(",")));
+        }
+        push("WALLET_MODE", self.wallet.mode.clone());
+
+        let copy = &self.copy_trading;
+        push("TARGET_PUBKEY", Some(copy.targets.join(",")));
+        push("JUP_PUBKEY", copy.ignored_pubkey.clone());
+        push("COPY_SIZING", copy.sizing.clone());
+        push("COPY_MIN_SOL", copy.min_sol.map(|v| v.to_string()));
+        push("COPY_MAX_SOL", copy.max_sol.map(|v| v.to_string()));
+
+        let risk = &self.risk;
+        push("SAFETY_MODE", risk.safety_mode.clone());
+        push(
+            "SAFETY_MAX_CREATOR_PCT",
+            risk.max_creator_pct.map(|v| v.to_string()),
+        );
+        push(
+            "SAFETY_MIN_LP_BURNED_PCT",
+            risk.min_lp_burned_pct.map(|v| v.to_string()),
+        );
+        push("MAX_HOLD_TIME", risk.max_hold_time.clone());
+        push(
+            "COPY_SLIPPAGE_BPS",
+            risk.slippage_bps.map(|v| v.to_string()),
+        );
+        push("STOP_LOSS_PCT", risk.stop_loss_pct.map(|v| v.to_string()));
+        push(
+            "MAX_PRICE_IMPACT_BPS",
+            risk.max_price_impact_bps.map(|v| v.to_string()),
+        );
+        push(
+            "APPROVAL_MIN_SOL",
+            risk.approval_min_sol.map(|v| v.to_string()),
+        );
+
+        push("JITO_BLOCK_ENGINE_URL", self.jito.block_engine_url.clone());
+        if !self.jito.regions.is_empty() {
+            push("JITO_REGIONS", Some(self.jito.regions.join(",")));
+        }
+        push("JITO_FANOUT", self.jito.fan_out.map(|v| v.to_string()));
+        push("JITO_TIP_STREAM_URL", self.jito.tip_stream_url.clone());
+        push("JITO_TIP_PERCENTILE", self.jito.tip_percentile.clone());
+
+        push("UNIT_PRICE", self.compute.unit_price.map(|v| v.to_string()));
+        push("UNIT_LIMIT", self.compute.unit_limit.map(|v| v.to_string()));
+        vars
+    }
+
+    /// Publishes the merged settings to the process environment so modules reading env vars
+    /// see the file's values; call once at startup, before spawning anything
+    pub fn export_env(&self) {
+        for (key, value) in self.env_vars() {
+            env::set_var(key, value);
+        }
+    }
+
+    /// The settings the running engine picks up without a restart
+    pub fn live(&self) -> LiveSettings {
+        LiveSettings {
+            targets: self.copy_trading.targets.clone(),
+            slippage_bps: self.risk.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS),
+            stop_loss_pct: self.risk.stop_loss_pct,
+        }
+    }
+}
+
+/// Hot-reloadable settings, always swapped in as a whole
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveSettings {
+    pub targets: Vec<String>,
+    pub slippage_bps: u64,
+    pub stop_loss_pct: Option<f64>,
+}
+
+impl LiveSettings {
+    /// One line per setting that differs from `previous`
+    pub fn changes_from(&self, previous: &LiveSettings) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.targets != previous.targets {
+            changes.push(format!("targets: {}", self.targets.join(", ")));
+        }
+        if self.slippage_bps != previous.slippage_bps {
+            changes.push(format!(
+                "slippage: {} -> {} bps",
+                previous.slippage_bps, self.slippage_bps
+            ));
+        }
+        if self.stop_loss_pct != previous.stop_loss_pct {
+            changes.push(format!(
+                "stop loss: {:?} -> {:?} %",
+                previous.stop_loss_pct, self.stop_loss_pct
+            ));
+        }
+        changes
+    }
+}
+
+/// The current `LiveSettings`; readers take a snapshot, so a reload never shows half an update
+pub struct LiveConfig {
+    sender: watch::Sender<Arc<LiveSettings>>,
+}
+
+impl LiveConfig {
+    pub fn new(settings: LiveSettings) -> Self {
+        Self {
+            sender: watch::Sender::new(Arc::new(settings)),
+        }
+    }
+
+    pub fn current(&self) -> Arc<LiveSettings> {
+        self.sender.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<LiveSettings>> {
+        self.sender.subscribe()
+    }
+
+    pub fn replace(&self, settings: LiveSettings) {
+        self.sender.send_replace(Arc::new(settings));
+    }
+}
+
+/// Adds (`track`) or removes `wallet` in the config file's `copy.targets`, keeping the rest of
+/// the file as written. Returns whether the list changed; a running bot picks it up live
+pub fn edit_targets(path: &str, wallet: &str, track: bool) -> Result<bool> {
+    Pubkey::from_str(wallet).map_err(|_| anyhow!("{} is not a wallet address", wallet))?;
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) if !Path::new(path).exists() => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+    };
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .with_context(|| format!("Failed to parse {}", path))?;
+    let targets = document["copy"]["targets"]
+        .or_insert(value(Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("copy.targets in {} is not a list", path))?;
+    let present = targets.iter().any(|target| target.as_str() == Some(wallet));
+    if track == present {
+        return Ok(false);
+    }
+    if track {
+        targets.push(wallet);
+    } else {
+        targets.retain(|target| target.as_str() != Some(wallet));
+    }
+    fs::write(path, document.to_string()).with_context(|| format!("Failed to write {}", path))?;
+    Ok(true)
+}
+
+fn modified_at(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Polls the config file every `CONFIG_WATCH_SECS` and swaps in its live settings when it
+/// changes; `env_overrides` is the environment captured before `export_env`, so the real
+/// env vars keep winning over edits. Invalid edits are logged and the old settings kept.
+pub async fn run_config_watcher(state: AppState, env_overrides: HashMap<String, String>) {
+    let watch_secs = env::var("CONFIG_WATCH_SECS")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_WATCH_SECS)
+        .max(1);
+    let path = Config::path();
+    let mut last_modified = modified_at(&path);
+
+    loop {
+        sleep(Duration::from_secs(watch_secs)).await;
+        let modified = modified_at(&path);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        let settings = match Config::load_from(&path, &env_overrides) {
+            Ok(config) => config.live(),
+            Err(e) => {
+                let _ = log_message(&format!(
+                    "Config reload rejected, keeping settings: {:#}",
+                    e
+                ))
+                .await;
+                continue;
+            }
+        };
+        let changes = settings.changes_from(&state.settings.current());
+        if changes.is_empty() {
+            continue;
+        }
+        if state.control.required() > 1 {
+            // Edits to a shared bot wait for the operators' sign-off
+            let summary = changes.join("; ");
+            submit(
+                &state,
+                ControlAction::ConfigChange { settings, summary },
+                None,
+            )
+            .await;
+            continue;
+        }
+        state.settings.replace(settings);
+        let message = format!("⚙️ Config reloaded: {}", changes.join("; "));
+        let _ = log_message(&message).await;
+        state.notifier.notify(Event::Info(message)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_sections() {
+        let config = Config::from_toml(
+            r#"
+            [rpc]
+            endpoint = "https://rpc.example.com"
+            broadcast_endpoints = ["https://a.example.com", "https://b.example.com"]
+
+            [copy]
+            targets = ["11111111111111111111111111111111"]
+            sizing = "fixed:0.05"
+            max_sol = 0.5
+
+            [compute]
+            unit_limit = 200000
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.rpc.endpoint.as_deref(),
+            Some("https://rpc.example.com")
+        );
+        assert_eq!(config.rpc.broadcast_endpoints.len(), 2);
+        assert_eq!(config.copy_trading.max_sol, Some(0.5));
+        assert_eq!(config.compute.unit_limit, Some(200_000));
+        assert_eq!(config.wallet_path(), DEFAULT_WALLET_PATH);
+
+        let vars = config.env_vars();
+        assert!(vars.contains(&("COPY_SIZING", "fixed:0.05".to_string())));
+        assert!(vars.contains(&(
+            "RPC_BROADCAST_ENDPOINTS",
+            "https://a.example.com,https://b.example.com".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_rejects_unknown_keys_and_bad_values() {
+        assert!(Config::from_toml("[rpc]\nendpiont = \"https://x\"").is_err());
+
+        let config = Config::from_toml(
+            r#"
+            [rpc]
+            endpoint = "rpc.example.com"
+            [copy]
+            sizing = "fixed:-1"
+            min_sol = 2.0
+            max_sol = 1.0
+            [jito]
+            regions = ["ny", "mars"]
+            "#,
+        )
+        .unwrap();
+        let problems = config.validate().unwrap_err().to_string();
+        assert!(problems.contains("must be http(s)"));
+        assert!(problems.contains("Unknown Jito region 'mars'"));
+        assert!(!problems.contains("jito.block_engine_url"));
+        assert!(problems.contains("copy.sizing"));
+        assert!(problems.contains("exceeds copy.max_sol"));
+        assert!(problems.contains("copy.targets needs at least one wallet"));
+    }
+
+    #[test]
+    fn test_env_overrides_win_over_file() {
+        let mut config = Config::from_toml(
+            r#"
+            [copy]
+            targets = ["11111111111111111111111111111111"]
+            [risk]
+            slippage_bps = 500
+            stop_loss_pct = 30
+            "#,
+        )
+        .unwrap();
+        let overrides = HashMap::from([
+            ("TARGET_PUBKEY".to_string(), "a, b,".to_string()),
+            ("STOP_LOSS_PCT".to_string(), "20".to_string()),
+        ]);
+        config.apply_overrides(&overrides).unwrap();
+
+        let live = config.live();
+        assert_eq!(live.targets, vec!["a", "b"]);
+        assert_eq!(live.slippage_bps, 500);
+        assert_eq!(live.stop_loss_pct, Some(20.0));
+
+        let previous = LiveSettings {
+            slippage_bps: 500,
+            stop_loss_pct: Some(30.0),
+            ..live.clone()
+        };
+        assert_eq!(
+            live.changes_from(&previous),
+            vec!["stop loss: Some(30.0) -> Some(20.0) %"]
+        );
+
+        let bad = HashMap::from([("STOP_LOSS_PCT".to_string(), "lots".to_string())]);
+        assert!(config.apply_overrides(&bad).is_err());
+    }
+
+    #[test]
+    fn test_edit_targets_keeps_comments() {
+        let path = env::temp_dir().join(format!("targets-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        let wallet = "So11111111111111111111111111111111111111112";
+        fs::write(path, "[copy]\ntargets = [] # TARGET_PUBKEY\n").unwrap();
+
+        assert!(edit_targets(path, wallet, true).unwrap());
+        assert!(!edit_targets(path, wallet, true).unwrap());
+        let contents = fs::read_to_string(path).unwrap();
+        assert!(contents.contains("# TARGET_PUBKEY"));
+        assert_eq!(
+            Config::from_toml(&contents).unwrap().copy_trading.targets,
+            vec![wallet]
+        );
+
+        assert!(edit_targets(path, wallet, false).unwrap());
+        assert!(edit_targets(path, "not a wallet", true).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
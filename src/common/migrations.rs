@@ -0,0 +1,186 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::storage::EventTime;
+
+// Configuration constants
+const VERSION_FILE: &str = "schema_version.json";
+
+/// One step of the storage schema. `apply` rewrites every record of `files` in place; it runs
+/// on each line of a `.jsonl` file and on the whole document of a `.json` file
+struct Migration {
+    description: &'static str,
+    files: &'static [&'static str],
+    apply: fn(file: &str, record: &mut Value),
+}
+
+/// Every schema change so far, oldest first; a migration's version is its position plus one.
+/// Only ever append to this list
+const MIGRATIONS: &[Migration] = &[Migration {
+    description: "add event times to stored records",
+    files: &[
+        "positions.json",
+        "trades.jsonl",
+        "executions.jsonl",
+        "audit.jsonl",
+        "remnant_sweeps.jsonl",
+    ],
+    apply: add_event_times,
+}];
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionRecord {
+    version: u32,
+    migrated_at: DateTime<Utc>,
+}
+
+fn add_event_times(file: &str, record: &mut Value) {
+    let time = serde_json::to_value(EventTime::default()).unwrap_or_default();
+    let stamp = |record: &mut Value| {
+        if let Some(record) = record.as_object_mut() {
+            record.entry("time").or_insert_with(|| time.clone());
+        }
+    };
+    // Positions carry their times on each fill
+    if file == "positions.json" {
+        for position in record
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|p| p.values_mut())
+        {
+            if let Some(fills) = position["fills"].as_array_mut() {
+                fills.iter_mut().for_each(stamp);
+            }
+        }
+    } else {
+        stamp(record);
+    }
+}
+
+/// Version the data in `dir` is at: a directory without a version file but with data predates
+/// versioning, an empty one starts out current
+pub fn schema_version(dir: &Path) -> Result<u32> {
+    let path = dir.join(VERSION_FILE);
+    if path.exists() {
+        let bytes =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let record = serde_json::from_slice::<VersionRecord>(&bytes)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        return Ok(record.version);
+    }
+    let has_data = fs::read_dir(dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    Ok(if has_data { 0 } else { SCHEMA_VERSION })
+}
+
+fn write_version(dir: &Path, version: u32) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let record = VersionRecord {
+        version,
+        migrated_at: Utc::now(),
+    };
+    fs::write(dir.join(VERSION_FILE), serde_json::to_vec_pretty(&record)?)?;
+    Ok(())
+}
+
+/// Rewrites one file through `migration`, keeping the original as `<file>.v<from>.bak`
+fn migrate_file(dir: &Path, file: &str, from: u32, migration: &Migration) -> Result<()> {
+    let path = dir.join(file);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let rewritten = if file.ends_with(".jsonl") {
+        let mut lines = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut record = serde_json::from_str::<Value>(line)
+                .with_context(|| format!("Bad record on line {} of {}", i + 1, path.display()))?;
+            (migration.apply)(file, &mut record);
+            lines.push(serde_json::to_string(&record)?);
+        }
+        lines
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect::<String>()
+    } else {
+        let mut document = serde_json::from_str::<Value>(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        (migration.apply)(file, &mut document);
+        serde_json::to_string_pretty(&document)?
+    };
+    fs::copy(&path, dir.join(format!("{}.v{}.bak", file, from)))?;
+    let tmp = dir.join(format!("{}.tmp", file));
+    fs::write(&tmp, rewritten)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Brings the data in `dir` up to `SCHEMA_VERSION`, one migration at a time, and returns the
+/// descriptions of those applied. Data from a newer build is refused rather than misread
+pub fn migrate(dir: &Path) -> Result<Vec<&'static str>> {
+    let version = schema_version(dir)?;
+    if version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "{} is at schema version {}, newer than this build's {}",
+            dir.display(),
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+    let mut applied = Vec::new();
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        for file in migration.files {
+            migrate_file(dir, file, i as u32, migration)
+                .with_context(|| format!("Migration to version {} failed", i + 1))?;
+        }
+        write_version(dir, i as u32 + 1)?;
+        applied.push(migration.description);
+    }
+    if !dir.join(VERSION_FILE).exists() {
+        write_version(dir, SCHEMA_VERSION)?;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::position::{ClosedTrade, Position};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_migrates_unversioned_data() {
+        let dir = std::env::temp_dir().join(format!("migrations-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let trade = r#"{"mint":"m","opened_at":"2024-01-01T00:00:00Z","closed_at":"2024-01-01T01:00:00Z","sol_invested":1,"sol_returned":2,"fees_paid":0}"#;
+        fs::write(dir.join("trades.jsonl"), format!("{}\n", trade)).unwrap();
+        let positions = r#"{"m":{"mint":"m","pool_id":null,"sol_invested":1,"sol_returned":0,"opened_at":"2024-01-01T00:00:00Z","fills":[{"at":"2024-01-01T00:00:00Z","side":"buy","lamports":1,"fees":0}]}}"#;
+        fs::write(dir.join("positions.json"), positions).unwrap();
+
+        assert_eq!(schema_version(&dir).unwrap(), 0);
+        assert_eq!(migrate(&dir).unwrap().len(), MIGRATIONS.len());
+        assert_eq!(schema_version(&dir).unwrap(), SCHEMA_VERSION);
+        assert!(migrate(&dir).unwrap().is_empty());
+
+        let migrated = fs::read_to_string(dir.join("trades.jsonl")).unwrap();
+        let trade = serde_json::from_str::<Value>(migrated.trim()).unwrap();
+        assert!(trade["time"].is_object());
+        serde_json::from_value::<ClosedTrade>(trade).unwrap();
+        let positions = fs::read(dir.join("positions.json")).unwrap();
+        let positions = serde_json::from_slice::<HashMap<String, Position>>(&positions).unwrap();
+        assert_eq!(positions["m"].fills[0].time, EventTime::default());
+        assert!(dir.join("trades.jsonl.v0.bak").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
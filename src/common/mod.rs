@@ -1 +1,7 @@
+pub mod archive;
+pub mod cache;
+pub mod config;
+pub mod migrations;
+pub mod storage;
 pub mod utils;
+pub mod wallet;
@@ -0,0 +1,109 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+const DEFAULT_DATA_DIR: &str = "./data";
+
+/// Start of this run on the monotonic and wall clocks, taken the first time anything is timed
+static STARTED: LazyLock<(Instant, DateTime<Utc>)> = LazyLock::new(|| (Instant::now(), Utc::now()));
+
+/// When a stored record happened, on every clock we have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventTime {
+    /// Slot the event landed in, for on-chain events
+    pub slot: Option<u64>,
+    /// Block time of that slot
+    pub block_time: Option<DateTime<Utc>>,
+    /// Milliseconds since `run_started` on the monotonic clock; immune to wall clock jumps
+    /// but only comparable within one run
+    pub monotonic_ms: u64,
+    /// Start of the run `monotonic_ms` counts from
+    pub run_started: Option<DateTime<Utc>>,
+}
+
+impl EventTime {
+    /// An off-chain event happening now
+    pub fn now() -> Self {
+        let (started, run_started) = *STARTED;
+        Self {
+            slot: None,
+            block_time: None,
+            monotonic_ms: started.elapsed().as_millis() as u64,
+            run_started: Some(run_started),
+        }
+    }
+
+    /// An event that landed in `slot`, with the block's unix timestamp when known
+    pub fn on_chain(slot: u64, block_time: Option<i64>) -> Self {
+        Self {
+            slot: Some(slot),
+            block_time: block_time.and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            ..Self::now()
+        }
+    }
+}
+
+/// Directory holding the bot's persisted state, from `DATA_DIR`
+pub fn data_dir() -> PathBuf {
+    env::var("DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_DATA_DIR))
+}
+
+/// Path of a named state file inside the data directory
+pub fn data_path(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Writes `value` as pretty JSON to `path`, via a temp file so readers never see half a file
+pub async fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let json = serde_json::to_vec_pretty(value)?;
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, json)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Reads JSON from `path`; a missing file is `Ok(None)`
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match std::fs::read(path) {
+        Ok(bytes) => {
+            Ok(Some(serde_json::from_slice(&bytes).with_context(|| {
+                format!("Failed to parse {}", path.display())
+            })?))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Appends `value` as one JSON line to `path`, creating the file and its directory if needed
+pub async fn append_json_line<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(&line).await?;
+    Ok(())
+}
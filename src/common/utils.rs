@@ -4,11 +4,89 @@ use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
 use std::process;
 use std::{env, sync::Arc};
 
+use crate::common::config::LiveConfig;
+use crate::common::wallet::{load_signer, load_wallet};
+use crate::core::{rpc_pool::RpcPool, tx::TxSigner};
+use crate::engine::{
+    approval::ApprovalBook, copy_timing::CopyTiming, creator_exit::CreatorWatch,
+    dual_control::DualControl, fees::FeeModel, position::PositionManager, reorg::ReorgGuard,
+    router::Router, sizing::SizingConfig, target_exit::TargetExits, wallets::WalletPool,
+    watchlist::Watchlist,
+};
+use crate::risk::{
+    breaker::LossBreaker, expectancy::ExpectancyGate, filters::TokenFilters,
+    holders::HolderTracker, impairment::ImpairedAction, limits::ExposureLimits,
+    market::MarketBounds, maturity::MaturityFilter, pause::CopyPause, tilt::TiltGuard,
+    token_safety::SafetyConfig,
+};
+use crate::services::{
+    alerts::AlertBook, curve_cache::CurveCache, notify::Notifier, price::PriceService,
+    price_feed::PriceFeed, slo::SloMonitor,
+};
+
 #[derive(Clone)]
 pub struct AppState {
+    /// Read endpoints behind `rpc_client` and `rpc_nonblocking_client`, with failover
+    pub rpc_pool: Arc<RpcPool>,
     pub rpc_client: Arc<solana_client::rpc_client::RpcClient>,
     pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
-    pub wallet: Arc<Keypair>,
+    /// Wallet this state trades from; the pool's primary unless narrowed by `with_wallet`
+    pub wallet: Arc<TxSigner>,
+    pub wallets: Arc<WalletPool>,
+    pub reorg_guard: Arc<ReorgGuard>,
+    pub positions: Arc<PositionManager>,
+    pub router: Arc<Router>,
+    pub notifier: Arc<Notifier>,
+    pub price_feed: Arc<PriceFeed>,
+    /// Off-chain USD prices, market caps and liquidity
+    pub prices: Arc<PriceService>,
+    /// Bonding curves of open positions, pushed over the websocket
+    pub curves: Arc<CurveCache>,
+    pub alerts: Arc<AlertBook>,
+    pub sizing: SizingConfig,
+    /// Separate sizing for the target's own launches; `None` copies them like any buy
+    pub creator_sizing: Option<SizingConfig>,
+    pub safety: SafetyConfig,
+    /// Market cap and liquidity a token needs before buys are copied into it
+    pub market: MarketBounds,
+    /// Minimum token age and holder count before buys are copied into it
+    pub maturity: Arc<MaturityFilter>,
+    pub watchlist: Arc<Watchlist>,
+    pub fees: FeeModel,
+    pub expectancy: Arc<ExpectancyGate>,
+    pub holders: Arc<HolderTracker>,
+    pub approvals: Arc<ApprovalBook>,
+    /// Targets and risk limits that follow config file edits
+    pub settings: Arc<LiveConfig>,
+    pub control: Arc<DualControl>,
+    pub slo: Arc<SloMonitor>,
+    pub creators: Arc<CreatorWatch>,
+    /// Cost basis of each target's buys, for classifying their sells
+    pub target_exits: Arc<TargetExits>,
+    /// Per-trade, position count and exposure caps on new buys
+    pub limits: Arc<ExposureLimits>,
+    /// Halts new buys for the rest of the day after too large a loss
+    pub loss_breaker: Arc<LossBreaker>,
+    /// Pauses new buys for a while after a losing streak or one outsized loss
+    pub tilt: Arc<TiltGuard>,
+    /// Stops new buys while an operator has copying paused
+    pub pause: Arc<CopyPause>,
+    /// Token and creator black/whitelists, editable at runtime
+    pub filters: Arc<TokenFilters>,
+    /// What happens to positions whose tokens can no longer be sold
+    pub impaired_action: ImpairedAction,
+    /// Per-target copy delay, debounce and quiet hours
+    pub timing: Arc<CopyTiming>,
+}
+
+impl AppState {
+    /// The same state signing and paying with `wallet`, for trades on another pool wallet
+    pub fn with_wallet(&self, wallet: Arc<TxSigner>) -> Self {
+        Self {
+            wallet,
+            ..self.clone()
+        }
+    }
 }
 
 pub struct ParseTx {
@@ -24,6 +102,11 @@ use std::io::{self, Read, Write};
 use std::time::SystemTime;
 
 pub async fn log_message(message: &str) -> io::Result<()> {
+    log_message_sync(message)
+}
+
+/// [`log_message`] for threads that run outside the async runtime
+pub fn log_message_sync(message: &str) -> io::Result<()> {
     // Open the file in append mode. If the file doesn't exist, it will be created.
     let mut file = OpenOptions::new()
         .append(true) // Ensure the log is appended, not overwritten
@@ -82,22 +165,14 @@ pub async fn create_nonblocking_rpc_client(
     Ok(Arc::new(rpc_client))
 }
 
-pub fn import_wallet() -> Result<Keypair> {
-    let mut file = File::open("./key.txt")?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    if contents == "" {
-        println!("Not set Private Key");
-    }
-    let wallet: Keypair = Keypair::from_base58_string(&contents);
-
-    Ok(wallet)
+/// Wallet key file from `WALLET_PATH`, defaulting to `./key.txt`
+pub fn wallet_path() -> String {
+    env::var("WALLET_PATH").unwrap_or_else(|_| "./key.txt".to_string())
 }
-pub fn import_arc_wallet() -> Result<Arc<Keypair>> {
-    let mut file = File::open("./key.txt")?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-    let wallet: Keypair = Keypair::from_base58_string(contents.as_str());
 
-    Ok(Arc::new(wallet))
+pub fn import_wallet() -> Result<Keypair> {
+    load_wallet()
+}
+pub fn import_arc_wallet() -> Result<Arc<TxSigner>> {
+    load_signer()
 }
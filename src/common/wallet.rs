@@ -0,0 +1,173 @@
+use std::{
+    env, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use rand::RngCore;
+use solana_sdk::signature::Keypair;
+
+#[cfg(feature = "ledger")]
+use crate::core::ledger::LedgerSigner;
+use crate::{
+    common::{archive::derive_key, utils::wallet_path},
+    core::tx::TxSigner,
+    services::remote_signer::RemoteSigner,
+};
+
+const MAGIC: &[u8] = b"CTBWALLT";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Passphrase of the first encrypted key unlocked, reused for the rest
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Encrypts a keypair as `MAGIC | salt | nonce | AES-256-GCM ciphertext`, with the key derived
+/// from `passphrase` by Argon2 like state archives
+pub fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt)?)
+        .map_err(|_| anyhow!("Invalid wallet key length"))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), keypair.to_bytes().as_ref())
+        .map_err(|_| anyhow!("Failed to encrypt the wallet"))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Decrypts a key written by `encrypt_keypair`
+pub fn decrypt_keypair(sealed: &[u8], passphrase: &str) -> Result<Keypair> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if sealed.len() < header || !is_encrypted(sealed) {
+        return Err(anyhow!("Not an encrypted wallet"));
+    }
+    let salt = &sealed[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &sealed[MAGIC.len() + SALT_LEN..header];
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, salt)?)
+        .map_err(|_| anyhow!("Invalid wallet key length"))?;
+    let bytes = cipher
+        .decrypt(Nonce::from_slice(nonce), &sealed[header..])
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted wallet"))?;
+    Keypair::from_bytes(&bytes).map_err(|e| anyhow!("Invalid decrypted key: {}", e))
+}
+
+/// A plaintext key: a `solana-keygen` JSON byte array or a base58 string
+pub fn parse_keypair(contents: &str) -> Result<Keypair> {
+    let contents = contents.trim();
+    let bytes = if contents.starts_with('[') {
+        serde_json::from_str::<Vec<u8>>(contents).context("Invalid JSON keypair")?
+    } else {
+        bs58::decode(contents)
+            .into_vec()
+            .context("Key is neither a JSON keypair nor base58")?
+    };
+    Keypair::from_bytes(&bytes).map_err(|e| anyhow!("Invalid key: {}", e))
+}
+
+/// `WALLET_PASSPHRASE`, or prompted for without echo once per run
+pub fn passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = env::var("WALLET_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let mut cached = PASSPHRASE.lock().unwrap();
+    if let Some(passphrase) = cached.as_ref() {
+        return Ok(passphrase.clone());
+    }
+    let passphrase = rpassword::prompt_password(prompt).context("Failed to read passphrase")?;
+    *cached = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+/// Reads a key file in any supported form, asking for the passphrase if it is encrypted
+pub fn read_keypair(path: &Path) -> Result<Keypair> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if is_encrypted(&contents) {
+        let passphrase = passphrase(&format!("Passphrase for {}: ", path.display()))?;
+        return decrypt_keypair(&contents, &passphrase)
+            .with_context(|| format!("Failed to unlock {}", path.display()));
+    }
+    parse_keypair(&String::from_utf8_lossy(&contents))
+        .with_context(|| format!("Invalid key in {}", path.display()))
+}
+
+/// The trading wallet: a base58 `WALLET_PRIVATE_KEY` if set, otherwise the key file at
+/// `WALLET_PATH` (default `./key.txt`), plain or encrypted
+pub fn load_wallet() -> Result<Keypair> {
+    if let Ok(key) = env::var("WALLET_PRIVATE_KEY") {
+        return parse_keypair(&key).context("Invalid WALLET_PRIVATE_KEY");
+    }
+    read_keypair(Path::new(&wallet_path()))
+}
+
+/// What signs for the trading wallet, by `WALLET_SIGNER`: `keypair` (the default, see
+/// `load_wallet`), `ledger`, which needs a build with the `ledger` feature, or `remote` for a
+/// signing service
+pub fn load_signer() -> Result<Arc<TxSigner>> {
+    match env::var("WALLET_SIGNER").as_deref().unwrap_or("keypair") {
+        "keypair" => Ok(Arc::new(load_wallet()?)),
+        #[cfg(feature = "ledger")]
+        "ledger" => Ok(Arc::new(LedgerSigner::from_env()?)),
+        #[cfg(not(feature = "ledger"))]
+        "ledger" => Err(anyhow!(
+            "Ledger signing needs a build with `--features ledger`"
+        )),
+        "remote" => Ok(Arc::new(RemoteSigner::from_env()?)),
+        other => Err(anyhow!("Unknown WALLET_SIGNER '{}'", other)),
+    }
+}
+
+/// Writes `input`'s key to `output` encrypted under a passphrase entered twice
+pub fn encrypt_key_file(input: &Path, output: &Path) -> Result<()> {
+    let keypair = read_keypair(input)?;
+    let passphrase = rpassword::prompt_password("New passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err(anyhow!("Passphrase can't be empty"));
+    }
+    if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        return Err(anyhow!("Passphrases don't match"));
+    }
+    fs::write(output, encrypt_keypair(&keypair, &passphrase)?)
+        .with_context(|| format!("Failed to write {}", output.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn test_reads_every_key_form() {
+        let keypair = Keypair::new();
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        assert_eq!(parse_keypair(&json).unwrap().pubkey(), keypair.pubkey());
+        let base58 = format!("{}\n", keypair.to_base58_string());
+        assert_eq!(parse_keypair(&base58).unwrap().pubkey(), keypair.pubkey());
+
+        let sealed = encrypt_keypair(&keypair, "hunter2").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(
+            decrypt_keypair(&sealed, "hunter2").unwrap().pubkey(),
+            keypair.pubkey()
+        );
+        assert!(decrypt_keypair(&sealed, "hunter3").is_err());
+    }
+}
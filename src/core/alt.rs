@@ -0,0 +1,251 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+    },
+    address_lookup_table_account::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    compute_budget,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signer::Signer,
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::{
+    common::{
+        storage::{data_path, load_json, save_json},
+        utils::{log_message, AppState},
+    },
+    core::tx::TxSigner,
+    dex::{
+        pump::{
+            PUMP_ACCOUNT_ID, PUMP_AMM_PROGRAM_ID, PUMP_GLOBAL_ID, PUMP_PROGRAM_ID, RENT_PROGRAM_ID,
+        },
+        pump_global::pump_fee_recipient,
+        raydium::{AMM_PROGRAM_ID, RAYDIUM_AUTHORITY_V4_ID},
+    },
+};
+
+// Configuration constants
+const LOOKUP_TABLE_FILE: &str = "lookup_table.json";
+
+/// The lookup table transactions are compiled against, once loaded
+pub static LOOKUP_TABLE: LazyLock<RwLock<Option<AddressLookupTableAccount>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+/// Accounts every pump.fun or Raydium swap touches, whatever the mint
+pub fn static_accounts() -> Vec<Pubkey> {
+    vec![
+        system_program::id(),
+        spl_token::id(),
+        spl_associated_token_account::id(),
+        compute_budget::id(),
+        RENT_PROGRAM_ID,
+        spl_token::native_mint::id(),
+        PUMP_PROGRAM_ID,
+        PUMP_GLOBAL_ID,
+        PUMP_ACCOUNT_ID,
+        pump_fee_recipient(),
+        PUMP_AMM_PROGRAM_ID,
+        AMM_PROGRAM_ID,
+        RAYDIUM_AUTHORITY_V4_ID,
+    ]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LookupTableConfig {
+    /// Table to use and keep extended; `None` reuses the one recorded in the data directory or
+    /// creates one
+    pub address: Option<Pubkey>,
+}
+
+impl LookupTableConfig {
+    /// Reads `ALT_ENABLED` (off by default) and `ALT_ADDRESS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let enabled = env::var("ALT_ENABLED")
+            .ok()
+            .and_then(|v| bool::from_str(&v).ok())
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+        let address = match env::var("ALT_ADDRESS") {
+            Ok(address) => Some(Pubkey::from_str(&address).context("Invalid ALT_ADDRESS")?),
+            Err(_) => None,
+        };
+        Ok(Some(Self { address }))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedTable {
+    address: String,
+}
+
+async fn fetch_table(state: &AppState, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+    let account = state
+        .rpc_nonblocking_client
+        .get_account(address)
+        .await
+        .with_context(|| format!("Failed to fetch lookup table {}", address))?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow!("Invalid lookup table {}: {}", address, e))?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Sends one legacy transaction from the bot's wallet and waits for it to land
+async fn send_admin(state: &AppState, instruction: Instruction) -> Result<()> {
+    let blockhash = state.rpc_nonblocking_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&state.wallet.pubkey()),
+        &[&*state.wallet as &dyn Signer],
+        blockhash,
+    );
+    state
+        .rpc_nonblocking_client
+        .send_and_confirm_transaction(&transaction)
+        .await?;
+    Ok(())
+}
+
+/// Finds or creates the bot's lookup table, extends it with any missing static accounts and
+/// installs it for transaction building. New entries are usable from the next slot
+pub async fn init_lookup_table(state: &AppState, config: &LookupTableConfig) -> Result<Pubkey> {
+    let path = data_path(LOOKUP_TABLE_FILE);
+    let recorded = load_json::<RecordedTable>(&path)?
+        .map(|recorded| Pubkey::from_str(&recorded.address))
+        .transpose()?;
+    let wallet = state.wallet.pubkey();
+    let address = match config.address.or(recorded) {
+        Some(address) => address,
+        None => {
+            let slot = state
+                .rpc_nonblocking_client
+                .get_slot_with_commitment(CommitmentConfig::finalized())
+                .await?;
+            let (instruction, address) = create_lookup_table(wallet, wallet, slot);
+            send_admin(state, instruction).await?;
+            let _ = log_message(&format!("Created address lookup table {}", address)).await;
+            address
+        }
+    };
+    save_json(
+        &path,
+        &RecordedTable {
+            address: address.to_string(),
+        },
+    )
+    .await?;
+
+    let mut table = fetch_table(state, &address).await?;
+    let missing = static_accounts()
+        .into_iter()
+        .filter(|account| !table.addresses.contains(account))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        let count = missing.len();
+        send_admin(
+            state,
+            extend_lookup_table(address, wallet, Some(wallet), missing),
+        )
+        .await?;
+        table = fetch_table(state, &address).await?;
+        let _ = log_message(&format!(
+            "Added {} accounts to lookup table {}",
+            count, address
+        ))
+        .await;
+    }
+    *LOOKUP_TABLE.write().unwrap() = Some(table);
+    Ok(address)
+}
+
+/// Signs `instructions` as a v0 transaction against `tables`, or as a legacy one when there
+/// are none
+pub fn compile_signed(
+    keypair: &TxSigner,
+    instructions: &[Instruction],
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    if tables.is_empty() {
+        return Ok(VersionedTransaction::from(
+            Transaction::new_signed_with_payer(
+                instructions,
+                Some(&keypair.pubkey()),
+                &[keypair as &dyn Signer],
+                recent_blockhash,
+            ),
+        ));
+    }
+    let message =
+        v0::Message::try_compile(&keypair.pubkey(), instructions, tables, recent_blockhash)
+            .context("Failed to compile v0 message")?;
+    VersionedTransaction::try_new(VersionedMessage::V0(message), &[keypair as &dyn Signer])
+        .context("Failed to sign v0 transaction")
+}
+
+/// Signs `instructions` against the installed lookup table, if any
+pub fn sign_with_table(
+    keypair: &TxSigner,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let tables = LOOKUP_TABLE
+        .read()
+        .unwrap()
+        .clone()
+        .into_iter()
+        .collect::<Vec<_>>();
+    compile_signed(keypair, instructions, &tables, recent_blockhash)
+}
+
+/// Serialized size of a signed transaction, to check against the 1232 byte packet limit
+pub fn wire_size(versioned_tx: &VersionedTransaction) -> usize {
+    bincode::serialized_size(versioned_tx).map_or(usize::MAX, |size| size as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::AccountMeta, signature::Keypair};
+
+    #[test]
+    fn test_table_shrinks_transactions() {
+        let keypair = Keypair::new();
+        let accounts = static_accounts();
+        let instruction = Instruction::new_with_bytes(
+            PUMP_PROGRAM_ID,
+            &[0; 24],
+            accounts
+                .iter()
+                .map(|account| AccountMeta::new_readonly(*account, false))
+                .collect(),
+        );
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts,
+        };
+        let legacy =
+            compile_signed(&keypair, &[instruction.clone()], &[], Hash::default()).unwrap();
+        let v0 = compile_signed(&keypair, &[instruction], &[table], Hash::default()).unwrap();
+        assert!(matches!(v0.message, VersionedMessage::V0(_)));
+        assert!(wire_size(&v0) < wire_size(&legacy));
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+};
+
+use anyhow::{Context, Result};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_sdk::{instruction::Instruction, message::VersionedMessage, pubkey::Pubkey};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+
+// spl-token's `CloseAccount`, shared by Token-2022
+const CLOSE_ACCOUNT_TAG: u8 = 9;
+
+/// Token accounts of our wallets known to exist, so swaps can leave out their creation
+pub static KNOWN_ATAS: LazyLock<AtaCache> = LazyLock::new(AtaCache::default);
+
+#[derive(Debug, Default)]
+pub struct AtaCache {
+    known: RwLock<HashSet<Pubkey>>,
+}
+
+/// Token accounts a transaction creates and closes, applied to the cache once it lands
+#[derive(Debug, Default, PartialEq)]
+pub struct AtaChanges {
+    created: Vec<Pubkey>,
+    closed: Vec<Pubkey>,
+}
+
+impl AtaCache {
+    pub fn contains(&self, ata: &Pubkey) -> bool {
+        self.known.read().unwrap().contains(ata)
+    }
+
+    pub fn insert(&self, ata: Pubkey) {
+        self.known.write().unwrap().insert(ata);
+    }
+
+    pub fn forget(&self, ata: &Pubkey) {
+        self.known.write().unwrap().remove(ata);
+    }
+
+    pub fn len(&self) -> usize {
+        self.known.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An idempotent create of `owner`'s `mint` account, unless it is known to exist
+    pub fn create_if_missing(
+        &self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Option<Instruction> {
+        let ata = get_associated_token_address_with_program_id(owner, mint, token_program);
+        (!self.contains(&ata))
+            .then(|| create_associated_token_account_idempotent(owner, owner, mint, token_program))
+    }
+
+    /// Reads what `message` creates and closes, forgetting the closed accounts straight away:
+    /// a stale entry would drop a create a later swap needs, a missing one only costs a create
+    pub fn begin(&self, message: &VersionedMessage) -> AtaChanges {
+        let changes = AtaChanges::of(message);
+        for ata in &changes.closed {
+            self.forget(ata);
+        }
+        changes
+    }
+
+    /// Records `changes` once their transaction has landed
+    pub fn landed(&self, changes: &AtaChanges) {
+        let mut known = self.known.write().unwrap();
+        known.extend(changes.created.iter().copied());
+        // Created and closed in the same transaction, like a swap's WSOL account
+        for ata in &changes.closed {
+            known.remove(ata);
+        }
+    }
+
+    /// Adds every token account `owners` hold under both token programs, returning how many
+    pub async fn seed(&self, client: &RpcClient, owners: &[Pubkey]) -> Result<usize> {
+        let mut found = vec![];
+        for owner in owners {
+            for program in [spl_token::id(), spl_token_2022::ID] {
+                let accounts = client
+                    .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(program))
+                    .await
+                    .with_context(|| format!("Failed to list token accounts of {}", owner))?;
+                for keyed in accounts {
+                    found.push(Pubkey::from_str(&keyed.pubkey)?);
+                }
+            }
+        }
+        let count = found.len();
+        self.known.write().unwrap().extend(found);
+        Ok(count)
+    }
+}
+
+impl AtaChanges {
+    /// Associated token account creates and token account closes among `message`'s top-level
+    /// instructions. Accounts behind a lookup table aren't resolved
+    pub fn of(message: &VersionedMessage) -> Self {
+        let keys = message.static_account_keys();
+        let key = |index: Option<&u8>| index.and_then(|index| keys.get(*index as usize)).copied();
+        let mut changes = Self::default();
+        for ix in message.instructions() {
+            let Some(program) = key(Some(&ix.program_id_index)) else {
+                continue;
+            };
+            let tag = ix.data.first().copied();
+            let is_token = program == spl_token::id() || program == spl_token_2022::ID;
+            // `Create` and `CreateIdempotent`; the account itself is the second one
+            if program == spl_associated_token_account::id() && tag.map_or(true, |tag| tag <= 1) {
+                changes.created.extend(key(ix.accounts.get(1)));
+            } else if is_token && tag == Some(CLOSE_ACCOUNT_TAG) {
+                changes.closed.extend(key(ix.accounts.first()));
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::Message;
+
+    #[test]
+    fn test_tracks_created_and_closed_accounts() {
+        let cache = AtaCache::default();
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ata = get_associated_token_address_with_program_id(&owner, &mint, &spl_token::id());
+        let create = cache
+            .create_if_missing(&owner, &mint, &spl_token::id())
+            .unwrap();
+        let message = VersionedMessage::Legacy(Message::new(&[create], Some(&owner)));
+
+        let changes = cache.begin(&message);
+        assert!(cache.is_empty());
+        cache.landed(&changes);
+        assert!(cache.contains(&ata));
+        assert!(cache
+            .create_if_missing(&owner, &mint, &spl_token::id())
+            .is_none());
+
+        let close =
+            spl_token::instruction::close_account(&spl_token::id(), &ata, &owner, &owner, &[])
+                .unwrap();
+        let message = VersionedMessage::Legacy(Message::new(&[close], Some(&owner)));
+        cache.begin(&message);
+        assert!(!cache.contains(&ata));
+    }
+}
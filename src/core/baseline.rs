@@ -0,0 +1,222 @@
+// Straightforward versions of the detect→sign builders, kept for `benches/hot_path.rs` to
+// measure the real ones against. Only built with the `bench` feature; each must produce exactly
+// what the builder it stands in for does, which the tests below check.
+
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+use crate::{
+    core::tx::TxConfig,
+    dex::{
+        pump::{
+            PUMP_ACCOUNT_ID, PUMP_BUY_METHOD, PUMP_GLOBAL_ID, PUMP_PROGRAM_ID, PUMP_SELL_METHOD,
+            RENT_PROGRAM_ID,
+        },
+        pump_global::pump_fee_recipient,
+        raydium_clmm::{
+            bitmap_extension_address, ClmmPool, CLMM_PROGRAM_ID, MEMO_PROGRAM_ID,
+            SWAP_V2_DISCRIMINATOR,
+        },
+    },
+};
+
+/// [`crate::engine::sniper::BuyTemplate::instructions`], deriving the wallet's token account
+/// twice and building the accounts from scratch
+pub fn pump_buy_instructions(
+    user: &Pubkey,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    token_amount: u64,
+    max_sol_cost: u64,
+) -> Vec<Instruction> {
+    let mut data = PUMP_BUY_METHOD.to_le_bytes().to_vec();
+    data.extend(token_amount.to_le_bytes());
+    data.extend(max_sol_cost.to_le_bytes());
+    vec![
+        create_associated_token_account_idempotent(user, user, mint, &spl_token::id()),
+        Instruction::new_with_bytes(
+            PUMP_PROGRAM_ID,
+            &data,
+            vec![
+                AccountMeta::new_readonly(PUMP_GLOBAL_ID, false),
+                AccountMeta::new(pump_fee_recipient(), false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*bonding_curve, false),
+                AccountMeta::new(get_associated_token_address(bonding_curve, mint), false),
+                AccountMeta::new(get_associated_token_address(user, mint), false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(RENT_PROGRAM_ID, false),
+                AccountMeta::new_readonly(PUMP_ACCOUNT_ID, false),
+                AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+            ],
+        ),
+    ]
+}
+
+/// [`crate::dex::pump::sell_instruction`], copying its data through `new_with_bytes`
+pub fn pump_sell_instruction(
+    user: &Pubkey,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    associated_bonding_curve: &Pubkey,
+    token_amount: u64,
+    min_sol_output: u64,
+) -> Instruction {
+    let mut data = PUMP_SELL_METHOD.to_le_bytes().to_vec();
+    data.extend(token_amount.to_le_bytes());
+    data.extend(min_sol_output.to_le_bytes());
+    Instruction::new_with_bytes(
+        PUMP_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(PUMP_GLOBAL_ID, false),
+            AccountMeta::new(pump_fee_recipient(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(PUMP_ACCOUNT_ID, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// [`crate::dex::raydium_clmm::swap_v2_instruction`], growing its buffers as it goes
+#[allow(clippy::too_many_arguments)]
+pub fn clmm_swap_v2_instruction(
+    pool: &ClmmPool,
+    payer: &Pubkey,
+    zero_for_one: bool,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    tick_arrays: &[Pubkey],
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let (input_vault, output_vault, input_mint, output_mint) = if zero_for_one {
+        (pool.vault_0, pool.vault_1, pool.mint_0, pool.mint_1)
+    } else {
+        (pool.vault_1, pool.vault_0, pool.mint_1, pool.mint_0)
+    };
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(pool.amm_config, false),
+        AccountMeta::new(pool.id, false),
+        AccountMeta::new(*user_input, false),
+        AccountMeta::new(*user_output, false),
+        AccountMeta::new(input_vault, false),
+        AccountMeta::new(output_vault, false),
+        AccountMeta::new(pool.observation, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+        AccountMeta::new_readonly(input_mint, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new(bitmap_extension_address(&pool.id), false),
+    ];
+    for address in tick_arrays {
+        accounts.push(AccountMeta::new(*address, false));
+    }
+
+    let mut data = SWAP_V2_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.push(1);
+    Instruction::new_with_bytes(CLMM_PROGRAM_ID, &data, accounts)
+}
+
+/// [`crate::core::tx::budgeted_instructions`], shifting the swap to make room at the front
+pub fn budgeted_instructions(
+    mut instructions: Vec<Instruction>,
+    config: &TxConfig,
+    tip: Option<Instruction>,
+) -> Vec<Instruction> {
+    instructions.insert(
+        0,
+        ComputeBudgetInstruction::set_compute_unit_limit(config.unit_limit),
+    );
+    if config.unit_price > 0 {
+        instructions.insert(
+            1,
+            ComputeBudgetInstruction::set_compute_unit_price(config.unit_price),
+        );
+    }
+    instructions.extend(tip);
+    instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::system_instruction;
+
+    use super::*;
+    use crate::{core::tx, dex::pump, engine::sniper::BuyTemplate};
+
+    fn pool() -> ClmmPool {
+        ClmmPool {
+            id: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            mint_0: spl_token::native_mint::ID,
+            mint_1: Pubkey::new_unique(),
+            vault_0: Pubkey::new_unique(),
+            vault_1: Pubkey::new_unique(),
+            observation: Pubkey::new_unique(),
+            decimals_0: 9,
+            decimals_1: 6,
+            tick_spacing: 60,
+            liquidity: 1 << 40,
+            sqrt_price_x64: 1 << 64,
+            tick_current: 0,
+        }
+    }
+
+    #[test]
+    fn test_baselines_match_the_real_builders() {
+        let (user, mint, curve) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        assert_eq!(
+            pump_buy_instructions(&user, &mint, &curve, 7, 9),
+            BuyTemplate::new(user).instructions(&mint, &curve, 7, 9)
+        );
+
+        let curve_ata = get_associated_token_address(&curve, &mint);
+        assert_eq!(
+            pump_sell_instruction(&user, &mint, &curve, &curve_ata, 7, 9),
+            pump::sell_instruction(&user, &mint, &curve, &curve_ata, 7, 9)
+        );
+
+        let (pool, ticks) = (pool(), [Pubkey::new_unique(), Pubkey::new_unique()]);
+        let (input, output) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            clmm_swap_v2_instruction(&pool, &user, true, &input, &output, &ticks, 5, 1),
+            crate::dex::raydium_clmm::swap_v2_instruction(
+                &pool, &user, true, &input, &output, &ticks, 5, 1
+            )
+        );
+
+        let config = TxConfig::default();
+        let swap = vec![system_instruction::transfer(&user, &mint, 1)];
+        let tip = system_instruction::transfer(&user, &curve, 2);
+        assert_eq!(
+            budgeted_instructions(swap.clone(), &config, Some(tip.clone())),
+            tx::budgeted_instructions(swap, &config, Some(tip))
+        );
+    }
+}
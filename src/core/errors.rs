@@ -0,0 +1,333 @@
+use solana_sdk::{
+    instruction::InstructionError, message::VersionedMessage, pubkey::Pubkey, system_program,
+    transaction::TransactionError,
+};
+use thiserror::Error;
+
+use crate::dex::{
+    jupiter::JUPITER_PROGRAM_ID,
+    pump::{PUMP_AMM_PROGRAM_ID, PUMP_PROGRAM_ID},
+    raydium::AMM_PROGRAM_ID,
+    raydium_clmm::CLMM_PROGRAM_ID,
+    raydium_cpmm::CPMM_PROGRAM_ID,
+};
+
+// Anchor's AccountNotInitialized, raised by every Anchor program for a missing account
+const ANCHOR_ACCOUNT_NOT_INITIALIZED: u32 = 3_012;
+// Token program errors, passed up unchanged by the swap programs that transfer for us
+const TOKEN_ACCOUNT_FROZEN: u32 = spl_token::error::TokenError::AccountFrozen as u32;
+const TOKEN_NON_TRANSFERABLE: u32 = spl_token_2022::error::TokenError::NonTransferable as u32;
+
+/// What the engine should do about a failed trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reaction {
+    /// Quote again, at higher slippage if allowed
+    Requote,
+    /// Resolve the mint's venue again and swap there
+    Reroute,
+    /// Sending again can't help
+    Abort,
+    /// Transient, so worth another attempt as is
+    Retry,
+}
+
+/// Why a transaction failed to land, decoded from its simulation or on-chain error where
+/// possible. Custom codes keep the node's wording so logs read the same as before
+#[derive(Debug, Error)]
+pub enum TxError {
+    #[error("slippage exceeded in {program} (custom program error: {code:#x})")]
+    SlippageExceeded { program: Pubkey, code: u32 },
+    #[error("bonding curve complete")]
+    CurveComplete,
+    #[error("insufficient funds")]
+    InsufficientFunds,
+    #[error("account not found")]
+    AccountNotFound,
+    #[error("token account frozen")]
+    Frozen,
+    #[error("mint is non-transferable")]
+    NonTransferable,
+    #[error("{program} failed (custom program error: {code:#x})")]
+    Program { program: Pubkey, code: u32 },
+    #[error("{program} failed: {error}")]
+    Instruction {
+        program: Pubkey,
+        error: InstructionError,
+    },
+    /// Rejected before it could land, e.g. for an expired blockhash
+    #[error("transaction rejected: {0}")]
+    Rejected(String),
+    /// Sent but not seen confirmed in time; it may still land
+    #[error("transaction not confirmed: {0}")]
+    Unconfirmed(String),
+    /// The node or relay couldn't be reached
+    #[error("RPC request failed: {0}")]
+    Rpc(String),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Why a venue couldn't price a swap
+#[derive(Debug, Error)]
+pub enum QuoteError {
+    /// No pool or route can fill the swap
+    #[error("no route: {0}")]
+    NoRoute(String),
+    /// The quote service is down or rate limiting us
+    #[error("quote service unavailable: {0}")]
+    Unavailable(String),
+    #[error("malformed quote: {0}")]
+    Malformed(String),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Why a swap on a venue failed, before or after its transaction was sent
+#[derive(Debug, Error)]
+pub enum SwapError {
+    /// Parameters no venue would accept, like a zero amount
+    #[error("invalid swap: {0}")]
+    InvalidInput(String),
+    /// The pool isn't the mint's SOL pair, so the cached route is stale
+    #[error("pool {pool} does not pair {mint} with SOL")]
+    WrongPool { pool: Pubkey, mint: Pubkey },
+    #[error(transparent)]
+    Quote(#[from] QuoteError),
+    #[error("{venue} swap failed: {source}")]
+    Send {
+        venue: &'static str,
+        #[source]
+        source: TxError,
+    },
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl TxError {
+    /// Decodes a simulated or on-chain error, using `message` to tell which program raised it
+    pub fn decode(err: &TransactionError, message: &VersionedMessage) -> Self {
+        match err {
+            TransactionError::InstructionError(index, error) => {
+                let program = message
+                    .instructions()
+                    .get(*index as usize)
+                    .and_then(|ix| {
+                        message
+                            .static_account_keys()
+                            .get(ix.program_id_index as usize)
+                    })
+                    .copied()
+                    .unwrap_or_default();
+                Self::from_instruction(program, error)
+            }
+            TransactionError::InsufficientFundsForFee
+            | TransactionError::InsufficientFundsForRent { .. } => Self::InsufficientFunds,
+            TransactionError::AccountNotFound | TransactionError::ProgramAccountNotFound => {
+                Self::AccountNotFound
+            }
+            err => Self::Rejected(err.to_string()),
+        }
+    }
+
+    fn from_instruction(program: Pubkey, error: &InstructionError) -> Self {
+        match error {
+            InstructionError::Custom(code) => Self::from_custom(program, *code),
+            InstructionError::InsufficientFunds => Self::InsufficientFunds,
+            InstructionError::UninitializedAccount | InstructionError::MissingAccount => {
+                Self::AccountNotFound
+            }
+            error => Self::Instruction {
+                program,
+                error: error.clone(),
+            },
+        }
+    }
+
+    fn from_custom(program: Pubkey, code: u32) -> Self {
+        let slippage = Self::SlippageExceeded { program, code };
+        match (program, code) {
+            // TooMuchSolRequired, TooLittleSolReceived
+            (PUMP_PROGRAM_ID, 6_002 | 6_003) => slippage,
+            (PUMP_PROGRAM_ID, 6_005) => Self::CurveComplete,
+            (PUMP_AMM_PROGRAM_ID, 6_004) => slippage,
+            (AMM_PROGRAM_ID, 30) => slippage,
+            (CPMM_PROGRAM_ID, 6_005) => slippage,
+            // PriceSlippageCheck, TooLittleOutputReceived, TooMuchInputPaid
+            (CLMM_PROGRAM_ID, 6_021..=6_023) => slippage,
+            (JUPITER_PROGRAM_ID, 6_001) => slippage,
+            // ResultWithNegativeLamports
+            (program, 1) if program == system_program::id() => Self::InsufficientFunds,
+            (program, 1) if program == spl_token::id() || program == spl_token_2022::ID => {
+                Self::InsufficientFunds
+            }
+            (_, ANCHOR_ACCOUNT_NOT_INITIALIZED) => Self::AccountNotFound,
+            // Raydium AMM v4 numbers its own errors from 0, so these codes are its own there
+            (program, TOKEN_ACCOUNT_FROZEN) if program != AMM_PROGRAM_ID => Self::Frozen,
+            (program, TOKEN_NON_TRANSFERABLE) if program != AMM_PROGRAM_ID => {
+                Self::NonTransferable
+            }
+            (program, code) => Self::Program { program, code },
+        }
+    }
+
+    /// `None` when the cause wasn't decoded, such as an error the node only described in text
+    pub fn reaction(&self) -> Option<Reaction> {
+        match self {
+            Self::SlippageExceeded { .. } => Some(Reaction::Requote),
+            Self::CurveComplete => Some(Reaction::Reroute),
+            Self::Rejected(_) | Self::Rpc(_) => Some(Reaction::Retry),
+            // An unconfirmed transaction may still land, so sending it again risks a double fill
+            Self::InsufficientFunds
+            | Self::AccountNotFound
+            | Self::Frozen
+            | Self::NonTransferable
+            | Self::Program { .. }
+            | Self::Instruction { .. }
+            | Self::Unconfirmed(_) => Some(Reaction::Abort),
+            Self::Other(err) => reaction(err),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.reaction() == Some(Reaction::Retry)
+    }
+}
+
+impl QuoteError {
+    pub fn reaction(&self) -> Option<Reaction> {
+        match self {
+            Self::NoRoute(_) => Some(Reaction::Reroute),
+            Self::Unavailable(_) => Some(Reaction::Retry),
+            Self::Malformed(_) => Some(Reaction::Abort),
+            Self::Other(err) => reaction(err),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.reaction() == Some(Reaction::Retry)
+    }
+}
+
+impl SwapError {
+    /// Wraps a send failure on `venue`
+    pub fn send(venue: &'static str) -> impl FnOnce(TxError) -> Self {
+        move |source| Self::Send { venue, source }
+    }
+
+    pub fn reaction(&self) -> Option<Reaction> {
+        match self {
+            Self::InvalidInput(_) => Some(Reaction::Abort),
+            Self::WrongPool { .. } => Some(Reaction::Reroute),
+            Self::Quote(error) => error.reaction(),
+            Self::Send { source, .. } => source.reaction(),
+            Self::Other(err) => reaction(err),
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.reaction() == Some(Reaction::Retry)
+    }
+}
+
+// Typed errors raised below an anyhow layer come back out intact rather than as `Other`
+impl From<anyhow::Error> for TxError {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast().unwrap_or_else(Self::Other)
+    }
+}
+
+impl From<anyhow::Error> for QuoteError {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast().unwrap_or_else(Self::Other)
+    }
+}
+
+impl From<anyhow::Error> for SwapError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<Self>() {
+            Ok(error) => error,
+            Err(err) => err
+                .downcast::<QuoteError>()
+                .map(Self::Quote)
+                .unwrap_or_else(Self::Other),
+        }
+    }
+}
+
+/// The first [`TxError`] in `err`'s chain, including one a [`SwapError`] wraps
+pub fn tx_error(err: &anyhow::Error) -> Option<&TxError> {
+    err.chain().find_map(|cause| match cause.downcast_ref::<SwapError>() {
+        Some(SwapError::Send { source, .. }) => Some(source),
+        _ => cause.downcast_ref::<TxError>(),
+    })
+}
+
+/// How the engine should react to `err`, from the first typed error in its chain that knows.
+/// The transparent `Other` variants hide the error they wrap from the chain, hence the recursion
+pub fn reaction(err: &anyhow::Error) -> Option<Reaction> {
+    err.chain().find_map(|cause| {
+        if let Some(error) = cause.downcast_ref::<SwapError>() {
+            error.reaction()
+        } else if let Some(error) = cause.downcast_ref::<TxError>() {
+            error.reaction()
+        } else {
+            cause
+                .downcast_ref::<QuoteError>()
+                .and_then(QuoteError::reaction)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+    use solana_sdk::{instruction::Instruction, message::Message};
+
+    #[test]
+    fn test_decodes_transaction_errors() {
+        let payer = Pubkey::new_unique();
+        let message = VersionedMessage::Legacy(Message::new(
+            &[
+                Instruction::new_with_bytes(CPMM_PROGRAM_ID, &[], vec![]),
+                Instruction::new_with_bytes(PUMP_PROGRAM_ID, &[], vec![]),
+            ],
+            Some(&payer),
+        ));
+        let custom = |index, code| {
+            let err = TransactionError::InstructionError(index, InstructionError::Custom(code));
+            TxError::decode(&err, &message)
+        };
+        // The same code means different things in different programs
+        assert!(matches!(
+            custom(0, 6_005),
+            TxError::SlippageExceeded { program, code: 6_005 } if program == CPMM_PROGRAM_ID
+        ));
+        assert!(matches!(custom(1, 6_005), TxError::CurveComplete));
+        assert!(matches!(custom(1, 3_012), TxError::AccountNotFound));
+        assert!(matches!(custom(1, 0x11), TxError::Frozen));
+        assert!(matches!(custom(0, 0x25), TxError::NonTransferable));
+        assert_eq!(custom(1, 6_004).reaction(), Some(Reaction::Abort));
+        assert!(matches!(
+            TxError::decode(&TransactionError::InsufficientFundsForFee, &message),
+            TxError::InsufficientFunds
+        ));
+        assert!(TxError::decode(&TransactionError::BlockhashNotFound, &message).is_retryable());
+    }
+
+    #[test]
+    fn test_typed_errors_survive_anyhow() {
+        let sent = Err::<(), _>(anyhow::Error::new(TxError::CurveComplete))
+            .context("Pre-flight simulation failed")
+            .unwrap_err();
+        assert!(matches!(TxError::from(sent), TxError::CurveComplete));
+
+        let swap = anyhow::Error::new(SwapError::send("pump")(TxError::CurveComplete));
+        assert_eq!(reaction(&swap), Some(Reaction::Reroute));
+        let frozen = anyhow::Error::new(SwapError::send("pump")(TxError::Frozen));
+        assert!(matches!(tx_error(&frozen.context("sell")), Some(TxError::Frozen)));
+        let quote = SwapError::from(anyhow::Error::new(QuoteError::Unavailable("429".into())));
+        assert!(quote.is_retryable());
+        assert_eq!(reaction(&anyhow!("timed out")), None);
+    }
+}
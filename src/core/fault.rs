@@ -0,0 +1,199 @@
+// Failure injection for integration tests. Only built with the `failure-injection` feature;
+// otherwise every hook below is a no-op.
+
+#[cfg(feature = "failure-injection")]
+mod injection {
+    use std::{
+        env,
+        str::FromStr,
+        sync::{LazyLock, Mutex},
+        time::Duration,
+    };
+
+    use anyhow::{anyhow, Result};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::common::utils::log_message;
+
+    pub static FAULTS: LazyLock<FaultInjector> =
+        LazyLock::new(|| FaultInjector::from_env().expect("Invalid failure injection settings"));
+
+    /// A fault that fires with probability `rate`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Fault {
+        pub rate: f64,
+    }
+
+    impl FromStr for Fault {
+        type Err = anyhow::Error;
+
+        /// Parses a probability between 0 and 1
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let rate = f64::from_str(s.trim())?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(anyhow!("Fault rate must be between 0 and 1: '{}'", s));
+            }
+            Ok(Self { rate })
+        }
+    }
+
+    /// A delay of `delay` applied with probability `rate`
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct DelayFault {
+        pub rate: f64,
+        pub delay: Duration,
+    }
+
+    impl FromStr for DelayFault {
+        type Err = anyhow::Error;
+
+        /// Parses `<rate>:<delay ms>`, e.g. `0.2:1500`
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (rate, delay_ms) = s
+                .trim()
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid delay fault: '{}'. Use <rate>:<ms>", s))?;
+            Ok(Self {
+                rate: Fault::from_str(rate)?.rate,
+                delay: Duration::from_millis(u64::from_str(delay_ms)?),
+            })
+        }
+    }
+
+    /// Probabilistic faults on the WebSocket stream, RPC calls and bundle submission
+    pub struct FaultInjector {
+        ws_drop: Option<Fault>,
+        rpc_delay: Option<DelayFault>,
+        bundle_fail: Option<Fault>,
+        rng: Mutex<StdRng>,
+    }
+
+    fn parse_env<T: FromStr<Err = anyhow::Error>>(key: &str) -> Result<Option<T>> {
+        env::var(key).ok().map(|v| T::from_str(&v)).transpose()
+    }
+
+    impl FaultInjector {
+        pub fn new(
+            ws_drop: Option<Fault>,
+            rpc_delay: Option<DelayFault>,
+            bundle_fail: Option<Fault>,
+            seed: u64,
+        ) -> Self {
+            Self {
+                ws_drop,
+                rpc_delay,
+                bundle_fail,
+                rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            }
+        }
+
+        /// Reads `FAULT_WS_DROP`, `FAULT_RPC_DELAY`, `FAULT_BUNDLE_FAIL` and `FAULT_SEED`
+        /// (a fixed seed replays the same failures run after run)
+        pub fn from_env() -> Result<Self> {
+            let seed = env::var("FAULT_SEED")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .unwrap_or_else(rand::random);
+            Ok(Self::new(
+                parse_env("FAULT_WS_DROP")?,
+                parse_env("FAULT_RPC_DELAY")?,
+                parse_env("FAULT_BUNDLE_FAIL")?,
+                seed,
+            ))
+        }
+
+        fn roll(&self, rate: f64) -> bool {
+            rate > 0.0 && self.rng.lock().unwrap().gen_bool(rate.min(1.0))
+        }
+
+        pub fn drop_ws_message(&self) -> bool {
+            self.ws_drop.is_some_and(|fault| self.roll(fault.rate))
+        }
+
+        pub fn rpc_delay(&self) -> Option<Duration> {
+            self.rpc_delay
+                .filter(|fault| self.roll(fault.rate))
+                .map(|fault| fault.delay)
+        }
+
+        pub fn fail_bundle(&self) -> bool {
+            self.bundle_fail.is_some_and(|fault| self.roll(fault.rate))
+        }
+    }
+
+    pub fn drop_ws_message() -> bool {
+        FAULTS.drop_ws_message()
+    }
+
+    pub async fn delay_rpc(operation: &str) {
+        if let Some(delay) = FAULTS.rpc_delay() {
+            let _ = log_message(&format!(
+                "Fault injection: delaying {} by {:?}",
+                operation, delay
+            ))
+            .await;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    pub fn fail_bundle() -> Result<()> {
+        if FAULTS.fail_bundle() {
+            return Err(anyhow!("Fault injection: bundle submission failed"));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_faults() {
+            assert_eq!(Fault::from_str("0.25").unwrap().rate, 0.25);
+            assert!(Fault::from_str("1.5").is_err());
+            assert_eq!(
+                DelayFault::from_str("0.5:1500").unwrap(),
+                DelayFault {
+                    rate: 0.5,
+                    delay: Duration::from_millis(1_500),
+                }
+            );
+            assert!(DelayFault::from_str("0.5").is_err());
+        }
+
+        #[test]
+        fn test_seeded_faults_replay() {
+            let injector = || FaultInjector::new(Some(Fault { rate: 0.3 }), None, None, 7);
+            let (a, b) = (injector(), injector());
+            let run_a = (0..100).map(|_| a.drop_ws_message()).collect::<Vec<_>>();
+            let run_b = (0..100).map(|_| b.drop_ws_message()).collect::<Vec<_>>();
+            assert_eq!(run_a, run_b);
+            let dropped = run_a.iter().filter(|d| **d).count();
+            assert!((10..=50).contains(&dropped));
+            assert!(!a.fail_bundle());
+            assert_eq!(a.rpc_delay(), None);
+        }
+    }
+}
+
+#[cfg(feature = "failure-injection")]
+pub use injection::*;
+
+/// Whether to swallow the next WebSocket message
+#[cfg(not(feature = "failure-injection"))]
+#[inline(always)]
+pub fn drop_ws_message() -> bool {
+    false
+}
+
+/// Holds up an RPC call
+#[cfg(not(feature = "failure-injection"))]
+#[inline(always)]
+pub async fn delay_rpc(_operation: &str) {}
+
+/// Fails a bundle submission before it is sent
+#[cfg(not(feature = "failure-injection"))]
+#[inline(always)]
+pub fn fail_bundle() -> anyhow::Result<()> {
+    Ok(())
+}
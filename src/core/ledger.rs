@@ -0,0 +1,107 @@
+// Ledger signing. Only built with the `ledger` feature, which links the USB HID stack.
+
+use std::{env, str::FromStr, sync::mpsc, thread};
+
+use anyhow::{anyhow, Context, Result};
+use solana_remote_wallet::{
+    locator::Locator, remote_keypair::generate_remote_keypair, remote_wallet::maybe_wallet_manager,
+};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+
+// Configuration constants
+const DEFAULT_LOCATOR: &str = "usb://ledger";
+
+type SignRequest = (
+    Vec<u8>,
+    mpsc::Sender<std::result::Result<Signature, SignerError>>,
+);
+
+/// A Solana key held on a Ledger. The device handle isn't thread-safe, so one thread owns it
+/// and signs whatever is queued; each signature blocks its caller until the transaction is
+/// approved on the device, so callers sign through `tx::signing`
+pub struct LedgerSigner {
+    pubkey: Pubkey,
+    requests: mpsc::Sender<SignRequest>,
+}
+
+impl LedgerSigner {
+    /// Opens `WALLET_LEDGER` (default `usb://ledger`, or `usb://ledger/<pubkey>` to pick one of
+    /// several devices) at account `WALLET_LEDGER_ACCOUNT` (default 0), the key
+    /// `solana-keygen pubkey usb://ledger?key=<account>` shows
+    pub fn from_env() -> Result<Self> {
+        let locator = env::var("WALLET_LEDGER").unwrap_or_else(|_| DEFAULT_LOCATOR.to_string());
+        let account = env::var("WALLET_LEDGER_ACCOUNT")
+            .ok()
+            .map(|v| u32::from_str(&v))
+            .transpose()
+            .context("Invalid WALLET_LEDGER_ACCOUNT")?
+            .unwrap_or(0);
+        Self::open(locator, DerivationPath::new_bip44(Some(account), None))
+    }
+
+    pub fn open(locator: String, derivation_path: DerivationPath) -> Result<Self> {
+        let (requests, inbox) = mpsc::channel::<SignRequest>();
+        let (ready, opened) = mpsc::channel::<Result<Pubkey>>();
+        thread::Builder::new()
+            .name("ledger".to_string())
+            .spawn(move || {
+                let manager = match maybe_wallet_manager() {
+                    Ok(Some(manager)) => manager,
+                    Ok(None) => {
+                        let _ = ready.send(Err(anyhow!("No hardware wallet connected")));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = ready.send(Err(e.into()));
+                        return;
+                    }
+                };
+                let keypair = Locator::new_from_path(&locator)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|locator| {
+                        generate_remote_keypair(locator, derivation_path, &manager, false, "wallet")
+                            .map_err(anyhow::Error::from)
+                    });
+                let keypair = match keypair {
+                    Ok(keypair) => keypair,
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready.send(keypair.try_pubkey().map_err(anyhow::Error::from));
+                for (message, reply) in inbox {
+                    let _ = reply.send(keypair.try_sign_message(&message));
+                }
+            })?;
+        let pubkey = opened
+            .recv()
+            .map_err(|_| anyhow!("Ledger thread exited"))?
+            .context("Failed to open the Ledger")?;
+        Ok(Self { pubkey, requests })
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        let lost = || SignerError::Connection("Ledger thread exited".to_string());
+        let (reply, signed) = mpsc::channel();
+        self.requests
+            .send((message.to_vec(), reply))
+            .map_err(|_| lost())?;
+        signed.recv().map_err(|_| lost())?
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
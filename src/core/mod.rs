@@ -1,2 +1,12 @@
+pub mod alt;
+pub mod ata;
+#[cfg(feature = "bench")]
+pub mod baseline;
+pub mod errors;
+pub mod fault;
+#[cfg(feature = "ledger")]
+pub mod ledger;
+pub mod rpc_pool;
 pub mod token;
 pub mod tx;
+pub mod tx_archive;
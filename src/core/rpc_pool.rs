@@ -0,0 +1,415 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures_util::future::{join_all, select_ok};
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    http_sender::HttpSender,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_config::RpcSendTransactionConfig,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use tokio::time::{sleep, Instant};
+
+use crate::common::utils::log_message;
+
+// Configuration constants
+const STATUS_POLL_INTERVAL_MS: u64 = 400;
+const DEFAULT_HEALTH_CHECK_SECS: u64 = 5;
+const DEFAULT_MAX_SLOT_LAG: u64 = 50;
+// Consecutive failures before an endpoint is taken out of rotation
+const MAX_FAILURES: u32 = 3;
+const DOWN_SECS: u64 = 30;
+// Weight of the newest sample in the latency and error averages
+const EWMA_ALPHA: f64 = 0.2;
+/// JSON-RPC error a node returns while it is behind or otherwise unhealthy
+const NODE_UNHEALTHY: i64 = -32005;
+
+/// Extra RPC endpoints every broadcast transaction is also sent through
+pub static RPC_POOL: LazyLock<RpcPool> = LazyLock::new(RpcPool::from_env);
+
+/// How an endpoint has been doing lately
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EndpointHealth {
+    /// Moving average of request latency; `None` before the first answer
+    pub latency_ms: Option<f64>,
+    /// Moving average of the share of requests that failed
+    pub error_rate: f64,
+    pub failures: u32,
+    /// Out of rotation until then, after repeated failures or falling behind
+    pub down_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    pub fn record_success(&mut self, latency: Duration) {
+        let ms = latency.as_secs_f64() * 1_000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(avg) => avg + EWMA_ALPHA * (ms - avg),
+            None => ms,
+        });
+        self.error_rate *= 1.0 - EWMA_ALPHA;
+        self.failures = 0;
+        self.down_until = None;
+    }
+
+    /// Counts a failure; true if it took the endpoint out of rotation
+    pub fn record_failure(&mut self, now: Instant) -> bool {
+        self.error_rate += EWMA_ALPHA * (1.0 - self.error_rate);
+        self.failures += 1;
+        let was_healthy = self.is_healthy(now);
+        if self.failures >= MAX_FAILURES {
+            self.down_until = Some(now + Duration::from_secs(DOWN_SECS));
+        }
+        was_healthy && !self.is_healthy(now)
+    }
+
+    pub fn is_healthy(&self, now: Instant) -> bool {
+        self.down_until.map_or(true, |until| now >= until)
+    }
+
+    /// Expected cost of routing a request here: latency inflated by the error rate, so a fast
+    /// but flaky endpoint loses to a steady one. Unmeasured endpoints rank first to get measured
+    fn score(&self) -> f64 {
+        self.latency_ms.unwrap_or(0.0) / (1.0 - self.error_rate).max(0.05)
+    }
+}
+
+/// Indices of `health` in the order requests should try them: healthy endpoints by score,
+/// then the ones out of rotation as a last resort
+pub fn rank(health: &[EndpointHealth], now: Instant) -> Vec<usize> {
+    let mut order = (0..health.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&health[a], &health[b]);
+        b.is_healthy(now)
+            .cmp(&a.is_healthy(now))
+            .then(a.score().total_cmp(&b.score()))
+    });
+    order
+}
+
+/// Whether another endpoint might answer `error` differently. Errors about the request itself,
+/// like a missing account or a failed simulation, would only repeat
+fn is_endpoint_failure(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY
+        }
+        _ => false,
+    }
+}
+
+struct Endpoint {
+    url: String,
+    client: Arc<RpcClient>,
+    sender: HttpSender,
+    health: Mutex<EndpointHealth>,
+}
+
+/// A set of RPC endpoints. Reads go to the fastest healthy one and fail over to the next on
+/// transport errors; broadcasts race the same signed transaction through all of them
+pub struct RpcPool {
+    endpoints: Vec<Endpoint>,
+}
+
+impl RpcPool {
+    pub fn new(endpoints: Vec<String>) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .map(|url| Endpoint {
+                client: Arc::new(RpcClient::new_with_commitment(
+                    url.clone(),
+                    CommitmentConfig::processed(),
+                )),
+                sender: HttpSender::new(url.clone()),
+                url,
+                health: Mutex::new(EndpointHealth::default()),
+            })
+            .collect();
+        Self { endpoints }
+    }
+
+    /// Builds the pool from `RPC_ENDPOINT` plus the comma-separated `RPC_BROADCAST_ENDPOINTS`
+    pub fn from_env() -> Self {
+        Self::new(endpoints_from_env("RPC_BROADCAST_ENDPOINTS"))
+    }
+
+    /// The read pool: `RPC_ENDPOINT` plus the comma-separated `RPC_FALLBACK_ENDPOINTS`
+    pub fn reads_from_env() -> Self {
+        Self::new(endpoints_from_env("RPC_FALLBACK_ENDPOINTS"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// Each endpoint's URL and current health
+    pub fn health(&self) -> Vec<(String, EndpointHealth)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.url.clone(), *endpoint.health.lock().unwrap()))
+            .collect()
+    }
+
+    fn ranked(&self) -> Vec<usize> {
+        let health = self
+            .health()
+            .into_iter()
+            .map(|(_, h)| h)
+            .collect::<Vec<_>>();
+        rank(&health, Instant::now())
+    }
+
+    async fn record_failure(&self, endpoint: &Endpoint, reason: &str) {
+        let went_down = endpoint
+            .health
+            .lock()
+            .unwrap()
+            .record_failure(Instant::now());
+        if went_down {
+            let _ = log_message(&format!(
+                "RPC endpoint {} out of rotation for {}s: {}",
+                endpoint.url, DOWN_SECS, reason
+            ))
+            .await;
+        }
+    }
+
+    /// Sends one JSON-RPC request, trying endpoints best first until one answers
+    pub async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut last_error = None;
+        for index in self.ranked() {
+            let endpoint = &self.endpoints[index];
+            let started = Instant::now();
+            match endpoint.sender.send(request, params.clone()).await {
+                Err(e) if is_endpoint_failure(&e) => {
+                    self.record_failure(endpoint, &e.to_string()).await;
+                    last_error = Some(e);
+                }
+                result => {
+                    endpoint
+                        .health
+                        .lock()
+                        .unwrap()
+                        .record_success(started.elapsed());
+                    return result;
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            ClientErrorKind::Custom("No RPC endpoints configured".to_string()).into()
+        }))
+    }
+
+    /// Pings every endpoint's slot, recording latency, and takes out of rotation any that
+    /// has fallen more than `max_slot_lag` behind the furthest one
+    pub async fn check_health(&self, max_slot_lag: u64) {
+        let slots = join_all(self.endpoints.iter().map(|endpoint| async move {
+            let started = Instant::now();
+            let slot = endpoint.client.get_slot().await;
+            (slot, started.elapsed())
+        }))
+        .await;
+        let tip = slots
+            .iter()
+            .filter_map(|(slot, _)| slot.as_ref().ok())
+            .max()
+            .copied()
+            .unwrap_or(0);
+        for (endpoint, (slot, latency)) in self.endpoints.iter().zip(slots) {
+            match slot {
+                Ok(slot) if tip.saturating_sub(slot) > max_slot_lag => {
+                    let reason = format!("{} slots behind", tip - slot);
+                    self.record_failure(endpoint, &reason).await;
+                }
+                Ok(_) => endpoint.health.lock().unwrap().record_success(latency),
+                Err(e) => self.record_failure(endpoint, &e.to_string()).await,
+            }
+        }
+    }
+
+    /// Sends the transaction through every endpoint at once; succeeds if any accepts it
+    pub async fn broadcast(&self, tx: &VersionedTransaction) -> Result<Signature> {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            preflight_commitment: Some(CommitmentLevel::Processed),
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        let results = join_all(
+            self.endpoints
+                .iter()
+                .map(|endpoint| endpoint.client.send_transaction_with_config(tx, config)),
+        )
+        .await;
+
+        let mut signature = None;
+        for (endpoint, result) in self.endpoints.iter().zip(results) {
+            match result {
+                Ok(sig) => signature = Some(sig),
+                Err(e) => {
+                    let _ =
+                        log_message(&format!("Broadcast via {} failed: {}", endpoint.url, e)).await;
+                }
+            }
+        }
+        signature.ok_or_else(|| anyhow!("No RPC endpoint accepted the transaction"))
+    }
+
+    /// Waits until any endpoint reports the signature confirmed
+    pub async fn confirm_any(&self, signature: &Signature, timeout: Duration) -> Result<()> {
+        let polls = self.endpoints.iter().map(|endpoint| {
+            let client = &endpoint.client;
+            Box::pin(async move {
+                let deadline = Instant::now() + timeout;
+                while Instant::now() < deadline {
+                    let statuses = client.get_signature_statuses(&[*signature]).await?;
+                    if let Some(Some(status)) = statuses.value.first() {
+                        if let Some(err) = &status.err {
+                            return Err(anyhow!("Transaction {} failed: {:?}", signature, err));
+                        }
+                        if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                            return Ok(());
+                        }
+                    }
+                    sleep(Duration::from_millis(STATUS_POLL_INTERVAL_MS)).await;
+                }
+                Err(anyhow!("Transaction {} not confirmed in time", signature))
+            })
+        });
+
+        select_ok(polls).await.map(|_| ())
+    }
+}
+
+fn endpoints_from_env(extra: &str) -> Vec<String> {
+    env::var("RPC_ENDPOINT")
+        .into_iter()
+        .chain(
+            env::var(extra)
+                .unwrap_or_default()
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect::<Vec<_>>(),
+        )
+        .collect()
+}
+
+/// Lets a `solana_client` RPC client run every request through a pool
+pub struct PoolSender(pub Arc<RpcPool>);
+
+#[async_trait]
+impl RpcSender for PoolSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        self.0.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.0
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.sender.get_transport_stats())
+            .fold(RpcTransportStats::default(), |total, stats| {
+                RpcTransportStats {
+                    request_count: total.request_count + stats.request_count,
+                    elapsed_time: total.elapsed_time + stats.elapsed_time,
+                    rate_limited_time: total.rate_limited_time + stats.rate_limited_time,
+                }
+            })
+    }
+
+    fn url(&self) -> String {
+        self.0
+            .ranked()
+            .first()
+            .map(|&index| self.0.endpoints[index].url.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Blocking and nonblocking clients whose requests all go through `pool`
+pub fn pooled_clients(
+    pool: &Arc<RpcPool>,
+) -> (Arc<solana_client::rpc_client::RpcClient>, Arc<RpcClient>) {
+    let config = || RpcClientConfig::with_commitment(CommitmentConfig::processed());
+    (
+        Arc::new(solana_client::rpc_client::RpcClient::new_sender(
+            PoolSender(pool.clone()),
+            config(),
+        )),
+        Arc::new(RpcClient::new_sender(PoolSender(pool.clone()), config())),
+    )
+}
+
+/// Re-checks every endpoint of `pool` every `RPC_HEALTH_CHECK_SECS`, taking any more than
+/// `RPC_MAX_SLOT_LAG` slots behind out of rotation. Runs forever
+pub async fn run_health_checks(pool: Arc<RpcPool>) {
+    let setting = |key: &str, default: u64| {
+        env::var(key)
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(default)
+    };
+    let interval =
+        Duration::from_secs(setting("RPC_HEALTH_CHECK_SECS", DEFAULT_HEALTH_CHECK_SECS).max(1));
+    let max_slot_lag = setting("RPC_MAX_SLOT_LAG", DEFAULT_MAX_SLOT_LAG);
+    if pool.len() < 2 {
+        return;
+    }
+    loop {
+        pool.check_health(max_slot_lag).await;
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks_healthy_fast_endpoints_first() {
+        let now = Instant::now();
+        let measured = |ms: u64| {
+            let mut health = EndpointHealth::default();
+            health.record_success(Duration::from_millis(ms));
+            health
+        };
+        let mut flaky = measured(20);
+        assert!(!flaky.record_failure(now));
+        assert!(!flaky.record_failure(now));
+        let mut down = measured(5);
+        for _ in 0..MAX_FAILURES - 1 {
+            down.record_failure(now);
+        }
+        assert!(down.record_failure(now));
+        assert!(!down.is_healthy(now));
+        assert!(down.is_healthy(now + Duration::from_secs(DOWN_SECS)));
+
+        // Slow but steady beats fast but failing a third of the time; down goes last
+        let health = [down, flaky, measured(25), EndpointHealth::default()];
+        assert_eq!(rank(&health, now), vec![3, 2, 1, 0]);
+
+        flaky.record_success(Duration::from_millis(20));
+        assert_eq!(flaky.failures, 0);
+        assert!((flaky.error_rate - 0.288).abs() < 1e-9);
+    }
+}
@@ -1,17 +1,86 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use borsh::BorshDeserialize;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
 use spl_token_2022::{
-    extension::StateWithExtensionsOwned,
+    extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        StateWithExtensionsOwned,
+    },
     state::{Account, Mint},
 };
 use spl_token_client::{
     client::{ProgramClient, ProgramRpcClient, ProgramRpcClientSendTransaction},
     token::{Token, TokenError, TokenResult},
 };
-use std::sync::Arc;
+use crate::core::tx::TxSigner;
+use crate::dex::pump::{get_pda, BondingCurveAccount, PUMP_GLOBAL_ID, PUMP_PROGRAM_ID};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+/// Owning program of each mint seen so far; a mint never changes program
+static TOKEN_PROGRAMS: LazyLock<RwLock<HashMap<Pubkey, Pubkey>>> = LazyLock::new(Default::default);
+
+/// True for the legacy SPL token program and Token-2022
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == spl_token::ID || *program == spl_token_2022::ID
+}
+
+/// Token program that owns `mint`
+pub async fn get_token_program(
+    client: &solana_client::nonblocking::rpc_client::RpcClient,
+    mint: &Pubkey,
+) -> anyhow::Result<Pubkey> {
+    if let Some(program) = TOKEN_PROGRAMS.read().unwrap().get(mint) {
+        return Ok(*program);
+    }
+    let program = client.get_account(mint).await?.owner;
+    if !is_token_program(&program) {
+        return Err(anyhow::anyhow!("{} is not a token mint", mint));
+    }
+    TOKEN_PROGRAMS.write().unwrap().insert(*mint, program);
+    Ok(program)
+}
+
+/// `owner`'s associated account for `mint`, under whichever token program owns the mint
+pub async fn get_token_account_address(
+    client: &solana_client::nonblocking::rpc_client::RpcClient,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> anyhow::Result<Pubkey> {
+    let program = get_token_program(client, mint).await?;
+    Ok(get_associated_token_address_with_program_id(owner, mint, &program))
+}
+
+/// Transfer fee settings of a Token-2022 mint account, if it has any
+pub fn transfer_fee_config(mint: &solana_sdk::account::Account) -> Option<TransferFeeConfig> {
+    if mint.owner != spl_token_2022::ID {
+        return None;
+    }
+    let state = StateWithExtensions::<Mint>::unpack(&mint.data).ok()?;
+    state.get_extension::<TransferFeeConfig>().ok().copied()
+}
+
+/// What a Token-2022 mint withholds from a transfer of `amount`; zero for legacy mints and
+/// mints without a fee. The epoch is only fetched for mints that have one
+pub async fn transfer_fee(
+    client: &solana_client::nonblocking::rpc_client::RpcClient,
+    mint: &solana_sdk::account::Account,
+    amount: u64,
+) -> anyhow::Result<u64> {
+    let Some(config) = transfer_fee_config(mint) else {
+        return Ok(0);
+    };
+    let epoch = client.get_epoch_info().await?.epoch;
+    Ok(config.calculate_epoch_fee(epoch, amount).unwrap_or(0))
+}
 
 pub fn get_associated_token_address(
     client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
-    keypair: Arc<Keypair>,
+    keypair: Arc<TxSigner>,
     address: &Pubkey,
     owner: &Pubkey,
 ) -> Pubkey {
@@ -23,7 +92,7 @@ pub fn get_associated_token_address(
         &spl_token::ID,
         address,
         None,
-        Arc::new(Keypair::from_bytes(&keypair.to_bytes()).expect("failed to copy keypair")),
+        keypair,
     );
     token_client.get_associated_token_address(owner)
 }
@@ -44,7 +113,7 @@ pub async fn get_account_info(
         .ok_or(TokenError::AccountNotFound)
         .inspect_err(|err| println!("get_account_info: {} {}: mint {}", account, err, address))?;
 
-    if account.owner != spl_token::ID {
+    if !is_token_program(&account.owner) {
         return Err(TokenError::AccountInvalidOwner);
     }
     let account = StateWithExtensionsOwned::<Account>::unpack(account.data)?;
@@ -57,7 +126,7 @@ pub async fn get_account_info(
 
 pub async fn get_mint_info(
     client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
-    _keypair: Arc<Keypair>,
+    _keypair: Arc<TxSigner>,
     address: &Pubkey,
 ) -> TokenResult<StateWithExtensionsOwned<Mint>> {
     let program_client = Arc::new(ProgramRpcClient::new(
@@ -71,7 +140,7 @@ pub async fn get_mint_info(
         .ok_or(TokenError::AccountNotFound)
         .inspect_err(|err| println!("{} {}: mint {}", address, err, address))?;
 
-    if account.owner != spl_token::ID {
+    if !is_token_program(&account.owner) {
         return Err(TokenError::AccountInvalidOwner);
     }
 
@@ -85,3 +154,118 @@ pub async fn get_mint_info(
 
     mint_result
 }
+
+/// Metaplex token metadata program, which holds the name and symbol of SPL mints
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+// Key, update authority and mint precede the name
+const METADATA_NAME_OFFSET: usize = 1 + 32 + 32;
+
+/// Name and symbol a mint was launched with
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+}
+
+impl TokenMetadata {
+    /// Decodes the start of a Metaplex metadata account; strings are null-padded on chain
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut offset = METADATA_NAME_OFFSET;
+        let mut string = || {
+            let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+            let bytes = data.get(offset + 4..offset + 4 + len)?;
+            offset += 4 + len;
+            Some(String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string())
+        };
+        let name = string()?;
+        let symbol = string()?;
+        Some(Self { name, symbol })
+    }
+}
+
+pub fn get_metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+pub async fn get_token_metadata(
+    client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    mint: &Pubkey,
+) -> anyhow::Result<TokenMetadata> {
+    let data = client.get_account_data(&get_metadata_address(mint)).await?;
+    TokenMetadata::decode(&data).ok_or_else(|| anyhow::anyhow!("Bad metadata account for {}", mint))
+}
+
+// getMultipleAccounts takes at most this many keys per call
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Fetches any number of accounts in as few `getMultipleAccounts` round trips as the RPC allows,
+/// in the order of `keys`
+pub fn get_multiple_accounts(
+    client: &solana_client::rpc_client::RpcClient,
+    keys: &[Pubkey],
+) -> anyhow::Result<Vec<Option<solana_sdk::account::Account>>> {
+    let mut accounts = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(client.get_multiple_accounts(chunk)?);
+    }
+    Ok(accounts)
+}
+
+/// Everything a pump.fun trade reads before it can be built, from a single round trip
+#[derive(Debug, Clone)]
+pub struct PumpAccounts {
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+    pub bonding_curve_account: solana_sdk::account::Account,
+    /// Missing only in the moment between a curve's creation and its token account's
+    pub associated_bonding_curve_account: Option<solana_sdk::account::Account>,
+    pub mint_account: solana_sdk::account::Account,
+    pub global_account: solana_sdk::account::Account,
+    /// Slot the accounts were read at
+    pub slot: u64,
+}
+
+impl PumpAccounts {
+    pub fn curve(&self) -> anyhow::Result<BondingCurveAccount> {
+        // Newer curves carry fields past the ones decoded here
+        Ok(BondingCurveAccount::deserialize(
+            &mut self.bonding_curve_account.data.as_slice(),
+        )?)
+    }
+}
+
+/// Bonding curve, its token account, the mint and pump.fun's global account in one call
+pub fn get_pump_accounts(
+    client: &solana_client::rpc_client::RpcClient,
+    mint: &Pubkey,
+) -> anyhow::Result<PumpAccounts> {
+    let bonding_curve = get_pda(mint, &PUMP_PROGRAM_ID)?;
+    let associated_bonding_curve = get_associated_token_address(&bonding_curve, mint);
+    let keys = [bonding_curve, associated_bonding_curve, *mint, PUMP_GLOBAL_ID];
+    let response = client.get_multiple_accounts_with_commitment(&keys, client.commitment())?;
+    let slot = response.context.slot;
+    let mut accounts = response.value.into_iter();
+    let mut next = |name: &str| {
+        accounts
+            .next()
+            .flatten()
+            .ok_or_else(|| anyhow::anyhow!("No {} account for {}", name, mint))
+    };
+    let bonding_curve_account = next("bonding curve")?;
+    let associated_bonding_curve_account = next("bonding curve token").ok();
+    let mint_account = next("mint")?;
+    let global_account = next("pump.fun global")?;
+    Ok(PumpAccounts {
+        bonding_curve,
+        associated_bonding_curve,
+        bonding_curve_account,
+        associated_bonding_curve_account,
+        mint_account,
+        global_account,
+        slot,
+    })
+}
@@ -1,24 +1,48 @@
-use std::{env, sync::Arc, time::Duration};
+use std::{
+    env,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures_util::future::{select_ok, BoxFuture, FutureExt};
 use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
-use solana_client::rpc_client::RpcClient;
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+};
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     hash::Hash,
     instruction::Instruction,
-    signature::{Keypair, Signature},
+    pubkey::Pubkey,
+    signature::Signature,
     signer::Signer,
     transaction::{Transaction, VersionedTransaction},
 };
 use std::str::FromStr;
-use tokio::time::{sleep, Instant};
+use tokio::{
+    runtime::{Handle, RuntimeFlavor},
+    task::block_in_place,
+    time::{sleep, timeout, Instant},
+};
 
 use crate::{
     common::utils::log_message,
+    core::{
+        alt::sign_with_table, ata::KNOWN_ATAS, errors::TxError, fault, rpc_pool::RPC_POOL,
+        tx_archive::TX_ARCHIVE,
+    },
+    engine::recovery::PENDING_TXS,
+    services::blockhash::BLOCKHASH_CACHE,
+    services::leader_schedule::LEADER_SCHEDULE,
+    services::metrics::METRICS,
+    services::relay::RelaySender,
     services::jito::{
-        get_tip_account, get_tip_value, init_tip_accounts, wait_for_bundle_confirmation,
+        get_tip_account, get_tip_value, init_tip_accounts, BundleOutcome, BLOCK_ENGINES,
     },
 };
 
@@ -28,6 +52,8 @@ const DEFAULT_UNIT_LIMIT: u32 = 300_000;
 const MAX_RETRIES: u32 = 3;
 const RETRY_DELAY_MS: u64 = 1000;
 const CONFIRMATION_TIMEOUT_SECS: u64 = 60;
+const MAX_BUNDLE_TXS: usize = 5;
+const DEFAULT_MAX_UNIT_PRICE: u64 = 1_000_000;
 
 /// Configuration for transaction processing
 #[derive(Debug, Clone)]
@@ -36,6 +62,15 @@ pub struct TxConfig {
     pub unit_limit: u32,
     pub max_retries: u32,
     pub use_jito: bool,
+    /// Race the signed transaction through every pooled RPC endpoint and Jito at once
+    pub broadcast: bool,
+    /// Fast lane tried before falling back to plain RPC
+    pub sender: SenderKind,
+    /// Percent the unit price rises on each RPC retry, up to `max_unit_price`
+    pub fee_escalation_pct: u64,
+    pub max_unit_price: u64,
+    /// Simulate before sending, failing fast with a decoded [`TxError`]
+    pub preflight: bool,
 }
 
 impl Default for TxConfig {
@@ -45,10 +80,154 @@ impl Default for TxConfig {
             unit_limit: get_unit_limit(),
             max_retries: MAX_RETRIES,
             use_jito: true,
+            broadcast: get_broadcast(),
+            sender: get_sender(),
+            fee_escalation_pct: get_fee_escalation_pct(),
+            max_unit_price: get_max_unit_price(),
+            preflight: get_preflight(),
         }
     }
 }
 
+/// Which relay gets a transaction on chain ahead of plain RPC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderKind {
+    Jito,
+    BloXroute,
+    Nextblock,
+    Rpc,
+}
+
+impl FromStr for SenderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "jito" => Ok(SenderKind::Jito),
+            "bloxroute" => Ok(SenderKind::BloXroute),
+            "nextblock" => Ok(SenderKind::Nextblock),
+            "rpc" => Ok(SenderKind::Rpc),
+            _ => Err(anyhow!(
+                "Invalid tx sender: '{}'. Use jito, bloxroute, nextblock or rpc",
+                s
+            )),
+        }
+    }
+}
+
+/// Whatever signs for a wallet: an in-memory keypair, a Ledger or a remote signing service.
+/// Everything that builds or sends transactions takes this rather than a `Keypair`
+pub type TxSigner = dyn Signer + Send + Sync;
+
+/// Runs `sign` for `keypair`. A Ledger waits on the device for as long as the user takes to
+/// confirm and a remote signer on the network, so their signing moves off the async worker; a
+/// keypair signs in place
+pub fn signing<T>(keypair: &TxSigner, sign: impl FnOnce() -> T) -> T {
+    let multi_thread = Handle::try_current()
+        .is_ok_and(|handle| handle.runtime_flavor() == RuntimeFlavor::MultiThread);
+    if keypair.is_interactive() && multi_thread {
+        block_in_place(sign)
+    } else {
+        sign()
+    }
+}
+
+/// A path for landing a signed transaction
+#[async_trait]
+pub trait TxSender: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// An instruction the sender needs inside the transaction itself, such as a relay tip
+    fn tip_instruction(&self, _payer: &Pubkey) -> Option<Instruction> {
+        None
+    }
+
+    /// Submits the transaction and waits for it to land; returns its signature or bundle id
+    async fn send(
+        &self,
+        keypair: &TxSigner,
+        versioned_tx: VersionedTransaction,
+        recent_blockhash: &Hash,
+    ) -> Result<String>;
+}
+
+/// Lands the transaction as a Jito bundle with a separate tip transaction
+pub struct JitoSender {
+    pub client: Arc<JitoRpcClient>,
+}
+
+#[async_trait]
+impl TxSender for JitoSender {
+    fn name(&self) -> &'static str {
+        "Jito"
+    }
+
+    async fn send(
+        &self,
+        keypair: &TxSigner,
+        versioned_tx: VersionedTransaction,
+        recent_blockhash: &Hash,
+    ) -> Result<String> {
+        jito_confirm(keypair, versioned_tx, recent_blockhash, self.client.clone()).await
+    }
+}
+
+/// Sends through a regular RPC node, resending on slot boundaries when the leader tracker is live
+pub struct RpcSender<'a> {
+    pub client: &'a RpcClient,
+}
+
+#[async_trait]
+impl TxSender for RpcSender<'_> {
+    fn name(&self) -> &'static str {
+        "RPC"
+    }
+
+    async fn send(
+        &self,
+        _keypair: &TxSigner,
+        versioned_tx: VersionedTransaction,
+        _recent_blockhash: &Hash,
+    ) -> Result<String> {
+        let signature = if LEADER_SCHEDULE.is_live() {
+            send_with_leader_timing(self.client, &versioned_tx).await?
+        } else {
+            send_transaction_with_confirmation(self.client, &versioned_tx).await?
+        };
+        Ok(signature.to_string())
+    }
+}
+
+/// The relay picked by `TX_SENDER`, built once on first use
+static RELAY: LazyLock<Result<Arc<RelaySender>, String>> = LazyLock::new(|| {
+    let relay = match get_sender() {
+        SenderKind::BloXroute => RelaySender::bloxroute(),
+        SenderKind::Nextblock => RelaySender::nextblock(),
+        kind => return Err(format!("{:?} is not a relay", kind)),
+    };
+    relay.map(Arc::new).map_err(|e| e.to_string())
+});
+
+/// The configured fast lane, or `None` to go straight to plain RPC
+async fn fast_lane(
+    config: &TxConfig,
+    jito_client: Option<Arc<JitoRpcClient>>,
+) -> Option<Arc<dyn TxSender>> {
+    match config.sender {
+        SenderKind::Jito => jito_client
+            .filter(|_| config.use_jito)
+            .map(|client| Arc::new(JitoSender { client }) as Arc<dyn TxSender>),
+        SenderKind::BloXroute | SenderKind::Nextblock => match &*RELAY {
+            Ok(relay) => Some(relay.clone() as Arc<dyn TxSender>),
+            Err(e) => {
+                let _ = log_message(&format!("Relay unavailable, using RPC: {}", e)).await;
+                None
+            }
+        },
+        SenderKind::Rpc => None,
+    }
+}
+
 /// Get prioritization fee unit price from environment or default
 fn get_unit_price() -> u64 {
     env::var("UNIT_PRICE")
@@ -65,37 +244,204 @@ fn get_unit_limit() -> u32 {
         .unwrap_or(DEFAULT_UNIT_LIMIT)
 }
 
+/// Whether to broadcast through the RPC pool, from `TX_BROADCAST`
+fn get_broadcast() -> bool {
+    env::var("TX_BROADCAST")
+        .ok()
+        .and_then(|v| bool::from_str(&v).ok())
+        .unwrap_or(false)
+}
+
+/// Whether to simulate transactions before sending, from `PREFLIGHT_SIMULATION`
+fn get_preflight() -> bool {
+    env::var("PREFLIGHT_SIMULATION")
+        .ok()
+        .and_then(|v| bool::from_str(&v).ok())
+        .unwrap_or(false)
+}
+
+/// Unit price rise per retry from `RETRY_FEE_ESCALATION_PCT`, off by default
+fn get_fee_escalation_pct() -> u64 {
+    env::var("RETRY_FEE_ESCALATION_PCT")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(0)
+}
+
+/// Ceiling for escalated unit prices from `RETRY_MAX_UNIT_PRICE`
+fn get_max_unit_price() -> u64 {
+    env::var("RETRY_MAX_UNIT_PRICE")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_MAX_UNIT_PRICE)
+}
+
+/// Fast lane from `TX_SENDER`, defaulting to Jito
+fn get_sender() -> SenderKind {
+    env::var("TX_SENDER")
+        .ok()
+        .and_then(|v| SenderKind::from_str(&v).ok())
+        .unwrap_or(SenderKind::Jito)
+}
+
 /// Calculate total prioritization fee
 fn calculate_priority_fee(unit_price: u64, unit_limit: u32) -> u64 {
     unit_price.saturating_mul(unit_limit as u64)
 }
 
-/// Add compute budget instructions for transaction prioritization
-fn add_compute_budget_instructions(
-    instructions: &mut Vec<Instruction>,
+/// Compute budget instructions, then `instructions`, then the relay tip, built in a single
+/// allocation instead of shifting the swap instructions to make room at the front
+pub fn budgeted_instructions(
+    instructions: Vec<Instruction>,
     config: &TxConfig,
-) -> Result<()> {
-    // Set compute unit limit
-    let compute_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(config.unit_limit);
-    instructions.insert(0, compute_limit_ix);
+    tip: Option<Instruction>,
+) -> Vec<Instruction> {
+    let mut budgeted = Vec::with_capacity(instructions.len() + 3);
+    budgeted.push(ComputeBudgetInstruction::set_compute_unit_limit(config.unit_limit));
 
     // Set compute unit price for prioritization
     if config.unit_price > 0 {
-        let compute_price_ix = ComputeBudgetInstruction::set_compute_unit_price(config.unit_price);
-        instructions.insert(1, compute_price_ix);
+        budgeted.push(ComputeBudgetInstruction::set_compute_unit_price(config.unit_price));
     }
+    budgeted.extend(instructions);
+    budgeted.extend(tip);
+    budgeted
+}
 
-    Ok(())
+/// Unit price for the `attempt`th send (1-based), raised `fee_escalation_pct` per retry
+fn escalated_unit_price(config: &TxConfig, attempt: u32) -> u64 {
+    let mut price = config.unit_price;
+    for _ in 1..attempt {
+        price = price.saturating_mul(100 + config.fee_escalation_pct) / 100;
+    }
+    price.min(config.max_unit_price.max(config.unit_price))
+}
+
+/// `instructions` with the compute unit price swapped for `unit_price`, right after the limit
+fn with_unit_price(instructions: &[Instruction], unit_price: u64) -> Vec<Instruction> {
+    let price = ComputeBudgetInstruction::set_compute_unit_price(unit_price);
+    let is_price = |instruction: &Instruction| {
+        instruction.program_id == price.program_id && instruction.data.first() == price.data.first()
+    };
+    let mut repriced = instructions
+        .iter()
+        .filter(|instruction| !is_price(instruction))
+        .cloned()
+        .collect::<Vec<_>>();
+    if unit_price > 0 {
+        repriced.insert(1.min(repriced.len()), price);
+    }
+    repriced
+}
+
+/// Signs `instructions` again at `unit_price` against a blockhash fetched just now, with the
+/// last block height that blockhash is valid for
+fn resign(
+    client: &RpcClient,
+    keypair: &TxSigner,
+    instructions: &[Instruction],
+    unit_price: u64,
+) -> Result<(VersionedTransaction, u64)> {
+    let (recent_blockhash, last_valid_block_height) = client
+        .get_latest_blockhash_with_commitment(client.commitment())
+        .inspect_err(|_| METRICS.rpc_error("get_latest_blockhash"))
+        .context("Failed to get recent blockhash")?;
+    let instructions = with_unit_price(instructions, unit_price);
+    let versioned_tx =
+        signing(keypair, || sign_with_table(keypair, &instructions, recent_blockhash))?;
+    TX_ARCHIVE.record_transaction(&versioned_tx);
+    Ok((versioned_tx, last_valid_block_height))
+}
+
+/// Whether `versioned_tx`, whose send ended in `error`, landed after all. Unless the node
+/// refused it outright, waits until it can no longer land: it has a confirmed status, or its
+/// blockhash is past `last_valid_block_height` (asked of the node when unknown)
+async fn landed_after_error(
+    client: &RpcClient,
+    versioned_tx: &VersionedTransaction,
+    error: &anyhow::Error,
+    last_valid_block_height: Option<u64>,
+) -> Result<Option<Signature>> {
+    // A decoded transaction error before any signature status means preflight turned it away
+    let refused = error
+        .downcast_ref::<TxError>()
+        .is_some_and(|e| !matches!(e, TxError::Rpc(_) | TxError::Unconfirmed(_)));
+    let signature = versioned_tx.signatures[0];
+    loop {
+        // Expiry first, so a landing in the blockhash's last blocks still shows in the status
+        let expired = match last_valid_block_height {
+            Some(height) => client.get_block_height()? > height,
+            None => !client.is_blockhash_valid(
+                versioned_tx.message.recent_blockhash(),
+                CommitmentConfig::processed(),
+            )?,
+        };
+        let status = client
+            .get_signature_statuses(&[signature])
+            .inspect_err(|_| METRICS.rpc_error("get_signature_statuses"))?
+            .value
+            .remove(0);
+        match status {
+            // One that failed on chain can't land again
+            Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                return Ok(status.err.is_none().then_some(signature));
+            }
+            // Seen but not yet confirmed; it could still go either way
+            Some(_) => {}
+            None if refused || expired => return Ok(None),
+            None => {}
+        }
+        sleep(Duration::from_millis(RETRY_DELAY_MS)).await;
+    }
+}
+
+/// Whether a relay's failed send certainly never went out: it answered with an error or could
+/// not be reached. A timeout or a failed confirmation leaves the transaction possibly in flight
+fn relay_refused(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| !e.is_timeout())
+}
+
+/// Prefetched blockhash if fresh, otherwise a synchronous fetch
+async fn recent_blockhash(client: &RpcClient) -> Result<Hash> {
+    if let Some(hash) = BLOCKHASH_CACHE.fresh().await {
+        return Ok(hash);
+    }
+    let _ = log_message("Blockhash cache is stale, fetching on the hot path").await;
+    fault::delay_rpc("get_latest_blockhash").await;
+    client
+        .get_latest_blockhash()
+        .inspect_err(|_| METRICS.rpc_error("get_latest_blockhash"))
+        .map_err(|e| TxError::Rpc(format!("Failed to get recent blockhash: {}", e)).into())
 }
 
 /// Confirm transaction using Jito bundle service
 pub async fn jito_confirm(
-    keypair: &Keypair,
+    keypair: &TxSigner,
     versioned_tx: VersionedTransaction,
     recent_block_hash: &Hash,
     jito_client: Arc<JitoRpcClient>,
 ) -> Result<String> {
-    log_message("Starting Jito bundle confirmation");
+    jito_bundle_confirm(keypair, vec![versioned_tx], recent_block_hash, jito_client).await
+}
+
+/// Send several transactions plus a tip as one atomic bundle and wait for it to land.
+/// The bundle goes to `jito_client`'s region, or the next healthy one if that refuses it;
+/// with `JITO_FANOUT` to every healthy region, landing through whichever is first
+pub async fn jito_bundle_confirm(
+    keypair: &TxSigner,
+    versioned_txs: Vec<VersionedTransaction>,
+    recent_block_hash: &Hash,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<String> {
+    if versioned_txs.len() >= MAX_BUNDLE_TXS {
+        return Err(anyhow::anyhow!(
+            "Bundle holds at most {} transactions besides the tip",
+            MAX_BUNDLE_TXS - 1
+        ));
+    }
+    let _ = log_message("Starting Jito bundle confirmation").await;
 
     // Initialize tip accounts and get tip details concurrently
     let (tip_account, tip_value) = tokio::try_join!(
@@ -107,8 +453,8 @@ pub async fn jito_confirm(
     )?;
 
     // Pre-allocate bundle vector with known capacity
-    let mut bundle_txs = Vec::with_capacity(2);
-    bundle_txs.push(versioned_tx);
+    let mut bundle_txs = Vec::with_capacity(versioned_txs.len() + 1);
+    bundle_txs.extend(versioned_txs);
 
     // Create and add tip transaction
     let tip_instruction = solana_sdk::system_instruction::transfer(
@@ -117,120 +463,456 @@ pub async fn jito_confirm(
         tip_value,
     );
 
-    let tip_tx = Transaction::new_signed_with_payer(
-        &[tip_instruction],
-        Some(&keypair.pubkey()),
-        &[keypair],
-        *recent_block_hash,
-    );
+    let tip_tx = signing(keypair, || {
+        Transaction::new_signed_with_payer(
+            &[tip_instruction],
+            Some(&keypair.pubkey()),
+            &[keypair as &dyn Signer],
+            *recent_block_hash,
+        )
+    });
     
     bundle_txs.push(VersionedTransaction::from(tip_tx));
 
-    // Send bundle and wait for confirmation concurrently
-    let bundle_id = jito_client
-        .send_bundle(&bundle_txs)
-        .await
-        .context("Failed to send bundle to Jito")?;
-
-    log_message(&format!("Bundle sent with ID: {}", bundle_id));
-
-    // Use a more efficient confirmation with early return
-    tokio::time::timeout(
-        Duration::from_secs(CONFIRMATION_TIMEOUT_SECS),
-        wait_for_bundle_confirmation(&bundle_id, jito_client),
-    )
-    .await
-    .context("Bundle confirmation timeout")?
-    .context("Bundle confirmation failed")?;
-    
-    log_message("Bundle confirmed successfully");
-    Ok(bundle_id)
+    fault::fail_bundle()?;
+    let sent = BLOCK_ENGINES.send_bundle(&jito_client, &bundle_txs).await;
+    // Every region gets the same transactions, so the bundle ID is the same everywhere
+    TX_ARCHIVE.record_bundle(
+        &bundle_txs,
+        sent.as_ref()
+            .map(|sent| sent[0].1.as_str())
+            .map_err(|e| e.to_string()),
+    );
+    let sent = sent.context("Failed to send bundle to Jito")?;
+    let bundle_id = sent[0].1.clone();
+    METRICS.jito_bundles_sent.inc();
+
+    let regions = sent
+        .iter()
+        .map(|(engine, _)| engine.region.as_str())
+        .collect::<Vec<_>>();
+    let _ = log_message(&format!(
+        "Bundle sent to Jito {} with ID: {}",
+        regions.join(", "),
+        bundle_id
+    ))
+    .await;
+
+    let limit = Duration::from_secs(CONFIRMATION_TIMEOUT_SECS);
+    match BLOCK_ENGINES.wait_for_bundle(&sent, limit).await {
+        BundleOutcome::Landed { slot } => {
+            METRICS.jito_bundles_landed.inc();
+            let _ = log_message(&format!("Bundle confirmed in slot {}", slot)).await;
+            Ok(bundle_id)
+        }
+        // Neither can land any more, so sending again is safe
+        BundleOutcome::Failed(reason) => {
+            Err(TxError::Rejected(format!("bundle {} failed: {}", bundle_id, reason)).into())
+        }
+        BundleOutcome::Dropped => {
+            Err(TxError::Rejected(format!("bundle {} was dropped", bundle_id)).into())
+        }
+        BundleOutcome::Pending => Err(TxError::Unconfirmed(format!(
+            "bundle {} not confirmed within {}s",
+            bundle_id, CONFIRMATION_TIMEOUT_SECS
+        ))
+        .into()),
+    }
+}
+
+/// Send the same signed transaction through every pooled RPC endpoint (and Jito, if given)
+/// concurrently, returning as soon as any path confirms it
+pub async fn broadcast_confirm(
+    keypair: &TxSigner,
+    versioned_tx: VersionedTransaction,
+    recent_block_hash: &Hash,
+    jito_client: Option<Arc<JitoRpcClient>>,
+) -> Result<String> {
+    let mut paths: Vec<BoxFuture<'_, Result<String>>> = Vec::new();
+
+    let rpc_tx = versioned_tx.clone();
+    paths.push(
+        async move {
+            let signature = RPC_POOL.broadcast(&rpc_tx).await?;
+            RPC_POOL
+                .confirm_any(&signature, Duration::from_secs(CONFIRMATION_TIMEOUT_SECS))
+                .await?;
+            Ok(signature.to_string())
+        }
+        .boxed(),
+    );
+    if let Some(jito_client) = jito_client {
+        paths.push(jito_confirm(keypair, versioned_tx, recent_block_hash, jito_client).boxed());
+    }
+
+    let _ = log_message(&format!(
+        "Broadcasting transaction via {} RPC endpoints{}",
+        RPC_POOL.len(),
+        if paths.len() > 1 { " and Jito" } else { "" }
+    ))
+    .await;
+    let (landed, _) = select_ok(paths).await?;
+    Ok(landed)
 }
 
 /// Create, sign, and send transaction with retry logic
 pub async fn new_signed_and_send(
     client: &RpcClient,
-    keypair: &Keypair,
-    mut instructions: Vec<Instruction>,
+    keypair: &TxSigner,
+    instructions: Vec<Instruction>,
     jito_client: Option<Arc<JitoRpcClient>>,
     config: Option<TxConfig>,
     timestamp: Instant,
-) -> Result<Vec<String>> {
+) -> Result<Vec<String>, TxError> {
     let config = config.unwrap_or_default();
     let mut results = Vec::new();
-    
-    log_message(&format!(
+    let priority_fee = calculate_priority_fee(config.unit_price, config.unit_limit);
+
+    let _ = log_message(&format!(
         "Processing transaction with {} instructions (Priority fee: {} lamports)",
         instructions.len(),
-        calculate_priority_fee(config.unit_price, config.unit_limit)
-    ));
+        priority_fee
+    ))
+    .await;
+    METRICS.priority_fees_lamports.inc_by(priority_fee);
 
-    // Add compute budget instructions for prioritization
-    add_compute_budget_instructions(&mut instructions, &config)?;
+    // Relays are paid by a tip inside the transaction, so pick one before signing
+    let broadcast = config.broadcast && !RPC_POOL.is_empty();
+    let sender = if broadcast {
+        None
+    } else {
+        fast_lane(&config, jito_client.clone()).await
+    };
+    let tip = sender
+        .as_ref()
+        .and_then(|sender| sender.tip_instruction(&keypair.pubkey()));
+    let tipped = tip.is_some();
+    let mut instructions = budgeted_instructions(instructions, &config, tip);
 
     // Get recent blockhash
-    let recent_blockhash = client
-        .get_latest_blockhash()
-        .context("Failed to get recent blockhash")?;
+    let recent_blockhash = recent_blockhash(client).await?;
 
-    // Create and sign transaction
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&keypair.pubkey()),
-        &[keypair],
-        recent_blockhash,
-    );
+    // Create and sign transaction, as v0 against the lookup table once one is installed
+    let versioned_tx =
+        signing(keypair, || sign_with_table(keypair, &instructions, recent_blockhash))?;
+    TX_ARCHIVE.record_transaction(&versioned_tx);
+    if config.preflight {
+        preflight(client, &versioned_tx).await?;
+    }
+    // Journaled until this returns, so a crash mid-send leaves it for startup recovery
+    let mut pending = vec![PENDING_TXS.track(&versioned_tx).await];
+    // Retries re-sign the same instructions, so this holds for whichever lands
+    let atas = KNOWN_ATAS.begin(&versioned_tx.message);
 
-    let versioned_tx = VersionedTransaction::from(transaction);
+    if broadcast {
+        let jito_client = jito_client.filter(|_| config.use_jito);
+        let landed = broadcast_confirm(keypair, versioned_tx, &recent_blockhash, jito_client).await?;
+        let _ = log_message(&format!(
+            "Transaction landed via broadcast (took: {:?})",
+            timestamp.elapsed()
+        ))
+        .await;
+        KNOWN_ATAS.landed(&atas);
+        return Ok(vec![landed]);
+    }
 
-    // Try Jito first if available and enabled
-    if config.use_jito && jito_client.is_some() {
-        match jito_confirm(
-            keypair,
-            versioned_tx.clone(),
-            &recent_blockhash,
-            jito_client.unwrap(),
-        )
-        .await
+    // Try the configured fast lane first
+    let mut versioned_tx = versioned_tx;
+    if let Some(sender) = sender {
+        match sender
+            .send(keypair, versioned_tx.clone(), &recent_blockhash)
+            .await
         {
-            Ok(bundle_id) => {
-                results.push(bundle_id);
-                log_message("Transaction sent successfully via Jito");
+            Ok(id) => {
+                results.push(id);
+                KNOWN_ATAS.landed(&atas);
+                let _ = log_message(&format!(
+                    "Transaction sent successfully via {}",
+                    sender.name()
+                ))
+                .await;
                 return Ok(results);
             }
             Err(e) => {
-                log_message(&format!("Jito submission failed: {}, falling back to RPC", e));
+                let _ = log_message(&format!(
+                    "{} submission failed: {}, falling back to RPC",
+                    sender.name(),
+                    e
+                ))
+                .await;
+                // Plain RPC doesn't earn the relay's tip, so the fallback is signed without it
+                if tipped {
+                    // A relay that took the transaction may still land it; a copy must not race
+                    if !relay_refused(&e) {
+                        match landed_after_error(client, &versioned_tx, &e, None).await {
+                            Ok(Some(signature)) => {
+                                KNOWN_ATAS.landed(&atas);
+                                return Ok(vec![signature.to_string()]);
+                            }
+                            Ok(None) => {}
+                            Err(_) => return Err(e.into()),
+                        }
+                    }
+                    // `budgeted_instructions` put the tip last
+                    instructions.pop();
+                    versioned_tx = resign(client, keypair, &instructions, config.unit_price)?.0;
+                    pending.push(PENDING_TXS.track(&versioned_tx).await);
+                }
             }
         }
     }
 
-    // Fallback to regular RPC with retry logic
+    if LEADER_SCHEDULE.is_live() {
+        let signature = send_with_leader_timing(client, &versioned_tx).await?;
+        KNOWN_ATAS.landed(&atas);
+        let _ = log_message(&format!(
+            "Transaction sent successfully via RPC with slot-timed resends (took: {:?})",
+            timestamp.elapsed()
+        ))
+        .await;
+        return Ok(vec![signature.to_string()]);
+    }
+
+    // Fallback to regular RPC. Retries are re-signed against a fresh blockhash, so an expired
+    // one can't sink every attempt, and pay more for priority when escalation is on
+    let mut last_valid_block_height = BLOCKHASH_CACHE
+        .latest()
+        .await
+        .filter(|cached| cached.hash == recent_blockhash)
+        .map(|cached| cached.last_valid_block_height);
     let mut last_error = None;
     for attempt in 1..=config.max_retries {
+        if attempt > 1 {
+            let unit_price = escalated_unit_price(&config, attempt);
+            match resign(client, keypair, &instructions, unit_price) {
+                Ok((resigned, valid_until)) => {
+                    pending.push(PENDING_TXS.track(&resigned).await);
+                    versioned_tx = resigned;
+                    last_valid_block_height = Some(valid_until);
+                }
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            }
+            let _ = log_message(&format!(
+                "Retry {} re-signed with a fresh blockhash at unit price {}",
+                attempt, unit_price
+            ))
+            .await;
+        }
         match send_transaction_with_confirmation(client, &versioned_tx).await {
             Ok(signature) => {
                 results.push(signature.to_string());
-                log_message(&format!(
+                KNOWN_ATAS.landed(&atas);
+                let _ = log_message(&format!(
                     "Transaction sent successfully via RPC on attempt {} (took: {:?})",
                     attempt,
                     timestamp.elapsed()
-                ));
+                ))
+                .await;
                 return Ok(results);
             }
             Err(e) => {
-                last_error = Some(e);
+                // A re-signed copy must not race this one: if both landed the trade would double
                 if attempt < config.max_retries {
-                    log_message(&format!(
+                    let landed =
+                        landed_after_error(client, &versioned_tx, &e, last_valid_block_height)
+                            .await;
+                    match landed {
+                        Ok(Some(signature)) => {
+                            results.push(signature.to_string());
+                            KNOWN_ATAS.landed(&atas);
+                            let _ = log_message(&format!(
+                                "Transaction attempt {} landed despite: {}",
+                                attempt, e
+                            ))
+                            .await;
+                            return Ok(results);
+                        }
+                        Ok(None) => {}
+                        Err(status_error) => {
+                            let _ = log_message(&format!(
+                                "Not retrying: couldn't tell whether attempt {} landed: {}",
+                                attempt, status_error
+                            ))
+                            .await;
+                            return Err(e.into());
+                        }
+                    }
+                    let _ = log_message(&format!(
                         "Transaction attempt {} failed, retrying in {}ms",
                         attempt, RETRY_DELAY_MS
-                    ));
+                    ))
+                    .await;
                     sleep(Duration::from_millis(RETRY_DELAY_MS)).await;
                 }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| anyhow::anyhow!("All transaction attempts failed"))
+        .into())
+}
+
+/// Sign each instruction set as its own transaction and land them all in a single bundle.
+/// There is no RPC fallback: either every transaction lands in the same block or none do.
+/// Returns the signatures of the transactions, in order
+pub async fn send_bundle_only(
+    client: &RpcClient,
+    keypair: &TxSigner,
+    instruction_sets: Vec<Vec<Instruction>>,
+    jito_client: Arc<JitoRpcClient>,
+    config: Option<TxConfig>,
+) -> Result<Vec<String>, TxError> {
+    let config = config.unwrap_or_default();
+    let recent_blockhash = recent_blockhash(client).await?;
+
+    let mut versioned_txs = Vec::with_capacity(instruction_sets.len());
+    for instructions in instruction_sets {
+        let instructions = budgeted_instructions(instructions, &config, None);
+        versioned_txs.push(signing(keypair, || {
+            sign_with_table(keypair, &instructions, recent_blockhash)
+        })?);
+    }
+    let signatures = versioned_txs
+        .iter()
+        .map(|tx| tx.signatures[0].to_string())
+        .collect();
+
+    let bundle_id =
+        jito_bundle_confirm(keypair, versioned_txs, &recent_blockhash, jito_client).await?;
+    let _ = log_message(&format!("Bundle {} landed", bundle_id)).await;
+    Ok(signatures)
+}
+
+/// Sign a prebuilt versioned transaction (e.g. from an aggregator) and send it
+pub async fn sign_and_send_versioned(
+    client: &RpcClient,
+    keypair: &TxSigner,
+    unsigned_tx: VersionedTransaction,
+    jito_client: Option<Arc<JitoRpcClient>>,
+    timestamp: Instant,
+) -> Result<Vec<String>, TxError> {
+    let versioned_tx = signing(keypair, || {
+        VersionedTransaction::try_new(unsigned_tx.message, &[keypair as &dyn Signer])
+    })
+    .context("Failed to sign versioned transaction")?;
+    send_signed(client, keypair, versioned_tx, jito_client, timestamp).await
+}
+
+/// Send an already signed transaction (e.g. a prebuilt emergency exit) as is
+pub async fn send_signed(
+    client: &RpcClient,
+    keypair: &TxSigner,
+    versioned_tx: VersionedTransaction,
+    jito_client: Option<Arc<JitoRpcClient>>,
+    timestamp: Instant,
+) -> Result<Vec<String>, TxError> {
+    let config = TxConfig::default();
+    let recent_blockhash = *versioned_tx.message.recent_blockhash();
+    TX_ARCHIVE.record_transaction(&versioned_tx);
+    if config.preflight {
+        preflight(client, &versioned_tx).await?;
+    }
+    let _pending = PENDING_TXS.track(&versioned_tx).await;
+    let atas = KNOWN_ATAS.begin(&versioned_tx.message);
+
+    if config.broadcast && !RPC_POOL.is_empty() {
+        let jito_client = jito_client.filter(|_| config.use_jito);
+        let landed = broadcast_confirm(keypair, versioned_tx, &recent_blockhash, jito_client).await?;
+        KNOWN_ATAS.landed(&atas);
+        return Ok(vec![landed]);
+    }
+
+    // A prebuilt message has no room for a relay tip, so only tipless senders apply here
+    let sender = fast_lane(&config, jito_client)
+        .await
+        .filter(|sender| sender.tip_instruction(&keypair.pubkey()).is_none());
+    if let Some(sender) = sender {
+        match sender
+            .send(keypair, versioned_tx.clone(), &recent_blockhash)
+            .await
+        {
+            Ok(id) => {
+                KNOWN_ATAS.landed(&atas);
+                return Ok(vec![id]);
+            }
+            Err(e) => {
+                let _ = log_message(&format!(
+                    "{} submission failed: {}, falling back to RPC",
+                    sender.name(),
+                    e
+                ))
+                .await;
             }
         }
     }
 
-    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("All transaction attempts failed")))
+    let signature = RpcSender { client }
+        .send(keypair, versioned_tx, &recent_blockhash)
+        .await?;
+    KNOWN_ATAS.landed(&atas);
+    let _ = log_message(&format!(
+        "Versioned transaction sent via RPC (took: {:?})",
+        timestamp.elapsed()
+    ))
+    .await;
+    Ok(vec![signature])
+}
+
+/// Simulates `versioned_tx`, failing with the decoded [`TxError`] if it would fail on chain.
+/// A simulation that can't be run lets the send go ahead rather than costing the trade
+async fn preflight(client: &RpcClient, versioned_tx: &VersionedTransaction) -> Result<(), TxError> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        commitment: Some(CommitmentConfig::processed()),
+        ..Default::default()
+    };
+    let simulation = match client.simulate_transaction_with_config(versioned_tx, config) {
+        Ok(response) => response.value,
+        Err(e) => {
+            METRICS.rpc_error("simulate_transaction");
+            let _ = log_message(&format!(
+                "Pre-flight simulation unavailable, sending anyway: {}",
+                e
+            ))
+            .await;
+            return Ok(());
+        }
+    };
+    let Some(err) = simulation.err else {
+        return Ok(());
+    };
+    let error = TxError::decode(&err, &versioned_tx.message);
+    let last_log = simulation
+        .logs
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .find(|line| line.contains("Error"));
+    let _ = log_message(&format!(
+        "Pre-flight simulation failed: {}{}",
+        error,
+        last_log.map(|line| format!(" ({})", line)).unwrap_or_default()
+    ))
+    .await;
+    Err(error)
+}
+
+/// A failed send or confirmation as a [`TxError`]. The node's preflight or the landed
+/// transaction may carry the program's error; anything else becomes `otherwise`
+fn client_error(
+    err: ClientError,
+    versioned_tx: &VersionedTransaction,
+    otherwise: fn(String) -> TxError,
+) -> TxError {
+    match err.get_transaction_error() {
+        Some(err) => TxError::decode(&err, &versioned_tx.message),
+        None => otherwise(err.to_string()),
+    }
 }
 
 /// Send transaction and wait for confirmation
@@ -239,39 +921,95 @@ async fn send_transaction_with_confirmation(
     versioned_tx: &VersionedTransaction,
 ) -> Result<Signature> {
     // Send transaction
+    fault::delay_rpc("send_transaction").await;
     let signature = client
         .send_transaction(versioned_tx)
-        .context("Failed to send transaction")?;
+        .inspect_err(|_| METRICS.rpc_error("send_transaction"))
+        .map_err(|e| client_error(e, versioned_tx, TxError::Rpc))?;
 
     // Wait for confirmation
     let confirmation = client
         .confirm_transaction_with_spinner(
             &signature,
-            &recent_blockhash,
+            versioned_tx.message.recent_blockhash(),
             CommitmentConfig::confirmed(),
         )
-        .context("Failed to confirm transaction")?;
+        .inspect_err(|_| METRICS.rpc_error("confirm_transaction"))
+        .map_err(|e| client_error(e, versioned_tx, TxError::Unconfirmed))?;
 
     if confirmation {
         Ok(signature)
     } else {
-        Err(anyhow::anyhow!("Transaction confirmation failed"))
+        Err(TxError::Unconfirmed(signature.to_string()).into())
+    }
+}
+
+/// Send once with preflight, then resend at every slot boundary until the signature lands
+async fn send_with_leader_timing(
+    client: &RpcClient,
+    versioned_tx: &VersionedTransaction,
+) -> Result<Signature> {
+    let mut slots = LEADER_SCHEDULE.subscribe();
+    let signature = client
+        .send_transaction(versioned_tx)
+        .map_err(|e| client_error(e, versioned_tx, TxError::Rpc))?;
+    let resend_config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        max_retries: Some(0),
+        ..Default::default()
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(CONFIRMATION_TIMEOUT_SECS);
+    let mut last_leader = None;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        // A stalled tracker falls back to polling at the old fixed delay
+        let wait = remaining.min(Duration::from_millis(RETRY_DELAY_MS));
+        let next_slot = match timeout(wait, slots.changed()).await {
+            Ok(Ok(())) => (*slots.borrow_and_update()).map(|tick| tick.slot),
+            _ => None,
+        };
+
+        if let Some(status) = client.get_signature_status(&signature)? {
+            status.map_err(|err| TxError::decode(&err, &versioned_tx.message))?;
+            return Ok(signature);
+        }
+        if Instant::now() >= deadline {
+            return Err(TxError::Unconfirmed(format!(
+                "{} not confirmed within {}s",
+                signature, CONFIRMATION_TIMEOUT_SECS
+            ))
+            .into());
+        }
+
+        if let Some(slot) = next_slot {
+            let leader = LEADER_SCHEDULE.leader_at(slot).await;
+            if leader.is_some() && leader != last_leader {
+                let _ = log_message(&format!(
+                    "Resending {} to new leader at slot {}",
+                    signature, slot
+                ))
+                .await;
+                last_leader = leader;
+            }
+        }
+        let _ = client.send_transaction_with_config(versioned_tx, resend_config);
     }
 }
 
 /// Batch process multiple transactions
 pub async fn batch_send_transactions(
     client: &RpcClient,
-    keypair: &Keypair,
+    keypair: &TxSigner,
     instruction_batches: Vec<Vec<Instruction>>,
     jito_client: Option<Arc<JitoRpcClient>>,
     config: Option<TxConfig>,
-) -> Result<Vec<String>> {
+) -> Result<Vec<String>, TxError> {
     let mut all_results = Vec::new();
     let timestamp = Instant::now();
 
     for (i, instructions) in instruction_batches.into_iter().enumerate() {
-        log_message(&format!("Processing batch {} of transactions", i + 1));
+        let _ = log_message(&format!("Processing batch {} of transactions", i + 1)).await;
         
         match new_signed_and_send(
             client,
@@ -285,7 +1023,7 @@ pub async fn batch_send_transactions(
         {
             Ok(mut results) => all_results.append(&mut results),
             Err(e) => {
-                log_message(&format!("Batch {} failed: {}", i + 1, e));
+                let _ = log_message(&format!("Batch {} failed: {}", i + 1, e)).await;
                 return Err(e);
             }
         }
@@ -315,4 +1053,63 @@ mod tests {
         assert!(config.max_retries > 0);
         assert!(config.use_jito);
     }
+
+    #[test]
+    fn test_budgeted_instructions_order() {
+        let swap = solana_sdk::system_instruction::transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        );
+        let tip = solana_sdk::system_instruction::transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            2,
+        );
+        let config = TxConfig::default();
+        let budgeted = budgeted_instructions(vec![swap.clone()], &config, Some(tip.clone()));
+        assert_eq!(budgeted.len(), 4);
+        assert_eq!(
+            budgeted[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(config.unit_limit)
+        );
+        assert_eq!(budgeted[2], swap);
+        assert_eq!(budgeted[3], tip);
+        let free = TxConfig {
+            unit_price: 0,
+            ..config
+        };
+        assert_eq!(budgeted_instructions(vec![swap], &free, None).len(), 2);
+    }
+
+    #[test]
+    fn test_retry_repricing() {
+        let config = TxConfig {
+            unit_price: 1_000,
+            fee_escalation_pct: 50,
+            max_unit_price: 2_000,
+            ..TxConfig::default()
+        };
+        assert_eq!(escalated_unit_price(&config, 1), 1_000);
+        assert_eq!(escalated_unit_price(&config, 2), 1_500);
+        assert_eq!(escalated_unit_price(&config, 3), 2_000);
+
+        let swap = solana_sdk::system_instruction::transfer(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+        );
+        let budgeted = budgeted_instructions(vec![swap.clone()], &config, None);
+        let repriced = with_unit_price(&budgeted, 1_500);
+        assert_eq!(repriced.len(), 3);
+        assert_eq!(repriced[1], ComputeBudgetInstruction::set_compute_unit_price(1_500));
+        assert_eq!(repriced[2], swap);
+    }
+
+    #[test]
+    fn test_parse_sender_kind() {
+        assert_eq!(SenderKind::from_str("bloXroute").unwrap(), SenderKind::BloXroute);
+        assert_eq!(SenderKind::from_str("nextblock").unwrap(), SenderKind::Nextblock);
+        assert!(SenderKind::from_str("carrier-pigeon").is_err());
+    }
 }
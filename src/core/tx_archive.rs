@@ -0,0 +1,244 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    sync::LazyLock,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig,
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, transaction::VersionedTransaction};
+
+use crate::{
+    common::{
+        storage::{append_json_line, data_path},
+        utils::log_message,
+    },
+    services::leader_schedule::LEADER_SCHEDULE,
+};
+
+// Configuration constants
+const ARCHIVE_DIR: &str = "tx_archive";
+
+/// Process-wide archive, enabled by `TX_ARCHIVE=true`
+pub static TX_ARCHIVE: LazyLock<TxArchive> = LazyLock::new(TxArchive::from_env);
+
+/// One signed transaction exactly as it was sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedTx {
+    pub signature: String,
+    /// Base64 of the bincode wire bytes
+    pub bytes: String,
+}
+
+impl ArchivedTx {
+    pub fn new(tx: &VersionedTransaction) -> Result<Self> {
+        Ok(Self {
+            signature: tx
+                .signatures
+                .first()
+                .map(|signature| signature.to_string())
+                .unwrap_or_default(),
+            bytes: base64::encode(bincode::serialize(tx)?),
+        })
+    }
+
+    pub fn decode(&self) -> Result<VersionedTransaction> {
+        let bytes = base64::decode(&self.bytes).context("Archived bytes are not base64")?;
+        bincode::deserialize(&bytes).context("Archived bytes are not a transaction")
+    }
+}
+
+/// A single submission: one transaction, or a Jito bundle in landing order, tip included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedSubmission {
+    pub at: DateTime<Utc>,
+    /// `transaction` or `bundle`
+    pub kind: String,
+    /// Last slot seen when it was sent, if the leader tracker is live
+    pub slot: Option<u64>,
+    pub bundle_id: Option<String>,
+    /// Why the submission was rejected outright, if it was
+    pub error: Option<String>,
+    pub transactions: Vec<ArchivedTx>,
+}
+
+impl ArchivedSubmission {
+    pub fn contains(&self, signature: &str) -> bool {
+        self.bundle_id.as_deref() == Some(signature)
+            || self.transactions.iter().any(|tx| tx.signature == signature)
+    }
+}
+
+/// Appends every submission to `data/tx_archive/<date>.jsonl` so it can be replayed later
+#[derive(Debug, Default)]
+pub struct TxArchive {
+    enabled: bool,
+}
+
+impl TxArchive {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env::var("TX_ARCHIVE").as_deref() == Ok("true"),
+        }
+    }
+
+    fn dir() -> PathBuf {
+        data_path(ARCHIVE_DIR)
+    }
+
+    /// Records a lone transaction in the background, off the send path
+    pub fn record_transaction(&self, tx: &VersionedTransaction) {
+        self.record("transaction", std::slice::from_ref(tx), None, None);
+    }
+
+    /// Records a bundle in the background along with the id Jito gave it, or why it refused it
+    pub fn record_bundle(&self, txs: &[VersionedTransaction], sent: Result<&str, String>) {
+        let (bundle_id, error) = match sent {
+            Ok(bundle_id) => (Some(bundle_id.to_string()), None),
+            Err(error) => (None, Some(error)),
+        };
+        self.record("bundle", txs, bundle_id, error);
+    }
+
+    fn record(
+        &self,
+        kind: &str,
+        txs: &[VersionedTransaction],
+        bundle_id: Option<String>,
+        error: Option<String>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let transactions = txs.iter().map(ArchivedTx::new).collect::<Result<Vec<_>>>();
+        let at = Utc::now();
+        let slot = LEADER_SCHEDULE.current_slot();
+        let kind = kind.to_string();
+        tokio::spawn(async move {
+            let path = Self::dir().join(format!("{}.jsonl", at.format("%Y-%m-%d")));
+            let archived = match transactions {
+                Ok(transactions) => {
+                    let submission = ArchivedSubmission {
+                        at,
+                        kind: kind.clone(),
+                        slot,
+                        bundle_id,
+                        error,
+                        transactions,
+                    };
+                    append_json_line(&path, &submission).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = archived {
+                let _ = log_message(&format!("Failed to archive {}: {}", kind, e)).await;
+            }
+        });
+    }
+
+    /// Every archived submission holding `signature`, or sent as bundle `signature`, oldest first
+    pub fn find(signature: &str) -> Result<Vec<ArchivedSubmission>> {
+        let mut files = match fs::read_dir(Self::dir()) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+                .collect::<Vec<_>>(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Failed to read the transaction archive"),
+        };
+        files.sort();
+
+        let mut found = Vec::new();
+        for path in files {
+            let file = fs::File::open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                // Cheap filter before parsing the whole line
+                if !line.contains(signature) {
+                    continue;
+                }
+                let submission: ArchivedSubmission = serde_json::from_str(&line)
+                    .with_context(|| format!("Corrupt line in {}", path.display()))?;
+                if submission.contains(signature) {
+                    found.push(submission);
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Re-simulates each transaction of `submission` against whatever state `client` holds.
+/// Signatures are not checked; `fresh_blockhash` swaps in a current blockhash for nodes that
+/// no longer know the original one
+pub fn replay(
+    client: &RpcClient,
+    submission: &ArchivedSubmission,
+    fresh_blockhash: bool,
+) -> Result<Vec<(String, RpcSimulateTransactionResult)>> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: fresh_blockhash,
+        commitment: Some(CommitmentConfig::processed()),
+        ..Default::default()
+    };
+    submission
+        .transactions
+        .iter()
+        .map(|archived| {
+            let tx = archived.decode()?;
+            let result = client
+                .simulate_transaction_with_config(&tx, config.clone())
+                .with_context(|| format!("Failed to simulate {}", archived.signature))?;
+            Ok((archived.signature.clone(), result.value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash, signature::Keypair, signer::Signer, system_instruction,
+        transaction::Transaction,
+    };
+
+    #[test]
+    fn test_roundtrip() {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &payer.pubkey(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        ));
+        let archived = ArchivedTx::new(&tx).unwrap();
+        assert_eq!(archived.signature, tx.signatures[0].to_string());
+        assert_eq!(archived.decode().unwrap(), tx);
+
+        let submission = ArchivedSubmission {
+            at: Utc::now(),
+            kind: "bundle".to_string(),
+            slot: Some(1),
+            bundle_id: Some("bundle".to_string()),
+            error: None,
+            transactions: vec![archived],
+        };
+        let line = serde_json::to_string(&submission).unwrap();
+        let parsed: ArchivedSubmission = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, submission);
+        assert!(parsed.contains(&tx.signatures[0].to_string()));
+        assert!(parsed.contains("bundle"));
+    }
+}
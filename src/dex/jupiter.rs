@@ -0,0 +1,190 @@
+use std::{env, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::{pubkey, pubkey::Pubkey, signer::Signer, transaction::VersionedTransaction};
+use tokio::time::Instant;
+
+use crate::{
+    core::{
+        errors::{QuoteError, SwapError},
+        tx::{self, TxSigner},
+    },
+    engine::swap::SwapDirection,
+};
+
+pub const JUPITER_API: &str = "https://quote-api.jup.ag/v6";
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapRequest {
+    quote_response: Value,
+    user_public_key: String,
+    wrap_and_unwrap_sol: bool,
+    dynamic_compute_unit_limit: bool,
+    prioritization_fee_lamports: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapResponse {
+    swap_transaction: String,
+}
+
+pub struct Jupiter {
+    pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
+    pub keypair: Arc<TxSigner>,
+    pub api_url: String,
+    /// Jupiter labels of pools left out of quoted routes
+    pub exclude_dexes: Vec<&'static str>,
+    http: reqwest::Client,
+}
+
+impl Jupiter {
+    /// Creates a new Jupiter client, using `JUPITER_API_URL` when set
+    pub fn new(
+        rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+        rpc_client: Arc<solana_client::rpc_client::RpcClient>,
+        keypair: Arc<TxSigner>,
+    ) -> Self {
+        Self {
+            rpc_nonblocking_client,
+            rpc_client: Some(rpc_client),
+            keypair,
+            api_url: env::var("JUPITER_API_URL").unwrap_or_else(|_| JUPITER_API.to_string()),
+            exclude_dexes: Vec::new(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Keeps quotes off the pools Jupiter labels as any of `dexes`
+    pub fn excluding(mut self, dexes: Vec<&'static str>) -> Self {
+        self.exclude_dexes = dexes;
+        self
+    }
+
+    /// Fetches a v6 quote for swapping `amount` of `input_mint` into `output_mint`
+    pub async fn get_quote(
+        &self,
+        input_mint: &str,
+        output_mint: &str,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<Value, QuoteError> {
+        let mut request = self.http.get(format!("{}/quote", self.api_url)).query(&[
+            ("inputMint", input_mint),
+            ("outputMint", output_mint),
+            ("amount", amount.to_string().as_str()),
+            ("slippageBps", slippage_bps.to_string().as_str()),
+        ]);
+        if !self.exclude_dexes.is_empty() {
+            request = request.query(&[("excludeDexes", self.exclude_dexes.join(","))]);
+        }
+        let quote = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| QuoteError::Unavailable(format!("Jupiter quote request failed: {}", e)))?
+            .json::<Value>()
+            .await
+            .map_err(|e| QuoteError::Malformed(format!("Jupiter quote JSON: {}", e)))?;
+
+        // Jupiter answers 200 with an error body when nothing routes the pair
+        if quote.get("error").is_some() {
+            return Err(QuoteError::NoRoute(format!("Jupiter: {}", quote["error"])));
+        }
+        Ok(quote)
+    }
+
+    /// Output amount a quote promises before slippage
+    pub fn out_amount(quote: &Value) -> Result<u64, QuoteError> {
+        quote["outAmount"]
+            .as_str()
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(|| QuoteError::Malformed("Jupiter quote has no outAmount".to_string()))
+    }
+
+    /// Requests the unsigned swap transaction for a quote
+    pub async fn get_swap_transaction(
+        &self,
+        quote: Value,
+        prioritization_fee_lamports: u64,
+    ) -> Result<VersionedTransaction> {
+        let request = SwapRequest {
+            quote_response: quote,
+            user_public_key: self.keypair.pubkey().to_string(),
+            wrap_and_unwrap_sol: true,
+            dynamic_compute_unit_limit: true,
+            prioritization_fee_lamports,
+        };
+
+        let response = self
+            .http
+            .post(format!("{}/swap", self.api_url))
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Jupiter swap request failed")?
+            .json::<SwapResponse>()
+            .await
+            .context("Failed to parse Jupiter swap JSON")?;
+
+        let bytes = base64::decode(response.swap_transaction)
+            .context("Invalid base64 in Jupiter swap transaction")?;
+        bincode::deserialize(&bytes).context("Failed to deserialize Jupiter swap transaction")
+    }
+
+    /// Swaps between SOL and `mint` through Jupiter and submits via core::tx
+    pub async fn swap(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        slippage_bps: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        let (input_mint, output_mint) = match swap_direction {
+            SwapDirection::Buy => (SOL_MINT, mint),
+            SwapDirection::Sell => (mint, SOL_MINT),
+        };
+
+        let quote = self
+            .get_quote(input_mint, output_mint, amount_in, slippage_bps)
+            .await?;
+        self.execute_quote(quote, jito_client, timestamp).await
+    }
+
+    /// Builds, signs and submits the swap for a quote between any two mints
+    pub async fn execute_quote(
+        &self,
+        quote: Value,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        // Jupiter builds its own compute budget, so hand it the same fee core::tx would pay
+        let config = tx::TxConfig::default();
+        let priority_fee = config.unit_price.saturating_mul(config.unit_limit as u64) / 1_000_000;
+        let transaction = self.get_swap_transaction(quote, priority_fee).await?;
+
+        let client = self
+            .rpc_client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Blocking RPC client not available"))?;
+        tx::sign_and_send_versioned(
+            client,
+            &self.keypair,
+            transaction,
+            Some(jito_client),
+            timestamp,
+        )
+        .await
+        .map_err(SwapError::send("Jupiter"))
+    }
+}
@@ -1,2 +1,83 @@
+pub mod jupiter;
 pub mod pump;
+pub mod pump_global;
+pub mod pump_swap;
 pub mod raydium;
+pub mod raydium_clmm;
+pub mod raydium_cpmm;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::{jupiter::*, pump::*, raydium::*, raydium_clmm::*, raydium_cpmm::*};
+    use crate::core::token::METADATA_PROGRAM_ID;
+
+    #[test]
+    fn test_program_ids_match_addresses() {
+        let pairs = [
+            (
+                RENT_PROGRAM_ID,
+                "SysvarRent111111111111111111111111111111111",
+            ),
+            (
+                PUMP_GLOBAL_ID,
+                "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf",
+            ),
+            (
+                PUMP_FEE_RECIPIENT_ID,
+                "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM",
+            ),
+            (
+                PUMP_PROGRAM_ID,
+                "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P",
+            ),
+            (
+                PUMP_AMM_PROGRAM_ID,
+                "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA",
+            ),
+            (
+                PUMP_ACCOUNT_ID,
+                "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1",
+            ),
+            (
+                AMM_PROGRAM_ID,
+                "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+            ),
+            (
+                RAYDIUM_AUTHORITY_V4_ID,
+                "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1",
+            ),
+            (
+                CLMM_PROGRAM_ID,
+                "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK",
+            ),
+            (
+                MEMO_PROGRAM_ID,
+                "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+            ),
+            (
+                CPMM_PROGRAM_ID,
+                "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C",
+            ),
+            (
+                JUPITER_PROGRAM_ID,
+                "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4",
+            ),
+            (
+                METADATA_PROGRAM_ID,
+                "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s",
+            ),
+            (spl_token::ID, "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+            (
+                spl_associated_token_account::ID,
+                "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+            ),
+        ];
+        for (id, address) in pairs {
+            assert_eq!(id, Pubkey::from_str(address).unwrap(), "{}", address);
+        }
+    }
+}
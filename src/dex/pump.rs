@@ -1,50 +1,61 @@
 use std::{str::FromStr, sync::Arc};
 
 use crate::{
+    common::utils::AppState,
     core::{
-        token::{self, get_account_info},
-        tx,
+        errors::{QuoteError, SwapError, TxError},
+        token::{self, get_account_info, PumpAccounts},
+        tx::{self, TxSigner},
     },
-    engine::swap::{SwapDirection, SwapInType},
+    dex::pump_global::{pump_fee_bps, pump_fee_recipient},
+    engine::{
+        execution::{fill_price, QUOTES},
+        executor::COPY_EXECUTOR,
+        impact::PRICE_IMPACT,
+        projection::{project, quote_buy, quote_sell, TARGET_FLOWS},
+        sniper::BuyTemplate,
+        swap::{SwapDirection, SwapInType},
+    },
+    services::{curve_cache::curve_price, metrics::METRICS, price_feed::CurveReserves},
 };
 use anyhow::{anyhow, Context, Result};
-use borsh::from_slice;
 use borsh_derive::{BorshDeserialize, BorshSerialize};
+use futures_util::future::join_all;
 use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
-use raydium_amm::math::U128;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey,
     pubkey::Pubkey,
-    signature::Keypair,
     signer::Signer,
     system_program,
-    commitment_config::CommitmentConfig,
-    account::Account,
-};
-use spl_associated_token_account::{
-    get_associated_token_address, instruction::create_associated_token_account_idempotent,
 };
+use spl_associated_token_account::get_associated_token_address;
+use spl_token_client::token::TokenError;
 use tokio::time::Instant;
 pub const TEN_THOUSAND: u64 = 10000;
-pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
-pub const RENT_PROGRAM: &str = "SysvarRent111111111111111111111111111111111";
-pub const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
-pub const PUMP_GLOBAL: &str = "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf";
-pub const PUMP_FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
-pub const PUMP_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
 // pub const PUMP_FUN_MINT_AUTHORITY: &str = "TSLvdd1pWpHVjahSpsvCXUbgwsL3JAcvokwaKt1eokM";
-pub const PUMP_ACCOUNT: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
 pub const PUMP_BUY_METHOD: u64 = 16927863322537952870;
 pub const PUMP_SELL_METHOD: u64 = 12502976635542562355;
+// Program and account addresses, parsed at compile time rather than on every swap
+pub const RENT_PROGRAM_ID: Pubkey = pubkey!("SysvarRent111111111111111111111111111111111");
+pub const PUMP_GLOBAL_ID: Pubkey = pubkey!("4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
+pub const PUMP_FEE_RECIPIENT_ID: Pubkey = pubkey!("CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM");
+pub const PUMP_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+pub const PUMP_AMM_PROGRAM_ID: Pubkey = pubkey!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA");
+pub const PUMP_ACCOUNT_ID: Pubkey = pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1");
 // Additional constants
 pub const MIN_SOL_BALANCE: u64 = 5000000; // 0.005 SOL minimum
 pub const MAX_SLIPPAGE_BPS: u64 = 5000; // 50% max slippage
 pub const DEFAULT_SLIPPAGE_BPS: u64 = 100; // 1% default slippage
+pub const INITIAL_REAL_TOKEN_RESERVES: u64 = 793_100_000_000_000; // tokens sold before graduation
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const PUMP_TOKEN_UNITS: f64 = 1_000_000.0; // pump.fun tokens have 6 decimals
 
 pub struct Pump {
     pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
-    pub keypair: Arc<Keypair>,
+    pub keypair: Arc<TxSigner>,
     pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
 }
 
@@ -53,7 +64,7 @@ impl Pump {
     pub fn new(
         rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
         rpc_client: Arc<solana_client::rpc_client::RpcClient>,
-        keypair: Arc<Keypair>,
+        keypair: Arc<TxSigner>,
     ) -> Self {
         Self {
             rpc_nonblocking_client,
@@ -65,7 +76,7 @@ impl Pump {
     /// Creates a new Pump instance with only non-blocking client
     pub fn new_nonblocking(
         rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
-        keypair: Arc<Keypair>,
+        keypair: Arc<TxSigner>,
     ) -> Self {
         Self {
             rpc_nonblocking_client,
@@ -74,16 +85,47 @@ impl Pump {
         }
     }
 
-    /// Executes a token swap on PumpFun with the specified parameters
+    /// Executes a token swap on PumpFun with the specified parameters.
+    /// With `SwapInType::Pct`, `amount_in` is a percentage of the current SOL (buy) or token (sell) balance.
     pub async fn swap(
         &self,
         mint: &str,
         amount_in: u64,
+        in_type: SwapInType,
+        swap_direction: SwapDirection,
+        slippage_bps: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        self.swap_with_quote(
+            mint,
+            amount_in,
+            in_type,
+            swap_direction,
+            slippage_bps,
+            jito_client,
+            timestamp,
+        )
+        .await
+        .map(|(signatures, _)| signatures)
+    }
+
+    /// `swap`, also returning the quote its minimum output was set from
+    pub async fn swap_with_quote(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        in_type: SwapInType,
         swap_direction: SwapDirection,
         slippage_bps: u64,
         jito_client: Arc<JitoRpcClient>,
         timestamp: Instant,
-    ) -> Result<Vec<String>> {
+    ) -> Result<(Vec<String>, SwapQuote), SwapError> {
+        // Turn a percentage into raw units against the live balance
+        let (amount_in, sells_all) = self
+            .resolve_amount_in(mint, amount_in, &in_type, &swap_direction)
+            .await?;
+
         // Input validation
         self.validate_swap_params(mint, amount_in, slippage_bps)?;
         
@@ -91,15 +133,26 @@ impl Pump {
         let client = self.get_rpc_client()?;
         
         // Build swap instructions based on direction and parameters
-        let instructions = self.build_swap_instructions(
+        let (mut instructions, quote) = self.build_swap_instructions(
             mint,
             amount_in,
             swap_direction,
             slippage_bps,
         ).await?;
+
+        // Dumping the whole balance leaves an empty ATA; close it to reclaim the rent
+        if sells_all {
+            let mint_pubkey = Pubkey::from_str(mint).context("Invalid mint address")?;
+            let owner = self.keypair.pubkey();
+            let ata = get_associated_token_address(&owner, &mint_pubkey);
+            instructions.push(
+                spl_token::instruction::close_account(&spl_token::id(), &ata, &owner, &owner, &[])
+                    .context("Failed to build the token account close")?,
+            );
+        }
         
         // Execute the transaction
-        tx::new_signed_and_send(
+        let result = tx::new_signed_and_send(
             &client,
             &self.keypair,
             instructions,
@@ -107,7 +160,29 @@ impl Pump {
             timestamp,
         )
         .await
-        .context("Failed to execute swap transaction")
+        .map_err(SwapError::send("pump.fun"));
+        METRICS.observe_swap("pump", &result);
+        result.map(|signatures| (signatures, quote))
+    }
+
+    /// Resolves the raw amount to swap and whether it empties the token account
+    async fn resolve_amount_in(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        in_type: &SwapInType,
+        swap_direction: &SwapDirection,
+    ) -> Result<(u64, bool)> {
+        if let SwapInType::Qty = in_type {
+            return Ok((amount_in, false));
+        }
+        let balance = match swap_direction {
+            SwapDirection::Buy => self.get_sol_balance().await?,
+            SwapDirection::Sell => self.get_token_balance(mint).await?,
+        };
+        let amount = in_type.resolve(amount_in, balance)?;
+        let sells_all = matches!(swap_direction, SwapDirection::Sell) && amount == balance;
+        Ok((amount, sells_all))
     }
 
     /// Validates swap parameters to ensure they are within acceptable ranges
@@ -116,19 +191,22 @@ impl Pump {
         mint: &str,
         amount_in: u64,
         slippage_bps: u64,
-    ) -> Result<()> {
+    ) -> Result<(), SwapError> {
         // Validate mint address format
         Pubkey::from_str(mint)
-            .context("Invalid mint address format")?;
+            .map_err(|_| SwapError::InvalidInput(format!("Invalid mint address: {}", mint)))?;
         
         // Validate amount is not zero
         if amount_in == 0 {
-            return Err(anyhow!("Swap amount cannot be zero"));
+            return Err(SwapError::InvalidInput("Swap amount cannot be zero".to_string()));
         }
         
         // Validate slippage is reasonable (max 50% = 5000 bps)
         if slippage_bps > 5000 {
-            return Err(anyhow!("Slippage tolerance too high: {}bps (max: 5000bps)", slippage_bps));
+            return Err(SwapError::InvalidInput(format!(
+                "Slippage tolerance too high: {}bps (max: 5000bps)",
+                slippage_bps
+            )));
         }
         
         Ok(())
@@ -141,170 +219,286 @@ impl Pump {
             .ok_or_else(|| anyhow!("Blocking RPC client not available"))
     }
 
-    /// Builds the necessary instructions for the swap transaction
-    async fn build_swap_instructions(
+    /// Builds the instructions for the swap, with the quote their limits come from
+    pub async fn build_swap_instructions(
         &self,
         mint: &str,
         amount_in: u64,
         swap_direction: SwapDirection,
         slippage_bps: u64,
-    ) -> Result<Vec<Instruction>> {
+    ) -> Result<(Vec<Instruction>, SwapQuote)> {
         let mint_pubkey = Pubkey::from_str(mint)?;
-        
-        // Get bonding curve information
-        let pump_program = Pubkey::from_str(PUMP_PROGRAM)?;
-        let (bonding_curve, associated_bonding_curve, bonding_curve_account) = 
-            get_bonding_curve_account(
-                self.rpc_client.as_ref().unwrap().clone(),
-                &mint_pubkey,
-                &pump_program,
-            ).await?;
-
-        // Calculate amounts based on swap direction and slippage
-        let (min_amount_out, max_amount_in) = self.calculate_swap_amounts(
+        let (accounts, reserves) = self.curve_state(mint)?;
+
+        // Fee-free, like the curve amounts the fill is later measured from
+        let expected = match swap_direction {
+            SwapDirection::Buy => fill_price(amount_in, quote_buy(reserves, amount_in, 0), 6),
+            SwapDirection::Sell => fill_price(quote_sell(reserves, amount_in, 0), amount_in, 6),
+        };
+        if let Some(price) = expected {
+            QUOTES.record(mint, &swap_direction, price);
+        }
+        if matches!(swap_direction, SwapDirection::Buy) {
+            PRICE_IMPACT
+                .check(reserves.virtual_sol_reserves, amount_in)
+                .with_context(|| format!("Refusing to buy {}", mint))?;
+        }
+
+        // Slippage bounds what comes out, against the curve's quote for what goes in
+        let quote = quote_curve_swap(
+            reserves,
             amount_in,
-            slippage_bps,
             &swap_direction,
-            &bonding_curve_account,
+            slippage_bps,
+            pump_fee_bps(),
         )?;
+        let instructions = self.swap_instructions(&mint_pubkey, &accounts, &swap_direction, &quote);
+        Ok((instructions, quote))
+    }
 
-        // Build instructions based on swap direction
-        match swap_direction {
-            SwapDirection::Buy => {
-                self.build_buy_instructions(
-                    &mint_pubkey,
-                    amount_in,
-                    min_amount_out,
-                    &bonding_curve,
-                    &associated_bonding_curve,
-                ).await
-            }
-            SwapDirection::Sell => {
-                self.build_sell_instructions(
-                    &mint_pubkey,
-                    amount_in,
-                    min_amount_out,
-                    &bonding_curve,
-                    &associated_bonding_curve,
-                ).await
-            }
+    /// The curve's accounts, read in one call, and its reserves as they will be once the
+    /// target's trade we follow has landed
+    pub fn curve_state(&self, mint: &str) -> Result<(PumpAccounts, CurveReserves)> {
+        let accounts = token::get_pump_accounts(self.get_rpc_client()?, &Pubkey::from_str(mint)?)?;
+        let bonding_curve_account = accounts.curve()?;
+        // The program would refuse the swap; saying so here lets the router move on at once
+        if bonding_curve_account.complete {
+            return Err(QuoteError::NoRoute(format!("{} has left the bonding curve", mint)).into());
         }
+        let reserves = project(
+            CurveReserves {
+                virtual_sol_reserves: bonding_curve_account.virtual_sol_reserves,
+                virtual_token_reserves: bonding_curve_account.virtual_token_reserves,
+            },
+            accounts.slot,
+            TARGET_FLOWS.latest(mint).as_ref(),
+        );
+        Ok((accounts, reserves))
     }
 
-    /// Calculates the appropriate amounts for the swap based on slippage tolerance
-    fn calculate_swap_amounts(
+    /// Instructions that fill `quote` on the curve in `accounts`
+    pub fn swap_instructions(
         &self,
-        amount_in: u64,
-        slippage_bps: u64,
+        mint: &Pubkey,
+        accounts: &PumpAccounts,
         swap_direction: &SwapDirection,
-        bonding_curve_account: &BondingCurveAccount,
-    ) -> Result<(u64, u64)> {
+        quote: &SwapQuote,
+    ) -> Vec<Instruction> {
         match swap_direction {
-            SwapDirection::Buy => {
-                // For buys: calculate minimum tokens to receive
-                let min_tokens_out = min_amount_with_slippage(amount_in, slippage_bps)?;
-                let max_sol_in = max_amount_with_slippage(amount_in, slippage_bps)?;
-                Ok((min_tokens_out, max_sol_in))
-            }
-            SwapDirection::Sell => {
-                // For sells: calculate minimum SOL to receive
-                let min_sol_out = min_amount_with_slippage(amount_in, slippage_bps)?;
-                Ok((min_sol_out, amount_in))
-            }
+            SwapDirection::Buy => self.build_buy_instructions(
+                mint,
+                quote.expected_out,
+                quote.max_amount_in,
+                &accounts.bonding_curve,
+            ),
+            SwapDirection::Sell => self.build_sell_instructions(
+                mint,
+                quote.amount_in,
+                quote.min_amount_out,
+                &accounts.bonding_curve,
+                &accounts.associated_bonding_curve,
+            ),
         }
     }
 
-    /// Builds instructions for buying tokens
-    async fn build_buy_instructions(
+    /// Creates our token account and buys exactly `token_amount` for at most `max_sol_cost`
+    fn build_buy_instructions(
         &self,
         mint: &Pubkey,
-        sol_amount: u64,
-        min_tokens_out: u64,
+        token_amount: u64,
+        max_sol_cost: u64,
         bonding_curve: &Pubkey,
-        associated_bonding_curve: &Pubkey,
-    ) -> Result<Vec<Instruction>> {
-        // Implementation for buy instructions
-        // This would include creating associated token accounts if needed,
-        // and building the actual pump.fun buy instruction
-        todo!("Implement buy instruction building")
+    ) -> Vec<Instruction> {
+        BuyTemplate::new(self.keypair.pubkey()).instructions(
+            mint,
+            bonding_curve,
+            token_amount,
+            max_sol_cost,
+        )
     }
 
-    /// Builds instructions for selling tokens
-    async fn build_sell_instructions(
+    /// Sells `token_amount` for at least `min_sol_output`
+    fn build_sell_instructions(
         &self,
         mint: &Pubkey,
         token_amount: u64,
-        min_sol_out: u64,
+        min_sol_output: u64,
         bonding_curve: &Pubkey,
         associated_bonding_curve: &Pubkey,
-    ) -> Result<Vec<Instruction>> {
-        // Implementation for sell instructions
-        // This would include building the actual pump.fun sell instruction
-        todo!("Implement sell instruction building")
+    ) -> Vec<Instruction> {
+        vec![sell_instruction(
+            &self.keypair.pubkey(),
+            mint,
+            bonding_curve,
+            associated_bonding_curve,
+            token_amount,
+            min_sol_output,
+        )]
+    }
+
+    /// Bonding curve of `mint` as it stands
+    async fn bonding_curve(&self, mint: &str) -> Result<BondingCurveAccount> {
+        let bonding_curve = get_pda(&Pubkey::from_str(mint)?, &PUMP_PROGRAM_ID)?;
+        let account = self
+            .rpc_nonblocking_client
+            .get_account(&bonding_curve)
+            .await
+            .with_context(|| format!("No bonding curve for {}", mint))?;
+        // Newer curves carry fields past the ones decoded here
+        Ok(<BondingCurveAccount as borsh::BorshDeserialize>::deserialize(
+            &mut account.data.as_slice(),
+        )?)
     }
 
-    /// Gets current token price from bonding curve
-    pub async fn get_token_price(&self, mint: &str) -> Result<f64>
+    /// Gets current token price from bonding curve, in SOL per token
+    pub async fn get_token_price(&self, mint: &str) -> Result<f64> {
+        let curve = self.bonding_curve(mint).await?;
+        if curve.complete {
+            return Err(QuoteError::NoRoute(format!("{} has left the bonding curve", mint)).into());
+        }
+        Ok(curve_price(&curve))
+    }
 
     /// Checks if a token has graduated to Raydium
-    pub async fn is_token_graduated(&self, mint: &str) -> Result<bool>
+    pub async fn is_token_graduated(&self, mint: &str) -> Result<bool> {
+        Ok(self.bonding_curve(mint).await?.complete)
+    }
 
     /// Gets comprehensive token information
-    pub async fn get_token_info(&self, mint: &str) -> Result<TokenInfo>
+    pub async fn get_token_info(&self, mint: &str) -> Result<TokenInfo> {
+        let curve = self.bonding_curve(mint).await?;
+        let price = curve_price(&curve);
+        Ok(TokenInfo {
+            mint: mint.to_string(),
+            price,
+            user_balance: self.get_token_balance(mint).await?,
+            virtual_sol_reserves: curve.virtual_sol_reserves,
+            virtual_token_reserves: curve.virtual_token_reserves,
+            total_supply: curve.token_total_supply,
+            is_graduated: curve.complete,
+            market_cap: price * curve.token_total_supply as f64 / PUMP_TOKEN_UNITS,
+        })
+    }
 
     /// Estimates transaction fees for a swap
-    pub async fn estimate_swap_fees(&self, mint: &str, swap_direction: SwapDirection) -> Result<SwapFees>
+    pub async fn estimate_swap_fees(
+        &self,
+        mint: &str,
+        swap_direction: &SwapDirection,
+    ) -> Result<SwapFees> {
+        // A buy into a mint we don't hold yet pays the rent for its token account
+        let token_account_creation_fee = match swap_direction {
+            SwapDirection::Buy if self.get_token_balance(mint).await? == 0 => {
+                self.rpc_nonblocking_client
+                    .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+                    .await?
+            }
+            _ => 0,
+        };
+        Ok(SwapFees {
+            base_transaction_fee: LAMPORTS_PER_SIGNATURE,
+            platform_fee_bps: pump_fee_bps(),
+            token_account_creation_fee,
+            total_estimated_fee: LAMPORTS_PER_SIGNATURE + token_account_creation_fee,
+        })
+    }
 
     /// Gets the user's SOL balance
-    pub async fn get_sol_balance(&self) -> Result<u64>
-
-    /// Gets the user's token balance for a specific mint
-    pub async fn get_token_balance(&self, mint: &str) -> Result<u64>
-
-    /// Checks if a token has graduated to Raydium
-    pub async fn is_token_graduated(&self, mint: &str) -> Result<bool>
-
-    /// Gets comprehensive token information
-    pub async fn get_token_info(&self, mint: &str) -> Result<TokenInfo>
+    pub async fn get_sol_balance(&self) -> Result<u64> {
+        Ok(self
+            .rpc_nonblocking_client
+            .get_balance(&self.keypair.pubkey())
+            .await?)
+    }
 
-    /// Estimates transaction fees for a swap
-    pub async fn estimate_swap_fees(&self, mint: &str, swap_direction: SwapDirection) -> Result<SwapFees>
+    /// Gets the user's token balance for a specific mint; zero without a token account
+    pub async fn get_token_balance(&self, mint: &str) -> Result<u64> {
+        let mint_pubkey = Pubkey::from_str(mint)?;
+        let ata = get_associated_token_address(&self.keypair.pubkey(), &mint_pubkey);
+        match get_account_info(self.rpc_nonblocking_client.clone(), &mint_pubkey, &ata).await {
+            Ok(account) => Ok(account.base.amount),
+            Err(TokenError::AccountNotFound) => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
 
     /// Checks if wallet has sufficient balance for the swap
-    async fn check_wallet_balance(&self, swap_direction: &SwapDirection, amount: u64) -> Result<()>
+    async fn check_wallet_balance(
+        &self,
+        mint: &str,
+        swap_direction: &SwapDirection,
+        amount: u64,
+    ) -> Result<()> {
+        // Buys keep enough SOL back to pay for this and the next transaction
+        let (balance, needed) = match swap_direction {
+            SwapDirection::Buy => (self.get_sol_balance().await?, amount + MIN_SOL_BALANCE),
+            SwapDirection::Sell => (self.get_token_balance(mint).await?, amount),
+        };
+        if balance < needed {
+            return Err(TxError::InsufficientFunds)
+                .with_context(|| format!("{} held, {} needed for {}", balance, needed, mint));
+        }
+        Ok(())
+    }
+}
+
+/// What a swap is expected to return, and the least it may return before the program rejects it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Lamports (buy) or raw tokens (sell) going in
+    pub amount_in: u64,
+    /// Raw tokens (buy) or lamports (sell) the curve gives for `amount_in`, after its fee
+    pub expected_out: u64,
+    /// `expected_out` less the slippage tolerance; what a sell asks for at least
+    pub min_amount_out: u64,
+    /// `amount_in` plus the slippage tolerance; the most a buy of exactly `expected_out` may
+    /// spend. Sells always spend `amount_in`
+    pub max_amount_in: u64,
+    pub slippage_bps: u64,
 }
 
-fn min_amount_with_slippage(input_amount: u64, slippage_bps: u64) -> Result<u64, &'static str> {
-    // Validate slippage is not greater than 100% (10,000 basis points)
+/// `expected_out` reduced by `slippage_bps`
+pub fn min_amount_out(expected_out: u64, slippage_bps: u64) -> Result<u64> {
     if slippage_bps >= TEN_THOUSAND {
-        return Err("Slippage cannot be 100% or greater");
+        return Err(anyhow!("Slippage cannot be 100% or greater"));
     }
-    
-    // Calculate the percentage to keep (more efficient single calculation)
-    let keep_percentage = TEN_THOUSAND - slippage_bps;
-    
-    // Perform the calculation with proper error handling
-    input_amount
-        .checked_mul(keep_percentage)
-        .and_then(|result| result.checked_div(TEN_THOUSAND))
-        .ok_or("Arithmetic overflow in slippage calculation")
+    Ok((expected_out as u128 * (TEN_THOUSAND - slippage_bps) as u128 / TEN_THOUSAND as u128) as u64)
+}
+
+/// `amount_in` raised by `slippage_bps`
+pub fn max_amount_in(amount_in: u64, slippage_bps: u64) -> u64 {
+    (amount_in as u128 * (TEN_THOUSAND + slippage_bps) as u128 / TEN_THOUSAND as u128)
+        .min(u64::MAX as u128) as u64
 }
-fn max_amount_with_slippage(input_amount: u64, slippage_bps: u64) -> Result<u64, &'static str> {
-    // Validate slippage to prevent unreasonable values (e.g., > 10000 bps = 100%)
-    if slippage_bps > TEN_THOUSAND {
-        return Err("Slippage exceeds 100%, which may indicate an error");
+
+/// Quotes a curve swap of `amount_in` and sets its limits `slippage_bps` either side of it
+pub fn quote_curve_swap(
+    reserves: CurveReserves,
+    amount_in: u64,
+    swap_direction: &SwapDirection,
+    slippage_bps: u64,
+    fee_bps: u64,
+) -> Result<SwapQuote, QuoteError> {
+    let expected_out = match swap_direction {
+        SwapDirection::Buy => quote_buy(reserves, amount_in, fee_bps),
+        SwapDirection::Sell => quote_sell(reserves, amount_in, fee_bps),
+    };
+    if expected_out == 0 {
+        return Err(QuoteError::NoRoute(format!(
+            "the curve quotes nothing out for {} in",
+            amount_in
+        )));
     }
-    
-    // Calculate the multiplier percentage (100% + slippage)
-    let multiplier_percentage = TEN_THOUSAND
-        .checked_add(slippage_bps)
-        .ok_or("Overflow when adding slippage to base percentage")?;
-    
-    // Perform the calculation with proper error handling
-    input_amount
-        .checked_mul(multiplier_percentage)
-        .and_then(|result| result.checked_div(TEN_THOUSAND))
-        .ok_or("Arithmetic overflow in slippage calculation")
+    Ok(SwapQuote {
+        amount_in,
+        expected_out,
+        min_amount_out: min_amount_out(expected_out, slippage_bps)?,
+        max_amount_in: match swap_direction {
+            SwapDirection::Buy => max_amount_in(amount_in, slippage_bps),
+            SwapDirection::Sell => amount_in,
+        },
+        slippage_bps,
+    })
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -326,7 +520,7 @@ pub struct PumpInfo {
     pub total_supply: u64,
 }
 
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct BondingCurveAccount {
     pub discriminator: u64,
     pub virtual_token_reserves: u64,
@@ -337,11 +531,29 @@ pub struct BondingCurveAccount {
     pub complete: bool,
 }
 
+impl BondingCurveAccount {
+    /// How far the curve is toward graduation, in percent of its sellable tokens
+    pub fn progress_pct(&self) -> f64 {
+        if self.complete {
+            return 100.0;
+        }
+        let sold = INITIAL_REAL_TOKEN_RESERVES.saturating_sub(self.real_token_reserves);
+        sold as f64 / INITIAL_REAL_TOKEN_RESERVES as f64 * 100.0
+    }
+}
+
 pub async fn get_bonding_curve_account(
     rpc_client: Arc<solana_client::rpc_client::RpcClient>,
     mint: &Pubkey,
     program_id: &Pubkey,
 ) -> Result<(Pubkey, Pubkey, BondingCurveAccount)> {
+    if *program_id != PUMP_PROGRAM_ID {
+        return Err(anyhow!("Not the pump.fun program: {}", program_id));
+    }
+    let accounts = token::get_pump_accounts(&rpc_client, mint)?;
+    let bonding_curve_account = accounts.curve()?;
+    let (bonding_curve, associated_bonding_curve) =
+        (accounts.bonding_curve, accounts.associated_bonding_curve);
     Ok((
         bonding_curve,
         associated_bonding_curve,
@@ -355,14 +567,46 @@ pub fn get_pda(mint: &Pubkey, program_id: &Pubkey) -> Result<Pubkey> {
     Ok(bonding_curve)
 }
 
+/// Canonical PumpSwap pool a graduated pump.fun token migrates into
+pub fn get_pump_amm_pool_pda(mint: &Pubkey) -> Result<Pubkey> {
+    let pump_program = PUMP_PROGRAM_ID;
+    let pump_amm_program = PUMP_AMM_PROGRAM_ID;
+    let (pool_authority, _bump) =
+        Pubkey::find_program_address(&[b"pool-authority".as_ref(), mint.as_ref()], &pump_program);
+    let index = 0u16.to_le_bytes();
+    let seeds = [
+        b"pool".as_ref(),
+        index.as_ref(),
+        pool_authority.as_ref(),
+        mint.as_ref(),
+        spl_token::native_mint::ID.as_ref(),
+    ];
+    let (pool, _bump) = Pubkey::find_program_address(&seeds, &pump_amm_program);
+    Ok(pool)
+}
+
 pub async fn get_pump_info(
     rpc_client: Arc<solana_client::rpc_client::RpcClient>,
     mint: &str,
 ) -> Result<PumpInfo> {
+    let accounts = token::get_pump_accounts(&rpc_client, &Pubkey::from_str(mint)?)?;
+    let curve = accounts.curve()?;
+    let pump_info = PumpInfo {
+        mint: mint.to_string(),
+        bonding_curve: accounts.bonding_curve.to_string(),
+        associated_bonding_curve: accounts.associated_bonding_curve.to_string(),
+        raydium_pool: None,
+        raydium_info: None,
+        complete: curve.complete,
+        virtual_sol_reserves: curve.virtual_sol_reserves,
+        virtual_token_reserves: curve.virtual_token_reserves,
+        total_supply: curve.token_total_supply,
+    };
     Ok(pump_info)
 }
 
-// These would need to be added to support the new functions:
+/// A curve token as `Pump::get_token_info` sees it
+#[derive(Debug, Clone)]
 pub struct TokenInfo {
     pub mint: String,
     pub price: f64,
@@ -374,20 +618,50 @@ pub struct TokenInfo {
     pub market_cap: f64,
 }
 
+/// Lamports a swap costs beyond what it trades
+#[derive(Debug, Clone)]
 pub struct SwapFees {
     pub base_transaction_fee: u64,
+    /// Charged by the curve on the amount traded, so not part of `total_estimated_fee`
     pub platform_fee_bps: u64,
     pub token_account_creation_fee: u64,
     pub total_estimated_fee: u64,
 }
 
-use tracing::{info, warn, error};
-use std::time::Instant;
-
-use anyhow::{anyhow, Result};
-use std::sync::Arc;
-use tokio::time::Instant;
-use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+/// pump.fun `sell`: `token_amount` raw tokens for at least `min_sol_output` lamports
+pub fn sell_instruction(
+    user: &Pubkey,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    associated_bonding_curve: &Pubkey,
+    token_amount: u64,
+    min_sol_output: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(24);
+    data.extend(PUMP_SELL_METHOD.to_le_bytes());
+    data.extend(token_amount.to_le_bytes());
+    data.extend(min_sol_output.to_le_bytes());
+
+    // Built in place; `Instruction::new_with_bytes` would copy `data` into a second buffer
+    Instruction {
+        program_id: PUMP_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(PUMP_GLOBAL_ID, false),
+            AccountMeta::new(pump_fee_recipient(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*bonding_curve, false),
+            AccountMeta::new(*associated_bonding_curve, false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(PUMP_ACCOUNT_ID, false),
+            AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+        ],
+        data,
+    }
+}
 
 /// Executes a pump swap with improved error handling and validation
 pub async fn pump_swap(
@@ -419,6 +693,7 @@ pub async fn pump_swap(
     pump.swap(
         mint,
         amount_in,
+        SwapInType::Qty,
         swap_direction,
         slippage,
         jito_client,
@@ -455,21 +730,22 @@ pub async fn pump_swap_enhanced(
     );
     
     // Pre-swap validation
-    pump.check_wallet_balance(&swap_direction, amount_in).await?;
+    pump.check_wallet_balance(mint, &swap_direction, amount_in).await?;
     
     // Get price before swap for comparison
     let price_before = pump.get_token_price(mint).await.ok();
     
     // Estimate fees
-    let estimated_fees = pump.estimate_swap_fees(mint, swap_direction).await?;
+    let estimated_fees = pump.estimate_swap_fees(mint, &swap_direction).await?;
     
     println!("Executing swap - Elapsed: {:.2?}, Estimated fees: {} lamports", 
              timestamp.elapsed(), estimated_fees.total_estimated_fee);
     
     // Execute the swap
-    let transaction_signatures = pump.swap(
+    let (transaction_signatures, quote) = pump.swap_with_quote(
         mint,
         amount_in,
+        SwapInType::Qty,
         swap_direction,
         slippage,
         jito_client,
@@ -481,6 +757,7 @@ pub async fn pump_swap_enhanced(
     
     Ok(PumpSwapResult {
         transaction_signatures,
+        quote,
         estimated_fees,
         price_before,
         price_after,
@@ -504,11 +781,17 @@ pub async fn pump_swap_simple(
         state.wallet,
     );
     
-    // Use appropriate method based on direction
-    match swap_direction {
-        SwapDirection::Buy => pump.buy_token(mint, amount_in, jito_client).await,
-        SwapDirection::Sell => pump.sell_token(mint, amount_in, jito_client).await,
-    }
+    pump.swap(
+        mint,
+        amount_in,
+        SwapInType::Qty,
+        swap_direction,
+        DEFAULT_SLIPPAGE_BPS,
+        jito_client,
+        Instant::now(),
+    )
+    .await
+    .map_err(anyhow::Error::from)
 }
 
 /// Parses string swap direction into enum
@@ -544,42 +827,51 @@ fn validate_pump_swap_inputs(amount_in: u64, slippage: u64, mint: &str) -> Resul
 #[derive(Debug)]
 pub struct PumpSwapResult {
     pub transaction_signatures: Vec<String>,
+    /// Expected and minimum output the swap was sent with
+    pub quote: SwapQuote,
     pub estimated_fees: SwapFees,
     pub price_before: Option<f64>,
     pub price_after: Option<f64>,
     pub execution_time: std::time::Duration,
 }
 
-/// Batch swap function for multiple tokens
+/// Batch swap function for multiple tokens. Different mints swap concurrently through the
+/// copy executor, swaps of the same mint in the order given; results keep the input order
 pub async fn pump_swap_batch(
     state: AppState,
     swaps: Vec<SwapRequest>,
     jito_client: Arc<JitoRpcClient>,
 ) -> Result<Vec<Result<Vec<String>>>> {
-    let pump = Pump::new(
+    let pump = Arc::new(Pump::new(
         state.rpc_nonblocking_client,
         state.rpc_client,
         state.wallet,
-    );
-    
-    let mut results = Vec::new();
-    
-    for swap_request in swaps {
-        let swap_direction = parse_swap_direction(&swap_request.direction)?;
-        
-        let result = pump.swap(
-            &swap_request.mint,
-            swap_request.amount,
-            swap_direction,
-            swap_request.slippage.unwrap_or(DEFAULT_SLIPPAGE_BPS),
-            jito_client.clone(),
-            Instant::now(),
-        ).await;
-        
-        results.push(result);
-    }
-    
-    Ok(results)
+    ));
+
+    let directions = swaps
+        .iter()
+        .map(|swap_request| parse_swap_direction(&swap_request.direction))
+        .collect::<Result<Vec<_>>>()?;
+    let runs = swaps.into_iter().zip(directions).map(|(swap_request, swap_direction)| {
+        let (pump, jito_client) = (pump.clone(), jito_client.clone());
+        let mint = swap_request.mint.clone();
+        let swap = async move {
+            pump.swap(
+                &swap_request.mint,
+                swap_request.amount,
+                SwapInType::Qty,
+                swap_direction,
+                swap_request.slippage.unwrap_or(DEFAULT_SLIPPAGE_BPS),
+                jito_client,
+                Instant::now(),
+            )
+            .await
+            .map_err(anyhow::Error::from)
+        };
+        async move { COPY_EXECUTOR.run(&mint, swap).await.and_then(|result| result) }
+    });
+
+    Ok(join_all(runs).await)
 }
 
 /// Request structure for batch swaps
@@ -590,3 +882,39 @@ pub struct SwapRequest {
     pub direction: String,
     pub slippage: Option<u64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buy_quote_caps_sol_and_sell_floors_it() {
+        let reserves = CurveReserves {
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+        };
+        let buy = quote_curve_swap(reserves, 1_000_000_000, &SwapDirection::Buy, 500, 100).unwrap();
+        assert_eq!(buy.max_amount_in, 1_050_000_000);
+        assert!(buy.min_amount_out < buy.expected_out);
+
+        let sell =
+            quote_curve_swap(reserves, buy.expected_out, &SwapDirection::Sell, 500, 100).unwrap();
+        assert_eq!(sell.max_amount_in, buy.expected_out);
+        assert_eq!(sell.min_amount_out, min_amount_out(sell.expected_out, 500).unwrap());
+    }
+
+    #[test]
+    fn test_sell_instruction_layout() {
+        let (user, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let bonding_curve = get_pda(&mint, &PUMP_PROGRAM_ID).unwrap();
+        let associated_bonding_curve = get_associated_token_address(&bonding_curve, &mint);
+        let ix = sell_instruction(&user, &mint, &bonding_curve, &associated_bonding_curve, 7, 9);
+
+        assert_eq!(ix.program_id, PUMP_PROGRAM_ID);
+        assert_eq!(ix.data[..8], PUMP_SELL_METHOD.to_le_bytes());
+        assert_eq!(ix.data[8..16], 7u64.to_le_bytes());
+        assert_eq!(ix.data[16..], 9u64.to_le_bytes());
+        assert_eq!(ix.accounts[5].pubkey, get_associated_token_address(&user, &mint));
+        assert!(ix.accounts[6].is_signer && ix.accounts[6].pubkey == user);
+    }
+}
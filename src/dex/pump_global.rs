@@ -0,0 +1,159 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::pump::{PUMP_FEE_RECIPIENT_ID, PUMP_GLOBAL_ID},
+    services::idle::idle_sleep,
+};
+
+// Configuration constants
+const DEFAULT_PUMP_FEE_BPS: u64 = 100;
+const DEFAULT_REFRESH_SECS: u64 = 300;
+
+/// Protocol parameters in use, from the last read of pump.fun's Global account
+pub static PUMP_PARAMS: LazyLock<RwLock<PumpParams>> =
+    LazyLock::new(|| RwLock::new(PumpParams::default()));
+
+/// Leading fields of pump.fun's Global account; keys are raw bytes since this solana-sdk's
+/// `Pubkey` doesn't implement borsh 1
+#[derive(Debug, Clone, borsh_derive::BorshDeserialize)]
+pub struct PumpGlobalAccount {
+    pub discriminator: u64,
+    pub initialized: bool,
+    pub authority: [u8; 32],
+    pub fee_recipient: [u8; 32],
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+}
+
+impl PumpGlobalAccount {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        // Later program versions append fields past the ones decoded here
+        Self::deserialize(&mut &data[..]).context("Failed to decode pump.fun Global account")
+    }
+}
+
+/// The parts of the Global account that swaps depend on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PumpParams {
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u64,
+}
+
+impl Default for PumpParams {
+    /// The values that held when the bot was written, used until the first fetch lands
+    fn default() -> Self {
+        Self {
+            fee_recipient: PUMP_FEE_RECIPIENT_ID,
+            fee_bps: DEFAULT_PUMP_FEE_BPS,
+        }
+    }
+}
+
+impl From<&PumpGlobalAccount> for PumpParams {
+    fn from(global: &PumpGlobalAccount) -> Self {
+        Self {
+            fee_recipient: Pubkey::new_from_array(global.fee_recipient),
+            fee_bps: global.fee_basis_points,
+        }
+    }
+}
+
+/// Curve fee pump.fun currently charges on each side of a trade
+pub fn pump_fee_bps() -> u64 {
+    PUMP_PARAMS.read().unwrap().fee_bps
+}
+
+/// Account pump.fun currently wants its fees paid to
+pub fn pump_fee_recipient() -> Pubkey {
+    PUMP_PARAMS.read().unwrap().fee_recipient
+}
+
+/// Reads the Global account and installs its parameters; returns the previous ones if they
+/// changed
+pub async fn refresh_pump_params(state: &AppState) -> Result<Option<PumpParams>> {
+    let account = state
+        .rpc_nonblocking_client
+        .get_account(&PUMP_GLOBAL_ID)
+        .await
+        .context("Failed to fetch pump.fun Global account")?;
+    let params = PumpParams::from(&PumpGlobalAccount::decode(&account.data)?);
+    let mut current = PUMP_PARAMS.write().unwrap();
+    let previous = *current;
+    *current = params;
+    Ok((previous != params).then_some(previous))
+}
+
+/// Refreshes the pump.fun parameters every `PUMP_GLOBAL_REFRESH_SECS`, forever
+pub async fn run_pump_params_refresh(state: AppState) {
+    let interval = Duration::from_secs(
+        env::var("PUMP_GLOBAL_REFRESH_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_REFRESH_SECS)
+            .max(1),
+    );
+    loop {
+        idle_sleep(interval).await;
+        match refresh_pump_params(&state).await {
+            Ok(Some(previous)) => {
+                let current = *PUMP_PARAMS.read().unwrap();
+                let _ = log_message(&format!(
+                    "pump.fun parameters changed: fee {} -> {} bps, recipient {} -> {}",
+                    previous.fee_bps,
+                    current.fee_bps,
+                    previous.fee_recipient,
+                    current.fee_recipient
+                ))
+                .await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let _ = log_message(&format!("Keeping pump.fun parameters: {}", e)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_global() {
+        let fee_recipient = Pubkey::new_unique();
+        let mut data = Vec::new();
+        data.extend(0u64.to_le_bytes());
+        data.push(1);
+        data.extend(Pubkey::new_unique().to_bytes());
+        data.extend(fee_recipient.to_bytes());
+        for value in [
+            1_073_000_000_000_000u64,
+            30_000_000_000,
+            793_100_000_000_000,
+            1e15 as u64,
+        ] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(95u64.to_le_bytes());
+        // Trailing fields from newer layouts are ignored
+        data.extend([0u8; 32]);
+
+        let params = PumpParams::from(&PumpGlobalAccount::decode(&data).unwrap());
+        assert_eq!(params.fee_recipient, fee_recipient);
+        assert_eq!(params.fee_bps, 95);
+        assert!(PumpGlobalAccount::decode(&data[..40]).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    core::{
+        ata::KNOWN_ATAS,
+        errors::SwapError,
+        tx::{self, TxSigner},
+    },
+    dex::pump::{max_amount_in, PUMP_AMM_PROGRAM_ID, TEN_THOUSAND},
+    engine::{impact::PRICE_IMPACT, swap::SwapDirection},
+    services::metrics::METRICS,
+};
+use anyhow::{anyhow, Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction, system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
+use tokio::time::Instant;
+
+/// The AMM's fee and admin settings, one per program
+pub static GLOBAL_CONFIG: LazyLock<Pubkey> =
+    LazyLock::new(|| Pubkey::find_program_address(&[b"global_config"], &PUMP_AMM_PROGRAM_ID).0);
+/// Anchor event CPI authority every swap passes
+pub static EVENT_AUTHORITY: LazyLock<Pubkey> =
+    LazyLock::new(|| Pubkey::find_program_address(&[b"__event_authority"], &PUMP_AMM_PROGRAM_ID).0);
+/// Anchor discriminators of `buy` and `sell`
+pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+// Discriminator and two amounts
+const SWAP_DATA_LEN: usize = 8 + 8 + 8;
+
+// Pool field offsets, after the 8-byte discriminator
+const POOL_BASE_MINT: usize = 43;
+const POOL_QUOTE_MINT: usize = 75;
+const POOL_BASE_ACCOUNT: usize = 139;
+const POOL_QUOTE_ACCOUNT: usize = 171;
+const POOL_LEN: usize = 211;
+// GlobalConfig and SPL token account field offsets
+const CONFIG_LP_FEE_BPS: usize = 40;
+const CONFIG_PROTOCOL_FEE_BPS: usize = 48;
+const CONFIG_FEE_RECIPIENTS: usize = 57;
+const FEE_RECIPIENTS: usize = 8;
+const TOKEN_ACCOUNT_AMOUNT: usize = 64;
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Account data too short at offset {}", offset))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read::<32>(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read(data, offset)?))
+}
+
+/// The parts of a PumpSwap `Pool` needed to quote and swap; base is the token, quote WSOL
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpSwapPool {
+    pub id: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_account: Pubkey,
+    pub quote_account: Pubkey,
+}
+
+impl PumpSwapPool {
+    pub fn decode(id: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < POOL_LEN {
+            return Err(anyhow!("{} is not a PumpSwap pool", id));
+        }
+        Ok(Self {
+            id,
+            base_mint: read_pubkey(data, POOL_BASE_MINT)?,
+            quote_mint: read_pubkey(data, POOL_QUOTE_MINT)?,
+            base_account: read_pubkey(data, POOL_BASE_ACCOUNT)?,
+            quote_account: read_pubkey(data, POOL_QUOTE_ACCOUNT)?,
+        })
+    }
+}
+
+/// Fees the global config charges on the quote side, and who collects the protocol's share
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpSwapFees {
+    pub lp_fee_bps: u64,
+    pub protocol_fee_bps: u64,
+    pub protocol_fee_recipient: Pubkey,
+}
+
+impl PumpSwapFees {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let recipient = (0..FEE_RECIPIENTS)
+            .map(|i| read_pubkey(data, CONFIG_FEE_RECIPIENTS + i * 32))
+            .find(|recipient| !matches!(recipient, Ok(key) if *key == Pubkey::default()))
+            .ok_or_else(|| anyhow!("PumpSwap global config has no fee recipient"))??;
+        Ok(Self {
+            lp_fee_bps: read_u64(data, CONFIG_LP_FEE_BPS)?,
+            protocol_fee_bps: read_u64(data, CONFIG_PROTOCOL_FEE_BPS)?,
+            protocol_fee_recipient: recipient,
+        })
+    }
+
+    fn total_bps(&self) -> u64 {
+        self.lp_fee_bps + self.protocol_fee_bps
+    }
+}
+
+/// Tokens a buy spending at most `quote_in` lamports, fees included, receives
+pub fn quote_buy(quote_in: u64, base_reserve: u64, quote_reserve: u64, fee_bps: u64) -> u64 {
+    let quote_in = quote_in as u128 * TEN_THOUSAND as u128 / (TEN_THOUSAND + fee_bps) as u128;
+    (base_reserve as u128 * quote_in / (quote_reserve as u128 + quote_in)) as u64
+}
+
+/// Lamports selling `base_in` tokens returns, net of fees, rounded like the program
+pub fn quote_sell(base_in: u64, base_reserve: u64, quote_reserve: u64, fee_bps: u64) -> u64 {
+    let out = quote_reserve as u128 * base_in as u128 / (base_reserve as u128 + base_in as u128);
+    let fee = (out * fee_bps as u128).div_ceil(TEN_THOUSAND as u128);
+    out.saturating_sub(fee) as u64
+}
+
+fn swap_accounts(
+    pool: &PumpSwapPool,
+    fees: &PumpSwapFees,
+    user: &Pubkey,
+    user_base: &Pubkey,
+    user_quote: &Pubkey,
+) -> Vec<AccountMeta> {
+    let recipient_quote =
+        get_associated_token_address(&fees.protocol_fee_recipient, &pool.quote_mint);
+    vec![
+        AccountMeta::new_readonly(pool.id, false),
+        AccountMeta::new(*user, true),
+        AccountMeta::new_readonly(*GLOBAL_CONFIG, false),
+        AccountMeta::new_readonly(pool.base_mint, false),
+        AccountMeta::new_readonly(pool.quote_mint, false),
+        AccountMeta::new(*user_base, false),
+        AccountMeta::new(*user_quote, false),
+        AccountMeta::new(pool.base_account, false),
+        AccountMeta::new(pool.quote_account, false),
+        AccountMeta::new_readonly(fees.protocol_fee_recipient, false),
+        AccountMeta::new(recipient_quote, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(*EVENT_AUTHORITY, false),
+        AccountMeta::new_readonly(PUMP_AMM_PROGRAM_ID, false),
+    ]
+}
+
+fn swap_instruction(
+    discriminator: [u8; 8],
+    amounts: [u64; 2],
+    accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut data = Vec::with_capacity(SWAP_DATA_LEN);
+    data.extend_from_slice(&discriminator);
+    data.extend_from_slice(&amounts[0].to_le_bytes());
+    data.extend_from_slice(&amounts[1].to_le_bytes());
+    Instruction {
+        program_id: PUMP_AMM_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+/// `buy`: receive exactly `base_amount_out` tokens, spend at most `max_quote_amount_in` lamports
+pub fn buy_instruction(
+    pool: &PumpSwapPool,
+    fees: &PumpSwapFees,
+    user: &Pubkey,
+    base_amount_out: u64,
+    max_quote_amount_in: u64,
+) -> Instruction {
+    let user_base = get_associated_token_address(user, &pool.base_mint);
+    let user_quote = get_associated_token_address(user, &pool.quote_mint);
+    swap_instruction(
+        BUY_DISCRIMINATOR,
+        [base_amount_out, max_quote_amount_in],
+        swap_accounts(pool, fees, user, &user_base, &user_quote),
+    )
+}
+
+/// `sell`: spend exactly `base_amount_in` tokens, receive at least `min_quote_amount_out`
+pub fn sell_instruction(
+    pool: &PumpSwapPool,
+    fees: &PumpSwapFees,
+    user: &Pubkey,
+    base_amount_in: u64,
+    min_quote_amount_out: u64,
+) -> Instruction {
+    let user_base = get_associated_token_address(user, &pool.base_mint);
+    let user_quote = get_associated_token_address(user, &pool.quote_mint);
+    swap_instruction(
+        SELL_DISCRIMINATOR,
+        [base_amount_in, min_quote_amount_out],
+        swap_accounts(pool, fees, user, &user_base, &user_quote),
+    )
+}
+
+pub struct PumpSwap {
+    pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
+    pub keypair: Arc<TxSigner>,
+}
+
+impl PumpSwap {
+    pub fn new(
+        rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+        rpc_client: Arc<solana_client::rpc_client::RpcClient>,
+        keypair: Arc<TxSigner>,
+    ) -> Self {
+        Self {
+            rpc_nonblocking_client,
+            rpc_client: Some(rpc_client),
+            keypair,
+        }
+    }
+
+    /// Pool, fees and reserves (base, quote) in two round trips
+    async fn load(&self, pool: &Pubkey) -> Result<(PumpSwapPool, PumpSwapFees, (u64, u64))> {
+        let account = self.rpc_nonblocking_client.get_account(pool).await?;
+        let pool = PumpSwapPool::decode(*pool, &account.data)?;
+        let keys = [*GLOBAL_CONFIG, pool.base_account, pool.quote_account];
+        let accounts = self
+            .rpc_nonblocking_client
+            .get_multiple_accounts(&keys)
+            .await?;
+        let data = |i: usize| {
+            accounts[i]
+                .as_ref()
+                .map(|account| account.data.as_slice())
+                .ok_or_else(|| anyhow!("Missing account {}", keys[i]))
+        };
+        let fees = PumpSwapFees::decode(data(0)?)?;
+        let reserves = (
+            read_u64(data(1)?, TOKEN_ACCOUNT_AMOUNT)?,
+            read_u64(data(2)?, TOKEN_ACCOUNT_AMOUNT)?,
+        );
+        Ok((pool, fees, reserves))
+    }
+
+    /// Swaps between SOL and `mint` in its PumpSwap pool: lamports in on a buy, raw tokens on a
+    /// sell
+    pub async fn swap(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        let instructions = self
+            .swap_instructions(mint, amount_in, swap_direction, pool, slippage_bps)
+            .await?;
+        let client = self
+            .rpc_client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Blocking RPC client not available"))?;
+        let result = tx::new_signed_and_send(
+            client,
+            &self.keypair,
+            instructions,
+            Some(jito_client),
+            None,
+            timestamp,
+        )
+        .await
+        .map_err(SwapError::send("PumpSwap"));
+        METRICS.observe_swap("pumpswap", &result);
+        result
+    }
+
+    async fn swap_instructions(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+    ) -> Result<Vec<Instruction>> {
+        let (pool, fees, (base_reserve, quote_reserve)) = self.load(pool).await?;
+        let mint = Pubkey::from_str(mint)?;
+        let wsol = spl_token::native_mint::ID;
+        if pool.base_mint != mint || pool.quote_mint != wsol {
+            return Err(SwapError::WrongPool {
+                pool: pool.id,
+                mint,
+            }
+            .into());
+        }
+
+        let owner = self.keypair.pubkey();
+        let user_quote = get_associated_token_address(&owner, &wsol);
+        // Creates are left out for accounts known to exist
+        let mut instructions = Vec::new();
+        instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &wsol, &spl_token::id()));
+        match swap_direction {
+            SwapDirection::Buy => {
+                PRICE_IMPACT
+                    .check(quote_reserve, amount_in)
+                    .with_context(|| format!("Refusing to buy {}", mint))?;
+                let base_out = quote_buy(amount_in, base_reserve, quote_reserve, fees.total_bps());
+                if base_out == 0 {
+                    return Err(anyhow!("{} lamports buy nothing of {}", amount_in, mint));
+                }
+                let max_quote_in = max_amount_in(amount_in, slippage_bps);
+                instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &mint, &spl_token::id()));
+                instructions.push(system_instruction::transfer(
+                    &owner,
+                    &user_quote,
+                    max_quote_in,
+                ));
+                instructions.push(spl_token::instruction::sync_native(
+                    &spl_token::id(),
+                    &user_quote,
+                )?);
+                instructions.push(buy_instruction(
+                    &pool,
+                    &fees,
+                    &owner,
+                    base_out,
+                    max_quote_in,
+                ));
+            }
+            SwapDirection::Sell => {
+                let quote = quote_sell(amount_in, base_reserve, quote_reserve, fees.total_bps());
+                let min_quote_out = (quote as u128
+                    * (TEN_THOUSAND as u128).saturating_sub(slippage_bps as u128)
+                    / TEN_THOUSAND as u128) as u64;
+                instructions.push(sell_instruction(
+                    &pool,
+                    &fees,
+                    &owner,
+                    amount_in,
+                    min_quote_out,
+                ));
+            }
+        }
+        // Unwrap whatever WSOL is left or received
+        instructions.push(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &user_quote,
+            &owner,
+            &owner,
+            &[],
+        )?);
+
+        Ok(instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_pool_and_fees() {
+        let mut data = vec![0u8; POOL_LEN];
+        let mint = Pubkey::new_unique();
+        data[POOL_BASE_MINT..POOL_BASE_MINT + 32].copy_from_slice(mint.as_ref());
+        let pool = PumpSwapPool::decode(Pubkey::new_unique(), &data).unwrap();
+        assert_eq!(pool.base_mint, mint);
+        assert!(PumpSwapPool::decode(Pubkey::new_unique(), &data[..100]).is_err());
+
+        let mut config = vec![0u8; CONFIG_FEE_RECIPIENTS + FEE_RECIPIENTS * 32];
+        config[CONFIG_LP_FEE_BPS..CONFIG_LP_FEE_BPS + 8].copy_from_slice(&20u64.to_le_bytes());
+        config[CONFIG_PROTOCOL_FEE_BPS..CONFIG_PROTOCOL_FEE_BPS + 8]
+            .copy_from_slice(&5u64.to_le_bytes());
+        // The first slot may be empty
+        let recipient = Pubkey::new_unique();
+        let second = CONFIG_FEE_RECIPIENTS + 32;
+        config[second..second + 32].copy_from_slice(recipient.as_ref());
+        let fees = PumpSwapFees::decode(&config).unwrap();
+        assert_eq!(fees.total_bps(), 25);
+        assert_eq!(fees.protocol_fee_recipient, recipient);
+    }
+
+    #[test]
+    fn test_quotes_and_instruction_layout() {
+        let (base_reserve, quote_reserve) = (200_000_000_000_000, 85_000_000_000);
+        let bought = quote_buy(1_000_000_000, base_reserve, quote_reserve, 25);
+        assert!(bought < quote_buy(1_000_000_000, base_reserve, quote_reserve, 0));
+        // Selling straight back loses the fee twice
+        let returned = quote_sell(
+            bought,
+            base_reserve - bought,
+            quote_reserve + 997_506_234,
+            25,
+        );
+        assert!(returned < 1_000_000_000 && returned > 990_000_000);
+
+        let pool = PumpSwapPool {
+            id: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: spl_token::native_mint::ID,
+            base_account: Pubkey::new_unique(),
+            quote_account: Pubkey::new_unique(),
+        };
+        let fees = PumpSwapFees {
+            lp_fee_bps: 20,
+            protocol_fee_bps: 5,
+            protocol_fee_recipient: Pubkey::new_unique(),
+        };
+        let user = Pubkey::new_unique();
+        let ix = sell_instruction(&pool, &fees, &user, 7, 3);
+        assert_eq!(ix.program_id, PUMP_AMM_PROGRAM_ID);
+        assert_eq!(ix.accounts.len(), 17);
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(ix.data[..8], SELL_DISCRIMINATOR);
+        assert_eq!(ix.data[8..16], 7u64.to_le_bytes());
+        assert_eq!(ix.data[16..], 3u64.to_le_bytes());
+    }
+}
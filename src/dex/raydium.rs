@@ -1,9 +1,12 @@
 use crate::{
     core::{
+        ata::KNOWN_ATAS,
+        errors::SwapError,
         token::{get_account_info, get_mint_info},
-        tx,
+        tx::{self, TxSigner},
     },
     engine::swap::{SwapDirection, SwapInType},
+    services::metrics::METRICS,
 };
 use amm_cli::AmmSwapInfoResult;
 use anyhow::{anyhow, Context, Result};
@@ -14,8 +17,8 @@ use serde::Deserialize;
 use serde::Serialize;
 use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_sdk::{
-    instruction::Instruction, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
-    signer::Signer, system_instruction,
+    instruction::Instruction, program_pack::Pack, pubkey, pubkey::Pubkey, signer::Signer,
+    system_instruction,
 };
 use spl_associated_token_account::{
     get_associated_token_address, get_associated_token_address_with_program_id,
@@ -26,17 +29,8 @@ use spl_token_client::token::TokenError;
 use std::{str::FromStr, sync::Arc, time::Duration};
 use tokio::time::Instant;
 
-pub const AMM_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
-pub const RAYDIUM_AUTHORITY_V4: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
-
-#[derive(Serialize)]
-struct SwapRequest {
-    quoteResponse: serde_json::Value, // You may deserialize it into a specific struct if known
-    userPublicKey: String,
-    wrapAndUnwrapSol: bool,
-    dynamicComputeUnitLimit: bool,
-    prioritizationFeeLamports: u64,
-}
+pub const AMM_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const RAYDIUM_AUTHORITY_V4_ID: Pubkey = pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
 
 #[derive(Debug, Deserialize)]
 pub struct PoolInfo {
@@ -80,7 +74,7 @@ pub struct Mint {
 pub struct Raydium {
     pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
     pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
-    pub keypair: Arc<Keypair>,
+    pub keypair: Arc<TxSigner>,
     pub pool_id: Option<String>,
 }
 
@@ -88,7 +82,7 @@ impl Raydium {
     pub fn new(
         rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
         rpc_client: Arc<solana_client::rpc_client::RpcClient>,
-        keypair: Arc<Keypair>,
+        keypair: Arc<TxSigner>,
     ) -> Self {
         Self {
             rpc_nonblocking_client,
@@ -107,10 +101,10 @@ impl Raydium {
         slippage: u64,
         start_time: Instant,
         jito_client: Arc<JitoRpcClient>,
-    ) -> Result<Vec<String>> {
+    ) -> Result<Vec<String>, SwapError> {
         // make instructions on raydium
 
-        tx::new_signed_and_send(
+        let result = tx::new_signed_and_send(
             &self.rpc_client.clone().unwrap(),
             &self.keypair,
             instructions,
@@ -118,6 +112,9 @@ impl Raydium {
             start_time.clone(),
         )
         .await
+        .map_err(SwapError::send("Raydium"));
+        METRICS.observe_swap("raydium", &result);
+        result
     }
 
     // Function to get current token price from a pool
@@ -236,7 +233,10 @@ impl Raydium {
             &mint_pubkey,
         );
         
-        // Check if account exists
+        // Check if account exists, without a round trip for accounts already seen
+        if KNOWN_ATAS.contains(&user_token_account) {
+            return Ok(user_token_account);
+        }
         if get_account_info(&self.rpc_nonblocking_client, &user_token_account).await.is_err() {
             // Create the account
             let create_instruction = create_associated_token_account_idempotent(
@@ -257,6 +257,7 @@ impl Raydium {
                 Instant::now(),
             ).await?;
         }
+        KNOWN_ATAS.insert(user_token_account);
         
         Ok(user_token_account)
     }
@@ -368,7 +369,7 @@ pub async fn get_pool_state_by_mint(
     ];
 
     let pool_len = core::mem::size_of::<AmmInfo>() as u64;
-    let amm_program = Pubkey::from_str(AMM_PROGRAM)?;
+    let amm_program = AMM_PROGRAM_ID;
     // Find matching AMM pool from mint pairs by filter
     let mut found_pools = None;
     for (coin_mint, pc_mint) in pairs {
@@ -0,0 +1,552 @@
+use std::{str::FromStr, sync::Arc};
+
+use crate::{
+    core::{
+        ata::KNOWN_ATAS,
+        errors::SwapError,
+        token::transfer_fee,
+        tx::{self, TxSigner},
+    },
+    engine::swap::SwapDirection,
+    services::metrics::METRICS,
+};
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use tokio::time::Instant;
+
+/// Parsed at compile time; `Pubkey::from_str` on every swap shows up in profiles
+pub const CLMM_PROGRAM_ID: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+/// Anchor discriminator of `swap_v2`
+pub const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+pub const POOL_STATE_LEN: u64 = 1544;
+pub const TICK_ARRAY_SIZE: i32 = 60;
+const TICK_STATE_LEN: usize = 168;
+const FEE_RATE_DENOMINATOR: f64 = 1_000_000.0;
+// Tick arrays passed after the current one; swaps that need more fail on-chain
+const EXTRA_TICK_ARRAYS: i32 = 2;
+const SWAP_V2_ACCOUNTS: usize = 14;
+// Discriminator, amount, threshold, sqrt price limit and the is_base_input flag
+const SWAP_V2_DATA_LEN: usize = 8 + 8 + 8 + 16 + 1;
+
+// PoolState field offsets, after the 8-byte discriminator
+const POOL_AMM_CONFIG: usize = 9;
+const POOL_MINT_0: usize = 73;
+const POOL_MINT_1: usize = 105;
+const POOL_VAULT_0: usize = 137;
+const POOL_VAULT_1: usize = 169;
+const POOL_OBSERVATION: usize = 201;
+const POOL_DECIMALS_0: usize = 233;
+const POOL_DECIMALS_1: usize = 234;
+const POOL_TICK_SPACING: usize = 235;
+const POOL_LIQUIDITY: usize = 237;
+const POOL_SQRT_PRICE: usize = 253;
+const POOL_TICK_CURRENT: usize = 269;
+// AmmConfig and TickArrayState field offsets
+const CONFIG_TRADE_FEE_RATE: usize = 47;
+const TICK_ARRAY_START: usize = 40;
+const TICK_ARRAY_TICKS: usize = 44;
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Account data too short at offset {}", offset))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read::<32>(data, offset)?))
+}
+
+/// The parts of a CLMM `PoolState` needed to quote and swap
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClmmPool {
+    pub id: Pubkey,
+    pub amm_config: Pubkey,
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    pub vault_0: Pubkey,
+    pub vault_1: Pubkey,
+    pub observation: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl ClmmPool {
+    pub fn decode(id: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < POOL_STATE_LEN as usize {
+            return Err(anyhow!("{} is not a CLMM pool", id));
+        }
+        Ok(Self {
+            id,
+            amm_config: read_pubkey(data, POOL_AMM_CONFIG)?,
+            mint_0: read_pubkey(data, POOL_MINT_0)?,
+            mint_1: read_pubkey(data, POOL_MINT_1)?,
+            vault_0: read_pubkey(data, POOL_VAULT_0)?,
+            vault_1: read_pubkey(data, POOL_VAULT_1)?,
+            observation: read_pubkey(data, POOL_OBSERVATION)?,
+            decimals_0: data[POOL_DECIMALS_0],
+            decimals_1: data[POOL_DECIMALS_1],
+            tick_spacing: u16::from_le_bytes(read(data, POOL_TICK_SPACING)?),
+            liquidity: u128::from_le_bytes(read(data, POOL_LIQUIDITY)?),
+            sqrt_price_x64: u128::from_le_bytes(read(data, POOL_SQRT_PRICE)?),
+            tick_current: i32::from_le_bytes(read(data, POOL_TICK_CURRENT)?),
+        })
+    }
+
+    /// Price of one whole token 0 in token 1
+    pub fn price_0_in_1(&self) -> f64 {
+        let sqrt_price = self.sqrt_price_x64 as f64 / 2f64.powi(64);
+        sqrt_price * sqrt_price * 10f64.powi(self.decimals_0 as i32 - self.decimals_1 as i32)
+    }
+
+    /// Start indices of the tick arrays a swap walks through, current one first
+    pub fn tick_array_starts(&self, zero_for_one: bool) -> Vec<i32> {
+        let span = self.tick_spacing as i32 * TICK_ARRAY_SIZE;
+        let current = tick_array_start(self.tick_current, self.tick_spacing);
+        // Price falls when token 0 goes in, so the walk heads to lower ticks
+        let step = if zero_for_one { -span } else { span };
+        (0..=EXTRA_TICK_ARRAYS)
+            .map(|i| current + i * step)
+            .collect()
+    }
+}
+
+/// First tick of the array holding `tick`
+pub fn tick_array_start(tick: i32, tick_spacing: u16) -> i32 {
+    let span = tick_spacing as i32 * TICK_ARRAY_SIZE;
+    tick.div_euclid(span) * span
+}
+
+pub fn tick_array_address(pool: &Pubkey, start: i32) -> Pubkey {
+    let (address, _) = Pubkey::find_program_address(
+        &[b"tick_array", pool.as_ref(), &start.to_be_bytes()],
+        &CLMM_PROGRAM_ID,
+    );
+    address
+}
+
+pub fn bitmap_extension_address(pool: &Pubkey) -> Pubkey {
+    let (address, _) = Pubkey::find_program_address(
+        &[b"pool_tick_array_bitmap_extension", pool.as_ref()],
+        &CLMM_PROGRAM_ID,
+    );
+    address
+}
+
+/// Initialized ticks of a `TickArrayState` as (tick, liquidity_net)
+pub fn decode_tick_array(data: &[u8]) -> Result<Vec<(i32, i128)>> {
+    let start = i32::from_le_bytes(read(data, TICK_ARRAY_START)?);
+    let mut ticks = vec![];
+    for i in 0..TICK_ARRAY_SIZE as usize {
+        let offset = TICK_ARRAY_TICKS + i * TICK_STATE_LEN;
+        let tick = i32::from_le_bytes(read(data, offset)?);
+        let liquidity_net = i128::from_le_bytes(read(data, offset + 4)?);
+        let liquidity_gross = u128::from_le_bytes(read(data, offset + 20)?);
+        if liquidity_gross > 0 {
+            ticks.push((tick, liquidity_net));
+        }
+    }
+    if ticks.iter().any(|(tick, _)| *tick < start) {
+        return Err(anyhow!("Corrupt tick array starting at {}", start));
+    }
+    Ok(ticks)
+}
+
+fn sqrt_price_at_tick(tick: i32) -> f64 {
+    1.0001f64.powf(tick as f64 / 2.0)
+}
+
+/// Estimated output of an exact-in swap, walking `ticks` (initialized ticks from the loaded
+/// tick arrays) the way the program does. Floating point, so only good for a min-out threshold.
+pub fn quote_exact_in(
+    pool: &ClmmPool,
+    ticks: &[(i32, i128)],
+    amount_in: u64,
+    trade_fee_rate: u32,
+    zero_for_one: bool,
+) -> Result<u64> {
+    let mut ticks = ticks.to_vec();
+    ticks.sort_by_key(|(tick, _)| *tick);
+    let mut sqrt_price = pool.sqrt_price_x64 as f64 / 2f64.powi(64);
+    let mut liquidity = pool.liquidity as f64;
+    let mut tick_current = pool.tick_current;
+    let mut remaining = amount_in as f64 * (1.0 - trade_fee_rate as f64 / FEE_RATE_DENOMINATOR);
+    let mut out = 0.0;
+
+    while remaining > 0.0 {
+        let next = if zero_for_one {
+            ticks.iter().rev().find(|(tick, _)| *tick <= tick_current)
+        } else {
+            ticks.iter().find(|(tick, _)| *tick > tick_current)
+        };
+        let Some(&(next_tick, liquidity_net)) = next else {
+            return Err(anyhow!("Swap runs past the loaded tick arrays"));
+        };
+        let target = sqrt_price_at_tick(next_tick);
+
+        if zero_for_one {
+            let max_in = liquidity * (1.0 / target - 1.0 / sqrt_price);
+            if remaining < max_in {
+                let new_price = liquidity * sqrt_price / (liquidity + remaining * sqrt_price);
+                out += liquidity * (sqrt_price - new_price);
+                break;
+            }
+            out += liquidity * (sqrt_price - target);
+            remaining -= max_in;
+            liquidity -= liquidity_net as f64;
+            tick_current = next_tick - 1;
+        } else {
+            let max_in = liquidity * (target - sqrt_price);
+            if remaining < max_in {
+                let new_price = sqrt_price + remaining / liquidity;
+                out += liquidity * (1.0 / sqrt_price - 1.0 / new_price);
+                break;
+            }
+            out += liquidity * (1.0 / sqrt_price - 1.0 / target);
+            remaining -= max_in;
+            liquidity += liquidity_net as f64;
+            tick_current = next_tick;
+        }
+        sqrt_price = target;
+    }
+    Ok(out.max(0.0) as u64)
+}
+
+/// Exact-in `swap_v2`, with the program picking its own price limit
+#[allow(clippy::too_many_arguments)]
+pub fn swap_v2_instruction(
+    pool: &ClmmPool,
+    payer: &Pubkey,
+    zero_for_one: bool,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    tick_arrays: &[Pubkey],
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let (input_vault, output_vault, input_mint, output_mint) = if zero_for_one {
+        (pool.vault_0, pool.vault_1, pool.mint_0, pool.mint_1)
+    } else {
+        (pool.vault_1, pool.vault_0, pool.mint_1, pool.mint_0)
+    };
+    // Sized up front so pushing the tick arrays never reallocates
+    let mut accounts = Vec::with_capacity(SWAP_V2_ACCOUNTS + tick_arrays.len());
+    accounts.extend([
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(pool.amm_config, false),
+        AccountMeta::new(pool.id, false),
+        AccountMeta::new(*user_input, false),
+        AccountMeta::new(*user_output, false),
+        AccountMeta::new(input_vault, false),
+        AccountMeta::new(output_vault, false),
+        AccountMeta::new(pool.observation, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+        AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+        AccountMeta::new_readonly(input_mint, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new(bitmap_extension_address(&pool.id), false),
+    ]);
+    accounts.extend(
+        tick_arrays
+            .iter()
+            .map(|address| AccountMeta::new(*address, false)),
+    );
+
+    let mut data = Vec::with_capacity(SWAP_V2_DATA_LEN);
+    data.extend_from_slice(&SWAP_V2_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    // Zero lets the program use its min/max sqrt price
+    data.extend_from_slice(&0u128.to_le_bytes());
+    data.push(1); // is_base_input
+
+    Instruction {
+        program_id: CLMM_PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+/// Deepest CLMM pool pairing `mint` with WSOL
+pub async fn find_pool_by_mint(
+    client: &solana_client::nonblocking::rpc_client::RpcClient,
+    mint: &Pubkey,
+) -> Result<ClmmPool> {
+    let wsol = spl_token::native_mint::ID;
+    let mut pools = vec![];
+    for (mint_0, mint_1) in [(wsol, *mint), (*mint, wsol)] {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(POOL_STATE_LEN),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(POOL_MINT_0, &mint_0.to_bytes())),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(POOL_MINT_1, &mint_1.to_bytes())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for (id, account) in client
+            .get_program_accounts_with_config(&CLMM_PROGRAM_ID, config)
+            .await?
+        {
+            pools.push(ClmmPool::decode(id, &account.data)?);
+        }
+    }
+    pools
+        .into_iter()
+        .max_by_key(|pool| pool.liquidity)
+        .ok_or_else(|| anyhow!("NotFoundPool: no CLMM pool for {}", mint))
+}
+
+pub struct RaydiumClmm {
+    pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
+    pub keypair: Arc<TxSigner>,
+}
+
+impl RaydiumClmm {
+    pub fn new(
+        rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+        rpc_client: Arc<solana_client::rpc_client::RpcClient>,
+        keypair: Arc<TxSigner>,
+    ) -> Self {
+        Self {
+            rpc_nonblocking_client,
+            rpc_client: Some(rpc_client),
+            keypair,
+        }
+    }
+
+    pub async fn get_pool(&self, pool: &Pubkey) -> Result<ClmmPool> {
+        let account = self.rpc_nonblocking_client.get_account(pool).await?;
+        ClmmPool::decode(*pool, &account.data)
+    }
+
+    /// SOL per whole token in a WSOL-paired pool
+    pub async fn get_token_price(&self, mint: &str, pool: &Pubkey) -> Result<f64> {
+        let pool = self.get_pool(pool).await?;
+        let mint = Pubkey::from_str(mint)?;
+        let price = pool.price_0_in_1();
+        match (pool.mint_0 == mint, pool.mint_1 == mint) {
+            (true, _) => Ok(price),
+            (_, true) if price > 0.0 => Ok(1.0 / price),
+            _ => Err(anyhow!("{} does not trade in pool {}", mint, pool.id)),
+        }
+    }
+
+    /// Swaps between SOL and `mint` in a CLMM pool: lamports in on a buy, raw tokens on a sell
+    pub async fn swap(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        let instructions = self
+            .swap_instructions(mint, amount_in, swap_direction, pool, slippage_bps)
+            .await?;
+        let client = self
+            .rpc_client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Blocking RPC client not available"))?;
+        let result = tx::new_signed_and_send(
+            client,
+            &self.keypair,
+            instructions,
+            Some(jito_client),
+            None,
+            timestamp,
+        )
+        .await
+        .map_err(SwapError::send("Raydium CLMM"));
+        METRICS.observe_swap("raydium_clmm", &result);
+        result
+    }
+
+    async fn swap_instructions(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+    ) -> Result<Vec<Instruction>> {
+        let pool = self.get_pool(pool).await?;
+        let mint = Pubkey::from_str(mint)?;
+        let wsol = spl_token::native_mint::ID;
+        let (input_mint, output_mint) = match swap_direction {
+            SwapDirection::Buy => (wsol, mint),
+            SwapDirection::Sell => (mint, wsol),
+        };
+        if ![input_mint, output_mint].contains(&pool.mint_0)
+            || ![input_mint, output_mint].contains(&pool.mint_1)
+        {
+            return Err(SwapError::WrongPool { pool: pool.id, mint }.into());
+        }
+        let zero_for_one = input_mint == pool.mint_0;
+
+        // Config, both mints (for their token programs) and the tick arrays in one round trip
+        let starts = pool.tick_array_starts(zero_for_one);
+        let tick_array_addresses = starts
+            .iter()
+            .map(|start| tick_array_address(&pool.id, *start))
+            .collect::<Vec<_>>();
+        let mut keys = vec![pool.amm_config, input_mint, output_mint];
+        keys.extend(&tick_array_addresses);
+        let accounts = self
+            .rpc_nonblocking_client
+            .get_multiple_accounts(&keys)
+            .await?;
+        let account = |i: usize| {
+            accounts[i]
+                .clone()
+                .ok_or_else(|| anyhow!("Missing account {}", keys[i]))
+        };
+        let trade_fee_rate = u32::from_le_bytes(read(&account(0)?.data, CONFIG_TRADE_FEE_RATE)?);
+        let (input_mint_account, output_mint_account) = (account(1)?, account(2)?);
+        let input_program = input_mint_account.owner;
+        let output_program = output_mint_account.owner;
+
+        // Uninitialized arrays don't exist on-chain and are left out of the swap
+        let mut tick_arrays = vec![];
+        let mut ticks = vec![];
+        for (address, account) in tick_array_addresses.iter().zip(&accounts[3..]) {
+            if let Some(account) = account {
+                ticks.extend(decode_tick_array(&account.data)?);
+                tick_arrays.push(*address);
+            }
+        }
+        if tick_arrays.is_empty() {
+            return Err(anyhow!(
+                "No initialized tick arrays near the price in {}",
+                pool.id
+            ));
+        }
+
+        // Token-2022 transfer fees come off what reaches the pool and what reaches us
+        let client = &self.rpc_nonblocking_client;
+        let pool_in = amount_in - transfer_fee(client, &input_mint_account, amount_in).await?;
+        let quote = quote_exact_in(&pool, &ticks, pool_in, trade_fee_rate, zero_for_one)?;
+        let quote = quote - transfer_fee(client, &output_mint_account, quote).await?;
+        let min_amount_out =
+            (quote as u128 * 10_000u128.saturating_sub(slippage_bps as u128) / 10_000) as u64;
+
+        let owner = self.keypair.pubkey();
+        let user_input =
+            get_associated_token_address_with_program_id(&owner, &input_mint, &input_program);
+        let user_output =
+            get_associated_token_address_with_program_id(&owner, &output_mint, &output_program);
+        let wsol_account = if input_mint == wsol {
+            user_input
+        } else {
+            user_output
+        };
+
+        // Creates are left out for accounts known to exist
+        let mut instructions = Vec::new();
+        instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &output_mint, &output_program));
+        if matches!(swap_direction, SwapDirection::Buy) {
+            instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &wsol, &spl_token::id()));
+            instructions.push(system_instruction::transfer(&owner, &user_input, amount_in));
+            instructions.push(spl_token::instruction::sync_native(
+                &spl_token::id(),
+                &user_input,
+            )?);
+        }
+        instructions.push(swap_v2_instruction(
+            &pool,
+            &owner,
+            zero_for_one,
+            &user_input,
+            &user_output,
+            &tick_arrays,
+            amount_in,
+            min_amount_out,
+        ));
+        // Unwrap whatever WSOL is left or received
+        instructions.push(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &wsol_account,
+            &owner,
+            &owner,
+            &[],
+        )?);
+
+        Ok(instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(liquidity: u128) -> ClmmPool {
+        ClmmPool {
+            id: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            mint_0: Pubkey::new_unique(),
+            mint_1: Pubkey::new_unique(),
+            vault_0: Pubkey::new_unique(),
+            vault_1: Pubkey::new_unique(),
+            observation: Pubkey::new_unique(),
+            decimals_0: 9,
+            decimals_1: 6,
+            tick_spacing: 10,
+            liquidity,
+            // Price 1
+            sqrt_price_x64: 1u128 << 64,
+            tick_current: 0,
+        }
+    }
+
+    #[test]
+    fn test_tick_array_starts() {
+        assert_eq!(tick_array_start(0, 10), 0);
+        assert_eq!(tick_array_start(599, 10), 0);
+        assert_eq!(tick_array_start(-1, 10), -600);
+        let mut pool = pool(0);
+        pool.tick_current = 650;
+        assert_eq!(pool.tick_array_starts(true), vec![600, 0, -600]);
+        assert_eq!(pool.tick_array_starts(false), vec![600, 1200, 1800]);
+    }
+
+    #[test]
+    fn test_quote_within_and_across_ranges() {
+        let liquidity = 1_000_000_000_000u128;
+        let ticks = [(-1_000, liquidity as i128), (1_000, -(liquidity as i128))];
+        let pool = pool(liquidity);
+
+        // Inside one range this is constant product on virtual reserves of L each side
+        let out = quote_exact_in(&pool, &ticks, 1_000_000, 0, true).unwrap();
+        let expected = 1_000_000.0 * liquidity as f64 / (liquidity as f64 + 1_000_000.0);
+        assert!((out as f64 - expected).abs() <= 1.0);
+        let with_fee = quote_exact_in(&pool, &ticks, 1_000_000, 2_500, true).unwrap();
+        assert!(with_fee < out);
+
+        // Past the last initialized tick there is no liquidity left to quote against
+        assert!(quote_exact_in(&pool, &ticks, u64::MAX, 0, false).is_err());
+    }
+}
@@ -0,0 +1,523 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    core::{
+        ata::KNOWN_ATAS,
+        errors::SwapError,
+        token::transfer_fee,
+        tx::{self, TxSigner},
+    },
+    engine::{impact::PRICE_IMPACT, swap::SwapDirection},
+    services::metrics::METRICS,
+};
+use anyhow::{anyhow, Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use tokio::time::Instant;
+
+pub const CPMM_PROGRAM_ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+/// The program's single vault and LP mint authority; deriving it costs a PDA search per swap
+pub static AUTHORITY: LazyLock<Pubkey> =
+    LazyLock::new(|| Pubkey::find_program_address(&[AUTH_SEED], &CPMM_PROGRAM_ID).0);
+/// Anchor discriminators of `swap_base_input` and `swap_base_output`
+pub const SWAP_BASE_INPUT_DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+pub const SWAP_BASE_OUTPUT_DISCRIMINATOR: [u8; 8] = [55, 217, 98, 86, 163, 74, 180, 173];
+pub const POOL_STATE_LEN: u64 = 637;
+const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+// Discriminator and two amounts
+const SWAP_DATA_LEN: usize = 8 + 8 + 8;
+
+// PoolState field offsets, after the 8-byte discriminator
+const POOL_AMM_CONFIG: usize = 8;
+const POOL_VAULT_0: usize = 72;
+const POOL_VAULT_1: usize = 104;
+const POOL_MINT_0: usize = 168;
+const POOL_MINT_1: usize = 200;
+const POOL_PROGRAM_0: usize = 232;
+const POOL_PROGRAM_1: usize = 264;
+const POOL_OBSERVATION: usize = 296;
+const POOL_DECIMALS_0: usize = 331;
+const POOL_DECIMALS_1: usize = 332;
+const POOL_LP_SUPPLY: usize = 333;
+const POOL_PROTOCOL_FEES_0: usize = 341;
+const POOL_PROTOCOL_FEES_1: usize = 349;
+const POOL_FUND_FEES_0: usize = 357;
+const POOL_FUND_FEES_1: usize = 365;
+// Older pools have zeroed padding here
+const POOL_CREATOR_FEES_0: usize = 397;
+const POOL_CREATOR_FEES_1: usize = 405;
+// AmmConfig and SPL token account field offsets
+const CONFIG_TRADE_FEE_RATE: usize = 12;
+const TOKEN_ACCOUNT_AMOUNT: usize = 64;
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Account data too short at offset {}", offset))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read::<32>(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read(data, offset)?))
+}
+
+/// The parts of a CP-Swap `PoolState` needed to quote and swap
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpmmPool {
+    pub id: Pubkey,
+    pub amm_config: Pubkey,
+    pub vault_0: Pubkey,
+    pub vault_1: Pubkey,
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    pub program_0: Pubkey,
+    pub program_1: Pubkey,
+    pub observation: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub lp_supply: u64,
+    /// Protocol, fund and creator fees still sitting in each vault
+    pub owed_0: u64,
+    pub owed_1: u64,
+}
+
+impl CpmmPool {
+    pub fn decode(id: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < POOL_STATE_LEN as usize {
+            return Err(anyhow!("{} is not a CPMM pool", id));
+        }
+        let owed = |offsets: [usize; 3]| -> Result<u64> {
+            offsets.iter().try_fold(0u64, |sum, offset| {
+                Ok(sum.saturating_add(read_u64(data, *offset)?))
+            })
+        };
+        Ok(Self {
+            id,
+            amm_config: read_pubkey(data, POOL_AMM_CONFIG)?,
+            vault_0: read_pubkey(data, POOL_VAULT_0)?,
+            vault_1: read_pubkey(data, POOL_VAULT_1)?,
+            mint_0: read_pubkey(data, POOL_MINT_0)?,
+            mint_1: read_pubkey(data, POOL_MINT_1)?,
+            program_0: read_pubkey(data, POOL_PROGRAM_0)?,
+            program_1: read_pubkey(data, POOL_PROGRAM_1)?,
+            observation: read_pubkey(data, POOL_OBSERVATION)?,
+            decimals_0: data[POOL_DECIMALS_0],
+            decimals_1: data[POOL_DECIMALS_1],
+            lp_supply: read_u64(data, POOL_LP_SUPPLY)?,
+            owed_0: owed([POOL_PROTOCOL_FEES_0, POOL_FUND_FEES_0, POOL_CREATOR_FEES_0])?,
+            owed_1: owed([POOL_PROTOCOL_FEES_1, POOL_FUND_FEES_1, POOL_CREATOR_FEES_1])?,
+        })
+    }
+}
+
+/// Trading reserves: vault balances less the fees owed out of them
+pub fn reserves(pool: &CpmmPool, vault_0_amount: u64, vault_1_amount: u64) -> (u64, u64) {
+    (
+        vault_0_amount.saturating_sub(pool.owed_0),
+        vault_1_amount.saturating_sub(pool.owed_1),
+    )
+}
+
+/// Output of an exact-in swap, rounded like the program: fee up, output down
+pub fn quote_base_input(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    trade_fee_rate: u64,
+) -> u64 {
+    let fee = (amount_in as u128 * trade_fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR);
+    let amount_in = (amount_in as u128).saturating_sub(fee);
+    (amount_in * reserve_out as u128 / (reserve_in as u128 + amount_in)) as u64
+}
+
+/// Input needed for an exact-out swap, fee included; `None` if the pool can't pay it out
+pub fn quote_base_output(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    trade_fee_rate: u64,
+) -> Option<u64> {
+    if amount_out >= reserve_out || trade_fee_rate as u128 >= FEE_RATE_DENOMINATOR {
+        return None;
+    }
+    let before_fee = (amount_out as u128 * reserve_in as u128)
+        .div_ceil(reserve_out as u128 - amount_out as u128);
+    let amount_in =
+        (before_fee * FEE_RATE_DENOMINATOR).div_ceil(FEE_RATE_DENOMINATOR - trade_fee_rate as u128);
+    u64::try_from(amount_in).ok()
+}
+
+fn swap_accounts(
+    pool: &CpmmPool,
+    payer: &Pubkey,
+    zero_for_one: bool,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+) -> Vec<AccountMeta> {
+    let (input_vault, output_vault, input_mint, output_mint, input_program, output_program) =
+        if zero_for_one {
+            (
+                pool.vault_0,
+                pool.vault_1,
+                pool.mint_0,
+                pool.mint_1,
+                pool.program_0,
+                pool.program_1,
+            )
+        } else {
+            (
+                pool.vault_1,
+                pool.vault_0,
+                pool.mint_1,
+                pool.mint_0,
+                pool.program_1,
+                pool.program_0,
+            )
+        };
+    vec![
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(*AUTHORITY, false),
+        AccountMeta::new_readonly(pool.amm_config, false),
+        AccountMeta::new(pool.id, false),
+        AccountMeta::new(*user_input, false),
+        AccountMeta::new(*user_output, false),
+        AccountMeta::new(input_vault, false),
+        AccountMeta::new(output_vault, false),
+        AccountMeta::new_readonly(input_program, false),
+        AccountMeta::new_readonly(output_program, false),
+        AccountMeta::new_readonly(input_mint, false),
+        AccountMeta::new_readonly(output_mint, false),
+        AccountMeta::new(pool.observation, false),
+    ]
+}
+
+/// `swap_base_input`: spend exactly `amount_in`, receive at least `min_amount_out`
+pub fn swap_base_input_instruction(
+    pool: &CpmmPool,
+    payer: &Pubkey,
+    zero_for_one: bool,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(SWAP_DATA_LEN);
+    data.extend_from_slice(&SWAP_BASE_INPUT_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    Instruction {
+        program_id: CPMM_PROGRAM_ID,
+        accounts: swap_accounts(pool, payer, zero_for_one, user_input, user_output),
+        data,
+    }
+}
+
+/// `swap_base_output`: receive exactly `amount_out`, spend at most `max_amount_in`
+pub fn swap_base_output_instruction(
+    pool: &CpmmPool,
+    payer: &Pubkey,
+    zero_for_one: bool,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    max_amount_in: u64,
+    amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(SWAP_DATA_LEN);
+    data.extend_from_slice(&SWAP_BASE_OUTPUT_DISCRIMINATOR);
+    data.extend_from_slice(&max_amount_in.to_le_bytes());
+    data.extend_from_slice(&amount_out.to_le_bytes());
+    Instruction {
+        program_id: CPMM_PROGRAM_ID,
+        accounts: swap_accounts(pool, payer, zero_for_one, user_input, user_output),
+        data,
+    }
+}
+
+/// CPMM pool pairing `mint` with WSOL with the most LP tokens outstanding
+pub async fn find_pool_by_mint(
+    client: &solana_client::nonblocking::rpc_client::RpcClient,
+    mint: &Pubkey,
+) -> Result<CpmmPool> {
+    let wsol = spl_token::native_mint::ID;
+    let mut pools = vec![];
+    for (mint_0, mint_1) in [(wsol, *mint), (*mint, wsol)] {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(POOL_STATE_LEN),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(POOL_MINT_0, &mint_0.to_bytes())),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(POOL_MINT_1, &mint_1.to_bytes())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for (id, account) in client
+            .get_program_accounts_with_config(&CPMM_PROGRAM_ID, config)
+            .await?
+        {
+            pools.push(CpmmPool::decode(id, &account.data)?);
+        }
+    }
+    pools
+        .into_iter()
+        .max_by_key(|pool| pool.lp_supply)
+        .ok_or_else(|| anyhow!("NotFoundPool: no CPMM pool for {}", mint))
+}
+
+pub struct RaydiumCpmm {
+    pub rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    pub rpc_client: Option<Arc<solana_client::rpc_client::RpcClient>>,
+    pub keypair: Arc<TxSigner>,
+}
+
+impl RaydiumCpmm {
+    pub fn new(
+        rpc_nonblocking_client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+        rpc_client: Arc<solana_client::rpc_client::RpcClient>,
+        keypair: Arc<TxSigner>,
+    ) -> Self {
+        Self {
+            rpc_nonblocking_client,
+            rpc_client: Some(rpc_client),
+            keypair,
+        }
+    }
+
+    pub async fn get_pool(&self, pool: &Pubkey) -> Result<CpmmPool> {
+        let account = self.rpc_nonblocking_client.get_account(pool).await?;
+        CpmmPool::decode(*pool, &account.data)
+    }
+
+    /// Pool state, trade fee rate and trading reserves (token 0, token 1) in one round trip
+    async fn load(&self, pool: &Pubkey) -> Result<(CpmmPool, u64, (u64, u64))> {
+        let pool = self.get_pool(pool).await?;
+        let keys = [pool.amm_config, pool.vault_0, pool.vault_1];
+        let accounts = self
+            .rpc_nonblocking_client
+            .get_multiple_accounts(&keys)
+            .await?;
+        let data = |i: usize| {
+            accounts[i]
+                .as_ref()
+                .map(|account| account.data.as_slice())
+                .ok_or_else(|| anyhow!("Missing account {}", keys[i]))
+        };
+        let trade_fee_rate = read_u64(data(0)?, CONFIG_TRADE_FEE_RATE)?;
+        let reserves = reserves(
+            &pool,
+            read_u64(data(1)?, TOKEN_ACCOUNT_AMOUNT)?,
+            read_u64(data(2)?, TOKEN_ACCOUNT_AMOUNT)?,
+        );
+        Ok((pool, trade_fee_rate, reserves))
+    }
+
+    /// SOL per whole token in a WSOL-paired pool
+    pub async fn get_token_price(&self, mint: &str, pool: &Pubkey) -> Result<f64> {
+        let (pool, _, (reserve_0, reserve_1)) = self.load(pool).await?;
+        let mint = Pubkey::from_str(mint)?;
+        let ui = |amount: u64, decimals: u8| amount as f64 / 10f64.powi(decimals as i32);
+        let (sol, token) = if pool.mint_0 == mint {
+            (
+                ui(reserve_1, pool.decimals_1),
+                ui(reserve_0, pool.decimals_0),
+            )
+        } else if pool.mint_1 == mint {
+            (
+                ui(reserve_0, pool.decimals_0),
+                ui(reserve_1, pool.decimals_1),
+            )
+        } else {
+            return Err(anyhow!("{} does not trade in pool {}", mint, pool.id));
+        };
+        if token == 0.0 {
+            return Err(anyhow!("Pool {} has no {} left", pool.id, mint));
+        }
+        Ok(sol / token)
+    }
+
+    /// Swaps between SOL and `mint` in a CPMM pool: lamports in on a buy, raw tokens on a sell
+    pub async fn swap(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>, SwapError> {
+        let instructions = self
+            .swap_instructions(mint, amount_in, swap_direction, pool, slippage_bps)
+            .await?;
+        let client = self
+            .rpc_client
+            .as_ref()
+            .ok_or_else(|| anyhow!("Blocking RPC client not available"))?;
+        let result = tx::new_signed_and_send(
+            client,
+            &self.keypair,
+            instructions,
+            Some(jito_client),
+            None,
+            timestamp,
+        )
+        .await
+        .map_err(SwapError::send("Raydium CPMM"));
+        METRICS.observe_swap("raydium_cpmm", &result);
+        result
+    }
+
+    async fn swap_instructions(
+        &self,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        pool: &Pubkey,
+        slippage_bps: u64,
+    ) -> Result<Vec<Instruction>> {
+        let (pool, trade_fee_rate, (reserve_0, reserve_1)) = self.load(pool).await?;
+        let mint = Pubkey::from_str(mint)?;
+        let wsol = spl_token::native_mint::ID;
+        let (input_mint, output_mint) = match swap_direction {
+            SwapDirection::Buy => (wsol, mint),
+            SwapDirection::Sell => (mint, wsol),
+        };
+        if ![input_mint, output_mint].contains(&pool.mint_0)
+            || ![input_mint, output_mint].contains(&pool.mint_1)
+        {
+            return Err(SwapError::WrongPool { pool: pool.id, mint }.into());
+        }
+        let zero_for_one = input_mint == pool.mint_0;
+        let (reserve_in, reserve_out, input_program, output_program) = if zero_for_one {
+            (reserve_0, reserve_1, pool.program_0, pool.program_1)
+        } else {
+            (reserve_1, reserve_0, pool.program_1, pool.program_0)
+        };
+
+        // Token-2022 transfer fees come off what reaches the pool and what reaches us
+        let client = &self.rpc_nonblocking_client;
+        let mint_account =
+            if input_program == spl_token_2022::ID || output_program == spl_token_2022::ID {
+                Some(client.get_account(&mint).await?)
+            } else {
+                None
+            };
+        let pool_in = match (&mint_account, &swap_direction) {
+            (Some(account), SwapDirection::Sell) => {
+                amount_in - transfer_fee(client, account, amount_in).await?
+            }
+            _ => amount_in,
+        };
+        if matches!(swap_direction, SwapDirection::Buy) {
+            PRICE_IMPACT
+                .check(reserve_in, pool_in)
+                .with_context(|| format!("Refusing to buy {}", mint))?;
+        }
+        let mut quote = quote_base_input(pool_in, reserve_in, reserve_out, trade_fee_rate);
+        if let (Some(account), SwapDirection::Buy) = (&mint_account, &swap_direction) {
+            quote -= transfer_fee(client, account, quote).await?;
+        }
+        let min_amount_out =
+            (quote as u128 * 10_000u128.saturating_sub(slippage_bps as u128) / 10_000) as u64;
+
+        let owner = self.keypair.pubkey();
+        let user_input =
+            get_associated_token_address_with_program_id(&owner, &input_mint, &input_program);
+        let user_output =
+            get_associated_token_address_with_program_id(&owner, &output_mint, &output_program);
+        let wsol_account = if input_mint == wsol {
+            user_input
+        } else {
+            user_output
+        };
+
+        // Creates are left out for accounts known to exist
+        let mut instructions = Vec::new();
+        instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &output_mint, &output_program));
+        if matches!(swap_direction, SwapDirection::Buy) {
+            instructions.extend(KNOWN_ATAS.create_if_missing(&owner, &wsol, &spl_token::id()));
+            instructions.push(system_instruction::transfer(&owner, &user_input, amount_in));
+            instructions.push(spl_token::instruction::sync_native(
+                &spl_token::id(),
+                &user_input,
+            )?);
+        }
+        instructions.push(swap_base_input_instruction(
+            &pool,
+            &owner,
+            zero_for_one,
+            &user_input,
+            &user_output,
+            amount_in,
+            min_amount_out,
+        ));
+        // Unwrap whatever WSOL is left or received
+        instructions.push(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &wsol_account,
+            &owner,
+            &owner,
+            &[],
+        )?);
+
+        Ok(instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_pool_state() {
+        let mut data = vec![0u8; POOL_STATE_LEN as usize];
+        let mint_0 = Pubkey::new_unique();
+        data[POOL_MINT_0..POOL_MINT_0 + 32].copy_from_slice(mint_0.as_ref());
+        data[POOL_DECIMALS_1] = 6;
+        data[POOL_PROTOCOL_FEES_0..POOL_PROTOCOL_FEES_0 + 8].copy_from_slice(&5u64.to_le_bytes());
+        data[POOL_CREATOR_FEES_0..POOL_CREATOR_FEES_0 + 8].copy_from_slice(&2u64.to_le_bytes());
+        let pool = CpmmPool::decode(Pubkey::new_unique(), &data).unwrap();
+        assert_eq!(pool.mint_0, mint_0);
+        assert_eq!(pool.decimals_1, 6);
+        assert_eq!(reserves(&pool, 100, 50), (93, 50));
+        assert!(CpmmPool::decode(Pubkey::new_unique(), &data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_quotes_round_trip() {
+        let (reserve_in, reserve_out) = (1_000_000_000, 5_000_000_000);
+        // 0.25% fee tier
+        let out = quote_base_input(1_000_000, reserve_in, reserve_out, 2_500);
+        let no_fee = quote_base_input(1_000_000, reserve_in, reserve_out, 0);
+        assert!(out < no_fee);
+        assert_eq!(no_fee, 4_995_004);
+        // Asking for exactly that output costs no more than what produced it
+        let amount_in = quote_base_output(out, reserve_in, reserve_out, 2_500).unwrap();
+        assert!(amount_in <= 1_000_000 && amount_in >= 999_990);
+        assert_eq!(
+            quote_base_output(reserve_out, reserve_in, reserve_out, 2_500),
+            None
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::{collections::HashMap, env, str::FromStr, time::Duration};
+
+use solana_sdk::native_token::sol_to_lamports;
+use tokio::{
+    sync::{oneshot, Mutex},
+    time::timeout,
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::executor::COPY_EXECUTOR,
+    services::notify::Event,
+};
+
+// Configuration constants
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Copied buys at or above a notional that wait for an operator's go-ahead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalConfig {
+    pub min_lamports: u64,
+    pub timeout: Duration,
+}
+
+impl ApprovalConfig {
+    /// Reads `APPROVAL_MIN_SOL` (unset turns approvals off) and `APPROVAL_TIMEOUT_SECS`
+    pub fn from_env() -> Option<Self> {
+        let min_sol = env::var("APPROVAL_MIN_SOL")
+            .ok()
+            .and_then(|v| f64::from_str(&v).ok())?;
+        let timeout_secs = env::var("APPROVAL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        Some(Self {
+            min_lamports: sol_to_lamports(min_sol),
+            timeout: Duration::from_secs(timeout_secs),
+        })
+    }
+
+    pub fn requires_approval(&self, amount_lamports: u64) -> bool {
+        amount_lamports >= self.min_lamports
+    }
+}
+
+/// Intents waiting on a human decision, answered from Telegram or `/approvals/{id}/approve`
+#[derive(Default)]
+pub struct ApprovalBook {
+    config: Option<ApprovalConfig>,
+    waiting: Mutex<HashMap<u64, oneshot::Sender<bool>>>,
+}
+
+impl ApprovalBook {
+    pub fn new(config: Option<ApprovalConfig>) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(ApprovalConfig::from_env())
+    }
+
+    pub fn config(&self) -> Option<&ApprovalConfig> {
+        self.config.as_ref()
+    }
+
+    async fn register(&self, intent_id: u64) -> oneshot::Receiver<bool> {
+        let (sender, receiver) = oneshot::channel();
+        self.waiting.lock().await.insert(intent_id, sender);
+        receiver
+    }
+
+    /// Answers a waiting intent; returns false if it is unknown or already timed out
+    pub async fn resolve(&self, intent_id: u64, approved: bool) -> bool {
+        match self.waiting.lock().await.remove(&intent_id) {
+            Some(sender) => sender.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    pub async fn waiting_ids(&self) -> Vec<u64> {
+        self.waiting.lock().await.keys().copied().collect()
+    }
+}
+
+/// Holds a copied buy until it is approved; small buys and disabled approvals pass straight through
+pub async fn await_approval(state: &AppState, intent_id: u64, mint: &str, amount: u64) -> bool {
+    let Some(config) = state.approvals.config().cloned() else {
+        return true;
+    };
+    if !config.requires_approval(amount) {
+        return true;
+    }
+
+    let decision = state.approvals.register(intent_id).await;
+    state
+        .notifier
+        .notify(Event::ApprovalRequest {
+            intent_id,
+            mint: mint.to_string(),
+            amount_lamports: amount,
+            timeout_secs: config.timeout.as_secs(),
+        })
+        .await;
+
+    // Other mints' copies keep running while this one waits on the operator
+    let outcome = match COPY_EXECUTOR.parked(timeout(config.timeout, decision)).await {
+        Ok(Ok(true)) => return true,
+        Ok(Ok(false)) => "rejected",
+        Ok(Err(_)) => "cancelled",
+        Err(_) => {
+            state.approvals.waiting.lock().await.remove(&intent_id);
+            "timed out"
+        }
+    };
+    let message = format!(
+        "Copy buy #{} of {} lamports into {} {}, dropped",
+        intent_id, amount, mint, outcome
+    );
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_delivers_decision_once() {
+        let book = ApprovalBook::new(Some(ApprovalConfig {
+            min_lamports: 1_000_000_000,
+            timeout: Duration::from_secs(60),
+        }));
+        let decision = book.register(7).await;
+        assert_eq!(book.waiting_ids().await, vec![7]);
+
+        assert!(book.resolve(7, true).await);
+        assert!(decision.await.unwrap());
+        assert!(!book.resolve(7, false).await);
+        assert!(!book.resolve(8, true).await);
+    }
+
+    #[test]
+    fn test_threshold_is_inclusive() {
+        let config = ApprovalConfig {
+            min_lamports: 500_000_000,
+            timeout: Duration::from_secs(30),
+        };
+        assert!(config.requires_approval(500_000_000));
+        assert!(!config.requires_approval(499_999_999));
+    }
+}
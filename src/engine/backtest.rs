@@ -0,0 +1,346 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{
+    engine::{
+        projection::{quote_buy, quote_sell},
+        signal::{parse_trade_signal, pump_trades, TradeSignal},
+        sizing::SizingConfig,
+        swap::SwapDirection,
+    },
+    services::{price_feed::CurveReserves, sources::SignalEvent},
+};
+
+// Configuration constants
+const SIGNATURE_PAGE: usize = 1000;
+
+/// What a backtest sizes and charges copies with
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub sizing: SizingConfig,
+    pub starting_lamports: u64,
+    /// pump.fun trade fee applied to every simulated fill
+    pub fee_bps: u64,
+    /// Network fee plus tip paid per simulated transaction
+    pub tx_cost: u64,
+}
+
+/// A target's pump.fun trade, with the curve reserves it left behind
+#[derive(Debug, Clone)]
+pub struct HistoricalTrade {
+    pub target: String,
+    pub signal: TradeSignal,
+    pub reserves: CurveReserves,
+}
+
+/// Outcome of replaying a target history
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestReport {
+    /// Target trades decoded from the history
+    pub signals: usize,
+    /// Target trades that weren't on a pump.fun curve, which the backtest can't price
+    pub unpriced: usize,
+    pub copied_buys: usize,
+    pub copied_sells: usize,
+    /// Buys skipped because the simulated balance couldn't cover them
+    pub skipped_buys: usize,
+    pub closed: usize,
+    pub wins: usize,
+    pub realized_lamports: i64,
+    /// Open positions marked at the last reserves seen for their mint
+    pub open_positions: usize,
+    pub unrealized_lamports: i64,
+    pub fees_lamports: u64,
+    /// Deepest fall of realized PnL from its running peak
+    pub max_drawdown_lamports: u64,
+    pub final_balance_lamports: u64,
+}
+
+#[derive(Debug, Default)]
+struct SimPosition {
+    tokens: u64,
+    invested: u64,
+    returned: u64,
+    fees: u64,
+}
+
+impl SimPosition {
+    fn pnl(&self) -> i64 {
+        self.returned as i64 - self.invested as i64 - self.fees as i64
+    }
+}
+
+/// Pages a target's signatures newest first and fetches up to `limit` successful transactions,
+/// returned as `getTransaction` results
+pub async fn fetch_history(client: &RpcClient, target: &str, limit: usize) -> Result<Vec<Value>> {
+    let address = Pubkey::from_str(target).context("Target is not an address")?;
+    let mut signatures = Vec::new();
+    let mut before = None;
+    while signatures.len() < limit {
+        let page = client
+            .get_signatures_for_address_with_config(
+                &address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    limit: Some(SIGNATURE_PAGE.min(limit - signatures.len())),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        let full = page.len() == SIGNATURE_PAGE;
+        signatures.extend(
+            page.into_iter()
+                .filter(|status| status.err.is_none())
+                .map(|status| status.signature),
+        );
+        if !full {
+            break;
+        }
+    }
+    let mut txs = Vec::new();
+    for signature in signatures {
+        let tx = client
+            .get_transaction_with_config(
+                &Signature::from_str(&signature)?,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::JsonParsed),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to fetch {}", signature))?;
+        txs.push(serde_json::to_value(&tx)?);
+    }
+    Ok(txs)
+}
+
+/// Reads `getTransaction` results, one per line, as written by `save_archive`
+pub fn load_archive(path: &Path) -> Result<Vec<Value>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Bad transaction on line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+pub fn save_archive(path: &Path, txs: &[Value]) -> Result<()> {
+    let lines = txs.iter().map(|tx| format!("{}\n", tx)).collect::<String>();
+    fs::write(path, lines).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Decodes every target trade in `txs` the way the live bot does, oldest first and once per
+/// signature. Returns the trades made on a pump.fun curve and how many others there were
+pub fn decode_history(targets: &[String], txs: &[Value]) -> (Vec<HistoricalTrade>, usize) {
+    let mut events = txs
+        .iter()
+        .filter_map(|tx| SignalEvent::from_transaction("backtest", tx))
+        .collect::<Vec<_>>();
+    events.sort_by_key(|event| event.slot);
+    let mut seen = HashSet::new();
+    let mut trades = Vec::new();
+    let mut unpriced = 0;
+    for event in events {
+        if !seen.insert(event.signature.clone()) {
+            continue;
+        }
+        let tx = &event.json["params"]["result"]["transaction"];
+        for target in targets {
+            let Some(signal) = parse_trade_signal(&event.json, target) else {
+                continue;
+            };
+            let curve = pump_trades(tx).into_iter().find(|trade| {
+                trade.user.to_string() == *target && trade.mint.to_string() == signal.mint
+            });
+            match curve {
+                Some(trade) => trades.push(HistoricalTrade {
+                    target: target.clone(),
+                    signal,
+                    reserves: CurveReserves {
+                        virtual_sol_reserves: trade.virtual_sol_reserves,
+                        virtual_token_reserves: trade.virtual_token_reserves,
+                    },
+                }),
+                None => unpriced += 1,
+            }
+        }
+    }
+    (trades, unpriced)
+}
+
+/// Copies `trades` in order with the live sizing rules, filling each copy against the curve
+/// as the target's trade left it. That is the best case for a bot landing right behind its
+/// target; anyone trading in between would move the price further
+pub fn simulate(config: &BacktestConfig, trades: &[HistoricalTrade]) -> BacktestReport {
+    let mut report = BacktestReport {
+        signals: trades.len(),
+        ..Default::default()
+    };
+    let mut balance = config.starting_lamports;
+    let mut positions = HashMap::<String, SimPosition>::new();
+    let mut last_reserves = HashMap::new();
+    let mut peak = 0i64;
+    for trade in trades {
+        let signal = &trade.signal;
+        last_reserves.insert(signal.mint.clone(), trade.reserves);
+        match signal.direction {
+            SwapDirection::Buy => {
+                let lamports = config.sizing.buy_amount(signal.sol_amount, balance);
+                if lamports == 0 || lamports + config.tx_cost > balance {
+                    report.skipped_buys += 1;
+                    continue;
+                }
+                let tokens = quote_buy(trade.reserves, lamports, config.fee_bps);
+                balance -= lamports + config.tx_cost;
+                let position = positions.entry(signal.mint.clone()).or_default();
+                position.tokens += tokens;
+                position.invested += lamports;
+                position.fees += config.tx_cost;
+                report.copied_buys += 1;
+            }
+            SwapDirection::Sell => {
+                let Some(position) = positions.get_mut(&signal.mint) else {
+                    continue;
+                };
+                let tokens = signal.mirrored_sell_amount(position.tokens);
+                if tokens == 0 {
+                    continue;
+                }
+                let lamports = quote_sell(trade.reserves, tokens, config.fee_bps);
+                balance = (balance + lamports).saturating_sub(config.tx_cost);
+                position.tokens -= tokens;
+                position.returned += lamports;
+                position.fees += config.tx_cost;
+                report.copied_sells += 1;
+                if position.tokens > 0 {
+                    continue;
+                }
+                let pnl = position.pnl();
+                positions.remove(&signal.mint);
+                report.closed += 1;
+                report.wins += (pnl > 0) as usize;
+                report.realized_lamports += pnl;
+                peak = peak.max(report.realized_lamports);
+                report.max_drawdown_lamports = report
+                    .max_drawdown_lamports
+                    .max((peak - report.realized_lamports) as u64);
+            }
+        }
+        report.fees_lamports += config.tx_cost;
+    }
+    for (mint, position) in &positions {
+        let value = last_reserves
+            .get(mint)
+            .map(|reserves| quote_sell(*reserves, position.tokens, config.fee_bps))
+            .unwrap_or(0);
+        report.unrealized_lamports += position.pnl() + value as i64;
+    }
+    report.open_positions = positions.len();
+    report.final_balance_lamports = balance;
+    report
+}
+
+/// Replays `targets`' trades in `txs` and simulates copying them
+pub fn backtest(config: &BacktestConfig, targets: &[String], txs: &[Value]) -> BacktestReport {
+    let (trades, unpriced) = decode_history(targets, txs);
+    let mut report = simulate(config, &trades);
+    report.signals += unpriced;
+    report.unpriced = unpriced;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sizing::CopySizing;
+
+    fn trade(
+        slot: u64,
+        direction: SwapDirection,
+        sol: u64,
+        tokens: u64,
+        pre: u64,
+    ) -> HistoricalTrade {
+        HistoricalTrade {
+            target: "target".to_string(),
+            signal: TradeSignal {
+                signature: slot.to_string(),
+                slot,
+                mint: "mint".to_string(),
+                direction,
+                sol_amount: sol,
+                token_amount: tokens,
+                token_pre_balance: pre,
+                decimals: 6,
+            },
+            reserves: CurveReserves {
+                virtual_sol_reserves: 30_000_000_000 + slot * 1_000_000_000,
+                virtual_token_reserves: 1_000_000_000_000_000 / (30 + slot),
+            },
+        }
+    }
+
+    #[test]
+    fn test_copies_round_trip_on_a_rising_curve() {
+        let config = BacktestConfig {
+            sizing: SizingConfig {
+                mode: CopySizing::Fixed(100_000_000),
+                ..Default::default()
+            },
+            starting_lamports: 1_000_000_000,
+            fee_bps: 100,
+            tx_cost: 10_000,
+        };
+        let trades = [
+            trade(0, SwapDirection::Buy, 1_000_000_000, 1_000, 0),
+            // Half the target's bag, then the rest once the curve has run
+            trade(5, SwapDirection::Sell, 0, 500, 1_000),
+            trade(10, SwapDirection::Sell, 0, 500, 500),
+        ];
+        let report = simulate(&config, &trades);
+        assert_eq!(report.copied_buys, 1);
+        assert_eq!(report.copied_sells, 2);
+        assert_eq!(
+            (report.closed, report.wins, report.open_positions),
+            (1, 1, 0)
+        );
+        assert!(report.realized_lamports > 0);
+        assert_eq!(report.fees_lamports, 30_000);
+        assert_eq!(
+            report.final_balance_lamports as i64,
+            1_000_000_000 + report.realized_lamports
+        );
+
+        // Too little balance to cover a copy skips it rather than going negative
+        let config = BacktestConfig {
+            starting_lamports: 50_000_000,
+            ..config
+        };
+        let report = simulate(&config, &trades[..1]);
+        assert_eq!((report.copied_buys, report.skipped_buys), (0, 1));
+    }
+}
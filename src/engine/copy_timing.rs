@@ -0,0 +1,235 @@
+use std::{
+    collections::HashMap,
+    env,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveTime, Utc};
+use tokio::time::Instant;
+
+use crate::engine::{hold_timer::parse_hold_duration, signal::TradeSignal, swap::SwapDirection};
+
+/// How one target's buys are timed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetTiming {
+    /// Buys wait this long and are dropped if the target sells the mint meanwhile
+    pub delay: Duration,
+    /// Further buys of the mint within this long of the first are folded into one copy
+    pub debounce: Duration,
+    /// Daily UTC window, possibly across midnight, in which buys aren't copied
+    pub quiet: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl FromStr for TargetTiming {
+    type Err = anyhow::Error;
+
+    /// Parses `delay=<time>,debounce=<time>,quiet=HH:MM-HH:MM`, any of them optional
+    fn from_str(s: &str) -> Result<Self> {
+        let mut timing = Self::default();
+        for option in s.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected key=value, got '{}'", option))?;
+            match key.trim() {
+                "delay" => timing.delay = Duration::from_secs(parse_hold_duration(value)?),
+                "debounce" => timing.debounce = Duration::from_secs(parse_hold_duration(value)?),
+                "quiet" => {
+                    let (start, end) = value
+                        .split_once('-')
+                        .ok_or_else(|| anyhow!("quiet must be HH:MM-HH:MM"))?;
+                    let time = |t: &str| {
+                        NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                            .with_context(|| format!("Invalid quiet time '{}'", t))
+                    };
+                    timing.quiet = Some((time(start)?, time(end)?));
+                }
+                other => return Err(anyhow!("Unknown copy timing option '{}'", other)),
+            }
+        }
+        Ok(timing)
+    }
+}
+
+impl TargetTiming {
+    /// How long a new buy is held before it's copied
+    fn hold(&self) -> Duration {
+        self.delay.max(self.debounce)
+    }
+
+    pub fn is_quiet(&self, now: NaiveTime) -> bool {
+        match self.quiet {
+            None => false,
+            Some((start, end)) if start <= end => now >= start && now < end,
+            Some((start, end)) => now >= start || now < end,
+        }
+    }
+}
+
+/// What to do with a target's trade
+#[derive(Debug)]
+pub enum Admission {
+    /// Copy it now
+    Copy(TradeSignal),
+    /// Wait, then copy whatever `release` hands back
+    Hold { id: u64, wait: Duration },
+    /// Folded into a buy already held
+    Merged,
+    /// A buy inside the target's quiet hours
+    Quiet,
+}
+
+struct HeldBuy {
+    id: u64,
+    opened: Instant,
+    signal: TradeSignal,
+}
+
+/// Per-target copy delay, debounce and quiet hours, and the buys currently held back by them
+#[derive(Default)]
+pub struct CopyTiming {
+    default: TargetTiming,
+    targets: HashMap<String, TargetTiming>,
+    held: Mutex<HashMap<(String, String), Vec<HeldBuy>>>,
+    next_id: AtomicU64,
+}
+
+impl CopyTiming {
+    /// Reads `COPY_TIMING` as `;`-separated `<wallet>:<options>` entries, with `*` for targets
+    /// without their own, e.g. `*:delay=3s;<wallet>:debounce=20s,quiet=22:00-06:00`. Unset
+    /// copies every trade straight away
+    pub fn from_env() -> Result<Self> {
+        let mut timing = Self::default();
+        let value = env::var("COPY_TIMING").unwrap_or_default();
+        for entry in value.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let (wallet, options) = entry
+                .split_once(':')
+                .ok_or_else(|| anyhow!("COPY_TIMING entries must be <wallet>:<options>"))?;
+            let parsed = TargetTiming::from_str(options)
+                .with_context(|| format!("Invalid COPY_TIMING for {}", wallet))?;
+            match wallet.trim() {
+                "*" => timing.default = parsed,
+                wallet => {
+                    timing.targets.insert(wallet.to_string(), parsed);
+                }
+            }
+        }
+        Ok(timing)
+    }
+
+    pub fn timing(&self, target: &str) -> &TargetTiming {
+        self.targets.get(target).unwrap_or(&self.default)
+    }
+
+    /// Decides when to copy a target's trade. Sells are always copied at once, and drop any of
+    /// the target's buys of the mint still held, which were reverted before we copied them
+    pub fn admit(&self, target: &str, signal: &TradeSignal) -> Admission {
+        let key = (target.to_string(), signal.mint.clone());
+        let mut held = self.held.lock().unwrap();
+        if matches!(signal.direction, SwapDirection::Sell) {
+            held.remove(&key);
+            return Admission::Copy(signal.clone());
+        }
+        let timing = self.timing(target);
+        if timing.is_quiet(Utc::now().time()) {
+            return Admission::Quiet;
+        }
+        if timing.hold().is_zero() {
+            return Admission::Copy(signal.clone());
+        }
+        let buys = held.entry(key).or_default();
+        if let Some(buy) = buys
+            .iter_mut()
+            .find(|buy| buy.opened.elapsed() < timing.debounce)
+        {
+            buy.signal.sol_amount += signal.sol_amount;
+            buy.signal.token_amount += signal.token_amount;
+            return Admission::Merged;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        buys.push(HeldBuy {
+            id,
+            opened: Instant::now(),
+            signal: signal.clone(),
+        });
+        Admission::Hold {
+            id,
+            wait: timing.hold(),
+        }
+    }
+
+    /// Hands back a held buy, with any buys folded into it, once its wait is over; `None` if
+    /// the target sold the mint meanwhile
+    pub fn release(&self, target: &str, mint: &str, id: u64) -> Option<TradeSignal> {
+        let key = (target.to_string(), mint.to_string());
+        let mut held = self.held.lock().unwrap();
+        let buys = held.get_mut(&key)?;
+        let buy = buys.remove(buys.iter().position(|buy| buy.id == id)?);
+        if buys.is_empty() {
+            held.remove(&key);
+        }
+        Some(buy.signal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(direction: SwapDirection, sol_amount: u64) -> TradeSignal {
+        TradeSignal {
+            signature: "sig".to_string(),
+            slot: 1,
+            mint: "mint".to_string(),
+            direction,
+            sol_amount,
+            token_amount: 10,
+            token_pre_balance: 0,
+            decimals: 6,
+        }
+    }
+
+    #[test]
+    fn test_holds_merges_and_drops_reverted_buys() {
+        let timing = TargetTiming::from_str("delay=5s, debounce=20s, quiet=22:00-06:00").unwrap();
+        assert_eq!(timing.hold(), Duration::from_secs(20));
+        assert!(timing.is_quiet(NaiveTime::from_hms_opt(23, 0, 0).unwrap()));
+        assert!(timing.is_quiet(NaiveTime::from_hms_opt(5, 59, 0).unwrap()));
+        assert!(!timing.is_quiet(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+        assert!(TargetTiming::from_str("wait=5s").is_err());
+
+        let copy = CopyTiming {
+            default: TargetTiming {
+                debounce: Duration::from_secs(60),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let Admission::Hold { id, .. } = copy.admit("target", &signal(SwapDirection::Buy, 1))
+        else {
+            panic!("first buy should be held");
+        };
+        assert!(matches!(
+            copy.admit("target", &signal(SwapDirection::Buy, 2)),
+            Admission::Merged
+        ));
+        assert_eq!(copy.release("target", "mint", id).unwrap().sol_amount, 3);
+        assert!(copy.release("target", "mint", id).is_none());
+
+        // A sell while a buy is held means the buy is never copied
+        let Admission::Hold { id, .. } = copy.admit("target", &signal(SwapDirection::Buy, 1))
+        else {
+            panic!("buy should be held");
+        };
+        assert!(matches!(
+            copy.admit("target", &signal(SwapDirection::Sell, 1)),
+            Admission::Copy(_)
+        ));
+        assert!(copy.release("target", "mint", id).is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use std::{collections::HashMap, env, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::Value;
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::token::{get_account_info, get_token_account_address},
+    engine::{
+        exit::market_exit,
+        signal::{has_log, launch_creator, parse_trade_signal},
+        swap::SwapDirection,
+    },
+    services::notify::Event,
+};
+
+// Configuration constants
+const DEFAULT_SYNC_SECS: u64 = 10;
+const CREATOR_EXIT_SLIPPAGE_BPS: u64 = 2_500;
+/// Anchor log line emitted by pump.fun's `sell` instruction
+const PUMP_SELL_LOG: &str = "Program log: Instruction: Sell";
+const SIGNATURE_PAGE: usize = 1_000;
+// Mints with more history than this are not walked back to their creation
+const MAX_SIGNATURE_PAGES: usize = 5;
+
+/// Share of `baseline` tokens the creator no longer holds, in percent
+pub fn sold_pct(baseline: u64, remaining: u64) -> f64 {
+    if baseline == 0 {
+        return 0.0;
+    }
+    baseline.saturating_sub(remaining) as f64 / baseline as f64 * 100.0
+}
+
+#[derive(Debug, Clone)]
+struct TrackedCreator {
+    creator: String,
+    /// Creator's balance when tracking started; 0 until known
+    baseline: u64,
+}
+
+/// Follows the creator of every held mint and flags positions whose creator dumps
+pub struct CreatorWatch {
+    /// Sold share of their holdings at which we exit; `None` disables the watch
+    exit_pct: Option<f64>,
+    sync_interval: Duration,
+    tracked: RwLock<HashMap<String, TrackedCreator>>,
+}
+
+impl CreatorWatch {
+    pub fn new(exit_pct: Option<f64>, sync_interval: Duration) -> Self {
+        Self {
+            exit_pct,
+            sync_interval,
+            tracked: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Reads `CREATOR_SELL_EXIT_PCT` (unset disables) and `CREATOR_SYNC_SECS`
+    pub fn from_env() -> Self {
+        let exit_pct = env::var("CREATOR_SELL_EXIT_PCT")
+            .ok()
+            .and_then(|v| f64::from_str(&v).ok());
+        let sync_secs = env::var("CREATOR_SYNC_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_SYNC_SECS)
+            .max(1);
+        Self::new(exit_pct, Duration::from_secs(sync_secs))
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.exit_pct.is_some()
+    }
+
+    pub async fn track(&self, mint: &str, creator: &str, baseline: u64) {
+        self.tracked.write().await.insert(
+            mint.to_string(),
+            TrackedCreator {
+                creator: creator.to_string(),
+                baseline,
+            },
+        );
+    }
+
+    pub async fn untrack(&self, mint: &str) {
+        self.tracked.write().await.remove(mint);
+    }
+
+    async fn is_tracked(&self, mint: &str) -> bool {
+        self.tracked.read().await.contains_key(mint)
+    }
+
+    /// Checks a pump.fun transaction for a tracked creator selling their token. Returns the
+    /// mint and the share sold once it reaches the exit threshold, and stops tracking it.
+    pub async fn observe(&self, json: &Value) -> Option<(String, f64)> {
+        let exit_pct = self.exit_pct?;
+        if !has_log(&json["params"]["result"]["transaction"], PUMP_SELL_LOG) {
+            return None;
+        }
+        let mut tracked = self.tracked.write().await;
+        let (mint, pct) = tracked.iter_mut().find_map(|(mint, tracked)| {
+            let signal = parse_trade_signal(json, &tracked.creator).filter(|signal| {
+                signal.mint == *mint && matches!(signal.direction, SwapDirection::Sell)
+            })?;
+            if tracked.baseline == 0 {
+                tracked.baseline = signal.token_pre_balance;
+            }
+            let remaining = signal.token_pre_balance.saturating_sub(signal.token_amount);
+            Some((mint.clone(), sold_pct(tracked.baseline, remaining)))
+        })?;
+        if pct < exit_pct {
+            return None;
+        }
+        tracked.remove(&mint);
+        Some((mint, pct))
+    }
+}
+
+/// The oldest transaction touching `mint`, its creation unless the mint has more history than
+/// is walked back through
+pub async fn oldest_signature(
+    state: &AppState,
+    mint: &str,
+) -> Result<RpcConfirmedTransactionStatusWithSignature> {
+    let client = &state.rpc_nonblocking_client;
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let mut before = None;
+    let mut oldest = None;
+    for _ in 0..MAX_SIGNATURE_PAGES {
+        let page = client
+            .get_signatures_for_address_with_config(
+                &mint_pubkey,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    limit: Some(SIGNATURE_PAGE),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let full = page.len() == SIGNATURE_PAGE;
+        let Some(last) = page.into_iter().last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        oldest = Some(last);
+        if !full {
+            break;
+        }
+    }
+    oldest.ok_or_else(|| anyhow!("No transactions for {}", mint))
+}
+
+/// Finds who created a pump.fun mint from the oldest transaction touching it
+pub async fn resolve_creator(state: &AppState, mint: &str) -> Result<String> {
+    let client = &state.rpc_nonblocking_client;
+    let signature = Signature::from_str(&oldest_signature(state, mint).await?.signature)?;
+
+    let tx = client
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::JsonParsed),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let tx = serde_json::to_value(&tx.transaction)?;
+    launch_creator(&tx).ok_or_else(|| anyhow!("Could not find the creation of {}", mint))
+}
+
+/// Raw token units of `mint` the creator holds in their ATA
+pub async fn creator_balance(state: &AppState, mint: &str, creator: &str) -> u64 {
+    let (Ok(mint_pubkey), Ok(creator)) = (Pubkey::from_str(mint), Pubkey::from_str(creator)) else {
+        return 0;
+    };
+    let Ok(ata) =
+        get_token_account_address(&state.rpc_nonblocking_client, &creator, &mint_pubkey).await
+    else {
+        return 0;
+    };
+    get_account_info(state.rpc_nonblocking_client.clone(), &mint_pubkey, &ata)
+        .await
+        .map(|account| account.base.amount)
+        .unwrap_or(0)
+}
+
+/// Starts tracking the creator of every open position and drops closed ones
+async fn sync_creators(state: &AppState) {
+    let watch = &state.creators;
+    let open = state.positions.open_positions().await;
+    for position in &open {
+        if watch.is_tracked(&position.mint).await {
+            continue;
+        }
+        let creator = match &position.creator {
+            Some(creator) => creator.clone(),
+            None => match resolve_creator(state, &position.mint).await {
+                Ok(creator) => {
+                    state.positions.set_creator(&position.mint, &creator).await;
+                    creator
+                }
+                Err(e) => {
+                    let _ = log_message(&format!(
+                        "Could not resolve the creator of {}: {}",
+                        position.mint, e
+                    ))
+                    .await;
+                    continue;
+                }
+            },
+        };
+        let baseline = creator_balance(state, &position.mint, &creator).await;
+        watch.track(&position.mint, &creator, baseline).await;
+    }
+
+    let stale = watch
+        .tracked
+        .read()
+        .await
+        .keys()
+        .filter(|mint| !open.iter().any(|position| &position.mint == *mint))
+        .cloned()
+        .collect::<Vec<_>>();
+    for mint in stale {
+        watch.untrack(&mint).await;
+    }
+}
+
+/// Keeps the creator watch in step with open positions, forever; idle while disabled
+pub async fn run_creator_sync(state: AppState) {
+    if !state.creators.enabled() {
+        return;
+    }
+    loop {
+        sync_creators(&state).await;
+        sleep(state.creators.sync_interval).await;
+    }
+}
+
+/// Dumps a position after its creator sold `pct` of their holdings
+pub async fn creator_exit(
+    state: AppState,
+    mint: String,
+    pct: f64,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let Some(position) = state.positions.get(&mint).await else {
+        return;
+    };
+    let message = match market_exit(&state, &position, CREATOR_EXIT_SLIPPAGE_BPS, jito_client).await
+    {
+        Ok(()) => format!("🚨 Creator sold {:.0}% of {}, exited", pct, mint),
+        Err(e) => format!(
+            "🚨 Creator sold {:.0}% of {} but the exit failed: {}",
+            pct, mint, e
+        ),
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn creator_sell(pre: &str, post: &str) -> Value {
+        json!({
+            "params": { "result": {
+                "signature": "sig",
+                "slot": 1,
+                "transaction": {
+                    "transaction": { "message": {
+                        "accountKeys": [{ "pubkey": "creator" }],
+                        "instructions": []
+                    }},
+                    "meta": {
+                        "err": null,
+                        "fee": 5000,
+                        "preBalances": [1_000_000_000],
+                        "postBalances": [2_000_000_000],
+                        "preTokenBalances": [{ "owner": "creator", "mint": "mint",
+                            "uiTokenAmount": { "amount": pre, "decimals": 6 } }],
+                        "postTokenBalances": [{ "owner": "creator", "mint": "mint",
+                            "uiTokenAmount": { "amount": post, "decimals": 6 } }],
+                        "logMessages": [PUMP_SELL_LOG]
+                    }
+                }
+            }}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_exits_once_creator_sells_past_threshold() {
+        let watch = CreatorWatch::new(Some(50.0), Duration::from_secs(1));
+        watch.track("mint", "creator", 1_000).await;
+        assert_eq!(watch.observe(&creator_sell("1000", "700")).await, None);
+        let (mint, pct) = watch.observe(&creator_sell("700", "400")).await.unwrap();
+        assert_eq!(mint, "mint");
+        assert_eq!(pct, 60.0);
+        // Fired once; no longer tracked
+        assert_eq!(watch.observe(&creator_sell("400", "0")).await, None);
+    }
+
+    #[test]
+    fn test_sold_pct() {
+        assert_eq!(sold_pct(1_000, 250), 75.0);
+        assert_eq!(sold_pct(0, 0), 0.0);
+        assert_eq!(sold_pct(1_000, 2_000), 0.0);
+    }
+}
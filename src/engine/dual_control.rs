@@ -0,0 +1,402 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde::Serialize;
+use solana_sdk::native_token::lamports_to_sol;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::common::{
+    config::LiveSettings,
+    storage::{append_json_line, data_path, EventTime},
+    utils::{log_message, AppState},
+};
+use crate::engine::{
+    execution::record_execution,
+    exit::{market_exit, partial_exit},
+    swap::SwapDirection,
+};
+use crate::risk::limits::reserve_buy;
+use crate::services::notify::Event;
+
+// Configuration constants
+const AUDIT_FILE: &str = "audit.jsonl";
+const DEFAULT_REQUIRED_APPROVALS: usize = 1;
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MANUAL_TRADE_SLIPPAGE_BPS: u64 = 2_500;
+
+/// An operator action that may need sign-off from more than one person
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlAction {
+    /// Buy into a mint for `lamports`, within the usual risk limits
+    ManualBuy { mint: String, lamports: u64 },
+    /// Sell `pct` percent of the position in a mint; 100 closes it
+    ManualSell { mint: String, pct: f64 },
+    /// Swap in settings read from an edited config file
+    ConfigChange {
+        settings: LiveSettings,
+        summary: String,
+    },
+}
+
+impl ControlAction {
+    pub fn describe(&self) -> String {
+        match self {
+            ControlAction::ManualBuy { mint, lamports } => {
+                format!("buy {} SOL of {}", lamports_to_sol(*lamports), mint)
+            }
+            ControlAction::ManualSell { mint, pct } if *pct >= 100.0 => {
+                format!("sell all of {}", mint)
+            }
+            ControlAction::ManualSell { mint, pct } => format!("sell {}% of {}", pct, mint),
+            ControlAction::ConfigChange { summary, .. } => format!("apply config: {}", summary),
+        }
+    }
+}
+
+/// Where an action stands after a vote
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vote {
+    /// Still short of the quorum
+    Pending {
+        approvals: usize,
+        required: usize,
+    },
+    /// Quorum reached; the action is handed back for execution
+    Approved(ControlAction),
+    Rejected,
+    /// This approver already signed the action
+    Duplicate,
+    /// No such action, or it expired
+    Unknown,
+}
+
+/// One line of the audit log
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    at: DateTime<Utc>,
+    action_id: u64,
+    action: String,
+    actor: &'a str,
+    event: &'a str,
+    time: EventTime,
+}
+
+struct PendingAction {
+    action: ControlAction,
+    approvers: Vec<String>,
+    requested_at: Instant,
+}
+
+/// Collects approvals from distinct operators (`telegram:<user id>`, `api:<key name>`, ...)
+/// before an action runs, and records every step in an audit log
+pub struct DualControl {
+    required: usize,
+    timeout: Duration,
+    pending: Mutex<HashMap<u64, PendingAction>>,
+    next_id: AtomicU64,
+    /// Audit log location; `None` only logs to the bot log
+    audit_path: Option<PathBuf>,
+}
+
+impl DualControl {
+    pub fn new(required: usize, timeout: Duration, audit_path: Option<PathBuf>) -> Self {
+        Self {
+            required: required.max(1),
+            timeout,
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            audit_path,
+        }
+    }
+
+    /// Reads `DUAL_CONTROL_APPROVALS` (default 1, i.e. off) and `DUAL_CONTROL_TIMEOUT_SECS`,
+    /// auditing to `audit.jsonl` in the data directory
+    pub fn from_env() -> Self {
+        let required = env::var("DUAL_CONTROL_APPROVALS")
+            .ok()
+            .and_then(|v| usize::from_str(&v).ok())
+            .unwrap_or(DEFAULT_REQUIRED_APPROVALS);
+        let timeout_secs = env::var("DUAL_CONTROL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        Self::new(
+            required,
+            Duration::from_secs(timeout_secs),
+            Some(data_path(AUDIT_FILE)),
+        )
+    }
+
+    pub fn required(&self) -> usize {
+        self.required
+    }
+
+    async fn audit(&self, action_id: u64, action: &ControlAction, actor: &str, event: &str) {
+        let entry = AuditEntry {
+            at: Utc::now(),
+            action_id,
+            action: action.describe(),
+            actor,
+            event,
+            time: EventTime::now(),
+        };
+        let _ = log_message(&format!(
+            "Audit #{} {} by {}: {}",
+            action_id, event, actor, entry.action
+        ))
+        .await;
+        if let Some(path) = &self.audit_path {
+            if let Err(e) = append_json_line(path, &entry).await {
+                let _ = log_message(&format!("Failed to write audit log: {}", e)).await;
+            }
+        }
+    }
+
+    async fn drop_expired(&self, pending: &mut HashMap<u64, PendingAction>) {
+        let expired = pending
+            .iter()
+            .filter(|(_, p)| p.requested_at.elapsed() >= self.timeout)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in expired {
+            if let Some(p) = pending.remove(&id) {
+                self.audit(id, &p.action, "system", "expired").await;
+            }
+        }
+    }
+
+    /// Opens an action; a `requester` counts as its first approval
+    pub async fn request(&self, action: ControlAction, requester: Option<&str>) -> (u64, Vote) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.audit(id, &action, requester.unwrap_or("system"), "requested")
+            .await;
+        self.pending.lock().await.insert(
+            id,
+            PendingAction {
+                action,
+                approvers: vec![],
+                requested_at: Instant::now(),
+            },
+        );
+        let vote = match requester {
+            Some(requester) => self.approve(id, requester).await,
+            None => Vote::Pending {
+                approvals: 0,
+                required: self.required,
+            },
+        };
+        (id, vote)
+    }
+
+    /// Adds `approver`'s sign-off, returning the action once enough distinct operators agree
+    pub async fn approve(&self, id: u64, approver: &str) -> Vote {
+        let mut pending = self.pending.lock().await;
+        self.drop_expired(&mut pending).await;
+        let Some(entry) = pending.get_mut(&id) else {
+            return Vote::Unknown;
+        };
+        if entry.approvers.iter().any(|a| a == approver) {
+            return Vote::Duplicate;
+        }
+        entry.approvers.push(approver.to_string());
+        let approvals = entry.approvers.len();
+        let action = entry.action.clone();
+        self.audit(id, &action, approver, "approved").await;
+        if approvals < self.required {
+            return Vote::Pending {
+                approvals,
+                required: self.required,
+            };
+        }
+        pending.remove(&id);
+        Vote::Approved(action)
+    }
+
+    /// Cancels an action; any single operator can veto
+    pub async fn reject(&self, id: u64, approver: &str) -> Vote {
+        let mut pending = self.pending.lock().await;
+        self.drop_expired(&mut pending).await;
+        match pending.remove(&id) {
+            Some(entry) => {
+                self.audit(id, &entry.action, approver, "rejected").await;
+                Vote::Rejected
+            }
+            None => Vote::Unknown,
+        }
+    }
+
+    /// Records how an approved action turned out
+    pub async fn record_outcome(&self, id: u64, action: &ControlAction, result: &Result<String>) {
+        let event = match result {
+            Ok(_) => "executed",
+            Err(_) => "failed",
+        };
+        self.audit(id, action, "system", event).await;
+    }
+}
+
+/// Opens an action and, while it lacks approvals, asks the operators to sign it off
+pub async fn submit(
+    state: &AppState,
+    action: ControlAction,
+    requester: Option<&str>,
+) -> (u64, Vote) {
+    let description = action.describe();
+    let (id, vote) = state.control.request(action, requester).await;
+    if let Vote::Pending {
+        approvals,
+        required,
+    } = vote
+    {
+        state
+            .notifier
+            .notify(Event::ControlRequest {
+                action_id: id,
+                description,
+                approvals,
+                required,
+            })
+            .await;
+    }
+    (id, vote)
+}
+
+/// Turns a vote into a reply for the voter, running the action once it is approved
+pub async fn act_on_vote(
+    state: &AppState,
+    id: u64,
+    vote: Vote,
+    jito_client: Arc<JitoRpcClient>,
+) -> String {
+    match vote {
+        Vote::Pending {
+            approvals,
+            required,
+        } => format!("#{} has {}/{} approvals", id, approvals, required),
+        Vote::Approved(action) => {
+            let result = execute(state, &action, jito_client).await;
+            state.control.record_outcome(id, &action, &result).await;
+            let message = match result {
+                Ok(done) => format!("✅ #{} {}", id, done),
+                Err(e) => format!("❌ #{} failed: {}", id, e),
+            };
+            state.notifier.notify(Event::Info(message.clone())).await;
+            message
+        }
+        Vote::Rejected => format!("#{} vetoed", id),
+        Vote::Duplicate => format!("You already approved #{}", id),
+        Vote::Unknown => format!("#{} is no longer pending", id),
+    }
+}
+
+/// Carries out an approved action, returning a line for the operators
+pub async fn execute(
+    state: &AppState,
+    action: &ControlAction,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<String> {
+    match action {
+        ControlAction::ManualBuy { mint, lamports } => {
+            let _reservation = reserve_buy(state, "manual", mint, *lamports)
+                .await
+                .ok_or_else(|| anyhow!("Risk limits blocked the buy of {}", mint))?;
+            let signatures = state
+                .router
+                .swap(
+                    state.clone(),
+                    mint,
+                    *lamports,
+                    SwapDirection::Buy,
+                    MANUAL_TRADE_SLIPPAGE_BPS,
+                    jito_client,
+                    Instant::now(),
+                )
+                .await?;
+            state
+                .positions
+                .record_buy(mint, None, *lamports, state.fees.tx_cost())
+                .await;
+            tokio::spawn(record_execution(
+                state.clone(),
+                mint.clone(),
+                SwapDirection::Buy,
+                signatures,
+                None,
+            ));
+            Ok(format!(
+                "Bought {} SOL of {}",
+                lamports_to_sol(*lamports),
+                mint
+            ))
+        }
+        ControlAction::ManualSell { mint, pct } => {
+            let position = state
+                .positions
+                .get(mint)
+                .await
+                .ok_or_else(|| anyhow!("No open position in {}", mint))?;
+            if *pct >= 100.0 {
+                market_exit(state, &position, MANUAL_TRADE_SLIPPAGE_BPS, jito_client).await?;
+                return Ok(format!("Sold all of {}", mint));
+            }
+            partial_exit(state, &position, *pct, MANUAL_TRADE_SLIPPAGE_BPS, jito_client).await?;
+            Ok(format!("Sold {}% of {}", pct, mint))
+        }
+        ControlAction::ConfigChange { settings, summary } => {
+            state.settings.replace(settings.clone());
+            Ok(format!("Config applied: {}", summary))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sell() -> ControlAction {
+        ControlAction::ManualSell {
+            mint: "mint".to_string(),
+            pct: 100.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_needs_two_distinct_approvers() {
+        let control = DualControl::new(2, Duration::from_secs(60), None);
+        let (id, vote) = control.request(sell(), Some("telegram:1")).await;
+        assert_eq!(
+            vote,
+            Vote::Pending {
+                approvals: 1,
+                required: 2
+            }
+        );
+        assert_eq!(control.approve(id, "telegram:1").await, Vote::Duplicate);
+        assert_eq!(control.approve(id, "api:ops").await, Vote::Approved(sell()));
+        assert_eq!(control.approve(id, "telegram:2").await, Vote::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_single_approval_and_veto() {
+        let control = DualControl::new(1, Duration::from_secs(60), None);
+        let (_, vote) = control.request(sell(), Some("telegram:1")).await;
+        assert_eq!(vote, Vote::Approved(sell()));
+
+        let control = DualControl::new(2, Duration::from_secs(60), None);
+        let (id, _) = control.request(sell(), None).await;
+        assert_eq!(control.reject(id, "telegram:2").await, Vote::Rejected);
+        assert_eq!(control.approve(id, "telegram:1").await, Vote::Unknown);
+    }
+}
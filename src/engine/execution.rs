@@ -0,0 +1,310 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, signer::Signer};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::time::sleep;
+
+use crate::{
+    common::{
+        storage::{append_json_line, data_path, EventTime},
+        utils::{log_message, AppState},
+    },
+    engine::{
+        fees::report_breakeven,
+        position::FillSide,
+        signal::{parse_trade_signal, pump_fill},
+        swap::SwapDirection,
+    },
+    services::{metrics::METRICS, notify::Event, sources::SignalEvent},
+};
+
+// Configuration constants
+const EXECUTIONS_FILE: &str = "executions.jsonl";
+const FETCH_ATTEMPTS: u32 = 10;
+const FETCH_RETRY_MS: u64 = 1_500;
+
+/// Price each venue last quoted per mint and side, for measuring realized slippage
+pub static QUOTES: LazyLock<QuoteBook> = LazyLock::new(QuoteBook::default);
+
+#[derive(Debug, Default)]
+pub struct QuoteBook {
+    quotes: Mutex<HashMap<(String, FillSide), f64>>,
+}
+
+impl QuoteBook {
+    /// Records the SOL per token a venue expects a trade to fill at
+    pub fn record(&self, mint: &str, direction: &SwapDirection, price: f64) {
+        if price.is_finite() && price > 0.0 {
+            let key = (mint.to_string(), FillSide::from(direction));
+            self.quotes.lock().unwrap().insert(key, price);
+        }
+    }
+
+    pub fn take(&self, mint: &str, side: FillSide) -> Option<f64> {
+        self.quotes
+            .lock()
+            .unwrap()
+            .remove(&(mint.to_string(), side))
+    }
+}
+
+impl From<&SwapDirection> for FillSide {
+    fn from(direction: &SwapDirection) -> Self {
+        match direction {
+            SwapDirection::Buy => FillSide::Buy,
+            SwapDirection::Sell => FillSide::Sell,
+        }
+    }
+}
+
+/// SOL per UI token for a fill of `lamports` against `tokens` raw units
+pub fn fill_price(lamports: u64, tokens: u64, decimals: u8) -> Option<f64> {
+    if tokens == 0 {
+        return None;
+    }
+    Some((lamports as f64 / 1e9) / (tokens as f64 / 10f64.powi(decimals as i32)))
+}
+
+/// How much worse than quoted a fill was, in basis points; negative when it beat the quote
+pub fn slippage_bps(side: FillSide, quoted: f64, actual: f64) -> f64 {
+    let worse = match side {
+        FillSide::Buy => actual - quoted,
+        FillSide::Sell => quoted - actual,
+    };
+    worse / quoted * 10_000.0
+}
+
+/// How many slots after the target's trade in `target_slot` ours landed in `landed_slot`
+pub fn slots_behind(target_slot: Option<u64>, landed_slot: Option<u64>) -> Option<u64> {
+    let (target, landed) = (target_slot?, landed_slot?);
+    Some(landed.saturating_sub(target))
+}
+
+/// One of our own swaps as it actually landed, as kept in the executions ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub at: DateTime<Utc>,
+    pub signature: String,
+    pub mint: String,
+    pub side: FillSide,
+    /// Lamports that left (buy) or reached (sell) the wallet, net of the network fee
+    pub lamports: u64,
+    pub tokens: u64,
+    /// Network fee the transaction paid
+    pub fee: u64,
+    /// Realized SOL per token
+    pub price: f64,
+    pub quoted_price: Option<f64>,
+    pub slippage_bps: Option<f64>,
+    #[serde(default)]
+    pub time: EventTime,
+    /// Slot of the target's trade this copied, for copies
+    #[serde(default)]
+    pub target_slot: Option<u64>,
+    /// Slots between the target's trade and this one landing
+    #[serde(default)]
+    pub slots_behind: Option<u64>,
+}
+
+impl Execution {
+    /// Tokens filled in UI units, recovered from the realized price, which is quoted per UI token
+    pub fn ui_tokens(&self) -> f64 {
+        self.lamports as f64 / 1e9 / self.price
+    }
+}
+
+/// Our wallet's fill of `mint` in a fetched (`getTransaction`) transaction
+pub fn parse_execution(tx: &Value, wallet: &str, mint: &str) -> Option<Execution> {
+    let event = SignalEvent::from_transaction("own", tx)?;
+    let signal = parse_trade_signal(&event.json, wallet).filter(|signal| signal.mint == mint)?;
+    let side = FillSide::from(&signal.direction);
+    // The curve's own event is exact; balance deltas also carry tips
+    let lamports = pump_fill(&event.json["params"]["result"]["transaction"], wallet, mint)
+        .map_or(signal.sol_amount, |fill| fill.sol_amount);
+    Some(Execution {
+        at: Utc::now(),
+        signature: event.signature,
+        mint: mint.to_string(),
+        side,
+        lamports,
+        tokens: signal.token_amount,
+        fee: tx["meta"]["fee"].as_u64().unwrap_or(0),
+        price: fill_price(lamports, signal.token_amount, signal.decimals)?,
+        quoted_price: None,
+        slippage_bps: None,
+        time: EventTime::on_chain(event.slot, tx["blockTime"].as_i64()),
+        target_slot: None,
+        slots_behind: None,
+    })
+}
+
+pub(crate) async fn fetch_transaction(state: &AppState, signature: &str) -> Result<Value> {
+    let signature = Signature::from_str(signature)?;
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::JsonParsed),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    for _ in 0..FETCH_ATTEMPTS {
+        if let Ok(tx) = state
+            .rpc_nonblocking_client
+            .get_transaction_with_config(&signature, config)
+            .await
+        {
+            return Ok(serde_json::to_value(&tx)?);
+        }
+        sleep(Duration::from_millis(FETCH_RETRY_MS)).await;
+    }
+    Err(anyhow!("{} never confirmed", signature))
+}
+
+/// How our wallet's swap of `mint` filled, from the first of `signatures` that holds it
+pub async fn find_execution(
+    state: &AppState,
+    mint: &str,
+    signatures: &[String],
+) -> Option<Execution> {
+    let wallet = state.wallet.pubkey().to_string();
+    for signature in signatures {
+        let Ok(tx) = fetch_transaction(state, signature).await else {
+            continue;
+        };
+        if let Some(execution) = parse_execution(&tx, &wallet, mint) {
+            return Some(execution);
+        }
+    }
+    None
+}
+
+/// Every fill of `mint` by our wallet among `signatures`, for swaps sent as several transactions
+pub async fn find_executions(
+    state: &AppState,
+    mint: &str,
+    signatures: &[String],
+) -> Vec<Execution> {
+    let wallet = state.wallet.pubkey().to_string();
+    let mut executions = Vec::with_capacity(signatures.len());
+    for signature in signatures {
+        let Ok(tx) = fetch_transaction(state, signature).await else {
+            continue;
+        };
+        executions.extend(parse_execution(&tx, &wallet, mint));
+    }
+    executions
+}
+
+/// Looks up how one of our swaps of `mint` filled and appends it to `data/executions.jsonl`.
+/// `signatures` is what the send returned; the first one holding our trade is used.
+/// `target_slot` is the slot of the target's trade when the swap copied one. A buy's tokens
+/// are booked on its position, and the breakeven reported once every buy has them
+pub async fn record_execution(
+    state: AppState,
+    mint: String,
+    direction: SwapDirection,
+    signatures: Vec<String>,
+    target_slot: Option<u64>,
+) {
+    let side = FillSide::from(&direction);
+    let quoted_price = QUOTES.take(&mint, side);
+    let Some(mut execution) = find_execution(&state, &mint, &signatures).await else {
+        let _ = log_message(&format!(
+            "Could not find how the {:?} of {} filled",
+            side, mint
+        ))
+        .await;
+        return;
+    };
+    execution.quoted_price = quoted_price;
+    execution.slippage_bps = quoted_price.map(|quoted| slippage_bps(side, quoted, execution.price));
+    execution.target_slot = target_slot;
+    execution.slots_behind = slots_behind(target_slot, execution.time.slot);
+    if let Some(slots) = execution.slots_behind {
+        METRICS.observe_slots_behind(slots);
+    }
+    let _ = log_message(&format!(
+        "Filled {:?} {} at {:.10} SOL{}{}",
+        side,
+        mint,
+        execution.price,
+        execution
+            .slippage_bps
+            .map(|bps| format!(", {:+.0} bps vs quote", bps))
+            .unwrap_or_default(),
+        execution
+            .slots_behind
+            .map(|slots| format!(", {} slots behind target", slots))
+            .unwrap_or_default()
+    ))
+    .await;
+    if let Err(e) = append_json_line(&data_path(EXECUTIONS_FILE), &execution).await {
+        let _ = log_message(&format!("Failed to record execution of {}: {}", mint, e)).await;
+    }
+    if side == FillSide::Buy
+        && state
+            .positions
+            .record_bought_tokens(&mint, execution.ui_tokens())
+            .await
+    {
+        report_breakeven(&state, &mint).await;
+    }
+    state
+        .notifier
+        .notify(Event::Trade {
+            side,
+            mint,
+            lamports: execution.lamports,
+            tokens: execution.tokens,
+            price: execution.price,
+            signature: execution.signature,
+            slots_behind: execution.slots_behind,
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_execution() {
+        let tx = json!({
+            "slot": 7,
+            "transaction": {
+                "signatures": ["sig"],
+                "message": { "accountKeys": [{ "pubkey": "me" }], "instructions": [] }
+            },
+            "meta": {
+                "err": null,
+                "fee": 5_000,
+                "preBalances": [2_000_005_000u64],
+                "postBalances": [1_000_000_000u64],
+                "preTokenBalances": [],
+                "postTokenBalances": [{ "owner": "me", "mint": "mint",
+                    "uiTokenAmount": { "amount": "2000000", "decimals": 6 } }]
+            }
+        });
+        let execution = parse_execution(&tx, "me", "mint").unwrap();
+        assert_eq!(execution.side, FillSide::Buy);
+        assert_eq!(execution.lamports, 1_000_000_000);
+        assert_eq!(execution.fee, 5_000);
+        assert_eq!(execution.price, 0.5);
+        assert_eq!(execution.time.slot, Some(7));
+        assert!(parse_execution(&tx, "me", "other").is_none());
+        assert_eq!(slots_behind(Some(5), execution.time.slot), Some(2));
+        assert_eq!(slots_behind(None, execution.time.slot), None);
+
+        assert_eq!(slippage_bps(FillSide::Buy, 0.4, 0.5), 2_500.0);
+        assert_eq!(slippage_bps(FillSide::Sell, 0.5, 0.4), 2_000.0);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use futures_util::future::{BoxFuture, FutureExt};
+use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
+
+// Configuration constants
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Runs copies as they arrive, keeping each mint's in order
+pub static COPY_EXECUTOR: LazyLock<MintExecutor> =
+    LazyLock::new(|| MintExecutor::new(concurrency()));
+
+type Job = BoxFuture<'static, ()>;
+
+tokio::task_local! {
+    /// Concurrency slot of the job running on this task
+    static PERMIT: RefCell<Option<OwnedSemaphorePermit>>;
+}
+
+/// Runs jobs for different mints in parallel, up to a limit, and jobs for the same mint one
+/// at a time in submission order, so a target's buy is always copied before its sell
+pub struct MintExecutor {
+    permits: Arc<Semaphore>,
+    /// Queue of every mint with a job running or waiting
+    queues: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Job>>>>,
+}
+
+/// Concurrent copies from `COPY_CONCURRENCY`, default 8
+fn concurrency() -> usize {
+    env::var("COPY_CONCURRENCY")
+        .ok()
+        .and_then(|v| usize::from_str(&v).ok())
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .max(1)
+}
+
+impl MintExecutor {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(concurrency)),
+            queues: Arc::default(),
+        }
+    }
+
+    /// Queues `job` behind whatever is already queued for `mint`
+    pub fn submit(&self, mint: &str, job: impl Future<Output = ()> + Send + 'static) {
+        let mut queues = self.queues.lock().unwrap();
+        let job = job.boxed();
+        // A queue whose worker is gone is replaced below
+        let job = match queues.get(mint) {
+            Some(queue) => match queue.send(job) {
+                Ok(()) => return,
+                Err(mpsc::error::SendError(job)) => job,
+            },
+            None => job,
+        };
+        let (queue, jobs) = mpsc::unbounded_channel();
+        let _ = queue.send(job);
+        queues.insert(mint.to_string(), queue);
+        tokio::spawn(run_queue(
+            mint.to_string(),
+            jobs,
+            self.permits.clone(),
+            self.queues.clone(),
+        ));
+    }
+
+    /// Queues `job` like `submit` and waits for its output
+    pub async fn run<T: Send + 'static>(
+        &self,
+        mint: &str,
+        job: impl Future<Output = T> + Send + 'static,
+    ) -> Result<T> {
+        let (done, output) = oneshot::channel();
+        self.submit(mint, async move {
+            let _ = done.send(job.await);
+        });
+        output
+            .await
+            .map_err(|_| anyhow!("Job for {} panicked", mint))
+    }
+
+    /// Awaits `wait` without holding the calling job's concurrency slot, so a job parked on
+    /// something slow, like an operator's approval, doesn't hold up other mints' copies. The
+    /// job's mint stays blocked; the slot is taken back before this returns
+    pub async fn parked<T>(&self, wait: impl Future<Output = T>) -> T {
+        let Ok(Some(permit)) = PERMIT.try_with(|permit| permit.borrow_mut().take()) else {
+            return wait.await;
+        };
+        drop(permit);
+        let output = wait.await;
+        if let Ok(permit) = self.permits.clone().acquire_owned().await {
+            let _ = PERMIT.try_with(|slot| *slot.borrow_mut() = Some(permit));
+        }
+        output
+    }
+
+    /// Mints with a job running or waiting
+    pub fn busy_mints(&self) -> usize {
+        self.queues.lock().unwrap().len()
+    }
+}
+
+/// Works through one mint's jobs, then retires its queue once nothing is left
+async fn run_queue(
+    mint: String,
+    mut jobs: mpsc::UnboundedReceiver<Job>,
+    permits: Arc<Semaphore>,
+    queues: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Job>>>>,
+) {
+    loop {
+        let job = match jobs.try_recv() {
+            Ok(job) => job,
+            Err(_) => {
+                // Checked again under the lock, so a job submitted meanwhile isn't stranded
+                let mut queues = queues.lock().unwrap();
+                match jobs.try_recv() {
+                    Ok(job) => job,
+                    Err(_) => {
+                        queues.remove(&mint);
+                        return;
+                    }
+                }
+            }
+        };
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            return;
+        };
+        // Its own task, so a panicking job doesn't take the mint's later jobs with it
+        let _ = tokio::spawn(PERMIT.scope(RefCell::new(Some(permit)), job)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    #[tokio::test]
+    async fn test_orders_per_mint_and_overlaps_mints() {
+        let executor = MintExecutor::new(4);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let job = |name: &'static str, wait: u64| {
+            let log = log.clone();
+            async move {
+                log.lock().unwrap().push(format!("{} start", name));
+                sleep(Duration::from_millis(wait)).await;
+                log.lock().unwrap().push(format!("{} end", name));
+            }
+        };
+
+        let buy = executor.run("a", job("a buy", 50));
+        let sell = executor.run("a", job("a sell", 0));
+        let other = executor.run("b", job("b buy", 0));
+        let _ = tokio::join!(buy, sell, other);
+
+        let log = log.lock().unwrap();
+        let at = |entry: &str| log.iter().position(|e| e == entry).unwrap();
+        assert!(at("a buy end") < at("a sell start"));
+        // b ran while a's buy was still waiting
+        assert!(at("b buy end") < at("a buy end"));
+        drop(log);
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(executor.busy_mints(), 0);
+
+        assert!(executor.run("c", async { panic!("boom") }).await.is_err());
+        assert_eq!(executor.run("c", async { 7 }).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_parked_job_frees_its_slot() {
+        static EXECUTOR: LazyLock<MintExecutor> = LazyLock::new(|| MintExecutor::new(1));
+        let (approve, approval) = oneshot::channel::<()>();
+        let parked = tokio::spawn(EXECUTOR.run("a", async { EXECUTOR.parked(approval).await }));
+        sleep(Duration::from_millis(10)).await;
+
+        // Runs in the only slot while a waits
+        assert_eq!(EXECUTOR.run("b", async { 7 }).await.unwrap(), 7);
+        approve.send(()).unwrap();
+        assert!(parked.await.unwrap().unwrap().is_ok());
+    }
+}
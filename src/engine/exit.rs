@@ -0,0 +1,299 @@
+use std::{env, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use tokio::time::Instant;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::{
+        token::{get_account_info, get_token_account_address},
+        tx,
+    },
+    dex::{
+        pump::{quote_curve_swap, Pump, SwapQuote},
+        pump_global::pump_fee_bps,
+    },
+    engine::{
+        position::Position,
+        projection::apply_trade,
+        swap::{sell_entire_balance, SwapDirection},
+        venues::VenueKind,
+    },
+    risk::{
+        expectancy::{record_exit, settle_position},
+        impairment::{diagnose_failed_sell, impair},
+    },
+    services::price_feed::CurveReserves,
+};
+
+// Configuration constants
+const DEFAULT_ATOMIC_EXIT_CHUNKS: usize = 4;
+// A bundle is five transactions, one of which is the tip
+const MAX_ATOMIC_EXIT_CHUNKS: usize = 4;
+
+/// Settings for splitting large exits into a single Jito bundle
+#[derive(Debug, Clone)]
+pub struct AtomicExitConfig {
+    /// Positions with at least this many lamports invested exit atomically
+    pub min_position_lamports: u64,
+    pub chunks: usize,
+}
+
+impl AtomicExitConfig {
+    /// Reads `ATOMIC_EXIT_MIN_SOL` and `ATOMIC_EXIT_CHUNKS`; unset threshold disables it
+    pub fn from_env() -> Option<Self> {
+        let min_sol = env::var("ATOMIC_EXIT_MIN_SOL")
+            .ok()
+            .and_then(|v| f64::from_str(&v).ok())?;
+        let chunks = env::var("ATOMIC_EXIT_CHUNKS")
+            .ok()
+            .and_then(|v| usize::from_str(&v).ok())
+            .unwrap_or(DEFAULT_ATOMIC_EXIT_CHUNKS)
+            .clamp(1, MAX_ATOMIC_EXIT_CHUNKS);
+
+        Some(Self {
+            min_position_lamports: sol_to_lamports(min_sol),
+            chunks,
+        })
+    }
+
+    /// True if a position of this size should be exited as one bundle
+    pub fn applies_to(&self, sol_invested: u64) -> bool {
+        sol_invested >= self.min_position_lamports
+    }
+}
+
+/// Splits `total` into `chunks` near-equal parts that sum back to `total`
+pub fn split_amount(total: u64, chunks: usize) -> Vec<u64> {
+    let chunks = chunks.max(1) as u64;
+    let base = total / chunks;
+    let remainder = total % chunks;
+    (0..chunks)
+        .map(|i| base + u64::from(i < remainder))
+        .filter(|amount| *amount > 0)
+        .collect()
+}
+
+/// Quotes each of `amounts` as sold in turn, every sell against the reserves the previous
+/// one leaves behind
+pub fn chunk_quotes(
+    mut reserves: CurveReserves,
+    amounts: &[u64],
+    slippage_bps: u64,
+    fee_bps: u64,
+) -> Result<Vec<SwapQuote>> {
+    amounts
+        .iter()
+        .map(|&amount| {
+            let quote =
+                quote_curve_swap(reserves, amount, &SwapDirection::Sell, slippage_bps, fee_bps)?;
+            reserves = apply_trade(reserves, &SwapDirection::Sell, amount);
+            Ok(quote)
+        })
+        .collect()
+}
+
+/// Sells the wallet's whole balance of `token_amount` on the bonding curve as several sells
+/// landed together in one bundle, the last of which closes the emptied token account. Returns
+/// the sells' signatures
+pub async fn atomic_exit(
+    state: AppState,
+    mint: &str,
+    token_amount: u64,
+    slippage: u64,
+    config: &AtomicExitConfig,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<Vec<String>> {
+    state
+        .router
+        .venues
+        .check(VenueKind::PumpFun, &SwapDirection::Sell)?;
+    let pump = Pump::new(
+        state.rpc_nonblocking_client.clone(),
+        state.rpc_client.clone(),
+        state.wallet.clone(),
+    );
+
+    let amounts = split_amount(token_amount, config.chunks);
+    if amounts.is_empty() {
+        return Err(anyhow!("Nothing to sell for {}", mint));
+    }
+
+    // One read of the curve; the bundle's sells land in order, each moving it for the next
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let (accounts, reserves) = pump.curve_state(mint)?;
+    let quotes = chunk_quotes(reserves, &amounts, slippage, pump_fee_bps())?;
+    let mut instruction_sets = quotes
+        .iter()
+        .map(|quote| pump.swap_instructions(&mint_pubkey, &accounts, &SwapDirection::Sell, quote))
+        .collect::<Vec<_>>();
+    let owner = state.wallet.pubkey();
+    let ata = get_associated_token_address(&owner, &mint_pubkey);
+    if let Some(last) = instruction_sets.last_mut() {
+        last.push(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &ata,
+            &owner,
+            &owner,
+            &[],
+        )?);
+    }
+
+    let _ = log_message(&format!(
+        "Atomic exit of {} {} in {} bundled sells",
+        token_amount,
+        mint,
+        instruction_sets.len()
+    ))
+    .await;
+
+    Ok(tx::send_bundle_only(
+        &state.rpc_client,
+        &state.wallet,
+        instruction_sets,
+        jito_client,
+        None,
+    )
+    .await?)
+}
+
+/// Market-sells a whole position from every wallet holding it and books its proceeds and
+/// realised PnL; the position stays open if any wallet fails to sell. Impaired positions are
+/// not retried
+pub async fn market_exit(
+    state: &AppState,
+    position: &Position,
+    slippage: u64,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<()> {
+    if let Some(impairment) = position.impaired {
+        return Err(anyhow!("{} cannot be sold: {}", position.mint, impairment));
+    }
+    let mut failed = None;
+    for wallet in state.wallets.holders(Some(position)) {
+        let leg = state.with_wallet(wallet);
+        let sold = sell_entire_balance(
+            leg.clone(),
+            &position.mint,
+            position.pool_id.clone(),
+            slippage,
+            jito_client.clone(),
+            Instant::now(),
+        )
+        .await;
+        match sold {
+            Ok(signatures) => record_exit(&leg, &position.mint, &signatures).await,
+            Err(e) => {
+                if let Some(impairment) = diagnose_failed_sell(&leg, &position.mint, &e).await {
+                    impair(state, &position.mint, impairment).await;
+                }
+                failed = Some(e);
+            }
+        }
+    }
+    if let Some(e) = failed {
+        return Err(e);
+    }
+    settle_position(state, &position.mint).await;
+    Ok(())
+}
+
+/// Raw tokens to sell for `pct` percent of `balance`
+pub fn share_of(balance: u64, pct: f64) -> u64 {
+    (balance as f64 * pct.clamp(0.0, 100.0) / 100.0) as u64
+}
+
+/// Market-sells `pct` percent of a position from every wallet holding it and books the
+/// proceeds; the position stays open. Use `market_exit` to sell all of it
+pub async fn partial_exit(
+    state: &AppState,
+    position: &Position,
+    pct: f64,
+    slippage: u64,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<()> {
+    if let Some(impairment) = position.impaired {
+        return Err(anyhow!("{} cannot be sold: {}", position.mint, impairment));
+    }
+    let mint = Pubkey::from_str(&position.mint)?;
+    let mut failed = None;
+    for wallet in state.wallets.holders(Some(position)) {
+        let leg = state.with_wallet(wallet);
+        let client = &leg.rpc_nonblocking_client;
+        let ata = get_token_account_address(client, &leg.wallet.pubkey(), &mint).await?;
+        let balance = get_account_info(client.clone(), &mint, &ata)
+            .await?
+            .base
+            .amount;
+        let amount = share_of(balance, pct);
+        if amount == 0 {
+            continue;
+        }
+        let sold = state
+            .router
+            .swap(
+                leg.clone(),
+                &position.mint,
+                amount,
+                SwapDirection::Sell,
+                slippage,
+                jito_client.clone(),
+                Instant::now(),
+            )
+            .await;
+        match sold {
+            Ok(signatures) => record_exit(&leg, &position.mint, &signatures).await,
+            Err(e) => failed = Some(e),
+        }
+    }
+    match failed {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_amount_sums_to_total() {
+        let parts = split_amount(1_000_003, 4);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts.iter().sum::<u64>(), 1_000_003);
+        assert_eq!(parts, vec![250_001, 250_001, 250_001, 250_000]);
+    }
+
+    #[test]
+    fn test_split_amount_drops_empty_chunks() {
+        assert_eq!(split_amount(2, 4), vec![1, 1]);
+        assert!(split_amount(0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_later_chunks_quote_against_moved_curve() {
+        let reserves = CurveReserves {
+            virtual_sol_reserves: 60_000_000_000,
+            virtual_token_reserves: 500_000_000_000_000,
+        };
+        let amounts = split_amount(100_000_000_000_000, 4);
+        let quotes = chunk_quotes(reserves, &amounts, 100, 100).unwrap();
+        assert!(quotes.windows(2).all(|w| w[1].expected_out < w[0].expected_out));
+
+        // Together the chunks fetch what one sell of the lot would
+        let whole = quote_curve_swap(reserves, 100_000_000_000_000, &SwapDirection::Sell, 100, 100)
+            .unwrap();
+        let total = quotes.iter().map(|q| q.expected_out).sum::<u64>();
+        assert!(total.abs_diff(whole.expected_out) <= amounts.len() as u64);
+    }
+
+    #[test]
+    fn test_share_of_balance() {
+        assert_eq!(share_of(1_000, 25.0), 250);
+        assert_eq!(share_of(1_000, 150.0), 1_000);
+        assert_eq!(share_of(1_000, -5.0), 0);
+    }
+}
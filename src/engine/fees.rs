@@ -0,0 +1,184 @@
+use std::{env, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::native_token::sol_to_lamports;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::tx::TxConfig,
+    dex::pump_global::pump_fee_bps,
+    engine::{position::Position, router::Venue},
+    services::notify::Event,
+};
+
+// Configuration constants
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+const DEFAULT_TIP_SOL: f64 = 0.0001;
+const PUMP_SWAP_FEE_BPS: u64 = 25;
+const RAYDIUM_FEE_BPS: u64 = 25;
+// CLMM fee tiers vary per pool; most SOL memecoin pools sit in the 0.25% tier
+const RAYDIUM_CLMM_FEE_BPS: u64 = 25;
+// Graduated pump.fun pools land in the 0.25% CPMM config
+const RAYDIUM_CPMM_FEE_BPS: u64 = 25;
+const DEFAULT_JUPITER_FEE_BPS: u64 = 25;
+
+/// Costs paid on every swap, used to work out what a position must sell for to break even
+#[derive(Debug, Clone)]
+pub struct FeeModel {
+    /// Base signature fee plus compute-unit priority fee, per transaction
+    pub network_fee_lamports: u64,
+    /// Jito tip paid per transaction
+    pub tip_lamports: u64,
+    /// Assumed route fee for Jupiter swaps, whose pools vary
+    pub jupiter_fee_bps: u64,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self {
+            network_fee_lamports: SIGNATURE_FEE_LAMPORTS,
+            tip_lamports: sol_to_lamports(DEFAULT_TIP_SOL),
+            jupiter_fee_bps: DEFAULT_JUPITER_FEE_BPS,
+        }
+    }
+}
+
+impl FeeModel {
+    /// Derives the network fee from `UNIT_PRICE`/`UNIT_LIMIT` and reads `EXPECTED_TIP_SOL`
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let tx_config = TxConfig::default();
+        // Unit price is in micro-lamports per compute unit
+        let priority_fee = tx_config
+            .unit_price
+            .saturating_mul(tx_config.unit_limit as u64)
+            / 1_000_000;
+
+        Self {
+            network_fee_lamports: SIGNATURE_FEE_LAMPORTS + priority_fee,
+            tip_lamports: env::var("EXPECTED_TIP_SOL")
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .map(sol_to_lamports)
+                .unwrap_or(default.tip_lamports),
+            jupiter_fee_bps: env::var("JUPITER_FEE_BPS")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .unwrap_or(default.jupiter_fee_bps),
+        }
+    }
+
+    /// Fixed lamports paid to land one swap transaction
+    pub fn tx_cost(&self) -> u64 {
+        self.network_fee_lamports + self.tip_lamports
+    }
+
+    /// Trading fee the venue takes from each side of a swap
+    pub fn venue_fee_bps(&self, venue: &Venue) -> u64 {
+        match venue {
+            Venue::BondingCurve => pump_fee_bps(),
+            Venue::PumpSwap { .. } => PUMP_SWAP_FEE_BPS,
+            Venue::Raydium { .. } => RAYDIUM_FEE_BPS,
+            Venue::RaydiumClmm { .. } => RAYDIUM_CLMM_FEE_BPS,
+            Venue::RaydiumCpmm { .. } => RAYDIUM_CPMM_FEE_BPS,
+            Venue::Jupiter => self.jupiter_fee_bps,
+        }
+    }
+
+    /// Price (SOL per token) at which selling the tokens the position's fills hold recovers
+    /// its full cost, after the exit's venue fee and transaction cost
+    pub fn breakeven_price(&self, position: &Position, venue: &Venue) -> Option<f64> {
+        let tokens_held = position.tokens_held();
+        if tokens_held <= 0.0 {
+            return None;
+        }
+        let cost = (position.sol_invested + position.fees_paid + self.tx_cost())
+            .saturating_sub(position.sol_returned);
+        let exit_keep = 1.0 - self.venue_fee_bps(venue) as f64 / 10_000.0;
+        Some(cost as f64 / 1e9 / (tokens_held * exit_keep))
+    }
+}
+
+/// Breakeven price of an open position, from the tokens its fills bought and sold
+pub async fn position_breakeven(state: &AppState, position: &Position) -> Result<f64> {
+    let venue = state.router.route(state, &position.mint).await?;
+    state
+        .fees
+        .breakeven_price(position, &venue)
+        .ok_or_else(|| anyhow!("No {} tokens held", position.mint))
+}
+
+/// Logs and notifies a position's fee-aware breakeven after an entry
+pub async fn report_breakeven(state: &AppState, mint: &str) {
+    let Some(position) = state.positions.get(mint).await else {
+        return;
+    };
+    let message = match position_breakeven(state, &position).await {
+        Ok(price) => format!("{} breakeven incl. fees: {:.10} SOL", mint, price),
+        Err(e) => format!("Could not compute breakeven for {}: {}", mint, e),
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::storage::EventTime,
+        engine::position::{Fill, FillSide},
+    };
+    use chrono::Utc;
+
+    fn position(sol_invested: u64, fees_paid: u64, sol_returned: u64, tokens: f64) -> Position {
+        Position {
+            mint: "mint".to_string(),
+            pool_id: None,
+            sol_invested,
+            sol_returned,
+            fees_paid,
+            opened_at: Utc::now(),
+            max_hold_secs: None,
+            creator: None,
+            wallets: vec![],
+            target: None,
+            signal: None,
+            fills: vec![Fill {
+                at: Utc::now(),
+                side: FillSide::Buy,
+                lamports: sol_invested,
+                fees: fees_paid,
+                tokens,
+                time: EventTime::default(),
+            }],
+            impaired: None,
+        }
+    }
+
+    #[test]
+    fn test_breakeven_includes_entry_and_exit_costs() {
+        let model = FeeModel {
+            network_fee_lamports: 5_000,
+            tip_lamports: 95_000,
+            jupiter_fee_bps: 0,
+        };
+        // 1 SOL in, 0.0001 SOL entry fees, 0.0001 SOL exit tx cost, 1% curve fee on exit
+        let price = model
+            .breakeven_price(
+                &position(1_000_000_000, 100_000, 0, 1_000_000.0),
+                &Venue::BondingCurve,
+            )
+            .unwrap();
+        let expected = 1.0002 / (1_000_000.0 * 0.99);
+        assert!((price - expected).abs() < 1e-15);
+        assert!(price > 1.0 / 1_000_000.0);
+    }
+
+    #[test]
+    fn test_breakeven_needs_tokens() {
+        let model = FeeModel::default();
+        assert!(model
+            .breakeven_price(&position(1, 0, 0, 0.0), &Venue::Jupiter)
+            .is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use std::{env, sync::Arc};
+
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, Utc};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use tokio::time::sleep;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::exit::market_exit,
+};
+
+const FLATTEN_SLIPPAGE_BPS: u64 = 2_500;
+
+/// Daily UTC time at which every open position is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlattenSchedule {
+    pub at: NaiveTime,
+}
+
+impl FlattenSchedule {
+    /// Reads `FLATTEN_AT_UTC` ("HH:MM"); unset means the feature is off
+    pub fn from_env() -> Option<Self> {
+        let value = env::var("FLATTEN_AT_UTC").ok()?;
+        NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .ok()
+            .map(|at| Self { at })
+    }
+
+    /// Next time strictly after `now` at which the flatten should run
+    pub fn next_run(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive().and_time(self.at).and_utc();
+        if today > now {
+            today
+        } else {
+            today + ChronoDuration::days(1)
+        }
+    }
+}
+
+/// Outcome of a flatten run
+#[derive(Debug, Default)]
+pub struct FlattenReport {
+    pub closed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+    pub cancelled_intents: usize,
+}
+
+impl FlattenReport {
+    pub fn summary(&self) -> String {
+        self.summary_as("End-of-day flatten")
+    }
+
+    /// The report under another heading, for flattens not run by the schedule
+    pub fn summary_as(&self, title: &str) -> String {
+        let mut summary = format!(
+            "{}: {} closed, {} failed, {} pending intents cancelled",
+            title,
+            self.closed.len(),
+            self.failed.len(),
+            self.cancelled_intents
+        );
+        for mint in &self.closed {
+            summary.push_str(&format!("\n  closed {}", mint));
+        }
+        for (mint, err) in &self.failed {
+            summary.push_str(&format!("\n  failed {}: {}", mint, err));
+        }
+        summary
+    }
+}
+
+/// Cancels pending intents and market-sells every open position
+pub async fn flatten_all(state: AppState, jito_client: Arc<JitoRpcClient>) -> FlattenReport {
+    let mut report = FlattenReport {
+        cancelled_intents: state.positions.cancel_all_intents().await,
+        ..Default::default()
+    };
+
+    for position in state.positions.open_positions().await {
+        match market_exit(&state, &position, FLATTEN_SLIPPAGE_BPS, jito_client.clone()).await {
+            Ok(()) => report.closed.push(position.mint),
+            Err(e) => report.failed.push((position.mint, e.to_string())),
+        }
+    }
+
+    report
+}
+
+/// Sleeps until each scheduled time and flattens, forever
+pub async fn run_flatten_schedule(
+    schedule: FlattenSchedule,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    loop {
+        let now = Utc::now();
+        let wait = (schedule.next_run(now) - now).to_std().unwrap_or_default();
+        sleep(wait).await;
+
+        let report = flatten_all(state.clone(), jito_client.clone()).await;
+        let _ = log_message(&report.summary()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_run_later_today() {
+        let schedule = FlattenSchedule {
+            at: NaiveTime::from_hms_opt(23, 30, 0).unwrap(),
+        };
+        let now = Utc.with_ymd_and_hms(2025, 2, 10, 8, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_run(now),
+            Utc.with_ymd_and_hms(2025, 2, 10, 23, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_next_run_rolls_to_tomorrow() {
+        let schedule = FlattenSchedule {
+            at: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+        };
+        let now = Utc.with_ymd_and_hms(2025, 2, 10, 6, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_run(now),
+            Utc.with_ymd_and_hms(2025, 2, 11, 6, 0, 0).unwrap()
+        );
+    }
+}
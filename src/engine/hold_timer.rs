@@ -0,0 +1,225 @@
+use std::{collections::HashMap, env, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::{exit::market_exit, position::Position},
+    risk::impairment::{impair, Impairment},
+    services::{idle::idle_sleep, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_HOLD_CHECK_SECS: u64 = 5;
+const HOLD_EXIT_SLIPPAGE_BPS: u64 = 2_500;
+const DEFAULT_HOLD_EXIT_ATTEMPTS: u32 = 5;
+const MAX_HOLD_RETRY_SECS: u64 = 300;
+
+/// Parses "90", "90s", "15m" or "2h" into seconds
+pub fn parse_hold_duration(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 3_600),
+        _ => (value, 1),
+    };
+    let amount =
+        u64::from_str(digits.trim()).map_err(|_| anyhow!("Invalid hold time '{}'", value))?;
+    Ok(amount.saturating_mul(multiplier))
+}
+
+/// Market-sells positions held longer than their max hold time
+#[derive(Debug, Clone)]
+pub struct HoldTimer {
+    /// Applied to positions without their own limit; `None` leaves them untimed
+    pub default_max_hold_secs: Option<u64>,
+    pub check_interval: Duration,
+    /// Failed exits in a row before the position is handed to the impairment path
+    pub max_exit_attempts: u32,
+}
+
+impl HoldTimer {
+    /// Reads `MAX_HOLD_TIME` (e.g. "10m"), `HOLD_CHECK_SECS` and `HOLD_EXIT_MAX_ATTEMPTS`
+    pub fn from_env() -> Result<Self> {
+        let default_max_hold_secs = match env::var("MAX_HOLD_TIME") {
+            Ok(value) => Some(parse_hold_duration(&value)?),
+            Err(_) => None,
+        };
+        let check_secs = env::var("HOLD_CHECK_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_HOLD_CHECK_SECS)
+            .max(1);
+        let max_exit_attempts = env::var("HOLD_EXIT_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| u32::from_str(&v).ok())
+            .unwrap_or(DEFAULT_HOLD_EXIT_ATTEMPTS)
+            .max(1);
+        Ok(Self {
+            default_max_hold_secs,
+            check_interval: Duration::from_secs(check_secs),
+            max_exit_attempts,
+        })
+    }
+
+    /// True once the position has been open for at least its max hold time
+    pub fn is_expired(&self, position: &Position, now: DateTime<Utc>) -> bool {
+        let Some(max_hold) = position.max_hold_secs.or(self.default_max_hold_secs) else {
+            return false;
+        };
+        (now - position.opened_at).num_seconds() >= max_hold as i64
+    }
+}
+
+/// Failed exits of expired positions, so retries back off instead of hammering every check
+#[derive(Debug, Default)]
+struct ExitRetries {
+    /// Mint to failed attempts in a row and when the next one is due
+    failures: HashMap<String, (u32, DateTime<Utc>)>,
+}
+
+impl ExitRetries {
+    fn is_due(&self, mint: &str, now: DateTime<Utc>) -> bool {
+        self.failures.get(mint).is_none_or(|(_, next)| *next <= now)
+    }
+
+    /// Records a failed attempt, doubling the wait from `base` up to `MAX_HOLD_RETRY_SECS`;
+    /// returns how many attempts have failed in a row
+    fn fail(&mut self, mint: &str, base: Duration, now: DateTime<Utc>) -> u32 {
+        let attempts = self.failures.get(mint).map_or(0, |(attempts, _)| *attempts) + 1;
+        let delay = base
+            .saturating_mul(1 << (attempts - 1).min(16))
+            .min(Duration::from_secs(MAX_HOLD_RETRY_SECS));
+        let next = now + ChronoDuration::seconds(delay.as_secs() as i64);
+        self.failures.insert(mint.to_string(), (attempts, next));
+        attempts
+    }
+
+    fn clear(&mut self, mint: &str) {
+        self.failures.remove(mint);
+    }
+}
+
+/// Sells a position whose hold time ran out and settles it
+async fn expire_position(
+    state: &AppState,
+    position: &Position,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<()> {
+    let held = (Utc::now() - position.opened_at).num_seconds();
+    market_exit(state, position, HOLD_EXIT_SLIPPAGE_BPS, jito_client).await?;
+    let message = format!(
+        "⏱ Max hold time reached for {} after {}s, sold",
+        position.mint, held
+    );
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+    Ok(())
+}
+
+/// Checks open positions every interval and exits the expired ones, forever. A position whose
+/// exit keeps failing is retried with backoff, then handed to the impairment path
+pub async fn run_hold_timer(timer: HoldTimer, state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let mut retries = ExitRetries::default();
+    loop {
+        idle_sleep(timer.check_interval).await;
+
+        let now = Utc::now();
+        let open = state.positions.open_positions().await;
+        retries
+            .failures
+            .retain(|mint, _| open.iter().any(|position| &position.mint == mint));
+        for position in open {
+            if !timer.is_expired(&position, now) || !retries.is_due(&position.mint, now) {
+                continue;
+            }
+            let Err(e) = expire_position(&state, &position, jito_client.clone()).await else {
+                retries.clear(&position.mint);
+                continue;
+            };
+            let attempts = retries.fail(&position.mint, timer.check_interval, now);
+            let _ = log_message(&format!(
+                "Max hold exit for {} failed (attempt {} of {}): {}",
+                position.mint, attempts, timer.max_exit_attempts, e
+            ))
+            .await;
+            if attempts >= timer.max_exit_attempts {
+                retries.clear(&position.mint);
+                impair(&state, &position.mint, Impairment::ExitsFailing).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(opened_at: DateTime<Utc>, max_hold_secs: Option<u64>) -> Position {
+        Position {
+            mint: "mint".to_string(),
+            pool_id: None,
+            sol_invested: 1_000_000,
+            sol_returned: 0,
+            fees_paid: 0,
+            opened_at,
+            max_hold_secs,
+            creator: None,
+            wallets: vec![],
+            target: None,
+            signal: None,
+            fills: vec![],
+            impaired: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_hold_duration() {
+        assert_eq!(parse_hold_duration("90").unwrap(), 90);
+        assert_eq!(parse_hold_duration("45s").unwrap(), 45);
+        assert_eq!(parse_hold_duration("15m").unwrap(), 900);
+        assert_eq!(parse_hold_duration("2h").unwrap(), 7_200);
+        assert!(parse_hold_duration("soon").is_err());
+    }
+
+    #[test]
+    fn test_position_limit_overrides_default() {
+        let timer = HoldTimer {
+            default_max_hold_secs: Some(600),
+            check_interval: Duration::from_secs(5),
+            max_exit_attempts: 5,
+        };
+        let now = Utc::now();
+        let opened = now - ChronoDuration::seconds(120);
+        assert!(!timer.is_expired(&position(opened, None), now));
+        assert!(timer.is_expired(&position(opened, Some(60)), now));
+
+        let untimed = HoldTimer {
+            default_max_hold_secs: None,
+            ..timer
+        };
+        assert!(!untimed.is_expired(&position(opened, None), now));
+    }
+
+    #[test]
+    fn test_exit_retries_back_off() {
+        let mut retries = ExitRetries::default();
+        let now = Utc::now();
+        let base = Duration::from_secs(5);
+        assert!(retries.is_due("mint", now));
+        assert_eq!(retries.fail("mint", base, now), 1);
+        assert!(!retries.is_due("mint", now + ChronoDuration::seconds(4)));
+        assert!(retries.is_due("mint", now + ChronoDuration::seconds(5)));
+        assert_eq!(retries.fail("mint", base, now), 2);
+        assert!(!retries.is_due("mint", now + ChronoDuration::seconds(9)));
+        for _ in 0..10 {
+            retries.fail("mint", base, now);
+        }
+        assert!(retries.is_due("mint", now + ChronoDuration::seconds(300)));
+        retries.clear("mint");
+        assert!(retries.is_due("mint", now));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{env, str::FromStr, sync::LazyLock};
+
+use anyhow::{anyhow, Result};
+
+// Configuration constants
+const DEFAULT_MAX_IMPACT_BPS: u64 = 800;
+
+/// Process-wide guard, from `MAX_PRICE_IMPACT_BPS`
+pub static PRICE_IMPACT: LazyLock<PriceImpactGuard> = LazyLock::new(PriceImpactGuard::from_env);
+
+/// How far putting `amount_in` into a constant-product pool holding `reserve_in` moves the
+/// price against it, in bps: the share of the spot-price output the trade doesn't get, fees
+/// aside. A pump.fun curve counts its virtual SOL reserves
+pub fn price_impact_bps(reserve_in: u64, amount_in: u64) -> u64 {
+    let total = reserve_in as u128 + amount_in as u128;
+    if total == 0 {
+        return 0;
+    }
+    (amount_in as u128 * 10_000 / total) as u64
+}
+
+/// Refuses buys that would move a pool's price too far. Unlike slippage, which tolerates the
+/// pool moving before our swap lands, this is about the move our own swap makes: a copy sized
+/// for a whale on a thin curve is blocked rather than filled at a ruinous price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceImpactGuard {
+    pub max_bps: u64,
+}
+
+impl PriceImpactGuard {
+    /// Reads `MAX_PRICE_IMPACT_BPS`, default 800 (8%); 10000 turns the guard off
+    pub fn from_env() -> Self {
+        Self {
+            max_bps: env::var("MAX_PRICE_IMPACT_BPS")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .unwrap_or(DEFAULT_MAX_IMPACT_BPS),
+        }
+    }
+
+    /// Errors if buying with `amount_in` against `reserve_in` exceeds the limit
+    pub fn check(&self, reserve_in: u64, amount_in: u64) -> Result<()> {
+        let impact = price_impact_bps(reserve_in, amount_in);
+        if impact > self.max_bps {
+            return Err(anyhow!(
+                "Price impact {:.2}% exceeds the {:.2}% limit",
+                impact as f64 / 100.0,
+                self.max_bps as f64 / 100.0
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::projection::quote_buy, services::price_feed::CurveReserves};
+
+    #[test]
+    fn test_blocks_whale_sized_buys_on_thin_curves() {
+        let reserves = CurveReserves {
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+        };
+        // Impact matches the curve's own quote against the spot price
+        let sol_in = 3_000_000_000;
+        let at_spot = sol_in as u128 * reserves.virtual_token_reserves as u128
+            / reserves.virtual_sol_reserves as u128;
+        let lost = 10_000 - quote_buy(reserves, sol_in, 0) as u128 * 10_000 / at_spot;
+        let impact = price_impact_bps(reserves.virtual_sol_reserves, sol_in) as u128;
+        assert!(impact.abs_diff(lost) <= 1);
+
+        let guard = PriceImpactGuard { max_bps: 800 };
+        assert!(guard
+            .check(reserves.virtual_sol_reserves, 1_000_000_000)
+            .is_ok());
+        assert!(guard.check(reserves.virtual_sol_reserves, sol_in).is_err());
+        assert!(PriceImpactGuard { max_bps: 10_000 }
+            .check(1, u64::MAX)
+            .is_ok());
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::LazyLock;
+
+use borsh::BorshDeserialize;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    dex::jupiter::JUPITER_PROGRAM_ID,
+    engine::{
+        signal::{account_index, flat_instructions, token_balance, TradeSignal, WSOL_MINT},
+        swap::SwapDirection,
+    },
+};
+
+// Configuration constants
+/// Prefix of the self-invoked instructions Anchor programs log events through
+const EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+/// Anchor discriminator of Jupiter's `SwapEvent`, emitted once per hop of a route
+const SWAP_EVENT: [u8; 8] = [64, 198, 205, 232, 38, 8, 113, 226];
+/// Jupiter's program id as it appears in `programId` fields, rendered once
+static JUPITER_PROGRAM: LazyLock<String> = LazyLock::new(|| JUPITER_PROGRAM_ID.to_string());
+
+/// One hop of a Jupiter route
+#[derive(Debug, Clone, PartialEq, borsh_derive::BorshDeserialize)]
+pub struct SwapEvent {
+    pub amm: [u8; 32],
+    pub input_mint: [u8; 32],
+    pub input_amount: u64,
+    pub output_mint: [u8; 32],
+    pub output_amount: u64,
+}
+
+/// Returns true if any instruction, top-level or inner, invokes Jupiter
+pub fn invokes_jupiter(tx: &Value) -> bool {
+    flat_instructions(tx)
+        .iter()
+        .any(|(ix, _)| ix["programId"].as_str() == Some(JUPITER_PROGRAM.as_str()))
+}
+
+/// Every hop Jupiter logged in `tx`, in order, from the event instructions it invokes on itself
+pub fn swap_events(tx: &Value) -> Vec<SwapEvent> {
+    flat_instructions(tx)
+        .into_iter()
+        .filter(|(ix, _)| ix["programId"].as_str() == Some(JUPITER_PROGRAM.as_str()))
+        .filter_map(|(ix, _)| bs58::decode(ix["data"].as_str()?).into_vec().ok())
+        .filter_map(|data| {
+            let event = data
+                .strip_prefix(&EVENT_IX_TAG)?
+                .strip_prefix(&SWAP_EVENT)?;
+            SwapEvent::deserialize(&mut &event[..]).ok()
+        })
+        .collect()
+}
+
+/// Decodes the target's trade from a Jupiter route in a `transactionSubscribe` notification.
+/// The hops' swap events are netted so split and multi-hop routes read as one trade against
+/// SOL, and the target's own token balance has to have moved the same way, since the events
+/// don't name who routed. Token-to-token routes are not signals
+pub fn parse_jupiter_trade(json: &Value, target: &str) -> Option<TradeSignal> {
+    let result = &json["params"]["result"];
+    let tx = &result["transaction"];
+    let meta = &tx["meta"];
+    if !meta["err"].is_null() {
+        return None;
+    }
+    account_index(tx, target)?;
+    let mut net: Vec<(String, i128)> = Vec::new();
+    for event in swap_events(tx) {
+        for (mint, delta) in [
+            (event.input_mint, -(event.input_amount as i128)),
+            (event.output_mint, event.output_amount as i128),
+        ] {
+            let mint = Pubkey::new_from_array(mint).to_string();
+            match net.iter_mut().find(|(seen, _)| *seen == mint) {
+                Some((_, total)) => *total += delta,
+                None => net.push((mint, delta)),
+            }
+        }
+    }
+    let sol = net
+        .iter()
+        .find(|(mint, _)| mint == WSOL_MINT)
+        .map(|(_, sol)| *sol)
+        .filter(|sol| *sol != 0)?;
+    let (mint, tokens) = net
+        .iter()
+        .find(|(mint, tokens)| mint != WSOL_MINT && tokens.signum() == -sol.signum())?;
+
+    let (pre_tokens, pre_decimals) = token_balance(&meta["preTokenBalances"], target, mint);
+    let (post_tokens, post_decimals) = token_balance(&meta["postTokenBalances"], target, mint);
+    let direction = if sol < 0 {
+        if post_tokens <= pre_tokens {
+            return None;
+        }
+        SwapDirection::Buy
+    } else {
+        if pre_tokens <= post_tokens {
+            return None;
+        }
+        SwapDirection::Sell
+    };
+    Some(TradeSignal {
+        signature: result["signature"].as_str().unwrap_or_default().to_string(),
+        slot: result["slot"].as_u64().unwrap_or_default(),
+        mint: mint.clone(),
+        direction,
+        sol_amount: sol.unsigned_abs() as u64,
+        token_amount: tokens.unsigned_abs() as u64,
+        token_pre_balance: pre_tokens,
+        decimals: pre_decimals.max(post_decimals),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn event_ix(input: Pubkey, input_amount: u64, output: Pubkey, output_amount: u64) -> Value {
+        let mut data = [EVENT_IX_TAG, SWAP_EVENT].concat();
+        data.extend([9; 32]);
+        data.extend(input.to_bytes());
+        data.extend(input_amount.to_le_bytes());
+        data.extend(output.to_bytes());
+        data.extend(output_amount.to_le_bytes());
+        json!({
+            "programId": *JUPITER_PROGRAM,
+            "accounts": [],
+            "data": bs58::encode(data).into_string(),
+            "stackHeight": 2
+        })
+    }
+
+    #[test]
+    fn test_nets_a_two_hop_sell_route() {
+        let wsol = Pubkey::from_str(WSOL_MINT).unwrap();
+        let (usdc, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let json = json!({ "params": { "result": {
+            "signature": "sig",
+            "slot": 9,
+            "transaction": {
+                "transaction": { "message": {
+                    "accountKeys": [{ "pubkey": "target" }],
+                    "instructions": [{ "programId": *JUPITER_PROGRAM, "accounts": [], "data": "" }]
+                }},
+                "meta": {
+                    "err": null,
+                    "innerInstructions": [{ "index": 0, "instructions": [
+                        event_ix(mint, 4_000, usdc, 300_000_000),
+                        event_ix(usdc, 300_000_000, wsol, 2_000_000_000),
+                    ]}],
+                    "preTokenBalances": [{ "owner": "target", "mint": mint.to_string(),
+                        "uiTokenAmount": { "amount": "10000", "decimals": 6 } }],
+                    "postTokenBalances": [{ "owner": "target", "mint": mint.to_string(),
+                        "uiTokenAmount": { "amount": "6000", "decimals": 6 } }]
+                }
+            }
+        }}});
+
+        let signal = parse_jupiter_trade(&json, "target").unwrap();
+        assert_eq!(signal.mint, mint.to_string());
+        assert!(matches!(signal.direction, SwapDirection::Sell));
+        assert_eq!(signal.sol_amount, 2_000_000_000);
+        assert_eq!(signal.token_amount, 4_000);
+        assert_eq!(signal.mirrored_sell_amount(100), 40);
+        assert!(invokes_jupiter(&json["params"]["result"]["transaction"]));
+
+        // Someone else's route in a transaction the target only signed
+        let mut json = json;
+        json["params"]["result"]["transaction"]["meta"]["postTokenBalances"][0]["uiTokenAmount"]
+            ["amount"] = json!("10000");
+        assert!(parse_jupiter_trade(&json, "target").is_none());
+    }
+}
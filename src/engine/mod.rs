@@ -1 +1,35 @@
+pub mod approval;
+pub mod backtest;
+pub mod copy_timing;
+pub mod creator_exit;
+pub mod dual_control;
+pub mod execution;
+pub mod executor;
+pub mod exit;
+pub mod fees;
+pub mod flatten;
+pub mod hold_timer;
+pub mod impact;
+pub mod jupiter_signal;
+pub mod momentum;
+pub mod multi_hop;
+pub mod panic;
+pub mod position;
+pub mod projection;
+pub mod raydium_signal;
+pub mod recovery;
+pub mod remnants;
+pub mod rent_reclaim;
+pub mod reorg;
+pub mod router;
+pub mod shutdown;
+pub mod signal;
+pub mod sizing;
+pub mod slippage;
+pub mod sniper;
+pub mod stop_loss;
 pub mod swap;
+pub mod target_exit;
+pub mod venues;
+pub mod wallets;
+pub mod watchlist;
@@ -0,0 +1,268 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::native_token::sol_to_lamports;
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{interval, Instant},
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::{exit::market_exit, router::Venue},
+    services::{notify::Event, price_feed::CurveDelta},
+};
+
+// Configuration constants
+const DEFAULT_CHECK_SECS: u64 = 5;
+const MOMENTUM_EXIT_SLIPPAGE_BPS: u64 = 2_500;
+
+/// Exit a curve position once everyone else's net buying over a window fades below a floor
+#[derive(Debug, Clone, PartialEq)]
+pub struct MomentumExit {
+    pub window: Duration,
+    /// Net lamports that must have entered the curve over the window to keep holding
+    pub min_net_lamports: i64,
+}
+
+impl FromStr for MomentumExit {
+    type Err = anyhow::Error;
+
+    /// Parses `<window secs>:<min net SOL>`, e.g. `60:0` exits after a minute of net selling
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (window, min_net_sol) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid momentum exit: '{}'. Use <secs>:<min net sol>", s))?;
+        let window = u64::from_str(window)?;
+        if window == 0 {
+            return Err(anyhow!("Momentum exit window must be positive: '{}'", s));
+        }
+        let min_net_sol = f64::from_str(min_net_sol)?;
+        let min_net_lamports = sol_to_lamports(min_net_sol.abs()) as i64;
+        Ok(Self {
+            window: Duration::from_secs(window),
+            min_net_lamports: if min_net_sol < 0.0 {
+                -min_net_lamports
+            } else {
+                min_net_lamports
+            },
+        })
+    }
+}
+
+impl MomentumExit {
+    /// Reads `MOMENTUM_EXIT`; unset means the rule is off
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("MOMENTUM_EXIT") {
+            Ok(value) => Self::from_str(&value).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// True once a mint has been tracked for a full window and its net flow is below the floor
+    pub fn is_fading(&self, tracker: &FlowTracker, mint: &str, now: Instant) -> bool {
+        let Some(since) = tracker.tracked_since(mint) else {
+            return false;
+        };
+        now.duration_since(since) >= self.window
+            && tracker.net_flow(mint, now, self.window) < self.min_net_lamports
+    }
+}
+
+/// Rolling record of curve deltas for the mints we hold
+#[derive(Debug, Default)]
+pub struct FlowTracker {
+    tracked: HashMap<String, Instant>,
+    flows: HashMap<String, VecDeque<(Instant, i64)>>,
+}
+
+impl FlowTracker {
+    pub fn track(&mut self, mint: &str, now: Instant) {
+        self.tracked.entry(mint.to_string()).or_insert(now);
+    }
+
+    pub fn forget(&mut self, mint: &str) {
+        self.tracked.remove(mint);
+        self.flows.remove(mint);
+    }
+
+    pub fn tracked_since(&self, mint: &str) -> Option<Instant> {
+        self.tracked.get(mint).copied()
+    }
+
+    /// Records a delta for a tracked mint, dropping entries older than `window`
+    pub fn observe(&mut self, delta: &CurveDelta, window: Duration) {
+        if !self.tracked.contains_key(&delta.mint) {
+            return;
+        }
+        let flows = self.flows.entry(delta.mint.clone()).or_default();
+        flows.push_back((delta.observed_at, delta.sol_delta));
+        while flows
+            .front()
+            .is_some_and(|(at, _)| delta.observed_at.duration_since(*at) > window)
+        {
+            flows.pop_front();
+        }
+    }
+
+    /// Net lamports that entered the curve within `window` of `now`
+    pub fn net_flow(&self, mint: &str, now: Instant, window: Duration) -> i64 {
+        self.flows.get(mint).map_or(0, |flows| {
+            flows
+                .iter()
+                .filter(|(at, _)| now.duration_since(*at) <= window)
+                .map(|(_, sol_delta)| sol_delta)
+                .sum()
+        })
+    }
+}
+
+/// Starts tracking new curve positions, drops closed ones and exits those that have faded
+async fn check_positions(
+    rule: &MomentumExit,
+    state: &AppState,
+    tracker: &mut FlowTracker,
+    watched: &mut HashSet<String>,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let now = Instant::now();
+    let mut open = HashSet::new();
+    for position in state.positions.open_positions().await {
+        // Only curve positions produce deltas to judge momentum by
+        if state.router.route(state, &position.mint).await.ok() != Some(Venue::BondingCurve) {
+            continue;
+        }
+        open.insert(position.mint.clone());
+        if watched.insert(position.mint.clone()) {
+            state.price_feed.watch(&position.mint).await;
+            tracker.track(&position.mint, now);
+            continue;
+        }
+        if !rule.is_fading(tracker, &position.mint, now) {
+            continue;
+        }
+
+        let net_flow = tracker.net_flow(&position.mint, now, rule.window);
+        match market_exit(
+            state,
+            &position,
+            MOMENTUM_EXIT_SLIPPAGE_BPS,
+            jito_client.clone(),
+        )
+        .await
+        {
+            Ok(()) => {
+                let message = format!(
+                    "📉 Momentum faded on {} (net flow {} lamports over {}s), sold",
+                    position.mint,
+                    net_flow,
+                    rule.window.as_secs()
+                );
+                let _ = log_message(&message).await;
+                state.notifier.notify(Event::Info(message)).await;
+            }
+            Err(e) => {
+                let _ = log_message(&format!(
+                    "Momentum exit for {} failed, retrying next check: {}",
+                    position.mint, e
+                ))
+                .await;
+            }
+        }
+    }
+
+    for mint in watched.difference(&open) {
+        state.price_feed.unwatch(mint).await;
+        tracker.forget(mint);
+    }
+    *watched = open;
+}
+
+/// Watches curve flow on held mints and exits positions whose momentum has faded, forever
+pub async fn run_momentum_exit(
+    rule: MomentumExit,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let check_secs = env::var("MOMENTUM_CHECK_SECS")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_CHECK_SECS)
+        .max(1);
+    let mut deltas = state.price_feed.subscribe_curves();
+    let mut checks = interval(Duration::from_secs(check_secs));
+    let mut tracker = FlowTracker::default();
+    let mut watched = HashSet::new();
+
+    loop {
+        tokio::select! {
+            delta = deltas.recv() => match delta {
+                Ok(delta) => tracker.observe(&delta, rule.window),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            _ = checks.tick() => {
+                let jito_client = jito_client.clone();
+                check_positions(&rule, &state, &mut tracker, &mut watched, jito_client).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(mint: &str, sol_delta: i64, observed_at: Instant) -> CurveDelta {
+        CurveDelta {
+            mint: mint.to_string(),
+            sol_delta,
+            token_delta: -sol_delta,
+            observed_at,
+        }
+    }
+
+    #[test]
+    fn test_parse_momentum_exit() {
+        assert_eq!(
+            MomentumExit::from_str("60:0.5").unwrap(),
+            MomentumExit {
+                window: Duration::from_secs(60),
+                min_net_lamports: 500_000_000,
+            }
+        );
+        assert_eq!(
+            MomentumExit::from_str("30:-1").unwrap().min_net_lamports,
+            -1_000_000_000
+        );
+        assert!(MomentumExit::from_str("0:1").is_err());
+        assert!(MomentumExit::from_str("60").is_err());
+    }
+
+    #[test]
+    fn test_fades_after_window_of_selling() {
+        let rule = MomentumExit::from_str("60:0").unwrap();
+        let start = Instant::now();
+        let mut tracker = FlowTracker::default();
+        tracker.track("mint", start);
+
+        tracker.observe(&delta("mint", 2_000_000_000, start), rule.window);
+        tracker.observe(&delta("other", -5_000_000_000, start), rule.window);
+        // Not judged before a full window has passed
+        assert!(!rule.is_fading(&tracker, "mint", start + Duration::from_secs(30)));
+
+        let later = start + Duration::from_secs(90);
+        tracker.observe(&delta("mint", -1_000_000_000, later), rule.window);
+        assert_eq!(tracker.net_flow("mint", later, rule.window), -1_000_000_000);
+        assert!(rule.is_fading(&tracker, "mint", later));
+        assert!(!rule.is_fading(&tracker, "other", later));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{env, fmt, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::Value;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use tokio::time::Instant;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::token::get_token_account_address,
+    dex::jupiter::{Jupiter, SOL_MINT},
+    engine::{swap::SwapDirection, venues::VenueKind},
+};
+
+// Configuration constants
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+#[derive(Debug, Clone)]
+pub struct MultiHopConfig {
+    /// Mints an exit may pass through on its way to SOL
+    pub hops: Vec<String>,
+}
+
+impl MultiHopConfig {
+    /// Reads `EXIT_HOP_MINTS` as a comma separated list, USDC and USDT by default; an empty
+    /// list disables multi-hop exits
+    pub fn from_env() -> Option<Self> {
+        let hops = match env::var("EXIT_HOP_MINTS") {
+            Ok(hops) => hops
+                .split(',')
+                .map(|hop| hop.trim().to_string())
+                .filter(|hop| !hop.is_empty())
+                .collect(),
+            Err(_) => vec![USDC_MINT.to_string(), USDT_MINT.to_string()],
+        };
+        (!hops.is_empty()).then_some(Self { hops })
+    }
+}
+
+/// Path an exit takes to SOL
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitRoute {
+    Direct,
+    /// Token to the given mint, then that mint to SOL
+    Via(String),
+}
+
+impl fmt::Display for ExitRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitRoute::Direct => write!(f, "direct"),
+            ExitRoute::Via(hop) => write!(f, "via {}", hop),
+        }
+    }
+}
+
+/// A priced candidate route; `first_leg` is the quote to execute first
+#[derive(Debug, Clone)]
+pub struct RouteQuote {
+    pub route: ExitRoute,
+    pub sol_out: u64,
+    pub first_leg: Value,
+}
+
+/// The candidate that returns the most SOL
+pub fn best_route(quotes: Vec<RouteQuote>) -> Option<RouteQuote> {
+    quotes.into_iter().max_by_key(|quote| quote.sol_out)
+}
+
+async fn quote_route(
+    jupiter: &Jupiter,
+    mint: &str,
+    amount: u64,
+    slippage: u64,
+    route: ExitRoute,
+) -> Result<RouteQuote> {
+    match &route {
+        ExitRoute::Direct => {
+            let first_leg = jupiter.get_quote(mint, SOL_MINT, amount, slippage).await?;
+            Ok(RouteQuote {
+                sol_out: Jupiter::out_amount(&first_leg)?,
+                route,
+                first_leg,
+            })
+        }
+        ExitRoute::Via(hop) => {
+            let first_leg = jupiter.get_quote(mint, hop, amount, slippage).await?;
+            let hop_out = Jupiter::out_amount(&first_leg)?;
+            let second_leg = jupiter.get_quote(hop, SOL_MINT, hop_out, slippage).await?;
+            Ok(RouteQuote {
+                sol_out: Jupiter::out_amount(&second_leg)?,
+                route,
+                first_leg,
+            })
+        }
+    }
+}
+
+/// Raw balance the trading wallet holds of `mint`, zero without an account
+async fn hop_balance(state: &AppState, mint: &str) -> Result<u64> {
+    let client = &state.rpc_nonblocking_client;
+    let ata =
+        get_token_account_address(client, &state.wallet.pubkey(), &Pubkey::from_str(mint)?).await?;
+    match client.get_token_account_balance(&ata).await {
+        Ok(balance) => Ok(u64::from_str(&balance.amount)?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Sells `amount` of `mint` over whichever of the direct and two-hop Jupiter routes returns
+/// the most SOL. Only what the first leg delivered of the intermediate mint is sold on
+pub async fn multi_hop_exit(
+    state: &AppState,
+    config: &MultiHopConfig,
+    mint: &str,
+    amount: u64,
+    slippage: u64,
+    jito_client: Arc<JitoRpcClient>,
+    timestamp: Instant,
+) -> Result<Vec<String>> {
+    state
+        .router
+        .venues
+        .check(VenueKind::Jupiter, &SwapDirection::Sell)?;
+    let jupiter = Jupiter::new(
+        state.rpc_nonblocking_client.clone(),
+        state.rpc_client.clone(),
+        state.wallet.clone(),
+    )
+    .excluding(state.router.venues.excluded_dexes(&SwapDirection::Sell));
+    let routes = std::iter::once(ExitRoute::Direct)
+        .chain(config.hops.iter().cloned().map(ExitRoute::Via))
+        .filter(|route| !matches!(route, ExitRoute::Via(hop) if hop == mint));
+    let mut quotes = Vec::new();
+    for route in routes {
+        match quote_route(&jupiter, mint, amount, slippage, route.clone()).await {
+            Ok(quote) => quotes.push(quote),
+            Err(e) => {
+                let _ = log_message(&format!("No {} exit quote for {}: {}", route, mint, e)).await;
+            }
+        }
+    }
+    let best = best_route(quotes).ok_or_else(|| anyhow!("No exit route to SOL for {}", mint))?;
+    let _ = log_message(&format!(
+        "Exiting {} {} for an expected {} lamports",
+        mint, best.route, best.sol_out
+    ))
+    .await;
+
+    let ExitRoute::Via(hop) = best.route else {
+        return Ok(jupiter
+            .execute_quote(best.first_leg, jito_client, timestamp)
+            .await?);
+    };
+    let before = hop_balance(state, &hop).await?;
+    let mut signatures = jupiter
+        .execute_quote(best.first_leg, jito_client.clone(), timestamp)
+        .await?;
+    let received = hop_balance(state, &hop).await?.saturating_sub(before);
+    if received == 0 {
+        return Err(anyhow!(
+            "First leg of the {} exit via {} delivered nothing",
+            mint,
+            hop
+        ));
+    }
+    // A failed second leg strands the hop mint until the remnant sweep folds it back
+    let second_leg = jupiter
+        .get_quote(&hop, SOL_MINT, received, slippage)
+        .await
+        .with_context(|| format!("{} of {} left for the remnant sweep", received, hop))?;
+    signatures.extend(
+        jupiter
+            .execute_quote(second_leg, jito_client, timestamp)
+            .await
+            .with_context(|| format!("{} of {} left for the remnant sweep", received, hop))?,
+    );
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_best_route() {
+        let quote = |route, out: &str| RouteQuote {
+            sol_out: Jupiter::out_amount(&json!({ "outAmount": out })).unwrap(),
+            route,
+            first_leg: Value::Null,
+        };
+        let best = best_route(vec![
+            quote(ExitRoute::Direct, "1000"),
+            quote(ExitRoute::Via(USDC_MINT.to_string()), "5000"),
+            quote(ExitRoute::Via(USDT_MINT.to_string()), "4000"),
+        ])
+        .unwrap();
+        assert_eq!(best.route, ExitRoute::Via(USDC_MINT.to_string()));
+        assert_eq!(best.sol_out, 5000);
+        assert!(best_route(vec![]).is_none());
+        assert!(Jupiter::out_amount(&json!({})).is_err());
+    }
+}
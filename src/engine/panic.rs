@@ -0,0 +1,412 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::future::join_all;
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_client::nonce_utils::nonblocking::{data_from_account, get_account_with_commitment};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use spl_associated_token_account::get_associated_token_address;
+use tokio::time::{sleep, Instant};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::tx::{self, budgeted_instructions, TxConfig},
+    dex::pump::Pump,
+    engine::{
+        execution::{find_execution, Execution},
+        exit::market_exit,
+        flatten::FlattenReport,
+        router::Venue,
+        swap::SwapDirection,
+    },
+    risk::expectancy::settle_position,
+    services::blockhash::BLOCKHASH_CACHE,
+};
+
+// Configuration constants
+const DEFAULT_PANIC_SLIPPAGE_BPS: u64 = 3_000;
+// A blockhash stops being accepted after roughly 150 slots
+const BLOCKHASH_LIFETIME_SECS: u64 = 60;
+
+/// Signed full exits of every open curve position, ready to send the moment they are needed
+pub static PANIC_EXITS: LazyLock<PanicBook> = LazyLock::new(PanicBook::default);
+
+#[derive(Debug, Clone)]
+pub struct PanicConfig {
+    /// How often every prepared exit is rebuilt against the current balance and price
+    pub refresh: Duration,
+    pub slippage_bps: u64,
+    /// Durable nonce accounts, each owned by the wallet it signs for; exits signed against a
+    /// nonce never expire
+    pub nonces: Vec<Pubkey>,
+}
+
+impl PanicConfig {
+    /// Reads `PANIC_EXIT_REFRESH_SECS` (unset disables), `PANIC_EXIT_SLIPPAGE_BPS` and
+    /// `PANIC_NONCE_ACCOUNTS` (comma separated)
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(secs) = env::var("PANIC_EXIT_REFRESH_SECS") else {
+            return Ok(None);
+        };
+        let refresh = u64::from_str(&secs)
+            .ok()
+            .filter(|secs| *secs > 0)
+            .context("PANIC_EXIT_REFRESH_SECS must be a positive number of seconds")?;
+        let slippage_bps = match env::var("PANIC_EXIT_SLIPPAGE_BPS") {
+            Ok(bps) => u64::from_str(&bps).context("PANIC_EXIT_SLIPPAGE_BPS must be bps")?,
+            Err(_) => DEFAULT_PANIC_SLIPPAGE_BPS,
+        };
+        let nonces = env::var("PANIC_NONCE_ACCOUNTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|nonce| !nonce.is_empty())
+            .map(|nonce| {
+                Pubkey::from_str(nonce).map_err(|_| {
+                    anyhow!("Invalid nonce account in PANIC_NONCE_ACCOUNTS: {}", nonce)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(Self {
+            refresh: Duration::from_secs(refresh),
+            slippage_bps,
+            nonces,
+        }))
+    }
+}
+
+/// One wallet's signed sell of its whole balance of a mint
+#[derive(Debug, Clone)]
+pub struct PreparedExit {
+    pub mint: String,
+    pub wallet: Pubkey,
+    pub tokens: u64,
+    pub tx: VersionedTransaction,
+    pub nonce: Option<Pubkey>,
+    built_at: Instant,
+}
+
+impl PreparedExit {
+    /// Whether the network would still accept the transaction
+    pub fn usable(&self) -> bool {
+        self.nonce.is_some()
+            || self.built_at.elapsed() < Duration::from_secs(BLOCKHASH_LIFETIME_SECS)
+    }
+}
+
+/// A mint held by one wallet
+type ExitKey = (String, Pubkey);
+
+#[derive(Debug, Default)]
+pub struct PanicBook {
+    exits: Mutex<HashMap<ExitKey, PreparedExit>>,
+}
+
+impl PanicBook {
+    pub fn insert(&self, exit: PreparedExit) {
+        let key = (exit.mint.clone(), exit.wallet);
+        self.exits.lock().unwrap().insert(key, exit);
+    }
+
+    /// Drops exits for anything no longer held
+    pub fn retain(&self, held: &HashSet<ExitKey>) {
+        self.exits
+            .lock()
+            .unwrap()
+            .retain(|key, _| held.contains(key));
+    }
+
+    pub fn len(&self) -> usize {
+        self.exits.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes and returns every exit still usable
+    pub fn take_all(&self) -> Vec<PreparedExit> {
+        let mut exits = self.exits.lock().unwrap();
+        exits
+            .drain()
+            .map(|(_, exit)| exit)
+            .filter(PreparedExit::usable)
+            .collect()
+    }
+}
+
+/// Hands out nonce accounts to held mints, keeping each assignment stable across refreshes
+#[derive(Debug, Default)]
+pub struct NonceAssignments {
+    assigned: HashMap<ExitKey, Pubkey>,
+}
+
+impl NonceAssignments {
+    /// Nonces for `keys`, freeing those of keys no longer present; keys past the pool's size
+    /// get none
+    pub fn assign(&mut self, nonces: &[Pubkey], keys: &[ExitKey]) -> HashMap<ExitKey, Pubkey> {
+        self.assigned.retain(|key, _| keys.contains(key));
+        let mut free = nonces
+            .iter()
+            .filter(|nonce| !self.assigned.values().any(|used| used == *nonce))
+            .copied()
+            .collect::<Vec<_>>()
+            .into_iter();
+        for key in keys {
+            if !self.assigned.contains_key(key) {
+                let Some(nonce) = free.next() else {
+                    break;
+                };
+                self.assigned.insert(key.clone(), nonce);
+            }
+        }
+        self.assigned.clone()
+    }
+}
+
+/// Signs a sell of `leg`'s whole balance of `mint`, closing the token account with it
+async fn prepare_exit(
+    leg: &AppState,
+    mint: &str,
+    slippage_bps: u64,
+    nonce: Option<Pubkey>,
+) -> Result<Option<PreparedExit>> {
+    let pump = Pump::new(
+        leg.rpc_nonblocking_client.clone(),
+        leg.rpc_client.clone(),
+        leg.wallet.clone(),
+    );
+    let tokens = pump.get_token_balance(mint).await?;
+    if tokens == 0 {
+        return Ok(None);
+    }
+    let owner = leg.wallet.pubkey();
+    let (mut instructions, _) = pump
+        .build_swap_instructions(mint, tokens, SwapDirection::Sell, slippage_bps)
+        .await?;
+    let ata = get_associated_token_address(&owner, &Pubkey::from_str(mint)?);
+    instructions.push(spl_token::instruction::close_account(
+        &spl_token::id(),
+        &ata,
+        &owner,
+        &owner,
+        &[],
+    )?);
+    let mut instructions = budgeted_instructions(instructions, &TxConfig::default(), None);
+
+    // The nonce's stored hash stands in for a recent blockhash once it is advanced first
+    let nonce_hash = match nonce {
+        Some(nonce) => {
+            let account = get_account_with_commitment(
+                &leg.rpc_nonblocking_client,
+                &nonce,
+                CommitmentConfig::confirmed(),
+            )
+            .await?;
+            let data = data_from_account(&account)?;
+            (data.authority == owner).then(|| (nonce, data.blockhash()))
+        }
+        None => None,
+    };
+    let blockhash = match nonce_hash {
+        Some((nonce, hash)) => {
+            instructions.insert(0, system_instruction::advance_nonce_account(&nonce, &owner));
+            hash
+        }
+        None => match BLOCKHASH_CACHE.fresh().await {
+            Some(hash) => hash,
+            None => leg.rpc_nonblocking_client.get_latest_blockhash().await?,
+        },
+    };
+    let signers = [&*leg.wallet as &dyn Signer];
+    let tx = tx::signing(&*leg.wallet, || {
+        Transaction::new_signed_with_payer(&instructions, Some(&owner), &signers, blockhash)
+    });
+    Ok(Some(PreparedExit {
+        mint: mint.to_string(),
+        wallet: owner,
+        tokens,
+        tx: VersionedTransaction::from(tx),
+        nonce: nonce_hash.map(|(nonce, _)| nonce),
+        built_at: Instant::now(),
+    }))
+}
+
+/// Rebuilds a signed full exit for every wallet holding an open curve position every
+/// `refresh`, forever
+pub async fn run_panic_exits(config: PanicConfig, state: AppState) {
+    let mut nonces = NonceAssignments::default();
+    loop {
+        let mut legs = Vec::new();
+        for position in state.positions.open_positions().await {
+            if position.impaired.is_some()
+                || !matches!(
+                    state.router.route(&state, &position.mint).await,
+                    Ok(Venue::BondingCurve)
+                )
+            {
+                continue;
+            }
+            for wallet in state.wallets.holders(Some(&position)) {
+                legs.push((position.mint.clone(), wallet));
+            }
+        }
+        let keys = legs
+            .iter()
+            .map(|(mint, wallet)| (mint.clone(), wallet.pubkey()))
+            .collect::<Vec<_>>();
+        let assigned = nonces.assign(&config.nonces, &keys);
+        PANIC_EXITS.retain(&keys.iter().cloned().collect());
+
+        for ((mint, wallet), key) in legs.into_iter().zip(keys) {
+            let leg = state.with_wallet(wallet);
+            let nonce = assigned.get(&key).copied();
+            match prepare_exit(&leg, &mint, config.slippage_bps, nonce).await {
+                Ok(Some(exit)) => PANIC_EXITS.insert(exit),
+                Ok(None) => {}
+                Err(e) => {
+                    let _ = log_message(&format!(
+                        "Could not prepare the panic exit of {} from {}: {}",
+                        mint, key.1, e
+                    ))
+                    .await;
+                }
+            }
+        }
+        sleep(config.refresh).await;
+    }
+}
+
+/// Sends one prepared exit, returning how it filled once landed
+async fn fire(
+    state: &AppState,
+    exit: PreparedExit,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<Option<Execution>> {
+    let wallet = state
+        .wallets
+        .get(&exit.wallet.to_string())
+        .ok_or_else(|| anyhow!("{} is no longer in the wallet pool", exit.wallet))?;
+    let leg = state.with_wallet(wallet);
+    let signature = exit.tx.signatures[0].to_string();
+    // A Jito tip is signed against a recent blockhash, which a nonce exit doesn't carry
+    let jito_client = exit.nonce.is_none().then_some(jito_client);
+    tx::send_signed(
+        &leg.rpc_client,
+        &leg.wallet,
+        exit.tx,
+        jito_client,
+        Instant::now(),
+    )
+    .await?;
+    match find_execution(&leg, &exit.mint, &[signature]).await {
+        Some(execution) => Ok(Some(execution)),
+        None => {
+            let _ = log_message(&format!(
+                "Panic exit of {} landed but its proceeds could not be read",
+                exit.mint
+            ))
+            .await;
+            Ok(None)
+        }
+    }
+}
+
+/// Cancels pending intents and fires every prepared exit at once, then market-sells whatever
+/// had none. A position stays open if any of its wallets failed to sell
+pub async fn fire_panic_exits(state: &AppState, jito_client: Arc<JitoRpcClient>) -> FlattenReport {
+    let mut report = FlattenReport {
+        cancelled_intents: state.positions.cancel_all_intents().await,
+        ..Default::default()
+    };
+    let exits = PANIC_EXITS.take_all();
+    let prepared = exits
+        .iter()
+        .map(|exit| exit.mint.clone())
+        .collect::<HashSet<_>>();
+    let _ = log_message(&format!("Firing {} prepared panic exits", exits.len())).await;
+
+    let mints = exits
+        .iter()
+        .map(|exit| exit.mint.clone())
+        .collect::<Vec<_>>();
+    let results = join_all(
+        exits
+            .into_iter()
+            .map(|exit| fire(state, exit, jito_client.clone())),
+    )
+    .await;
+    let mut failed = HashMap::new();
+    for (mint, result) in mints.into_iter().zip(results) {
+        match result {
+            Ok(execution) => {
+                let (lamports, fee, tokens) = execution.map_or((0, 0, 0.0), |execution| {
+                    let fill = execution.lamports + execution.fee;
+                    (fill, execution.fee, execution.ui_tokens())
+                });
+                state
+                    .positions
+                    .record_sell(&mint, lamports, fee, tokens)
+                    .await;
+            }
+            Err(e) => {
+                failed.insert(mint, e.to_string());
+            }
+        }
+    }
+
+    for position in state.positions.open_positions().await {
+        if let Some(error) = failed.remove(&position.mint) {
+            report.failed.push((position.mint, error));
+        } else if prepared.contains(&position.mint) {
+            settle_position(state, &position.mint).await;
+            report.closed.push(position.mint);
+        } else {
+            match market_exit(
+                state,
+                &position,
+                DEFAULT_PANIC_SLIPPAGE_BPS,
+                jito_client.clone(),
+            )
+            .await
+            {
+                Ok(()) => report.closed.push(position.mint),
+                Err(e) => report.failed.push((position.mint, e.to_string())),
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce_assignments() {
+        let nonces = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let wallet = Pubkey::new_unique();
+        let key = |mint: &str| (mint.to_string(), wallet);
+        let mut assignments = NonceAssignments::default();
+
+        let first = assignments.assign(&nonces, &[key("a"), key("b"), key("c")]);
+        assert_eq!(first.len(), 2);
+        assert!(!first.contains_key(&key("c")));
+        // Closing a position frees its nonce for one that had none, without moving the rest
+        let second = assignments.assign(&nonces, &[key("b"), key("c")]);
+        assert_eq!(second[&key("b")], first[&key("b")]);
+        assert_eq!(second[&key("c")], first[&key("a")]);
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{
+    common::{
+        storage::{append_json_line, data_path, load_json, save_json, EventTime},
+        utils::log_message,
+    },
+    risk::impairment::Impairment,
+};
+
+const POSITIONS_FILE: &str = "positions.json";
+/// Every closed position, one JSON line each, next to the positions file
+const TRADES_FILE: &str = "trades.jsonl";
+
+/// An open copy position on a single mint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub mint: String,
+    /// Raydium pool the position trades on, `None` while on the pump.fun curve
+    pub pool_id: Option<String>,
+    /// Lamports spent on entries so far
+    pub sol_invested: u64,
+    /// Lamports received from partial exits so far, before their network fees
+    pub sol_returned: u64,
+    /// Lamports paid in network fees and tips on entries and exits
+    #[serde(default)]
+    pub fees_paid: u64,
+    pub opened_at: DateTime<Utc>,
+    /// Seconds after opening at which the position is force-sold; `None` uses the global default
+    #[serde(default)]
+    pub max_hold_secs: Option<u64>,
+    /// Wallet that launched the mint, once known
+    #[serde(default)]
+    pub creator: Option<String>,
+    /// Pool wallets that bought into the position; the primary is always checked as well
+    #[serde(default)]
+    pub wallets: Vec<String>,
+    /// Copy target whose buy opened the position
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Signature of the target's trade that opened the position
+    #[serde(default)]
+    pub signal: Option<String>,
+    /// Entry, adds and partial exits, oldest first
+    #[serde(default)]
+    pub fills: Vec<Fill>,
+    /// Set once the tokens turn out to be unsellable
+    #[serde(default)]
+    pub impaired: Option<Impairment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillSide {
+    Buy,
+    Sell,
+}
+
+/// One trade within a position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub at: DateTime<Utc>,
+    pub side: FillSide,
+    /// Lamports spent on a buy or received from a sell
+    pub lamports: u64,
+    pub fees: u64,
+    /// Tokens bought or sold, in UI units; 0 until the fill has been read from the chain
+    #[serde(default)]
+    pub tokens: f64,
+    #[serde(default)]
+    pub time: EventTime,
+}
+
+impl Position {
+    /// Tokens the fills add up to holding, bought less sold
+    pub fn tokens_held(&self) -> f64 {
+        let tokens = self
+            .fills
+            .iter()
+            .map(|fill| match fill.side {
+                FillSide::Buy => fill.tokens,
+                FillSide::Sell => -fill.tokens,
+            })
+            .sum::<f64>();
+        tokens.max(0.0)
+    }
+}
+
+/// A fully exited position, as kept in the trade log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosedTrade {
+    pub mint: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    pub sol_invested: u64,
+    pub sol_returned: u64,
+    pub fees_paid: u64,
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub time: EventTime,
+}
+
+impl ClosedTrade {
+    pub fn new(position: &Position, closed_at: DateTime<Utc>) -> Self {
+        Self {
+            mint: position.mint.clone(),
+            opened_at: position.opened_at,
+            closed_at,
+            sol_invested: position.sol_invested,
+            sol_returned: position.sol_returned,
+            fees_paid: position.fees_paid,
+            target: position.target.clone(),
+            time: EventTime::now(),
+        }
+    }
+
+    /// Net lamports made, after fees
+    pub fn pnl(&self) -> i64 {
+        self.sol_returned as i64 - self.sol_invested as i64 - self.fees_paid as i64
+    }
+}
+
+/// Reads the trade log in the data directory, oldest first; no log yet means no trades
+pub fn load_closed_trades() -> Result<Vec<ClosedTrade>> {
+    let path = data_path(TRADES_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Bad trade on line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// A copy that has been decided on but not yet sent
+#[derive(Debug, Clone)]
+pub struct PendingIntent {
+    pub id: u64,
+    pub mint: String,
+    pub direction: String,
+    pub amount: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Tracks open positions and in-flight copy intents
+#[derive(Default)]
+pub struct PositionManager {
+    positions: RwLock<HashMap<String, Position>>,
+    intents: RwLock<HashMap<u64, PendingIntent>>,
+    next_intent_id: AtomicU64,
+    /// Where positions are persisted; `None` keeps them in memory only
+    path: Option<PathBuf>,
+}
+
+impl PositionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restores positions from the data directory and persists every change back to it
+    pub fn load() -> Result<Self> {
+        let path = data_path(POSITIONS_FILE);
+        let positions: HashMap<String, Position> = load_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            positions: RwLock::new(positions),
+            path: Some(path),
+            ..Default::default()
+        })
+    }
+
+    /// Writes the open positions out now, as a final snapshot on shutdown
+    pub async fn flush(&self) {
+        self.persist().await;
+    }
+
+    async fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let snapshot = self.positions.read().await.clone();
+        if let Err(e) = save_json(path, &snapshot).await {
+            let _ = log_message(&format!("Failed to persist positions: {}", e)).await;
+        }
+    }
+
+    /// Records a buy and the fees paid to land it, opening the position if needed
+    pub async fn record_buy(
+        &self,
+        mint: &str,
+        pool_id: Option<String>,
+        sol_spent: u64,
+        fees_paid: u64,
+    ) {
+        let mut positions = self.positions.write().await;
+        let position = positions
+            .entry(mint.to_string())
+            .or_insert_with(|| Position {
+                mint: mint.to_string(),
+                pool_id: pool_id.clone(),
+                sol_invested: 0,
+                sol_returned: 0,
+                fees_paid: 0,
+                opened_at: Utc::now(),
+                max_hold_secs: None,
+                creator: None,
+                wallets: vec![],
+                target: None,
+                signal: None,
+                fills: vec![],
+                impaired: None,
+            });
+        position.sol_invested = position.sol_invested.saturating_add(sol_spent);
+        position.fees_paid = position.fees_paid.saturating_add(fees_paid);
+        position.fills.push(Fill {
+            at: Utc::now(),
+            side: FillSide::Buy,
+            lamports: sol_spent,
+            fees: fees_paid,
+            tokens: 0.0,
+            time: EventTime::now(),
+        });
+        if pool_id.is_some() {
+            position.pool_id = pool_id;
+        }
+        drop(positions);
+        self.persist().await;
+    }
+
+    /// Tokens a buy filled for, read from the chain after `record_buy`; goes to the oldest buy
+    /// still without them. Returns true once every buy of the position has its tokens
+    pub async fn record_bought_tokens(&self, mint: &str, tokens: f64) -> bool {
+        let complete = match self.positions.write().await.get_mut(mint) {
+            Some(position) => {
+                let unfilled = position
+                    .fills
+                    .iter_mut()
+                    .find(|fill| fill.side == FillSide::Buy && fill.tokens == 0.0);
+                if let Some(fill) = unfilled {
+                    fill.tokens = tokens;
+                }
+                position
+                    .fills
+                    .iter()
+                    .all(|fill| fill.side != FillSide::Buy || fill.tokens > 0.0)
+            }
+            None => return false,
+        };
+        self.persist().await;
+        complete
+    }
+
+    /// Records a partial exit, the fees paid to land it and the tokens it sold
+    pub async fn record_sell(&self, mint: &str, sol_received: u64, fees_paid: u64, tokens: f64) {
+        if let Some(position) = self.positions.write().await.get_mut(mint) {
+            position.sol_returned = position.sol_returned.saturating_add(sol_received);
+            position.fees_paid = position.fees_paid.saturating_add(fees_paid);
+            position.fills.push(Fill {
+                at: Utc::now(),
+                side: FillSide::Sell,
+                lamports: sol_received,
+                fees: fees_paid,
+                tokens,
+                time: EventTime::now(),
+            });
+        }
+        self.persist().await;
+    }
+
+    /// Removes and returns a position once it is fully exited, adding it to the trade log
+    pub async fn close(&self, mint: &str) -> Option<Position> {
+        let position = self.positions.write().await.remove(mint);
+        self.persist().await;
+        if let (Some(position), Some(path)) = (&position, &self.path) {
+            let trade = ClosedTrade::new(position, Utc::now());
+            if let Err(e) = append_json_line(&path.with_file_name(TRADES_FILE), &trade).await {
+                let _ = log_message(&format!("Failed to log closed trade: {}", e)).await;
+            }
+        }
+        position
+    }
+
+    /// Overrides the max hold time of an open position; returns false if there is none
+    pub async fn set_max_hold(&self, mint: &str, max_hold_secs: Option<u64>) -> bool {
+        let updated = match self.positions.write().await.get_mut(mint) {
+            Some(position) => {
+                position.max_hold_secs = max_hold_secs;
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.persist().await;
+        }
+        updated
+    }
+
+    /// Records who launched a held mint; returns false if there is no position
+    pub async fn set_creator(&self, mint: &str, creator: &str) -> bool {
+        let updated = match self.positions.write().await.get_mut(mint) {
+            Some(position) => {
+                position.creator = Some(creator.to_string());
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.persist().await;
+        }
+        updated
+    }
+
+    /// Attributes a position to the target and trade that opened it; later targets buying in
+    /// do not take it over. Returns false if there is no position
+    pub async fn set_target(&self, mint: &str, target: &str, signal: &str) -> bool {
+        let updated = match self.positions.write().await.get_mut(mint) {
+            Some(position) if position.target.is_none() => {
+                position.target = Some(target.to_string());
+                position.signal = Some(signal.to_string());
+                true
+            }
+            Some(_) => return true,
+            None => false,
+        };
+        if updated {
+            self.persist().await;
+        }
+        updated
+    }
+
+    /// Flags a position as unsellable, taking it out of [`Self::open_positions`]; returns false
+    /// if there is none
+    pub async fn set_impaired(&self, mint: &str, impairment: Impairment) -> bool {
+        let updated = match self.positions.write().await.get_mut(mint) {
+            Some(position) => {
+                position.impaired = Some(impairment);
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.persist().await;
+        }
+        updated
+    }
+
+    /// Records that `wallet` holds part of a position; returns false if there is none
+    pub async fn add_wallet(&self, mint: &str, wallet: &str) -> bool {
+        let updated = match self.positions.write().await.get_mut(mint) {
+            Some(position) if !position.wallets.iter().any(|w| w == wallet) => {
+                position.wallets.push(wallet.to_string());
+                true
+            }
+            Some(_) => return true,
+            None => false,
+        };
+        if updated {
+            self.persist().await;
+        }
+        updated
+    }
+
+    pub async fn get(&self, mint: &str) -> Option<Position> {
+        self.positions.read().await.get(mint).cloned()
+    }
+
+    /// Positions that can still be traded and count towards equity; impaired ones are left out
+    pub async fn open_positions(&self) -> Vec<Position> {
+        self.positions
+            .read()
+            .await
+            .values()
+            .filter(|position| position.impaired.is_none())
+            .cloned()
+            .collect()
+    }
+
+    pub async fn impaired_positions(&self) -> Vec<Position> {
+        self.positions
+            .read()
+            .await
+            .values()
+            .filter(|position| position.impaired.is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Registers a pending copy and returns its id
+    pub async fn add_intent(&self, mint: &str, direction: &str, amount: u64) -> u64 {
+        let id = self.next_intent_id.fetch_add(1, Ordering::Relaxed);
+        self.intents.write().await.insert(
+            id,
+            PendingIntent {
+                id,
+                mint: mint.to_string(),
+                direction: direction.to_string(),
+                amount,
+                created_at: Utc::now(),
+            },
+        );
+        id
+    }
+
+    /// Claims an intent for execution; `None` means it was cancelled in the meantime
+    pub async fn take_intent(&self, id: u64) -> Option<PendingIntent> {
+        self.intents.write().await.remove(&id)
+    }
+
+    pub async fn pending_intents(&self) -> Vec<PendingIntent> {
+        self.intents.read().await.values().cloned().collect()
+    }
+
+    /// Cancels every pending intent and returns how many were dropped
+    pub async fn cancel_all_intents(&self) -> usize {
+        let mut intents = self.intents.write().await;
+        let count = intents.len();
+        intents.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fill_timeline() {
+        let positions = PositionManager::new();
+        positions.record_buy("mint", None, 1_000, 10).await;
+        positions.record_buy("mint", None, 500, 5).await;
+        assert!(!positions.record_bought_tokens("mint", 100.0).await);
+        assert!(positions.record_bought_tokens("mint", 40.0).await);
+        positions.record_sell("mint", 800, 7, 90.0).await;
+        let position = positions.get("mint").await.unwrap();
+        let fills = position
+            .fills
+            .iter()
+            .map(|fill| (fill.side, fill.lamports, fill.fees))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fills,
+            [
+                (FillSide::Buy, 1_000, 10),
+                (FillSide::Buy, 500, 5),
+                (FillSide::Sell, 800, 7)
+            ]
+        );
+        assert_eq!(position.sol_invested, 1_500);
+        assert_eq!(position.fees_paid, 22);
+        assert_eq!(position.tokens_held(), 50.0);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    engine::{signal::TradeSignal, swap::SwapDirection},
+    services::price_feed::CurveReserves,
+};
+
+// Configuration constants
+// A target trade older than this has long since landed in whatever we fetch
+const FLOW_TTL_SECS: u64 = 10;
+
+/// Latest target trade seen on each mint, for quoting a copy against the reserves it leaves
+pub static TARGET_FLOWS: LazyLock<TargetFlows> = LazyLock::new(TargetFlows::default);
+
+/// A target's trade the copy is about to follow
+#[derive(Debug, Clone)]
+pub struct TargetFlow {
+    pub slot: u64,
+    pub direction: SwapDirection,
+    /// Raw tokens the target took out of (buy) or put into (sell) the pool
+    pub token_amount: u64,
+    observed_at: Instant,
+}
+
+impl From<&TradeSignal> for TargetFlow {
+    fn from(signal: &TradeSignal) -> Self {
+        Self {
+            slot: signal.slot,
+            direction: signal.direction.clone(),
+            token_amount: signal.token_amount,
+            observed_at: Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TargetFlows {
+    flows: Mutex<HashMap<String, TargetFlow>>,
+}
+
+impl TargetFlows {
+    pub fn record(&self, signal: &TradeSignal) {
+        let mut flows = self.flows.lock().unwrap();
+        flows.retain(|_, flow| flow.observed_at.elapsed() < Duration::from_secs(FLOW_TTL_SECS));
+        flows.insert(signal.mint.clone(), TargetFlow::from(signal));
+    }
+
+    pub fn latest(&self, mint: &str) -> Option<TargetFlow> {
+        let flows = self.flows.lock().unwrap();
+        flows
+            .get(mint)
+            .filter(|flow| flow.observed_at.elapsed() < Duration::from_secs(FLOW_TTL_SECS))
+            .cloned()
+    }
+}
+
+/// Reserves after a trade moving `token_amount` in `direction`, holding the constant product
+pub fn apply_trade(
+    reserves: CurveReserves,
+    direction: &SwapDirection,
+    token_amount: u64,
+) -> CurveReserves {
+    let k = reserves.virtual_sol_reserves as u128 * reserves.virtual_token_reserves as u128;
+    let tokens = match direction {
+        SwapDirection::Buy => reserves.virtual_token_reserves.saturating_sub(token_amount),
+        SwapDirection::Sell => reserves.virtual_token_reserves.saturating_add(token_amount),
+    };
+    if tokens == 0 {
+        return reserves;
+    }
+    CurveReserves {
+        virtual_sol_reserves: k.div_ceil(tokens as u128) as u64,
+        virtual_token_reserves: tokens,
+    }
+}
+
+/// Reserves as they will be once `flow` lands. Accounts read at or after the flow's slot
+/// already include it and are returned unchanged
+pub fn project(
+    reserves: CurveReserves,
+    observed_slot: u64,
+    flow: Option<&TargetFlow>,
+) -> CurveReserves {
+    match flow {
+        Some(flow) if observed_slot < flow.slot => {
+            apply_trade(reserves, &flow.direction, flow.token_amount)
+        }
+        _ => reserves,
+    }
+}
+
+/// Raw tokens `sol_in` lamports buy, after a `fee_bps` fee on the input
+pub fn quote_buy(reserves: CurveReserves, sol_in: u64, fee_bps: u64) -> u64 {
+    let sol_in = sol_in as u128 * 10_000u64.saturating_sub(fee_bps) as u128 / 10_000;
+    let after = apply_trade_sol(reserves, sol_in);
+    reserves.virtual_token_reserves.saturating_sub(after)
+}
+
+/// Lamports `token_in` raw tokens sell for, after a `fee_bps` fee on the output
+pub fn quote_sell(reserves: CurveReserves, token_in: u64, fee_bps: u64) -> u64 {
+    let after = apply_trade(reserves, &SwapDirection::Sell, token_in);
+    let sol_out = reserves
+        .virtual_sol_reserves
+        .saturating_sub(after.virtual_sol_reserves) as u128;
+    (sol_out * 10_000u64.saturating_sub(fee_bps) as u128 / 10_000) as u64
+}
+
+/// Token reserves left once `sol_in` lamports reach the pool
+fn apply_trade_sol(reserves: CurveReserves, sol_in: u128) -> u64 {
+    let k = reserves.virtual_sol_reserves as u128 * reserves.virtual_token_reserves as u128;
+    k.div_ceil(reserves.virtual_sol_reserves as u128 + sol_in) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projected_quote() {
+        let reserves = CurveReserves {
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+        };
+        let flow = TargetFlow {
+            slot: 100,
+            direction: SwapDirection::Buy,
+            token_amount: 100_000_000_000_000,
+            observed_at: Instant::now(),
+        };
+        // Reserves fetched before the target's buy landed get it applied
+        let projected = project(reserves, 99, Some(&flow));
+        assert_eq!(projected.virtual_token_reserves, 973_000_000_000_000);
+        assert!(projected.virtual_sol_reserves > reserves.virtual_sol_reserves);
+        assert!(quote_buy(projected, 1_000_000_000, 100) < quote_buy(reserves, 1_000_000_000, 100));
+        // Reserves read at the target's slot already include it
+        assert_eq!(project(reserves, 100, Some(&flow)), reserves);
+
+        let tokens = quote_buy(reserves, 1_000_000_000, 0);
+        let back = quote_sell(
+            apply_trade(reserves, &SwapDirection::Buy, tokens),
+            tokens,
+            0,
+        );
+        assert!(back <= 1_000_000_000 && back > 999_000_000);
+    }
+}
@@ -0,0 +1,335 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use serde_json::Value;
+
+use crate::{
+    dex::{
+        raydium::AMM_PROGRAM_ID,
+        raydium_clmm::{CLMM_PROGRAM_ID, SWAP_V2_DISCRIMINATOR},
+        raydium_cpmm::{
+            CPMM_PROGRAM_ID, SWAP_BASE_INPUT_DISCRIMINATOR, SWAP_BASE_OUTPUT_DISCRIMINATOR,
+        },
+    },
+    engine::{
+        signal::{account_index, flat_instructions, token_balance, TradeSignal, WSOL_MINT},
+        swap::SwapDirection,
+    },
+};
+
+// Configuration constants
+/// AMM v4 `swapBaseIn` and `swapBaseOut`, and their v2 forms without the OpenBook accounts
+const AMM_SWAP_TAGS: [u8; 4] = [9, 11, 16, 17];
+/// Anchor discriminator of the CLMM's original `swap`, still used by older routers
+const CLMM_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+// Program ids as they appear in `programId` fields, rendered once instead of parsing every one
+static AMM_PROGRAM: LazyLock<String> = LazyLock::new(|| AMM_PROGRAM_ID.to_string());
+static CLMM_PROGRAM: LazyLock<String> = LazyLock::new(|| CLMM_PROGRAM_ID.to_string());
+static CPMM_PROGRAM: LazyLock<String> = LazyLock::new(|| CPMM_PROGRAM_ID.to_string());
+
+/// Raydium programs a target can swap through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaydiumProgram {
+    AmmV4,
+    Clmm,
+    Cpmm,
+}
+
+impl RaydiumProgram {
+    pub fn from_id(program_id: &str) -> Option<Self> {
+        match program_id {
+            id if id == *AMM_PROGRAM => Some(Self::AmmV4),
+            id if id == *CLMM_PROGRAM => Some(Self::Clmm),
+            id if id == *CPMM_PROGRAM => Some(Self::Cpmm),
+            _ => None,
+        }
+    }
+
+    /// Whether `data` is one of the program's swap instructions
+    fn is_swap(&self, data: &[u8]) -> bool {
+        match self {
+            Self::AmmV4 => data.first().is_some_and(|tag| AMM_SWAP_TAGS.contains(tag)),
+            Self::Clmm => {
+                data.starts_with(&CLMM_SWAP_DISCRIMINATOR)
+                    || data.starts_with(&SWAP_V2_DISCRIMINATOR)
+            }
+            Self::Cpmm => {
+                data.starts_with(&SWAP_BASE_INPUT_DISCRIMINATOR)
+                    || data.starts_with(&SWAP_BASE_OUTPUT_DISCRIMINATOR)
+            }
+        }
+    }
+
+    /// Position of the pool in the program's swap accounts
+    fn pool_index(&self) -> usize {
+        match self {
+            Self::AmmV4 => 1,
+            Self::Clmm => 2,
+            Self::Cpmm => 3,
+        }
+    }
+}
+
+/// A target trade decoded from the Raydium swaps in a transaction
+#[derive(Debug, Clone)]
+pub struct RaydiumTrade {
+    pub signal: TradeSignal,
+    pub program: RaydiumProgram,
+    /// Pool of the hop that traded `signal.mint`
+    pub pool: String,
+}
+
+/// One Raydium swap instruction and the tokens it moved for the wallet, positive for tokens
+/// the wallet received
+struct RaydiumSwap {
+    program: RaydiumProgram,
+    pool: String,
+    legs: Vec<(String, i128)>,
+}
+
+/// An SPL token `transfer` or `transferChecked`, as jsonParsed shows it
+struct TokenTransfer<'a> {
+    source: &'a str,
+    destination: &'a str,
+    authority: &'a str,
+    amount: u64,
+    /// Only `transferChecked` names its mint
+    mint: Option<&'a str>,
+}
+
+fn token_transfer(ix: &Value) -> Option<TokenTransfer<'_>> {
+    let parsed = &ix["parsed"];
+    if !matches!(ix["program"].as_str(), Some("spl-token" | "spl-token-2022"))
+        || !matches!(
+            parsed["type"].as_str(),
+            Some("transfer" | "transferChecked")
+        )
+    {
+        return None;
+    }
+    let info = &parsed["info"];
+    let amount = info["amount"]
+        .as_str()
+        .or(info["tokenAmount"]["amount"].as_str())?
+        .parse()
+        .ok()?;
+    Some(TokenTransfer {
+        source: info["source"].as_str()?,
+        destination: info["destination"].as_str()?,
+        authority: info["authority"]
+            .as_str()
+            .or(info["multisigAuthority"].as_str())?,
+        amount,
+        mint: info["mint"].as_str(),
+    })
+}
+
+/// Mint of every token account the transaction's balances mention
+fn token_account_mints(tx: &Value) -> HashMap<&str, &str> {
+    let keys = &tx["transaction"]["message"]["accountKeys"];
+    let meta = &tx["meta"];
+    meta["preTokenBalances"]
+        .as_array()
+        .into_iter()
+        .chain(meta["postTokenBalances"].as_array())
+        .flatten()
+        .filter_map(|balance| {
+            let key = &keys[balance["accountIndex"].as_u64()? as usize];
+            Some((
+                key["pubkey"].as_str().or(key.as_str())?,
+                balance["mint"].as_str()?,
+            ))
+        })
+        .collect()
+}
+
+/// Every Raydium swap in `tx` with the transfers it made directly to and from `wallet`
+fn raydium_swaps(tx: &Value, wallet: &str) -> Vec<RaydiumSwap> {
+    let mints = token_account_mints(tx);
+    let instructions = flat_instructions(tx);
+    let mut swaps = Vec::new();
+    for (i, (ix, depth)) in instructions.iter().enumerate() {
+        let Some(program) = ix["programId"].as_str().and_then(RaydiumProgram::from_id) else {
+            continue;
+        };
+        let Some(data) = ix["data"]
+            .as_str()
+            .and_then(|data| bs58::decode(data).into_vec().ok())
+        else {
+            continue;
+        };
+        let Some(pool) = ix["accounts"][program.pool_index()].as_str() else {
+            continue;
+        };
+        if !program.is_swap(&data) {
+            continue;
+        }
+        let transfers = instructions[i + 1..]
+            .iter()
+            .take_while(|(_, inner)| inner > depth)
+            .filter(|(_, inner)| *inner == depth + 1)
+            .filter_map(|(inner, _)| token_transfer(inner))
+            .collect::<Vec<_>>();
+        // The wallet signs for what it pays in; the pool pays out to whatever account the
+        // wallet named, which may be a WSOL account opened and closed in the same transaction
+        if !transfers
+            .iter()
+            .any(|transfer| transfer.authority == wallet)
+        {
+            continue;
+        }
+        let legs = transfers
+            .iter()
+            .filter_map(|transfer| {
+                let mint = transfer
+                    .mint
+                    .or_else(|| mints.get(transfer.source).copied())
+                    .or_else(|| mints.get(transfer.destination).copied())?;
+                let amount = transfer.amount as i128;
+                let delta = if transfer.authority == wallet {
+                    -amount
+                } else {
+                    amount
+                };
+                Some((mint.to_string(), delta))
+            })
+            .collect();
+        swaps.push(RaydiumSwap {
+            program,
+            pool: pool.to_string(),
+            legs,
+        });
+    }
+    swaps
+}
+
+/// Returns true if any instruction, top-level or inner, invokes a Raydium program
+pub fn invokes_raydium(tx: &Value) -> bool {
+    flat_instructions(tx).iter().any(|(ix, _)| {
+        ix["programId"]
+            .as_str()
+            .and_then(RaydiumProgram::from_id)
+            .is_some()
+    })
+}
+
+/// Decodes the target's trade from the Raydium swaps in a `transactionSubscribe` notification,
+/// whether it called Raydium itself or went through a router. Amounts come from the token
+/// transfers each swap made, netted across hops, so a SOL -> USDC -> token route reads as one
+/// buy. Token-to-token trades with no WSOL leg are not signals
+pub fn parse_raydium_trade(json: &Value, target: &str) -> Option<RaydiumTrade> {
+    let result = &json["params"]["result"];
+    let tx = &result["transaction"];
+    let meta = &tx["meta"];
+    if !meta["err"].is_null() {
+        return None;
+    }
+    account_index(tx, target)?;
+    let swaps = raydium_swaps(tx, target);
+    let mut net: Vec<(&str, i128)> = Vec::new();
+    for (mint, delta) in swaps.iter().flat_map(|swap| &swap.legs) {
+        match net.iter_mut().find(|(seen, _)| *seen == mint) {
+            Some((_, total)) => *total += delta,
+            None => net.push((mint, *delta)),
+        }
+    }
+    let sol = net
+        .iter()
+        .find(|(mint, _)| *mint == WSOL_MINT)
+        .map(|(_, sol)| *sol)
+        .filter(|sol| *sol != 0)?;
+    // Intermediate hops net out; the traded mint moved against SOL
+    let (mint, tokens) = net
+        .iter()
+        .find(|(mint, tokens)| *mint != WSOL_MINT && tokens.signum() == -sol.signum())?;
+    let swap = swaps
+        .iter()
+        .find(|swap| swap.legs.iter().any(|(leg, _)| leg == mint))?;
+    let (pre_tokens, pre_decimals) = token_balance(&meta["preTokenBalances"], target, mint);
+    let (_, post_decimals) = token_balance(&meta["postTokenBalances"], target, mint);
+    let direction = if sol < 0 {
+        SwapDirection::Buy
+    } else {
+        SwapDirection::Sell
+    };
+    Some(RaydiumTrade {
+        signal: TradeSignal {
+            signature: result["signature"].as_str().unwrap_or_default().to_string(),
+            slot: result["slot"].as_u64().unwrap_or_default(),
+            mint: mint.to_string(),
+            direction,
+            sol_amount: sol.unsigned_abs() as u64,
+            token_amount: tokens.unsigned_abs() as u64,
+            token_pre_balance: pre_tokens,
+            decimals: pre_decimals.max(post_decimals),
+        },
+        program: swap.program,
+        pool: swap.pool.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transfer(source: &str, destination: &str, authority: &str, amount: u64) -> Value {
+        json!({
+            "program": "spl-token",
+            "parsed": { "type": "transfer", "info": {
+                "source": source, "destination": destination,
+                "authority": authority, "amount": amount.to_string()
+            }},
+            "stackHeight": 3
+        })
+    }
+
+    #[test]
+    fn test_decodes_routed_clmm_buy_paid_from_a_wsol_account() {
+        let swap_data = bs58::encode(SWAP_V2_DISCRIMINATOR).into_string();
+        let accounts = ["target", "config", "pool", "wsol-ata", "mint-ata"];
+        let json = json!({ "params": { "result": {
+            "signature": "sig",
+            "slot": 7,
+            "transaction": {
+                "transaction": { "message": {
+                    "accountKeys": [
+                        { "pubkey": "target" }, { "pubkey": "wsol-ata" },
+                        { "pubkey": "mint-ata" }, { "pubkey": "wsol-vault" },
+                        { "pubkey": "mint-vault" }
+                    ],
+                    "instructions": [{ "programId": "router", "accounts": [], "data": "" }]
+                }},
+                "meta": {
+                    "err": null,
+                    "innerInstructions": [{ "index": 0, "instructions": [
+                        { "programId": *CLMM_PROGRAM, "accounts": accounts,
+                          "data": swap_data, "stackHeight": 2 },
+                        transfer("wsol-ata", "wsol-vault", "target", 2_000_000_000),
+                        transfer("mint-vault", "mint-ata", "pool-authority", 5_000),
+                    ]}],
+                    "preTokenBalances": [
+                        { "accountIndex": 1, "owner": "target", "mint": WSOL_MINT,
+                          "uiTokenAmount": { "amount": "3000000000", "decimals": 9 } },
+                        { "accountIndex": 4, "owner": "pool-authority", "mint": "mint",
+                          "uiTokenAmount": { "amount": "90000", "decimals": 6 } }
+                    ],
+                    "postTokenBalances": [
+                        { "accountIndex": 2, "owner": "target", "mint": "mint",
+                          "uiTokenAmount": { "amount": "5000", "decimals": 6 } }
+                    ]
+                }
+            }
+        }}});
+
+        let trade = parse_raydium_trade(&json, "target").unwrap();
+        assert_eq!(trade.program, RaydiumProgram::Clmm);
+        assert_eq!(trade.pool, "pool");
+        assert_eq!(trade.signal.mint, "mint");
+        assert!(matches!(trade.signal.direction, SwapDirection::Buy));
+        assert_eq!(trade.signal.sol_amount, 2_000_000_000);
+        assert_eq!(trade.signal.token_amount, 5_000);
+        assert_eq!(trade.signal.decimals, 6);
+        assert!(invokes_raydium(&json["params"]["result"]["transaction"]));
+        assert!(parse_raydium_trade(&json, "someone-else").is_none());
+    }
+}
@@ -0,0 +1,571 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, LazyLock, Mutex,
+    },
+    thread,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use tokio::sync::watch;
+
+use crate::{
+    common::{
+        storage::{data_path, load_json},
+        utils::{log_message, log_message_sync, AppState},
+    },
+    engine::{
+        execution::fetch_transaction, position::Position, signal::parse_trade_signal,
+        swap::SwapDirection,
+    },
+    risk::expectancy::settle_position,
+    services::{notify::Event, sources::SignalEvent},
+};
+
+// Configuration constants
+const PENDING_FILE: &str = "pending_txs.json";
+const STATUS_BATCH: usize = 256;
+
+/// Signatures sent but not yet settled, persisted so a restart can find out how they ended
+pub static PENDING_TXS: LazyLock<PendingTxs> = LazyLock::new(PendingTxs::load);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingTx {
+    /// Fee payer, the wallet that traded
+    pub wallet: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+type Snapshot = (u64, HashMap<String, PendingTx>);
+
+#[derive(Default)]
+pub struct PendingTxs {
+    /// `None` keeps the journal in memory only
+    writer: Option<JournalWriter>,
+    txs: Mutex<HashMap<String, PendingTx>>,
+}
+
+/// Writes journal snapshots on a thread of its own, so a send never holds a runtime worker
+/// on disk I/O
+struct JournalWriter {
+    snapshots: mpsc::Sender<Snapshot>,
+    /// Last change queued, bumped under the `txs` lock so snapshots are queued in order
+    queued: AtomicU64,
+    /// Last change written, or that failed to write and was logged
+    saved: watch::Receiver<u64>,
+}
+
+impl JournalWriter {
+    fn spawn(path: PathBuf) -> std::io::Result<Self> {
+        let (snapshots, queue) = mpsc::channel::<Snapshot>();
+        let (written, saved) = watch::channel(0);
+        thread::Builder::new()
+            .name("pending-txs".to_string())
+            .spawn(move || {
+                while let Ok(next) = queue.recv() {
+                    // Only the newest snapshot needs writing; any queued behind it are older
+                    let (change, txs) = queue.try_iter().last().unwrap_or(next);
+                    if let Err(e) = write_journal(&path, &txs) {
+                        let message = format!("Failed to save pending transactions: {:#}", e);
+                        let _ = log_message_sync(&message);
+                    }
+                    written.send_replace(change);
+                }
+            })?;
+        Ok(Self {
+            snapshots,
+            queued: AtomicU64::new(0),
+            saved,
+        })
+    }
+}
+
+fn write_journal(path: &Path, txs: &HashMap<String, PendingTx>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(txs)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Keeps a signature in the journal until dropped, once its send has returned either way
+pub struct Tracked<'a> {
+    journal: &'a PendingTxs,
+    signature: String,
+}
+
+impl Drop for Tracked<'_> {
+    fn drop(&mut self) {
+        let mut txs = self.journal.txs.lock().unwrap();
+        txs.remove(&self.signature);
+        self.journal.save(&txs);
+    }
+}
+
+impl PendingTxs {
+    /// Restores the journal from the data directory; an unreadable one starts empty
+    fn load() -> Self {
+        let path = data_path(PENDING_FILE);
+        let txs = load_json(&path).ok().flatten().unwrap_or_default();
+        Self::persisted(path, txs)
+    }
+
+    /// Kept in memory only if the writer thread can't be started
+    fn persisted(path: PathBuf, txs: HashMap<String, PendingTx>) -> Self {
+        let writer = JournalWriter::spawn(path)
+            .inspect_err(|e| {
+                let message = format!("Failed to start the pending transaction writer: {}", e);
+                let _ = log_message_sync(&message);
+            })
+            .ok();
+        Self {
+            writer,
+            txs: Mutex::new(txs),
+        }
+    }
+
+    /// Queues `txs` for the writer, returning the change to wait on. Called under the `txs`
+    /// lock, so the writer always ends up with the latest journal
+    fn save(&self, txs: &HashMap<String, PendingTx>) -> Option<u64> {
+        let writer = self.writer.as_ref()?;
+        let change = writer.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        writer.snapshots.send((change, txs.clone())).ok()?;
+        Some(change)
+    }
+
+    /// Journals `tx` before it is sent, returning once the signature is on disk; the record
+    /// goes when the returned guard is dropped
+    pub async fn track(&self, tx: &VersionedTransaction) -> Tracked<'_> {
+        let signature = tx
+            .signatures
+            .first()
+            .copied()
+            .unwrap_or_default()
+            .to_string();
+        let wallet = tx
+            .message
+            .static_account_keys()
+            .first()
+            .map(|key| key.to_string())
+            .unwrap_or_default();
+        let change = {
+            let mut txs = self.txs.lock().unwrap();
+            txs.insert(
+                signature.clone(),
+                PendingTx {
+                    wallet,
+                    sent_at: Utc::now(),
+                },
+            );
+            self.save(&txs)
+        };
+        if let (Some(writer), Some(change)) = (&self.writer, change) {
+            let mut saved = writer.saved.clone();
+            let _ = saved.wait_for(|saved| *saved >= change).await;
+        }
+        Tracked {
+            journal: self,
+            signature,
+        }
+    }
+
+    /// Everything in the journal, which before any sends is what a previous run left in it
+    pub fn all(&self) -> Vec<(String, PendingTx)> {
+        let txs = self.txs.lock().unwrap();
+        txs.iter().map(|(s, tx)| (s.clone(), tx.clone())).collect()
+    }
+
+    /// Drops `signatures` from the journal once they're accounted for
+    pub fn forget(&self, signatures: &[String]) {
+        let mut txs = self.txs.lock().unwrap();
+        for signature in signatures {
+            txs.remove(signature);
+        }
+        self.save(&txs);
+    }
+}
+
+/// A journaled swap that landed after all, so the run that sent it never recorded it
+#[derive(Debug, Clone)]
+pub struct LandedTrade {
+    pub wallet: String,
+    pub mint: String,
+    pub direction: SwapDirection,
+    pub lamports: u64,
+}
+
+/// One correction to the persisted positions
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    RecordBuy {
+        mint: String,
+        wallet: String,
+        lamports: u64,
+    },
+    RecordSell {
+        mint: String,
+        lamports: u64,
+    },
+    AddWallet {
+        mint: String,
+        wallet: String,
+    },
+    /// No wallet holds the mint any more; `settled` when a landed sell accounts for the exit
+    Close {
+        mint: String,
+        settled: bool,
+    },
+}
+
+/// Corrections that bring `positions` in line with the wallets' token `holdings` (wallet to
+/// mint to raw amount) and the swaps that `landed` unrecorded. Tokens held without a position
+/// or a landed buy behind them aren't the bot's and are left alone
+pub fn reconcile(
+    positions: &[Position],
+    holdings: &HashMap<String, HashMap<String, u64>>,
+    landed: &[LandedTrade],
+) -> Vec<Fix> {
+    let holders = |mint: &str| {
+        let mut holders = holdings
+            .iter()
+            .filter(|(_, balances)| balances.get(mint).is_some_and(|amount| *amount > 0))
+            .map(|(wallet, _)| wallet.clone())
+            .collect::<Vec<_>>();
+        holders.sort();
+        holders
+    };
+    let mut fixes = vec![];
+    let mut mints = positions
+        .iter()
+        .map(|position| position.mint.clone())
+        .collect::<Vec<_>>();
+    for trade in landed {
+        if !mints.contains(&trade.mint) {
+            mints.push(trade.mint.clone());
+        }
+    }
+    for mint in mints {
+        let position = positions.iter().find(|position| position.mint == mint);
+        let trades = landed.iter().filter(|trade| trade.mint == mint);
+        let (mut bought, mut sold) = (false, false);
+        for trade in trades {
+            fixes.push(match trade.direction {
+                SwapDirection::Buy => {
+                    bought = true;
+                    Fix::RecordBuy {
+                        mint: mint.clone(),
+                        wallet: trade.wallet.clone(),
+                        lamports: trade.lamports,
+                    }
+                }
+                SwapDirection::Sell => {
+                    sold = true;
+                    Fix::RecordSell {
+                        mint: mint.clone(),
+                        lamports: trade.lamports,
+                    }
+                }
+            });
+        }
+        let holders = holders(&mint);
+        if holders.is_empty() {
+            // A landed sell of a mint with no position has nothing left to close
+            if position.is_some() || bought {
+                fixes.push(Fix::Close {
+                    mint,
+                    settled: sold,
+                });
+            }
+            continue;
+        }
+        let known = position.map_or(&[][..], |position| &position.wallets);
+        for wallet in holders {
+            let bought = landed.iter().any(|trade| {
+                trade.mint == mint
+                    && trade.wallet == wallet
+                    && matches!(trade.direction, SwapDirection::Buy)
+            });
+            if !known.contains(&wallet) && !bought {
+                fixes.push(Fix::AddWallet {
+                    mint: mint.clone(),
+                    wallet,
+                });
+            }
+        }
+    }
+    fixes
+}
+
+/// Raw token balances by mint, from jsonParsed token accounts
+fn token_balances(accounts: &[Value]) -> HashMap<String, u64> {
+    let mut balances = HashMap::new();
+    for data in accounts {
+        let info = &data["parsed"]["info"];
+        let (Some(mint), Some(amount)) = (
+            info["mint"].as_str(),
+            info["tokenAmount"]["amount"]
+                .as_str()
+                .and_then(|amount| u64::from_str(amount).ok()),
+        ) else {
+            continue;
+        };
+        *balances.entry(mint.to_string()).or_insert(0) += amount;
+    }
+    balances
+}
+
+/// Every pool wallet's token balances under both token programs
+async fn fetch_holdings(state: &AppState) -> Result<HashMap<String, HashMap<String, u64>>> {
+    let programs = [spl_token::ID, spl_token_2022::ID];
+    let mut holdings = HashMap::new();
+    for wallet in state.wallets.all() {
+        let owner = wallet.pubkey();
+        let mut accounts = vec![];
+        for program in programs {
+            let keyed = state
+                .rpc_nonblocking_client
+                .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(program))
+                .await
+                .with_context(|| format!("Failed to list token accounts of {}", owner))?;
+            for account in keyed {
+                accounts.push(serde_json::to_value(&account.account.data)?);
+            }
+        }
+        holdings.insert(owner.to_string(), token_balances(&accounts));
+    }
+    Ok(holdings)
+}
+
+/// How the previous run's journaled signatures ended: swaps that landed, plus how many failed
+/// or never landed
+async fn settle_pending(
+    state: &AppState,
+    pending: Vec<(String, PendingTx)>,
+) -> Result<(Vec<LandedTrade>, usize)> {
+    let mut landed = vec![];
+    let mut lost = 0;
+    for batch in pending.chunks(STATUS_BATCH) {
+        let signatures = batch
+            .iter()
+            .map(|(signature, _)| Signature::from_str(signature))
+            .collect::<Result<Vec<_>, _>>()?;
+        let statuses = state
+            .rpc_nonblocking_client
+            .get_signature_statuses_with_history(&signatures)
+            .await
+            .context("Failed to read pending signature statuses")?
+            .value;
+        for ((signature, pending), status) in batch.iter().zip(statuses) {
+            if !status.is_some_and(|status| status.err.is_none()) {
+                lost += 1;
+                continue;
+            }
+            let tx = fetch_transaction(state, signature).await?;
+            let Some(event) = SignalEvent::from_transaction("recovery", &tx) else {
+                continue;
+            };
+            if let Some(signal) = parse_trade_signal(&event.json, &pending.wallet) {
+                landed.push(LandedTrade {
+                    wallet: pending.wallet.clone(),
+                    mint: signal.mint,
+                    direction: signal.direction,
+                    lamports: signal.sol_amount,
+                });
+            }
+        }
+    }
+    Ok((landed, lost))
+}
+
+/// Brings the persisted positions in line with the chain after a restart: swaps sent just
+/// before the last run stopped are looked up and recorded if they landed, positions no wallet
+/// holds any more are closed and holders missing from a position are added. Exit monitors
+/// work from the open positions, so whatever is left open is watched again straight away
+pub async fn recover_positions(state: &AppState) -> Result<()> {
+    // Kept in the journal until recorded, so a failed recovery is retried on the next start
+    let pending = PENDING_TXS.all();
+    let journaled = pending
+        .iter()
+        .map(|(signature, _)| signature.clone())
+        .collect::<Vec<_>>();
+    let (landed, lost) = settle_pending(state, pending).await?;
+    let holdings = fetch_holdings(state).await?;
+    let positions = state.positions.open_positions().await;
+    let fixes = reconcile(&positions, &holdings, &landed);
+
+    let mut closed = HashSet::new();
+    for fix in &fixes {
+        match fix {
+            Fix::RecordBuy {
+                mint,
+                wallet,
+                lamports,
+            } => {
+                state.positions.record_buy(mint, None, *lamports, 0).await;
+                state.positions.add_wallet(mint, wallet).await;
+            }
+            Fix::RecordSell { mint, lamports } => {
+                state.positions.record_sell(mint, *lamports, 0, 0.0).await;
+            }
+            Fix::AddWallet { mint, wallet } => {
+                state.positions.add_wallet(mint, wallet).await;
+            }
+            // Without a landed sell the proceeds are unknown, so the gate isn't fed a loss
+            Fix::Close { mint, settled } => {
+                if *settled {
+                    settle_position(state, mint).await;
+                } else {
+                    state.positions.close(mint).await;
+                }
+                closed.insert(mint.clone());
+            }
+        }
+    }
+
+    PENDING_TXS.forget(&journaled);
+
+    let open = state.positions.open_positions().await.len();
+    let summary = format!(
+        "Recovered state: {} open positions monitored, {} of {} pending transactions landed \
+         ({} failed or expired), {} corrections{}",
+        open,
+        landed.len(),
+        journaled.len(),
+        lost,
+        fixes.len(),
+        if closed.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ", closed {}",
+                closed.into_iter().collect::<Vec<_>>().join(", ")
+            )
+        }
+    );
+    let _ = log_message(&summary).await;
+    if !fixes.is_empty() {
+        state.notifier.notify(Event::Info(summary)).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(mint: &str, wallets: &[&str]) -> Position {
+        Position {
+            mint: mint.to_string(),
+            pool_id: None,
+            sol_invested: 1_000,
+            sol_returned: 0,
+            fees_paid: 0,
+            opened_at: Utc::now(),
+            max_hold_secs: None,
+            creator: None,
+            wallets: wallets.iter().map(|w| w.to_string()).collect(),
+            target: None,
+            signal: None,
+            fills: vec![],
+            impaired: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let holdings = HashMap::from([
+            (
+                "w1".to_string(),
+                HashMap::from([("held".to_string(), 10), ("usdc".to_string(), 5)]),
+            ),
+            (
+                "w2".to_string(),
+                HashMap::from([("held".to_string(), 4), ("new".to_string(), 7)]),
+            ),
+        ]);
+        let positions = [position("held", &["w1"]), position("sold", &["w1"])];
+        let landed = [
+            LandedTrade {
+                wallet: "w2".to_string(),
+                mint: "new".to_string(),
+                direction: SwapDirection::Buy,
+                lamports: 500,
+            },
+            LandedTrade {
+                wallet: "w1".to_string(),
+                mint: "sold".to_string(),
+                direction: SwapDirection::Sell,
+                lamports: 900,
+            },
+        ];
+
+        let fixes = reconcile(&positions, &holdings, &landed);
+        let expected = [
+            Fix::AddWallet {
+                mint: "held".to_string(),
+                wallet: "w2".to_string(),
+            },
+            Fix::RecordSell {
+                mint: "sold".to_string(),
+                lamports: 900,
+            },
+            Fix::Close {
+                mint: "sold".to_string(),
+                settled: true,
+            },
+            Fix::RecordBuy {
+                mint: "new".to_string(),
+                wallet: "w2".to_string(),
+                lamports: 500,
+            },
+        ];
+        assert_eq!(fixes, expected);
+    }
+
+    #[tokio::test]
+    async fn test_track_writes_the_journal_before_returning() {
+        use solana_sdk::{
+            hash::Hash, signature::Keypair, signer::Signer, transaction::Transaction,
+        };
+
+        let path = std::env::temp_dir().join(format!("pending-txs-{}.json", std::process::id()));
+        let journal = PendingTxs::persisted(path.clone(), HashMap::new());
+        let signed = |payer: &Keypair| {
+            VersionedTransaction::from(Transaction::new_signed_with_payer(
+                &[],
+                Some(&payer.pubkey()),
+                &[payer],
+                Hash::default(),
+            ))
+        };
+        let on_disk = || {
+            load_json::<HashMap<String, PendingTx>>(&path)
+                .unwrap()
+                .unwrap()
+        };
+
+        let first = Keypair::new();
+        let tracked = journal.track(&signed(&first)).await;
+        assert_eq!(
+            on_disk()[&tracked.signature].wallet,
+            first.pubkey().to_string()
+        );
+
+        // Queued after the first one's removal, so once it's written that is too
+        drop(tracked);
+        let tracked = journal.track(&signed(&Keypair::new())).await;
+        assert_eq!(on_disk().keys().collect::<Vec<_>>(), [&tracked.signature]);
+        let _ = fs::remove_file(&path);
+    }
+}
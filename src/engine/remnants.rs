@@ -0,0 +1,254 @@
+use std::{collections::HashSet, env, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signer::Signer, transaction::Transaction};
+use spl_associated_token_account::get_associated_token_address;
+use tokio::time::Instant;
+
+use crate::{
+    common::{
+        storage::{append_json_line, data_path, EventTime},
+        utils::{log_message, AppState},
+    },
+    core::{ata::KNOWN_ATAS, tx::signing},
+    dex::jupiter::{Jupiter, SOL_MINT},
+    engine::{
+        execution::find_executions, multi_hop::MultiHopConfig, swap::SwapDirection,
+        venues::VenueKind,
+    },
+    services::{idle::idle_sleep, notify::Event},
+};
+
+// Configuration constants
+const SWEEPS_FILE: &str = "remnant_sweeps.jsonl";
+const DEFAULT_SWEEP_SECS: u64 = 300;
+const DEFAULT_MIN_LAMPORTS: u64 = 100_000;
+const SWEEP_SLIPPAGE_BPS: u64 = 300;
+
+#[derive(Debug, Clone)]
+pub struct RemnantConfig {
+    pub interval: Duration,
+    /// Intermediate mints our own routes leave behind; WSOL is always swept
+    pub mints: Vec<String>,
+    /// Remnants quoted below this many lamports are not worth a swap and stay put
+    pub min_lamports: u64,
+}
+
+impl RemnantConfig {
+    /// Reads `REMNANT_SWEEP_SECS` (0 disables), `REMNANT_MINTS` (the exit hop mints by
+    /// default) and `REMNANT_MIN_LAMPORTS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let secs = match env::var("REMNANT_SWEEP_SECS") {
+            Ok(secs) => u64::from_str(&secs).context("REMNANT_SWEEP_SECS must be seconds")?,
+            Err(_) => DEFAULT_SWEEP_SECS,
+        };
+        if secs == 0 {
+            return Ok(None);
+        }
+        let mints = match env::var("REMNANT_MINTS") {
+            Ok(mints) => mints
+                .split(',')
+                .map(str::trim)
+                .filter(|mint| !mint.is_empty())
+                .map(|mint| Pubkey::from_str(mint).map(|_| mint.to_string()))
+                .collect::<Result<Vec<_>, _>>()
+                .context("REMNANT_MINTS must be comma-separated mint addresses")?,
+            Err(_) => MultiHopConfig::from_env()
+                .map(|config| config.hops)
+                .unwrap_or_default(),
+        };
+        let min_lamports = match env::var("REMNANT_MIN_LAMPORTS") {
+            Ok(lamports) => {
+                u64::from_str(&lamports).context("REMNANT_MIN_LAMPORTS must be lamports")?
+            }
+            Err(_) => DEFAULT_MIN_LAMPORTS,
+        };
+        Ok(Some(Self {
+            interval: Duration::from_secs(secs),
+            mints,
+            min_lamports,
+        }))
+    }
+
+    /// Remnant mints to look for, leaving out anything held as a position
+    pub fn candidates(&self, held: &HashSet<String>) -> Vec<&str> {
+        self.mints
+            .iter()
+            .map(String::as_str)
+            .filter(|mint| *mint != SOL_MINT && !held.contains(*mint))
+            .collect()
+    }
+}
+
+/// One remnant folded back into SOL, as recorded in `data/remnant_sweeps.jsonl`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sweep {
+    pub at: DateTime<Utc>,
+    pub wallet: String,
+    pub mint: String,
+    /// Raw token amount swept
+    pub amount: u64,
+    /// SOL the sweep's swap filled for
+    pub lamports: u64,
+    pub signatures: Vec<String>,
+    #[serde(default)]
+    pub time: EventTime,
+}
+
+/// Closes a leftover WSOL account, which unwraps its balance and returns its rent
+async fn unwrap_wsol(leg: &AppState) -> Result<Option<Sweep>> {
+    let owner = leg.wallet.pubkey();
+    let ata = get_associated_token_address(&owner, &spl_token::native_mint::id());
+    let Ok(account) = leg.rpc_nonblocking_client.get_account(&ata).await else {
+        return Ok(None);
+    };
+    let amount = spl_token::state::Account::unpack(&account.data)?.amount;
+    let close = spl_token::instruction::close_account(&spl_token::id(), &ata, &owner, &owner, &[])?;
+    let blockhash = leg.rpc_nonblocking_client.get_latest_blockhash().await?;
+    let signers = [&*leg.wallet as &dyn Signer];
+    let tx = signing(&*leg.wallet, || {
+        Transaction::new_signed_with_payer(&[close], Some(&owner), &signers, blockhash)
+    });
+    let signature = leg
+        .rpc_nonblocking_client
+        .send_and_confirm_transaction(&tx)
+        .await?;
+    KNOWN_ATAS.forget(&ata);
+    Ok(Some(Sweep {
+        at: Utc::now(),
+        wallet: owner.to_string(),
+        mint: SOL_MINT.to_string(),
+        amount,
+        lamports: account.lamports,
+        signatures: vec![signature.to_string()],
+        time: EventTime::now(),
+    }))
+}
+
+/// Sells a stranded intermediate token back to SOL through Jupiter, if it is worth the fee
+async fn swap_remnant(
+    leg: &AppState,
+    config: &RemnantConfig,
+    mint: &str,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<Option<Sweep>> {
+    let client = &leg.rpc_nonblocking_client;
+    let ata = get_associated_token_address(&leg.wallet.pubkey(), &Pubkey::from_str(mint)?);
+    let Ok(balance) = client.get_token_account_balance(&ata).await else {
+        return Ok(None);
+    };
+    let amount = u64::from_str(&balance.amount)?;
+    if amount == 0 {
+        return Ok(None);
+    }
+    let venues = &leg.router.venues;
+    venues.check(VenueKind::Jupiter, &SwapDirection::Sell)?;
+    let jupiter = Jupiter::new(
+        leg.rpc_nonblocking_client.clone(),
+        leg.rpc_client.clone(),
+        leg.wallet.clone(),
+    )
+    .excluding(venues.excluded_dexes(&SwapDirection::Sell));
+    let quote = jupiter
+        .get_quote(mint, SOL_MINT, amount, SWEEP_SLIPPAGE_BPS)
+        .await?;
+    if Jupiter::out_amount(&quote)? < config.min_lamports {
+        return Ok(None);
+    }
+    let signatures = jupiter
+        .execute_quote(quote, jito_client, Instant::now())
+        .await?;
+    Ok(Some(Sweep {
+        at: Utc::now(),
+        wallet: leg.wallet.pubkey().to_string(),
+        mint: mint.to_string(),
+        amount,
+        lamports: find_executions(leg, mint, &signatures)
+            .await
+            .iter()
+            .map(|execution| execution.lamports)
+            .sum(),
+        signatures,
+        time: EventTime::now(),
+    }))
+}
+
+/// Sweeps WSOL and every remnant mint out of one wallet; failures are logged and retried on
+/// the next pass
+pub async fn sweep_wallet(
+    leg: &AppState,
+    config: &RemnantConfig,
+    jito_client: Arc<JitoRpcClient>,
+) -> Vec<Sweep> {
+    let mut sweeps = Vec::new();
+    match unwrap_wsol(leg).await {
+        Ok(sweep) => sweeps.extend(sweep),
+        Err(e) => {
+            let _ = log_message(&format!("Failed to unwrap leftover WSOL: {}", e)).await;
+        }
+    }
+    let held = leg
+        .positions
+        .open_positions()
+        .await
+        .into_iter()
+        .map(|position| position.mint)
+        .collect::<HashSet<_>>();
+    for mint in config.candidates(&held) {
+        match swap_remnant(leg, config, mint, jito_client.clone()).await {
+            Ok(sweep) => sweeps.extend(sweep),
+            Err(e) => {
+                let _ = log_message(&format!("Failed to sweep {} remnant: {}", mint, e)).await;
+            }
+        }
+    }
+    sweeps
+}
+
+/// Periodically folds WSOL and intermediate route tokens left by our own swaps back into SOL
+/// in every pool wallet, recording each sweep, forever
+pub async fn run_remnant_sweeper(
+    config: RemnantConfig,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    loop {
+        idle_sleep(config.interval).await;
+        for wallet in state.wallets.all() {
+            let leg = state.with_wallet(wallet.clone());
+            for sweep in sweep_wallet(&leg, &config, jito_client.clone()).await {
+                if let Err(e) = append_json_line(&data_path(SWEEPS_FILE), &sweep).await {
+                    let _ = log_message(&format!("Failed to record sweep: {}", e)).await;
+                }
+                let message = format!(
+                    "🧹 Swept {} of {} from {} for {} lamports",
+                    sweep.amount, sweep.mint, sweep.wallet, sweep.lamports
+                );
+                let _ = log_message(&message).await;
+                state.notifier.notify(Event::Info(message)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remnant_candidates() {
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string();
+        let usdt = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string();
+        let config = RemnantConfig {
+            interval: Duration::from_secs(1),
+            mints: vec![usdc.clone(), usdt.clone(), SOL_MINT.to_string()],
+            min_lamports: 0,
+        };
+        // A remnant mint that is also held as a position is left alone
+        let held = HashSet::from([usdt]);
+        assert_eq!(config.candidates(&held), vec![usdc.as_str()]);
+    }
+}
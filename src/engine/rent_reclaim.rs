@@ -0,0 +1,255 @@
+use std::{collections::HashSet, env, slice, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signer::Signer, transaction::Transaction,
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::{ata::KNOWN_ATAS, tx::signing},
+    services::{idle::idle_sleep, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_RECLAIM_SECS: u64 = 3_600;
+const DEFAULT_BATCH: usize = 20;
+// Each close adds an account to the transaction, which tops out at 1232 bytes
+const MAX_BATCH: usize = 25;
+
+#[derive(Debug, Clone)]
+pub struct RentReclaimConfig {
+    pub interval: Duration,
+    /// Closes packed into one transaction
+    pub batch: usize,
+}
+
+impl RentReclaimConfig {
+    /// Reads `RENT_RECLAIM_SECS` (default hourly, 0 disables) and `RENT_RECLAIM_BATCH`
+    pub fn from_env() -> Result<Option<Self>> {
+        let secs = match env::var("RENT_RECLAIM_SECS") {
+            Ok(secs) => u64::from_str(&secs).context("RENT_RECLAIM_SECS must be seconds")?,
+            Err(_) => DEFAULT_RECLAIM_SECS,
+        };
+        if secs == 0 {
+            return Ok(None);
+        }
+        let batch = match env::var("RENT_RECLAIM_BATCH") {
+            Ok(batch) => usize::from_str(&batch).context("RENT_RECLAIM_BATCH must be a count")?,
+            Err(_) => DEFAULT_BATCH,
+        };
+        if batch == 0 || batch > MAX_BATCH {
+            return Err(anyhow!("RENT_RECLAIM_BATCH must be 1 to {}", MAX_BATCH));
+        }
+        Ok(Some(Self {
+            interval: Duration::from_secs(secs),
+            batch,
+        }))
+    }
+}
+
+/// An empty token account the wallet can close
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmptyAccount {
+    pub address: Pubkey,
+    pub program: Pubkey,
+    pub lamports: u64,
+}
+
+/// Whether a jsonParsed token account is empty and closable by `owner`, leaving out `keep`
+/// mints. Frozen accounts and those with another close authority can't be closed
+pub fn is_closable(data: &Value, owner: &str, keep: &HashSet<String>) -> bool {
+    let info = &data["parsed"]["info"];
+    info["tokenAmount"]["amount"].as_str() == Some("0")
+        && info["state"].as_str() == Some("initialized")
+        && info["owner"].as_str() == Some(owner)
+        && info["closeAuthority"]
+            .as_str()
+            .map_or(true, |authority| authority == owner)
+        && info["mint"]
+            .as_str()
+            .is_some_and(|mint| !keep.contains(mint))
+}
+
+/// Empty token accounts of the wallet under both token programs, except for held mints
+async fn find_empty_accounts(state: &AppState) -> Result<Vec<EmptyAccount>> {
+    let owner = state.wallet.pubkey();
+    // Open positions and copies in flight may be about to use their accounts again
+    let mut keep = state
+        .positions
+        .open_positions()
+        .await
+        .into_iter()
+        .map(|position| position.mint)
+        .collect::<HashSet<_>>();
+    keep.extend(
+        state
+            .positions
+            .pending_intents()
+            .await
+            .into_iter()
+            .map(|intent| intent.mint),
+    );
+
+    let mut empty = vec![];
+    for program in [spl_token::id(), spl_token_2022::ID] {
+        let accounts = state
+            .rpc_nonblocking_client
+            .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(program))
+            .await
+            .with_context(|| format!("Failed to list token accounts of {}", owner))?;
+        for keyed in accounts {
+            let data = serde_json::to_value(&keyed.account.data)?;
+            if is_closable(&data, &owner.to_string(), &keep) {
+                empty.push(EmptyAccount {
+                    address: Pubkey::from_str(&keyed.pubkey)?,
+                    program,
+                    lamports: keyed.account.lamports,
+                });
+            }
+        }
+    }
+    Ok(empty)
+}
+
+fn close_instruction(account: &EmptyAccount, owner: &Pubkey) -> Result<Instruction> {
+    let close = if account.program == spl_token_2022::ID {
+        spl_token_2022::instruction::close_account(
+            &account.program,
+            &account.address,
+            owner,
+            owner,
+            &[],
+        )?
+    } else {
+        spl_token::instruction::close_account(
+            &account.program,
+            &account.address,
+            owner,
+            owner,
+            &[],
+        )?
+    };
+    Ok(close)
+}
+
+async fn close_batch(state: &AppState, accounts: &[EmptyAccount]) -> Result<String> {
+    let owner = state.wallet.pubkey();
+    let closes = accounts
+        .iter()
+        .map(|account| close_instruction(account, &owner))
+        .collect::<Result<Vec<_>>>()?;
+    let blockhash = state.rpc_nonblocking_client.get_latest_blockhash().await?;
+    let signers = [&*state.wallet as &dyn Signer];
+    let tx = signing(&*state.wallet, || {
+        Transaction::new_signed_with_payer(&closes, Some(&owner), &signers, blockhash)
+    });
+    let signature = state
+        .rpc_nonblocking_client
+        .send_and_confirm_transaction(&tx)
+        .await?;
+    for account in accounts {
+        KNOWN_ATAS.forget(&account.address);
+    }
+    Ok(signature.to_string())
+}
+
+/// Closes every empty token account of the wallet `batch` at a time, returning how many were
+/// closed and the rent they returned. A batch that fails is retried one account at a time,
+/// so one account that can't be closed doesn't hold up the rest
+pub async fn reclaim_rent(state: &AppState, batch: usize) -> Result<(usize, u64)> {
+    let empty = find_empty_accounts(state).await?;
+    let (mut closed, mut lamports) = (0, 0);
+    for chunk in empty.chunks(batch) {
+        if close_batch(state, chunk).await.is_ok() {
+            closed += chunk.len();
+            lamports += chunk.iter().map(|account| account.lamports).sum::<u64>();
+            continue;
+        }
+        for account in chunk {
+            match close_batch(state, slice::from_ref(account)).await {
+                Ok(_) => {
+                    closed += 1;
+                    lamports += account.lamports;
+                }
+                Err(e) => {
+                    let _ = log_message(&format!(
+                        "Failed to close token account {}: {}",
+                        account.address, e
+                    ))
+                    .await;
+                }
+            }
+        }
+    }
+    Ok((closed, lamports))
+}
+
+/// Periodically closes empty token accounts in every pool wallet to get their rent back,
+/// forever
+pub async fn run_rent_reclaim(config: RentReclaimConfig, state: AppState) {
+    loop {
+        idle_sleep(config.interval).await;
+        for wallet in state.wallets.all() {
+            let leg = state.with_wallet(wallet.clone());
+            match reclaim_rent(&leg, config.batch).await {
+                Ok((0, _)) => {}
+                Ok((closed, lamports)) => {
+                    let message = format!(
+                        "♻️ Closed {} empty token accounts in {}, reclaiming {:.4} SOL",
+                        closed,
+                        wallet.pubkey(),
+                        lamports as f64 / 1e9
+                    );
+                    let _ = log_message(&message).await;
+                    state.notifier.notify(Event::Info(message)).await;
+                }
+                Err(e) => {
+                    let _ = log_message(&format!("Rent reclaim failed: {}", e)).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_closable_accounts() {
+        let owner = Pubkey::new_unique().to_string();
+        let account = |mint: &str, amount: &str, state: &str| {
+            json!({ "parsed": { "info": {
+                "mint": mint,
+                "owner": owner,
+                "state": state,
+                "tokenAmount": { "amount": amount, "decimals": 6 },
+            }}})
+        };
+        let keep = HashSet::from(["held".to_string()]);
+        assert!(is_closable(
+            &account("sold", "0", "initialized"),
+            &owner,
+            &keep
+        ));
+        assert!(!is_closable(
+            &account("sold", "15", "initialized"),
+            &owner,
+            &keep
+        ));
+        assert!(!is_closable(
+            &account("held", "0", "initialized"),
+            &owner,
+            &keep
+        ));
+        assert!(!is_closable(&account("sold", "0", "frozen"), &owner, &keep));
+
+        let mut delegated = account("sold", "0", "initialized");
+        delegated["parsed"]["info"]["closeAuthority"] = json!(Pubkey::new_unique().to_string());
+        assert!(!is_closable(&delegated, &owner, &keep));
+    }
+}
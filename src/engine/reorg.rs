@@ -0,0 +1,242 @@
+use std::{collections::HashSet, env, str::FromStr, sync::Arc, time::Duration};
+
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, signer::Signer};
+use tokio::{
+    sync::RwLock,
+    time::{sleep, Instant},
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::{
+        execution::find_executions,
+        swap::{raydium_swap, SwapDirection},
+        wallets::{holder_balances, position_balance},
+    },
+    risk::expectancy::{record_exit, settle_position},
+};
+
+// Configuration constants
+const DEFAULT_CONFIRM_TIMEOUT_MS: u64 = 20_000;
+const POLL_INTERVAL_MS: u64 = 500;
+const UNWIND_SLIPPAGE_BPS: u64 = 2_500;
+
+/// What to do with a copy whose source signal never reached confirmed commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackAction {
+    /// Sell back whatever the copy bought
+    Unwind,
+    /// Keep the position but mark the mint as built on a phantom signal
+    Flag,
+}
+
+impl FromStr for RollbackAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unwind" => Ok(RollbackAction::Unwind),
+            "flag" => Ok(RollbackAction::Flag),
+            _ => Err(anyhow::anyhow!(
+                "Invalid rollback action: '{}'. Use 'unwind' or 'flag'",
+                s
+            )),
+        }
+    }
+}
+
+/// Final status of a processed-commitment signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalStatus {
+    Confirmed,
+    Failed,
+    Dropped,
+}
+
+/// Watches processed-commitment signals until they confirm or are rolled back
+pub struct ReorgGuard {
+    client: Arc<solana_client::nonblocking::rpc_client::RpcClient>,
+    timeout: Duration,
+    action: RollbackAction,
+    flagged: RwLock<HashSet<String>>,
+}
+
+impl ReorgGuard {
+    /// Creates a guard configured from `REORG_CONFIRM_TIMEOUT_MS` and `REORG_ACTION`
+    pub fn new(client: Arc<solana_client::nonblocking::rpc_client::RpcClient>) -> Self {
+        let timeout_ms = env::var("REORG_CONFIRM_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_CONFIRM_TIMEOUT_MS);
+        let action = env::var("REORG_ACTION")
+            .ok()
+            .and_then(|v| RollbackAction::from_str(&v).ok())
+            .unwrap_or(RollbackAction::Flag);
+
+        Self {
+            client,
+            timeout: Duration::from_millis(timeout_ms),
+            action,
+            flagged: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Polls the signal's signature until it reaches confirmed commitment, fails, or times out
+    pub async fn wait_for_confirmation(&self, signature: &Signature) -> SignalStatus {
+        let deadline = Instant::now() + self.timeout;
+
+        while Instant::now() < deadline {
+            if let Ok(response) = self.client.get_signature_statuses(&[*signature]).await {
+                if let Some(Some(status)) = response.value.first() {
+                    if status.err.is_some() {
+                        return SignalStatus::Failed;
+                    }
+                    if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                        return SignalStatus::Confirmed;
+                    }
+                }
+            }
+            sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+        }
+
+        SignalStatus::Dropped
+    }
+
+    /// Returns true if a copy on this mint was built on a rolled-back signal
+    pub async fn is_flagged(&self, mint: &str) -> bool {
+        self.flagged.read().await.contains(mint)
+    }
+
+    /// Clears the phantom-signal flag for a mint
+    pub async fn clear_flag(&self, mint: &str) {
+        self.flagged.write().await.remove(mint);
+    }
+
+    /// Verifies the source signal of an executed copy and rolls it back if it never confirmed.
+    /// `fills` are the signatures of the copy itself; an unwind sells only what they bought
+    #[allow(clippy::too_many_arguments)]
+    pub async fn guard_copy(
+        &self,
+        signature: String,
+        mint: String,
+        direction: String,
+        pool_id: Option<String>,
+        fills: Vec<String>,
+        state: AppState,
+        jito_client: Arc<JitoRpcClient>,
+    ) {
+        let Ok(sig) = Signature::from_str(&signature) else {
+            return;
+        };
+
+        let status = self.wait_for_confirmation(&sig).await;
+        if status == SignalStatus::Confirmed {
+            return;
+        }
+
+        let _ = log_message(&format!(
+            "Signal {} for {} was {:?} at confirmed commitment",
+            signature, mint, status
+        ))
+        .await;
+
+        // Only a copied buy leaves us holding something we can give back
+        if direction != "buy" || self.action == RollbackAction::Flag {
+            self.flagged.write().await.insert(mint);
+            return;
+        }
+
+        // Tokens the wallet held before the phantom copy stay in the position
+        let bought: u64 = find_executions(&state, &mint, &fills)
+            .await
+            .iter()
+            .map(|execution| execution.tokens)
+            .sum();
+        let wallet = state.wallet.pubkey();
+        let held = holder_balances(&state, &mint)
+            .await
+            .into_iter()
+            .find(|(holder, _)| holder.pubkey() == wallet)
+            .map_or(0, |(_, balance)| balance);
+        let amount = bought.min(held);
+        if amount == 0 {
+            let _ = log_message(&format!("No tokens of the phantom copy on {} left", mint)).await;
+            self.flagged.write().await.insert(mint);
+            return;
+        }
+
+        let sold = match pool_id {
+            Some(pool_id) => {
+                raydium_swap(
+                    state.clone(),
+                    amount,
+                    "sell",
+                    pool_id,
+                    UNWIND_SLIPPAGE_BPS,
+                    &mint,
+                    jito_client,
+                    Instant::now(),
+                )
+                .await
+            }
+            None => {
+                state
+                    .router
+                    .swap(
+                        state.clone(),
+                        &mint,
+                        amount,
+                        SwapDirection::Sell,
+                        UNWIND_SLIPPAGE_BPS,
+                        jito_client,
+                        Instant::now(),
+                    )
+                    .await
+            }
+        };
+        match sold {
+            Ok(signatures) => {
+                record_exit(&state, &mint, &signatures).await;
+                // Other wallets may still hold their legs of the same position
+                if position_balance(&state, &mint).await == 0 {
+                    settle_position(&state, &mint).await;
+                }
+                let _ = log_message(&format!(
+                    "Unwound phantom copy on {}: sold {} tokens",
+                    mint, amount
+                ))
+                .await;
+            }
+            Err(e) => {
+                let _ = log_message(&format!("Failed to unwind copy on {}: {}", mint, e)).await;
+                self.flagged.write().await.insert(mint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_rollback_action() {
+        assert_eq!(RollbackAction::from_str("Unwind").unwrap(), RollbackAction::Unwind);
+        assert_eq!(RollbackAction::from_str("flag").unwrap(), RollbackAction::Flag);
+        assert!(RollbackAction::from_str("sell").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_flags_until_cleared() {
+        let client = solana_client::nonblocking::rpc_client::RpcClient::new(
+            "http://localhost:8899".to_string(),
+        );
+        let guard = ReorgGuard::new(Arc::new(client));
+        assert!(!guard.is_flagged("mint").await);
+        guard.flagged.write().await.insert("mint".to_string());
+        assert!(guard.is_flagged("mint").await);
+        guard.clear_flag("mint").await;
+        assert!(!guard.is_flagged("mint").await);
+    }
+}
@@ -0,0 +1,430 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::Instant;
+
+use crate::{
+    common::{
+        cache::BoundedCache,
+        utils::{log_message, AppState},
+    },
+    core::errors::{Reaction, SwapError},
+    dex::{
+        jupiter::Jupiter,
+        pump::{get_bonding_curve_account, get_pump_amm_pool_pda, Pump, PUMP_PROGRAM_ID},
+        pump_swap::PumpSwap,
+        raydium::{get_pool_state_by_mint, Raydium},
+        raydium_clmm::{self, RaydiumClmm},
+        raydium_cpmm::{self, RaydiumCpmm},
+    },
+    engine::{
+        slippage::{is_slippage_error, SlippageRetry},
+        swap::{SwapDirection, SwapInType},
+        venues::{VenueKind, VenuePolicy},
+    },
+};
+
+// Configuration constants
+const ROUTE_CACHE_ENTRIES: usize = 5_000;
+const ROUTE_CACHE_TTL_SECS: u64 = 3_600;
+
+/// Where a mint's liquidity currently lives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Venue {
+    /// Still trading on the pump.fun bonding curve
+    BondingCurve,
+    /// Migrated into its canonical PumpSwap pool
+    PumpSwap { pool: Pubkey },
+    /// Migrated into a Raydium AMM v4 pool
+    Raydium { pool: Pubkey },
+    /// Trades in a Raydium concentrated liquidity pool
+    RaydiumClmm { pool: Pubkey },
+    /// Trades in a Raydium CP-Swap (CPMM) pool
+    RaydiumCpmm { pool: Pubkey },
+    /// No pool we build for directly; let the aggregator find one
+    Jupiter,
+}
+
+/// Resolves and caches the venue for each mint and dispatches swaps to it
+pub struct Router {
+    routes: BoundedCache<String, Venue>,
+    slippage_retry: Option<SlippageRetry>,
+    /// Venues swaps may execute on, per direction
+    pub venues: VenuePolicy,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new(VenuePolicy::default())
+    }
+}
+
+impl Router {
+    /// Creates a router limited to `venues`, with slippage retries if `SLIPPAGE_CEILING_BPS` is
+    /// set
+    pub fn new(venues: VenuePolicy) -> Self {
+        Self {
+            routes: BoundedCache::from_env(
+                "routes",
+                ROUTE_CACHE_ENTRIES,
+                Some(Duration::from_secs(ROUTE_CACHE_TTL_SECS)),
+            ),
+            slippage_retry: SlippageRetry::from_env(),
+            venues,
+        }
+    }
+
+    /// Returns the cached venue for a mint, resolving it on a miss
+    pub async fn route(&self, state: &AppState, mint: &str) -> Result<Venue> {
+        if let Some(venue) = self.cached(mint).await {
+            return Ok(venue);
+        }
+
+        let venue = resolve_venue(state, mint).await?;
+        self.routes.insert(mint.to_string(), venue.clone()).await;
+        Ok(venue)
+    }
+
+    /// The venue cached for a mint, if any
+    pub async fn cached(&self, mint: &str) -> Option<Venue> {
+        self.routes.get(&mint.to_string()).await
+    }
+
+    /// Feeds an observed bonding curve `complete` flag; a flip drops the cached curve route
+    pub async fn observe_curve(&self, mint: &str, complete: bool) {
+        if !complete {
+            return;
+        }
+        self.routes
+            .remove_if(&mint.to_string(), |venue| *venue == Venue::BondingCurve)
+            .await;
+    }
+
+    /// Forgets the cached venue for a mint
+    pub async fn invalidate(&self, mint: &str) {
+        self.routes.remove(&mint.to_string()).await;
+    }
+
+    /// Forgets every cached venue
+    pub async fn clear_cache(&self) {
+        self.routes.clear().await;
+    }
+
+    /// Swaps on whichever venue currently holds the mint's liquidity, retrying
+    /// slippage failures at higher slippage when configured
+    pub async fn swap(
+        &self,
+        state: AppState,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        slippage: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>> {
+        let mut slippage = slippage;
+        let mut retries = 0;
+        loop {
+            let result = self
+                .swap_once(
+                    state.clone(),
+                    mint,
+                    amount_in,
+                    swap_direction.clone(),
+                    slippage,
+                    jito_client.clone(),
+                    timestamp,
+                )
+                .await;
+
+            let next = match (&result, &self.slippage_retry) {
+                (Err(e), Some(retry)) if is_slippage_error(e) => retry.next(slippage, retries),
+                _ => None,
+            };
+            let Some(next) = next else {
+                if result.is_ok() && retries > 0 {
+                    let _ = log_message(&format!(
+                        "{} {} landed at {} bps slippage after {} retries",
+                        swap_direction.as_str(),
+                        mint,
+                        slippage,
+                        retries
+                    ))
+                    .await;
+                }
+                return result;
+            };
+
+            let _ = log_message(&format!(
+                "{} {} exceeded {} bps slippage, retrying at {} bps",
+                swap_direction.as_str(),
+                mint,
+                slippage,
+                next
+            ))
+            .await;
+            slippage = next;
+            retries += 1;
+        }
+    }
+
+    async fn swap_once(
+        &self,
+        state: AppState,
+        mint: &str,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        slippage: u64,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) -> Result<Vec<String>> {
+        let venue = self.route(&state, mint).await?;
+        self.venues
+            .check(VenueKind::from(&venue), &swap_direction)?;
+        let result = swap_on_venue(
+            &venue,
+            state.clone(),
+            mint,
+            amount_in,
+            swap_direction.clone(),
+            slippage,
+            jito_client.clone(),
+            timestamp,
+        )
+        .await;
+
+        // The cached venue may have gone stale since it was resolved; re-resolve once
+        let reroute = match result.as_ref().map_err(SwapError::reaction) {
+            Ok(_) => false,
+            Err(Some(reaction)) => reaction == Reaction::Reroute,
+            // An undecoded failure on the curve may still be the curve completing mid-send
+            Err(None) => {
+                venue == Venue::BondingCurve && curve_complete(&state, mint).await.unwrap_or(false)
+            }
+        };
+        if reroute {
+            self.invalidate(mint).await;
+            let stale = venue;
+            let venue = self.route(&state, mint).await?;
+            if venue != stale {
+                let _ = log_message(&format!(
+                    "{} moved from {:?}, rerouting to {:?}",
+                    mint, stale, venue
+                ))
+                .await;
+                self.venues
+                    .check(VenueKind::from(&venue), &swap_direction)?;
+                return Ok(swap_on_venue(
+                    &venue,
+                    state,
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await?);
+            }
+        }
+
+        Ok(result?)
+    }
+}
+
+/// Returns the bonding curve's `complete` flag, or an error if there is no curve
+async fn curve_complete(state: &AppState, mint: &str) -> Result<bool> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let (_, _, curve) =
+        get_bonding_curve_account(state.rpc_client.clone(), &mint_pubkey, &PUMP_PROGRAM_ID).await?;
+    Ok(curve.complete)
+}
+
+/// Inspects on-chain accounts to find where a mint trades
+async fn resolve_venue(state: &AppState, mint: &str) -> Result<Venue> {
+    if let Ok(false) = curve_complete(state, mint).await {
+        return Ok(Venue::BondingCurve);
+    }
+
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let pump_pool = get_pump_amm_pool_pda(&mint_pubkey)?;
+    if state
+        .rpc_nonblocking_client
+        .get_account(&pump_pool)
+        .await
+        .is_ok()
+    {
+        return Ok(Venue::PumpSwap { pool: pump_pool });
+    }
+
+    if let Ok((pool, _)) = get_pool_state_by_mint(state.rpc_client.clone(), mint).await {
+        return Ok(Venue::Raydium { pool });
+    }
+
+    let client = &state.rpc_nonblocking_client;
+    if let Ok(pool) = raydium_cpmm::find_pool_by_mint(client, &mint_pubkey).await {
+        return Ok(Venue::RaydiumCpmm { pool: pool.id });
+    }
+
+    if let Ok(pool) = raydium_clmm::find_pool_by_mint(client, &mint_pubkey).await {
+        return Ok(Venue::RaydiumClmm { pool: pool.id });
+    }
+
+    Ok(Venue::Jupiter)
+}
+
+async fn swap_on_venue(
+    venue: &Venue,
+    state: AppState,
+    mint: &str,
+    amount_in: u64,
+    swap_direction: SwapDirection,
+    slippage: u64,
+    jito_client: Arc<JitoRpcClient>,
+    timestamp: Instant,
+) -> Result<Vec<String>, SwapError> {
+    match venue {
+        Venue::BondingCurve => {
+            let swapx = Pump::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    SwapInType::Qty,
+                    swap_direction,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await
+        }
+        Venue::Raydium { pool } => {
+            let swapx = Raydium::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            swapx
+                .swap_by_mint(
+                    mint,
+                    swap_direction,
+                    amount_in,
+                    pool.to_string(),
+                    slippage,
+                    timestamp,
+                    jito_client,
+                )
+                .await
+        }
+        Venue::RaydiumClmm { pool } => {
+            let swapx =
+                RaydiumClmm::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    pool,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await
+        }
+        Venue::RaydiumCpmm { pool } => {
+            let swapx =
+                RaydiumCpmm::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    pool,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await
+        }
+        Venue::PumpSwap { pool } => {
+            let swapx = PumpSwap::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    pool,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await
+        }
+        Venue::Jupiter => {
+            let excluded = state.router.venues.excluded_dexes(&swap_direction);
+            let swapx = Jupiter::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet)
+                .excluding(excluded);
+            swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::engine::venues::VenueRule;
+
+    #[tokio::test]
+    async fn test_completed_curve_drops_only_curve_routes() {
+        let router = Router::default();
+        let pool = Pubkey::new_unique();
+        router
+            .routes
+            .insert("curve".to_string(), Venue::BondingCurve)
+            .await;
+        router
+            .routes
+            .insert("pool".to_string(), Venue::PumpSwap { pool })
+            .await;
+
+        // A curve still trading keeps its route
+        router.observe_curve("curve", false).await;
+        assert_eq!(router.cached("curve").await, Some(Venue::BondingCurve));
+        router.observe_curve("curve", true).await;
+        assert_eq!(router.cached("curve").await, None);
+        // A mint already routed past its curve is left alone
+        router.observe_curve("pool", true).await;
+        assert_eq!(router.cached("pool").await, Some(Venue::PumpSwap { pool }));
+
+        router.invalidate("pool").await;
+        assert_eq!(router.cached("pool").await, None);
+    }
+
+    #[test]
+    fn test_pump_swap_routes_skip_the_aggregator() {
+        let venue = Venue::PumpSwap {
+            pool: Pubkey::new_unique(),
+        };
+        let router = Router::new(VenuePolicy {
+            buy: VenueRule {
+                allow: None,
+                deny: HashSet::from([VenueKind::Jupiter]),
+            },
+            sell: VenueRule::default(),
+        });
+        // Disabling Jupiter leaves migrated pump.fun tokens tradable on their own pool
+        assert!(router
+            .venues
+            .check(VenueKind::from(&venue), &SwapDirection::Buy)
+            .is_ok());
+    }
+}
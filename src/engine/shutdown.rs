@@ -0,0 +1,129 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        LazyLock,
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use tokio::{sync::Notify, time::timeout};
+
+// Configuration constants
+const DEFAULT_DRAIN_SECS: u64 = 90;
+
+/// Process-wide shutdown state, consulted before every trade is sent
+pub static SHUTDOWN: LazyLock<Shutdown> = LazyLock::new(Shutdown::default);
+
+/// Stops new trades once shutdown starts and counts the ones still in flight
+#[derive(Default)]
+pub struct Shutdown {
+    stopping: AtomicBool,
+    in_flight: AtomicUsize,
+    drained: Notify,
+}
+
+/// Held for as long as a trade is being sent and confirmed
+pub struct InFlight<'a>(&'a Shutdown);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.drained.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Registers a trade about to be sent, or `None` once shutdown has started
+    pub fn begin_trade(&self) -> Option<InFlight<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self);
+        // Checked after counting, so `drain` can't miss a trade that slipped in
+        (!self.is_stopping()).then_some(guard)
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits up to `limit` for in-flight trades to confirm or expire, returning how many are
+    /// still outstanding
+    pub async fn drain(&self, limit: Duration) -> usize {
+        let _ = timeout(limit, async {
+            loop {
+                let drained = self.drained.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                drained.await;
+            }
+        })
+        .await;
+        self.in_flight()
+    }
+}
+
+/// How long shutdown waits for in-flight trades, from `SHUTDOWN_DRAIN_SECS` (default 90, about
+/// as long as a blockhash stays valid)
+pub fn drain_timeout() -> Result<Duration> {
+    let secs = env::var("SHUTDOWN_DRAIN_SECS")
+        .ok()
+        .map(|v| u64::from_str(&v))
+        .transpose()
+        .context("Invalid SHUTDOWN_DRAIN_SECS")?
+        .unwrap_or(DEFAULT_DRAIN_SECS);
+    Ok(Duration::from_secs(secs))
+}
+
+/// Resolves on the first SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drains_in_flight_trades() {
+        let shutdown = Shutdown::default();
+        let trade = shutdown.begin_trade().unwrap();
+        shutdown.stop();
+        assert!(shutdown.begin_trade().is_none());
+        assert_eq!(shutdown.in_flight(), 1);
+        assert_eq!(shutdown.drain(Duration::from_millis(10)).await, 1);
+
+        let drain = shutdown.drain(Duration::from_secs(5));
+        tokio::pin!(drain);
+        tokio::select! {
+            _ = &mut drain => panic!("drained with a trade in flight"),
+            _ = tokio::task::yield_now() => {}
+        }
+        drop(trade);
+        assert_eq!(drain.await, 0);
+    }
+}
@@ -0,0 +1,553 @@
+use std::sync::LazyLock;
+
+use borsh::BorshDeserialize;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    dex::pump::PUMP_PROGRAM_ID,
+    engine::{
+        jupiter_signal::parse_jupiter_trade, raydium_signal::parse_raydium_trade,
+        swap::SwapDirection,
+    },
+};
+
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+/// pump.fun's program id as it appears in `programId` fields, rendered once
+pub static PUMP_PROGRAM: LazyLock<String> = LazyLock::new(|| PUMP_PROGRAM_ID.to_string());
+/// Anchor log line emitted by pump.fun's `create` instruction
+const PUMP_CREATE_LOG: &str = "Program log: Instruction: Create";
+// Positions of the mint and creator in pump.fun's `create` accounts
+const CREATE_MINT_INDEX: usize = 0;
+const CREATE_USER_INDEX: usize = 7;
+/// Prefix of the log lines carrying Anchor events
+const PROGRAM_DATA_LOG: &str = "Program data: ";
+/// Anchor discriminator of pump.fun's `CreateEvent`
+const PUMP_CREATE_EVENT: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+/// Anchor discriminator of pump.fun's `TradeEvent`
+const PUMP_TRADE_EVENT: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+
+/// A target wallet's trade, reconstructed from its balance changes
+#[derive(Debug, Clone)]
+pub struct TradeSignal {
+    pub signature: String,
+    pub slot: u64,
+    pub mint: String,
+    pub direction: SwapDirection,
+    /// Lamports the target spent (buy) or received (sell), net of the network fee
+    pub sol_amount: u64,
+    /// Raw token units the target received (buy) or sent (sell)
+    pub token_amount: u64,
+    /// Target's raw token balance before the trade
+    pub token_pre_balance: u64,
+    pub decimals: u8,
+}
+
+/// A pump.fun token the target launched, with its dev buy if it made one in the same transaction
+#[derive(Debug, Clone)]
+pub struct LaunchSignal {
+    pub signature: String,
+    pub slot: u64,
+    pub mint: String,
+    pub dev_buy: Option<TradeSignal>,
+}
+
+impl LaunchSignal {
+    /// The launch as a buy of its mint for copy timing: the dev buy, or an empty one without
+    pub fn as_buy(&self) -> TradeSignal {
+        self.dev_buy.clone().unwrap_or_else(|| TradeSignal {
+            signature: self.signature.clone(),
+            slot: self.slot,
+            mint: self.mint.clone(),
+            direction: SwapDirection::Buy,
+            sol_amount: 0,
+            token_amount: 0,
+            token_pre_balance: 0,
+            // pump.fun mints all have 6 decimals
+            decimals: 6,
+        })
+    }
+}
+
+/// A new pump.fun token, decoded from the `CreateEvent` its creation logs
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpCreate {
+    pub signature: String,
+    pub slot: u64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+}
+
+#[derive(borsh_derive::BorshDeserialize)]
+struct CreateEvent {
+    name: String,
+    symbol: String,
+    uri: String,
+    mint: [u8; 32],
+    bonding_curve: [u8; 32],
+    user: [u8; 32],
+}
+
+/// One curve trade, decoded from the `TradeEvent` pump.fun logs for every buy and sell
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpTrade {
+    pub mint: Pubkey,
+    /// Lamports that went into (buy) or came out of (sell) the curve, before the protocol fee
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    /// Curve reserves right after the trade
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+impl PumpTrade {
+    pub fn direction(&self) -> SwapDirection {
+        if self.is_buy {
+            SwapDirection::Buy
+        } else {
+            SwapDirection::Sell
+        }
+    }
+}
+
+#[derive(borsh_derive::BorshDeserialize)]
+struct TradeEvent {
+    mint: [u8; 32],
+    sol_amount: u64,
+    token_amount: u64,
+    is_buy: bool,
+    user: [u8; 32],
+    timestamp: i64,
+    virtual_sol_reserves: u64,
+    virtual_token_reserves: u64,
+}
+
+impl TradeSignal {
+    /// Applies the share of its holdings the target sold to our own balance
+    pub fn mirrored_sell_amount(&self, my_balance: u64) -> u64 {
+        if self.token_pre_balance == 0 || self.token_amount >= self.token_pre_balance {
+            return my_balance;
+        }
+        (my_balance as u128 * self.token_amount as u128 / self.token_pre_balance as u128) as u64
+    }
+}
+
+/// Index of `key` in the transaction's account keys (jsonParsed or plain encoding)
+pub fn account_index(tx: &Value, key: &str) -> Option<usize> {
+    tx["transaction"]["message"]["accountKeys"]
+        .as_array()?
+        .iter()
+        .position(|account| account["pubkey"].as_str().or(account.as_str()) == Some(key))
+}
+
+/// Returns true if any top-level instruction invokes `program_id`
+pub fn invokes_program(tx: &Value, program_id: &str) -> bool {
+    tx["transaction"]["message"]["instructions"]
+        .as_array()
+        .is_some_and(|ixs| {
+            ixs.iter()
+                .any(|ix| ix["programId"].as_str() == Some(program_id))
+        })
+}
+
+/// The `index`-th account of the first top-level instruction for `program_id`
+pub fn instruction_account(tx: &Value, program_id: &str, index: usize) -> Option<String> {
+    tx["transaction"]["message"]["instructions"]
+        .as_array()?
+        .iter()
+        .find(|ix| ix["programId"].as_str() == Some(program_id))?["accounts"][index]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Every instruction in execution order with its call depth: top-level instructions at 1,
+/// followed by the inner instructions they invoked
+pub fn flat_instructions(tx: &Value) -> Vec<(&Value, u64)> {
+    let inner = tx["meta"]["innerInstructions"].as_array();
+    let mut flat = Vec::new();
+    let top_level = tx["transaction"]["message"]["instructions"].as_array();
+    for (i, ix) in top_level.into_iter().flatten().enumerate() {
+        flat.push((ix, 1));
+        let nested = inner
+            .into_iter()
+            .flatten()
+            .filter(|set| set["index"].as_u64() == Some(i as u64))
+            .flat_map(|set| set["instructions"].as_array().into_iter().flatten());
+        flat.extend(nested.map(|ix| (ix, ix["stackHeight"].as_u64().unwrap_or(2))));
+    }
+    flat
+}
+
+/// Sum of `owner`'s raw balances per mint in a pre/postTokenBalances array
+pub fn token_balance(balances: &Value, owner: &str, mint: &str) -> (u64, u8) {
+    balances
+        .as_array()
+        .map(|balances| {
+            balances
+                .iter()
+                .filter(|b| b["owner"].as_str() == Some(owner) && b["mint"].as_str() == Some(mint))
+                .fold((0u64, 0u8), |(sum, _), b| {
+                    let amount = b["uiTokenAmount"]["amount"]
+                        .as_str()
+                        .and_then(|a| a.parse::<u64>().ok())
+                        .unwrap_or(0);
+                    let decimals = b["uiTokenAmount"]["decimals"].as_u64().unwrap_or(0) as u8;
+                    (sum.saturating_add(amount), decimals)
+                })
+        })
+        .unwrap_or((0, 0))
+}
+
+/// Decodes the target's trade from a `transactionSubscribe` notification. Amounts come from
+/// pump.fun's `TradeEvent`, Jupiter's swap events or Raydium's swap transfers when the
+/// transaction has them for the target, otherwise from its balance changes
+pub fn parse_trade_signal(json: &Value, target: &str) -> Option<TradeSignal> {
+    let signal = balance_trade_signal(json, target)?;
+    let tx = &json["params"]["result"]["transaction"];
+    let event = pump_trades(tx)
+        .into_iter()
+        .find(|event| event.user.to_string() == target && event.mint.to_string() == signal.mint);
+    if let Some(event) = event {
+        // Balances also move with fees, tips and anything else bundled into the transaction
+        return Some(TradeSignal {
+            direction: event.direction(),
+            sol_amount: event.sol_amount,
+            token_amount: event.token_amount,
+            ..signal
+        });
+    }
+    // A router's own swap events cover the whole route, whichever venues it went through
+    if let Some(routed) = parse_jupiter_trade(json, target) {
+        if routed.mint == signal.mint {
+            return Some(routed);
+        }
+    }
+    // Raydium's transfers are exact too, and see SOL paid from a standing WSOL account
+    if let Some(trade) = parse_raydium_trade(json, target) {
+        if trade.signal.mint == signal.mint {
+            return Some(trade.signal);
+        }
+    }
+    Some(signal)
+}
+
+/// Every pump.fun trade a transaction logged, in order
+pub fn pump_trades(tx: &Value) -> Vec<PumpTrade> {
+    tx["meta"]["logMessages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|log| log.as_str()?.strip_prefix(PROGRAM_DATA_LOG))
+        .filter_map(|data| base64::decode(data).ok())
+        .filter(|data| data.starts_with(&PUMP_TRADE_EVENT))
+        // Newer program versions append fields; only the leading ones are needed
+        .filter_map(|data| TradeEvent::deserialize(&mut &data[PUMP_TRADE_EVENT.len()..]).ok())
+        .map(|event| PumpTrade {
+            mint: Pubkey::new_from_array(event.mint),
+            sol_amount: event.sol_amount,
+            token_amount: event.token_amount,
+            is_buy: event.is_buy,
+            user: Pubkey::new_from_array(event.user),
+            timestamp: event.timestamp,
+            virtual_sol_reserves: event.virtual_sol_reserves,
+            virtual_token_reserves: event.virtual_token_reserves,
+        })
+        .collect()
+}
+
+/// Exact fill of `wallet`'s own trade of `mint` in a fetched transaction, for recording fills
+pub fn pump_fill(tx: &Value, wallet: &str, mint: &str) -> Option<PumpTrade> {
+    pump_trades(tx)
+        .into_iter()
+        .find(|event| event.user.to_string() == wallet && event.mint.to_string() == mint)
+}
+
+/// The target's trade as its balance changes show it
+fn balance_trade_signal(json: &Value, target: &str) -> Option<TradeSignal> {
+    let result = &json["params"]["result"];
+    let tx = &result["transaction"];
+    let meta = &tx["meta"];
+    if !meta["err"].is_null() {
+        return None;
+    }
+
+    let target_index = account_index(tx, target)?;
+    let pre_sol = meta["preBalances"][target_index].as_u64()?;
+    let post_sol = meta["postBalances"][target_index].as_u64()?;
+    // Only the fee payer is charged the network fee; add it back to get the swap amount
+    let fee = if target_index == 0 {
+        meta["fee"].as_u64().unwrap_or(0)
+    } else {
+        0
+    };
+
+    // The traded mint is the non-WSOL mint whose balance moved for the target
+    let mints = meta["preTokenBalances"]
+        .as_array()
+        .into_iter()
+        .chain(meta["postTokenBalances"].as_array())
+        .flatten()
+        .filter(|b| b["owner"].as_str() == Some(target))
+        .filter_map(|b| b["mint"].as_str())
+        .filter(|mint| *mint != WSOL_MINT)
+        .collect::<Vec<_>>();
+
+    // Only the mint that actually moved gets copied into an owned String
+    mints.into_iter().find_map(|mint| {
+        let (pre_tokens, pre_decimals) = token_balance(&meta["preTokenBalances"], target, mint);
+        let (post_tokens, post_decimals) = token_balance(&meta["postTokenBalances"], target, mint);
+        let decimals = pre_decimals.max(post_decimals);
+
+        let (direction, token_amount, sol_amount) = if post_tokens > pre_tokens {
+            (
+                SwapDirection::Buy,
+                post_tokens - pre_tokens,
+                (pre_sol.saturating_sub(post_sol)).saturating_sub(fee),
+            )
+        } else if pre_tokens > post_tokens {
+            (
+                SwapDirection::Sell,
+                pre_tokens - post_tokens,
+                (post_sol + fee).saturating_sub(pre_sol),
+            )
+        } else {
+            return None;
+        };
+
+        Some(TradeSignal {
+            signature: result["signature"].as_str().unwrap_or_default().to_string(),
+            slot: result["slot"].as_u64().unwrap_or_default(),
+            mint: mint.to_string(),
+            direction,
+            sol_amount,
+            token_amount,
+            token_pre_balance: pre_tokens,
+            decimals,
+        })
+    })
+}
+
+/// True if the transaction's logs contain `log` verbatim
+pub fn has_log(tx: &Value, log: &str) -> bool {
+    tx["meta"]["logMessages"]
+        .as_array()
+        .is_some_and(|logs| logs.iter().any(|line| line.as_str() == Some(log)))
+}
+
+/// The wallet that created a pump.fun token in this transaction, if it is a creation
+pub fn launch_creator(tx: &Value) -> Option<String> {
+    if !has_log(tx, PUMP_CREATE_LOG) {
+        return None;
+    }
+    instruction_account(tx, &PUMP_PROGRAM, CREATE_USER_INDEX)
+}
+
+/// Decodes a pump.fun token creation signed by the target from a `transactionSubscribe` notification
+pub fn parse_launch_signal(json: &Value, target: &str) -> Option<LaunchSignal> {
+    let result = &json["params"]["result"];
+    let tx = &result["transaction"];
+    if !tx["meta"]["err"].is_null() {
+        return None;
+    }
+    if launch_creator(tx)? != target {
+        return None;
+    }
+
+    let mint = instruction_account(tx, &PUMP_PROGRAM, CREATE_MINT_INDEX)?;
+    let dev_buy = parse_trade_signal(json, target)
+        .filter(|signal| signal.mint == mint && matches!(signal.direction, SwapDirection::Buy));
+    Some(LaunchSignal {
+        signature: result["signature"].as_str().unwrap_or_default().to_string(),
+        slot: result["slot"].as_u64().unwrap_or_default(),
+        mint,
+        dev_buy,
+    })
+}
+
+/// Decodes any pump.fun token creation from a `transactionSubscribe` notification
+pub fn parse_pump_create(json: &Value) -> Option<PumpCreate> {
+    let result = &json["params"]["result"];
+    let meta = &result["transaction"]["meta"];
+    if !meta["err"].is_null() {
+        return None;
+    }
+    let event = meta["logMessages"]
+        .as_array()?
+        .iter()
+        .filter_map(|log| log.as_str()?.strip_prefix(PROGRAM_DATA_LOG))
+        .filter_map(|data| base64::decode(data).ok())
+        .find(|data| data.starts_with(&PUMP_CREATE_EVENT))?;
+    // Newer program versions append fields; only the leading ones are needed
+    let event = CreateEvent::deserialize(&mut &event[PUMP_CREATE_EVENT.len()..]).ok()?;
+
+    Some(PumpCreate {
+        signature: result["signature"].as_str().unwrap_or_default().to_string(),
+        slot: result["slot"].as_u64().unwrap_or_default(),
+        name: event.name,
+        symbol: event.symbol,
+        uri: event.uri,
+        mint: Pubkey::new_from_array(event.mint),
+        bonding_curve: Pubkey::new_from_array(event.bonding_curve),
+        creator: Pubkey::new_from_array(event.user),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notification(pre_tokens: &str, post_tokens: &str, pre_sol: u64, post_sol: u64) -> Value {
+        json!({
+            "params": { "result": {
+                "signature": "sig",
+                "slot": 42,
+                "transaction": {
+                    "transaction": { "message": {
+                        "accountKeys": [{ "pubkey": "target" }, { "pubkey": "curve" }],
+                        "instructions": []
+                    }},
+                    "meta": {
+                        "err": null,
+                        "fee": 5000,
+                        "preBalances": [pre_sol, 0],
+                        "postBalances": [post_sol, 0],
+                        "preTokenBalances": [{ "owner": "target", "mint": "mint",
+                            "uiTokenAmount": { "amount": pre_tokens, "decimals": 6 } }],
+                        "postTokenBalances": [{ "owner": "target", "mint": "mint",
+                            "uiTokenAmount": { "amount": post_tokens, "decimals": 6 } }]
+                    }
+                }
+            }}
+        })
+    }
+
+    #[test]
+    fn test_parse_buy_signal() {
+        let json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        let signal = parse_trade_signal(&json, "target").unwrap();
+        assert!(matches!(signal.direction, SwapDirection::Buy));
+        assert_eq!(signal.sol_amount, 1_000_000_000);
+        assert_eq!(signal.token_amount, 1000);
+        assert_eq!(signal.slot, 42);
+    }
+
+    #[test]
+    fn test_parse_sell_signal() {
+        let json = notification("1000", "250", 1_000_000_000, 1_499_995_000);
+        let signal = parse_trade_signal(&json, "target").unwrap();
+        assert!(matches!(signal.direction, SwapDirection::Sell));
+        assert_eq!(signal.sol_amount, 500_000_000);
+        assert_eq!(signal.token_amount, 750);
+        assert_eq!(signal.token_pre_balance, 1000);
+    }
+
+    #[test]
+    fn test_mirrors_sell_fraction() {
+        let json = notification("1000", "250", 1_000_000_000, 1_499_995_000);
+        let signal = parse_trade_signal(&json, "target").unwrap();
+        assert_eq!(signal.mirrored_sell_amount(400), 300);
+
+        let json = notification("1000", "0", 1_000_000_000, 1_499_995_000);
+        let signal = parse_trade_signal(&json, "target").unwrap();
+        assert_eq!(signal.mirrored_sell_amount(401), 401);
+    }
+
+    #[test]
+    fn test_parse_launch_with_dev_buy() {
+        let mut json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        let tx = &mut json["params"]["result"]["transaction"];
+        let accounts = [
+            "mint", "auth", "curve", "ata", "global", "mpl", "meta", "target",
+        ];
+        tx["transaction"]["message"]["instructions"] =
+            json!([{ "programId": *PUMP_PROGRAM, "accounts": accounts }]);
+        tx["meta"]["logMessages"] = json!([PUMP_CREATE_LOG]);
+
+        let mut launch = parse_launch_signal(&json, "target").unwrap();
+        assert_eq!(launch.mint, "mint");
+        assert_eq!(launch.as_buy().sol_amount, 1_000_000_000);
+        assert!(parse_launch_signal(&json, "someone-else").is_none());
+
+        // A launch without a dev buy still reaches copy timing as a buy of the mint
+        launch.dev_buy = None;
+        let buy = launch.as_buy();
+        assert_eq!((buy.mint.as_str(), buy.sol_amount), ("mint", 0));
+        assert!(matches!(buy.direction, SwapDirection::Buy));
+
+        // An ordinary buy is not a launch
+        let json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        assert!(parse_launch_signal(&json, "target").is_none());
+    }
+
+    #[test]
+    fn test_parse_create_event() {
+        let mut data = PUMP_CREATE_EVENT.to_vec();
+        for field in ["Moon Cat", "MCAT", "https://example.com/mcat.json"] {
+            data.extend((field.len() as u32).to_le_bytes());
+            data.extend(field.as_bytes());
+        }
+        data.extend([1; 32]);
+        data.extend([2; 32]);
+        data.extend([3; 32]);
+
+        let mut json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        json["params"]["result"]["transaction"]["meta"]["logMessages"] = json!([
+            PUMP_CREATE_LOG,
+            format!("{}{}", PROGRAM_DATA_LOG, base64::encode(&data)),
+        ]);
+        let create = parse_pump_create(&json).unwrap();
+        assert_eq!(create.symbol, "MCAT");
+        assert_eq!(create.mint, Pubkey::new_from_array([1; 32]));
+        assert_eq!(create.creator, Pubkey::new_from_array([3; 32]));
+
+        let json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        assert!(parse_pump_create(&json).is_none());
+    }
+
+    #[test]
+    fn test_trade_event_amounts() {
+        let (mint, target) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = PUMP_TRADE_EVENT.to_vec();
+        data.extend(mint.to_bytes());
+        data.extend(990_000_000u64.to_le_bytes());
+        data.extend(1_000u64.to_le_bytes());
+        data.push(1);
+        data.extend(target.to_bytes());
+        data.extend(1_700_000_000i64.to_le_bytes());
+        data.extend(31_000_000_000u64.to_le_bytes());
+        data.extend(1_000_000_000_000_000u64.to_le_bytes());
+
+        let mut json = notification("0", "1000", 2_000_005_000, 1_000_000_000);
+        let result = &mut json["params"]["result"]["transaction"];
+        result["transaction"]["message"]["accountKeys"][0]["pubkey"] = json!(target.to_string());
+        for balances in ["preTokenBalances", "postTokenBalances"] {
+            result["meta"][balances][0]["owner"] = json!(target.to_string());
+            result["meta"][balances][0]["mint"] = json!(mint.to_string());
+        }
+        result["meta"]["logMessages"] =
+            json!([format!("{}{}", PROGRAM_DATA_LOG, base64::encode(&data))]);
+
+        // The event's curve amount wins over the balance delta, which includes the fee
+        let signal = parse_trade_signal(&json, &target.to_string()).unwrap();
+        assert_eq!(signal.sol_amount, 990_000_000);
+        assert_eq!(signal.token_amount, 1_000);
+        let tx = &json["params"]["result"]["transaction"];
+        let fill = pump_fill(tx, &target.to_string(), &mint.to_string()).unwrap();
+        assert!(fill.is_buy);
+        assert_eq!(fill.virtual_sol_reserves, 31_000_000_000);
+    }
+
+    #[test]
+    fn test_ignores_other_wallets() {
+        let json = notification("0", "1000", 2_000_000_000, 1_000_000_000);
+        assert!(parse_trade_signal(&json, "someone-else").is_none());
+    }
+}
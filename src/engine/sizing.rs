@@ -0,0 +1,168 @@
+use std::{env, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::native_token::sol_to_lamports;
+
+use crate::services::sol_usd::SOL_USD;
+
+const DEFAULT_FIXED_SOL: f64 = 0.01;
+
+/// How much SOL to put into a copied buy
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopySizing {
+    /// Always spend this many lamports
+    Fixed(u64),
+    /// Always spend this many USD worth of SOL, at the current SOL price
+    FixedUsd(f64),
+    /// Spend this percentage of the wallet's current SOL balance
+    BalancePercent(f64),
+    /// Spend this percentage of what the target spent
+    Proportional(f64),
+}
+
+impl FromStr for CopySizing {
+    type Err = anyhow::Error;
+
+    /// Parses `fixed:<sol>`, `usd:<amount>`, `balance:<pct>` or `proportional:<pct>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mode, value) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid copy sizing: '{}'", s))?;
+        let value = f64::from_str(value)?;
+        if value <= 0.0 {
+            return Err(anyhow!("Copy sizing value must be positive: '{}'", s));
+        }
+        match mode {
+            "fixed" => Ok(CopySizing::Fixed(sol_to_lamports(value))),
+            "usd" => Ok(CopySizing::FixedUsd(value)),
+            "balance" => Ok(CopySizing::BalancePercent(value)),
+            "proportional" => Ok(CopySizing::Proportional(value)),
+            _ => Err(anyhow!(
+                "Invalid copy sizing mode: '{}'. Use fixed, usd, balance or proportional",
+                mode
+            )),
+        }
+    }
+}
+
+/// Sizing strategy plus lamport clamps applied to every copied buy
+#[derive(Debug, Clone)]
+pub struct SizingConfig {
+    pub mode: CopySizing,
+    pub min_lamports: u64,
+    pub max_lamports: u64,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            mode: CopySizing::Fixed(sol_to_lamports(DEFAULT_FIXED_SOL)),
+            min_lamports: 0,
+            max_lamports: u64::MAX,
+        }
+    }
+}
+
+impl SizingConfig {
+    /// Reads `COPY_SIZING`, `COPY_MIN_SOL` and `COPY_MAX_SOL`
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_prefixed("COPY")
+    }
+
+    /// Sizing for buys copied from the target's own pump.fun launches, read from
+    /// `CREATOR_SIZING`, `CREATOR_MIN_SOL` and `CREATOR_MAX_SOL`; unset leaves creator-follow off
+    pub fn creator_from_env() -> Result<Option<Self>> {
+        if env::var("CREATOR_SIZING").is_err() {
+            return Ok(None);
+        }
+        Self::from_env_prefixed("CREATOR").map(Some)
+    }
+
+    fn from_env_prefixed(prefix: &str) -> Result<Self> {
+        let default = Self::default();
+        let mode = match env::var(format!("{}_SIZING", prefix)) {
+            Ok(value) => CopySizing::from_str(&value)?,
+            Err(_) => default.mode,
+        };
+        let sol_var = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .map(sol_to_lamports)
+        };
+
+        let config = Self {
+            mode,
+            min_lamports: sol_var(&format!("{}_MIN_SOL", prefix)).unwrap_or(default.min_lamports),
+            max_lamports: sol_var(&format!("{}_MAX_SOL", prefix)).unwrap_or(default.max_lamports),
+        };
+        if config.min_lamports > config.max_lamports {
+            return Err(anyhow!(
+                "{}_MIN_SOL is greater than {}_MAX_SOL",
+                prefix,
+                prefix
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Lamports to spend copying a buy where the target spent `target_lamports`; 0 when
+    /// sizing in USD without a current SOL price
+    pub fn buy_amount(&self, target_lamports: u64, balance_lamports: u64) -> u64 {
+        let amount = match self.mode {
+            CopySizing::Fixed(lamports) => lamports,
+            CopySizing::FixedUsd(usd) => match SOL_USD.usd_to_lamports(usd) {
+                Some(lamports) => lamports,
+                None => return 0,
+            },
+            CopySizing::BalancePercent(pct) => (balance_lamports as f64 * pct / 100.0) as u64,
+            CopySizing::Proportional(pct) => (target_lamports as f64 * pct / 100.0) as u64,
+        };
+        amount.clamp(self.min_lamports, self.max_lamports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sizing() {
+        assert_eq!(
+            CopySizing::from_str("fixed:0.5").unwrap(),
+            CopySizing::Fixed(500_000_000)
+        );
+        assert_eq!(
+            CopySizing::from_str("proportional:10").unwrap(),
+            CopySizing::Proportional(10.0)
+        );
+        assert_eq!(
+            CopySizing::from_str("usd:25").unwrap(),
+            CopySizing::FixedUsd(25.0)
+        );
+        assert!(CopySizing::from_str("balance:-1").is_err());
+        assert!(CopySizing::from_str("martingale:2").is_err());
+    }
+
+    #[test]
+    fn test_proportional_with_clamps() {
+        let config = SizingConfig {
+            mode: CopySizing::Proportional(10.0),
+            min_lamports: 10_000_000,
+            max_lamports: 200_000_000,
+        };
+        assert_eq!(config.buy_amount(1_000_000_000, 0), 100_000_000);
+        assert_eq!(config.buy_amount(50_000_000, 0), 10_000_000);
+        assert_eq!(config.buy_amount(10_000_000_000, 0), 200_000_000);
+    }
+
+    #[test]
+    fn test_balance_percent() {
+        let config = SizingConfig {
+            mode: CopySizing::BalancePercent(5.0),
+            ..Default::default()
+        };
+        assert_eq!(config.buy_amount(0, 2_000_000_000), 100_000_000);
+    }
+}
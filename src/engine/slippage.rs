@@ -0,0 +1,93 @@
+use std::{env, str::FromStr};
+
+use crate::core::errors::{self, Reaction};
+
+// Configuration constants
+const DEFAULT_STEP_BPS: u64 = 500;
+const DEFAULT_MAX_RETRIES: u32 = 1;
+
+/// Retries slippage failures at stepped-up slippage, never past a user-set ceiling
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlippageRetry {
+    pub ceiling_bps: u64,
+    pub step_bps: u64,
+    pub max_retries: u32,
+}
+
+impl SlippageRetry {
+    /// Reads `SLIPPAGE_CEILING_BPS` (unset turns retries off), `SLIPPAGE_STEP_BPS` and `SLIPPAGE_MAX_RETRIES`
+    pub fn from_env() -> Option<Self> {
+        let ceiling_bps = env::var("SLIPPAGE_CEILING_BPS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())?;
+        Some(Self {
+            ceiling_bps,
+            step_bps: env::var("SLIPPAGE_STEP_BPS")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .unwrap_or(DEFAULT_STEP_BPS)
+                .max(1),
+            max_retries: env::var("SLIPPAGE_MAX_RETRIES")
+                .ok()
+                .and_then(|v| u32::from_str(&v).ok())
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+        })
+    }
+
+    /// Slippage for the next attempt, or `None` once retries or headroom are used up
+    pub fn next(&self, current_bps: u64, retries_done: u32) -> Option<u64> {
+        if retries_done >= self.max_retries || current_bps >= self.ceiling_bps {
+            return None;
+        }
+        Some((current_bps + self.step_bps).min(self.ceiling_bps))
+    }
+}
+
+/// True if the error (or anything it wraps) is a program's slippage check failing, as decoded
+/// into [`errors::TxError::SlippageExceeded`] where the node's error was parsed
+pub fn is_slippage_error(err: &anyhow::Error) -> bool {
+    errors::reaction(err) == Some(Reaction::Requote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::errors::{SwapError, TxError},
+        dex::{pump::PUMP_PROGRAM_ID, raydium::AMM_PROGRAM_ID},
+    };
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_detects_slippage_errors() {
+        let slippage = TxError::SlippageExceeded {
+            program: PUMP_PROGRAM_ID,
+            code: 0x1772,
+        };
+        let err = Err::<(), _>(anyhow::Error::new(SwapError::send("pump")(slippage)))
+            .context("Failed to execute swap transaction")
+            .unwrap_err();
+        assert!(is_slippage_error(&err));
+        let other = TxError::Program {
+            program: AMM_PROGRAM_ID,
+            code: 0x1e5,
+        };
+        assert!(!is_slippage_error(&anyhow::Error::new(other)));
+        // Only decoded errors count, not text that happens to look like one
+        assert!(!is_slippage_error(&anyhow!("custom program error: 0x1e")));
+        assert!(!is_slippage_error(&anyhow::Error::new(TxError::CurveComplete)));
+    }
+
+    #[test]
+    fn test_steps_up_to_ceiling() {
+        let retry = SlippageRetry {
+            ceiling_bps: 1_200,
+            step_bps: 500,
+            max_retries: 3,
+        };
+        assert_eq!(retry.next(500, 0), Some(1_000));
+        assert_eq!(retry.next(1_000, 1), Some(1_200));
+        assert_eq!(retry.next(1_200, 2), None);
+        assert_eq!(retry.next(500, 3), None);
+    }
+}
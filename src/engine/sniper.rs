@@ -0,0 +1,470 @@
+use std::{collections::HashSet, env, str::FromStr, sync::Arc};
+
+use anyhow::{Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use regex::Regex;
+use serde_json::Value;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
+use tokio::time::Instant;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::tx,
+    dex::{
+        pump::{
+            PUMP_ACCOUNT_ID, PUMP_BUY_METHOD, PUMP_GLOBAL_ID, PUMP_PROGRAM_ID, RENT_PROGRAM_ID,
+        },
+        pump_global::{pump_fee_bps, pump_fee_recipient},
+    },
+    engine::{
+        execution::record_execution,
+        projection::{apply_trade, quote_buy},
+        shutdown::SHUTDOWN,
+        signal::{parse_pump_create, parse_trade_signal, PumpCreate},
+        swap::SwapDirection,
+    },
+    risk::{filters::TokenInfo, limits::reserve_buy},
+    services::{
+        metrics::METRICS,
+        notify::Event,
+        price_feed::CurveReserves,
+        slo::{observe_latency, TradeLatency},
+    },
+};
+
+// Configuration constants
+const DEFAULT_SLIPPAGE_BPS: u64 = 2_500;
+const BUY_ACCOUNTS: usize = 12;
+// Method, token amount and max SOL cost
+const BUY_DATA_LEN: usize = 8 + 8 + 8;
+// Positions in pump.fun's `buy` accounts that change from launch to launch
+const BUY_FEE_RECIPIENT_INDEX: usize = 1;
+const BUY_MINT_INDEX: usize = 2;
+const BUY_CURVE_INDEX: usize = 3;
+const BUY_CURVE_ATA_INDEX: usize = 4;
+const BUY_USER_ATA_INDEX: usize = 5;
+/// `CreateIdempotent` in the associated token program's instruction enum
+const CREATE_IDEMPOTENT: u8 = 1;
+// Reserves every pump.fun curve starts from
+const INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+const INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+
+/// Tokens a buy of `sol_in` lamports gets from a fresh curve after the creator's dev buy of
+/// `dev_tokens`
+pub fn quote_fresh_curve(dev_tokens: u64, sol_in: u64) -> u64 {
+    if dev_tokens >= INITIAL_VIRTUAL_TOKEN_RESERVES {
+        return 0;
+    }
+    let initial = CurveReserves {
+        virtual_sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES,
+        virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+    };
+    let reserves = apply_trade(initial, &SwapDirection::Buy, dev_tokens);
+    quote_buy(reserves, sol_in, pump_fee_bps())
+}
+
+/// Which launches to snipe and how much to spend on each
+#[derive(Debug, Clone)]
+pub struct SniperConfig {
+    pub amount_lamports: u64,
+    pub slippage_bps: u64,
+    pub name_pattern: Option<Regex>,
+    pub symbol_pattern: Option<Regex>,
+    pub creator_blacklist: HashSet<Pubkey>,
+    /// Bounds on the creator's buy in the creation transaction
+    pub min_dev_buy_lamports: u64,
+    pub max_dev_buy_lamports: Option<u64>,
+}
+
+impl SniperConfig {
+    /// Reads `SNIPER_BUY_SOL` (unset disables sniping), `SNIPER_SLIPPAGE_BPS`,
+    /// `SNIPER_NAME_REGEX`, `SNIPER_SYMBOL_REGEX`, `SNIPER_CREATOR_BLACKLIST` (comma-separated),
+    /// `SNIPER_MIN_DEV_BUY_SOL` and `SNIPER_MAX_DEV_BUY_SOL`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(amount_sol) = env::var("SNIPER_BUY_SOL") else {
+            return Ok(None);
+        };
+        let amount_sol = f64::from_str(&amount_sol).context("Invalid SNIPER_BUY_SOL")?;
+        let pattern = |key: &str| -> Result<Option<Regex>> {
+            env::var(key)
+                .ok()
+                .map(|v| Regex::new(&v).with_context(|| format!("Invalid {}", key)))
+                .transpose()
+        };
+        let sol = |key: &str| -> Result<Option<u64>> {
+            env::var(key)
+                .ok()
+                .map(|v| {
+                    f64::from_str(&v)
+                        .map(sol_to_lamports)
+                        .with_context(|| format!("Invalid {}", key))
+                })
+                .transpose()
+        };
+        let creator_blacklist = env::var("SNIPER_CREATOR_BLACKLIST")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                Pubkey::from_str(v).with_context(|| format!("Invalid blacklisted creator {}", v))
+            })
+            .collect::<Result<HashSet<_>>>()?;
+
+        Ok(Some(Self {
+            amount_lamports: sol_to_lamports(amount_sol),
+            slippage_bps: env::var("SNIPER_SLIPPAGE_BPS")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .unwrap_or(DEFAULT_SLIPPAGE_BPS)
+                .min(10_000),
+            name_pattern: pattern("SNIPER_NAME_REGEX")?,
+            symbol_pattern: pattern("SNIPER_SYMBOL_REGEX")?,
+            creator_blacklist,
+            min_dev_buy_lamports: sol("SNIPER_MIN_DEV_BUY_SOL")?.unwrap_or(0),
+            max_dev_buy_lamports: sol("SNIPER_MAX_DEV_BUY_SOL")?,
+        }))
+    }
+
+    /// Why a launch is skipped, or `None` if it passes every filter
+    pub fn rejects(&self, create: &PumpCreate, dev_buy_lamports: u64) -> Option<String> {
+        if self.creator_blacklist.contains(&create.creator) {
+            return Some(format!("creator {} is blacklisted", create.creator));
+        }
+        if let Some(pattern) = &self.name_pattern {
+            if !pattern.is_match(&create.name) {
+                return Some(format!("name '{}' does not match", create.name));
+            }
+        }
+        if let Some(pattern) = &self.symbol_pattern {
+            if !pattern.is_match(&create.symbol) {
+                return Some(format!("symbol '{}' does not match", create.symbol));
+            }
+        }
+        if dev_buy_lamports < self.min_dev_buy_lamports {
+            return Some(format!(
+                "dev buy of {} lamports is too small",
+                dev_buy_lamports
+            ));
+        }
+        if self
+            .max_dev_buy_lamports
+            .is_some_and(|max| dev_buy_lamports > max)
+        {
+            return Some(format!(
+                "dev buy of {} lamports is too large",
+                dev_buy_lamports
+            ));
+        }
+        None
+    }
+}
+
+/// A pump.fun buy for one wallet; a launch only fills in its own mint and curve
+pub struct BuyTemplate {
+    user: Pubkey,
+    /// The buy's accounts with the wallet and the fixed programs in place
+    accounts: [AccountMeta; BUY_ACCOUNTS],
+}
+
+impl BuyTemplate {
+    pub fn new(user: Pubkey) -> Self {
+        let unset = Pubkey::default();
+        Self {
+            user,
+            accounts: [
+                AccountMeta::new_readonly(PUMP_GLOBAL_ID, false),
+                AccountMeta::new(unset, false),
+                AccountMeta::new_readonly(unset, false),
+                AccountMeta::new(unset, false),
+                AccountMeta::new(unset, false),
+                AccountMeta::new(unset, false),
+                AccountMeta::new(user, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(RENT_PROGRAM_ID, false),
+                AccountMeta::new_readonly(PUMP_ACCOUNT_ID, false),
+                AccountMeta::new_readonly(PUMP_PROGRAM_ID, false),
+            ],
+        }
+    }
+
+    /// Creates our token account and buys `token_amount` for at most `max_sol_cost`
+    pub fn instructions(
+        &self,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        token_amount: u64,
+        max_sol_cost: u64,
+    ) -> Vec<Instruction> {
+        // Each address is a PDA search; the account creation reuses ours rather than
+        // deriving it again
+        let associated_bonding_curve = get_associated_token_address(bonding_curve, mint);
+        let associated_user = get_associated_token_address(&self.user, mint);
+        let create_ata = Instruction {
+            program_id: spl_associated_token_account::id(),
+            accounts: vec![
+                AccountMeta::new(self.user, true),
+                AccountMeta::new(associated_user, false),
+                AccountMeta::new_readonly(self.user, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: vec![CREATE_IDEMPOTENT],
+        };
+
+        let mut accounts = self.accounts.to_vec();
+        // Read per buy: a Global account refresh can move the fee recipient
+        accounts[BUY_FEE_RECIPIENT_INDEX].pubkey = pump_fee_recipient();
+        accounts[BUY_MINT_INDEX].pubkey = *mint;
+        accounts[BUY_CURVE_INDEX].pubkey = *bonding_curve;
+        accounts[BUY_CURVE_ATA_INDEX].pubkey = associated_bonding_curve;
+        accounts[BUY_USER_ATA_INDEX].pubkey = associated_user;
+
+        let mut data = Vec::with_capacity(BUY_DATA_LEN);
+        data.extend(PUMP_BUY_METHOD.to_le_bytes());
+        data.extend(token_amount.to_le_bytes());
+        data.extend(max_sol_cost.to_le_bytes());
+
+        vec![
+            create_ata,
+            Instruction {
+                program_id: PUMP_PROGRAM_ID,
+                accounts,
+                data,
+            },
+        ]
+    }
+}
+
+/// Buys new pump.fun launches that pass the filters, in the slot they are created
+pub struct Sniper {
+    config: SniperConfig,
+    template: BuyTemplate,
+}
+
+impl Sniper {
+    /// A sniper for `wallet`, or `None` when `SNIPER_BUY_SOL` is unset
+    pub fn from_env(wallet: Pubkey) -> Result<Option<Self>> {
+        let Some(config) = SniperConfig::from_env()? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            config,
+            template: BuyTemplate::new(wallet),
+        }))
+    }
+
+    /// Snipes the launch in a `transactionSubscribe` notification, if it is one we want
+    pub async fn on_transaction(
+        &self,
+        json: &Value,
+        state: &AppState,
+        jito_client: Arc<JitoRpcClient>,
+        timestamp: Instant,
+    ) {
+        let Some(create) = parse_pump_create(json) else {
+            return;
+        };
+        let mint = create.mint.to_string();
+        let dev_buy = parse_trade_signal(json, &create.creator.to_string())
+            .filter(|signal| signal.mint == mint && matches!(signal.direction, SwapDirection::Buy));
+        let dev_buy_lamports = dev_buy.as_ref().map_or(0, |buy| buy.sol_amount);
+        if let Some(reason) = self.config.rejects(&create, dev_buy_lamports) {
+            let _ = log_message(&format!(
+                "Sniper skipped {} ({}): {}",
+                mint, create.symbol, reason
+            ))
+            .await;
+            return;
+        }
+        let token = TokenInfo {
+            mint: mint.clone(),
+            creator: Some(create.creator.to_string()),
+            name: Some(create.name.clone()),
+            symbol: Some(create.symbol.clone()),
+        };
+        if let Some(reason) = state.filters.rejects(&token).await {
+            let _ = log_message(&format!(
+                "Sniper skipped {} ({}): {}",
+                mint, create.symbol, reason
+            ))
+            .await;
+            return;
+        }
+        // Copy trading may already be in this mint
+        if state.positions.get(&mint).await.is_some() || !state.expectancy.allows_entry().await {
+            return;
+        }
+
+        let Some(_in_flight) = SHUTDOWN.begin_trade() else {
+            return;
+        };
+        let amount_in = self.config.amount_lamports;
+        let Some(_reservation) = reserve_buy(state, "sniper", &mint, amount_in).await else {
+            return;
+        };
+        let intent_id = state.positions.add_intent(&mint, "buy", amount_in).await;
+        let dev_tokens = dev_buy.map_or(0, |buy| buy.token_amount);
+        let tokens_out = quote_fresh_curve(dev_tokens, amount_in) as u128
+            * (10_000 - self.config.slippage_bps) as u128
+            / 10_000;
+        let instructions = self.template.instructions(
+            &create.mint,
+            &create.bonding_curve,
+            tokens_out as u64,
+            amount_in,
+        );
+        // A flatten or kill switch may have cancelled the intent in the meantime
+        if state.positions.take_intent(intent_id).await.is_none() {
+            return;
+        }
+
+        let submitted = Instant::now();
+        let result = tx::new_signed_and_send(
+            &state.rpc_client,
+            &state.wallet,
+            instructions,
+            Some(jito_client),
+            None,
+            timestamp,
+        )
+        .await;
+        let latency = TradeLatency::between(timestamp, submitted);
+        METRICS.observe_swap("sniper", &result);
+        let message = match result {
+            Ok(signatures) => {
+                state
+                    .positions
+                    .record_buy(&mint, None, amount_in, state.fees.tx_cost())
+                    .await;
+                state
+                    .positions
+                    .set_creator(&mint, &create.creator.to_string())
+                    .await;
+                tokio::spawn(record_execution(
+                    state.clone(),
+                    mint.clone(),
+                    SwapDirection::Buy,
+                    signatures,
+                    None,
+                ));
+                observe_latency(state, latency).await;
+                format!(
+                    "🎯 Sniped {} ({}) for {} lamports in {:?}",
+                    create.symbol,
+                    mint,
+                    amount_in,
+                    timestamp.elapsed()
+                )
+            }
+            Err(e) => format!("Sniper buy of {} failed: {}", mint, e),
+        };
+        let _ = log_message(&message).await;
+        state.notifier.notify(Event::Info(message)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create(name: &str, symbol: &str, creator: Pubkey) -> PumpCreate {
+        PumpCreate {
+            signature: "sig".to_string(),
+            slot: 1,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: String::new(),
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            creator,
+        }
+    }
+
+    #[test]
+    fn test_filters() {
+        let blacklisted = Pubkey::new_unique();
+        let config = SniperConfig {
+            amount_lamports: 1,
+            slippage_bps: DEFAULT_SLIPPAGE_BPS,
+            name_pattern: Some(Regex::new("(?i)cat").unwrap()),
+            symbol_pattern: None,
+            creator_blacklist: HashSet::from([blacklisted]),
+            min_dev_buy_lamports: 100,
+            max_dev_buy_lamports: Some(1_000),
+        };
+        let creator = Pubkey::new_unique();
+        assert!(config
+            .rejects(&create("Moon Cat", "MCAT", creator), 500)
+            .is_none());
+        assert!(config
+            .rejects(&create("Moon Dog", "MDOG", creator), 500)
+            .is_some());
+        assert!(config
+            .rejects(&create("Moon Cat", "MCAT", blacklisted), 500)
+            .is_some());
+        assert!(config
+            .rejects(&create("Moon Cat", "MCAT", creator), 50)
+            .is_some());
+        assert!(config
+            .rejects(&create("Moon Cat", "MCAT", creator), 5_000)
+            .is_some());
+    }
+
+    #[test]
+    fn test_buy_template_layout() {
+        let (user, mint, curve) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instructions = BuyTemplate::new(user).instructions(&mint, &curve, 7, 9);
+
+        assert_eq!(
+            instructions[0],
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &user,
+                &user,
+                &mint,
+                &spl_token::id(),
+            )
+        );
+        let buy = &instructions[1];
+        assert_eq!(buy.program_id, PUMP_PROGRAM_ID);
+        assert_eq!(buy.data[..8], PUMP_BUY_METHOD.to_le_bytes());
+        assert_eq!(buy.data[8..16], 7u64.to_le_bytes());
+        assert_eq!(buy.data[16..], 9u64.to_le_bytes());
+        assert_eq!(
+            buy.accounts[BUY_FEE_RECIPIENT_INDEX].pubkey,
+            pump_fee_recipient()
+        );
+        assert_eq!(buy.accounts[BUY_MINT_INDEX].pubkey, mint);
+        assert_eq!(buy.accounts[BUY_CURVE_INDEX].pubkey, curve);
+        assert_eq!(
+            buy.accounts[BUY_CURVE_ATA_INDEX].pubkey,
+            get_associated_token_address(&curve, &mint)
+        );
+        assert_eq!(
+            buy.accounts[BUY_USER_ATA_INDEX].pubkey,
+            get_associated_token_address(&user, &mint)
+        );
+        assert!(buy.accounts[6].is_signer && buy.accounts[6].pubkey == user);
+    }
+
+    #[test]
+    fn test_quote_fresh_curve() {
+        // One SOL into an untouched curve buys roughly 34.6M tokens
+        let tokens = quote_fresh_curve(0, 1_000_000_000);
+        assert!((34_000_000_000_000..35_000_000_000_000).contains(&tokens));
+        // A dev buy ahead of us moves the price up
+        assert!(quote_fresh_curve(tokens, 1_000_000_000) < tokens);
+    }
+}
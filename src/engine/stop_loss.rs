@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use tokio::{sync::broadcast::error::RecvError, time::interval};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::exit::market_exit,
+    services::{notify::Event, price_feed::PriceUpdate},
+};
+
+// Configuration constants
+const DEFAULT_CHECK_SECS: u64 = 5;
+const STOP_LOSS_SLIPPAGE_BPS: u64 = 2_500;
+
+/// Percentage `price` sits below `reference`; negative when it is above
+fn drawdown_pct(reference: f64, price: f64) -> f64 {
+    if reference <= 0.0 {
+        return 0.0;
+    }
+    (reference - price) / reference * 100.0
+}
+
+/// Reference price of each held mint: the first price the feed reported after opening
+#[derive(Default)]
+struct ReferencePrices {
+    prices: HashMap<String, f64>,
+}
+
+impl ReferencePrices {
+    /// Drawdown of `update` from its mint's reference, recording the reference on first sight
+    fn observe(&mut self, update: &PriceUpdate) -> f64 {
+        let reference = *self
+            .prices
+            .entry(update.mint.clone())
+            .or_insert(update.price);
+        drawdown_pct(reference, update.price)
+    }
+
+    fn forget(&mut self, mint: &str) {
+        self.prices.remove(mint);
+    }
+}
+
+/// Sells a position once its price drops `stop_loss_pct` below its reference
+async fn on_price(
+    update: PriceUpdate,
+    state: &AppState,
+    references: &mut ReferencePrices,
+    held: &HashSet<String>,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    if !held.contains(&update.mint) {
+        return;
+    }
+    let drawdown = references.observe(&update);
+    // Read on every update so a reloaded threshold applies immediately
+    let Some(stop_loss_pct) = state.settings.current().stop_loss_pct else {
+        return;
+    };
+    if drawdown < stop_loss_pct {
+        return;
+    }
+    let Some(position) = state.positions.get(&update.mint).await else {
+        return;
+    };
+
+    match market_exit(state, &position, STOP_LOSS_SLIPPAGE_BPS, jito_client).await {
+        Ok(()) => {
+            references.forget(&update.mint);
+            let message = format!(
+                "🛑 Stop loss on {} ({:.1}% down, limit {}%), sold",
+                update.mint, drawdown, stop_loss_pct
+            );
+            let _ = log_message(&message).await;
+            state.notifier.notify(Event::Info(message)).await;
+        }
+        Err(e) => {
+            let _ = log_message(&format!(
+                "Stop loss exit for {} failed, retrying on the next price: {}",
+                update.mint, e
+            ))
+            .await;
+        }
+    }
+}
+
+/// Keeps the price feed watching exactly the held mints
+async fn refresh_held(
+    state: &AppState,
+    references: &mut ReferencePrices,
+    held: &mut HashSet<String>,
+) {
+    let open = state
+        .positions
+        .open_positions()
+        .await
+        .into_iter()
+        .map(|position| position.mint)
+        .collect::<HashSet<_>>();
+    for mint in open.difference(held) {
+        state.price_feed.watch(mint).await;
+    }
+    for mint in held.difference(&open) {
+        state.price_feed.unwatch(mint).await;
+        references.forget(mint);
+    }
+    *held = open;
+}
+
+/// Enforces the live stop-loss threshold on open positions, forever; idle while it is unset
+pub async fn run_stop_loss(state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let check_secs = env::var("STOP_LOSS_CHECK_SECS")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_CHECK_SECS)
+        .max(1);
+    let mut prices = state.price_feed.subscribe();
+    let mut checks = interval(Duration::from_secs(check_secs));
+    let mut references = ReferencePrices::default();
+    let mut held = HashSet::new();
+
+    loop {
+        tokio::select! {
+            update = prices.recv() => match update {
+                Ok(update) => {
+                    on_price(update, &state, &mut references, &held, jito_client.clone()).await;
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            _ = checks.tick() => {
+                if state.settings.current().stop_loss_pct.is_some() {
+                    refresh_held(&state, &mut references, &mut held).await;
+                } else {
+                    // Threshold removed: stop paying for price polls
+                    for mint in held.drain() {
+                        state.price_feed.unwatch(&mint).await;
+                        references.forget(&mint);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    fn update(price: f64) -> PriceUpdate {
+        PriceUpdate {
+            mint: "mint".to_string(),
+            price,
+            observed_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_drawdown_from_first_price() {
+        let mut references = ReferencePrices::default();
+        assert_eq!(references.observe(&update(2.0)), 0.0);
+        assert!((references.observe(&update(1.5)) - 25.0).abs() < 1e-9);
+        assert!(references.observe(&update(3.0)) < 0.0);
+
+        references.forget("mint");
+        assert_eq!(references.observe(&update(1.0)), 0.0);
+    }
+}
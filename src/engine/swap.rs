@@ -1,14 +1,21 @@
 use std::sync::Arc;
 
-use crate::common::utils::AppState;
+use crate::common::utils::{log_message, AppState};
+use crate::core::token::{get_account_info, get_token_account_address};
+use crate::dex::jupiter::Jupiter;
 use crate::dex::pump::Pump;
-use crate::dex::raydium::Raydium;
+use crate::dex::raydium::{get_pool_state_by_mint, Raydium};
+use crate::engine::exit::{atomic_exit, AtomicExitConfig};
+use crate::engine::multi_hop::{multi_hop_exit, MultiHopConfig};
+use crate::engine::router::Venue;
+use crate::engine::venues::VenueKind;
 use anyhow::Result;
 use clap::ValueEnum;
 use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
 use raydium_amm::state::AmmInfo;
 use serde::Deserialize;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use std::str::FromStr;
 use tokio::time::Instant;
 
 #[derive(ValueEnum, Debug, Clone, Deserialize)]
@@ -18,6 +25,25 @@ pub enum SwapDirection {
     #[serde(rename = "sell")]
     Sell,
 }
+impl SwapDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapDirection::Buy => "buy",
+            SwapDirection::Sell => "sell",
+        }
+    }
+}
+impl FromStr for SwapDirection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "buy" => Ok(SwapDirection::Buy),
+            "sell" => Ok(SwapDirection::Sell),
+            _ => Err(anyhow::anyhow!("Invalid swap direction: '{}'", s)),
+        }
+    }
+}
 impl From<SwapDirection> for u8 {
     fn from(value: SwapDirection) -> Self {
         match value {
@@ -36,34 +62,72 @@ pub enum SwapInType {
     #[serde(rename = "pct")]
     Pct,
 }
+impl SwapInType {
+    /// Raw amount to swap: `amount_in` itself for Qty, `amount_in` percent of `balance` for Pct
+    pub fn resolve(&self, amount_in: u64, balance: u64) -> Result<u64> {
+        match self {
+            SwapInType::Qty => Ok(amount_in),
+            SwapInType::Pct => {
+                if amount_in == 0 || amount_in > 100 {
+                    return Err(anyhow::anyhow!(
+                        "Percentage must be within 1-100, got {}",
+                        amount_in
+                    ));
+                }
+                if balance == 0 {
+                    return Err(anyhow::anyhow!("Nothing to swap: balance is zero"));
+                }
+                Ok((balance as u128 * amount_in as u128 / 100) as u64)
+            }
+        }
+    }
+}
 
 pub async fn pump_swap(
     state: AppState,
     amount_in: u64,
+    in_type: SwapInType,
     swap_direction: &str,
     slippage: u64,
     mint: &str,
     jito_client: Arc<JitoRpcClient>,
     timestamp: Instant,
 ) -> Result<Vec<String>> {
-    let swap_direction = match swap_direction {
-        "buy" => SwapDirection::Buy,
-        "sell" => SwapDirection::Sell,
-        _ => todo!(),
-    };
-    let in_type = "qty";
+    let swap_direction = SwapDirection::from_str(swap_direction)?;
     let use_jito = true;
-    let in_type = match in_type {
-        "qty" => SwapInType::Qty,
-        "pct" => SwapInType::Pct,
-        _ => todo!(),
-    };
-    let swapx = Pump::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
-    println!("2.2: {:#?}", timestamp.elapsed());
+    let swapx = Pump::new(
+        state.rpc_nonblocking_client.clone(),
+        state.rpc_client.clone(),
+        state.wallet.clone(),
+    );
+    if swapx.is_token_graduated(mint).await.unwrap_or(false) {
+        // Graduated venues take raw amounts, so resolve a percentage here
+        let amount_in = match in_type {
+            SwapInType::Qty => amount_in,
+            SwapInType::Pct => {
+                let balance = match swap_direction {
+                    SwapDirection::Buy => swapx.get_sol_balance().await?,
+                    SwapDirection::Sell => swapx.get_token_balance(mint).await?,
+                };
+                in_type.resolve(amount_in, balance)?
+            }
+        };
+        return graduated_swap(
+            state,
+            amount_in,
+            swap_direction,
+            slippage,
+            mint,
+            jito_client,
+            timestamp,
+        )
+        .await;
+    }
     let res = match swapx
         .swap(
             mint,
             amount_in,
+            in_type,
             swap_direction,
             slippage,
             jito_client.clone(),
@@ -73,7 +137,7 @@ pub async fn pump_swap(
     {
         Ok(res) => res,
         Err(e) => {
-            return Err(e);
+            return Err(e.into());
         }
     };
     Ok(res)
@@ -89,14 +153,13 @@ pub async fn raydium_swap(
     jito_client: Arc<JitoRpcClient>,
     timestamp: Instant,
 ) -> Result<Vec<String>> {
-    let swap_direction = match swap_direction {
-        "buy" => SwapDirection::Buy,
-        "sell" => SwapDirection::Sell,
-        _ => todo!(),
-    };
+    let swap_direction = SwapDirection::from_str(swap_direction)?;
+    state
+        .router
+        .venues
+        .check(VenueKind::Raydium, &swap_direction)?;
 
     let swapx = Raydium::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
-    println!("2.2: {:#?}", timestamp.elapsed());
     let res = match swapx
         .swap_by_mint(
             mint,
@@ -111,8 +174,171 @@ pub async fn raydium_swap(
     {
         Ok(res) => res,
         Err(e) => {
-            return Err(e);
+            return Err(e.into());
         }
     };
     Ok(res)
 }
+
+/// Swaps a token that has left the bonding curve: Raydium v4 if a pool exists, Jupiter otherwise
+pub async fn graduated_swap(
+    state: AppState,
+    amount_in: u64,
+    swap_direction: SwapDirection,
+    slippage: u64,
+    mint: &str,
+    jito_client: Arc<JitoRpcClient>,
+    timestamp: Instant,
+) -> Result<Vec<String>> {
+    match get_pool_state_by_mint(state.rpc_client.clone(), mint).await {
+        Ok((pool_id, _)) => {
+            state
+                .router
+                .venues
+                .check(VenueKind::Raydium, &swap_direction)?;
+            let swapx = Raydium::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet);
+            Ok(swapx
+                .swap_by_mint(
+                    mint,
+                    swap_direction,
+                    amount_in,
+                    pool_id.to_string(),
+                    slippage,
+                    timestamp,
+                    jito_client,
+                )
+                .await?)
+        }
+        Err(e) => {
+            // Migrated to a pool type we don't build for (CLMM, Meteora, ...)
+            let _ = log_message(&format!(
+                "No Raydium v4 pool for {} ({}), routing through Jupiter",
+                mint, e
+            ))
+            .await;
+            state
+                .router
+                .venues
+                .check(VenueKind::Jupiter, &swap_direction)?;
+            let excluded = state.router.venues.excluded_dexes(&swap_direction);
+            let swapx = Jupiter::new(state.rpc_nonblocking_client, state.rpc_client, state.wallet)
+                .excluding(excluded);
+            Ok(swapx
+                .swap(
+                    mint,
+                    amount_in,
+                    swap_direction,
+                    slippage,
+                    jito_client,
+                    timestamp,
+                )
+                .await?)
+        }
+    }
+}
+
+/// Sells the wallet's whole balance of a mint on Raydium when `pool_id` is set, otherwise via the router
+pub async fn sell_entire_balance(
+    state: AppState,
+    mint: &str,
+    pool_id: Option<String>,
+    slippage: u64,
+    jito_client: Arc<JitoRpcClient>,
+    timestamp: Instant,
+) -> Result<Vec<String>> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let ata = get_token_account_address(
+        &state.rpc_nonblocking_client,
+        &state.wallet.pubkey(),
+        &mint_pubkey,
+    )
+    .await?;
+    let balance = get_account_info(state.rpc_nonblocking_client.clone(), &mint_pubkey, &ata)
+        .await?
+        .base
+        .amount;
+    if balance == 0 {
+        return Ok(vec![]);
+    }
+
+    let fallback_state = state.clone();
+    let direct = match pool_id {
+        Some(pool_id) => {
+            raydium_swap(
+                state,
+                balance,
+                "sell",
+                pool_id,
+                slippage,
+                mint,
+                jito_client.clone(),
+                timestamp,
+            )
+            .await
+        }
+        None => {
+            // Large curve positions leave in one bundle so serial sells don't walk the price down
+            if let Some(config) = AtomicExitConfig::from_env() {
+                let position = state.positions.get(mint).await;
+                let venue = state.router.route(&state, mint).await?;
+                if venue == Venue::BondingCurve
+                    && position.is_some_and(|p| config.applies_to(p.sol_invested))
+                {
+                    return atomic_exit(state, mint, balance, slippage, &config, jito_client)
+                        .await;
+                }
+            }
+
+            let router = state.router.clone();
+            router
+                .swap(
+                    state,
+                    mint,
+                    balance,
+                    SwapDirection::Sell,
+                    slippage,
+                    jito_client.clone(),
+                    timestamp,
+                )
+                .await
+        }
+    };
+
+    // A collapsed direct pool shouldn't strand the position if a route through USDC still works
+    match (direct, MultiHopConfig::from_env()) {
+        (Err(e), Some(config)) => {
+            let _ = log_message(&format!(
+                "Direct exit of {} failed ({}), trying multi-hop routes",
+                mint, e
+            ))
+            .await;
+            multi_hop_exit(
+                &fallback_state,
+                &config,
+                mint,
+                balance,
+                slippage,
+                jito_client,
+                timestamp,
+            )
+            .await
+            .map_err(|hop_err| e.context(format!("Multi-hop exit also failed: {}", hop_err)))
+        }
+        (direct, _) => direct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_percentage() {
+        assert_eq!(SwapInType::Qty.resolve(1_234, 0).unwrap(), 1_234);
+        assert_eq!(SwapInType::Pct.resolve(50, 1_001).unwrap(), 500);
+        assert_eq!(SwapInType::Pct.resolve(100, u64::MAX).unwrap(), u64::MAX);
+        assert!(SwapInType::Pct.resolve(0, 1_000).is_err());
+        assert!(SwapInType::Pct.resolve(101, 1_000).is_err());
+        assert!(SwapInType::Pct.resolve(100, 0).is_err());
+    }
+}
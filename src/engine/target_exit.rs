@@ -0,0 +1,337 @@
+use std::{env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use tokio::time::Instant;
+
+use crate::{common::cache::BoundedCache, engine::signal::TradeSignal};
+
+// Configuration constants
+const DEFAULT_PANIC_LOSS_PCT: f64 = 20.0;
+const DEFAULT_PANIC_HOLD_SECS: u64 = 60;
+// Selling at least this share of the bag counts as leaving the position
+const FULL_EXIT_PCT: f64 = 95.0;
+const ENTRY_CACHE_ENTRIES: usize = 10_000;
+const ENTRY_TTL_SECS: u64 = 7 * 24 * 3_600;
+
+/// What a target's sell looks like from their side of the trade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitKind {
+    /// A partial sell that is not a panic, typically trimming into strength
+    ProfitTaking,
+    /// Sold (nearly) the whole bag, not in panic
+    FullExit,
+    /// Sold at a steep loss, or at any loss shortly after buying
+    Panic,
+}
+
+impl ExitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExitKind::ProfitTaking => "profit-taking",
+            ExitKind::FullExit => "full exit",
+            ExitKind::Panic => "panic",
+        }
+    }
+}
+
+/// How we answer one kind of target exit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitAction {
+    /// Keep our position
+    Ignore,
+    /// Mirror only sells of at least this share of the target's holdings
+    MinPct(f64),
+    /// Sell the same share of our position as the target sold of theirs
+    Mirror,
+    /// Sell our whole position
+    All,
+}
+
+impl FromStr for ExitAction {
+    type Err = anyhow::Error;
+
+    /// Parses `ignore`, `mirror`, `all` or `min:<pct>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            None => match s.trim() {
+                "ignore" => Ok(ExitAction::Ignore),
+                "mirror" => Ok(ExitAction::Mirror),
+                "all" => Ok(ExitAction::All),
+                _ => Err(anyhow!(
+                    "Invalid exit action: '{}'. Use ignore, mirror, all or min:<pct>",
+                    s
+                )),
+            },
+            Some(("min", pct)) => Ok(ExitAction::MinPct(f64::from_str(pct)?)),
+            _ => Err(anyhow!(
+                "Invalid exit action: '{}'. Use ignore, mirror, all or min:<pct>",
+                s
+            )),
+        }
+    }
+}
+
+impl ExitAction {
+    /// Raw tokens of `balance` to sell for `signal`
+    pub fn sell_amount(&self, signal: &TradeSignal, balance: u64) -> u64 {
+        match self {
+            ExitAction::Ignore => 0,
+            ExitAction::MinPct(pct) if sold_pct(signal) < *pct => 0,
+            ExitAction::MinPct(_) | ExitAction::Mirror => signal.mirrored_sell_amount(balance),
+            ExitAction::All => balance,
+        }
+    }
+}
+
+/// Share of their holdings the target sold, in percent
+fn sold_pct(signal: &TradeSignal) -> f64 {
+    if signal.token_pre_balance == 0 {
+        return 100.0;
+    }
+    signal.token_amount as f64 / signal.token_pre_balance as f64 * 100.0
+}
+
+/// The target's estimated cost basis in one mint
+#[derive(Debug, Clone)]
+struct TargetEntry {
+    lamports: u64,
+    tokens: u64,
+    first_buy: Instant,
+}
+
+impl TargetEntry {
+    /// Lamports per raw token paid on average
+    fn price(&self) -> Option<f64> {
+        (self.tokens > 0).then(|| self.lamports as f64 / self.tokens as f64)
+    }
+}
+
+/// Per-kind copy-sell behaviour plus the thresholds that tell panic from a planned exit
+#[derive(Debug, Clone)]
+pub struct ExitPolicy {
+    pub profit_taking: ExitAction,
+    pub full_exit: ExitAction,
+    pub panic: ExitAction,
+    /// Loss from the target's entry that is a panic however long they held
+    pub panic_loss_pct: f64,
+    /// Any loss taken within this long of the first buy is a panic
+    pub panic_hold: Duration,
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        Self {
+            profit_taking: ExitAction::Mirror,
+            full_exit: ExitAction::Mirror,
+            panic: ExitAction::Mirror,
+            panic_loss_pct: DEFAULT_PANIC_LOSS_PCT,
+            panic_hold: Duration::from_secs(DEFAULT_PANIC_HOLD_SECS),
+        }
+    }
+}
+
+impl ExitPolicy {
+    /// Reads `EXIT_ON_PROFIT_TAKING`, `EXIT_ON_FULL_EXIT` and `EXIT_ON_PANIC` (default mirror),
+    /// plus `PANIC_LOSS_PCT` and `PANIC_HOLD_SECS`
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let action = |name: &str, default: ExitAction| match env::var(name) {
+            Ok(value) => ExitAction::from_str(&value),
+            Err(_) => Ok(default),
+        };
+        Ok(Self {
+            profit_taking: action("EXIT_ON_PROFIT_TAKING", default.profit_taking)?,
+            full_exit: action("EXIT_ON_FULL_EXIT", default.full_exit)?,
+            panic: action("EXIT_ON_PANIC", default.panic)?,
+            panic_loss_pct: env::var("PANIC_LOSS_PCT")
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .unwrap_or(default.panic_loss_pct),
+            panic_hold: env::var("PANIC_HOLD_SECS")
+                .ok()
+                .and_then(|v| u64::from_str(&v).ok())
+                .map(Duration::from_secs)
+                .unwrap_or(default.panic_hold),
+        })
+    }
+
+    pub fn action(&self, kind: ExitKind) -> ExitAction {
+        match kind {
+            ExitKind::ProfitTaking => self.profit_taking,
+            ExitKind::FullExit => self.full_exit,
+            ExitKind::Panic => self.panic,
+        }
+    }
+
+    /// Classifies a sell against the target's entry; without one, panic cannot be told apart
+    fn classify(
+        &self,
+        entry: Option<&TargetEntry>,
+        signal: &TradeSignal,
+        now: Instant,
+    ) -> ExitKind {
+        let loss_pct = entry
+            .and_then(|entry| entry.price())
+            .filter(|_| signal.token_amount > 0)
+            .map(|entry_price| {
+                let exit_price = signal.sol_amount as f64 / signal.token_amount as f64;
+                (1.0 - exit_price / entry_price) * 100.0
+            });
+        if let (Some(entry), Some(loss_pct)) = (entry, loss_pct) {
+            let quick = now.duration_since(entry.first_buy) < self.panic_hold;
+            if loss_pct >= self.panic_loss_pct || (loss_pct > 0.0 && quick) {
+                return ExitKind::Panic;
+            }
+        }
+        if sold_pct(signal) >= FULL_EXIT_PCT {
+            ExitKind::FullExit
+        } else {
+            ExitKind::ProfitTaking
+        }
+    }
+}
+
+/// Tracks each target's buys so their sells can be classified
+pub struct TargetExits {
+    policy: ExitPolicy,
+    entries: BoundedCache<(String, String), TargetEntry>,
+}
+
+impl TargetExits {
+    pub fn new(policy: ExitPolicy) -> Self {
+        Self {
+            policy,
+            entries: BoundedCache::from_env(
+                "target_entries",
+                ENTRY_CACHE_ENTRIES,
+                Some(Duration::from_secs(ENTRY_TTL_SECS)),
+            ),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(ExitPolicy::from_env()?))
+    }
+
+    /// Folds a target buy into their cost basis
+    pub async fn record_buy(&self, target: &str, signal: &TradeSignal) {
+        let key = (target.to_string(), signal.mint.clone());
+        let entry = match self.entries.get(&key).await {
+            Some(entry) => TargetEntry {
+                lamports: entry.lamports.saturating_add(signal.sol_amount),
+                tokens: entry.tokens.saturating_add(signal.token_amount),
+                first_buy: entry.first_buy,
+            },
+            None => TargetEntry {
+                lamports: signal.sol_amount,
+                tokens: signal.token_amount,
+                first_buy: Instant::now(),
+            },
+        };
+        self.entries.insert(key, entry).await;
+    }
+
+    /// Classifies a target sell and returns it with the action the policy takes for it. The
+    /// sold share leaves the cost basis, which is forgotten once the target is out.
+    pub async fn on_sell(&self, target: &str, signal: &TradeSignal) -> (ExitKind, ExitAction) {
+        let key = (target.to_string(), signal.mint.clone());
+        let entry = self.entries.get(&key).await;
+        let kind = self.policy.classify(entry.as_ref(), signal, Instant::now());
+        match entry {
+            Some(entry) if sold_pct(signal) < FULL_EXIT_PCT => {
+                let kept = 1.0 - sold_pct(signal) / 100.0;
+                let entry = TargetEntry {
+                    lamports: (entry.lamports as f64 * kept) as u64,
+                    tokens: (entry.tokens as f64 * kept) as u64,
+                    first_buy: entry.first_buy,
+                };
+                self.entries.insert(key, entry).await;
+            }
+            _ => {
+                self.entries.remove(&key).await;
+            }
+        }
+        (kind, self.policy.action(kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::swap::SwapDirection;
+
+    fn sell(sol_amount: u64, token_amount: u64, token_pre_balance: u64) -> TradeSignal {
+        TradeSignal {
+            signature: "sig".to_string(),
+            slot: 1,
+            mint: "mint".to_string(),
+            direction: SwapDirection::Sell,
+            sol_amount,
+            token_amount,
+            token_pre_balance,
+            decimals: 6,
+        }
+    }
+
+    fn entry(lamports: u64, tokens: u64, held: Duration) -> TargetEntry {
+        TargetEntry {
+            lamports,
+            tokens,
+            first_buy: Instant::now() - held,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let policy = ExitPolicy::default();
+        let now = Instant::now();
+        let long = Duration::from_secs(600);
+        // Entered at 1 lamport/token
+        let bought = entry(1_000, 1_000, long);
+        // Half the bag at 2x
+        assert_eq!(
+            policy.classify(Some(&bought), &sell(1_000, 500, 1_000), now),
+            ExitKind::ProfitTaking
+        );
+        // Everything at 2x
+        assert_eq!(
+            policy.classify(Some(&bought), &sell(2_000, 1_000, 1_000), now),
+            ExitKind::FullExit
+        );
+        // Everything at -50%
+        assert_eq!(
+            policy.classify(Some(&bought), &sell(500, 1_000, 1_000), now),
+            ExitKind::Panic
+        );
+        // A small loss is only a panic right after the buy
+        let small_loss = sell(450, 500, 1_000);
+        assert_eq!(
+            policy.classify(Some(&bought), &small_loss, now),
+            ExitKind::ProfitTaking
+        );
+        let fresh = entry(1_000, 1_000, Duration::from_secs(5));
+        assert_eq!(
+            policy.classify(Some(&fresh), &small_loss, now),
+            ExitKind::Panic
+        );
+        // No known entry
+        assert_eq!(
+            policy.classify(None, &sell(1, 1_000, 1_000), now),
+            ExitKind::FullExit
+        );
+    }
+
+    #[test]
+    fn test_actions() {
+        assert_eq!(
+            ExitAction::from_str("min:10").unwrap(),
+            ExitAction::MinPct(10.0)
+        );
+        assert!(ExitAction::from_str("hold").is_err());
+        let trim = sell(100, 50, 1_000);
+        assert_eq!(ExitAction::MinPct(10.0).sell_amount(&trim, 400), 0);
+        assert_eq!(ExitAction::Mirror.sell_amount(&trim, 400), 20);
+        assert_eq!(ExitAction::All.sell_amount(&trim, 400), 400);
+        assert_eq!(ExitAction::Ignore.sell_amount(&trim, 400), 0);
+    }
+}
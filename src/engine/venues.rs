@@ -0,0 +1,219 @@
+use std::{collections::HashSet, env, fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+
+use crate::engine::{router::Venue, swap::SwapDirection};
+
+/// A venue execution can be switched on or off for; signals are parsed from all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VenueKind {
+    PumpFun,
+    PumpSwap,
+    Raydium,
+    RaydiumCpmm,
+    RaydiumClmm,
+    /// Only reachable through Jupiter routes
+    Orca,
+    /// Only reachable through Jupiter routes
+    Meteora,
+    Jupiter,
+}
+
+impl VenueKind {
+    pub const ALL: [VenueKind; 8] = [
+        VenueKind::PumpFun,
+        VenueKind::PumpSwap,
+        VenueKind::Raydium,
+        VenueKind::RaydiumCpmm,
+        VenueKind::RaydiumClmm,
+        VenueKind::Orca,
+        VenueKind::Meteora,
+        VenueKind::Jupiter,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VenueKind::PumpFun => "pump",
+            VenueKind::PumpSwap => "pumpswap",
+            VenueKind::Raydium => "raydium",
+            VenueKind::RaydiumCpmm => "raydium_cpmm",
+            VenueKind::RaydiumClmm => "raydium_clmm",
+            VenueKind::Orca => "orca",
+            VenueKind::Meteora => "meteora",
+            VenueKind::Jupiter => "jupiter",
+        }
+    }
+
+    /// Jupiter's labels for this venue's pools, so disabled venues can be left out of its routes
+    pub fn jupiter_labels(&self) -> &'static [&'static str] {
+        match self {
+            VenueKind::PumpFun => &["Pump.fun"],
+            VenueKind::PumpSwap => &["Pump.fun Amm"],
+            VenueKind::Raydium => &["Raydium"],
+            VenueKind::RaydiumCpmm => &["Raydium CP"],
+            VenueKind::RaydiumClmm => &["Raydium CLMM"],
+            VenueKind::Orca => &["Whirlpool", "Orca V2", "Orca V1"],
+            VenueKind::Meteora => &["Meteora DLMM", "Meteora"],
+            VenueKind::Jupiter => &[],
+        }
+    }
+}
+
+impl fmt::Display for VenueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for VenueKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        VenueKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| anyhow!("Unknown venue '{}'", s.trim()))
+    }
+}
+
+impl From<&Venue> for VenueKind {
+    fn from(venue: &Venue) -> Self {
+        match venue {
+            Venue::BondingCurve => VenueKind::PumpFun,
+            Venue::PumpSwap { .. } => VenueKind::PumpSwap,
+            Venue::Raydium { .. } => VenueKind::Raydium,
+            Venue::RaydiumClmm { .. } => VenueKind::RaydiumClmm,
+            Venue::RaydiumCpmm { .. } => VenueKind::RaydiumCpmm,
+            Venue::Jupiter => VenueKind::Jupiter,
+        }
+    }
+}
+
+/// Which venues one trade direction may execute on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VenueRule {
+    /// Only these venues when set
+    pub allow: Option<HashSet<VenueKind>>,
+    /// Never these venues, even when allowed
+    pub deny: HashSet<VenueKind>,
+}
+
+impl VenueRule {
+    fn parse_list(var: &str) -> Result<Option<HashSet<VenueKind>>> {
+        let Ok(list) = env::var(var) else {
+            return Ok(None);
+        };
+        list.split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| VenueKind::from_str(name).map_err(|e| anyhow!("{}: {}", var, e)))
+            .collect::<Result<HashSet<_>>>()
+            .map(Some)
+    }
+
+    /// Reads `<PREFIX>_VENUES_ALLOW` and `<PREFIX>_VENUES_DENY`, comma-separated venue names
+    fn from_env(prefix: &str) -> Result<Self> {
+        Ok(Self {
+            allow: Self::parse_list(&format!("{}_VENUES_ALLOW", prefix))?,
+            deny: Self::parse_list(&format!("{}_VENUES_DENY", prefix))?.unwrap_or_default(),
+        })
+    }
+
+    pub fn allows(&self, kind: VenueKind) -> bool {
+        !self.deny.contains(&kind)
+            && self
+                .allow
+                .as_ref()
+                .map_or(true, |allow| allow.contains(&kind))
+    }
+}
+
+/// Per-direction venue restrictions enforced by the router; everything is enabled by default
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VenuePolicy {
+    pub buy: VenueRule,
+    pub sell: VenueRule,
+}
+
+impl VenuePolicy {
+    /// Reads `BUY_VENUES_ALLOW`, `BUY_VENUES_DENY`, `SELL_VENUES_ALLOW` and `SELL_VENUES_DENY`
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            buy: VenueRule::from_env("BUY")?,
+            sell: VenueRule::from_env("SELL")?,
+        })
+    }
+
+    fn rule(&self, swap_direction: &SwapDirection) -> &VenueRule {
+        match swap_direction {
+            SwapDirection::Buy => &self.buy,
+            SwapDirection::Sell => &self.sell,
+        }
+    }
+
+    pub fn allows(&self, kind: VenueKind, swap_direction: &SwapDirection) -> bool {
+        self.rule(swap_direction).allows(kind)
+    }
+
+    /// Errors if `kind` is disabled for `swap_direction`
+    pub fn check(&self, kind: VenueKind, swap_direction: &SwapDirection) -> Result<()> {
+        if self.allows(kind, swap_direction) {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "{} is disabled for {}s",
+            kind,
+            swap_direction.as_str()
+        ))
+    }
+
+    /// Jupiter labels of the venues disabled for `swap_direction`, for its `excludeDexes`
+    pub fn excluded_dexes(&self, swap_direction: &SwapDirection) -> Vec<&'static str> {
+        VenueKind::ALL
+            .into_iter()
+            .filter(|kind| !self.allows(*kind, swap_direction))
+            .flat_map(|kind| kind.jupiter_labels().iter().copied())
+            .collect()
+    }
+
+    /// Venues that can be bought on but not sold on, where a position could get stuck
+    pub fn one_way(&self) -> Vec<VenueKind> {
+        VenueKind::ALL
+            .into_iter()
+            .filter(|kind| self.buy.allows(*kind) && !self.sell.allows(*kind))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_venue_policy() {
+        let policy = VenuePolicy {
+            buy: VenueRule {
+                allow: Some(HashSet::from([VenueKind::PumpFun, VenueKind::Raydium])),
+                deny: HashSet::new(),
+            },
+            sell: VenueRule {
+                allow: None,
+                deny: HashSet::from([VenueKind::Meteora]),
+            },
+        };
+        assert!(policy.allows(VenueKind::PumpFun, &SwapDirection::Buy));
+        assert!(policy
+            .check(VenueKind::Jupiter, &SwapDirection::Buy)
+            .is_err());
+        assert!(policy.allows(VenueKind::Jupiter, &SwapDirection::Sell));
+        assert_eq!(
+            policy.excluded_dexes(&SwapDirection::Sell),
+            vec!["Meteora DLMM", "Meteora"]
+        );
+        assert!(policy.one_way().is_empty());
+        assert_eq!(
+            VenueKind::from_str(" Raydium_CPMM").unwrap(),
+            VenueKind::RaydiumCpmm
+        );
+        assert!(VenueKind::from_str("serum").is_err());
+    }
+}
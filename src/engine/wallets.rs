@@ -0,0 +1,289 @@
+use std::{
+    env,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::{
+    common::{utils::AppState, wallet::read_keypair},
+    core::{
+        token::{get_account_info, get_token_program},
+        tx::TxSigner,
+    },
+    engine::{exit::split_amount, position::Position, swap::SwapDirection},
+};
+
+/// How copy buys are spread over the wallet pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletMode {
+    /// Each buy goes to the next wallet in turn
+    Rotate,
+    /// Each buy is split evenly over this many wallets, taken in turn
+    Split(usize),
+}
+
+impl FromStr for WalletMode {
+    type Err = anyhow::Error;
+
+    /// Parses `rotate` or `split:<wallets>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            None if s.trim() == "rotate" => Ok(WalletMode::Rotate),
+            Some(("split", n)) => match usize::from_str(n)? {
+                0 => Err(anyhow!("Split must use at least one wallet")),
+                n => Ok(WalletMode::Split(n)),
+            },
+            _ => Err(anyhow!(
+                "Invalid wallet mode: '{}'. Use rotate or split:<wallets>",
+                s
+            )),
+        }
+    }
+}
+
+/// Every keypair the bot trades from; the first one also pays for everything else
+pub struct WalletPool {
+    wallets: Vec<Arc<TxSigner>>,
+    mode: WalletMode,
+    next: AtomicUsize,
+}
+
+impl WalletPool {
+    pub fn new(primary: Arc<TxSigner>, extra: Vec<Arc<TxSigner>>, mode: WalletMode) -> Self {
+        let mut wallets = vec![primary];
+        for wallet in extra {
+            if !wallets.iter().any(|w| w.pubkey() == wallet.pubkey()) {
+                wallets.push(wallet);
+            }
+        }
+        Self {
+            wallets,
+            mode,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds the key files in `WALLET_PATHS` (comma-separated, plain or encrypted) to `primary`,
+    /// spread per `WALLET_MODE` (default rotate)
+    pub fn from_env(primary: Arc<TxSigner>) -> Result<Self> {
+        let extra = env::var("WALLET_PATHS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(|path| Ok(Arc::new(read_keypair(Path::new(path))?) as Arc<TxSigner>))
+            .collect::<Result<Vec<_>>>()?;
+        let mode = match env::var("WALLET_MODE") {
+            Ok(mode) => WalletMode::from_str(&mode)?,
+            Err(_) => WalletMode::Rotate,
+        };
+        Ok(Self::new(primary, extra, mode))
+    }
+
+    pub fn primary(&self) -> &Arc<TxSigner> {
+        &self.wallets[0]
+    }
+
+    pub fn all(&self) -> &[Arc<TxSigner>] {
+        &self.wallets
+    }
+
+    pub fn len(&self) -> usize {
+        self.wallets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wallets.is_empty()
+    }
+
+    pub fn get(&self, pubkey: &str) -> Option<Arc<TxSigner>> {
+        self.wallets
+            .iter()
+            .find(|wallet| wallet.pubkey().to_string() == pubkey)
+            .cloned()
+    }
+
+    /// Wallets and lamports that carry one buy of `amount`
+    pub fn assign(&self, amount: u64) -> Vec<(Arc<TxSigner>, u64)> {
+        let count = match self.mode {
+            WalletMode::Rotate => 1,
+            WalletMode::Split(n) => n.min(self.wallets.len()),
+        };
+        let start = self.next.fetch_add(count, Ordering::Relaxed);
+        split_amount(amount, count)
+            .into_iter()
+            .enumerate()
+            .map(|(i, amount)| {
+                let wallet = &self.wallets[(start + i) % self.wallets.len()];
+                (wallet.clone(), amount)
+            })
+            .collect()
+    }
+
+    /// Wallets that may hold part of `position`: the primary plus every wallet that bought
+    /// into it. Wallets no longer in the pool are skipped.
+    pub fn holders(&self, position: Option<&Position>) -> Vec<Arc<TxSigner>> {
+        let mut holders = vec![self.primary().clone()];
+        for pubkey in position.map(|p| p.wallets.as_slice()).unwrap_or_default() {
+            if let Some(wallet) = self.get(pubkey) {
+                if !holders.iter().any(|w| w.pubkey() == wallet.pubkey()) {
+                    holders.push(wallet);
+                }
+            }
+        }
+        holders
+    }
+}
+
+
+/// Splits a sell of `amount` over `balances` in proportion to what each one holds
+pub fn pro_rata(balances: &[u64], amount: u64) -> Vec<u64> {
+    let total = balances.iter().map(|b| *b as u128).sum::<u128>();
+    if total == 0 {
+        return vec![0; balances.len()];
+    }
+    let amount = (amount as u128).min(total);
+    balances
+        .iter()
+        .map(|balance| (*balance as u128 * amount / total) as u64)
+        .collect()
+}
+
+/// Raw units of `mint` each holder of the position has
+pub async fn holder_balances(state: &AppState, mint: &str) -> Vec<(Arc<TxSigner>, u64)> {
+    let Ok(mint_pubkey) = Pubkey::from_str(mint) else {
+        return vec![];
+    };
+    let Ok(program) = get_token_program(&state.rpc_nonblocking_client, &mint_pubkey).await else {
+        return vec![];
+    };
+    let position = state.positions.get(mint).await;
+    let mut balances = vec![];
+    for wallet in state.wallets.holders(position.as_ref()) {
+        let ata =
+            get_associated_token_address_with_program_id(&wallet.pubkey(), &mint_pubkey, &program);
+        let balance = get_account_info(state.rpc_nonblocking_client.clone(), &mint_pubkey, &ata)
+            .await
+            .map(|account| account.base.amount)
+            .unwrap_or(0);
+        balances.push((wallet, balance));
+    }
+    balances
+}
+
+/// Raw units of `mint` held across the whole pool
+pub async fn position_balance(state: &AppState, mint: &str) -> u64 {
+    holder_balances(state, mint)
+        .await
+        .iter()
+        .map(|(_, balance)| balance)
+        .sum()
+}
+
+/// Tokens of `mint` held across the pool in UI units, for valuations
+pub async fn position_ui_balance(state: &AppState, mint: &str) -> Result<f64> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let program = get_token_program(&state.rpc_nonblocking_client, &mint_pubkey).await?;
+    let position = state.positions.get(mint).await;
+    let mut total = 0.0;
+    for wallet in state.wallets.holders(position.as_ref()) {
+        let ata =
+            get_associated_token_address_with_program_id(&wallet.pubkey(), &mint_pubkey, &program);
+        // A holder without a token account for the mint holds nothing
+        if let Ok(balance) = state
+            .rpc_nonblocking_client
+            .get_token_account_balance(&ata)
+            .await
+        {
+            total += balance.ui_amount.unwrap_or(0.0);
+        }
+    }
+    Ok(total)
+}
+
+/// Per-wallet states and amounts that execute one trade: buys follow the pool's mode, sells
+/// come out of every holder in proportion to its balance
+pub async fn trade_legs(
+    state: &AppState,
+    mint: &str,
+    direction: &SwapDirection,
+    amount: u64,
+) -> Vec<(AppState, u64)> {
+    match direction {
+        SwapDirection::Buy => state
+            .wallets
+            .assign(amount)
+            .into_iter()
+            .map(|(wallet, amount)| (state.with_wallet(wallet), amount))
+            .collect(),
+        SwapDirection::Sell => {
+            let balances = holder_balances(state, mint).await;
+            let amounts = pro_rata(
+                &balances.iter().map(|(_, b)| *b).collect::<Vec<_>>(),
+                amount,
+            );
+            balances
+                .into_iter()
+                .zip(amounts)
+                .filter(|(_, amount)| *amount > 0)
+                .map(|((wallet, _), amount)| (state.with_wallet(wallet), amount))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    fn pool(wallets: usize, mode: WalletMode) -> WalletPool {
+        let extra = (1..wallets)
+            .map(|_| Arc::new(Keypair::new()) as Arc<TxSigner>)
+            .collect();
+        WalletPool::new(Arc::new(Keypair::new()), extra, mode)
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(WalletMode::from_str("rotate").unwrap(), WalletMode::Rotate);
+        assert_eq!(
+            WalletMode::from_str("split:3").unwrap(),
+            WalletMode::Split(3)
+        );
+        assert!(WalletMode::from_str("split:0").is_err());
+        assert!(WalletMode::from_str("spread").is_err());
+    }
+
+    #[test]
+    fn test_assign_rotates_and_splits() {
+        let rotate = pool(3, WalletMode::Rotate);
+        let picked = (0..4)
+            .map(|_| rotate.assign(100)[0].0.pubkey())
+            .collect::<Vec<_>>();
+        assert_ne!(picked[0], picked[1]);
+        assert_eq!(picked[0], picked[3]);
+
+        let split = pool(2, WalletMode::Split(3));
+        let legs = split.assign(101);
+        // Capped at the pool size
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs.iter().map(|(_, amount)| amount).sum::<u64>(), 101);
+        assert_ne!(legs[0].0.pubkey(), legs[1].0.pubkey());
+    }
+
+    #[test]
+    fn test_pro_rata() {
+        assert_eq!(pro_rata(&[300, 100], 200), vec![150, 50]);
+        assert_eq!(pro_rata(&[300, 0], 500), vec![300, 0]);
+        assert_eq!(pro_rata(&[0, 0], 10), vec![0, 0]);
+    }
+}
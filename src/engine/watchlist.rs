@@ -0,0 +1,375 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::Value;
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey};
+use tokio::{
+    sync::{broadcast::error::RecvError, RwLock},
+    time::Instant,
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::pump::{get_bonding_curve_account, PUMP_PROGRAM_ID},
+    engine::{
+        execution::record_execution, router::Venue, signal::parse_trade_signal, swap::SwapDirection,
+    },
+    risk::token_safety::passes_safety,
+    services::notify::Event,
+};
+
+const ENTRY_SLIPPAGE_BPS: u64 = 1_000;
+
+/// A condition that must hold before a watched token is bought
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryCondition {
+    /// Price (SOL per token) is at or below the level
+    PriceBelow(f64),
+    /// Bonding curve progress is at or above this percentage
+    CurveProgressAbove(f64),
+    /// This wallet has bought the token since the entry was added
+    WalletBuys(String),
+}
+
+impl FromStr for EntryCondition {
+    type Err = anyhow::Error;
+
+    /// Parses `price_below:<price>`, `progress_above:<pct>` or `buyer:<wallet>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some(("price_below", price)) => Ok(EntryCondition::PriceBelow(f64::from_str(price)?)),
+            Some(("progress_above", pct)) => {
+                Ok(EntryCondition::CurveProgressAbove(f64::from_str(pct)?))
+            }
+            Some(("buyer", wallet)) => Ok(EntryCondition::WalletBuys(
+                Pubkey::from_str(wallet.trim())?.to_string(),
+            )),
+            _ => Err(anyhow!(
+                "Invalid entry condition: '{}'. Use price_below:<price>, progress_above:<pct> or buyer:<wallet>",
+                s
+            )),
+        }
+    }
+}
+
+/// A token under research, bought automatically once all its conditions hold
+#[derive(Debug, Clone)]
+pub struct WatchEntry {
+    pub id: u64,
+    pub mint: String,
+    pub amount_lamports: u64,
+    pub conditions: Vec<EntryCondition>,
+    /// Wallets from `WalletBuys` conditions that have been seen buying
+    pub seen_buyers: HashSet<String>,
+}
+
+impl WatchEntry {
+    fn needs_progress(&self) -> bool {
+        self.conditions
+            .iter()
+            .any(|c| matches!(c, EntryCondition::CurveProgressAbove(_)))
+    }
+
+    /// True once every condition holds for the latest known price and curve progress
+    pub fn is_triggered(&self, price: Option<f64>, progress: Option<f64>) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            EntryCondition::PriceBelow(level) => price.is_some_and(|price| price <= *level),
+            EntryCondition::CurveProgressAbove(pct) => {
+                progress.is_some_and(|progress| progress >= *pct)
+            }
+            EntryCondition::WalletBuys(wallet) => self.seen_buyers.contains(wallet),
+        })
+    }
+}
+
+/// Tokens waiting on entry conditions, separate from open positions
+#[derive(Default)]
+pub struct Watchlist {
+    entries: RwLock<HashMap<u64, WatchEntry>>,
+    prices: RwLock<HashMap<String, f64>>,
+    progress: RwLock<HashMap<String, f64>>,
+    next_id: AtomicU64,
+}
+
+impl Watchlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry and starts watching the mint's price
+    pub async fn add(
+        &self,
+        state: &AppState,
+        mint: &str,
+        amount_lamports: u64,
+        conditions: Vec<EntryCondition>,
+    ) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.entries.write().await.insert(
+            id,
+            WatchEntry {
+                id,
+                mint: mint.to_string(),
+                amount_lamports,
+                conditions,
+                seen_buyers: HashSet::new(),
+            },
+        );
+        state.price_feed.watch(mint).await;
+        id
+    }
+
+    /// Removes an entry; returns false if it did not exist
+    pub async fn remove(&self, state: &AppState, id: u64) -> bool {
+        match self.entries.write().await.remove(&id) {
+            Some(entry) => {
+                state.price_feed.unwatch(&entry.mint).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn list(&self) -> Vec<WatchEntry> {
+        self.entries.read().await.values().cloned().collect()
+    }
+
+    /// Seeds entries from `WATCHLIST` ("<mint>@<sol>=<condition>,<condition>;...")
+    pub async fn load_from_env(&self, state: &AppState) -> Result<()> {
+        let Ok(value) = env::var("WATCHLIST") else {
+            return Ok(());
+        };
+        for entry in value.split(';').filter(|e| !e.trim().is_empty()) {
+            let (target, conditions) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid WATCHLIST entry: '{}'", entry))?;
+            let (mint, sol) = target
+                .split_once('@')
+                .ok_or_else(|| anyhow!("WATCHLIST entry needs <mint>@<sol>: '{}'", entry))?;
+            let conditions = conditions
+                .split(',')
+                .map(EntryCondition::from_str)
+                .collect::<Result<Vec<_>>>()?;
+            self.add(
+                state,
+                mint.trim(),
+                sol_to_lamports(f64::from_str(sol.trim())?),
+                conditions,
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Whether any entry on this mint needs the curve progress fetched
+    pub async fn needs_progress(&self, mint: &str) -> bool {
+        self.entries
+            .read()
+            .await
+            .values()
+            .any(|entry| entry.mint == mint && entry.needs_progress())
+    }
+
+    /// Records a price (and optionally curve progress) and takes the entries that fired
+    pub async fn evaluate(&self, mint: &str, price: f64, progress: Option<f64>) -> Vec<WatchEntry> {
+        self.prices.write().await.insert(mint.to_string(), price);
+        if let Some(progress) = progress {
+            self.progress
+                .write()
+                .await
+                .insert(mint.to_string(), progress);
+        }
+        self.take_triggered(mint).await
+    }
+
+    /// Latches buys by watched wallets in a notification and takes the entries that fired
+    pub async fn observe_trade(&self, json: &Value) -> Vec<WatchEntry> {
+        let mut touched = Vec::new();
+        {
+            let mut entries = self.entries.write().await;
+            for entry in entries.values_mut() {
+                let wallets: Vec<String> = entry
+                    .conditions
+                    .iter()
+                    .filter_map(|c| match c {
+                        EntryCondition::WalletBuys(wallet) => Some(wallet.clone()),
+                        _ => None,
+                    })
+                    .filter(|wallet| !entry.seen_buyers.contains(wallet))
+                    .collect();
+                for wallet in wallets {
+                    let bought = parse_trade_signal(json, &wallet).is_some_and(|signal| {
+                        signal.mint == entry.mint && matches!(signal.direction, SwapDirection::Buy)
+                    });
+                    if bought {
+                        entry.seen_buyers.insert(wallet);
+                        touched.push(entry.mint.clone());
+                    }
+                }
+            }
+        }
+
+        let mut fired = Vec::new();
+        for mint in touched {
+            fired.extend(self.take_triggered(&mint).await);
+        }
+        fired
+    }
+
+    async fn take_triggered(&self, mint: &str) -> Vec<WatchEntry> {
+        let price = self.prices.read().await.get(mint).copied();
+        let progress = self.progress.read().await.get(mint).copied();
+
+        let mut entries = self.entries.write().await;
+        let fired: Vec<u64> = entries
+            .values()
+            .filter(|entry| entry.mint == mint && entry.is_triggered(price, progress))
+            .map(|entry| entry.id)
+            .collect();
+        fired
+            .into_iter()
+            .filter_map(|id| entries.remove(&id))
+            .collect()
+    }
+}
+
+/// Bonding curve progress of a mint, or None once it trades elsewhere
+async fn curve_progress(state: &AppState, mint: &str) -> Option<f64> {
+    if state.router.route(state, mint).await.ok()? != Venue::BondingCurve {
+        return None;
+    }
+    let mint = Pubkey::from_str(mint).ok()?;
+    let (_, _, curve) =
+        get_bonding_curve_account(state.rpc_client.clone(), &mint, &PUMP_PROGRAM_ID)
+            .await
+            .ok()?;
+    Some(curve.progress_pct())
+}
+
+/// Buys a watched token whose conditions fired
+pub async fn execute_entry(state: AppState, entry: WatchEntry, jito_client: Arc<JitoRpcClient>) {
+    state.price_feed.unwatch(&entry.mint).await;
+    if !passes_safety(&entry.mint, &state).await {
+        return;
+    }
+
+    let result = state
+        .router
+        .swap(
+            state.clone(),
+            &entry.mint,
+            entry.amount_lamports,
+            SwapDirection::Buy,
+            ENTRY_SLIPPAGE_BPS,
+            jito_client,
+            Instant::now(),
+        )
+        .await;
+    let message = match result {
+        Ok(signatures) => {
+            state
+                .positions
+                .record_buy(
+                    &entry.mint,
+                    None,
+                    entry.amount_lamports,
+                    state.fees.tx_cost(),
+                )
+                .await;
+            tokio::spawn(record_execution(
+                state.clone(),
+                entry.mint.clone(),
+                SwapDirection::Buy,
+                signatures,
+                None,
+            ));
+            format!("Watchlist entry {} bought {}", entry.id, entry.mint)
+        }
+        Err(e) => format!(
+            "Watchlist entry {} failed to buy {}: {}",
+            entry.id, entry.mint, e
+        ),
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+/// Evaluates watchlist entries on every price update and buys the ones that fire
+pub async fn run_watchlist(state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let mut updates = state.price_feed.subscribe();
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+
+        let progress = if state.watchlist.needs_progress(&update.mint).await {
+            curve_progress(&state, &update.mint).await
+        } else {
+            None
+        };
+        for entry in state
+            .watchlist
+            .evaluate(&update.mint, update.price, progress)
+            .await
+        {
+            tokio::spawn(execute_entry(state.clone(), entry, jito_client.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+    fn entry(conditions: Vec<EntryCondition>) -> WatchEntry {
+        WatchEntry {
+            id: 0,
+            mint: "mint".to_string(),
+            amount_lamports: 1,
+            conditions,
+            seen_buyers: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            EntryCondition::from_str("price_below:0.00003").unwrap(),
+            EntryCondition::PriceBelow(0.00003)
+        );
+        assert_eq!(
+            EntryCondition::from_str(&format!("buyer:{}", WALLET)).unwrap(),
+            EntryCondition::WalletBuys(WALLET.to_string())
+        );
+        assert!(EntryCondition::from_str("buyer:not-a-wallet").is_err());
+        assert!(EntryCondition::from_str("volume_above:10").is_err());
+    }
+
+    #[test]
+    fn test_all_conditions_must_hold() {
+        let mut entry = entry(vec![
+            EntryCondition::PriceBelow(1.0),
+            EntryCondition::CurveProgressAbove(50.0),
+            EntryCondition::WalletBuys(WALLET.to_string()),
+        ]);
+        assert!(!entry.is_triggered(Some(0.5), Some(60.0)));
+
+        entry.seen_buyers.insert(WALLET.to_string());
+        assert!(entry.is_triggered(Some(0.5), Some(60.0)));
+        assert!(!entry.is_triggered(Some(1.5), Some(60.0)));
+        assert!(!entry.is_triggered(Some(0.5), None));
+    }
+}
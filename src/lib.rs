@@ -2,4 +2,5 @@ pub mod common;
 pub mod core;
 pub mod dex;
 pub mod engine;
+pub mod risk;
 pub mod services;
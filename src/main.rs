@@ -1,210 +1,1246 @@
-use bincode::Options;
+use chrono::Utc;
 use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use clap::{Parser, Subcommand};
+use temp::common::archive::{export_state, import_state};
+use temp::common::config::{edit_targets, run_config_watcher, Config, LiveConfig};
+use temp::common::migrations::{migrate, schema_version, SCHEMA_VERSION};
+use temp::common::storage::data_dir;
 use temp::common::utils::{
-    create_arc_rpc_client, create_nonblocking_rpc_client, import_arc_wallet, import_env_var,
-    import_wallet, log_message, AppState,
+    create_nonblocking_rpc_client, import_arc_wallet, import_env_var, import_wallet, log_message,
+    AppState,
 };
-use temp::core::token::get_account_info;
-use temp::core::tx::jito_confirm;
-use temp::engine::swap::{pump_swap, raydium_swap};
+use temp::common::wallet::encrypt_key_file;
+use temp::core::alt::{init_lookup_table, LookupTableConfig};
+use temp::core::ata::KNOWN_ATAS;
+use temp::core::rpc_pool::{pooled_clients, run_health_checks, RpcPool};
+use temp::core::tx_archive::{replay, TxArchive};
+use temp::dex::pump_global::{pump_fee_bps, refresh_pump_params, run_pump_params_refresh};
+use temp::engine::fees::FeeModel;
+use temp::engine::approval::{await_approval, ApprovalBook};
+use temp::engine::backtest::{backtest, fetch_history, load_archive, save_archive, BacktestConfig};
+use temp::engine::copy_timing::{Admission, CopyTiming};
+use temp::engine::creator_exit::{creator_exit, run_creator_sync, CreatorWatch};
+use temp::engine::dual_control::{execute, ControlAction, DualControl};
+use temp::engine::execution::record_execution;
+use temp::engine::executor::COPY_EXECUTOR;
+use temp::engine::flatten::{run_flatten_schedule, FlattenSchedule};
+use temp::engine::hold_timer::{run_hold_timer, HoldTimer};
+use temp::engine::jupiter_signal::invokes_jupiter;
+use temp::engine::momentum::{run_momentum_exit, MomentumExit};
+use temp::engine::panic::{run_panic_exits, PanicConfig};
+use temp::engine::position::{load_closed_trades, PositionManager};
+use temp::engine::projection::TARGET_FLOWS;
+use temp::engine::remnants::{run_remnant_sweeper, RemnantConfig};
+use temp::engine::recovery::recover_positions;
+use temp::engine::rent_reclaim::{run_rent_reclaim, RentReclaimConfig};
+use temp::engine::reorg::ReorgGuard;
+use temp::engine::router::Router;
+use temp::engine::shutdown::{drain_timeout, shutdown_signal, SHUTDOWN};
+use temp::engine::raydium_signal::{invokes_raydium, parse_raydium_trade, RaydiumProgram};
+use temp::engine::signal::{
+    invokes_program, parse_launch_signal, parse_trade_signal, TradeSignal, PUMP_PROGRAM,
+};
+use temp::engine::sizing::{CopySizing, SizingConfig};
+use temp::engine::sniper::Sniper;
+use temp::engine::stop_loss::run_stop_loss;
+use temp::engine::swap::{raydium_swap, SwapDirection};
+use temp::engine::target_exit::TargetExits;
+use temp::engine::wallets::{position_balance, trade_legs, WalletPool};
+use temp::engine::venues::VenuePolicy;
+use temp::engine::watchlist::{execute_entry, run_watchlist, Watchlist};
+use temp::risk::breaker::{run_loss_breaker, BreakerConfig, LossBreaker};
+use temp::risk::expectancy::{record_exit, settle_position, ExpectancyGate};
+use temp::risk::filters::{passes_filters, TokenFilters};
+use temp::risk::hedge::{run_hedge_monitor, HedgeConfig};
+use temp::risk::limits::{reserve_buy, ExposureLimits, Reservation};
+use temp::risk::holders::{run_holder_tracker, HolderConfig, HolderTracker};
+use temp::risk::impairment::ImpairedAction;
+use temp::risk::lp_watch::{run_lp_watch, LpWatchConfig};
+use temp::risk::market::{market_allows, MarketBounds};
+use temp::risk::maturity::{maturity_allows, MaturityFilter};
+use temp::risk::pause::CopyPause;
+use temp::risk::supply_watch::{run_supply_watch, SupplyWatchConfig};
+use temp::risk::tilt::TiltGuard;
+use temp::risk::token_safety::{passes_safety, SafetyConfig};
+use temp::services::alerts::{run_alerts, AlertBook};
+use temp::services::api::{run_api_server, ApiConfig};
+use temp::services::attribution::{run_attribution, AttributionConfig};
+use temp::services::blockhash::run_blockhash_prefetch;
+use temp::services::curve_cache::{run_curve_cache, CurveCache};
+use temp::services::dashboard::ACTIVITY;
+use temp::services::grafana::write_monitoring;
+use temp::services::idle::{run_idle_monitor, IdleConfig, IDLE};
+use temp::services::jito::BLOCK_ENGINES;
+use temp::services::leader_schedule::run_leader_tracker;
+use temp::services::metrics::{run_metrics_server, METRICS};
+use temp::services::notify::{run_telegram_control, Event, Notifier};
+use temp::services::price::PriceService;
+use temp::services::price_feed::{run_price_feed, PriceFeed};
+use temp::services::remote_signer::run_signer_service;
+#[cfg(feature = "shredstream")]
+use temp::services::shredstream::ShredstreamSource;
+use temp::services::slo::{observe_latency, SloMonitor, TradeLatency};
+use temp::services::snapshot::{sign_report, verify_report, Snapshot};
+use temp::services::sol_usd::run_sol_usd_feed;
+use temp::services::sources::{EventAck, SignalHub, WebhookSource, WsSource};
 // use copy_trading_bot::dex::pump::pump_sdk_swap;
 use dotenv::dotenv;
-use futures_util::{SinkExt, StreamExt};
-use serde::Serialize;
+use futures_util::future::join_all;
 use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
 use solana_sdk::message::VersionedMessage;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::VersionedTransaction;
-use spl_associated_token_account::get_associated_token_address;
+use std::collections::HashMap;
 use std::env;
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::{Arc, LazyLock};
-use tokio::time::Instant;
-use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
-
-#[derive(Serialize)]
-struct SwapRequest {
-    quoteResponse: serde_json::Value, // You may deserialize it into a specific struct if known
-    userPublicKey: String,
-    wrapAndUnwrapSol: bool,
-    dynamicComputeUnitLimit: bool,
-    prioritizationFeeLamports: u64,
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+#[derive(Parser)]
+#[command(about = "Solana copy-trading bot")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 }
-#[tokio::main]
 
-async fn main() {
-    dotenv().ok();
-    let target = env::var("TARGET_PUBKEY").expect("TARGET not set");
+#[derive(Subcommand)]
+enum Command {
+    /// Move bot state between machines
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// Manage wallet key files
+    Wallet {
+        #[command(subcommand)]
+        action: WalletAction,
+    },
+    /// Write a shareable HTML/JSON performance snapshot with no wallet or full mint addresses
+    Snapshot {
+        /// Directory for snapshot.html and snapshot.json
+        #[arg(long, default_value = "snapshot")]
+        out: PathBuf,
+        /// How many recent trades to list
+        #[arg(long, default_value_t = 20)]
+        recent: usize,
+        /// Sign snapshot.json with the bot's wallet into snapshot.json.sig, naming the wallet
+        #[arg(long)]
+        sign: bool,
+    },
+    /// Check a signed snapshot against the wallet that signed it
+    Verify {
+        /// Directory holding snapshot.json and snapshot.json.sig
+        #[arg(default_value = "snapshot")]
+        dir: PathBuf,
+    },
+    /// Re-simulate an archived submission (`TX_ARCHIVE=true`) by signature or bundle id
+    Replay {
+        signature: String,
+        /// Node holding the state to replay against, ideally as of the archived slot;
+        /// defaults to `RPC_ENDPOINT`
+        #[arg(long)]
+        rpc: Option<String>,
+        /// Swap in a current blockhash, for nodes that no longer know the original one
+        #[arg(long)]
+        fresh_blockhash: bool,
+    },
+    /// Write a Grafana dashboard and Prometheus alert rules for the exported metrics
+    Monitoring {
+        /// Directory for grafana-dashboard.json and alert-rules.yml
+        #[arg(long, default_value = "monitoring")]
+        out: PathBuf,
+        /// Prometheus scrape job that polls `METRICS_ADDR`
+        #[arg(long, default_value = "copybot")]
+        job: String,
+    },
+    /// Bring the data directory up to this build's storage schema, or just report its version
+    Migrate {
+        /// Only print the schema version
+        #[arg(long)]
+        check: bool,
+    },
+    /// Buy a mint by hand through the same router and risk limits as copied buys
+    Buy { mint: String, sol: f64 },
+    /// Sell a share of a position by hand from every wallet holding it
+    Sell {
+        mint: String,
+        /// Percent of the position to sell
+        #[arg(default_value_t = 100.0)]
+        pct: f64,
+    },
+    /// List open positions
+    Positions,
+    /// Print realized PnL and open exposure
+    Pnl,
+    /// Add or remove copy targets in the config file; a running bot reloads them live
+    Track {
+        #[command(subcommand)]
+        action: TrackAction,
+    },
+    /// Replay target wallets' past pump.fun trades through the copy sizing and report the PnL
+    Backtest {
+        /// Wallets to replay; defaults to the configured targets
+        targets: Vec<String>,
+        /// Most recent transactions to fetch per target
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+        /// Replay `getTransaction` results from this JSONL file instead of fetching them
+        #[arg(long)]
+        archive: Option<PathBuf>,
+        /// Write the fetched transactions to this JSONL file for later runs
+        #[arg(long)]
+        save: Option<PathBuf>,
+        /// Simulated starting balance in SOL
+        #[arg(long, default_value_t = 10.0)]
+        balance: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrackAction {
+    Add { wallet: String },
+    Remove { wallet: String },
+}
+
+#[derive(Subcommand)]
+enum WalletAction {
+    /// Write a JSON or base58 key file encrypted under a passphrase, for `WALLET_PATH`
+    Encrypt { input: PathBuf, output: PathBuf },
+    /// Hold the key here and sign for a bot running with `WALLET_SIGNER=remote`, within
+    /// `SIGNER_MAX_SOL_PER_TX` and `SIGNER_ALLOWED_PROGRAMS`
+    ServeSigner,
+}
+
+#[derive(Subcommand)]
+enum StateAction {
+    /// Bundle config, positions, data files and journal into an encrypted archive
+    Export { path: PathBuf },
+    /// Restore an archive written by `state export`
+    Import {
+        path: PathBuf,
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Archive passphrase from `STATE_ARCHIVE_PASSPHRASE`, or prompted for
+fn archive_passphrase() -> String {
+    if let Ok(passphrase) = env::var("STATE_ARCHIVE_PASSPHRASE") {
+        return passphrase;
+    }
+    print!("Archive passphrase: ");
+    let _ = io::stdout().flush();
+    let mut passphrase = String::new();
+    io::stdin()
+        .read_line(&mut passphrase)
+        .expect("Failed to read passphrase");
+    passphrase.trim_end_matches(['\r', '\n']).to_string()
+}
+
+fn run_state_command(action: StateAction) -> anyhow::Result<()> {
+    let passphrase = archive_passphrase();
+    match action {
+        StateAction::Export { path } => {
+            let files = export_state(&path, &passphrase)?;
+            println!("Exported {} files to {}", files, path.display());
+        }
+        StateAction::Import { path, force } => {
+            let files = import_state(&path, &passphrase, force)?;
+            println!("Restored {} files from {}", files, path.display());
+        }
+    }
+    Ok(())
+}
+
+async fn run_wallet_command(action: WalletAction) -> anyhow::Result<()> {
+    match action {
+        WalletAction::Encrypt { input, output } => {
+            encrypt_key_file(&input, &output)?;
+            println!(
+                "Wrote {}; point WALLET_PATH at it and remove the plaintext key",
+                output.display()
+            );
+        }
+        WalletAction::ServeSigner => run_signer_service().await?,
+    }
+    Ok(())
+}
+
+fn run_snapshot_command(out: &Path, recent: usize, sign: bool) -> anyhow::Result<()> {
+    let trades = load_closed_trades()?;
+    Snapshot::build(&trades, recent, Utc::now()).write(out)?;
+    println!("Wrote a snapshot of {} trades to {}", trades.len(), out.display());
+    if sign {
+        let signed = sign_report(out, &import_arc_wallet()?)?;
+        println!("Signed by {}", signed.signer);
+    }
+    Ok(())
+}
+
+fn run_verify_command(dir: &Path) -> anyhow::Result<()> {
+    let signed = verify_report(dir)?;
+    println!("{} was signed by {}", signed.file, signed.signer);
+    Ok(())
+}
+
+fn run_replay_command(
+    signature: &str,
+    rpc: Option<String>,
+    fresh_blockhash: bool,
+) -> anyhow::Result<()> {
+    let submissions = TxArchive::find(signature)?;
+    if submissions.is_empty() {
+        anyhow::bail!("Nothing archived for {}", signature);
+    }
+    let client = RpcClient::new(rpc.unwrap_or_else(|| import_env_var("RPC_ENDPOINT")));
+    for submission in &submissions {
+        println!(
+            "{} {} at {} (slot {})",
+            submission.kind,
+            submission.bundle_id.as_deref().unwrap_or(""),
+            submission.at,
+            submission.slot.map_or("unknown".to_string(), |slot| slot.to_string())
+        );
+        if let Some(error) = &submission.error {
+            println!("  rejected on submit: {}", error);
+        }
+        for (signature, result) in replay(&client, submission, fresh_blockhash)? {
+            println!(
+                "  {}: {}",
+                signature,
+                result.err.map_or("ok".to_string(), |e| e.to_string())
+            );
+            for line in result.logs.unwrap_or_default() {
+                println!("    {}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_monitoring_command(out: &Path, job: &str) -> anyhow::Result<()> {
+    write_monitoring(out, job)?;
+    println!("Wrote the dashboard and alert rules to {}", out.display());
+    Ok(())
+}
+
+fn run_migrate_command(check: bool) -> anyhow::Result<()> {
+    let dir = data_dir();
+    if !check {
+        for description in migrate(&dir)? {
+            println!("Applied: {}", description);
+        }
+    }
+    println!(
+        "{} is at schema version {} (this build writes {})",
+        dir.display(),
+        schema_version(&dir)?,
+        SCHEMA_VERSION
+    );
+    Ok(())
+}
+
+async fn run_positions_command() -> anyhow::Result<()> {
+    let positions = PositionManager::load()?;
+    let mut open = positions.open_positions().await;
+    open.extend(positions.impaired_positions().await);
+    if open.is_empty() {
+        println!("No open positions");
+    }
+    open.sort_by_key(|position| position.opened_at);
+    for position in open {
+        println!(
+            "{} opened {} in {:.4} SOL out {:.4} SOL{}",
+            position.mint,
+            position.opened_at.format("%Y-%m-%d %H:%M"),
+            lamports_to_sol(position.sol_invested),
+            lamports_to_sol(position.sol_returned),
+            position
+                .impaired
+                .map_or(String::new(), |impairment| format!(" ({})", impairment))
+        );
+    }
+    Ok(())
+}
+
+async fn run_pnl_command() -> anyhow::Result<()> {
+    let trades = load_closed_trades()?;
+    let snapshot = Snapshot::build(&trades, 0, Utc::now());
+    let open = PositionManager::load()?.open_positions().await;
+    println!(
+        "{} trades, {} wins, realized {:+.4} SOL",
+        snapshot.trades, snapshot.wins, snapshot.net_pnl_sol
+    );
+    println!(
+        "{} open positions with {:.4} SOL invested",
+        open.len(),
+        lamports_to_sol(open.iter().map(|p| p.sol_invested).sum::<u64>())
+    );
+    Ok(())
+}
+
+fn run_track_command(action: TrackAction) -> anyhow::Result<()> {
+    let path = Config::path();
+    let (wallet, track) = match &action {
+        TrackAction::Add { wallet } => (wallet, true),
+        TrackAction::Remove { wallet } => (wallet, false),
+    };
+    if !edit_targets(&path, wallet, track)? {
+        println!("Nothing to change in {}", path);
+    } else if track {
+        println!("Now copying {} (in {})", wallet, path);
+    } else {
+        println!("No longer copying {} (in {})", wallet, path);
+    }
+    if env::var("TARGET_PUBKEY").is_ok() {
+        println!("TARGET_PUBKEY is set and overrides the file's targets");
+    }
+    Ok(())
+}
+
+async fn run_backtest_command(
+    targets: Vec<String>,
+    limit: usize,
+    archive: Option<PathBuf>,
+    save: Option<PathBuf>,
+    balance: f64,
+) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    config.export_env();
+    let targets = if targets.is_empty() {
+        config.copy_trading.targets.clone()
+    } else {
+        targets
+    };
+    let txs = match &archive {
+        Some(path) => load_archive(path)?,
+        None => {
+            let client = create_nonblocking_rpc_client().await?;
+            let mut txs = Vec::new();
+            for target in &targets {
+                txs.extend(fetch_history(&client, target, limit).await?);
+            }
+            txs
+        }
+    };
+    if let Some(path) = &save {
+        save_archive(path, &txs)?;
+        println!("Saved {} transactions to {}", txs.len(), path.display());
+    }
+    let backtest_config = BacktestConfig {
+        sizing: SizingConfig::from_env()?,
+        starting_lamports: sol_to_lamports(balance),
+        fee_bps: pump_fee_bps(),
+        tx_cost: FeeModel::from_env().tx_cost(),
+    };
+    let report = backtest(&backtest_config, &targets, &txs);
+    println!(
+        "{} target trades ({} off the curve, not simulated) in {} transactions",
+        report.signals,
+        report.unpriced,
+        txs.len()
+    );
+    println!(
+        "Copied {} buys and {} sells, skipped {} buys for lack of balance",
+        report.copied_buys, report.copied_sells, report.skipped_buys
+    );
+    println!(
+        "{} closed, {} wins, realized {:+.4} SOL, max drawdown {:.4} SOL",
+        report.closed,
+        report.wins,
+        report.realized_lamports as f64 / 1e9,
+        lamports_to_sol(report.max_drawdown_lamports)
+    );
+    println!(
+        "{} still open, worth {:+.4} SOL at their last price; {:.4} SOL paid in fees",
+        report.open_positions,
+        report.unrealized_lamports as f64 / 1e9,
+        lamports_to_sol(report.fees_lamports)
+    );
+    println!(
+        "Balance {:.4} -> {:.4} SOL",
+        balance,
+        lamports_to_sol(report.final_balance_lamports)
+    );
+    Ok(())
+}
+
+/// Runs a manual trade with the same config, state and engine code as the running bot
+async fn run_trade_command(command: Command) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    config.export_env();
+    migrate(&data_dir())?;
+    let state = build_state(&config).await;
+    if let Err(e) = refresh_pump_params(&state).await {
+        let _ = log_message(&format!("Using default pump.fun parameters: {}", e)).await;
+    }
+    let jito_client = connect_jito();
+    let action = match command {
+        Command::Buy { mint, sol } => ControlAction::ManualBuy {
+            mint,
+            lamports: sol_to_lamports(sol),
+        },
+        Command::Sell { mint, pct } => ControlAction::ManualSell { mint, pct },
+        _ => unreachable!("not a trade command"),
+    };
+    println!("{}", execute(&state, &action, jito_client).await?);
+    Ok(())
+}
 
-    let rpc_client = create_arc_rpc_client().unwrap();
-    let rpc_nonblocking_client = create_nonblocking_rpc_client().await.unwrap();
+fn connect_jito() -> Arc<JitoRpcClient> {
+    BLOCK_ENGINES.client()
+}
+
+/// Builds the shared engine state from the exported config
+async fn build_state(config: &Config) -> AppState {
+    let rpc_pool = Arc::new(RpcPool::reads_from_env());
+    let (rpc_client, rpc_nonblocking_client) = pooled_clients(&rpc_pool);
     let wallet = import_arc_wallet().unwrap();
+    let wallets = WalletPool::from_env(wallet.clone()).expect("Invalid wallet pool settings");
+    let venues = VenuePolicy::from_env().expect("Invalid venue settings");
+    for venue in venues.one_way() {
+        let _ = log_message(&format!("Buys on {} are allowed but sells are not", venue)).await;
+    }
+    let reorg_guard = Arc::new(ReorgGuard::new(rpc_nonblocking_client.clone()));
 
-    let state = AppState {
+    AppState {
+        rpc_pool,
         rpc_client,
         rpc_nonblocking_client,
         wallet,
-    };
-    pub static BLOCK_ENGINE_URL: LazyLock<String> =
-        LazyLock::new(|| import_env_var("JITO_BLOCK_ENGINE_URL"));
-    let jito_client = Arc::new(JitoRpcClient::new(format!(
-        "{}/api/v1/bundles",
-        *BLOCK_ENGINE_URL
-    )));
-    let unwanted_key = env::var("JUP_PUBKEY").expect("JUP_PUBKEY not set");
-    let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").expect("RPC_WEBSOCKET_ENDPOINT not set");
+        wallets: Arc::new(wallets),
+        reorg_guard,
+        positions: Arc::new(PositionManager::load().expect("Failed to load positions")),
+        router: Arc::new(Router::new(venues)),
+        notifier: Arc::new(Notifier::from_env()),
+        price_feed: Arc::new(PriceFeed::new()),
+        prices: Arc::new(PriceService::from_env().expect("Invalid price providers")),
+        curves: Arc::new(CurveCache::new()),
+        alerts: Arc::new(AlertBook::new()),
+        sizing: SizingConfig::from_env().expect("Invalid copy sizing settings"),
+        creator_sizing: SizingConfig::creator_from_env().expect("Invalid creator sizing settings"),
+        safety: SafetyConfig::from_env().expect("Invalid token safety settings"),
+        market: MarketBounds::from_env().expect("Invalid market cap or liquidity bounds"),
+        maturity: Arc::new(MaturityFilter::from_env().expect("Invalid token age or holder bounds")),
+        watchlist: Arc::new(Watchlist::new()),
+        fees: FeeModel::from_env(),
+        expectancy: Arc::new(ExpectancyGate::new()),
+        holders: Arc::new(HolderTracker::new()),
+        approvals: Arc::new(ApprovalBook::from_env()),
+        settings: Arc::new(LiveConfig::new(config.live())),
+        control: Arc::new(DualControl::from_env()),
+        slo: Arc::new(SloMonitor::from_env().expect("Invalid LATENCY_SLOS")),
+        creators: Arc::new(CreatorWatch::from_env()),
+        target_exits: Arc::new(TargetExits::from_env().expect("Invalid target exit policy")),
+        limits: Arc::new(ExposureLimits::from_env().expect("Invalid position limits")),
+        loss_breaker: Arc::new(LossBreaker::new()),
+        tilt: Arc::new(TiltGuard::from_env().expect("Invalid tilt guard settings")),
+        pause: Arc::new(CopyPause::new()),
+        filters: Arc::new(TokenFilters::load().expect("Failed to load token filters")),
+        impaired_action: ImpairedAction::from_env().expect("Invalid IMPAIRED_POSITION_ACTION"),
+        timing: Arc::new(CopyTiming::from_env().expect("Invalid COPY_TIMING")),
+    }
+}
 
-    let (ws_stream, _) = connect_async(ws_url)
-        .await
-        .expect("Failed to connect to WebSocket server");
-    let (mut write, mut read) = ws_stream.split();
-    // Subscribe to logs
-    let subscription_message = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "transactionSubscribe",
-        "params": [
-
-            {
-                "failed": false,
-                "accountInclude": ["675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P"],
-                "accountExclude": [unwanted_key],
-                // Optionally specify accounts of interest
-            },
-            {
-                "commitment": "processed",
-                "encoding": "jsonParsed",
-                "transactionDetails": "full",
-                "maxSupportedTransactionVersion": 0
+#[tokio::main]
+
+async fn main() {
+    dotenv().ok();
+    if let Some(command) = Cli::parse().command {
+        let result = match command {
+            Command::State { action } => run_state_command(action),
+            Command::Wallet { action } => run_wallet_command(action).await,
+            Command::Snapshot { out, recent, sign } => run_snapshot_command(&out, recent, sign),
+            Command::Verify { dir } => run_verify_command(&dir),
+            Command::Replay {
+                signature,
+                rpc,
+                fresh_blockhash,
+            } => run_replay_command(&signature, rpc, fresh_blockhash),
+            Command::Monitoring { out, job } => run_monitoring_command(&out, &job),
+            Command::Migrate { check } => run_migrate_command(check),
+            Command::Positions => run_positions_command().await,
+            Command::Pnl => run_pnl_command().await,
+            Command::Track { action } => run_track_command(action),
+            Command::Backtest {
+                targets,
+                limit,
+                archive,
+                save,
+                balance,
+            } => run_backtest_command(targets, limit, archive, save, balance).await,
+            trade @ (Command::Buy { .. } | Command::Sell { .. }) => {
+                run_trade_command(trade).await
             }
-        ]
+        };
+        if let Err(e) = result {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{:#}", e);
+        std::process::exit(1);
     });
+    // Real env vars keep overriding the file on every reload
+    let env_overrides = env::vars().collect::<HashMap<_, _>>();
+    config.export_env();
+    // Stored records are upgraded before anything reads them
+    match migrate(&data_dir()) {
+        Ok(applied) => {
+            for description in applied {
+                let _ = log_message(&format!("Storage migration applied: {}", description)).await;
+            }
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let state = build_state(&config).await;
+    tokio::spawn(run_health_checks(state.rpc_pool.clone()));
+    let owners = state.wallets.all().iter().map(|wallet| wallet.pubkey()).collect::<Vec<_>>();
+    match KNOWN_ATAS.seed(&state.rpc_nonblocking_client, &owners).await {
+        Ok(count) => {
+            let _ = log_message(&format!("Known token accounts: {}", count)).await;
+        }
+        Err(e) => {
+            let _ = log_message(&format!("Every swap will create its token accounts: {}", e)).await;
+        }
+    }
+    // Positions are squared with the chain before any monitor or signal acts on them
+    if let Err(e) = recover_positions(&state).await {
+        let _ = log_message(&format!("Startup recovery failed, positions unchecked: {}", e)).await;
+    }
+    tokio::spawn(run_blockhash_prefetch(state.rpc_nonblocking_client.clone()));
+    tokio::spawn(run_leader_tracker(state.rpc_nonblocking_client.clone()));
+    tokio::spawn(run_metrics_server());
+    tokio::spawn(run_config_watcher(state.clone(), env_overrides));
+    if let Err(e) = state.alerts.load_from_env(&state).await {
+        let _ = log_message(&format!("Ignoring PRICE_ALERTS: {}", e)).await;
+    }
+    // Fee math and instructions fall back to the built-in pump.fun parameters if this fails
+    if let Err(e) = refresh_pump_params(&state).await {
+        let _ = log_message(&format!("Using default pump.fun parameters: {}", e)).await;
+    }
+    tokio::spawn(run_pump_params_refresh(state.clone()));
+    if let Some(config) = LookupTableConfig::from_env().expect("Invalid lookup table settings") {
+        match init_lookup_table(&state, &config).await {
+            Ok(address) => {
+                let _ = log_message(&format!("Compiling swaps against lookup table {}", address))
+                    .await;
+            }
+            Err(e) => {
+                let _ = log_message(&format!("Sending legacy transactions: {}", e)).await;
+            }
+        }
+    }
+    tokio::spawn(run_price_feed(state.clone()));
+    tokio::spawn(run_sol_usd_feed(state.clone()));
+    tokio::spawn(run_creator_sync(state.clone()));
+    tokio::spawn(run_alerts(state.clone()));
+    let jito_client = connect_jito();
+    if let Some(schedule) = FlattenSchedule::from_env() {
+        tokio::spawn(run_flatten_schedule(
+            schedule,
+            state.clone(),
+            jito_client.clone(),
+        ));
+    }
+    tokio::spawn(run_hold_timer(
+        HoldTimer::from_env().expect("Invalid MAX_HOLD_TIME"),
+        state.clone(),
+        jito_client.clone(),
+    ));
+    if let Some(rule) = MomentumExit::from_env().expect("Invalid MOMENTUM_EXIT") {
+        tokio::spawn(run_momentum_exit(rule, state.clone(), jito_client.clone()));
+    }
+    tokio::spawn(run_stop_loss(state.clone(), jito_client.clone()));
+    if let Some(config) = PanicConfig::from_env().expect("Invalid panic exit settings") {
+        tokio::spawn(run_panic_exits(config, state.clone()));
+    }
+    tokio::spawn(run_telegram_control(state.clone(), jito_client.clone()));
+    if let Some(config) = ApiConfig::from_env().expect("Invalid control API settings") {
+        tokio::spawn(run_api_server(config, state.clone(), jito_client.clone()));
+    }
+    if let Some(config) = HolderConfig::from_env().expect("Invalid holder tracking settings") {
+        tokio::spawn(run_holder_tracker(config, state.clone(), jito_client.clone()));
+    }
+    if let Some(config) = HedgeConfig::from_env().expect("Invalid hedge settings") {
+        tokio::spawn(run_hedge_monitor(config, state.clone()));
+    }
+    if let Some(config) = LpWatchConfig::from_env().expect("Invalid LP watch settings") {
+        tokio::spawn(run_lp_watch(config, state.clone(), jito_client.clone()));
+    }
+    if let Some(config) = RemnantConfig::from_env().expect("Invalid remnant sweep settings") {
+        tokio::spawn(run_remnant_sweeper(config, state.clone(), jito_client.clone()));
+    }
+    if let Some(config) = RentReclaimConfig::from_env().expect("Invalid rent reclaim settings") {
+        tokio::spawn(run_rent_reclaim(config, state.clone()));
+    }
+    if let Some(config) = IdleConfig::from_env().expect("Invalid idle mode settings") {
+        tokio::spawn(run_idle_monitor(config, state.clone()));
+    }
+    if let Some(config) = BreakerConfig::from_env().expect("Invalid daily loss breaker settings") {
+        tokio::spawn(run_loss_breaker(config, state.clone()));
+    }
+    if let Some(config) = AttributionConfig::from_env().expect("Invalid attribution settings") {
+        tokio::spawn(run_attribution(config));
+    }
+    if let Err(e) = state.watchlist.load_from_env(&state).await {
+        let _ = log_message(&format!("Ignoring WATCHLIST: {}", e)).await;
+    }
+    tokio::spawn(run_watchlist(state.clone(), jito_client.clone()));
+    let sniper = Sniper::from_env(state.wallet.pubkey())
+        .expect("Invalid sniper settings")
+        .map(Arc::new);
+
+    let unwanted_key = env::var("JUP_PUBKEY").expect("JUP_PUBKEY not set");
+    let ws_url = env::var("RPC_WEBSOCKET_ENDPOINT").expect("RPC_WEBSOCKET_ENDPOINT not set");
+    tokio::spawn(run_curve_cache(state.clone(), ws_url.clone()));
+    if let Some(config) = SupplyWatchConfig::from_env().expect("Invalid supply watch settings") {
+        tokio::spawn(run_supply_watch(
+            config,
+            state.clone(),
+            ws_url.clone(),
+            jito_client.clone(),
+        ));
+    }
 
-    write
-        .send(subscription_message.to_string().into())
-        .await
-        .expect("Failed to send subscription message");
+    let mut hub = SignalHub::new().expect("Failed to load signal checkpoints");
+    hub.spawn(Box::new(WsSource::new(ws_url, unwanted_key, state.clone())));
+    if let Some(webhook) = WebhookSource::from_env() {
+        hub.spawn(Box::new(webhook));
+    }
+    #[cfg(feature = "shredstream")]
+    if let Some(shredstream) = ShredstreamSource::from_env(state.clone()) {
+        hub.spawn(Box::new(shredstream));
+    }
 
     let _ = log_message("---------------------   Copy-trading-bot start!!!  ------------------\n")
         .await;
 
-    // Listen for messages
-    while let Some(Ok(msg)) = read.next().await {
-        if let WsMessage::Text(text) = msg {
-            let json: Value = serde_json::from_str(&text).unwrap();
+    let drain_limit = drain_timeout().expect("Invalid SHUTDOWN_DRAIN_SECS");
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    // Listen for signals from every source until asked to stop
+    loop {
+        let event = tokio::select! {
+            event = hub.recv() => match event {
+                Some(event) => event,
+                None => break,
+            },
+            _ = &mut shutdown => break,
+        };
+        IDLE.touch();
+        // Acked once this loop and every copy it queued are done with the event
+        let ack = hub.track(&event);
+        let json = &event.json;
+        let timestamp = Instant::now();
+        let tx = &json["params"]["result"]["transaction"];
 
-            let sig = json["params"]["result"]["signature"]
-                .as_str()
-                .unwrap_or_default();
-            let timestamp = Instant::now();
+        // Watched wallets can trigger watchlist entries on any pump/Raydium trade
+        for entry in state.watchlist.observe_trade(json).await {
+            tokio::spawn(execute_entry(state.clone(), entry, jito_client.clone()));
+        }
 
-            // filter tx raydium part
-            tx_ray();
+        // Creators of held mints dumping their bags force an exit
+        if invokes_program(tx, &PUMP_PROGRAM) {
+            if let Some((mint, pct)) = state.creators.observe(json).await {
+                tokio::spawn(creator_exit(state.clone(), mint, pct, jito_client.clone()));
+            }
+        }
 
-            // filter tx pumpfun part
-            tx_pump();
+        // Launches from anyone, independent of the copy targets
+        if let Some(sniper) = &sniper {
+            if invokes_program(tx, &PUMP_PROGRAM) {
+                let (sniper, json) = (sniper.clone(), json.clone());
+                let (state, jito_client) = (state.clone(), jito_client.clone());
+                tokio::spawn(async move {
+                    sniper
+                        .on_transaction(&json, &state, jito_client, timestamp)
+                        .await;
+                });
+            }
         }
+
+        // Snapshot of the current targets; edits to the config file apply to the next message
+        let settings = state.settings.current();
+        for target in &settings.targets {
+            if invokes_raydium(tx) {
+                // filter tx raydium part, called directly or through a router
+                tx_ray(
+                    json,
+                    target.clone(),
+                    timestamp,
+                    state.clone(),
+                    jito_client.clone(),
+                    ack.clone(),
+                )
+                .await;
+            } else if invokes_program(tx, &PUMP_PROGRAM) || invokes_jupiter(tx) {
+                // filter tx pumpfun part; other routed trades go wherever the router finds the mint
+                tx_pump(
+                    json,
+                    target.clone(),
+                    timestamp,
+                    state.clone(),
+                    jito_client.clone(),
+                    ack.clone(),
+                )
+                .await;
+            }
+        }
+    }
+    shut_down(&state, &hub, drain_limit).await;
+}
+
+/// Stops taking trades, waits for those in flight to confirm or expire, then saves positions
+/// and signal checkpoints. A second SIGINT or SIGTERM stops waiting
+async fn shut_down(state: &AppState, hub: &SignalHub, drain_limit: Duration) {
+    SHUTDOWN.stop();
+    let cancelled = state.positions.cancel_all_intents().await;
+    let _ = log_message(&format!(
+        "Shutting down: waiting up to {}s for {} in-flight trades, {} pending copies cancelled",
+        drain_limit.as_secs(),
+        SHUTDOWN.in_flight(),
+        cancelled
+    ))
+    .await;
+    let outstanding = tokio::select! {
+        outstanding = SHUTDOWN.drain(drain_limit) => outstanding,
+        _ = shutdown_signal() => SHUTDOWN.in_flight(),
+    };
+    state.positions.flush().await;
+    if let Err(e) = hub.flush().await {
+        let _ = log_message(&format!("Failed to save signal checkpoints: {}", e)).await;
     }
+    let mut summary = format!(
+        "🛑 Bot stopped with {} open positions",
+        state.positions.open_positions().await.len()
+    );
+    if outstanding > 0 {
+        summary.push_str(&format!(", {} trades still unconfirmed", outstanding));
+    }
+    let _ = log_message(&summary).await;
+    state.notifier.notify(Event::Info(summary)).await;
 }
 
 pub async fn tx_ray(
-    json: Value,
+    json: &Value,
     target: String,
     timestamp: Instant,
     state: AppState,
     jito_client: Arc<JitoRpcClient>,
+    ack: EventAck,
 ) {
     // parsing tx part
+    let Some(trade) = parse_raydium_trade(json, &target) else {
+        return;
+    };
+    let (program, pool) = (trade.program, trade.pool);
+    let copy = {
+        let (state, target) = (state.clone(), target.clone());
+        move |signal, timestamp| {
+            copy_on_raydium(signal, program, pool, target, timestamp, state, jito_client)
+        }
+    };
+    copy_with_timing(&state, &target, trade.signal, timestamp, copy, ack).await;
+}
 
-    if  {
-        dirs = "buy".to_string();
-        swap_to_events_on_raydium(
-            mint,
-            amount_in * percent / 100,
-            dirs,
-            pool_id,
-            timestamp.clone(),
-            jito_client.clone(),
-            state.clone(),
-        )
-        .await;
-    } else {
-        dirs = "sell".to_string();
-        swap_to_events_on_raydium(
-            mint,
-            amount_in * percent / 100,
-            dirs,
-            pool_id,
-            timestamp.clone(),
-            jito_client.clone(),
-            state.clone(),
-        )
-        .await;
+async fn copy_on_raydium(
+    signal: TradeSignal,
+    program: RaydiumProgram,
+    pool: String,
+    target: String,
+    timestamp: Instant,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    // CLMM and CPMM pools are found by the router from the mint
+    if program != RaydiumProgram::AmmV4 {
+        return copy_routed(signal, target, timestamp, state, jito_client).await;
+    }
+    let amount_in = copy_amount(&signal, &target, &state).await;
+    ACTIVITY.signal(&signal, &target, amount_in);
+    if amount_in == 0 {
+        return;
+    }
+    let (mint, direction) = (signal.mint.clone(), signal.direction.clone());
+    let origin = signal.signature.clone();
+    swap_to_events_on_raydium(
+        signal.mint,
+        amount_in,
+        signal.direction.as_str().to_string(),
+        pool,
+        signal.signature,
+        signal.slot,
+        timestamp.clone(),
+        jito_client.clone(),
+        state.clone(),
+    )
+    .await;
+    // Outcomes of the position are attributed to the target that opened it
+    if matches!(direction, SwapDirection::Buy) {
+        state.positions.set_target(&mint, &target, &origin).await;
     }
 }
 
 pub async fn tx_pump(
-    json: Value,
+    json: &Value,
     target: String,
     timestamp: Instant,
     state: AppState,
     jito_client: Arc<JitoRpcClient>,
+    ack: EventAck,
 ) {
-    // Iterate over logs and check for unwanted_key
+    // Following a wallet's own launches is sized separately from following its buys, but
+    // waits out the same copy delay, debounce and quiet hours
+    if state.creator_sizing.is_some() {
+        if let Some(launch) = parse_launch_signal(json, &target) {
+            if let Some(buy) = &launch.dev_buy {
+                TARGET_FLOWS.record(buy);
+            }
+            let copy = {
+                let (state, target) = (state.clone(), target.clone());
+                move |signal, timestamp| {
+                    follow_launch(signal, target, timestamp, state, jito_client)
+                }
+            };
+            copy_with_timing(&state, &target, launch.as_buy(), timestamp, copy, ack).await;
+            return;
+        }
+    }
 
-    if  {
-        dirs = "buy".to_string();
-        swap_to_events_on_pump(
-            mint,
-            amount_in * percent / 100,
-            dirs,
-            timestamp.clone(),
-            jito_client.clone(),
-            state.clone(),
-        )
-        .await;
-    } else {
-        dirs = "sell".to_string();
+    let Some(signal) = parse_trade_signal(json, &target) else {
+        return;
+    };
+    // Our quote may be built from a curve read before the target's trade landed
+    TARGET_FLOWS.record(&signal);
 
-        swap_to_events_on_pump(
-            mint,
-            amount_in * percent / 100,
-            dirs,
-            timestamp.clone(),
-            jito_client.clone(),
-            state.clone(),
-        )
-        .await;
+    let copy = {
+        let (state, target) = (state.clone(), target.clone());
+        move |signal, timestamp| copy_routed(signal, target, timestamp, state, jito_client)
+    };
+    copy_with_timing(&state, &target, signal, timestamp, copy, ack).await;
+}
+
+/// Queues `copy` on the target's trade now, or once its `COPY_TIMING` delay or debounce window
+/// is over, unless the target sold the mint meanwhile. Copies of one mint run in order, those
+/// of different mints side by side. `ack` is held until the copy is done or dropped
+async fn copy_with_timing<F, Fut>(
+    state: &AppState,
+    target: &str,
+    signal: TradeSignal,
+    timestamp: Instant,
+    copy: F,
+    ack: EventAck,
+) where
+    F: FnOnce(TradeSignal, Instant) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    match state.timing.admit(target, &signal) {
+        Admission::Copy(signal) => {
+            let (mint, job) = (signal.mint.clone(), copy(signal, timestamp));
+            COPY_EXECUTOR.submit(&mint, async move {
+                job.await;
+                drop(ack);
+            });
+        }
+        Admission::Hold { id, wait } => {
+            let (state, target) = (state.clone(), target.to_string());
+            tokio::spawn(async move {
+                sleep(wait).await;
+                match state.timing.release(&target, &signal.mint, id) {
+                    // Latency is measured from the release, not the target's trade
+                    Some(signal) => {
+                        let (mint, job) = (signal.mint.clone(), copy(signal, Instant::now()));
+                        COPY_EXECUTOR.submit(&mint, async move {
+                            job.await;
+                            drop(ack);
+                        });
+                    }
+                    None => {
+                        let _ = log_message(&format!(
+                            "{} sold {} within its copy delay, not copying the buy",
+                            target, signal.mint
+                        ))
+                        .await;
+                    }
+                }
+            });
+        }
+        Admission::Merged => {}
+        Admission::Quiet => ACTIVITY.signal(&signal, target, 0),
     }
 }
 
-pub async fn swap_on_jup(mint: String, dir: String, amount: u64) {
-    // get tx
-    jito_confirm()
+/// Buys into a token the target launched, sized from its dev buy and any buys folded into it
+async fn follow_launch(
+    signal: TradeSignal,
+    target: String,
+    timestamp: Instant,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let Some(sizing) = &state.creator_sizing else {
+        return;
+    };
+    let amount_in = buy_amount(sizing, signal.sol_amount, &state).await;
+    let _ = log_message(&format!(
+        "Target launched {}, following with {} lamports",
+        signal.mint, amount_in
+    ))
+    .await;
+    if amount_in == 0 {
+        return;
+    }
+    swap_to_events_on_pump(
+        signal.mint.clone(),
+        amount_in,
+        SwapDirection::Buy.as_str().to_string(),
+        signal.signature,
+        signal.slot,
+        timestamp,
+        jito_client,
+        state.clone(),
+    )
+    .await;
+    state.positions.set_creator(&signal.mint, &target).await;
 }
+
+/// Copies a target's trade through the router, wherever the mint currently trades
+async fn copy_routed(
+    signal: TradeSignal,
+    target: String,
+    timestamp: Instant,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let amount_in = copy_amount(&signal, &target, &state).await;
+    ACTIVITY.signal(&signal, &target, amount_in);
+    if amount_in == 0 {
+        return;
+    }
+    let (mint, direction) = (signal.mint.clone(), signal.direction.clone());
+    let origin = signal.signature.clone();
+    swap_to_events_on_pump(
+        signal.mint,
+        amount_in,
+        signal.direction.as_str().to_string(),
+        signal.signature,
+        signal.slot,
+        timestamp.clone(),
+        jito_client.clone(),
+        state.clone(),
+    )
+    .await;
+    // Outcomes of the position are attributed to the target that opened it
+    if matches!(direction, SwapDirection::Buy) {
+        state.positions.set_target(&mint, &target, &origin).await;
+    }
+}
+
+async fn copy_amount(signal: &TradeSignal, target: &str, state: &AppState) -> u64 {
+    match signal.direction {
+        SwapDirection::Buy => {
+            state.target_exits.record_buy(target, signal).await;
+            buy_amount(&state.sizing, signal.sol_amount, state).await
+        }
+        // How much of our position follows depends on what kind of exit the target made
+        SwapDirection::Sell => {
+            let impaired = state
+                .positions
+                .get(&signal.mint)
+                .await
+                .is_some_and(|position| position.impaired.is_some());
+            if impaired {
+                return 0;
+            }
+            let (kind, action) = state.target_exits.on_sell(target, signal).await;
+            let balance = position_balance(state, &signal.mint).await;
+            let amount = action.sell_amount(signal, balance);
+            if balance > 0 {
+                let _ = log_message(&format!(
+                    "Target {} sell of {} classified as {}: selling {} of {}",
+                    target,
+                    signal.mint,
+                    kind.as_str(),
+                    amount,
+                    balance
+                ))
+                .await;
+            }
+            amount
+        }
+    }
+}
+
+/// Lamports to spend under `sizing` when the target spent `target_lamports`
+async fn buy_amount(sizing: &SizingConfig, target_lamports: u64, state: &AppState) -> u64 {
+    // Only hit the RPC for the balance when the sizing mode needs it
+    let balance = match sizing.mode {
+        CopySizing::BalancePercent(_) => state
+            .rpc_nonblocking_client
+            .get_balance(&state.wallet.pubkey())
+            .await
+            .unwrap_or(0),
+        _ => 0,
+    };
+    let amount = sizing.buy_amount(target_lamports, balance);
+    // Negative rolling expectancy scales entries down
+    (amount as f64 * state.expectancy.size_multiplier().await) as u64
+}
+
+/// Settles the position once a copied sell has emptied every wallet holding it
+async fn settle_copied_sell(state: &AppState, mint: &str) {
+    if position_balance(state, mint).await == 0 {
+        settle_position(state, mint).await;
+    }
+}
+
+/// Takes a copied trade through operator approval, the entry gates and the exposure limits,
+/// returning the exposure held for it. A sell adds none, so it only waits on its intent
+async fn pre_trade_gates(
+    state: &AppState,
+    mint: &str,
+    direction: &SwapDirection,
+    amount: u64,
+) -> Option<Reservation> {
+    let buy = matches!(direction, SwapDirection::Buy);
+    let intent_id = state
+        .positions
+        .add_intent(mint, direction.as_str(), amount)
+        .await;
+    // Large buys stay pending until the operator approves them
+    if buy && !await_approval(state, intent_id, mint, amount).await {
+        state.positions.take_intent(intent_id).await;
+        return None;
+    }
+    state.positions.take_intent(intent_id).await?;
+    if !buy {
+        return Some(state.limits.unreserved(mint));
+    }
+    if !state.expectancy.allows_entry().await {
+        let _ = log_message(&format!(
+            "Entries paused by expectancy gate, skipping {}",
+            mint
+        ))
+        .await;
+        return None;
+    }
+    // Don't add to a position a rolled-back signal opened
+    if state.reorg_guard.is_flagged(mint).await {
+        let _ = log_message(&format!(
+            "{} was bought on a phantom signal, skipping",
+            mint
+        ))
+        .await;
+        return None;
+    }
+    if !passes_safety(mint, state).await
+        || !passes_filters(mint, state).await
+        || !market_allows(state, "copy", mint).await
+        || !maturity_allows(state, "copy", mint).await
+    {
+        return None;
+    }
+    reserve_buy(state, "copy", mint, amount).await
+}
+
 pub async fn swap_to_events_on_pump(
     mint: String,
     amount_in: u64,
     dirs: String,
+    sig: String,
+    target_slot: u64,
     timestamp: Instant,
     jito_client: Arc<JitoRpcClient>,
     state: AppState,
 ) {
-    println!("2: {:#?}", timestamp.elapsed().clone());
+    // Counted until confirmed or expired, so shutdown can wait for it
+    let Some(_in_flight) = SHUTDOWN.begin_trade() else {
+        return;
+    };
 
-    let slippage = 10000;
-    println!("2.1: {:#?}", timestamp.elapsed());
-    let res = pump_swap(
-        state,
-        amount_in,
-        &dirs,
-        slippage,
-        &mint,
-        jito_client,
-        timestamp.clone(),
-    )
+    let Ok(swap_direction) = SwapDirection::from_str(&dirs) else {
+        return;
+    };
+    // Held until the buy is recorded as a position
+    let Some(_reservation) = pre_trade_gates(&state, &mint, &swap_direction, amount_in).await
+    else {
+        return;
+    };
+    let slippage = state.settings.current().slippage_bps;
+    // One leg per pool wallet taking part; a single wallet trades the whole amount
+    let legs = trade_legs(&state, &mint, &swap_direction, amount_in).await;
+    let submitted = Instant::now();
+    let results = join_all(legs.into_iter().map(|(leg, amount)| {
+        let (mint, direction, jito_client) = (&mint, swap_direction.clone(), jito_client.clone());
+        async move {
+            // The router picks curve, PumpSwap, Raydium or Jupiter depending on graduation state
+            let res = leg
+                .router
+                .swap(leg.clone(), mint, amount, direction, slippage, jito_client, timestamp)
+                .await;
+            (leg, amount, res)
+        }
+    }))
     .await;
+    let latency = TradeLatency::between(timestamp, submitted);
+
+    let mut landed = false;
+    for (leg, amount, res) in results {
+        let Ok(signatures) = res else {
+            continue;
+        };
+        landed = true;
+        match swap_direction {
+            SwapDirection::Buy => {
+                state
+                    .positions
+                    .record_buy(&mint, None, amount, state.fees.tx_cost())
+                    .await;
+                let wallet = leg.wallet.pubkey().to_string();
+                state.positions.add_wallet(&mint, &wallet).await;
+            }
+            SwapDirection::Sell => record_exit(&leg, &mint, &signatures).await,
+        }
+        // After the buy is booked, so its position is there to take the filled tokens
+        tokio::spawn(record_execution(
+            leg.clone(),
+            mint.clone(),
+            swap_direction.clone(),
+            signatures.clone(),
+            Some(target_slot),
+        ));
+        // The signal was taken at processed commitment; roll the copy back if it never confirms
+        let (guard, sig, mint, dirs) =
+            (state.reorg_guard.clone(), sig.clone(), mint.clone(), dirs.clone());
+        let jito_client = jito_client.clone();
+        tokio::spawn(async move {
+            guard
+                .guard_copy(sig, mint, dirs, None, signatures, leg, jito_client)
+                .await;
+        });
+    }
+    if !landed {
+        return;
+    }
+    if matches!(swap_direction, SwapDirection::Sell) {
+        settle_copied_sell(&state, &mint).await;
+    }
+    METRICS.observe_copy(target_slot, timestamp);
+    observe_latency(&state, latency).await;
 }
 
 pub async fn swap_to_events_on_raydium(
@@ -212,23 +1248,91 @@ pub async fn swap_to_events_on_raydium(
     amount_in: u64,
     dirs: String,
     pool_id: String,
+    sig: String,
+    target_slot: u64,
     timestamp: Instant,
     jito_client: Arc<JitoRpcClient>,
     state: AppState,
 ) {
-    println!("2: {:#?}", timestamp.elapsed().clone());
+    // Counted until confirmed or expired, so shutdown can wait for it
+    let Some(_in_flight) = SHUTDOWN.begin_trade() else {
+        return;
+    };
 
-    let slippage = 10000;
-    println!("2.1: {:#?}", timestamp.elapsed());
-    let res = raydium_swap(
-        state,
-        amount_in,
-        &dirs,
-        pool_id,
-        slippage,
-        &mint,
-        jito_client,
-        timestamp.clone(),
-    )
+    let Ok(swap_direction) = SwapDirection::from_str(&dirs) else {
+        return;
+    };
+    // Held until the buy is recorded as a position
+    let Some(_reservation) = pre_trade_gates(&state, &mint, &swap_direction, amount_in).await
+    else {
+        return;
+    };
+    let slippage = state.settings.current().slippage_bps;
+    // One leg per pool wallet taking part; a single wallet trades the whole amount
+    let legs = trade_legs(&state, &mint, &swap_direction, amount_in).await;
+    let submitted = Instant::now();
+    let results = join_all(legs.into_iter().map(|(leg, amount)| {
+        let (mint, dirs, pool_id) = (&mint, &dirs, pool_id.clone());
+        let jito_client = jito_client.clone();
+        async move {
+            let res = raydium_swap(
+                leg.clone(),
+                amount,
+                dirs,
+                pool_id,
+                slippage,
+                mint,
+                jito_client,
+                timestamp,
+            )
+            .await;
+            (leg, amount, res)
+        }
+    }))
     .await;
+    let latency = TradeLatency::between(timestamp, submitted);
+
+    let mut landed = false;
+    for (leg, amount, res) in results {
+        let Ok(signatures) = res else {
+            continue;
+        };
+        landed = true;
+        match swap_direction {
+            SwapDirection::Buy => {
+                state
+                    .positions
+                    .record_buy(&mint, Some(pool_id.clone()), amount, state.fees.tx_cost())
+                    .await;
+                let wallet = leg.wallet.pubkey().to_string();
+                state.positions.add_wallet(&mint, &wallet).await;
+            }
+            SwapDirection::Sell => record_exit(&leg, &mint, &signatures).await,
+        }
+        // After the buy is booked, so its position is there to take the filled tokens
+        tokio::spawn(record_execution(
+            leg.clone(),
+            mint.clone(),
+            swap_direction.clone(),
+            signatures.clone(),
+            Some(target_slot),
+        ));
+        // The signal was taken at processed commitment; roll the copy back if it never confirms
+        let (guard, sig, mint, dirs) =
+            (state.reorg_guard.clone(), sig.clone(), mint.clone(), dirs.clone());
+        let (pool_id, jito_client) = (pool_id.clone(), jito_client.clone());
+        tokio::spawn(async move {
+            guard
+                .guard_copy(sig, mint, dirs, Some(pool_id), signatures, leg, jito_client)
+                .await;
+        });
+    }
+    if !landed {
+        return;
+    }
+    if matches!(swap_direction, SwapDirection::Sell) {
+        settle_copied_sell(&state, &mint).await;
+    }
+    METRICS.observe_copy(target_slot, timestamp);
+    observe_latency(&state, latency).await;
 }
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    env,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Days, NaiveTime, Utc};
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use tokio::time::sleep;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::{
+        position::{load_closed_trades, ClosedTrade},
+        wallets::position_ui_balance,
+    },
+    risk::expectancy::wallet_lamports,
+    services::{notify::Event, price_feed::fetch_price},
+};
+
+// Configuration constants
+const DEFAULT_CHECK_SECS: u64 = 30;
+
+/// How much the bot may lose in a day before new buys stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LossLimit {
+    Lamports(u64),
+    /// Percentage of the equity held when the day started
+    Pct(f64),
+}
+
+impl FromStr for LossLimit {
+    type Err = anyhow::Error;
+
+    /// Parses `sol:<amount>` or `pct:<pct>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            Some(("sol", sol)) => Ok(LossLimit::Lamports(sol_to_lamports(f64::from_str(sol)?))),
+            Some(("pct", pct)) => {
+                let pct = f64::from_str(pct)?;
+                if !(0.0..=100.0).contains(&pct) {
+                    return Err(anyhow!("Loss percentage must be within 0-100: '{}'", s));
+                }
+                Ok(LossLimit::Pct(pct))
+            }
+            _ => Err(anyhow!(
+                "Invalid daily loss limit: '{}'. Use sol:<amount> or pct:<pct>",
+                s
+            )),
+        }
+    }
+}
+
+impl LossLimit {
+    pub fn lamports(&self, start_equity: u64) -> u64 {
+        match self {
+            LossLimit::Lamports(lamports) => *lamports,
+            LossLimit::Pct(pct) => (start_equity as f64 * pct / 100.0) as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    pub limit: LossLimit,
+    /// UTC hour at which the trading day, and the breaker, resets
+    pub reset_hour: u32,
+    pub check_interval: Duration,
+}
+
+impl BreakerConfig {
+    /// Reads `DAILY_LOSS_LIMIT` (unset disables), `DAILY_LOSS_RESET_HOUR` and
+    /// `DAILY_LOSS_CHECK_SECS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(limit) = env::var("DAILY_LOSS_LIMIT") else {
+            return Ok(None);
+        };
+        let reset_hour = match env::var("DAILY_LOSS_RESET_HOUR") {
+            Ok(hour) => u32::from_str(&hour)
+                .ok()
+                .filter(|hour| *hour < 24)
+                .context("DAILY_LOSS_RESET_HOUR must be an hour within 0-23")?,
+            Err(_) => 0,
+        };
+        Ok(Some(Self {
+            limit: LossLimit::from_str(&limit)?,
+            reset_hour,
+            check_interval: Duration::from_secs(
+                env::var("DAILY_LOSS_CHECK_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_CHECK_SECS),
+            ),
+        }))
+    }
+}
+
+/// Start of the trading day that `now` falls in
+pub fn day_start(now: DateTime<Utc>, reset_hour: u32) -> DateTime<Utc> {
+    let reset = NaiveTime::from_hms_opt(reset_hour, 0, 0).unwrap_or_default();
+    let today = now.date_naive().and_time(reset).and_utc();
+    if today <= now {
+        today
+    } else {
+        today - Days::new(1)
+    }
+}
+
+/// An open position marked to market
+#[derive(Debug, Clone)]
+pub struct Mark {
+    pub opened_at: DateTime<Utc>,
+    /// Lamports the tokens still held would fetch
+    pub value: u64,
+    /// Net lamports made so far if sold at `value`
+    pub pnl: i64,
+}
+
+/// One trading day's starting point
+#[derive(Debug, Clone)]
+pub struct TradingDay {
+    pub start: DateTime<Utc>,
+    pub start_equity: u64,
+    /// PnL of the positions carried into the day, which only count from where they started it
+    pub baseline: HashMap<String, i64>,
+}
+
+impl TradingDay {
+    pub fn new(start: DateTime<Utc>, wallet_lamports: u64, open: &HashMap<String, Mark>) -> Self {
+        Self {
+            start,
+            start_equity: wallet_lamports + open.values().map(|mark| mark.value).sum::<u64>(),
+            baseline: open
+                .iter()
+                .map(|(mint, mark)| (mint.clone(), mark.pnl))
+                .collect(),
+        }
+    }
+
+    fn baseline(&self, mint: &str, opened_at: DateTime<Utc>) -> i64 {
+        if opened_at < self.start {
+            self.baseline.get(mint).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    /// Realized plus unrealized lamports made since the day started
+    pub fn pnl(&self, closed: &[ClosedTrade], open: &HashMap<String, Mark>) -> i64 {
+        let realized = closed
+            .iter()
+            .filter(|trade| trade.closed_at >= self.start)
+            .map(|trade| trade.pnl() - self.baseline(&trade.mint, trade.opened_at))
+            .sum::<i64>();
+        let unrealized = open
+            .iter()
+            .map(|(mint, mark)| mark.pnl - self.baseline(mint, mark.opened_at))
+            .sum::<i64>();
+        realized + unrealized
+    }
+}
+
+/// Halts new buys for the rest of the day once the loss limit is hit; exits keep working
+#[derive(Debug, Default)]
+pub struct LossBreaker {
+    tripped: AtomicBool,
+}
+
+impl LossBreaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allows_entry(&self) -> bool {
+        !self.tripped.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the state changed
+    fn set_tripped(&self, tripped: bool) -> bool {
+        self.tripped.swap(tripped, Ordering::Relaxed) != tripped
+    }
+}
+
+/// Marks every open position to market; positions that cannot be priced are left out
+async fn open_marks(state: &AppState) -> HashMap<String, Mark> {
+    let mut marks = HashMap::new();
+    for position in state.positions.open_positions().await {
+        let Ok(balance) = position_ui_balance(state, &position.mint).await else {
+            continue;
+        };
+        let Ok(price) = fetch_price(state, &position.mint).await else {
+            continue;
+        };
+        let value = sol_to_lamports(balance * price);
+        let pnl = value as i64 + position.sol_returned as i64
+            - position.sol_invested as i64
+            - position.fees_paid as i64;
+        marks.insert(
+            position.mint,
+            Mark {
+                opened_at: position.opened_at,
+                value,
+                pnl,
+            },
+        );
+    }
+    marks
+}
+
+async fn announce(state: &AppState, message: String) {
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+/// Tracks the day's PnL and trips or resets the breaker in `state`, forever
+pub async fn run_loss_breaker(config: BreakerConfig, state: AppState) {
+    // A limit set against a balance that failed to read would trip on the first loss
+    let mut day = loop {
+        match wallet_lamports(&state).await {
+            Ok(lamports) => {
+                break TradingDay::new(
+                    day_start(Utc::now(), config.reset_hour),
+                    lamports,
+                    &open_marks(&state).await,
+                )
+            }
+            Err(e) => {
+                let _ = log_message(&format!("Loss breaker waiting to start: {}", e)).await;
+                sleep(config.check_interval).await;
+            }
+        }
+    };
+
+    loop {
+        sleep(config.check_interval).await;
+        let now = Utc::now();
+        let open = open_marks(&state).await;
+        let start = day_start(now, config.reset_hour);
+        if start > day.start {
+            let lamports = match wallet_lamports(&state).await {
+                Ok(lamports) => lamports,
+                Err(e) => {
+                    let _ = log_message(&format!("Loss breaker skipped a check: {}", e)).await;
+                    continue;
+                }
+            };
+            day = TradingDay::new(start, lamports, &open);
+            if state.loss_breaker.set_tripped(false) {
+                announce(
+                    &state,
+                    "New trading day, daily loss breaker reset".to_string(),
+                )
+                .await;
+            }
+            continue;
+        }
+
+        let closed = match load_closed_trades() {
+            Ok(closed) => closed,
+            Err(e) => {
+                let _ = log_message(&format!("Loss breaker skipped a check: {}", e)).await;
+                continue;
+            }
+        };
+        let loss = (-day.pnl(&closed, &open)).max(0) as u64;
+        let limit = config.limit.lamports(day.start_equity);
+        if loss >= limit && state.loss_breaker.set_tripped(true) {
+            announce(
+                &state,
+                format!(
+                    "🛑 Daily loss of {:.4} SOL hit the {:.4} SOL limit, new buys halted until {}",
+                    lamports_to_sol(loss),
+                    lamports_to_sol(limit),
+                    day.start + Days::new(1)
+                ),
+            )
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::EventTime;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        DateTime::<Utc>::from_timestamp(1_700_006_400, 0).unwrap()
+            + chrono::Duration::hours(hour as i64)
+    }
+
+    #[test]
+    fn test_day_start() {
+        // 1_700_006_400 is midnight UTC
+        assert_eq!(day_start(at(10), 0), at(0));
+        assert_eq!(day_start(at(10), 12), at(0) - chrono::Duration::hours(12));
+        assert_eq!(day_start(at(12), 12), at(12));
+        assert_eq!(
+            LossLimit::from_str("sol:1.5").unwrap(),
+            LossLimit::Lamports(1_500_000_000)
+        );
+        assert_eq!(LossLimit::Pct(10.0).lamports(5_000), 500);
+        assert!(LossLimit::from_str("pct:150").is_err());
+    }
+
+    #[test]
+    fn test_day_pnl() {
+        let mark = |opened_at, pnl| Mark {
+            opened_at,
+            value: 0,
+            pnl,
+        };
+        // Carried over at -100
+        let carried = HashMap::from([(
+            "a".to_string(),
+            mark(at(0) - chrono::Duration::hours(1), -100),
+        )]);
+        let day = TradingDay::new(at(0), 1_000, &carried);
+        let trade = |mint: &str, opened_at, closed_at, pnl: i64| ClosedTrade {
+            mint: mint.to_string(),
+            opened_at,
+            closed_at,
+            sol_invested: 1_000,
+            sol_returned: (1_000 + pnl) as u64,
+            fees_paid: 0,
+            target: None,
+            time: EventTime::default(),
+        };
+        let closed = vec![
+            // Yesterday's losses do not count
+            trade(
+                "x",
+                at(0) - chrono::Duration::hours(3),
+                at(0) - chrono::Duration::hours(2),
+                -500,
+            ),
+            // The carried position closed at -300, so -200 of it is today's
+            trade("a", at(0) - chrono::Duration::hours(1), at(2), -300),
+            trade("b", at(1), at(3), 50),
+        ];
+        let open = HashMap::from([("c".to_string(), mark(at(4), -80))]);
+        assert_eq!(day.pnl(&closed, &open), -200 + 50 - 80);
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::VecDeque,
+    env,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use solana_sdk::{native_token::lamports_to_sol, signer::Signer};
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::execution::{find_executions, Execution},
+    risk::tilt::observe_trade,
+    services::{metrics::METRICS, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_WINDOW: usize = 20;
+const DEFAULT_MIN_SAMPLES: usize = 10;
+const DEFAULT_PAUSE_SECS: u64 = 3_600;
+
+/// What to do with new entries while rolling expectancy is negative
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GateAction {
+    /// Scale buy sizes down to this percentage
+    Reduce(f64),
+    /// Stop opening new positions for a while
+    Pause,
+}
+
+impl FromStr for GateAction {
+    type Err = anyhow::Error;
+
+    /// Parses `pause` or `reduce:<pct>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(':') {
+            None if s.trim() == "pause" => Ok(GateAction::Pause),
+            Some(("reduce", pct)) => {
+                let pct = f64::from_str(pct)?;
+                if !(0.0..=100.0).contains(&pct) {
+                    return Err(anyhow!("Reduce percentage must be within 0-100: '{}'", s));
+                }
+                Ok(GateAction::Reduce(pct))
+            }
+            _ => Err(anyhow!(
+                "Invalid expectancy action: '{}'. Use pause or reduce:<pct>",
+                s
+            )),
+        }
+    }
+}
+
+/// Win/loss statistics over the most recent closed trades, in lamports net of fees
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpectancyStats {
+    pub samples: usize,
+    pub win_rate: f64,
+    pub avg_win: f64,
+    pub avg_loss: f64,
+    /// avg win × win rate − avg loss × loss rate
+    pub expectancy: f64,
+}
+
+impl ExpectancyStats {
+    pub fn from_outcomes(outcomes: &VecDeque<i64>) -> Self {
+        if outcomes.is_empty() {
+            return Self::default();
+        }
+        let wins: Vec<f64> = outcomes
+            .iter()
+            .filter(|pnl| **pnl > 0)
+            .map(|pnl| *pnl as f64)
+            .collect();
+        let losses: Vec<f64> = outcomes
+            .iter()
+            .filter(|pnl| **pnl <= 0)
+            .map(|pnl| -*pnl as f64)
+            .collect();
+        let mean = |values: &[f64]| {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / values.len() as f64
+            }
+        };
+
+        let win_rate = wins.len() as f64 / outcomes.len() as f64;
+        let avg_win = mean(&wins);
+        let avg_loss = mean(&losses);
+        Self {
+            samples: outcomes.len(),
+            win_rate,
+            avg_win,
+            avg_loss,
+            expectancy: avg_win * win_rate - avg_loss * (1.0 - win_rate),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} trades, win rate {:.0}%, avg win {:.4} SOL, avg loss {:.4} SOL, expectancy {:+.4} SOL/trade",
+            self.samples,
+            self.win_rate * 100.0,
+            lamports_to_sol(self.avg_win as u64),
+            lamports_to_sol(self.avg_loss as u64),
+            self.expectancy / 1e9
+        )
+    }
+}
+
+/// Throttles or pauses entries while the bot's own rolling expectancy is negative. A pause
+/// lifts after `pause` or when the operator resumes copying, starting a fresh window
+pub struct ExpectancyGate {
+    outcomes: RwLock<VecDeque<i64>>,
+    /// When the gate last closed
+    gated: RwLock<Option<Instant>>,
+    window: usize,
+    min_samples: usize,
+    action: GateAction,
+    pause: Duration,
+}
+
+impl Default for ExpectancyGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExpectancyGate {
+    /// Creates a gate configured from `EXPECTANCY_WINDOW`, `EXPECTANCY_MIN_SAMPLES`,
+    /// `EXPECTANCY_ACTION` and `EXPECTANCY_PAUSE_SECS`
+    pub fn new() -> Self {
+        let usize_var = |key: &str, default: usize| {
+            env::var(key)
+                .ok()
+                .and_then(|v| usize::from_str(&v).ok())
+                .unwrap_or(default)
+        };
+        let window = usize_var("EXPECTANCY_WINDOW", DEFAULT_WINDOW).max(1);
+
+        Self {
+            outcomes: RwLock::new(VecDeque::with_capacity(window)),
+            gated: RwLock::new(None),
+            window,
+            min_samples: usize_var("EXPECTANCY_MIN_SAMPLES", DEFAULT_MIN_SAMPLES).min(window),
+            action: env::var("EXPECTANCY_ACTION")
+                .ok()
+                .and_then(|v| GateAction::from_str(&v).ok())
+                .unwrap_or(GateAction::Pause),
+            pause: Duration::from_secs(
+                env::var("EXPECTANCY_PAUSE_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_PAUSE_SECS),
+            ),
+        }
+    }
+
+    pub async fn stats(&self) -> ExpectancyStats {
+        ExpectancyStats::from_outcomes(&*self.outcomes.read().await)
+    }
+
+    /// Adds a closed trade's net P&L; returns the stats when the gate opens or closes
+    pub async fn record_trade(&self, pnl_lamports: i64) -> Option<(bool, ExpectancyStats)> {
+        let stats = {
+            let mut outcomes = self.outcomes.write().await;
+            outcomes.push_back(pnl_lamports);
+            while outcomes.len() > self.window {
+                outcomes.pop_front();
+            }
+            ExpectancyStats::from_outcomes(&outcomes)
+        };
+
+        let should_gate = stats.samples >= self.min_samples && stats.expectancy < 0.0;
+        let mut gated = self.gated.write().await;
+        if gated.is_some() == should_gate {
+            return None;
+        }
+        *gated = should_gate.then(Instant::now);
+        Some((should_gate, stats))
+    }
+
+    pub async fn is_gated(&self) -> bool {
+        self.gated.read().await.is_some()
+    }
+
+    /// Reopens the gate on a fresh window, so the trades that closed it can't close it again
+    /// at once; returns whether it was closed
+    pub async fn resume(&self) -> bool {
+        if self.gated.write().await.take().is_none() {
+            return false;
+        }
+        self.outcomes.write().await.clear();
+        true
+    }
+
+    /// Lifts a pause that has run its course; returns whether it did
+    pub async fn expire_pause(&self) -> bool {
+        let expired = self
+            .gated
+            .read()
+            .await
+            .is_some_and(|at| self.action == GateAction::Pause && at.elapsed() >= self.pause);
+        expired && self.resume().await
+    }
+
+    /// Whether new positions may be opened at all
+    pub async fn allows_entry(&self) -> bool {
+        !(self.is_gated().await && self.action == GateAction::Pause)
+    }
+
+    /// Factor applied to buy sizes
+    pub async fn size_multiplier(&self) -> f64 {
+        match (self.is_gated().await, self.action) {
+            (true, GateAction::Reduce(pct)) => pct / 100.0,
+            (true, GateAction::Pause) => 0.0,
+            (false, _) => 1.0,
+        }
+    }
+}
+
+/// Closes a fully exited position, feeds its net P&L to the gate and alerts on gate changes
+pub async fn settle_position(state: &AppState, mint: &str) {
+    let Some(position) = state.positions.close(mint).await else {
+        return;
+    };
+    // A later position on the mint starts from a fresh signal
+    state.reorg_guard.clear_flag(mint).await;
+    let pnl =
+        position.sol_returned as i64 - position.sol_invested as i64 - position.fees_paid as i64;
+    METRICS.record_pnl(pnl);
+    let _ = log_message(&format!(
+        "Closed {} for {:+.4} SOL net of fees",
+        mint,
+        pnl as f64 / 1e9
+    ))
+    .await;
+    observe_trade(state, pnl).await;
+    state
+        .notifier
+        .notify(Event::PositionClosed {
+            mint: mint.to_string(),
+            invested_lamports: position.sol_invested,
+            pnl_lamports: pnl,
+        })
+        .await;
+
+    if let Some((gated, stats)) = state.expectancy.record_trade(pnl).await {
+        let message = if gated {
+            format!(
+                "Rolling expectancy turned negative, gating entries: {}",
+                stats.summary()
+            )
+        } else {
+            format!(
+                "Rolling expectancy recovered, entries resumed: {}",
+                stats.summary()
+            )
+        };
+        let _ = log_message(&message).await;
+        state.notifier.notify(Event::Info(message)).await;
+        if gated && state.expectancy.action == GateAction::Pause {
+            lift_pause_later(state.clone());
+        }
+    }
+}
+
+/// Reopens a paused gate once its pause is over, unless something reopened it first
+fn lift_pause_later(state: AppState) {
+    tokio::spawn(async move {
+        sleep(state.expectancy.pause).await;
+        if state.expectancy.expire_pause().await {
+            let message = format!(
+                "Expectancy pause over after {} min, entries resumed on a fresh window",
+                state.expectancy.pause.as_secs() / 60
+            );
+            let _ = log_message(&message).await;
+            state.notifier.notify(Event::Info(message)).await;
+        }
+    });
+}
+
+/// Wallet SOL balance
+pub async fn wallet_lamports(state: &AppState) -> Result<u64> {
+    state
+        .rpc_nonblocking_client
+        .get_balance(&state.wallet.pubkey())
+        .await
+        .context("Failed to read the wallet's SOL balance")
+}
+
+/// Credits a position with the SOL its exit in `signatures` actually filled for and the exit's
+/// network fees. Read from the exit's own transactions (a bundled exit has several), so other
+/// trades landing meanwhile don't count
+pub async fn record_exit(state: &AppState, mint: &str, signatures: &[String]) {
+    let executions = find_executions(state, mint, signatures).await;
+    if executions.is_empty() {
+        let _ = log_message(&format!(
+            "Could not find how the exit of {} filled; its proceeds are not booked",
+            mint
+        ))
+        .await;
+        return;
+    }
+    let fees = executions
+        .iter()
+        .map(|execution| execution.fee)
+        .sum::<u64>();
+    let received = executions
+        .iter()
+        .map(|execution| execution.lamports)
+        .sum::<u64>();
+    let tokens = executions.iter().map(Execution::ui_tokens).sum::<f64>();
+    // Fills book fees apart from proceeds, so the fee comes back out of the net amount
+    state
+        .positions
+        .record_sell(mint, received + fees, fees, tokens)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(window: usize, min_samples: usize, action: GateAction) -> ExpectancyGate {
+        ExpectancyGate {
+            outcomes: RwLock::new(VecDeque::new()),
+            gated: RwLock::new(None),
+            window,
+            min_samples,
+            action,
+            pause: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_expectancy_stats() {
+        let outcomes = VecDeque::from(vec![300, -100, 300, -100]);
+        let stats = ExpectancyStats::from_outcomes(&outcomes);
+        assert_eq!(stats.win_rate, 0.5);
+        assert_eq!(stats.avg_win, 300.0);
+        assert_eq!(stats.avg_loss, 100.0);
+        assert_eq!(stats.expectancy, 100.0);
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(GateAction::from_str("pause").unwrap(), GateAction::Pause);
+        assert_eq!(
+            GateAction::from_str("reduce:25").unwrap(),
+            GateAction::Reduce(25.0)
+        );
+        assert!(GateAction::from_str("reduce:150").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gate_closes_and_reopens() {
+        let gate = gate(4, 3, GateAction::Reduce(50.0));
+        assert!(gate.record_trade(-100).await.is_none());
+        assert!(gate.record_trade(-100).await.is_none());
+        let (gated, stats) = gate.record_trade(50).await.unwrap();
+        assert!(gated);
+        assert!(stats.expectancy < 0.0);
+        assert_eq!(gate.size_multiplier().await, 0.5);
+        assert!(gate.allows_entry().await);
+
+        // Still negative, so no change to report
+        assert!(gate.record_trade(-100).await.is_none());
+        let (gated, _) = gate.record_trade(500).await.unwrap();
+        assert!(!gated);
+        assert_eq!(gate.size_multiplier().await, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_lifts_on_a_fresh_window() {
+        let gate = gate(4, 2, GateAction::Pause);
+        gate.record_trade(-100).await;
+        gate.record_trade(-100).await;
+        assert!(!gate.allows_entry().await);
+
+        assert!(gate.expire_pause().await);
+        assert!(gate.allows_entry().await);
+        // The losses that closed it are forgotten
+        assert!(gate.record_trade(-100).await.is_none());
+        assert!(!gate.resume().await);
+    }
+}
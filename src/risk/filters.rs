@@ -0,0 +1,413 @@
+use std::{collections::BTreeSet, fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::RwLock;
+
+use crate::{
+    common::{
+        cache::BoundedCache,
+        storage::{data_path, load_json, save_json},
+        utils::{log_message, AppState},
+    },
+    core::token::get_token_metadata,
+    engine::creator_exit::resolve_creator,
+};
+
+// Configuration constants
+const FILTERS_FILE: &str = "filters.json";
+const INFO_CACHE_ENTRIES: usize = 5_000;
+const INFO_TTL_SECS: u64 = 24 * 3_600;
+
+/// What a list entry is matched against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterField {
+    Mint,
+    Creator,
+    /// Case-insensitive substring of the token's name or symbol
+    Keyword,
+}
+
+impl FromStr for FilterField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "mint" => Ok(FilterField::Mint),
+            "creator" => Ok(FilterField::Creator),
+            "keyword" => Ok(FilterField::Keyword),
+            _ => Err(anyhow!(
+                "Invalid filter field: '{}'. Use mint, creator or keyword",
+                s
+            )),
+        }
+    }
+}
+
+impl FilterField {
+    /// Normalizes an entry: addresses must parse, keywords are lowercased
+    fn entry(&self, value: &str) -> Result<String> {
+        let value = value.trim();
+        match self {
+            FilterField::Mint | FilterField::Creator => Ok(Pubkey::from_str(value)?.to_string()),
+            FilterField::Keyword if value.is_empty() => Err(anyhow!("Empty keyword")),
+            FilterField::Keyword => Ok(value.to_lowercase()),
+        }
+    }
+}
+
+/// Entries of one blacklist or whitelist
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterList {
+    #[serde(default)]
+    pub mints: BTreeSet<String>,
+    #[serde(default)]
+    pub creators: BTreeSet<String>,
+    #[serde(default)]
+    pub keywords: BTreeSet<String>,
+}
+
+impl FilterList {
+    fn field(&mut self, field: FilterField) -> &mut BTreeSet<String> {
+        match field {
+            FilterField::Mint => &mut self.mints,
+            FilterField::Creator => &mut self.creators,
+            FilterField::Keyword => &mut self.keywords,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mints.is_empty() && self.creators.is_empty() && self.keywords.is_empty()
+    }
+
+    /// The first entry `token` matches
+    fn matches(&self, token: &TokenInfo) -> Option<String> {
+        if self.mints.contains(&token.mint) {
+            return Some(format!("mint {}", token.mint));
+        }
+        if let Some(creator) = token
+            .creator
+            .as_ref()
+            .filter(|c| self.creators.contains(*c))
+        {
+            return Some(format!("creator {}", creator));
+        }
+        let text = [&token.name, &token.symbol]
+            .into_iter()
+            .flatten()
+            .map(|text| text.to_lowercase())
+            .collect::<Vec<_>>();
+        self.keywords
+            .iter()
+            .find(|keyword| text.iter().any(|text| text.contains(keyword.as_str())))
+            .map(|keyword| format!("keyword '{}'", keyword))
+    }
+}
+
+/// Blocked tokens, and the tokens allowed when any whitelist entry is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterLists {
+    #[serde(default)]
+    pub blacklist: FilterList,
+    #[serde(default)]
+    pub whitelist: FilterList,
+}
+
+impl FilterLists {
+    /// Why `token` may not be bought, or `None` if it may
+    pub fn rejects(&self, token: &TokenInfo) -> Option<String> {
+        if let Some(entry) = self.blacklist.matches(token) {
+            return Some(format!("blacklisted {}", entry));
+        }
+        if !self.whitelist.is_empty() && self.whitelist.matches(token).is_none() {
+            return Some("not on the whitelist".to_string());
+        }
+        None
+    }
+
+    fn needs_creator(&self) -> bool {
+        !self.blacklist.creators.is_empty() || !self.whitelist.creators.is_empty()
+    }
+
+    fn needs_metadata(&self) -> bool {
+        !self.blacklist.keywords.is_empty() || !self.whitelist.keywords.is_empty()
+    }
+}
+
+impl fmt::Display for FilterLists {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, list) in [
+            ("Blacklist", &self.blacklist),
+            ("Whitelist", &self.whitelist),
+        ] {
+            writeln!(f, "{}:", name)?;
+            for (field, entries) in [
+                ("mints", &list.mints),
+                ("creators", &list.creators),
+                ("keywords", &list.keywords),
+            ] {
+                let entries = entries.iter().cloned().collect::<Vec<_>>();
+                writeln!(f, "  {}: {}", field, entries.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What is known about a token when deciding whether to buy it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenInfo {
+    pub mint: String,
+    pub creator: Option<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+}
+
+/// Persistent token filters, editable while the bot runs
+pub struct TokenFilters {
+    lists: RwLock<FilterLists>,
+    /// Where the lists are persisted; `None` keeps them in memory only
+    path: Option<PathBuf>,
+    /// Creator and metadata lookups, which cost several RPC calls per mint
+    info: BoundedCache<String, TokenInfo>,
+}
+
+impl TokenFilters {
+    pub fn new(lists: FilterLists) -> Self {
+        Self {
+            lists: RwLock::new(lists),
+            path: None,
+            info: BoundedCache::from_env(
+                "token_info",
+                INFO_CACHE_ENTRIES,
+                Some(Duration::from_secs(INFO_TTL_SECS)),
+            ),
+        }
+    }
+
+    /// Restores the lists from the data directory and persists every change back to it
+    pub fn load() -> Result<Self> {
+        let path = data_path(FILTERS_FILE);
+        let lists = load_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path: Some(path),
+            ..Self::new(lists)
+        })
+    }
+
+    /// Forgets every cached creator and metadata lookup
+    pub async fn clear_cache(&self) {
+        self.info.clear().await;
+    }
+
+    async fn persist(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let snapshot = self.lists.read().await.clone();
+        if let Err(e) = save_json(path, &snapshot).await {
+            let _ = log_message(&format!("Failed to persist token filters: {}", e)).await;
+        }
+    }
+
+    /// Adds an entry to the blacklist or, with `allow`, the whitelist; returns false if it
+    /// was already there
+    pub async fn add(&self, allow: bool, field: FilterField, value: &str) -> Result<bool> {
+        let entry = field.entry(value)?;
+        let added = {
+            let mut lists = self.lists.write().await;
+            let list = if allow {
+                &mut lists.whitelist
+            } else {
+                &mut lists.blacklist
+            };
+            list.field(field).insert(entry)
+        };
+        if added {
+            self.persist().await;
+        }
+        Ok(added)
+    }
+
+    /// Removes an entry; returns false if it was not there
+    pub async fn remove(&self, allow: bool, field: FilterField, value: &str) -> Result<bool> {
+        let entry = field.entry(value)?;
+        let removed = {
+            let mut lists = self.lists.write().await;
+            let list = if allow {
+                &mut lists.whitelist
+            } else {
+                &mut lists.blacklist
+            };
+            list.field(field).remove(&entry)
+        };
+        if removed {
+            self.persist().await;
+        }
+        Ok(removed)
+    }
+
+    pub async fn lists(&self) -> FilterLists {
+        self.lists.read().await.clone()
+    }
+
+    /// Checks a token whose details are already known, e.g. from its creation transaction
+    pub async fn rejects(&self, token: &TokenInfo) -> Option<String> {
+        self.lists.read().await.rejects(token)
+    }
+
+    /// Fills in whichever of the creator and metadata the current lists need
+    async fn token_info(&self, state: &AppState, mint: &str) -> TokenInfo {
+        let (needs_creator, needs_metadata) = {
+            let lists = self.lists.read().await;
+            (lists.needs_creator(), lists.needs_metadata())
+        };
+        let mut info = self.info.get(&mint.to_string()).await.unwrap_or(TokenInfo {
+            mint: mint.to_string(),
+            ..Default::default()
+        });
+        let mut changed = false;
+        if needs_creator && info.creator.is_none() {
+            info.creator = match state.positions.get(mint).await.and_then(|p| p.creator) {
+                Some(creator) => Some(creator),
+                None => resolve_creator(state, mint).await.ok(),
+            };
+            changed |= info.creator.is_some();
+        }
+        if needs_metadata && info.name.is_none() {
+            if let Ok(mint_pubkey) = Pubkey::from_str(mint) {
+                let client = state.rpc_nonblocking_client.clone();
+                if let Ok(metadata) = get_token_metadata(client, &mint_pubkey).await {
+                    info.name = Some(metadata.name);
+                    info.symbol = Some(metadata.symbol);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.info.insert(mint.to_string(), info.clone()).await;
+        }
+        info
+    }
+}
+
+/// Whether the filters let the copy engine buy `mint`, logging why not
+pub async fn passes_filters(mint: &str, state: &AppState) -> bool {
+    let filters = &state.filters;
+    let token = filters.token_info(state, mint).await;
+    match filters.rejects(&token).await {
+        Some(reason) => {
+            let _ = log_message(&format!("Token filters skipped {}: {}", mint, reason)).await;
+            false
+        }
+        None => true,
+    }
+}
+
+/// Runs `/block`, `/unblock`, `/allow`, `/unallow` `<mint|creator|keyword> <value>` and
+/// `/filters`, returning the reply
+pub async fn handle_filter_command(state: &AppState, text: &str) -> Option<String> {
+    let mut words = text.split_whitespace();
+    let command = words.next()?;
+    if command == "/filters" {
+        return Some(state.filters.lists().await.to_string());
+    }
+    let (add, allow) = match command {
+        "/block" => (true, false),
+        "/unblock" => (false, false),
+        "/allow" => (true, true),
+        "/unallow" => (false, true),
+        _ => return None,
+    };
+    let (Some(field), value) = (words.next(), words.collect::<Vec<_>>().join(" ")) else {
+        return Some(format!("Usage: {} <mint|creator|keyword> <value>", command));
+    };
+    let list = if allow { "whitelist" } else { "blacklist" };
+    let field = match FilterField::from_str(field) {
+        Ok(field) => field,
+        Err(e) => return Some(e.to_string()),
+    };
+    let result = if add {
+        state.filters.add(allow, field, &value).await
+    } else {
+        state.filters.remove(allow, field, &value).await
+    };
+    Some(match (result, add) {
+        (Ok(true), true) => format!("Added {} to the {}", value, list),
+        (Ok(false), true) => format!("{} is already on the {}", value, list),
+        (Ok(true), false) => format!("Removed {} from the {}", value, list),
+        (Ok(false), false) => format!("{} is not on the {}", value, list),
+        (Err(e), _) => format!("Invalid entry '{}': {}", value, e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(mint: &str, creator: Option<&str>, name: &str, symbol: &str) -> TokenInfo {
+        TokenInfo {
+            mint: mint.to_string(),
+            creator: creator.map(str::to_string),
+            name: Some(name.to_string()),
+            symbol: Some(symbol.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rejects() {
+        let mut lists = FilterLists::default();
+        lists.blacklist.mints.insert("bad".to_string());
+        lists.blacklist.creators.insert("rugger".to_string());
+        lists.blacklist.keywords.insert("elon".to_string());
+        let ok = token("good", Some("dev"), "Moon Cat", "MCAT");
+        assert_eq!(lists.rejects(&ok), None);
+        assert!(lists.rejects(&token("bad", None, "x", "x")).is_some());
+        assert!(lists
+            .rejects(&token("good", Some("rugger"), "x", "x"))
+            .is_some());
+        assert!(lists
+            .rejects(&token("good", None, "ELON Doge", "ED"))
+            .is_some());
+        // Unknown details cannot match an entry
+        assert_eq!(
+            lists.rejects(&TokenInfo {
+                mint: "good".to_string(),
+                ..Default::default()
+            }),
+            None
+        );
+
+        // Once anything is whitelisted, only matching tokens pass
+        lists.whitelist.keywords.insert("cat".to_string());
+        assert_eq!(lists.rejects(&ok), None);
+        assert!(lists
+            .rejects(&token("other", Some("dev"), "Dog", "DOG"))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove() {
+        let filters = TokenFilters::new(FilterLists::default());
+        let mint = Pubkey::new_unique().to_string();
+        assert!(filters.add(false, FilterField::Mint, &mint).await.unwrap());
+        assert!(!filters.add(false, FilterField::Mint, &mint).await.unwrap());
+        assert!(filters
+            .add(false, FilterField::Mint, "not-a-key")
+            .await
+            .is_err());
+        assert!(filters
+            .add(true, FilterField::Keyword, " Pepe ")
+            .await
+            .unwrap());
+        let lists = filters.lists().await;
+        assert!(lists.blacklist.mints.contains(&mint));
+        assert!(lists.whitelist.keywords.contains("pepe"));
+        assert!(filters
+            .remove(false, FilterField::Mint, &mint)
+            .await
+            .unwrap());
+        assert!(filters.lists().await.blacklist.is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::wallets::position_ui_balance,
+    services::{notify::Event, price_feed::fetch_price},
+};
+
+// Configuration constants
+const DEFAULT_CHECK_SECS: u64 = 60;
+const DEFAULT_BETA_WINDOW: usize = 60;
+const DEFAULT_HEDGE_RATIO_PCT: f64 = 100.0;
+const DEFAULT_REBALANCE_PCT: f64 = 20.0;
+// Below this many return samples the beta falls back to 1
+const MIN_BETA_SAMPLES: usize = 10;
+const HEDGE_INSTRUMENT: &str = "SOL-PERP";
+
+/// When and how much to hedge; the bot only recommends, it never trades the perp itself
+#[derive(Debug, Clone)]
+pub struct HedgeConfig {
+    /// Memecoin exposure, in USD, above which a hedge is recommended
+    pub max_exposure_usd: f64,
+    /// Share of the beta-adjusted exposure to hedge, in percent
+    pub hedge_ratio_pct: f64,
+    /// Change in recommended size, in percent, that triggers a new recommendation
+    pub rebalance_pct: f64,
+    pub beta_window: usize,
+    pub check_interval: Duration,
+    /// Receives every recommendation as JSON, e.g. an external perp trader
+    pub webhook_url: Option<String>,
+}
+
+impl HedgeConfig {
+    /// Reads `HEDGE_MAX_EXPOSURE_USD` (unset disables), `HEDGE_RATIO_PCT`,
+    /// `HEDGE_REBALANCE_PCT`, `HEDGE_BETA_WINDOW`, `HEDGE_CHECK_SECS` and `HEDGE_WEBHOOK_URL`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(max_exposure) = env::var("HEDGE_MAX_EXPOSURE_USD") else {
+            return Ok(None);
+        };
+        let pct = |key: &str, default: f64| {
+            env::var(key)
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .unwrap_or(default)
+        };
+        Ok(Some(Self {
+            max_exposure_usd: f64::from_str(&max_exposure)
+                .context("Invalid HEDGE_MAX_EXPOSURE_USD")?,
+            hedge_ratio_pct: pct("HEDGE_RATIO_PCT", DEFAULT_HEDGE_RATIO_PCT),
+            rebalance_pct: pct("HEDGE_REBALANCE_PCT", DEFAULT_REBALANCE_PCT),
+            beta_window: env::var("HEDGE_BETA_WINDOW")
+                .ok()
+                .and_then(|v| usize::from_str(&v).ok())
+                .unwrap_or(DEFAULT_BETA_WINDOW)
+                .max(2),
+            check_interval: Duration::from_secs(
+                env::var("HEDGE_CHECK_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_CHECK_SECS)
+                    .max(1),
+            ),
+            webhook_url: env::var("HEDGE_WEBHOOK_URL").ok(),
+        }))
+    }
+}
+
+/// Regression beta of portfolio USD returns against SOL USD returns over a rolling window
+#[derive(Debug, Default)]
+pub struct BetaEstimator {
+    window: usize,
+    /// (portfolio return, SOL return) per check
+    returns: VecDeque<(f64, f64)>,
+}
+
+impl BetaEstimator {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            returns: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, portfolio_return: f64, sol_return: f64) {
+        self.returns.push_back((portfolio_return, sol_return));
+        while self.returns.len() > self.window {
+            self.returns.pop_front();
+        }
+    }
+
+    /// Falls back to 1 (tokens quoted in SOL move with SOL) until there is enough history
+    pub fn beta(&self) -> f64 {
+        let n = self.returns.len();
+        if n < MIN_BETA_SAMPLES {
+            return 1.0;
+        }
+        let mean_p = self.returns.iter().map(|(p, _)| p).sum::<f64>() / n as f64;
+        let mean_s = self.returns.iter().map(|(_, s)| s).sum::<f64>() / n as f64;
+        let (cov, var) = self.returns.iter().fold((0.0, 0.0), |(cov, var), (p, s)| {
+            (
+                cov + (p - mean_p) * (s - mean_s),
+                var + (s - mean_s).powi(2),
+            )
+        });
+        if var == 0.0 {
+            return 1.0;
+        }
+        cov / var
+    }
+}
+
+/// A recommended SOL perp short, sent to operators and the optional webhook
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HedgeRecommendation {
+    pub instrument: &'static str,
+    pub side: &'static str,
+    /// Size of the short in SOL; 0 means close any hedge
+    pub size_sol: f64,
+    pub notional_usd: f64,
+    pub exposure_usd: f64,
+    pub beta: f64,
+    pub sol_price_usd: f64,
+    pub at: DateTime<Utc>,
+}
+
+impl HedgeRecommendation {
+    pub fn describe(&self) -> String {
+        if self.size_sol == 0.0 {
+            return format!(
+                "🛡️ Hedge: close {} short, memecoin exposure ${:.0} is back under the limit",
+                self.instrument, self.exposure_usd
+            );
+        }
+        format!(
+            "🛡️ Hedge: short {:.2} SOL of {} (${:.0}) against ${:.0} memecoin exposure, beta {:.2}",
+            self.size_sol, self.instrument, self.notional_usd, self.exposure_usd, self.beta
+        )
+    }
+}
+
+/// Short size in SOL hedging `exposure_usd` at `beta`, or 0 while exposure is within the limit
+pub fn hedge_size_sol(config: &HedgeConfig, exposure_usd: f64, beta: f64, sol_price: f64) -> f64 {
+    if exposure_usd <= config.max_exposure_usd || sol_price <= 0.0 {
+        return 0.0;
+    }
+    (exposure_usd * beta.max(0.0) * config.hedge_ratio_pct / 100.0) / sol_price
+}
+
+/// True when the new size differs enough from the last one sent to be worth a message
+pub fn needs_rebalance(config: &HedgeConfig, last_sol: f64, size_sol: f64) -> bool {
+    if last_sol == 0.0 || size_sol == 0.0 {
+        return last_sol != size_sol;
+    }
+    ((size_sol - last_sol) / last_sol).abs() * 100.0 >= config.rebalance_pct
+}
+
+/// Tokens held and SOL price of each open position, across every pool wallet
+async fn position_values(state: &AppState) -> HashMap<String, (f64, f64)> {
+    let mut values = HashMap::new();
+    for position in state.positions.open_positions().await {
+        let Ok(balance) = position_ui_balance(state, &position.mint).await else {
+            continue;
+        };
+        let Ok(price) = fetch_price(state, &position.mint).await else {
+            continue;
+        };
+        values.insert(position.mint, (balance, price));
+    }
+    values
+}
+
+/// Return of today's holdings from the previous prices to the current ones
+fn holdings_return(
+    current: &HashMap<String, (f64, f64)>,
+    previous: &HashMap<String, (f64, f64)>,
+) -> Option<f64> {
+    let (before, after) = current
+        .iter()
+        .filter_map(|(mint, (amount, price))| {
+            let (_, previous_price) = previous.get(mint)?;
+            Some((amount * previous_price, amount * price))
+        })
+        .fold((0.0, 0.0), |(b, a), (pb, pa)| (b + pb, a + pa));
+    (before > 0.0).then(|| after / before - 1.0)
+}
+
+/// Publishes a recommendation to the operators and the webhook
+async fn publish(
+    state: &AppState,
+    http: &reqwest::Client,
+    config: &HedgeConfig,
+    rec: &HedgeRecommendation,
+) {
+    let message = rec.describe();
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+    if let Some(url) = &config.webhook_url {
+        let sent = http
+            .post(url)
+            .json(rec)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            let _ = log_message(&format!("Hedge webhook failed: {}", e)).await;
+        }
+    }
+}
+
+/// Watches memecoin exposure and recommends SOL perp hedges, forever
+pub async fn run_hedge_monitor(config: HedgeConfig, state: AppState) {
+    let http = reqwest::Client::new();
+    let mut beta = BetaEstimator::new(config.beta_window);
+    let mut previous: Option<(HashMap<String, (f64, f64)>, f64)> = None;
+    let mut last_size_sol = 0.0;
+
+    loop {
+        sleep(config.check_interval).await;
+        let sol_price = match state.prices.sol_price_usd().await {
+            Ok(price) => price,
+            Err(e) => {
+                let _ = log_message(&format!("Hedge monitor skipped a check: {}", e)).await;
+                continue;
+            }
+        };
+        let values = position_values(&state).await;
+        if let Some((previous_values, previous_sol)) = &previous {
+            // Portfolio USD return compounds the SOL-quoted return with SOL's own move
+            if let Some(sol_quoted) = holdings_return(&values, previous_values) {
+                let sol_return = sol_price / previous_sol - 1.0;
+                beta.push((1.0 + sol_quoted) * (1.0 + sol_return) - 1.0, sol_return);
+            }
+        }
+
+        let exposure_usd = values
+            .values()
+            .map(|(amount, price)| amount * price)
+            .sum::<f64>()
+            * sol_price;
+        let beta_now = beta.beta();
+        let size_sol = hedge_size_sol(&config, exposure_usd, beta_now, sol_price);
+        if needs_rebalance(&config, last_size_sol, size_sol) {
+            let rec = HedgeRecommendation {
+                instrument: HEDGE_INSTRUMENT,
+                side: "short",
+                size_sol,
+                notional_usd: size_sol * sol_price,
+                exposure_usd,
+                beta: beta_now,
+                sol_price_usd: sol_price,
+                at: Utc::now(),
+            };
+            publish(&state, &http, &config, &rec).await;
+            last_size_sol = size_sol;
+        }
+        previous = Some((values, sol_price));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HedgeConfig {
+        HedgeConfig {
+            max_exposure_usd: 1_000.0,
+            hedge_ratio_pct: 50.0,
+            rebalance_pct: 20.0,
+            beta_window: 20,
+            check_interval: Duration::from_secs(60),
+            webhook_url: None,
+        }
+    }
+
+    #[test]
+    fn test_beta_regression() {
+        let mut beta = BetaEstimator::new(20);
+        assert_eq!(beta.beta(), 1.0);
+        for i in 0..20 {
+            let sol = if i % 2 == 0 { 0.01 } else { -0.02 };
+            beta.push(3.0 * sol, sol);
+        }
+        assert!((beta.beta() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hedge_sizing_and_rebalance() {
+        let config = config();
+        assert_eq!(hedge_size_sol(&config, 900.0, 2.0, 100.0), 0.0);
+        // $2000 exposure at beta 2, half hedged, is a $2000 short: 20 SOL
+        assert_eq!(hedge_size_sol(&config, 2_000.0, 2.0, 100.0), 20.0);
+
+        assert!(needs_rebalance(&config, 0.0, 20.0));
+        assert!(!needs_rebalance(&config, 20.0, 22.0));
+        assert!(needs_rebalance(&config, 20.0, 25.0));
+        assert!(needs_rebalance(&config, 20.0, 0.0));
+        assert!(!needs_rebalance(&config, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use tokio::{sync::RwLock, time::Instant};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::exit::market_exit,
+    services::{idle::idle_sleep, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_SAMPLE_SECS: u64 = 60;
+const DEFAULT_MINTS_PER_CYCLE: usize = 5;
+const MAX_SAMPLES_PER_MINT: usize = 120;
+const HOLDER_EXIT_SLIPPAGE_BPS: u64 = 2_500;
+// The amount field of an SPL token account
+const TOKEN_AMOUNT_OFFSET: usize = 64;
+
+/// Holder count change of a mint over a window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HolderGrowth {
+    pub holders: u64,
+    pub change: i64,
+    pub pct: f64,
+    pub elapsed: Duration,
+}
+
+/// Exit a position once its holder count grows less than `min_growth_pct` over `window`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HolderStallExit {
+    pub window: Duration,
+    pub min_growth_pct: f64,
+}
+
+impl FromStr for HolderStallExit {
+    type Err = anyhow::Error;
+
+    /// Parses `<window secs>:<min growth pct>`, e.g. `300:5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (window, min_growth_pct) = s.trim().split_once(':').ok_or_else(|| {
+            anyhow!(
+                "Invalid holder stall exit: '{}'. Use <secs>:<min growth pct>",
+                s
+            )
+        })?;
+        let window = u64::from_str(window)?;
+        if window == 0 {
+            return Err(anyhow!("Holder stall window must be positive: '{}'", s));
+        }
+        Ok(Self {
+            window: Duration::from_secs(window),
+            min_growth_pct: f64::from_str(min_growth_pct)?,
+        })
+    }
+}
+
+/// How often holder counts are sampled and how many mints each round may query
+#[derive(Debug, Clone)]
+pub struct HolderConfig {
+    pub sample_interval: Duration,
+    /// RPC budget: at most this many `getProgramAccounts` calls per round
+    pub mints_per_cycle: usize,
+    pub stall_exit: Option<HolderStallExit>,
+}
+
+impl HolderConfig {
+    /// Reads `HOLDER_SAMPLE_SECS`, `HOLDER_MINTS_PER_CYCLE` and `HOLDER_STALL_EXIT`;
+    /// tracking stays off unless the interval or the exit rule is set
+    pub fn from_env() -> Result<Option<Self>> {
+        if env::var("HOLDER_SAMPLE_SECS").is_err() && env::var("HOLDER_STALL_EXIT").is_err() {
+            return Ok(None);
+        }
+        let sample_secs = env::var("HOLDER_SAMPLE_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_SAMPLE_SECS)
+            .max(1);
+        let mints_per_cycle = env::var("HOLDER_MINTS_PER_CYCLE")
+            .ok()
+            .and_then(|v| usize::from_str(&v).ok())
+            .unwrap_or(DEFAULT_MINTS_PER_CYCLE);
+        let stall_exit = match env::var("HOLDER_STALL_EXIT") {
+            Ok(value) => Some(HolderStallExit::from_str(&value)?),
+            Err(_) => None,
+        };
+        Ok(Some(Self {
+            sample_interval: Duration::from_secs(sample_secs),
+            mints_per_cycle,
+            stall_exit,
+        }))
+    }
+}
+
+/// Growth between the newest sample and the newest one at least `window` older
+fn growth_over(samples: &VecDeque<(Instant, u64)>, window: Duration) -> Option<HolderGrowth> {
+    let &(latest_at, latest) = samples.back()?;
+    let &(base_at, base) = samples
+        .iter()
+        .rev()
+        .find(|(at, _)| latest_at.duration_since(*at) >= window)?;
+    let change = latest as i64 - base as i64;
+    Some(HolderGrowth {
+        holders: latest,
+        change,
+        pct: if base == 0 {
+            0.0
+        } else {
+            change as f64 / base as f64 * 100.0
+        },
+        elapsed: latest_at.duration_since(base_at),
+    })
+}
+
+/// Holder count history for open positions
+#[derive(Default)]
+pub struct HolderTracker {
+    samples: RwLock<HashMap<String, VecDeque<(Instant, u64)>>>,
+}
+
+impl HolderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn record(&self, mint: &str, holders: u64, at: Instant) {
+        let mut samples = self.samples.write().await;
+        let history = samples.entry(mint.to_string()).or_default();
+        history.push_back((at, holders));
+        if history.len() > MAX_SAMPLES_PER_MINT {
+            history.pop_front();
+        }
+    }
+
+    pub async fn forget(&self, mint: &str) {
+        self.samples.write().await.remove(mint);
+    }
+
+    /// Latest holder count of a mint, if it has been sampled
+    pub async fn holders(&self, mint: &str) -> Option<u64> {
+        self.samples
+            .read()
+            .await
+            .get(mint)?
+            .back()
+            .map(|(_, holders)| *holders)
+    }
+
+    /// Holder growth over at least `window`, once there is enough history
+    pub async fn growth(&self, mint: &str, window: Duration) -> Option<HolderGrowth> {
+        growth_over(self.samples.read().await.get(mint)?, window)
+    }
+
+    /// Picks up to `limit` mints, least recently sampled first
+    async fn due(&self, mints: &[String], limit: usize) -> Vec<String> {
+        let samples = self.samples.read().await;
+        let mut due = mints
+            .iter()
+            .map(|mint| {
+                let last = samples
+                    .get(mint)
+                    .and_then(|history| history.back())
+                    .map(|(at, _)| *at);
+                (last, mint.clone())
+            })
+            .collect::<Vec<_>>();
+        // Never-sampled mints (None) sort first
+        due.sort();
+        due.into_iter().take(limit).map(|(_, mint)| mint).collect()
+    }
+}
+
+/// Counts token accounts of `mint` holding a nonzero balance, fetching only their amounts
+pub async fn fetch_holder_count(state: &AppState, mint: &str) -> Result<u64> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(spl_token::state::Account::LEN as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &mint_pubkey.to_bytes())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: TOKEN_AMOUNT_OFFSET,
+                length: 8,
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = state
+        .rpc_nonblocking_client
+        .get_program_accounts_with_config(&spl_token::id(), config)
+        .await?;
+
+    Ok(accounts
+        .iter()
+        .filter(|(_, account)| account.data.iter().any(|byte| *byte != 0))
+        .count() as u64)
+}
+
+/// Samples holder counts of open positions forever and exits those whose growth stalls
+pub async fn run_holder_tracker(
+    config: HolderConfig,
+    state: AppState,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    loop {
+        idle_sleep(config.sample_interval).await;
+
+        let positions = state.positions.open_positions().await;
+        let open = positions
+            .iter()
+            .map(|position| position.mint.clone())
+            .collect::<Vec<_>>();
+        for mint in state.holders.due(&open, config.mints_per_cycle).await {
+            match fetch_holder_count(&state, &mint).await {
+                Ok(holders) => state.holders.record(&mint, holders, Instant::now()).await,
+                Err(e) => {
+                    let _ = log_message(&format!("Holder count for {} failed: {}", mint, e)).await;
+                }
+            }
+        }
+
+        let open_set = open.iter().cloned().collect::<HashSet<_>>();
+        let tracked = state
+            .holders
+            .samples
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for mint in tracked.iter().filter(|mint| !open_set.contains(*mint)) {
+            state.holders.forget(mint).await;
+        }
+
+        let Some(rule) = &config.stall_exit else {
+            continue;
+        };
+        for position in positions {
+            let Some(growth) = state.holders.growth(&position.mint, rule.window).await else {
+                continue;
+            };
+            if growth.pct >= rule.min_growth_pct {
+                continue;
+            }
+            match market_exit(
+                &state,
+                &position,
+                HOLDER_EXIT_SLIPPAGE_BPS,
+                jito_client.clone(),
+            )
+            .await
+            {
+                Ok(()) => {
+                    state.holders.forget(&position.mint).await;
+                    let message = format!(
+                        "👥 Holder growth stalled on {} ({:+.1}% to {} holders over {}s), sold",
+                        position.mint,
+                        growth.pct,
+                        growth.holders,
+                        growth.elapsed.as_secs()
+                    );
+                    let _ = log_message(&message).await;
+                    state.notifier.notify(Event::Info(message)).await;
+                }
+                Err(e) => {
+                    let _ = log_message(&format!(
+                        "Holder stall exit for {} failed, retrying next round: {}",
+                        position.mint, e
+                    ))
+                    .await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_growth_needs_full_window() {
+        let start = Instant::now();
+        let mut samples = VecDeque::new();
+        samples.push_back((start, 100));
+        samples.push_back((start + Duration::from_secs(60), 110));
+        assert!(growth_over(&samples, Duration::from_secs(120)).is_none());
+
+        samples.push_back((start + Duration::from_secs(180), 99));
+        let growth = growth_over(&samples, Duration::from_secs(120)).unwrap();
+        assert_eq!(growth.holders, 99);
+        assert_eq!(growth.change, -11);
+        assert!((growth.pct + 10.0).abs() < 1e-9);
+        assert_eq!(growth.elapsed, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_parse_stall_exit() {
+        assert_eq!(
+            HolderStallExit::from_str("300:5").unwrap(),
+            HolderStallExit {
+                window: Duration::from_secs(300),
+                min_growth_pct: 5.0,
+            }
+        );
+        assert!(HolderStallExit::from_str("0:5").is_err());
+        assert!(HolderStallExit::from_str("300").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::{env, fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{non_transferable::NonTransferable, BaseStateWithExtensions, StateWithExtensions},
+    state::{Account, AccountState, Mint},
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::errors::{tx_error, TxError},
+    risk::expectancy::settle_position,
+    services::notify::Event,
+};
+
+/// Why a held token can no longer be sold
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Impairment {
+    /// The freeze authority froze our token account
+    Frozen,
+    /// The mint turned out to be Token-2022 non-transferable
+    NonTransferable,
+    /// Exits kept failing for no reason the token itself explains
+    ExitsFailing,
+}
+
+impl fmt::Display for Impairment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Impairment::Frozen => write!(f, "token account frozen"),
+            Impairment::NonTransferable => write!(f, "mint is non-transferable"),
+            Impairment::ExitsFailing => write!(f, "exits keep failing"),
+        }
+    }
+}
+
+impl Impairment {
+    /// Recognises the token program errors a failed sell was decoded into
+    pub fn from_error(error: &anyhow::Error) -> Option<Self> {
+        match tx_error(error)? {
+            TxError::Frozen => Some(Impairment::Frozen),
+            TxError::NonTransferable => Some(Impairment::NonTransferable),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a position once it cannot be sold
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ImpairedAction {
+    /// Keep it marked impaired, out of equity and exits, until it is dealt with by hand
+    #[default]
+    Hold,
+    /// Close it as a total loss
+    WriteOff,
+    /// Only alert; exits keep retrying
+    Retry,
+}
+
+impl FromStr for ImpairedAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "hold" => Ok(ImpairedAction::Hold),
+            "write_off" => Ok(ImpairedAction::WriteOff),
+            "retry" => Ok(ImpairedAction::Retry),
+            _ => Err(anyhow!(
+                "Invalid impaired position action: '{}'. Use hold, write_off or retry",
+                s
+            )),
+        }
+    }
+}
+
+impl ImpairedAction {
+    /// Reads `IMPAIRED_POSITION_ACTION`, holding by default
+    pub fn from_env() -> Result<Self> {
+        env::var("IMPAIRED_POSITION_ACTION")
+            .map(|v| Self::from_str(&v))
+            .unwrap_or(Ok(Self::default()))
+    }
+}
+
+/// Checks the mint and the trading wallet's account of it for anything that blocks a sell
+pub async fn detect(state: &AppState, mint: &str) -> Result<Option<Impairment>> {
+    let mint = Pubkey::from_str(mint)?;
+    let client = &state.rpc_nonblocking_client;
+    let mint_account = client.get_account(&mint).await?;
+    if mint_account.owner == spl_token_2022::ID {
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data)?;
+        if mint_state.get_extension::<NonTransferable>().is_ok() {
+            return Ok(Some(Impairment::NonTransferable));
+        }
+    }
+    let token_account = get_associated_token_address_with_program_id(
+        &state.wallet.pubkey(),
+        &mint,
+        &mint_account.owner,
+    );
+    let account = client.get_account(&token_account).await?;
+    let account = StateWithExtensions::<Account>::unpack(&account.data)?;
+    if account.base.state == AccountState::Frozen {
+        return Ok(Some(Impairment::Frozen));
+    }
+    Ok(None)
+}
+
+/// Works out whether a failed sell from `state`'s wallet failed because the token is stuck
+pub async fn diagnose_failed_sell(
+    state: &AppState,
+    mint: &str,
+    error: &anyhow::Error,
+) -> Option<Impairment> {
+    if let Some(impairment) = Impairment::from_error(error) {
+        return Some(impairment);
+    }
+    match detect(state, mint).await {
+        Ok(impairment) => impairment,
+        Err(e) => {
+            let _ = log_message(&format!("Could not check {} for impairment: {}", mint, e)).await;
+            None
+        }
+    }
+}
+
+/// Alerts about an unsellable position and applies the configured action to it
+pub async fn impair(state: &AppState, mint: &str, impairment: Impairment) {
+    let outcome = match state.impaired_action {
+        ImpairedAction::Hold => {
+            state.positions.set_impaired(mint, impairment).await;
+            "marked impaired, excluded from equity and no longer exited automatically"
+        }
+        ImpairedAction::WriteOff => {
+            state.positions.set_impaired(mint, impairment).await;
+            settle_position(state, mint).await;
+            "written off as a total loss"
+        }
+        ImpairedAction::Retry => "exits keep retrying",
+    };
+    let message = format!("🧊 Cannot sell {}: {}, {}", mint, impairment, outcome);
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let sell = |error| anyhow::Error::new(error).context("Failed to sell");
+        assert_eq!(
+            Impairment::from_error(&sell(TxError::Frozen)),
+            Some(Impairment::Frozen)
+        );
+        assert_eq!(
+            Impairment::from_error(&sell(TxError::NonTransferable)),
+            Some(Impairment::NonTransferable)
+        );
+        assert_eq!(Impairment::from_error(&sell(TxError::CurveComplete)), None);
+        // Text that merely mentions a frozen account isn't decoded
+        assert_eq!(
+            Impairment::from_error(&anyhow::anyhow!("Program log: Error: Account is frozen")),
+            None
+        );
+        assert_eq!(
+            ImpairedAction::from_str("write_off").unwrap(),
+            ImpairedAction::WriteOff
+        );
+        assert!(ImpairedAction::from_str("ignore").is_err());
+    }
+}
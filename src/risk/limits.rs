@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    env, fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::position::{Position, PositionManager},
+    services::{notify::Event, price_feed::fetch_price, sol_usd::SOL_USD},
+};
+
+/// Caps on what a new buy may add to the book; unset limits do not apply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LimitsConfig {
+    pub max_trade_lamports: Option<u64>,
+    pub max_open_positions: Option<usize>,
+    /// Lamports at stake across every position, buys in flight included
+    pub max_total_lamports: Option<u64>,
+    /// Lamports at stake in any single mint
+    pub max_token_lamports: Option<u64>,
+    /// USD caps, converted at the current SOL price; the tighter of a SOL and a USD cap applies
+    pub max_trade_usd: Option<f64>,
+    pub max_total_usd: Option<f64>,
+    pub max_token_usd: Option<f64>,
+}
+
+impl LimitsConfig {
+    /// Reads `MAX_SOL_PER_TRADE`, `MAX_OPEN_POSITIONS`, `MAX_TOTAL_EXPOSURE_SOL` and
+    /// `MAX_TOKEN_EXPOSURE_SOL`, and the USD caps `MAX_USD_PER_TRADE`, `MAX_TOTAL_EXPOSURE_USD`
+    /// and `MAX_TOKEN_EXPOSURE_USD`
+    pub fn from_env() -> Result<Self> {
+        let usd = |key: &str| -> Result<Option<f64>> {
+            env::var(key)
+                .ok()
+                .map(|v| f64::from_str(&v).with_context(|| format!("Invalid {}", key)))
+                .transpose()
+        };
+        let sol = |key: &str| -> Result<Option<u64>> {
+            env::var(key)
+                .ok()
+                .map(|v| {
+                    f64::from_str(&v)
+                        .map(sol_to_lamports)
+                        .with_context(|| format!("Invalid {}", key))
+                })
+                .transpose()
+        };
+        Ok(Self {
+            max_trade_lamports: sol("MAX_SOL_PER_TRADE")?,
+            max_open_positions: env::var("MAX_OPEN_POSITIONS")
+                .ok()
+                .map(|v| usize::from_str(&v).context("Invalid MAX_OPEN_POSITIONS"))
+                .transpose()?,
+            max_total_lamports: sol("MAX_TOTAL_EXPOSURE_SOL")?,
+            max_token_lamports: sol("MAX_TOKEN_EXPOSURE_SOL")?,
+            max_trade_usd: usd("MAX_USD_PER_TRADE")?,
+            max_total_usd: usd("MAX_TOTAL_EXPOSURE_USD")?,
+            max_token_usd: usd("MAX_TOKEN_EXPOSURE_USD")?,
+        })
+    }
+
+    fn has_usd_caps(&self) -> bool {
+        self.max_trade_usd.is_some() || self.max_total_usd.is_some() || self.max_token_usd.is_some()
+    }
+
+    /// The limits with the USD caps folded into the lamport ones at `sol_usd` USD per SOL
+    pub fn priced(&self, sol_usd: f64) -> Self {
+        let cap = |lamports: Option<u64>, usd: Option<f64>| {
+            let from_usd = usd.map(|usd| (usd / sol_usd * 1e9) as u64);
+            match (lamports, from_usd) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        };
+        Self {
+            max_trade_lamports: cap(self.max_trade_lamports, self.max_trade_usd),
+            max_total_lamports: cap(self.max_total_lamports, self.max_total_usd),
+            max_token_lamports: cap(self.max_token_lamports, self.max_token_usd),
+            max_trade_usd: None,
+            max_total_usd: None,
+            max_token_usd: None,
+            ..self.clone()
+        }
+    }
+
+    /// Checks a buy of `amount` lamports into `mint` against `book`, the lamports at stake per
+    /// mint
+    pub fn check(
+        &self,
+        mint: &str,
+        amount: u64,
+        book: &HashMap<String, u64>,
+    ) -> Result<(), LimitBreach> {
+        if let Some(max) = self.max_trade_lamports.filter(|max| amount > *max) {
+            return Err(LimitBreach::TradeSize { amount, max });
+        }
+        let held = book.get(mint).copied();
+        if let Some(max) = self.max_open_positions {
+            if held.is_none() && book.len() >= max {
+                return Err(LimitBreach::OpenPositions { max });
+            }
+        }
+        let total = book.values().sum::<u64>();
+        if let Some(max) = self
+            .max_total_lamports
+            .filter(|max| total.saturating_add(amount) > *max)
+        {
+            return Err(LimitBreach::TotalExposure {
+                exposure: total,
+                amount,
+                max,
+            });
+        }
+        let held = held.unwrap_or(0);
+        if let Some(max) = self
+            .max_token_lamports
+            .filter(|max| held.saturating_add(amount) > *max)
+        {
+            return Err(LimitBreach::TokenExposure {
+                exposure: held,
+                amount,
+                max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Which limit a buy would have broken
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitBreach {
+    TradeSize {
+        amount: u64,
+        max: u64,
+    },
+    OpenPositions {
+        max: usize,
+    },
+    TotalExposure {
+        exposure: u64,
+        amount: u64,
+        max: u64,
+    },
+    TokenExposure {
+        exposure: u64,
+        amount: u64,
+        max: u64,
+    },
+    /// USD caps are set but there is no current SOL price to apply them with
+    NoSolPrice,
+}
+
+impl fmt::Display for LimitBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitBreach::TradeSize { amount, max } => write!(
+                f,
+                "{:.4} SOL is over the {:.4} SOL per-trade limit",
+                lamports_to_sol(*amount),
+                lamports_to_sol(*max)
+            ),
+            LimitBreach::OpenPositions { max } => {
+                write!(f, "already at the limit of {} open positions", max)
+            }
+            LimitBreach::TotalExposure {
+                exposure,
+                amount,
+                max,
+            } => write!(
+                f,
+                "{:.4} SOL on top of {:.4} SOL at stake is over the {:.4} SOL total limit",
+                lamports_to_sol(*amount),
+                lamports_to_sol(*exposure),
+                lamports_to_sol(*max)
+            ),
+            LimitBreach::TokenExposure {
+                exposure,
+                amount,
+                max,
+            } => write!(
+                f,
+                "{:.4} SOL on top of {:.4} SOL in this token is over the {:.4} SOL per-token limit",
+                lamports_to_sol(*amount),
+                lamports_to_sol(*exposure),
+                lamports_to_sol(*max)
+            ),
+            LimitBreach::NoSolPrice => {
+                write!(f, "no current SOL/USD price to apply the USD limits with")
+            }
+        }
+    }
+}
+
+/// Lamports still at stake in a position
+pub fn exposure(position: &Position) -> u64 {
+    position.sol_invested.saturating_sub(position.sol_returned)
+}
+
+/// Enforces [`LimitsConfig`] over open positions plus buys that are in flight
+#[derive(Debug, Default)]
+pub struct ExposureLimits {
+    config: LimitsConfig,
+    in_flight: Mutex<HashMap<String, u64>>,
+}
+
+impl ExposureLimits {
+    pub fn new(config: LimitsConfig) -> Self {
+        Self {
+            config,
+            in_flight: Mutex::default(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(LimitsConfig::from_env()?))
+    }
+
+    /// Holds `amount` lamports of exposure in `mint` for a buy about to be sent, so concurrent
+    /// buys cannot slip past the limits together
+    pub async fn reserve(
+        self: &Arc<Self>,
+        positions: &PositionManager,
+        mint: &str,
+        amount: u64,
+    ) -> Result<Reservation, LimitBreach> {
+        let mut book: HashMap<String, u64> = positions
+            .open_positions()
+            .await
+            .iter()
+            .map(|position| (position.mint.clone(), exposure(position)))
+            .collect();
+        let mut in_flight = self.in_flight.lock().unwrap();
+        for (pending, lamports) in in_flight.iter() {
+            *book.entry(pending.clone()).or_default() += lamports;
+        }
+        if self.config.has_usd_caps() {
+            let sol_usd = SOL_USD.price().ok_or(LimitBreach::NoSolPrice)?;
+            self.config.priced(sol_usd).check(mint, amount, &book)?;
+        } else {
+            self.config.check(mint, amount, &book)?;
+        }
+        *in_flight.entry(mint.to_string()).or_default() += amount;
+        Ok(Reservation {
+            limits: self.clone(),
+            mint: mint.to_string(),
+            amount,
+        })
+    }
+
+    /// Holds no exposure, for a trade that doesn't add any
+    pub fn unreserved(self: &Arc<Self>, mint: &str) -> Reservation {
+        Reservation {
+            limits: self.clone(),
+            mint: mint.to_string(),
+            amount: 0,
+        }
+    }
+
+    fn release(&self, mint: &str, amount: u64) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(lamports) = in_flight.get_mut(mint) {
+            *lamports = lamports.saturating_sub(amount);
+            if *lamports == 0 {
+                in_flight.remove(mint);
+            }
+        }
+    }
+}
+
+/// Exposure held for one buy; released when dropped, by which time a landed buy is a position
+#[derive(Debug)]
+pub struct Reservation {
+    limits: Arc<ExposureLimits>,
+    mint: String,
+    amount: u64,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.limits.release(&self.mint, self.amount);
+    }
+}
+
+/// Reserves exposure for a buy by `source`, or logs and reports the limit it would break.
+/// Nothing is reserved while copying is paused, the daily loss breaker is tripped, the tilt
+/// guard is cooling off or the mint's on-chain price is far off the market's; each reports
+/// itself once
+pub async fn reserve_buy(
+    state: &AppState,
+    source: &str,
+    mint: &str,
+    amount: u64,
+) -> Option<Reservation> {
+    if state.pause.is_paused() {
+        let _ = log_message(&format!("Copying paused, skipping {} buy of {}", source, mint)).await;
+        return None;
+    }
+    if !state.loss_breaker.allows_entry() {
+        let _ = log_message(&format!(
+            "Daily loss breaker tripped, skipping {} buy of {}",
+            source, mint
+        ))
+        .await;
+        return None;
+    }
+    if !state.tilt.allows_entry() {
+        let _ = log_message(&format!(
+            "Tilt guard cooling off, skipping {} buy of {}",
+            source, mint
+        ))
+        .await;
+        return None;
+    }
+    if state.prices.checks_quotes() {
+        if let Ok(price) = fetch_price(state, mint).await {
+            if let Err(e) = state.prices.check_quote(mint, price).await {
+                let _ = log_message(&format!(
+                    "Price sanity check blocked {} buy of {}: {}",
+                    source, mint, e
+                ))
+                .await;
+                return None;
+            }
+        }
+    }
+    match state.limits.reserve(&state.positions, mint, amount).await {
+        Ok(reservation) => Some(reservation),
+        Err(breach) => {
+            let reason = breach.to_string();
+            let _ = log_message(&format!(
+                "Position limit blocked {} buy of {}: {}",
+                source, mint, reason
+            ))
+            .await;
+            state
+                .notifier
+                .notify(Event::LimitBreached {
+                    source: source.to_string(),
+                    mint: mint.to_string(),
+                    reason,
+                })
+                .await;
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_check() {
+        let config = LimitsConfig {
+            max_trade_lamports: Some(SOL),
+            max_open_positions: Some(2),
+            max_total_lamports: Some(3 * SOL),
+            max_token_lamports: Some(2 * SOL),
+            ..Default::default()
+        };
+        let book = HashMap::from([("a".to_string(), SOL), ("b".to_string(), SOL / 2)]);
+        assert_eq!(config.check("a", SOL / 2, &book), Ok(()));
+        assert_eq!(
+            config.check("a", 2 * SOL, &book),
+            Err(LimitBreach::TradeSize {
+                amount: 2 * SOL,
+                max: SOL
+            })
+        );
+        assert_eq!(
+            config.check("c", SOL / 2, &book),
+            Err(LimitBreach::OpenPositions { max: 2 })
+        );
+        assert!(matches!(
+            config.check("b", SOL, &HashMap::from([("b".to_string(), 3 * SOL / 2)])),
+            Err(LimitBreach::TokenExposure { .. })
+        ));
+        let full = HashMap::from([("a".to_string(), 2 * SOL), ("b".to_string(), SOL / 2)]);
+        assert!(matches!(
+            config.check("b", SOL, &full),
+            Err(LimitBreach::TotalExposure { .. })
+        ));
+        assert_eq!(LimitsConfig::default().check("c", 100 * SOL, &full), Ok(()));
+
+        // $300 per trade at $200 a SOL is looser than the 1 SOL cap, $100 is tighter
+        let usd = LimitsConfig {
+            max_trade_usd: Some(300.0),
+            ..config
+        };
+        assert_eq!(usd.priced(200.0).max_trade_lamports, Some(SOL));
+        assert_eq!(
+            LimitsConfig {
+                max_trade_usd: Some(100.0),
+                ..Default::default()
+            }
+            .priced(200.0)
+            .max_trade_lamports,
+            Some(SOL / 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reservations() {
+        let limits = Arc::new(ExposureLimits::new(LimitsConfig {
+            max_open_positions: Some(1),
+            ..Default::default()
+        }));
+        let positions = PositionManager::new();
+        let first = limits.reserve(&positions, "a", SOL).await.unwrap();
+        // The first buy has not landed yet but still holds the only slot
+        assert!(limits.reserve(&positions, "b", SOL).await.is_err());
+        assert!(limits.reserve(&positions, "a", SOL).await.is_ok());
+        drop(first);
+        assert!(limits.reserve(&positions, "b", SOL).await.is_ok());
+    }
+}
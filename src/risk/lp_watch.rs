@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::{raydium::get_pool_state, raydium_cpmm::RaydiumCpmm},
+    engine::{exit::market_exit, router::Venue},
+    services::{idle::idle_sleep, notify::Event},
+};
+
+// Configuration constants
+const DEFAULT_WATCH_SECS: u64 = 5;
+const LP_PULL_EXIT_SLIPPAGE_BPS: u64 = 3_000;
+
+/// What to do once liquidity is pulled from a pool we hold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LpPullAction {
+    Exit,
+    Alert,
+}
+
+impl FromStr for LpPullAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "exit" => Ok(LpPullAction::Exit),
+            "alert" => Ok(LpPullAction::Alert),
+            _ => Err(anyhow!(
+                "Invalid LP pull action: '{}'. Use exit or alert",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LpWatchConfig {
+    /// Share of the pool's peak LP supply that has to be withdrawn to trigger
+    pub pull_pct: f64,
+    pub action: LpPullAction,
+    pub interval: Duration,
+}
+
+impl LpWatchConfig {
+    /// Reads `LP_PULL_EXIT_PCT` (unset disables), `LP_PULL_ACTION` and `LP_WATCH_SECS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(pct) = env::var("LP_PULL_EXIT_PCT") else {
+            return Ok(None);
+        };
+        let pull_pct = f64::from_str(&pct)
+            .ok()
+            .filter(|pct| (0.0..=100.0).contains(pct))
+            .context("LP_PULL_EXIT_PCT must be a percentage within 0-100")?;
+        let action = match env::var("LP_PULL_ACTION") {
+            Ok(action) => LpPullAction::from_str(&action)?,
+            Err(_) => LpPullAction::Exit,
+        };
+        Ok(Some(Self {
+            pull_pct,
+            action,
+            interval: Duration::from_secs(
+                env::var("LP_WATCH_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_WATCH_SECS)
+                    .max(1),
+            ),
+        }))
+    }
+}
+
+/// Highest LP supply seen in each watched pool; withdrawals are measured from the peak
+#[derive(Debug, Default)]
+pub struct LpPeaks {
+    peaks: HashMap<Pubkey, u64>,
+}
+
+impl LpPeaks {
+    /// Records `lp_supply` for `pool` and returns the share of its peak withdrawn since
+    pub fn observe(&mut self, pool: Pubkey, lp_supply: u64) -> f64 {
+        let peak = self.peaks.entry(pool).or_insert(lp_supply);
+        *peak = (*peak).max(lp_supply);
+        if *peak == 0 {
+            return 0.0;
+        }
+        (*peak - lp_supply) as f64 / *peak as f64 * 100.0
+    }
+
+    pub fn retain(&mut self, pools: &HashSet<Pubkey>) {
+        self.peaks.retain(|pool, _| pools.contains(pool));
+    }
+}
+
+/// Outstanding LP of the pool `venue` trades in; `None` for venues without an LP token
+async fn lp_supply(state: &AppState, venue: &Venue) -> Result<Option<(Pubkey, u64)>> {
+    match venue {
+        Venue::Raydium { pool } => {
+            let (_, amm_info) =
+                get_pool_state(state.rpc_client.clone(), Some(&pool.to_string()), None).await?;
+            Ok(Some((*pool, amm_info.lp_amount)))
+        }
+        Venue::RaydiumCpmm { pool } => {
+            let cpmm = RaydiumCpmm::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            Ok(Some((*pool, cpmm.get_pool(pool).await?.lp_supply)))
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn on_pull(
+    state: &AppState,
+    config: &LpWatchConfig,
+    mint: &str,
+    pool: &Pubkey,
+    pct: f64,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let message = match config.action {
+        LpPullAction::Alert => format!("🚨 {:.0}% of LP pulled from {} ({})", pct, pool, mint),
+        LpPullAction::Exit => {
+            let Some(position) = state.positions.get(mint).await else {
+                return;
+            };
+            match market_exit(state, &position, LP_PULL_EXIT_SLIPPAGE_BPS, jito_client).await {
+                Ok(()) => format!("🚨 {:.0}% of LP pulled from {}, exited {}", pct, pool, mint),
+                Err(e) => format!(
+                    "🚨 {:.0}% of LP pulled from {} but the exit from {} failed: {}",
+                    pct, pool, mint, e
+                ),
+            }
+        }
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+/// Polls the LP supply of every pool backing an open position and reacts once a pool loses
+/// `pull_pct` of its peak liquidity, forever. Each pool fires once
+pub async fn run_lp_watch(config: LpWatchConfig, state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let mut peaks = LpPeaks::default();
+    let mut fired = HashSet::new();
+    loop {
+        idle_sleep(config.interval).await;
+        let mut watched = HashSet::new();
+        for position in state.positions.open_positions().await {
+            let Ok(venue) = state.router.route(&state, &position.mint).await else {
+                continue;
+            };
+            let (pool, supply) = match lp_supply(&state, &venue).await {
+                Ok(Some(lp)) => lp,
+                Ok(None) => continue,
+                Err(e) => {
+                    let _ = log_message(&format!(
+                        "Could not read LP supply for {}: {}",
+                        position.mint, e
+                    ))
+                    .await;
+                    continue;
+                }
+            };
+            watched.insert(pool);
+            let pct = peaks.observe(pool, supply);
+            if pct >= config.pull_pct && fired.insert(pool) {
+                on_pull(
+                    &state,
+                    &config,
+                    &position.mint,
+                    &pool,
+                    pct,
+                    jito_client.clone(),
+                )
+                .await;
+            }
+        }
+        peaks.retain(&watched);
+        fired.retain(|pool| watched.contains(pool));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pulled_from_peak() {
+        let pool = Pubkey::new_unique();
+        let mut peaks = LpPeaks::default();
+        assert_eq!(peaks.observe(pool, 1_000), 0.0);
+        // Added liquidity raises the peak
+        assert_eq!(peaks.observe(pool, 2_000), 0.0);
+        assert_eq!(peaks.observe(pool, 1_500), 25.0);
+        assert_eq!(peaks.observe(pool, 200), 90.0);
+        peaks.retain(&HashSet::new());
+        assert_eq!(peaks.observe(pool, 200), 0.0);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{env, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::{
+        pump::{get_bonding_curve_account, PUMP_PROGRAM_ID},
+        raydium::get_pool_state,
+        raydium_clmm::RaydiumClmm,
+        raydium_cpmm::RaydiumCpmm,
+    },
+    engine::router::Venue,
+    services::{notify::Event, price_feed::fetch_price},
+};
+
+/// Market cap and SOL liquidity a copied buy must fall within; unset bounds don't apply
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MarketBounds {
+    pub min_market_cap_sol: Option<f64>,
+    pub max_market_cap_sol: Option<f64>,
+    pub min_liquidity_sol: Option<f64>,
+    pub max_liquidity_sol: Option<f64>,
+}
+
+/// A token's size as measured from its curve or pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketMetrics {
+    pub market_cap_sol: f64,
+    /// SOL in the curve or the pool's SOL vault; `None` where it can't be read, e.g. a token
+    /// only Jupiter routes
+    pub liquidity_sol: Option<f64>,
+}
+
+impl MarketBounds {
+    /// Reads `MIN_MARKET_CAP_SOL`, `MAX_MARKET_CAP_SOL`, `MIN_LIQUIDITY_SOL` and
+    /// `MAX_LIQUIDITY_SOL`
+    pub fn from_env() -> Result<Self> {
+        let sol = |key: &str| -> Result<Option<f64>> {
+            env::var(key)
+                .ok()
+                .map(|v| f64::from_str(&v).with_context(|| format!("Invalid {}", key)))
+                .transpose()
+        };
+        Ok(Self {
+            min_market_cap_sol: sol("MIN_MARKET_CAP_SOL")?,
+            max_market_cap_sol: sol("MAX_MARKET_CAP_SOL")?,
+            min_liquidity_sol: sol("MIN_LIQUIDITY_SOL")?,
+            max_liquidity_sol: sol("MAX_LIQUIDITY_SOL")?,
+        })
+    }
+
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+
+    fn needs_liquidity(&self) -> bool {
+        self.min_liquidity_sol.is_some() || self.max_liquidity_sol.is_some()
+    }
+
+    /// Why a token measured at `metrics` may not be bought, or `None` if it may. Unreadable
+    /// liquidity fails any liquidity bound
+    pub fn rejects(&self, metrics: &MarketMetrics) -> Option<String> {
+        let outside = |name: &str, value: f64, min: Option<f64>, max: Option<f64>| {
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                Some(format!(
+                    "{} {:.2} SOL is outside {}-{} SOL",
+                    name,
+                    value,
+                    min.map_or("0".to_string(), |min| min.to_string()),
+                    max.map_or("∞".to_string(), |max| max.to_string())
+                ))
+            } else {
+                None
+            }
+        };
+        if let Some(reason) = outside(
+            "market cap",
+            metrics.market_cap_sol,
+            self.min_market_cap_sol,
+            self.max_market_cap_sol,
+        ) {
+            return Some(reason);
+        }
+        match metrics.liquidity_sol {
+            Some(liquidity) => outside(
+                "liquidity",
+                liquidity,
+                self.min_liquidity_sol,
+                self.max_liquidity_sol,
+            ),
+            None if self.needs_liquidity() => Some("liquidity can't be read".to_string()),
+            None => None,
+        }
+    }
+}
+
+impl MarketMetrics {
+    pub fn describe(&self) -> String {
+        format!(
+            "market cap {:.2} SOL, liquidity {}",
+            self.market_cap_sol,
+            self.liquidity_sol
+                .map_or("unknown".to_string(), |sol| format!("{:.2} SOL", sol))
+        )
+    }
+}
+
+/// The pool's token account holding its WSOL side
+async fn sol_vault(state: &AppState, venue: &Venue) -> Result<Option<Pubkey>> {
+    let wsol = spl_token::native_mint::ID;
+    let pick = |mint_0: Pubkey, vault_0: Pubkey, vault_1: Pubkey| {
+        if mint_0 == wsol {
+            vault_0
+        } else {
+            vault_1
+        }
+    };
+    Ok(match venue {
+        Venue::BondingCurve | Venue::Jupiter => None,
+        Venue::PumpSwap { pool } => Some(get_associated_token_address(pool, &wsol)),
+        Venue::Raydium { pool } => {
+            let (_, amm_info) =
+                get_pool_state(state.rpc_client.clone(), Some(&pool.to_string()), None).await?;
+            Some(pick(
+                amm_info.coin_vault_mint,
+                amm_info.coin_vault,
+                amm_info.pc_vault,
+            ))
+        }
+        Venue::RaydiumClmm { pool } => {
+            let clmm = RaydiumClmm::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            let pool = clmm.get_pool(pool).await?;
+            Some(pick(pool.mint_0, pool.vault_0, pool.vault_1))
+        }
+        Venue::RaydiumCpmm { pool } => {
+            let cpmm = RaydiumCpmm::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            let pool = cpmm.get_pool(pool).await?;
+            Some(pick(pool.mint_0, pool.vault_0, pool.vault_1))
+        }
+    })
+}
+
+/// Market cap and liquidity of `mint` from its curve's reserves, or from its pool's price,
+/// the mint's supply and the pool's SOL vault once it has migrated
+pub async fn market_metrics(state: &AppState, mint: &str) -> Result<MarketMetrics> {
+    let venue = state.router.route(state, mint).await?;
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    if venue == Venue::BondingCurve {
+        let (_, _, curve) =
+            get_bonding_curve_account(state.rpc_client.clone(), &mint_pubkey, &PUMP_PROGRAM_ID)
+                .await?;
+        if curve.virtual_token_reserves == 0 {
+            return Err(anyhow!("Curve of {} has no tokens", mint));
+        }
+        let market_cap = curve.token_total_supply as u128 * curve.virtual_sol_reserves as u128
+            / curve.virtual_token_reserves as u128;
+        return Ok(MarketMetrics {
+            market_cap_sol: lamports_to_sol(market_cap as u64),
+            liquidity_sol: Some(lamports_to_sol(curve.real_sol_reserves)),
+        });
+    }
+
+    let client = &state.rpc_nonblocking_client;
+    let price = fetch_price(state, mint).await?;
+    let supply = client.get_token_supply(&mint_pubkey).await?;
+    let supply = supply
+        .ui_amount
+        .unwrap_or_else(|| f64::from_str(&supply.ui_amount_string).unwrap_or(0.0));
+    let liquidity_sol = match sol_vault(state, &venue).await? {
+        Some(vault) => {
+            let balance = client.get_token_account_balance(&vault).await?;
+            Some(lamports_to_sol(u64::from_str(&balance.amount)?))
+        }
+        None => None,
+    };
+    Ok(MarketMetrics {
+        market_cap_sol: price * supply,
+        liquidity_sol,
+    })
+}
+
+/// Measures `mint` against `state.market` before a buy by `source`, logging the result and
+/// reporting a rejection. Tokens that can't be measured are skipped
+pub async fn market_allows(state: &AppState, source: &str, mint: &str) -> bool {
+    let bounds = state.market;
+    if !bounds.is_set() {
+        return true;
+    }
+    let reason = match market_metrics(state, mint).await {
+        Ok(metrics) => match bounds.rejects(&metrics) {
+            None => {
+                let _ = log_message(&format!(
+                    "Market filter passed {} buy of {}: {}",
+                    source,
+                    mint,
+                    metrics.describe()
+                ))
+                .await;
+                return true;
+            }
+            Some(reason) => format!("{} ({})", reason, metrics.describe()),
+        },
+        Err(e) => format!("couldn't measure the market: {}", e),
+    };
+    let _ = log_message(&format!(
+        "Market filter blocked {} buy of {}: {}",
+        source, mint, reason
+    ))
+    .await;
+    state
+        .notifier
+        .notify(Event::LimitBreached {
+            source: source.to_string(),
+            mint: mint.to_string(),
+            reason,
+        })
+        .await;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let bounds = MarketBounds {
+            min_liquidity_sol: Some(10.0),
+            max_liquidity_sol: Some(500.0),
+            ..Default::default()
+        };
+        let metrics = |liquidity_sol| MarketMetrics {
+            market_cap_sol: 100.0,
+            liquidity_sol,
+        };
+        assert!(bounds.is_set() && !MarketBounds::default().is_set());
+        assert_eq!(bounds.rejects(&metrics(Some(42.0))), None);
+        assert_eq!(
+            bounds.rejects(&metrics(Some(5.0))).unwrap(),
+            "liquidity 5.00 SOL is outside 10-500 SOL"
+        );
+        assert!(bounds.rejects(&metrics(Some(900.0))).is_some());
+        assert!(bounds.rejects(&metrics(None)).is_some());
+
+        let cap = MarketBounds {
+            max_market_cap_sol: Some(50.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            cap.rejects(&metrics(None)).unwrap(),
+            "market cap 100.00 SOL is outside 0-50 SOL"
+        );
+    }
+}
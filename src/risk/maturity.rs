@@ -0,0 +1,193 @@
+use std::{env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    common::{
+        cache::BoundedCache,
+        utils::{log_message, AppState},
+    },
+    engine::{creator_exit::oldest_signature, hold_timer::parse_hold_duration},
+    risk::holders::fetch_holder_count,
+    services::notify::Event,
+};
+
+// Configuration constants
+const CREATED_CACHE_ENTRIES: usize = 5_000;
+const HOLDERS_CACHE_ENTRIES: usize = 1_000;
+const HOLDERS_TTL_SECS: u64 = 60;
+
+/// How old and how widely held a token must be before buys are copied into it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MaturityBounds {
+    pub min_age: Option<Duration>,
+    pub min_holders: Option<u64>,
+}
+
+impl MaturityBounds {
+    /// Reads `MIN_TOKEN_AGE` (e.g. `30m`) and `MIN_HOLDERS`
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            min_age: env::var("MIN_TOKEN_AGE")
+                .ok()
+                .map(|v| parse_hold_duration(&v).map(Duration::from_secs))
+                .transpose()
+                .context("Invalid MIN_TOKEN_AGE")?,
+            min_holders: env::var("MIN_HOLDERS")
+                .ok()
+                .map(|v| u64::from_str(&v).context("Invalid MIN_HOLDERS"))
+                .transpose()?,
+        })
+    }
+
+    /// Why a token created at `created` with `holders` may not be bought, or `None` if it may.
+    /// Either may be `None` when its bound is unset
+    pub fn rejects(
+        &self,
+        created: Option<DateTime<Utc>>,
+        holders: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        if let (Some(min_age), Some(created)) = (self.min_age, created) {
+            let age = (now - created).to_std().unwrap_or_default();
+            if age < min_age {
+                return Some(format!(
+                    "{}m old, younger than {}m",
+                    age.as_secs() / 60,
+                    min_age.as_secs() / 60
+                ));
+            }
+        }
+        if let (Some(min_holders), Some(holders)) = (self.min_holders, holders) {
+            if holders < min_holders {
+                return Some(format!("{} holders, fewer than {}", holders, min_holders));
+            }
+        }
+        None
+    }
+}
+
+/// Applies [`MaturityBounds`], caching creation times for good and holder counts briefly
+pub struct MaturityFilter {
+    pub bounds: MaturityBounds,
+    created: BoundedCache<String, DateTime<Utc>>,
+    holders: BoundedCache<String, u64>,
+}
+
+impl MaturityFilter {
+    pub fn new(bounds: MaturityBounds) -> Self {
+        Self {
+            bounds,
+            created: BoundedCache::from_env("token_created", CREATED_CACHE_ENTRIES, None),
+            holders: BoundedCache::from_env(
+                "token_holders",
+                HOLDERS_CACHE_ENTRIES,
+                Some(Duration::from_secs(HOLDERS_TTL_SECS)),
+            ),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(MaturityBounds::from_env()?))
+    }
+
+    /// When `mint`'s first transaction landed. A mint with more history than is walked back
+    /// reports the oldest transaction seen, which only makes it look younger
+    async fn created(&self, state: &AppState, mint: &str) -> Result<DateTime<Utc>> {
+        if let Some(created) = self.created.get(&mint.to_string()).await {
+            return Ok(created);
+        }
+        let oldest = oldest_signature(state, mint).await?;
+        let created = oldest
+            .block_time
+            .and_then(|time| DateTime::from_timestamp(time, 0))
+            .ok_or_else(|| anyhow!("No block time for {}", oldest.signature))?;
+        self.created.insert(mint.to_string(), created).await;
+        Ok(created)
+    }
+
+    /// Token accounts holding `mint`, from the holder tracker's last sample when it has one
+    async fn holders(&self, state: &AppState, mint: &str) -> Result<u64> {
+        if let Some(holders) = state.holders.holders(mint).await {
+            return Ok(holders);
+        }
+        if let Some(holders) = self.holders.get(&mint.to_string()).await {
+            return Ok(holders);
+        }
+        let holders = fetch_holder_count(state, mint).await?;
+        self.holders.insert(mint.to_string(), holders).await;
+        Ok(holders)
+    }
+
+    /// Forgets every cached holder count and creation time
+    pub async fn clear_cache(&self) {
+        self.created.clear().await;
+        self.holders.clear().await;
+    }
+}
+
+/// Checks `mint`'s age and holder count before a buy by `source`, logging and reporting a
+/// rejection. Tokens whose age or holders can't be read are skipped
+pub async fn maturity_allows(state: &AppState, source: &str, mint: &str) -> bool {
+    let filter = &state.maturity;
+    let bounds = filter.bounds;
+    if bounds == MaturityBounds::default() {
+        return true;
+    }
+    let measured = async {
+        let created = match bounds.min_age {
+            Some(_) => Some(filter.created(state, mint).await?),
+            None => None,
+        };
+        let holders = match bounds.min_holders {
+            Some(_) => Some(filter.holders(state, mint).await?),
+            None => None,
+        };
+        Ok::<_, anyhow::Error>(bounds.rejects(created, holders, Utc::now()))
+    };
+    let reason = match measured.await {
+        Ok(None) => return true,
+        Ok(Some(reason)) => reason,
+        Err(e) => format!("couldn't read age or holders: {}", e),
+    };
+    let _ = log_message(&format!(
+        "Maturity filter blocked {} buy of {}: {}",
+        source, mint, reason
+    ))
+    .await;
+    state
+        .notifier
+        .notify(Event::LimitBreached {
+            source: source.to_string(),
+            mint: mint.to_string(),
+            reason,
+        })
+        .await;
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let bounds = MaturityBounds {
+            min_age: Some(Duration::from_secs(30 * 60)),
+            min_holders: Some(100),
+        };
+        let now = Utc::now();
+        let created = |minutes| Some(now - chrono::Duration::minutes(minutes));
+        assert_eq!(bounds.rejects(created(45), Some(250), now), None);
+        assert_eq!(
+            bounds.rejects(created(10), Some(250), now).unwrap(),
+            "10m old, younger than 30m"
+        );
+        assert_eq!(
+            bounds.rejects(created(45), Some(40), now).unwrap(),
+            "40 holders, fewer than 100"
+        );
+        assert_eq!(MaturityBounds::default().rejects(None, None, now), None);
+    }
+}
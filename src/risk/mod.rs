@@ -0,0 +1,14 @@
+pub mod breaker;
+pub mod expectancy;
+pub mod filters;
+pub mod hedge;
+pub mod holders;
+pub mod impairment;
+pub mod limits;
+pub mod lp_watch;
+pub mod market;
+pub mod maturity;
+pub mod pause;
+pub mod supply_watch;
+pub mod tilt;
+pub mod token_safety;
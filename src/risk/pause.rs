@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    services::notify::Event,
+};
+
+/// Operator switch that stops new buys from every source until resumed; exits keep working
+#[derive(Debug, Default)]
+pub struct CopyPause {
+    paused: AtomicBool,
+}
+
+impl CopyPause {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the state changed
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::Relaxed) != paused
+    }
+}
+
+/// Pauses or resumes copying on behalf of `actor`, announcing real changes to the operators.
+/// Resuming also reopens an expectancy gate, which would otherwise wait out its pause
+pub async fn set_copying_paused(state: &AppState, actor: &str, paused: bool) -> bool {
+    let reopened = !paused && state.expectancy.resume().await;
+    if !state.pause.set_paused(paused) && !reopened {
+        return false;
+    }
+    let message = if paused {
+        format!("⏸️ Copying paused by {}", actor)
+    } else if reopened {
+        format!("▶️ Copying resumed by {}, expectancy gate reopened", actor)
+    } else {
+        format!("▶️ Copying resumed by {}", actor)
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_reports_changes() {
+        let pause = CopyPause::new();
+        assert!(!pause.set_paused(false));
+        assert!(pause.set_paused(true));
+        assert!(pause.is_paused());
+        assert!(!pause.set_paused(true));
+        assert!(pause.set_paused(false));
+    }
+}
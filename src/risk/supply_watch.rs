@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::{json, Value};
+use tokio::time::{interval, sleep};
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    engine::exit::market_exit,
+    risk::lp_watch::LpPullAction,
+    services::{curve_cache::CurveSubscriptions, notify::Event},
+};
+
+// Configuration constants
+const RECONNECT_DELAY_MS: u64 = 1_000;
+const SYNC_INTERVAL_MS: u64 = 1_000;
+const SUPPLY_MINT_EXIT_SLIPPAGE_BPS: u64 = 3_000;
+
+#[derive(Debug, Clone)]
+pub struct SupplyWatchConfig {
+    /// Change in a held mint's supply, as a share of the last seen supply, worth reporting
+    pub change_pct: f64,
+    /// What to do when the supply grows; burns are only reported
+    pub mint_action: LpPullAction,
+}
+
+impl SupplyWatchConfig {
+    /// Reads `SUPPLY_CHANGE_PCT` (unset disables) and `SUPPLY_MINT_ACTION`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(pct) = env::var("SUPPLY_CHANGE_PCT") else {
+            return Ok(None);
+        };
+        let change_pct = f64::from_str(&pct)
+            .ok()
+            .filter(|pct| *pct > 0.0)
+            .context("SUPPLY_CHANGE_PCT must be a positive percentage")?;
+        let mint_action = match env::var("SUPPLY_MINT_ACTION") {
+            Ok(action) => LpPullAction::from_str(&action)?,
+            Err(_) => LpPullAction::Alert,
+        };
+        Ok(Some(Self {
+            change_pct,
+            mint_action,
+        }))
+    }
+}
+
+/// A significant move in a held token's supply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupplyEvent {
+    /// Raw tokens minted and the share of the previous supply they add
+    Minted(u64, f64),
+    /// Raw tokens burned and the share of the previous supply they remove
+    Burned(u64, f64),
+}
+
+impl fmt::Display for SupplyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupplyEvent::Minted(amount, pct) => {
+                write!(f, "{} tokens minted (+{:.2}% supply)", amount, pct)
+            }
+            SupplyEvent::Burned(amount, pct) => {
+                write!(f, "{} tokens burned (-{:.2}% supply)", amount, pct)
+            }
+        }
+    }
+}
+
+/// Supply each held mint was last reported at; changes are measured from there so a slow
+/// drip of small mints still adds up to an event
+#[derive(Debug, Default)]
+pub struct SupplyTracker {
+    baselines: HashMap<String, u64>,
+}
+
+impl SupplyTracker {
+    /// Records `supply` for `mint`, returning an event once it has moved `change_pct` from the
+    /// baseline; the baseline then moves to `supply`
+    pub fn observe(&mut self, mint: &str, supply: u64, change_pct: f64) -> Option<SupplyEvent> {
+        let baseline = *self.baselines.entry(mint.to_string()).or_insert(supply);
+        if baseline == 0 || supply == baseline {
+            return None;
+        }
+        let pct = supply.abs_diff(baseline) as f64 / baseline as f64 * 100.0;
+        if pct < change_pct {
+            return None;
+        }
+        self.baselines.insert(mint.to_string(), supply);
+        Some(if supply > baseline {
+            SupplyEvent::Minted(supply - baseline, pct)
+        } else {
+            SupplyEvent::Burned(baseline - supply, pct)
+        })
+    }
+
+    pub fn retain(&mut self, mints: &HashSet<String>) {
+        self.baselines.retain(|mint, _| mints.contains(mint));
+    }
+}
+
+/// Supply and mint authority out of a `jsonParsed` mint account notification
+fn decode_mint(notification: &Value) -> Result<(u64, Option<String>)> {
+    let info = &notification["params"]["result"]["value"]["data"]["parsed"]["info"];
+    let supply = info["supply"]
+        .as_str()
+        .and_then(|supply| u64::from_str(supply).ok())
+        .ok_or_else(|| anyhow!("Mint notification without a supply"))?;
+    Ok((supply, info["mintAuthority"].as_str().map(str::to_string)))
+}
+
+async fn on_supply_event(
+    state: &AppState,
+    config: &SupplyWatchConfig,
+    mint: &str,
+    authority: Option<&str>,
+    event: SupplyEvent,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let authority = authority.map_or(String::new(), |authority| {
+        format!(", mint authority {}", authority)
+    });
+    let message = match (event, config.mint_action) {
+        (SupplyEvent::Minted(..), LpPullAction::Exit) => {
+            let Some(position) = state.positions.get(mint).await else {
+                return;
+            };
+            match market_exit(state, &position, SUPPLY_MINT_EXIT_SLIPPAGE_BPS, jito_client).await {
+                Ok(()) => format!("🚨 {}: {}{}, exited", mint, event, authority),
+                Err(e) => format!(
+                    "🚨 {}: {}{} but the exit failed: {}",
+                    mint, event, authority, e
+                ),
+            }
+        }
+        (SupplyEvent::Minted(..), LpPullAction::Alert) => {
+            format!("🚨 {}: {}{}", mint, event, authority)
+        }
+        (SupplyEvent::Burned(..), _) => format!("🔥 {}: {}", mint, event),
+    };
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+}
+
+/// Keeps one subscription open per held mint account until the socket drops
+async fn follow_mints(
+    state: &AppState,
+    config: &SupplyWatchConfig,
+    url: &str,
+    tracker: &mut SupplyTracker,
+    jito_client: &Arc<JitoRpcClient>,
+) -> Result<()> {
+    let (mut ws, _) = connect_async(url)
+        .await
+        .context("Failed to connect to WebSocket server")?;
+    // Same per-mint bookkeeping as the curve cache, only the subscribed account differs
+    let mut subscriptions = CurveSubscriptions::default();
+    let mut sync = interval(Duration::from_millis(SYNC_INTERVAL_MS));
+    loop {
+        tokio::select! {
+            _ = sync.tick() => {
+                let open = state
+                    .positions
+                    .open_positions()
+                    .await
+                    .into_iter()
+                    .map(|position| position.mint)
+                    .collect::<HashSet<_>>();
+                tracker.retain(&open);
+                let (added, removed) = subscriptions.diff(&open);
+                for mint in added {
+                    let request = json!({
+                        "jsonrpc": "2.0",
+                        "id": subscriptions.request(Some(mint.clone())),
+                        "method": "accountSubscribe",
+                        "params": [mint, { "encoding": "jsonParsed", "commitment": "confirmed" }]
+                    });
+                    ws.send(request.to_string().into()).await?;
+                }
+                for (subscription, _) in removed {
+                    subscriptions.drop_subscription(subscription);
+                    let request = json!({
+                        "jsonrpc": "2.0",
+                        "id": subscriptions.request(None),
+                        "method": "accountUnsubscribe",
+                        "params": [subscription]
+                    });
+                    ws.send(request.to_string().into()).await?;
+                }
+            }
+            message = ws.next() => {
+                let text = match message {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(anyhow!("WebSocket closed")),
+                };
+                let Ok(json) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                let response = (json["id"].as_u64(), json["result"].as_u64());
+                if let (Some(id), Some(subscription)) = response {
+                    subscriptions.confirm(id, subscription);
+                    continue;
+                }
+                let Some(subscription) = json["params"]["subscription"].as_u64() else {
+                    continue;
+                };
+                let Some(mint) = subscriptions.mint(subscription).cloned() else {
+                    continue;
+                };
+                let (supply, authority) = match decode_mint(&json) {
+                    Ok(mint_state) => mint_state,
+                    Err(e) => {
+                        let _ = log_message(&format!("Bad mint update for {}: {}", mint, e)).await;
+                        continue;
+                    }
+                };
+                if let Some(event) = tracker.observe(&mint, supply, config.change_pct) {
+                    on_supply_event(
+                        state,
+                        config,
+                        &mint,
+                        authority.as_deref(),
+                        event,
+                        jito_client.clone(),
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+}
+
+/// Follows the supply of every held mint and reports burns and mints that move it by
+/// `change_pct` or more, reconnecting forever. Baselines survive reconnects
+pub async fn run_supply_watch(
+    config: SupplyWatchConfig,
+    state: AppState,
+    url: String,
+    jito_client: Arc<JitoRpcClient>,
+) {
+    let mut tracker = SupplyTracker::default();
+    loop {
+        if let Err(e) = follow_mints(&state, &config, &url, &mut tracker, &jito_client).await {
+            let _ = log_message(&format!("Mint subscriptions dropped: {}", e)).await;
+        }
+        sleep(Duration::from_millis(RECONNECT_DELAY_MS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supply_events() {
+        let mut tracker = SupplyTracker::default();
+        assert_eq!(tracker.observe("a", 1_000, 1.0), None);
+        assert_eq!(tracker.observe("a", 1_005, 1.0), None);
+        // Small mints accumulate against the baseline
+        assert_eq!(
+            tracker.observe("a", 1_020, 1.0),
+            Some(SupplyEvent::Minted(20, 2.0))
+        );
+        assert_eq!(
+            tracker.observe("a", 510, 1.0),
+            Some(SupplyEvent::Burned(510, 50.0))
+        );
+
+        let notification = json!({
+            "params": { "result": { "value": { "data": { "parsed": {
+                "info": { "supply": "510", "decimals": 6, "mintAuthority": null }
+            } } } } }
+        });
+        assert_eq!(decode_mint(&notification).unwrap(), (510, None));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    env, fmt,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use tokio::time::sleep;
+
+use crate::{
+    common::utils::{log_message, AppState},
+    services::notify::Event,
+};
+
+// Configuration constants
+const DEFAULT_COOLDOWN_SECS: u64 = 1_800;
+
+#[derive(Debug, Clone)]
+pub struct TiltConfig {
+    /// Consecutive losing trades that trigger a cool-off
+    pub max_losing_streak: Option<u32>,
+    /// A single loss at least this large, in lamports, triggers a cool-off
+    pub max_loss: Option<u64>,
+    pub cooldown: Duration,
+}
+
+impl TiltConfig {
+    /// Reads `TILT_MAX_LOSING_STREAK` and `TILT_MAX_LOSS_SOL` (both unset disables) and
+    /// `TILT_COOLDOWN_SECS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let max_losing_streak = match env::var("TILT_MAX_LOSING_STREAK") {
+            Ok(streak) => Some(
+                u32::from_str(&streak)
+                    .ok()
+                    .filter(|streak| *streak > 0)
+                    .context("TILT_MAX_LOSING_STREAK must be a positive number of trades")?,
+            ),
+            Err(_) => None,
+        };
+        let max_loss = match env::var("TILT_MAX_LOSS_SOL") {
+            Ok(sol) => Some(sol_to_lamports(
+                f64::from_str(&sol).context("TILT_MAX_LOSS_SOL must be an amount of SOL")?,
+            )),
+            Err(_) => None,
+        };
+        if max_losing_streak.is_none() && max_loss.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            max_losing_streak,
+            max_loss,
+            cooldown: Duration::from_secs(
+                env::var("TILT_COOLDOWN_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_COOLDOWN_SECS),
+            ),
+        }))
+    }
+}
+
+/// What sent the bot into a cool-off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TiltTrigger {
+    LosingStreak(u32),
+    /// Lamports lost on one trade
+    BigLoss(u64),
+}
+
+impl fmt::Display for TiltTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiltTrigger::LosingStreak(streak) => write!(f, "{} losing trades in a row", streak),
+            TiltTrigger::BigLoss(lamports) => {
+                write!(
+                    f,
+                    "a {:.4} SOL loss on one trade",
+                    lamports_to_sol(*lamports)
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TiltState {
+    streak: u32,
+    paused_until: Option<Instant>,
+}
+
+/// Pauses new entries for a while after a losing streak or one outsized loss
+#[derive(Debug, Default)]
+pub struct TiltGuard {
+    config: Option<TiltConfig>,
+    state: Mutex<TiltState>,
+}
+
+impl TiltGuard {
+    pub fn new(config: Option<TiltConfig>) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(TiltConfig::from_env()?))
+    }
+
+    pub fn allows_entry(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        !matches!(state.paused_until, Some(until) if Instant::now() < until)
+    }
+
+    /// Adds a closed trade's net P&L; returns the trigger and cool-off if it starts one
+    pub fn record_trade(&self, pnl: i64) -> Option<(TiltTrigger, Duration)> {
+        let config = self.config.as_ref()?;
+        let mut state = self.state.lock().unwrap();
+        if pnl > 0 {
+            state.streak = 0;
+            return None;
+        }
+        state.streak += 1;
+        let loss = pnl.unsigned_abs();
+        let trigger = if config.max_loss.is_some_and(|max| loss >= max) {
+            TiltTrigger::BigLoss(loss)
+        } else if config
+            .max_losing_streak
+            .is_some_and(|max| state.streak >= max)
+        {
+            TiltTrigger::LosingStreak(state.streak)
+        } else {
+            return None;
+        };
+        state.streak = 0;
+        state.paused_until = Some(Instant::now() + config.cooldown);
+        Some((trigger, config.cooldown))
+    }
+}
+
+/// Feeds a closed trade to the tilt guard, announcing the pause and, later, the resume
+pub async fn observe_trade(state: &AppState, pnl: i64) {
+    let Some((trigger, cooldown)) = state.tilt.record_trade(pnl) else {
+        return;
+    };
+    let message = format!(
+        "🧯 Tilt guard: {}, pausing new entries for {} min",
+        trigger,
+        cooldown.as_secs() / 60
+    );
+    let _ = log_message(&message).await;
+    state.notifier.notify(Event::Info(message)).await;
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        sleep(cooldown).await;
+        // A later trip extends the pause and announces its own resume
+        if state.tilt.allows_entry() {
+            let message = "Tilt guard cool-off over, entries resumed".to_string();
+            let _ = log_message(&message).await;
+            state.notifier.notify(Event::Info(message)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tilt_triggers() {
+        let guard = TiltGuard::new(Some(TiltConfig {
+            max_losing_streak: Some(3),
+            max_loss: Some(1_000),
+            cooldown: Duration::from_secs(60),
+        }));
+        assert!(guard.record_trade(-10).is_none());
+        // A win resets the streak
+        assert!(guard.record_trade(50).is_none());
+        assert!(guard.record_trade(-10).is_none());
+        assert!(guard.record_trade(-10).is_none());
+        assert!(guard.allows_entry());
+        assert_eq!(
+            guard.record_trade(-10).map(|(trigger, _)| trigger),
+            Some(TiltTrigger::LosingStreak(3))
+        );
+        assert!(!guard.allows_entry());
+        assert_eq!(
+            guard.record_trade(-1_500).map(|(trigger, _)| trigger),
+            Some(TiltTrigger::BigLoss(1_500))
+        );
+
+        let disabled = TiltGuard::default();
+        assert!(disabled.record_trade(-1_000_000).is_none());
+        assert!(disabled.allows_entry());
+    }
+}
@@ -0,0 +1,334 @@
+use std::{collections::HashSet, env, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use solana_sdk::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    core::token::get_mint_info,
+    dex::raydium::get_pool_state,
+    engine::{
+        creator_exit::{creator_balance, resolve_creator},
+        router::Venue,
+    },
+    services::notify::Event,
+};
+
+// Configuration constants
+const DEFAULT_MIN_LP_BURNED_PCT: f64 = 90.0;
+const LARGEST_LP_HOLDERS_CHECKED: usize = 5;
+
+/// Token-2022 mint extension from its snake_case name
+pub fn parse_extension(name: &str) -> Option<ExtensionType> {
+    Some(match name.trim() {
+        "transfer_fee" => ExtensionType::TransferFeeConfig,
+        "mint_close_authority" => ExtensionType::MintCloseAuthority,
+        "confidential_transfer" => ExtensionType::ConfidentialTransferMint,
+        "default_account_state" => ExtensionType::DefaultAccountState,
+        "non_transferable" => ExtensionType::NonTransferable,
+        "interest_bearing" => ExtensionType::InterestBearingConfig,
+        "permanent_delegate" => ExtensionType::PermanentDelegate,
+        "transfer_hook" => ExtensionType::TransferHook,
+        _ => return None,
+    })
+}
+
+/// What to do with a buy that fails a safety check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafetyMode {
+    Reject,
+    Flag,
+}
+
+/// Which checks run before a copy buy and how strict they are
+#[derive(Debug, Clone)]
+pub struct SafetyConfig {
+    pub mode: SafetyMode,
+    pub require_mint_revoked: bool,
+    pub require_freeze_null: bool,
+    /// Largest share of supply the mint's creator may still hold; off when unset, since
+    /// resolving the creator walks the mint's history
+    pub max_creator_pct: Option<f64>,
+    pub min_lp_burned_pct: f64,
+    /// Owners whose LP holdings count as locked (e.g. locker programs' vault authorities)
+    pub lp_lockers: HashSet<Pubkey>,
+    /// Token-2022 extensions that let someone else move or gate our tokens
+    pub blocked_extensions: Vec<ExtensionType>,
+    /// Mints exempt from `blocked_extensions`
+    pub extension_whitelist: HashSet<Pubkey>,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            mode: SafetyMode::Reject,
+            require_mint_revoked: true,
+            require_freeze_null: true,
+            max_creator_pct: None,
+            min_lp_burned_pct: DEFAULT_MIN_LP_BURNED_PCT,
+            lp_lockers: HashSet::new(),
+            blocked_extensions: vec![
+                ExtensionType::PermanentDelegate,
+                ExtensionType::TransferHook,
+            ],
+            extension_whitelist: HashSet::new(),
+        }
+    }
+}
+
+impl SafetyConfig {
+    /// Reads the `SAFETY_*` environment variables, keeping defaults for unset ones
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let flag = |key: &str, default: bool| {
+            env::var(key)
+                .ok()
+                .and_then(|v| bool::from_str(&v).ok())
+                .unwrap_or(default)
+        };
+        let pct = |key: &str, default: f64| {
+            env::var(key)
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .unwrap_or(default)
+        };
+
+        let blocked_extensions = match env::var("SAFETY_BLOCKED_EXTENSIONS") {
+            Ok(names) => names
+                .split(',')
+                .filter(|name| !name.trim().is_empty())
+                .map(|name| {
+                    parse_extension(name).ok_or_else(|| {
+                        anyhow!(
+                            "Unknown extension '{}' in SAFETY_BLOCKED_EXTENSIONS",
+                            name.trim()
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?,
+            Err(_) => default.blocked_extensions,
+        };
+
+        Ok(Self {
+            mode: match env::var("SAFETY_MODE").as_deref() {
+                Ok("flag") => SafetyMode::Flag,
+                _ => SafetyMode::Reject,
+            },
+            require_mint_revoked: flag("SAFETY_REQUIRE_MINT_REVOKED", default.require_mint_revoked),
+            require_freeze_null: flag("SAFETY_REQUIRE_FREEZE_NULL", default.require_freeze_null),
+            max_creator_pct: env::var("SAFETY_MAX_CREATOR_PCT")
+                .ok()
+                .and_then(|v| f64::from_str(&v).ok())
+                .or(default.max_creator_pct),
+            min_lp_burned_pct: pct("SAFETY_MIN_LP_BURNED_PCT", default.min_lp_burned_pct),
+            lp_lockers: env::var("SAFETY_LP_LOCKERS")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|key| Pubkey::from_str(key.trim()).ok())
+                .collect(),
+            blocked_extensions,
+            extension_whitelist: env::var("SAFETY_EXTENSION_WHITELIST")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|key| Pubkey::from_str(key.trim()).ok())
+                .collect(),
+        })
+    }
+}
+
+/// A single failed safety check
+#[derive(Debug, Clone, PartialEq)]
+pub enum SafetyFailure {
+    MintAuthorityActive,
+    FreezeAuthorityActive,
+    CreatorConcentration { creator: Pubkey, pct: f64 },
+    LpNotSecured { burned_or_locked_pct: f64 },
+    BlockedExtension(ExtensionType),
+}
+
+/// Outcome of the pre-buy safety checks for a mint
+#[derive(Debug, Clone, Default)]
+pub struct SafetyReport {
+    pub mint: String,
+    pub failures: Vec<SafetyFailure>,
+}
+
+impl SafetyReport {
+    pub fn is_safe(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_safe() {
+            return format!("{} passed safety checks", self.mint);
+        }
+        let reasons: Vec<String> = self
+            .failures
+            .iter()
+            .map(|failure| match failure {
+                SafetyFailure::MintAuthorityActive => "mint authority not revoked".to_string(),
+                SafetyFailure::FreezeAuthorityActive => "freeze authority set".to_string(),
+                SafetyFailure::CreatorConcentration { creator, pct } => {
+                    format!("creator {} holds {:.1}% of supply", creator, pct)
+                }
+                SafetyFailure::LpNotSecured {
+                    burned_or_locked_pct,
+                } => format!("only {:.1}% of LP burned or locked", burned_or_locked_pct),
+                SafetyFailure::BlockedExtension(extension) => {
+                    format!("has the {:?} extension", extension)
+                }
+            })
+            .collect();
+        format!("{} failed safety checks: {}", self.mint, reasons.join(", "))
+    }
+}
+
+/// Runs every configured check against a mint before buying it
+pub async fn check_token_safety(
+    state: &AppState,
+    config: &SafetyConfig,
+    mint: &str,
+) -> Result<SafetyReport> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let mut report = SafetyReport {
+        mint: mint.to_string(),
+        ..Default::default()
+    };
+
+    let mint_info = get_mint_info(
+        state.rpc_nonblocking_client.clone(),
+        state.wallet.clone(),
+        &mint_pubkey,
+    )
+    .await?;
+    if config.require_mint_revoked && mint_info.base.mint_authority != COption::None {
+        report.failures.push(SafetyFailure::MintAuthorityActive);
+    }
+    if config.require_freeze_null && mint_info.base.freeze_authority != COption::None {
+        report.failures.push(SafetyFailure::FreezeAuthorityActive);
+    }
+    if !config.extension_whitelist.contains(&mint_pubkey) {
+        for extension in mint_info.get_extension_types()? {
+            if config.blocked_extensions.contains(&extension) {
+                report
+                    .failures
+                    .push(SafetyFailure::BlockedExtension(extension));
+            }
+        }
+    }
+
+    let supply = mint_info.base.supply;
+    if let Some(max_pct) = config.max_creator_pct.filter(|_| supply > 0) {
+        let creator = resolve_creator(state, mint).await?;
+        let pct = creator_balance(state, mint, &creator).await as f64 / supply as f64 * 100.0;
+        if pct > max_pct {
+            report.failures.push(SafetyFailure::CreatorConcentration {
+                creator: Pubkey::from_str(&creator)?,
+                pct,
+            });
+        }
+    }
+
+    if let Venue::Raydium { pool } = state.router.route(state, mint).await? {
+        let secured = lp_burned_or_locked_pct(state, config, &pool).await?;
+        if secured < config.min_lp_burned_pct {
+            report.failures.push(SafetyFailure::LpNotSecured {
+                burned_or_locked_pct: secured,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Runs the token safety checks before a buy; false means skip the buy
+pub async fn passes_safety(mint: &str, state: &AppState) -> bool {
+    let report = match check_token_safety(state, &state.safety, mint).await {
+        Ok(report) => report,
+        Err(e) => {
+            let _ = log_message(&format!("Safety check failed for {}: {}", mint, e)).await;
+            return state.safety.mode == SafetyMode::Flag;
+        }
+    };
+    if report.is_safe() {
+        return true;
+    }
+
+    let _ = log_message(&report.summary()).await;
+    state.notifier.notify(Event::Info(report.summary())).await;
+    state.safety.mode == SafetyMode::Flag
+}
+
+/// Percentage of a Raydium pool's original LP that has been burned or sits with a locker
+async fn lp_burned_or_locked_pct(
+    state: &AppState,
+    config: &SafetyConfig,
+    pool: &Pubkey,
+) -> Result<f64> {
+    let (_, amm_info) =
+        get_pool_state(state.rpc_client.clone(), Some(&pool.to_string()), None).await?;
+    if amm_info.lp_amount == 0 {
+        return Err(anyhow!("Pool {} reports no LP issued", pool));
+    }
+
+    let lp_supply = state
+        .rpc_nonblocking_client
+        .get_token_supply(&amm_info.lp_mint)
+        .await?;
+    let outstanding = u64::from_str(&lp_supply.amount)?;
+    let burned = amm_info.lp_amount.saturating_sub(outstanding);
+
+    let mut locked = 0u64;
+    if !config.lp_lockers.is_empty() {
+        let largest = state
+            .rpc_nonblocking_client
+            .get_token_largest_accounts(&amm_info.lp_mint)
+            .await?;
+        for holder in largest.iter().take(LARGEST_LP_HOLDERS_CHECKED) {
+            let address = Pubkey::from_str(&holder.address)?;
+            let data = state
+                .rpc_nonblocking_client
+                .get_account_data(&address)
+                .await?;
+            let account = spl_token::state::Account::unpack(&data)?;
+            if config.lp_lockers.contains(&account.owner) {
+                locked = locked.saturating_add(account.amount);
+            }
+        }
+    }
+
+    Ok((burned + locked) as f64 / amm_info.lp_amount as f64 * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_summary() {
+        let mut report = SafetyReport {
+            mint: "mint".to_string(),
+            ..Default::default()
+        };
+        assert!(report.is_safe());
+        assert_eq!(report.summary(), "mint passed safety checks");
+
+        report.failures.push(SafetyFailure::FreezeAuthorityActive);
+        report.failures.push(SafetyFailure::LpNotSecured {
+            burned_or_locked_pct: 12.5,
+        });
+        assert!(!report.is_safe());
+        assert_eq!(
+            report.summary(),
+            "mint failed safety checks: freeze authority set, only 12.5% of LP burned or locked"
+        );
+        report.failures = vec![SafetyFailure::BlockedExtension(
+            parse_extension("permanent_delegate").unwrap(),
+        )];
+        assert_eq!(
+            report.summary(),
+            "mint failed safety checks: has the PermanentDelegate extension"
+        );
+    }
+}
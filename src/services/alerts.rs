@@ -0,0 +1,297 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fmt,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use tokio::{
+    sync::{broadcast::error::RecvError, RwLock},
+    time::Instant,
+};
+
+use crate::{
+    common::utils::AppState,
+    services::{notify::Event, price_feed::PriceUpdate},
+};
+
+/// When a price alert should fire
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertCondition {
+    /// Price crosses from below to at or above the level
+    CrossAbove(f64),
+    /// Price crosses from above to at or below the level
+    CrossBelow(f64),
+    /// Price moves by at least `percent` (either way) within `window`
+    PercentMove { percent: f64, window: Duration },
+}
+
+impl FromStr for AlertCondition {
+    type Err = anyhow::Error;
+
+    /// Parses `above:<price>`, `below:<price>` or `move:<percent>:<window secs>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        match parts.as_slice() {
+            ["above", price] => Ok(AlertCondition::CrossAbove(f64::from_str(price)?)),
+            ["below", price] => Ok(AlertCondition::CrossBelow(f64::from_str(price)?)),
+            ["move", percent, window] => Ok(AlertCondition::PercentMove {
+                percent: f64::from_str(percent)?,
+                window: Duration::from_secs(u64::from_str(window)?),
+            }),
+            _ => Err(anyhow!(
+                "Invalid alert condition: '{}'. Use above:<price>, below:<price> or move:<pct>:<secs>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    /// The form `from_str` parses
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertCondition::CrossAbove(price) => write!(f, "above:{}", price),
+            AlertCondition::CrossBelow(price) => write!(f, "below:{}", price),
+            AlertCondition::PercentMove { percent, window } => {
+                write!(f, "move:{}:{}", percent, window.as_secs())
+            }
+        }
+    }
+}
+
+/// A registered, not yet fired alert
+#[derive(Debug, Clone)]
+pub struct PriceAlert {
+    pub id: u64,
+    pub mint: String,
+    pub condition: AlertCondition,
+}
+
+/// Price alerts on arbitrary mints, independent of positions
+#[derive(Default)]
+pub struct AlertBook {
+    alerts: RwLock<HashMap<u64, PriceAlert>>,
+    history: RwLock<HashMap<String, VecDeque<(Instant, f64)>>>,
+    next_id: AtomicU64,
+}
+
+impl AlertBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an alert and starts watching the mint's price
+    pub async fn register(&self, state: &AppState, mint: &str, condition: AlertCondition) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.alerts.write().await.insert(
+            id,
+            PriceAlert {
+                id,
+                mint: mint.to_string(),
+                condition,
+            },
+        );
+        state.price_feed.watch(mint).await;
+        id
+    }
+
+    /// Removes an alert; returns false if it did not exist
+    pub async fn remove(&self, state: &AppState, id: u64) -> bool {
+        match self.alerts.write().await.remove(&id) {
+            Some(alert) => {
+                state.price_feed.unwatch(&alert.mint).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Alerts still waiting to fire, oldest first
+    pub async fn list(&self) -> Vec<PriceAlert> {
+        let mut alerts = self.alerts.read().await.values().cloned().collect::<Vec<_>>();
+        alerts.sort_by_key(|alert| alert.id);
+        alerts
+    }
+
+    /// Seeds alerts from `PRICE_ALERTS` ("<mint>=<condition>;...")
+    pub async fn load_from_env(&self, state: &AppState) -> Result<()> {
+        let Ok(value) = env::var("PRICE_ALERTS") else {
+            return Ok(());
+        };
+        for entry in value.split(';').filter(|e| !e.trim().is_empty()) {
+            let (mint, condition) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid PRICE_ALERTS entry: '{}'", entry))?;
+            self.register(state, mint.trim(), AlertCondition::from_str(condition)?)
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Checks an update against the mint's alerts and removes the ones that fired
+    pub async fn evaluate(&self, update: &PriceUpdate) -> Vec<(PriceAlert, String)> {
+        let mut history = self.history.write().await;
+        let samples = history.entry(update.mint.clone()).or_default();
+        let previous = samples.back().map(|(_, price)| *price);
+
+        let mut alerts = self.alerts.write().await;
+        let fired: Vec<(PriceAlert, String)> = alerts
+            .values()
+            .filter(|alert| alert.mint == update.mint)
+            .filter_map(|alert| {
+                check_condition(&alert.condition, previous, samples, update)
+                    .map(|message| (alert.clone(), message))
+            })
+            .collect();
+        for (alert, _) in &fired {
+            alerts.remove(&alert.id);
+        }
+
+        // Keep just enough history for the longest percent-move window on this mint
+        let max_window = alerts
+            .values()
+            .filter(|alert| alert.mint == update.mint)
+            .filter_map(|alert| match alert.condition {
+                AlertCondition::PercentMove { window, .. } => Some(window),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        samples.push_back((update.observed_at, update.price));
+        while samples
+            .front()
+            .is_some_and(|(at, _)| update.observed_at.duration_since(*at) > max_window)
+        {
+            samples.pop_front();
+        }
+
+        fired
+    }
+}
+
+fn check_condition(
+    condition: &AlertCondition,
+    previous: Option<f64>,
+    samples: &VecDeque<(Instant, f64)>,
+    update: &PriceUpdate,
+) -> Option<String> {
+    match *condition {
+        AlertCondition::CrossAbove(level) => previous
+            .filter(|prev| *prev < level && update.price >= level)
+            .map(|_| format!("crossed above {}", level)),
+        AlertCondition::CrossBelow(level) => previous
+            .filter(|prev| *prev > level && update.price <= level)
+            .map(|_| format!("crossed below {}", level)),
+        AlertCondition::PercentMove { percent, window } => samples
+            .iter()
+            .filter(|(at, _)| update.observed_at.duration_since(*at) <= window)
+            .map(|(_, price)| *price)
+            .filter(|price| *price > 0.0)
+            .map(|price| (update.price - price) / price * 100.0)
+            .find(|change| change.abs() >= percent)
+            .map(|change| format!("moved {:+.1}% within {}s", change, window.as_secs())),
+    }
+}
+
+/// Evaluates alerts on every price update and delivers the ones that fire
+pub async fn run_alerts(state: AppState) {
+    let mut updates = state.price_feed.subscribe();
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+
+        for (alert, message) in state.alerts.evaluate(&update).await {
+            state.price_feed.unwatch(&alert.mint).await;
+            state
+                .notifier
+                .notify(Event::PriceAlert {
+                    mint: alert.mint,
+                    price: update.price,
+                    message,
+                })
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(mint: &str, price: f64, at: Instant) -> PriceUpdate {
+        PriceUpdate {
+            mint: mint.to_string(),
+            price,
+            observed_at: at,
+        }
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            AlertCondition::from_str("above:0.5").unwrap(),
+            AlertCondition::CrossAbove(0.5)
+        );
+        assert_eq!(
+            AlertCondition::from_str("move:10:300").unwrap(),
+            AlertCondition::PercentMove {
+                percent: 10.0,
+                window: Duration::from_secs(300)
+            }
+        );
+        assert!(AlertCondition::from_str("sideways:1").is_err());
+        for condition in ["above:0.5", "below:0.001", "move:12.5:60"] {
+            let parsed = AlertCondition::from_str(condition).unwrap();
+            assert_eq!(parsed.to_string(), condition);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cross_above_fires_once() {
+        let book = AlertBook::new();
+        book.alerts.write().await.insert(
+            0,
+            PriceAlert {
+                id: 0,
+                mint: "mint".to_string(),
+                condition: AlertCondition::CrossAbove(1.0),
+            },
+        );
+        let now = Instant::now();
+
+        assert!(book.evaluate(&update("mint", 0.9, now)).await.is_empty());
+        assert_eq!(book.evaluate(&update("mint", 1.1, now)).await.len(), 1);
+        assert!(book.evaluate(&update("mint", 0.9, now)).await.is_empty());
+        assert!(book.evaluate(&update("mint", 1.2, now)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_percent_move_within_window() {
+        let book = AlertBook::new();
+        book.alerts.write().await.insert(
+            0,
+            PriceAlert {
+                id: 0,
+                mint: "mint".to_string(),
+                condition: AlertCondition::PercentMove {
+                    percent: 10.0,
+                    window: Duration::from_secs(60),
+                },
+            },
+        );
+        let start = Instant::now();
+
+        assert!(book.evaluate(&update("mint", 1.0, start)).await.is_empty());
+        let fired = book
+            .evaluate(&update("mint", 0.85, start + Duration::from_secs(30)))
+            .await;
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].1.contains("-15.0%"));
+    }
+}
@@ -0,0 +1,556 @@
+use std::{env, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::{json, Value};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    common::{
+        config::LiveSettings,
+        utils::{log_message, AppState},
+    },
+    engine::{
+        dual_control::{act_on_vote, submit, ControlAction, Vote},
+        fees::position_breakeven,
+        panic::fire_panic_exits,
+        position::{load_closed_trades, Position},
+    },
+    risk::pause::set_copying_paused,
+    services::{
+        alerts::{AlertCondition, PriceAlert},
+        dashboard::{stream_events, DASHBOARD_HTML},
+        notify::Event,
+        snapshot::Snapshot,
+        sources::header,
+    },
+};
+
+// Configuration constants
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    pub addr: String,
+    /// `(name, token)` pairs; the name identifies the operator in dual control and the audit log
+    pub keys: Vec<(String, String)>,
+}
+
+impl ApiConfig {
+    /// Reads `API_ADDR` (unset disables) and `API_KEYS` as comma-separated `name:token` pairs,
+    /// required once the API is on
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(addr) = env::var("API_ADDR") else {
+            return Ok(None);
+        };
+        let keys = env::var("API_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, token) = pair
+                    .split_once(':')
+                    .filter(|(name, token)| !name.is_empty() && !token.is_empty())
+                    .ok_or_else(|| anyhow!("API_KEYS entries must be name:token"))?;
+                Ok((name.to_string(), token.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Err(anyhow!("API_ADDR is set but API_KEYS is empty"));
+        }
+        Ok(Some(Self { addr, keys }))
+    }
+
+    /// Name of the key presented as `Authorization: Bearer <token>`, or as a `key` query
+    /// parameter for browsers, which can't set headers on an event stream
+    fn operator(&self, head: &str) -> Option<&str> {
+        let token = match header(head, "authorization") {
+            Some(value) => value.strip_prefix("Bearer ")?,
+            None => query_key(head)?,
+        };
+        self.keys
+            .iter()
+            .find(|(_, key)| key == token)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// `key` parameter of the request line's query string
+fn query_key(head: &str) -> Option<&str> {
+    let path = head.lines().next()?.split(' ').nth(1)?;
+    path.split_once('?')?
+        .1
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("key="))
+}
+
+/// What an API request asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    Positions,
+    Position(String),
+    Pnl,
+    Settings,
+    UpdateSettings,
+    Buy,
+    Sell,
+    Pause,
+    Resume,
+    Panic,
+    Approve(u64),
+    Reject(u64),
+    Approvals,
+    ApproveCopy(u64),
+    RejectCopy(u64),
+    Alerts,
+    AddAlert,
+    RemoveAlert(u64),
+    Dashboard,
+    Events,
+}
+
+impl Route {
+    pub fn parse(method: &str, path: &str) -> Option<Self> {
+        let segments = path
+            .split('?')
+            .next()?
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let route = match (method, segments.as_slice()) {
+            ("GET", ["positions"]) => Route::Positions,
+            ("GET", ["positions", mint]) => Route::Position(mint.to_string()),
+            ("GET", ["pnl"]) => Route::Pnl,
+            ("GET", ["settings"]) => Route::Settings,
+            ("POST", ["settings"]) => Route::UpdateSettings,
+            ("POST", ["buy"]) => Route::Buy,
+            ("POST", ["sell"]) => Route::Sell,
+            ("POST", ["pause"]) => Route::Pause,
+            ("POST", ["resume"]) => Route::Resume,
+            ("POST", ["panic"]) => Route::Panic,
+            ("POST", ["actions", id, "approve"]) => Route::Approve(id.parse().ok()?),
+            ("POST", ["actions", id, "reject"]) => Route::Reject(id.parse().ok()?),
+            ("GET", ["approvals"]) => Route::Approvals,
+            ("POST", ["approvals", id, "approve"]) => Route::ApproveCopy(id.parse().ok()?),
+            ("POST", ["approvals", id, "reject"]) => Route::RejectCopy(id.parse().ok()?),
+            ("GET", ["alerts"]) => Route::Alerts,
+            ("POST", ["alerts"]) => Route::AddAlert,
+            ("DELETE", ["alerts", id]) => Route::RemoveAlert(id.parse().ok()?),
+            ("GET", ["dashboard"]) => Route::Dashboard,
+            ("GET", ["events"]) => Route::Events,
+            _ => return None,
+        };
+        Some(route)
+    }
+}
+
+/// Reads one request, returning its head and body or an error status for the response
+pub(crate) async fn read_request(
+    stream: &mut TcpStream,
+) -> Result<(String, Vec<u8>), &'static str> {
+    let mut request = vec![];
+    let mut chunk = vec![0; 8 * 1024];
+    let (head, head_len, content_length) = loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| "400 Bad Request")?;
+        if read == 0 {
+            return Err("400 Bad Request");
+        }
+        request.extend_from_slice(&chunk[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            return Err("413 Payload Too Large");
+        }
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&request[..end]).to_string();
+            let length = header(&head, "content-length")
+                .and_then(|v| usize::from_str(v).ok())
+                .unwrap_or(0);
+            break (head, end + 4, length);
+        }
+    };
+    if content_length > MAX_REQUEST_BYTES {
+        return Err("413 Payload Too Large");
+    }
+    while request.len() < head_len + content_length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| "400 Bad Request")?;
+        if read == 0 {
+            return Err("400 Bad Request");
+        }
+        request.extend_from_slice(&chunk[..read]);
+    }
+    Ok((head, request[head_len..head_len + content_length].to_vec()))
+}
+
+fn error(status: &'static str, message: impl ToString) -> (&'static str, Value) {
+    (status, json!({ "error": message.to_string() }))
+}
+
+fn mint_param(body: &Value) -> Result<String, (&'static str, Value)> {
+    let mint = body["mint"]
+        .as_str()
+        .ok_or_else(|| error("400 Bad Request", "mint is required"))?;
+    Pubkey::from_str(mint).map_err(|_| error("400 Bad Request", "mint is not an address"))?;
+    Ok(mint.to_string())
+}
+
+/// Opens a dual-control action for `operator`, running it straight away when no other
+/// approval is needed
+async fn submit_action(
+    state: &AppState,
+    action: ControlAction,
+    operator: &str,
+    jito_client: Arc<JitoRpcClient>,
+) -> (&'static str, Value) {
+    let (id, vote) = submit(state, action, Some(&format!("api:{}", operator))).await;
+    vote_response(state, id, vote, jito_client).await
+}
+
+async fn vote_response(
+    state: &AppState,
+    id: u64,
+    vote: Vote,
+    jito_client: Arc<JitoRpcClient>,
+) -> (&'static str, Value) {
+    let status = match vote {
+        Vote::Pending { .. } => "202 Accepted",
+        Vote::Unknown => "404 Not Found",
+        Vote::Duplicate => "409 Conflict",
+        Vote::Approved(_) | Vote::Rejected => "200 OK",
+    };
+    let message = act_on_vote(state, id, vote, jito_client).await;
+    (status, json!({ "id": id, "message": message }))
+}
+
+fn alert_json(alert: &PriceAlert) -> Value {
+    json!({
+        "id": alert.id,
+        "mint": alert.mint,
+        "condition": alert.condition.to_string(),
+    })
+}
+
+/// A position with its fee-aware breakeven, `null` until its fills' tokens are known
+async fn position_json(state: &AppState, position: &Position) -> Value {
+    let mut value = json!(position);
+    value["breakeven_price"] = json!(position_breakeven(state, position).await.ok());
+    value
+}
+
+fn settings_json(settings: &LiveSettings) -> Value {
+    json!({
+        "targets": settings.targets,
+        "slippage_bps": settings.slippage_bps,
+        "stop_loss_pct": settings.stop_loss_pct,
+    })
+}
+
+/// Answers a copy waiting on approval, the API's side of the Telegram buttons
+async fn resolve_copy(
+    state: &AppState,
+    operator: &str,
+    intent_id: u64,
+    approved: bool,
+) -> (&'static str, Value) {
+    if !state.approvals.resolve(intent_id, approved).await {
+        return error("404 Not Found", format!("Copy #{} is not waiting", intent_id));
+    }
+    let decision = if approved { "approved" } else { "rejected" };
+    let _ = log_message(&format!("Copy #{} {} by api:{}", intent_id, decision, operator)).await;
+    ("200 OK", json!({ "id": intent_id, "approved": approved }))
+}
+
+async fn set_paused(state: &AppState, operator: &str, paused: bool) -> (&'static str, Value) {
+    set_copying_paused(state, &format!("api:{}", operator), paused).await;
+    ("200 OK", json!({ "paused": state.pause.is_paused() }))
+}
+
+async fn handle(
+    state: &AppState,
+    operator: &str,
+    route: Route,
+    body: Value,
+    jito_client: Arc<JitoRpcClient>,
+) -> (&'static str, Value) {
+    match route {
+        Route::Positions => {
+            let mut positions = vec![];
+            for position in state.positions.open_positions().await {
+                positions.push(position_json(state, &position).await);
+            }
+            ("200 OK", json!(positions))
+        }
+        Route::Position(mint) => match state.positions.get(&mint).await {
+            Some(position) => (
+                "200 OK",
+                json!({
+                    "position": position_json(state, &position).await,
+                    "stop_loss_pct": state.settings.current().stop_loss_pct,
+                }),
+            ),
+            None => error("404 Not Found", format!("No open position in {}", mint)),
+        },
+        Route::Pnl => {
+            let trades = match load_closed_trades() {
+                Ok(trades) => trades,
+                Err(e) => return error("500 Internal Server Error", e),
+            };
+            let snapshot = Snapshot::build(&trades, 0, Utc::now());
+            let open = state.positions.open_positions().await;
+            let invested = open.iter().map(|p| p.sol_invested).sum::<u64>();
+            (
+                "200 OK",
+                json!({
+                    "trades": snapshot.trades,
+                    "wins": snapshot.wins,
+                    "win_rate": snapshot.win_rate,
+                    "realized_pnl_sol": snapshot.net_pnl_sol,
+                    "open_positions": open.len(),
+                    "open_invested_sol": lamports_to_sol(invested),
+                    "paused": state.pause.is_paused(),
+                    "loss_breaker_tripped": !state.loss_breaker.allows_entry(),
+                }),
+            )
+        }
+        Route::Settings => ("200 OK", settings_json(&state.settings.current())),
+        Route::UpdateSettings => {
+            let current = state.settings.current();
+            let mut settings = (*current).clone();
+            if let Some(slippage_bps) = body.get("slippage_bps") {
+                match slippage_bps.as_u64() {
+                    Some(bps) => settings.slippage_bps = bps,
+                    None => return error("400 Bad Request", "slippage_bps must be a number"),
+                }
+            }
+            if let Some(stop_loss_pct) = body.get("stop_loss_pct") {
+                match (stop_loss_pct.is_null(), stop_loss_pct.as_f64()) {
+                    (true, _) => settings.stop_loss_pct = None,
+                    (false, Some(pct)) if pct > 0.0 => settings.stop_loss_pct = Some(pct),
+                    _ => return error("400 Bad Request", "stop_loss_pct must be positive or null"),
+                }
+            }
+            let changes = settings.changes_from(&current);
+            if changes.is_empty() {
+                return error("400 Bad Request", "No settings changed");
+            }
+            let summary = changes.join("; ");
+            let action = ControlAction::ConfigChange { settings, summary };
+            submit_action(state, action, operator, jito_client).await
+        }
+        Route::Buy => {
+            let mint = match mint_param(&body) {
+                Ok(mint) => mint,
+                Err(response) => return response,
+            };
+            let Some(sol) = body["sol"].as_f64().filter(|sol| *sol > 0.0) else {
+                return error("400 Bad Request", "sol must be a positive amount");
+            };
+            let lamports = sol_to_lamports(sol);
+            let action = ControlAction::ManualBuy { mint, lamports };
+            submit_action(state, action, operator, jito_client).await
+        }
+        Route::Sell => {
+            let mint = match mint_param(&body) {
+                Ok(mint) => mint,
+                Err(response) => return response,
+            };
+            let pct = match body.get("pct") {
+                None => 100.0,
+                Some(pct) => match pct.as_f64().filter(|pct| *pct > 0.0 && *pct <= 100.0) {
+                    Some(pct) => pct,
+                    None => return error("400 Bad Request", "pct must be in (0, 100]"),
+                },
+            };
+            if state.positions.get(&mint).await.is_none() {
+                return error("404 Not Found", format!("No open position in {}", mint));
+            }
+            let action = ControlAction::ManualSell { mint, pct };
+            submit_action(state, action, operator, jito_client).await
+        }
+        Route::Pause => set_paused(state, operator, true).await,
+        Route::Resume => set_paused(state, operator, false).await,
+        // Panic only ever closes exposure, so it doesn't wait on a second approver
+        Route::Panic => {
+            let report = fire_panic_exits(state, jito_client).await;
+            let summary = report.summary_as("🚨 Panic exit");
+            let _ = log_message(&summary).await;
+            state.notifier.notify(Event::Info(summary.clone())).await;
+            ("200 OK", json!({ "message": summary }))
+        }
+        Route::Approve(id) => {
+            let vote = state
+                .control
+                .approve(id, &format!("api:{}", operator))
+                .await;
+            vote_response(state, id, vote, jito_client).await
+        }
+        Route::Reject(id) => {
+            let vote = state.control.reject(id, &format!("api:{}", operator)).await;
+            vote_response(state, id, vote, jito_client).await
+        }
+        Route::Approvals => ("200 OK", json!({ "waiting": state.approvals.waiting_ids().await })),
+        Route::ApproveCopy(id) => resolve_copy(state, operator, id, true).await,
+        Route::RejectCopy(id) => resolve_copy(state, operator, id, false).await,
+        Route::Alerts => {
+            let alerts = state.alerts.list().await;
+            ("200 OK", json!(alerts.iter().map(alert_json).collect::<Vec<_>>()))
+        }
+        Route::AddAlert => {
+            let mint = match mint_param(&body) {
+                Ok(mint) => mint,
+                Err(response) => return response,
+            };
+            let condition = match body["condition"].as_str().map(AlertCondition::from_str) {
+                Some(Ok(condition)) => condition,
+                Some(Err(e)) => return error("400 Bad Request", e),
+                None => return error("400 Bad Request", "condition is required"),
+            };
+            let id = state.alerts.register(state, &mint, condition).await;
+            ("200 OK", json!({ "id": id }))
+        }
+        Route::RemoveAlert(id) => {
+            if !state.alerts.remove(state, id).await {
+                return error("404 Not Found", format!("No alert #{}", id));
+            }
+            ("200 OK", json!({ "id": id, "removed": true }))
+        }
+        // Served by `serve` itself, which owns the stream
+        Route::Dashboard | Route::Events => error("404 Not Found", "No such endpoint"),
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    config: &ApiConfig,
+    state: &AppState,
+    jito_client: Arc<JitoRpcClient>,
+) -> Result<()> {
+    let (status, body) = match read_request(&mut stream).await {
+        Err(status) => error(status, status),
+        Ok((head, body)) => {
+            let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+            let route = Route::parse(
+                request_line.next().unwrap_or_default(),
+                request_line.next().unwrap_or_default(),
+            );
+            let body = if body.is_empty() {
+                Ok(Value::Null)
+            } else {
+                serde_json::from_slice::<Value>(&body)
+            };
+            match (config.operator(&head), route, body) {
+                (None, _, _) => error("401 Unauthorized", "Missing or unknown API key"),
+                (_, None, _) => error("404 Not Found", "No such endpoint"),
+                (_, _, Err(_)) => error("400 Bad Request", "Body is not JSON"),
+                (Some(_), Some(Route::Dashboard), _) => {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        DASHBOARD_HTML.len(),
+                        DASHBOARD_HTML
+                    );
+                    stream.write_all(response.as_bytes()).await?;
+                    return Ok(());
+                }
+                (Some(_), Some(Route::Events), _) => {
+                    return stream_events(&mut stream, state).await
+                }
+                (Some(operator), Some(route), Ok(body)) => {
+                    handle(state, operator, route, body, jito_client).await
+                }
+            }
+        }
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Serves the JSON control API on `config.addr` forever: positions, PnL, manual trades,
+/// pause/resume, risk settings, dual-control votes and copy approvals, plus the live dashboard at
+/// `/dashboard?key=<token>`. Trades and settings changes go through dual control like their
+/// Telegram counterparts
+pub async fn run_api_server(config: ApiConfig, state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let listener = match TcpListener::bind(&config.addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            let _ = log_message(&format!("API failed to bind {}: {}", config.addr, e)).await;
+            return;
+        }
+    };
+    let _ = log_message(&format!(
+        "Serving the control API on http://{}",
+        config.addr
+    ))
+    .await;
+    let config = Arc::new(config);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let (config, state, jito_client) = (config.clone(), state.clone(), jito_client.clone());
+        tokio::spawn(async move {
+            let _ = serve(stream, &config, &state, jito_client).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_and_keys() {
+        assert_eq!(Route::parse("GET", "/positions"), Some(Route::Positions));
+        assert_eq!(
+            Route::parse("GET", "/positions/mint"),
+            Some(Route::Position("mint".to_string()))
+        );
+        assert_eq!(Route::parse("GET", "/pnl?fresh=1"), Some(Route::Pnl));
+        assert_eq!(
+            Route::parse("POST", "/actions/7/approve"),
+            Some(Route::Approve(7))
+        );
+        assert_eq!(Route::parse("GET", "/buy"), None);
+        assert_eq!(Route::parse("POST", "/actions/x/reject"), None);
+        assert_eq!(
+            Route::parse("POST", "/approvals/12/reject"),
+            Some(Route::RejectCopy(12))
+        );
+        assert_eq!(
+            Route::parse("DELETE", "/alerts/3"),
+            Some(Route::RemoveAlert(3))
+        );
+
+        let config = ApiConfig {
+            addr: String::new(),
+            keys: vec![("ops".to_string(), "secret".to_string())],
+        };
+        let head = "POST /pause HTTP/1.1\r\nAuthorization: Bearer secret";
+        assert_eq!(config.operator(head), Some("ops"));
+        assert_eq!(config.operator("POST /pause HTTP/1.1"), None);
+        assert_eq!(
+            config.operator("GET /events?key=secret HTTP/1.1"),
+            Some("ops")
+        );
+        assert_eq!(config.operator("GET /events?key=wrong HTTP/1.1"), None);
+    }
+}
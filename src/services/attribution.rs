@@ -0,0 +1,234 @@
+use std::{collections::BTreeMap, env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::{
+    common::{
+        storage::{data_path, load_json, save_json},
+        utils::log_message,
+    },
+    engine::position::{load_closed_trades, ClosedTrade},
+};
+
+// Configuration constants
+const DEFAULT_INTERVAL_SECS: u64 = 3_600;
+const CURSOR_FILE: &str = "attribution_cursor.json";
+
+/// Where outcome stats go; only built when the operator has explicitly opted in
+#[derive(Debug, Clone)]
+pub struct AttributionConfig {
+    pub url: String,
+    /// Subscription token issued by the signal provider, sent as a bearer token
+    pub token: Option<String>,
+    pub interval: Duration,
+}
+
+impl AttributionConfig {
+    /// Reads `ATTRIBUTION_URL` (unset disables), `ATTRIBUTION_OPT_IN`, which must be `true`,
+    /// `ATTRIBUTION_TOKEN` and `ATTRIBUTION_INTERVAL_SECS`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = env::var("ATTRIBUTION_URL") else {
+            return Ok(None);
+        };
+        if env::var("ATTRIBUTION_OPT_IN").as_deref() != Ok("true") {
+            return Err(anyhow!(
+                "ATTRIBUTION_URL is set but reporting is off until ATTRIBUTION_OPT_IN=true"
+            ));
+        }
+        Ok(Some(Self {
+            url,
+            token: env::var("ATTRIBUTION_TOKEN").ok(),
+            interval: Duration::from_secs(
+                env::var("ATTRIBUTION_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| u64::from_str(&v).ok())
+                    .unwrap_or(DEFAULT_INTERVAL_SECS)
+                    .max(60),
+            ),
+        }))
+    }
+}
+
+/// How copies of one target turned out. Only relative figures, so neither our wallet nor
+/// our size can be read off it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TargetOutcome {
+    pub target: String,
+    pub trades: usize,
+    pub wins: usize,
+    /// Mean net return per trade, in percent of what went in
+    pub avg_return_pct: f64,
+    pub avg_hold_secs: i64,
+}
+
+/// Everything a report contains
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub targets: Vec<TargetOutcome>,
+}
+
+impl AttributionReport {
+    /// Outcomes of the attributed trades closed after `from` and up to `to`
+    pub fn new(trades: &[ClosedTrade], from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        let mut by_target: BTreeMap<&str, Vec<&ClosedTrade>> = BTreeMap::new();
+        for trade in trades {
+            if trade.closed_at <= from || trade.closed_at > to {
+                continue;
+            }
+            if let Some(target) = &trade.target {
+                by_target.entry(target.as_str()).or_default().push(trade);
+            }
+        }
+        let targets = by_target
+            .into_iter()
+            .map(|(target, trades)| {
+                let count = trades.len();
+                let return_pct = |trade: &&ClosedTrade| {
+                    if trade.sol_invested == 0 {
+                        0.0
+                    } else {
+                        trade.pnl() as f64 / trade.sol_invested as f64 * 100.0
+                    }
+                };
+                TargetOutcome {
+                    target: target.to_string(),
+                    trades: count,
+                    wins: trades.iter().filter(|trade| trade.pnl() > 0).count(),
+                    avg_return_pct: trades.iter().map(return_pct).sum::<f64>() / count as f64,
+                    avg_hold_secs: trades
+                        .iter()
+                        .map(|trade| (trade.closed_at - trade.opened_at).num_seconds())
+                        .sum::<i64>()
+                        / count as i64,
+                }
+            })
+            .collect();
+        Self { from, to, targets }
+    }
+}
+
+async fn post(
+    http: &reqwest::Client,
+    config: &AttributionConfig,
+    report: &AttributionReport,
+) -> Result<()> {
+    let mut request = http.post(&config.url).json(report);
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Reports per-target outcomes of newly closed trades every interval, forever. Trades are
+/// only marked reported once the provider accepts them
+pub async fn run_attribution(config: AttributionConfig) {
+    let http = reqwest::Client::new();
+    let cursor_path = data_path(CURSOR_FILE);
+    // A first run starts from now rather than sending the whole history
+    let mut cursor = match load_json::<DateTime<Utc>>(&cursor_path) {
+        Ok(Some(cursor)) => cursor,
+        _ => Utc::now(),
+    };
+
+    loop {
+        sleep(config.interval).await;
+        let trades = match load_closed_trades() {
+            Ok(trades) => trades,
+            Err(e) => {
+                let _ = log_message(&format!("Attribution skipped a report: {}", e)).await;
+                continue;
+            }
+        };
+        let now = Utc::now();
+        let report = AttributionReport::new(&trades, cursor, now);
+        if !report.targets.is_empty() {
+            if let Err(e) = post(&http, &config, &report).await {
+                let _ = log_message(&format!("Attribution report failed: {}", e)).await;
+                continue;
+            }
+        }
+        cursor = now;
+        if let Err(e) = save_json(&cursor_path, &cursor).await {
+            let _ = log_message(&format!("Failed to persist attribution cursor: {}", e)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::EventTime;
+
+    fn trade(target: Option<&str>, minutes: i64, invested: u64, returned: u64) -> ClosedTrade {
+        let opened_at = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        ClosedTrade {
+            mint: "mint".to_string(),
+            opened_at,
+            closed_at: opened_at + chrono::Duration::minutes(minutes),
+            sol_invested: invested,
+            sol_returned: returned,
+            fees_paid: 0,
+            target: target.map(str::to_string),
+            time: EventTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let trades = vec![
+            trade(Some("a"), 10, 1_000, 1_500),
+            trade(Some("a"), 20, 1_000, 500),
+            trade(Some("b"), 30, 2_000, 2_200),
+            // Not copied from a target
+            trade(None, 40, 1_000, 3_000),
+            // Already reported
+            trade(Some("b"), 1, 1_000, 0),
+        ];
+        let from = trades[0].opened_at + chrono::Duration::minutes(5);
+        let report = AttributionReport::new(&trades, from, Utc::now());
+        assert_eq!(
+            report.targets,
+            vec![
+                TargetOutcome {
+                    target: "a".to_string(),
+                    trades: 2,
+                    wins: 1,
+                    avg_return_pct: 0.0,
+                    avg_hold_secs: 900,
+                },
+                TargetOutcome {
+                    target: "b".to_string(),
+                    trades: 1,
+                    wins: 1,
+                    avg_return_pct: 10.0,
+                    avg_hold_secs: 1_800,
+                },
+            ]
+        );
+        // Nothing beyond the per-target figures leaves the bot
+        let json = serde_json::to_value(&report).unwrap();
+        let mut keys = json["targets"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "avg_hold_secs",
+                "avg_return_pct",
+                "target",
+                "trades",
+                "wins"
+            ]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::{env, str::FromStr, sync::Arc, sync::LazyLock, time::Duration};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
+use tokio::{sync::RwLock, time::Instant};
+
+use crate::{common::utils::log_message, services::idle::idle_sleep};
+
+// Configuration constants
+const DEFAULT_POLL_INTERVAL_MS: u64 = 400;
+const DEFAULT_MAX_AGE_MS: u64 = 10_000;
+
+/// Latest blockhash kept warm by `run_blockhash_prefetch`
+pub static BLOCKHASH_CACHE: LazyLock<BlockhashCache> = LazyLock::new(BlockhashCache::new);
+
+/// A prefetched blockhash and when it was fetched
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBlockhash {
+    pub hash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+impl CachedBlockhash {
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+}
+
+pub struct BlockhashCache {
+    latest: RwLock<Option<CachedBlockhash>>,
+    max_age: Duration,
+}
+
+impl Default for BlockhashCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockhashCache {
+    /// Creates an empty cache that treats hashes older than `BLOCKHASH_MAX_AGE_MS` as stale
+    pub fn new() -> Self {
+        let max_age_ms = env::var("BLOCKHASH_MAX_AGE_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_MAX_AGE_MS);
+
+        Self {
+            latest: RwLock::new(None),
+            max_age: Duration::from_millis(max_age_ms),
+        }
+    }
+
+    pub async fn latest(&self) -> Option<CachedBlockhash> {
+        *self.latest.read().await
+    }
+
+    /// The cached hash, unless the prefetcher has fallen behind
+    pub async fn fresh(&self) -> Option<Hash> {
+        self.latest()
+            .await
+            .filter(|cached| cached.age() <= self.max_age)
+            .map(|cached| cached.hash)
+    }
+
+    pub async fn update(&self, hash: Hash, last_valid_block_height: u64) {
+        *self.latest.write().await = Some(CachedBlockhash {
+            hash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        });
+    }
+}
+
+/// Polls `getLatestBlockhash` forever so transaction building never waits on it
+pub async fn run_blockhash_prefetch(client: Arc<RpcClient>) {
+    let poll_interval = Duration::from_millis(
+        env::var("BLOCKHASH_POLL_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+    );
+
+    loop {
+        match client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+        {
+            Ok((hash, last_valid_block_height)) => {
+                BLOCKHASH_CACHE.update(hash, last_valid_block_height).await;
+            }
+            Err(e) => {
+                let _ = log_message(&format!("Blockhash prefetch failed: {}", e)).await;
+            }
+        }
+        idle_sleep(poll_interval).await;
+    }
+}
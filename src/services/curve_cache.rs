@@ -0,0 +1,264 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use borsh::BorshDeserialize;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::RwLock,
+    time::{interval, sleep},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::pump::{get_pda, BondingCurveAccount, PUMP_PROGRAM_ID},
+};
+
+// Configuration constants
+const RECONNECT_DELAY_MS: u64 = 1_000;
+const SYNC_INTERVAL_MS: u64 = 1_000;
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+const PUMP_TOKEN_UNITS: f64 = 1_000_000.0;
+
+/// SOL per token a curve currently trades at
+pub fn curve_price(curve: &BondingCurveAccount) -> f64 {
+    if curve.virtual_token_reserves == 0 {
+        return 0.0;
+    }
+    (curve.virtual_sol_reserves as f64 / LAMPORTS_PER_SOL)
+        / (curve.virtual_token_reserves as f64 / PUMP_TOKEN_UNITS)
+}
+
+/// Bonding curves of open positions as last pushed by `accountSubscribe`
+#[derive(Debug, Default)]
+pub struct CurveCache {
+    curves: RwLock<HashMap<String, BondingCurveAccount>>,
+}
+
+impl CurveCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, mint: &str) -> Option<BondingCurveAccount> {
+        self.curves.read().await.get(mint).cloned()
+    }
+
+    /// Price off the latest reserves; `None` if the curve isn't subscribed or has graduated
+    pub async fn price(&self, mint: &str) -> Option<f64> {
+        let curves = self.curves.read().await;
+        curves
+            .get(mint)
+            .filter(|curve| !curve.complete)
+            .map(curve_price)
+    }
+
+    async fn insert(&self, mint: String, curve: BondingCurveAccount) {
+        self.curves.write().await.insert(mint, curve);
+    }
+
+    async fn remove(&self, mint: &str) {
+        self.curves.write().await.remove(mint);
+    }
+
+    async fn clear(&self) {
+        self.curves.write().await.clear();
+    }
+}
+
+/// Which mint each subscription request and live subscription belongs to
+#[derive(Debug, Default)]
+pub struct CurveSubscriptions {
+    next_id: u64,
+    pending: HashMap<u64, String>,
+    active: HashMap<u64, String>,
+}
+
+impl CurveSubscriptions {
+    /// Mints to subscribe, and live subscriptions to drop, to cover exactly `open`
+    pub fn diff(&self, open: &HashSet<String>) -> (Vec<String>, Vec<(u64, String)>) {
+        let covered = self
+            .pending
+            .values()
+            .chain(self.active.values())
+            .collect::<HashSet<_>>();
+        let added = open
+            .iter()
+            .filter(|mint| !covered.contains(mint))
+            .cloned()
+            .collect();
+        let removed = self
+            .active
+            .iter()
+            .filter(|(_, mint)| !open.contains(*mint))
+            .map(|(subscription, mint)| (*subscription, mint.clone()))
+            .collect();
+        (added, removed)
+    }
+
+    /// Id for the next request, remembering which mint a subscribe request was for
+    pub fn request(&mut self, mint: Option<String>) -> u64 {
+        self.next_id += 1;
+        if let Some(mint) = mint {
+            self.pending.insert(self.next_id, mint);
+        }
+        self.next_id
+    }
+
+    /// Records the subscription id the node answered a subscribe request with
+    pub fn confirm(&mut self, request_id: u64, subscription: u64) {
+        if let Some(mint) = self.pending.remove(&request_id) {
+            self.active.insert(subscription, mint);
+        }
+    }
+
+    pub fn mint(&self, subscription: u64) -> Option<&String> {
+        self.active.get(&subscription)
+    }
+
+    pub fn drop_subscription(&mut self, subscription: u64) {
+        self.active.remove(&subscription);
+    }
+}
+
+fn decode_curve(notification: &Value) -> Result<BondingCurveAccount> {
+    let data = notification["params"]["result"]["value"]["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("Account notification without base64 data"))?;
+    let bytes = base64::decode(data).context("Invalid base64 in account notification")?;
+    // Newer curves carry fields past the ones decoded here
+    Ok(BondingCurveAccount::deserialize(&mut bytes.as_slice())?)
+}
+
+/// Keeps one subscription open per open position's bonding curve until the socket drops
+async fn follow_curves(state: &AppState, url: &str) -> Result<()> {
+    let (mut ws, _) = connect_async(url)
+        .await
+        .context("Failed to connect to WebSocket server")?;
+    let mut subscriptions = CurveSubscriptions::default();
+    let mut sync = interval(Duration::from_millis(SYNC_INTERVAL_MS));
+    loop {
+        tokio::select! {
+            _ = sync.tick() => {
+                let open = state
+                    .positions
+                    .open_positions()
+                    .await
+                    .into_iter()
+                    .map(|position| position.mint)
+                    .collect::<HashSet<_>>();
+                let (added, removed) = subscriptions.diff(&open);
+                for mint in added {
+                    let curve = get_pda(&Pubkey::from_str(&mint)?, &PUMP_PROGRAM_ID)?;
+                    let id = subscriptions.request(Some(mint));
+                    let request = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "method": "accountSubscribe",
+                        "params": [
+                            curve.to_string(),
+                            { "encoding": "base64", "commitment": "processed" }
+                        ]
+                    });
+                    ws.send(request.to_string().into()).await?;
+                }
+                for (subscription, mint) in removed {
+                    subscriptions.drop_subscription(subscription);
+                    state.curves.remove(&mint).await;
+                    let request = json!({
+                        "jsonrpc": "2.0",
+                        "id": subscriptions.request(None),
+                        "method": "accountUnsubscribe",
+                        "params": [subscription]
+                    });
+                    ws.send(request.to_string().into()).await?;
+                }
+            }
+            message = ws.next() => {
+                let text = match message {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(anyhow!("WebSocket closed")),
+                };
+                let Ok(json) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                let response = (json["id"].as_u64(), json["result"].as_u64());
+                if let (Some(id), Some(subscription)) = response {
+                    subscriptions.confirm(id, subscription);
+                    continue;
+                }
+                let Some(subscription) = json["params"]["subscription"].as_u64() else {
+                    continue;
+                };
+                let Some(mint) = subscriptions.mint(subscription).cloned() else {
+                    continue;
+                };
+                match decode_curve(&json) {
+                    Ok(curve) => {
+                        // A completed curve means the cached curve route is stale
+                        state.router.observe_curve(&mint, curve.complete).await;
+                        state.curves.insert(mint, curve).await;
+                    }
+                    Err(e) => {
+                        let _ = log_message(&format!("Bad curve update for {}: {}", mint, e)).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Streams the bonding curve of every open position into `state.curves`, reconnecting forever.
+/// The cache is emptied on disconnect so nothing prices off reserves that stopped updating
+pub async fn run_curve_cache(state: AppState, url: String) {
+    loop {
+        if let Err(e) = follow_curves(&state, &url).await {
+            let _ = log_message(&format!("Curve subscriptions dropped: {}", e)).await;
+        }
+        state.curves.clear().await;
+        sleep(Duration::from_millis(RECONNECT_DELAY_MS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriptions() {
+        let open = |mints: &[&str]| mints.iter().map(|m| m.to_string()).collect::<HashSet<_>>();
+        let mut subscriptions = CurveSubscriptions::default();
+        let (added, removed) = subscriptions.diff(&open(&["a"]));
+        assert_eq!(added, vec!["a".to_string()]);
+        assert!(removed.is_empty());
+
+        let id = subscriptions.request(Some("a".to_string()));
+        // Pending requests are not subscribed twice
+        assert!(subscriptions.diff(&open(&["a"])).0.is_empty());
+        subscriptions.confirm(id, 42);
+        assert_eq!(subscriptions.mint(42), Some(&"a".to_string()));
+        assert_eq!(
+            subscriptions.diff(&open(&[])).1,
+            vec![(42, "a".to_string())]
+        );
+
+        let curve = BondingCurveAccount {
+            discriminator: 0,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 0,
+            real_sol_reserves: 0,
+            token_total_supply: 0,
+            complete: false,
+        };
+        assert!((curve_price(&curve) - 30.0 / 1_073_000_000.0).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::native_token::lamports_to_sol;
+use tokio::{io::AsyncWriteExt, net::TcpStream, sync::broadcast::error::RecvError, time::interval};
+
+use crate::{
+    common::utils::AppState,
+    engine::{
+        position::{load_closed_trades, ClosedTrade},
+        signal::TradeSignal,
+    },
+};
+
+// Configuration constants
+const RECENT_SIGNALS: usize = 50;
+const RECENT_LANDINGS: usize = 100;
+const DEFAULT_REFRESH_MS: u64 = 2_000;
+
+pub static ACTIVITY: LazyLock<Activity> = LazyLock::new(Activity::default);
+
+/// A target trade the bot saw, and what it decided to copy
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalEntry {
+    pub at: DateTime<Utc>,
+    pub target: String,
+    pub mint: String,
+    pub side: &'static str,
+    pub target_sol: f64,
+    /// Lamports (buy) or raw tokens (sell) copied; zero when the signal was skipped
+    pub copied: u64,
+    pub slot: u64,
+}
+
+/// How far behind its target one copy landed
+#[derive(Debug, Clone, Serialize)]
+pub struct Landing {
+    pub at: DateTime<Utc>,
+    pub slots: Option<u64>,
+    pub seconds: f64,
+}
+
+/// Recent signals and landings kept in memory for the dashboard
+#[derive(Debug, Default)]
+pub struct Activity {
+    signals: Mutex<VecDeque<SignalEntry>>,
+    landings: Mutex<VecDeque<Landing>>,
+}
+
+fn push_bounded<T>(queue: &Mutex<VecDeque<T>>, item: T, capacity: usize) {
+    let mut queue = queue.lock().unwrap();
+    if queue.len() == capacity {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
+
+impl Activity {
+    pub fn signal(&self, signal: &TradeSignal, target: &str, copied: u64) {
+        let entry = SignalEntry {
+            at: Utc::now(),
+            target: target.to_string(),
+            mint: signal.mint.clone(),
+            side: signal.direction.as_str(),
+            target_sol: lamports_to_sol(signal.sol_amount),
+            copied,
+            slot: signal.slot,
+        };
+        push_bounded(&self.signals, entry, RECENT_SIGNALS);
+    }
+
+    pub fn landed(&self, slots: Option<u64>, seconds: f64) {
+        let landing = Landing {
+            at: Utc::now(),
+            slots,
+            seconds,
+        };
+        push_bounded(&self.landings, landing, RECENT_LANDINGS);
+    }
+
+    /// Newest first
+    pub fn signals(&self) -> Vec<SignalEntry> {
+        self.signals.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Oldest first
+    pub fn landings(&self) -> Vec<Landing> {
+        self.landings.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Realized PnL in SOL after each closed trade, in closing order
+pub fn pnl_curve(trades: &[ClosedTrade]) -> Vec<(DateTime<Utc>, f64)> {
+    let mut trades = trades.iter().collect::<Vec<_>>();
+    trades.sort_by_key(|trade| trade.closed_at);
+    let mut total = 0;
+    trades
+        .into_iter()
+        .map(|trade| {
+            total += trade.pnl();
+            (trade.closed_at, total as f64 / 1e9)
+        })
+        .collect()
+}
+
+/// One dashboard update: open positions priced from the feed, signals, landings and the PnL curve
+async fn frame(state: &AppState, prices: &HashMap<String, f64>) -> Value {
+    let positions = state
+        .positions
+        .open_positions()
+        .await
+        .into_iter()
+        .map(|position| {
+            json!({
+                "mint": position.mint,
+                "target": position.target,
+                "opened_at": position.opened_at,
+                "invested_sol": lamports_to_sol(position.sol_invested),
+                "returned_sol": lamports_to_sol(position.sol_returned),
+                "price": prices.get(&position.mint),
+                "impaired": position.impaired.is_some(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let pnl = load_closed_trades()
+        .map(|trades| pnl_curve(&trades))
+        .unwrap_or_default();
+    json!({
+        "at": Utc::now(),
+        "paused": state.pause.is_paused(),
+        "positions": positions,
+        "signals": ACTIVITY.signals(),
+        "landings": ACTIVITY.landings(),
+        "pnl": pnl,
+    })
+}
+
+/// Streams dashboard frames to `stream` as server-sent events every `DASHBOARD_REFRESH_MS`
+/// until the client goes away, keeping open positions on the price feed meanwhile
+pub async fn stream_events(stream: &mut TcpStream, state: &AppState) -> Result<()> {
+    let refresh_ms = env::var("DASHBOARD_REFRESH_MS")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_REFRESH_MS)
+        .max(100);
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )
+        .await?;
+    let mut updates = state.price_feed.subscribe();
+    let mut ticks = interval(Duration::from_millis(refresh_ms));
+    let mut prices = HashMap::new();
+    let mut watched = HashSet::new();
+    let result = loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    prices.insert(update.mint, update.price);
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break Ok(()),
+            },
+            _ = ticks.tick() => {
+                let open = state
+                    .positions
+                    .open_positions()
+                    .await
+                    .into_iter()
+                    .map(|position| position.mint)
+                    .collect::<HashSet<_>>();
+                for mint in open.difference(&watched) {
+                    state.price_feed.watch(mint).await;
+                }
+                for mint in watched.difference(&open) {
+                    state.price_feed.unwatch(mint).await;
+                }
+                prices.retain(|mint, _| open.contains(mint));
+                watched = open;
+                let event = format!("data: {}\n\n", frame(state, &prices).await);
+                if let Err(e) = stream.write_all(event.as_bytes()).await {
+                    break Err(e.into());
+                }
+            }
+        }
+    };
+    for mint in &watched {
+        state.price_feed.unwatch(mint).await;
+    }
+    result
+}
+
+/// Single-page dashboard; it authenticates its event stream with the `key` it was opened with
+pub const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Copy bot</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+td, th { padding: 4px 12px; border-bottom: 1px solid #ddd; text-align: left; }
+svg { border: 1px solid #ddd; margin-bottom: 2em; }
+</style>
+</head>
+<body>
+<h1>Copy bot <small id="status"></small></h1>
+<h2>Cumulative PnL (SOL)</h2>
+<svg id="pnl" width="800" height="200"></svg>
+<h2>Landing latency (slots behind target)</h2>
+<svg id="latency" width="800" height="120"></svg>
+<h2>Open positions</h2>
+<table id="positions"></table>
+<h2>Recent signals</h2>
+<table id="signals"></table>
+<script>
+function line(svg, values) {
+  const el = document.getElementById(svg);
+  if (values.length < 2) { el.innerHTML = ""; return; }
+  const w = el.width.baseVal.value, h = el.height.baseVal.value;
+  const lo = Math.min(0, ...values), hi = Math.max(0, ...values), span = (hi - lo) || 1;
+  const points = values.map((v, i) =>
+    (i * w / (values.length - 1)) + "," + (h - (v - lo) * h / span)).join(" ");
+  el.innerHTML = '<line x1="0" x2="' + w + '" y1="' + (h + lo * h / span) + '" y2="'
+    + (h + lo * h / span) + '" stroke="#ccc"/><polyline fill="none" stroke="#36c" points="'
+    + points + '"/>';
+}
+function table(id, head, rows) {
+  document.getElementById(id).innerHTML = "<tr>" + head.map(h => "<th>" + h + "</th>").join("")
+    + "</tr>" + rows.map(r => "<tr>" + r.map(c => "<td>" + (c ?? "") + "</td>").join("")
+    + "</tr>").join("");
+}
+const events = new EventSource("/events" + location.search);
+events.onmessage = (message) => {
+  const frame = JSON.parse(message.data);
+  const pnl = frame.pnl.map(p => p[1]);
+  document.getElementById("status").textContent = (frame.paused ? "paused" : "copying")
+    + " | realized " + (pnl.length ? pnl[pnl.length - 1].toFixed(4) : "0") + " SOL";
+  line("pnl", pnl);
+  line("latency", frame.landings.filter(l => l.slots !== null).map(l => l.slots));
+  table("positions", ["Mint", "Target", "Opened", "In (SOL)", "Out (SOL)", "Price", ""],
+    frame.positions.map(p => [p.mint, p.target, p.opened_at, p.invested_sol.toFixed(4),
+      p.returned_sol.toFixed(4), p.price && p.price.toPrecision(6), p.impaired ? "impaired" : ""]));
+  table("signals", ["At", "Target", "Side", "Mint", "Target SOL", "Copied", "Slot"],
+    frame.signals.map(s => [s.at, s.target, s.side, s.mint, s.target_sol.toFixed(4),
+      s.copied || "skipped", s.slot]));
+};
+events.onerror = () => { document.getElementById("status").textContent = "disconnected"; };
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::EventTime;
+
+    #[test]
+    fn test_pnl_curve_accumulates_in_closing_order() {
+        let trade = |closed_secs: i64, invested: u64, returned: u64| ClosedTrade {
+            mint: "m".to_string(),
+            opened_at: DateTime::from_timestamp(0, 0).unwrap(),
+            closed_at: DateTime::from_timestamp(closed_secs, 0).unwrap(),
+            sol_invested: invested,
+            sol_returned: returned,
+            fees_paid: 0,
+            target: None,
+            time: EventTime::default(),
+        };
+        let curve = pnl_curve(&[
+            trade(20, 1_000_000_000, 500_000_000),
+            trade(10, 1_000_000_000, 3_000_000_000),
+        ]);
+        let totals = curve.iter().map(|(_, pnl)| *pnl).collect::<Vec<_>>();
+        assert_eq!(totals, vec![2.0, 1.5]);
+    }
+}
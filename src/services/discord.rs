@@ -0,0 +1,138 @@
+use std::env;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    engine::position::FillSide,
+    services::notify::{solscan_tx, Event, Notification},
+};
+
+// Configuration constants
+const BUY_COLOR: u32 = 0x2ecc71;
+const SELL_COLOR: u32 = 0xe74c3c;
+const INFO_COLOR: u32 = 0x95a5a6;
+
+/// Posts notifications to a Discord channel through an incoming webhook
+pub struct DiscordNotification {
+    pub webhook_url: String,
+    http: reqwest::Client,
+}
+
+impl DiscordNotification {
+    /// Reads `DISCORD_WEBHOOK_URL`; unset disables the sink
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            webhook_url: env::var("DISCORD_WEBHOOK_URL").ok()?,
+            http: reqwest::Client::new(),
+        })
+    }
+}
+
+fn field(name: &str, value: String) -> Value {
+    json!({ "name": name, "value": value, "inline": true })
+}
+
+/// Webhook body for an event: trades and closed positions get fields, everything else the
+/// plain text Telegram gets
+pub fn payload(event: &Event) -> Value {
+    let embed = match event {
+        Event::Trade {
+            side,
+            mint,
+            lamports,
+            tokens,
+            price,
+            signature,
+            slots_behind,
+        } => {
+            let (title, color) = match side {
+                FillSide::Buy => ("Bought", BUY_COLOR),
+                FillSide::Sell => ("Sold", SELL_COLOR),
+            };
+            let mut fields = vec![
+                field("Mint", format!("`{}`", mint)),
+                field("SOL", format!("{:.4}", *lamports as f64 / 1e9)),
+                field("Tokens", tokens.to_string()),
+                field("Price", format!("{:.10} SOL", price)),
+            ];
+            if let Some(slots) = slots_behind {
+                fields.push(field("Slots behind", slots.to_string()));
+            }
+            json!({
+                "title": title,
+                "url": solscan_tx(signature),
+                "color": color,
+                "fields": fields,
+            })
+        }
+        Event::PositionClosed {
+            mint,
+            invested_lamports,
+            pnl_lamports,
+        } => {
+            let color = if *pnl_lamports >= 0 {
+                BUY_COLOR
+            } else {
+                SELL_COLOR
+            };
+            let pct = if *invested_lamports > 0 {
+                *pnl_lamports as f64 / *invested_lamports as f64 * 100.0
+            } else {
+                0.0
+            };
+            json!({
+                "title": "Position closed",
+                "color": color,
+                "fields": [
+                    field("Mint", format!("`{}`", mint)),
+                    field("Invested", format!("{:.4} SOL", *invested_lamports as f64 / 1e9)),
+                    field("PnL", format!("{:+.4} SOL ({:+.1}%)", *pnl_lamports as f64 / 1e9, pct)),
+                ],
+            })
+        }
+        _ => json!({ "description": event.text(), "color": INFO_COLOR }),
+    };
+    json!({ "embeds": [embed] })
+}
+
+#[async_trait]
+impl Notification for DiscordNotification {
+    async fn send(&self, event: &Event) -> Result<()> {
+        self.http
+            .post(&self.webhook_url)
+            .json(&payload(event))
+            .send()
+            .await?
+            .error_for_status()
+            .context("Discord webhook failed")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_embed_links_transaction() {
+        let body = payload(&Event::Trade {
+            side: FillSide::Sell,
+            mint: "mint".to_string(),
+            lamports: 1_500_000_000,
+            tokens: 42,
+            price: 0.01,
+            signature: "sig".to_string(),
+            slots_behind: Some(3),
+        });
+        let embed = &body["embeds"][0];
+        assert_eq!(embed["url"], "https://solscan.io/tx/sig");
+        assert_eq!(embed["color"], SELL_COLOR);
+        assert_eq!(embed["fields"][1]["value"], "1.5000");
+        assert_eq!(embed["fields"][4]["value"], "3");
+
+        let body = payload(&Event::Info("hello".to_string()));
+        assert_eq!(body["embeds"][0]["description"], "hello");
+    }
+}
@@ -0,0 +1,324 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::services::metrics::NAMESPACE;
+
+// Configuration constants
+const DASHBOARD_UID: &str = "copybot";
+const PANEL_WIDTH: u64 = 12;
+const PANEL_HEIGHT: u64 = 8;
+const SWAP_FAILURE_RATIO: f64 = 0.5;
+const COPY_LATENCY_P95_SECS: f64 = 5.0;
+const JITO_LAND_RATIO: f64 = 0.3;
+const RPC_ERRORS_PER_SEC: f64 = 1.0;
+
+/// Fully qualified name of one of the metrics the bot exports
+fn metric(name: &str) -> String {
+    format!("{}_{}", NAMESPACE, name)
+}
+
+fn rate(name: &str, by: &str, window: &str) -> String {
+    let by = if by.is_empty() {
+        String::new()
+    } else {
+        format!(" by ({})", by)
+    };
+    format!("sum{}(rate({}[{}]))", by, metric(name), window)
+}
+
+fn quantile(q: f64, histogram: &str) -> String {
+    format!(
+        "histogram_quantile({}, sum by (le) (rate({}_bucket[5m])))",
+        q,
+        metric(histogram)
+    )
+}
+
+/// One dashboard panel: a title, a Grafana unit and `(expression, legend)` queries
+struct Panel {
+    title: &'static str,
+    unit: &'static str,
+    queries: Vec<(String, &'static str)>,
+}
+
+fn panels() -> Vec<Panel> {
+    vec![
+        Panel {
+            title: "Swaps per second",
+            unit: "ops",
+            queries: vec![
+                (
+                    rate("swaps_succeeded_total", "venue", "5m"),
+                    "{{venue}} landed",
+                ),
+                (
+                    rate("swaps_failed_total", "venue", "5m"),
+                    "{{venue}} failed",
+                ),
+            ],
+        },
+        Panel {
+            title: "Swap success ratio",
+            unit: "percentunit",
+            queries: vec![(
+                format!(
+                    "{} / {}",
+                    rate("swaps_succeeded_total", "venue", "15m"),
+                    rate("swaps_attempted_total", "venue", "15m")
+                ),
+                "{{venue}}",
+            )],
+        },
+        Panel {
+            title: "Copy latency",
+            unit: "s",
+            queries: vec![
+                (quantile(0.5, "copy_latency_seconds"), "p50"),
+                (quantile(0.95, "copy_latency_seconds"), "p95"),
+            ],
+        },
+        Panel {
+            title: "Copy latency (slots)",
+            unit: "none",
+            queries: vec![
+                (quantile(0.5, "copy_latency_slots"), "p50"),
+                (quantile(0.95, "copy_latency_slots"), "p95"),
+            ],
+        },
+        Panel {
+            title: "Jito bundle land rate",
+            unit: "percentunit",
+            queries: vec![(
+                format!(
+                    "{} / {}",
+                    rate("jito_bundles_landed_total", "", "15m"),
+                    rate("jito_bundles_sent_total", "", "15m")
+                ),
+                "landed",
+            )],
+        },
+        Panel {
+            title: "RPC errors",
+            unit: "ops",
+            queries: vec![(rate("rpc_errors_total", "operation", "5m"), "{{operation}}")],
+        },
+        Panel {
+            title: "Realized PnL",
+            unit: "SOL",
+            queries: vec![(format!("{} / 1e9", metric("realized_pnl_lamports")), "net")],
+        },
+        Panel {
+            title: "Fees and closes per hour",
+            unit: "none",
+            queries: vec![
+                (
+                    format!(
+                        "increase({}[1h]) / 1e9",
+                        metric("priority_fees_lamports_total")
+                    ),
+                    "priority fees (SOL)",
+                ),
+                (
+                    format!("increase({}[1h])", metric("positions_closed_total")),
+                    "positions closed",
+                ),
+            ],
+        },
+        Panel {
+            title: "Cache hit ratio",
+            unit: "percentunit",
+            queries: vec![(
+                format!(
+                    "{} / ({} + {})",
+                    rate("cache_hits_total", "cache", "5m"),
+                    rate("cache_hits_total", "cache", "5m"),
+                    rate("cache_misses_total", "cache", "5m")
+                ),
+                "{{cache}}",
+            )],
+        },
+        Panel {
+            title: "Cache entries",
+            unit: "none",
+            queries: vec![(
+                format!("sum by (cache) ({})", metric("cache_entries")),
+                "{{cache}}",
+            )],
+        },
+    ]
+}
+
+/// Grafana dashboard over every metric the bot exports, with the Prometheus datasource left
+/// as a variable picked on import
+pub fn dashboard() -> Value {
+    let panels = panels()
+        .into_iter()
+        .enumerate()
+        .map(|(i, panel)| {
+            let targets = panel
+                .queries
+                .into_iter()
+                .enumerate()
+                .map(|(j, (expr, legend))| {
+                    json!({
+                        "refId": ((b'A' + j as u8) as char).to_string(),
+                        "expr": expr,
+                        "legendFormat": legend,
+                        "datasource": { "type": "prometheus", "uid": "${datasource}" }
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "id": i + 1,
+                "type": "timeseries",
+                "title": panel.title,
+                "datasource": { "type": "prometheus", "uid": "${datasource}" },
+                "gridPos": {
+                    "x": (i as u64 % 2) * PANEL_WIDTH,
+                    "y": (i as u64 / 2) * PANEL_HEIGHT,
+                    "w": PANEL_WIDTH,
+                    "h": PANEL_HEIGHT
+                },
+                "fieldConfig": { "defaults": { "unit": panel.unit }, "overrides": [] },
+                "targets": targets
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "uid": DASHBOARD_UID,
+        "title": "Copy-trading bot",
+        "tags": [NAMESPACE],
+        "timezone": "utc",
+        "schemaVersion": 39,
+        "refresh": "30s",
+        "time": { "from": "now-6h", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "datasource",
+                "label": "Prometheus",
+                "type": "datasource",
+                "query": "prometheus"
+            }]
+        },
+        "panels": panels
+    })
+}
+
+fn alert(name: &str, expr: String, duration: &str, summary: String) -> Value {
+    json!({
+        "alert": name,
+        "expr": expr,
+        "for": duration,
+        "labels": { "severity": "warning" },
+        "annotations": { "summary": summary }
+    })
+}
+
+/// Prometheus alert rules over the same metrics; `job` is the scrape job polling the bot
+pub fn alert_rules(job: &str) -> Value {
+    let rules = vec![
+        json!({
+            "alert": "CopyBotDown",
+            "expr": format!("up{{job=\"{}\"}} == 0", job),
+            "for": "2m",
+            "labels": { "severity": "critical" },
+            "annotations": { "summary": "The copy-trading bot's metrics endpoint is unreachable" }
+        }),
+        alert(
+            "CopyBotSwapFailures",
+            format!(
+                "{} / {} > {}",
+                rate("swaps_failed_total", "venue", "15m"),
+                rate("swaps_attempted_total", "venue", "15m"),
+                SWAP_FAILURE_RATIO
+            ),
+            "10m",
+            format!(
+                "More than {:.0}% of swaps on {{{{ $labels.venue }}}} are failing",
+                SWAP_FAILURE_RATIO * 100.0
+            ),
+        ),
+        alert(
+            "CopyBotSlowCopies",
+            format!(
+                "{} > {}",
+                quantile(0.95, "copy_latency_seconds"),
+                COPY_LATENCY_P95_SECS
+            ),
+            "10m",
+            format!("p95 copy latency is above {}s", COPY_LATENCY_P95_SECS),
+        ),
+        alert(
+            "CopyBotJitoNotLanding",
+            format!(
+                "{} / {} < {}",
+                rate("jito_bundles_landed_total", "", "15m"),
+                rate("jito_bundles_sent_total", "", "15m"),
+                JITO_LAND_RATIO
+            ),
+            "15m",
+            format!(
+                "Fewer than {:.0}% of Jito bundles are landing",
+                JITO_LAND_RATIO * 100.0
+            ),
+        ),
+        alert(
+            "CopyBotRpcErrors",
+            format!(
+                "{} > {}",
+                rate("rpc_errors_total", "operation", "5m"),
+                RPC_ERRORS_PER_SEC
+            ),
+            "5m",
+            "RPC {{ $labels.operation }} calls are failing repeatedly".to_string(),
+        ),
+    ];
+    json!({ "groups": [{ "name": NAMESPACE, "rules": rules }] })
+}
+
+/// Writes `grafana-dashboard.json` and `alert-rules.yml` into `dir`. The rules are JSON,
+/// which Prometheus reads as YAML
+pub fn write_monitoring(dir: &Path, job: &str) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    fs::write(
+        dir.join("grafana-dashboard.json"),
+        serde_json::to_vec_pretty(&dashboard())?,
+    )?;
+    fs::write(
+        dir.join("alert-rules.yml"),
+        serde_json::to_vec_pretty(&alert_rules(job))?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::metrics::METRICS;
+    use regex::Regex;
+
+    #[test]
+    fn test_queries_use_exported_metrics() {
+        METRICS.observe_swap::<(), ()>("pump", &Ok(()));
+        METRICS.rpc_error("getAccountInfo");
+        METRICS.cache_hits.with_label_values(&["test"]).inc();
+        METRICS.cache_misses.with_label_values(&["test"]).inc();
+        METRICS.cache_entries.with_label_values(&["test"]).set(1);
+        let exported = METRICS.render().unwrap();
+
+        let name = Regex::new(&format!(r"{}_[a-z_]+", NAMESPACE)).unwrap();
+        let text = format!("{}{}", dashboard(), alert_rules("copybot"));
+        let mut checked = 0;
+        for found in name.find_iter(&text) {
+            assert!(
+                exported.contains(found.as_str()),
+                "{} is not exported",
+                found.as_str()
+            );
+            checked += 1;
+        }
+        assert!(checked > 10);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        LazyLock, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use tokio::{
+    sync::Notify,
+    time::{sleep, Instant},
+};
+
+use crate::common::utils::{log_message, AppState};
+
+// Configuration constants
+const DEFAULT_SLOWDOWN: u32 = 4;
+const CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Whether the bot is idling, shared by every polling loop
+pub static IDLE: LazyLock<IdleMode> = LazyLock::new(IdleMode::default);
+
+#[derive(Debug, Clone)]
+pub struct IdleConfig {
+    /// Time without signals or open positions before polling slows down
+    pub after: Duration,
+    /// How many times longer polling loops sleep while idle
+    pub slowdown: u32,
+}
+
+impl IdleConfig {
+    /// Reads `IDLE_AFTER_SECS` (unset disables) and `IDLE_SLOWDOWN`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(secs) = env::var("IDLE_AFTER_SECS") else {
+            return Ok(None);
+        };
+        let after = u64::from_str(&secs).context("IDLE_AFTER_SECS must be a number of seconds")?;
+        let slowdown = match env::var("IDLE_SLOWDOWN") {
+            Ok(factor) => u32::from_str(&factor)
+                .ok()
+                .filter(|factor| *factor >= 1)
+                .context("IDLE_SLOWDOWN must be a whole factor of at least 1")?,
+            Err(_) => DEFAULT_SLOWDOWN,
+        };
+        Ok(Some(Self {
+            after: Duration::from_secs(after),
+            slowdown,
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct IdleMode {
+    idle: AtomicBool,
+    slowdown: AtomicU32,
+    last_signal: Mutex<Instant>,
+    wake: Notify,
+}
+
+impl Default for IdleMode {
+    fn default() -> Self {
+        Self {
+            idle: AtomicBool::new(false),
+            slowdown: AtomicU32::new(DEFAULT_SLOWDOWN),
+            last_signal: Mutex::new(Instant::now()),
+            wake: Notify::new(),
+        }
+    }
+}
+
+impl IdleMode {
+    pub fn is_idle(&self) -> bool {
+        self.idle.load(Ordering::Relaxed)
+    }
+
+    /// Marks activity; an idling bot springs back to full speed and wakes every sleeping loop
+    pub fn touch(&self) {
+        *self.last_signal.lock().unwrap() = Instant::now();
+        if self.idle.swap(false, Ordering::Relaxed) {
+            self.wake.notify_waiters();
+        }
+    }
+
+    /// How long a loop polling every `interval` waits right now
+    pub fn pace(&self, interval: Duration) -> Duration {
+        if self.is_idle() {
+            interval * self.slowdown.load(Ordering::Relaxed)
+        } else {
+            interval
+        }
+    }
+
+    /// Starts idling if nothing happened for `config.after`; true when it just started
+    fn settle(&self, config: &IdleConfig, positions_open: bool) -> bool {
+        if positions_open {
+            self.touch();
+            return false;
+        }
+        if self.is_idle() || self.last_signal.lock().unwrap().elapsed() < config.after {
+            return false;
+        }
+        self.slowdown.store(config.slowdown, Ordering::Relaxed);
+        !self.idle.swap(true, Ordering::Relaxed)
+    }
+}
+
+/// Sleeps `interval`, stretched while idle; returns early once activity resumes
+pub async fn idle_sleep(interval: Duration) {
+    if !IDLE.is_idle() {
+        return sleep(interval).await;
+    }
+    tokio::select! {
+        _ = sleep(IDLE.pace(interval)) => {}
+        _ = IDLE.wake.notified() => {}
+    }
+}
+
+/// Switches the bot into idle mode once there are no open positions and no signals for a
+/// while, forever. Idling stretches polling and empties lookup caches; curve and supply
+/// subscriptions follow open positions and so are already gone
+pub async fn run_idle_monitor(config: IdleConfig, state: AppState) {
+    let mut was_idle = false;
+    loop {
+        sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        let positions_open = !state.positions.open_positions().await.is_empty();
+        if IDLE.settle(&config, positions_open) {
+            state.router.clear_cache().await;
+            state.price_feed.clear_cache().await;
+            state.prices.clear_cache().await;
+            state.filters.clear_cache().await;
+            state.maturity.clear_cache().await;
+            let _ = log_message(&format!(
+                "Idle for {}s, polling {}x slower",
+                config.after.as_secs(),
+                config.slowdown
+            ))
+            .await;
+        } else if was_idle && !IDLE.is_idle() {
+            let _ = log_message("Activity resumed, back to full speed").await;
+        }
+        was_idle = IDLE.is_idle();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_transitions() {
+        let config = IdleConfig {
+            after: Duration::ZERO,
+            slowdown: 3,
+        };
+        let mode = IdleMode::default();
+        // Open positions keep the bot awake
+        assert!(!mode.settle(&config, true));
+        assert!(mode.settle(&config, false));
+        assert!(!mode.settle(&config, false));
+        assert_eq!(mode.pace(Duration::from_secs(2)), Duration::from_secs(6));
+        mode.touch();
+        assert!(!mode.is_idle());
+        assert_eq!(mode.pace(Duration::from_secs(2)), Duration::from_secs(2));
+    }
+}
@@ -1,17 +1,52 @@
-use std::{future::Future, str::FromStr, sync::LazyLock, time::Duration};
+use std::{
+    env,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
 
 use anyhow::{anyhow, Result};
+use futures_util::{
+    future::join_all,
+    stream::{FuturesUnordered, StreamExt},
+};
 use indicatif::{ProgressBar, ProgressStyle};
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
 use rand::{seq::IteratorRandom, thread_rng};
 use serde::Deserialize;
-use serde_json::Value;
-use solana_sdk::pubkey::Pubkey;
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
 use tokio::{
     sync::RwLock,
     time::{sleep, Instant},
 };
 
-use crate::common::utils::{import_env_var, log_message};
+use crate::{
+    common::utils::{import_env_var, log_message},
+    core::rpc_pool::EndpointHealth,
+};
+
+// Configuration constants
+const BUNDLE_POLL_MS: u64 = 500;
+// A bundle the engine doesn't know yet this soon after sending is still on its way in
+const DROP_GRACE_MS: u64 = 2_000;
+
+/// Jito's public block engines by region
+const REGIONS: &[(&str, &str)] = &[
+    ("ny", "https://ny.mainnet.block-engine.jito.wtf"),
+    (
+        "amsterdam",
+        "https://amsterdam.mainnet.block-engine.jito.wtf",
+    ),
+    (
+        "frankfurt",
+        "https://frankfurt.mainnet.block-engine.jito.wtf",
+    ),
+    ("london", "https://london.mainnet.block-engine.jito.wtf"),
+    ("tokyo", "https://tokyo.mainnet.block-engine.jito.wtf"),
+    ("slc", "https://slc.mainnet.block-engine.jito.wtf"),
+];
 
 pub static BLOCK_ENGINE_URL: LazyLock<String> =
     LazyLock::new(|| import_env_var("JITO_BLOCK_ENGINE_URL"));
@@ -22,6 +57,9 @@ pub static TIP_PERCENTILE: LazyLock<String> =
 
 pub static TIP_ACCOUNTS: LazyLock<RwLock<Vec<String>>> = LazyLock::new(|| RwLock::new(vec![]));
 
+/// Block engines bundles are sent to
+pub static BLOCK_ENGINES: LazyLock<BlockEngines> = LazyLock::new(BlockEngines::from_env);
+
 #[derive(Debug)]
 pub struct TipAccountResult {
     pub accounts: Vec<String>,
@@ -51,3 +89,400 @@ pub fn new_progress_bar() -> ProgressBar {
     progress_bar.enable_steady_tick(Duration::from_millis(100));
     progress_bar
 }
+
+/// Where a sent bundle ended up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleOutcome {
+    /// Confirmed on chain
+    Landed { slot: u64 },
+    /// The engine gave up on it, or a transaction in it failed
+    Failed(String),
+    /// No longer tracked without having landed, e.g. it lost the auction or expired
+    Dropped,
+    /// Still in flight
+    Pending,
+}
+
+/// Block engine URL of a region name (`ams` for short) or of a URL given as is
+pub fn region_url(region: &str) -> Result<String> {
+    let region = region.trim();
+    if region.starts_with("http") {
+        return Ok(region.trim_end_matches('/').to_string());
+    }
+    let name = match region.to_lowercase().as_str() {
+        "ams" => "amsterdam".to_string(),
+        name => name.to_string(),
+    };
+    REGIONS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, url)| url.to_string())
+        .ok_or_else(|| {
+            anyhow!(
+                "Unknown Jito region '{}'. Use {} or a block engine URL",
+                region,
+                REGIONS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// One region's block engine
+pub struct BlockEngine {
+    pub region: String,
+    url: String,
+    client: Arc<JitoRpcClient>,
+    health: Mutex<EndpointHealth>,
+}
+
+impl BlockEngine {
+    fn new(region: String, url: String) -> Self {
+        Self {
+            client: Arc::new(JitoRpcClient::new(format!("{}/api/v1/bundles", url))),
+            region,
+            url,
+            health: Mutex::new(EndpointHealth::default()),
+        }
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.health.lock().unwrap().is_healthy(now)
+    }
+
+    async fn call(&self, http: &reqwest::Client, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = http
+            .post(format!("{}/api/v1/bundles", self.url))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed on {}: {}", method, self.region, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Where `bundle_id` is now. In-flight status says whether it landed at all; a landed
+    /// bundle is `Pending` until its slot is confirmed
+    pub async fn bundle_outcome(
+        &self,
+        http: &reqwest::Client,
+        bundle_id: &str,
+    ) -> Result<BundleOutcome> {
+        let params = json!([[bundle_id]]);
+        let in_flight = self
+            .call(http, "getInflightBundleStatuses", params.clone())
+            .await?;
+        match in_flight_outcome(&in_flight["value"][0]) {
+            BundleOutcome::Landed { .. } => {
+                let statuses = self.call(http, "getBundleStatuses", params).await?;
+                Ok(landed_outcome(&statuses["value"][0]))
+            }
+            outcome => Ok(outcome),
+        }
+    }
+}
+
+/// Outcome from a `getInflightBundleStatuses` entry
+fn in_flight_outcome(status: &Value) -> BundleOutcome {
+    match status["status"].as_str() {
+        Some("Landed") => BundleOutcome::Landed {
+            slot: status["landed_slot"].as_u64().unwrap_or_default(),
+        },
+        Some("Failed") => BundleOutcome::Failed("not accepted by any leader".to_string()),
+        // Unknown to the engine, which remembers bundles for five minutes
+        Some("Invalid") => BundleOutcome::Dropped,
+        _ => BundleOutcome::Pending,
+    }
+}
+
+/// Outcome from a `getBundleStatuses` entry, which only lists bundles that landed
+fn landed_outcome(status: &Value) -> BundleOutcome {
+    if status.is_null() {
+        return BundleOutcome::Pending;
+    }
+    let err = &status["err"];
+    if !err.is_null() && err.get("Ok").is_none() {
+        return BundleOutcome::Failed(err.to_string());
+    }
+    match status["confirmation_status"].as_str() {
+        Some("confirmed" | "finalized") => BundleOutcome::Landed {
+            slot: status["slot"].as_u64().unwrap_or_default(),
+        },
+        _ => BundleOutcome::Pending,
+    }
+}
+
+/// What a bundle sent to several regions came to, given the outcome in one more region: it
+/// landed if any region landed it, and can't land any more only once no region has it pending
+fn merge_outcomes(outcome: Option<BundleOutcome>, next: BundleOutcome) -> BundleOutcome {
+    match (outcome, next) {
+        (_, BundleOutcome::Landed { slot }) | (Some(BundleOutcome::Landed { slot }), _) => {
+            BundleOutcome::Landed { slot }
+        }
+        (Some(BundleOutcome::Pending), _) | (_, BundleOutcome::Pending) => BundleOutcome::Pending,
+        (Some(BundleOutcome::Failed(reason)), _) | (_, BundleOutcome::Failed(reason)) => {
+            BundleOutcome::Failed(reason)
+        }
+        _ => BundleOutcome::Dropped,
+    }
+}
+
+/// The block engines of `JITO_REGIONS` in preference order, or just `JITO_BLOCK_ENGINE_URL`.
+/// Bundles go to the first healthy one and fail over down the list, or with `JITO_FANOUT` to
+/// every healthy one at once; a region that keeps failing sits out for a while, like an RPC
+/// endpoint
+pub struct BlockEngines {
+    engines: Vec<BlockEngine>,
+    /// Send each bundle to every healthy region rather than the first
+    fan_out: bool,
+    http: reqwest::Client,
+}
+
+impl BlockEngines {
+    pub fn new(regions: Vec<(String, String)>, fan_out: bool) -> Self {
+        Self {
+            engines: regions
+                .into_iter()
+                .map(|(region, url)| BlockEngine::new(region, url))
+                .collect(),
+            fan_out,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Unknown regions are skipped here; config validation reports them at startup
+    pub fn from_env() -> Self {
+        let regions = env::var("JITO_REGIONS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|region| !region.is_empty())
+            .filter_map(|region| Some((region.to_string(), region_url(region).ok()?)))
+            .collect::<Vec<_>>();
+        let fan_out = env::var("JITO_FANOUT")
+            .ok()
+            .and_then(|v| bool::from_str(&v).ok())
+            .unwrap_or(false);
+        if regions.is_empty() {
+            let url = BLOCK_ENGINE_URL.trim_end_matches('/').to_string();
+            return Self::new(vec![("default".to_string(), url)], fan_out);
+        }
+        Self::new(regions, fan_out)
+    }
+
+    /// Client of the region bundles currently go to first
+    pub fn client(&self) -> Arc<JitoRpcClient> {
+        self.order(None)[0].client.clone()
+    }
+
+    /// Healthy engines first, `preferred`'s ahead of the others, otherwise in configured order
+    fn order(&self, preferred: Option<&Arc<JitoRpcClient>>) -> Vec<&BlockEngine> {
+        let now = Instant::now();
+        let mut order = self.engines.iter().collect::<Vec<_>>();
+        order.sort_by_key(|engine| {
+            let is_preferred = preferred.is_some_and(|client| Arc::ptr_eq(&engine.client, client));
+            (!engine.is_healthy(now), !is_preferred)
+        });
+        order
+    }
+
+    /// Sends `bundle` to `preferred`'s region, failing over to the others until one accepts it,
+    /// or to every healthy region at once when fanning out. Returns each region that took it
+    pub async fn send_bundle(
+        &self,
+        preferred: &Arc<JitoRpcClient>,
+        bundle: &[VersionedTransaction],
+    ) -> Result<Vec<(&BlockEngine, String)>> {
+        let order = self.order(Some(preferred));
+        if self.fan_out {
+            let now = Instant::now();
+            let mut targets = order
+                .iter()
+                .copied()
+                .filter(|engine| engine.is_healthy(now))
+                .collect::<Vec<_>>();
+            // With every region out of rotation, all of them are tried anyway
+            if targets.is_empty() {
+                targets = order;
+            }
+            let sent = join_all(
+                targets
+                    .into_iter()
+                    .map(|engine| async move { (engine, self.send_to(engine, bundle).await) }),
+            )
+            .await;
+            let mut accepted = Vec::new();
+            let mut last_error = None;
+            for (engine, result) in sent {
+                match result {
+                    Ok(bundle_id) => accepted.push((engine, bundle_id)),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            if accepted.is_empty() {
+                return Err(
+                    last_error.unwrap_or_else(|| anyhow!("No Jito block engine configured"))
+                );
+            }
+            return Ok(accepted);
+        }
+        let mut last_error = None;
+        for engine in order {
+            match self.send_to(engine, bundle).await {
+                Ok(bundle_id) => return Ok(vec![(engine, bundle_id)]),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No Jito block engine configured")))
+    }
+
+    async fn send_to(
+        &self,
+        engine: &BlockEngine,
+        bundle: &[VersionedTransaction],
+    ) -> Result<String> {
+        let started = Instant::now();
+        match engine.client.send_bundle(bundle).await {
+            Ok(bundle_id) => {
+                engine
+                    .health
+                    .lock()
+                    .unwrap()
+                    .record_success(started.elapsed());
+                Ok(bundle_id)
+            }
+            Err(e) => {
+                let benched = engine.health.lock().unwrap().record_failure(Instant::now());
+                let _ = log_message(&format!(
+                    "Jito {} refused the bundle: {}{}",
+                    engine.region,
+                    e,
+                    if benched {
+                        ", taking it out of rotation"
+                    } else {
+                        ""
+                    }
+                ))
+                .await;
+                Err(anyhow!("Jito {}: {}", engine.region, e))
+            }
+        }
+    }
+
+    /// Polls every region a bundle was sent to until one lands it or none can any more.
+    /// `Pending` once `limit` passes
+    pub async fn wait_for_bundle(
+        &self,
+        sent: &[(&BlockEngine, String)],
+        limit: Duration,
+    ) -> BundleOutcome {
+        let mut polls = sent
+            .iter()
+            .map(|(engine, bundle_id)| self.poll_bundle(engine, bundle_id, limit))
+            .collect::<FuturesUnordered<_>>();
+        let mut outcome = None;
+        while let Some(next) = polls.next().await {
+            let merged = merge_outcomes(outcome, next);
+            if matches!(merged, BundleOutcome::Landed { .. }) {
+                return merged;
+            }
+            outcome = Some(merged);
+        }
+        outcome.unwrap_or(BundleOutcome::Dropped)
+    }
+
+    /// Polls `bundle_id` on one engine that took it until it lands, fails or is dropped
+    async fn poll_bundle(
+        &self,
+        engine: &BlockEngine,
+        bundle_id: &str,
+        limit: Duration,
+    ) -> BundleOutcome {
+        let started = Instant::now();
+        loop {
+            match engine.bundle_outcome(&self.http, bundle_id).await {
+                Ok(BundleOutcome::Pending) => {}
+                Ok(BundleOutcome::Dropped)
+                    if started.elapsed() < Duration::from_millis(DROP_GRACE_MS) => {}
+                Ok(outcome) => return outcome,
+                // The bundle may still land; keep asking
+                Err(e) => {
+                    let _ = log_message(&format!("Bundle status poll failed: {}", e)).await;
+                }
+            }
+            if started.elapsed() >= limit {
+                return BundleOutcome::Pending;
+            }
+            sleep(Duration::from_millis(BUNDLE_POLL_MS)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_outcomes_and_regions() {
+        let in_flight = |status: Value| in_flight_outcome(&status);
+        assert_eq!(
+            in_flight(json!({ "status": "Landed", "landed_slot": 9 })),
+            BundleOutcome::Landed { slot: 9 }
+        );
+        assert_eq!(
+            in_flight(json!({ "status": "Invalid" })),
+            BundleOutcome::Dropped
+        );
+        assert_eq!(
+            in_flight(json!({ "status": "Pending" })),
+            BundleOutcome::Pending
+        );
+
+        let landed = |status: Value| landed_outcome(&status);
+        assert_eq!(landed(Value::Null), BundleOutcome::Pending);
+        assert_eq!(
+            landed(json!({ "slot": 9, "confirmation_status": "confirmed", "err": { "Ok": null } })),
+            BundleOutcome::Landed { slot: 9 }
+        );
+        assert_eq!(
+            landed(json!({ "slot": 9, "confirmation_status": "processed", "err": { "Ok": null } })),
+            BundleOutcome::Pending
+        );
+        assert!(matches!(
+            landed(json!({ "slot": 9, "confirmation_status": "confirmed", "err": { "Err": 1 } })),
+            BundleOutcome::Failed(_)
+        ));
+
+        // A region that landed it wins; one still pending keeps the bundle alive
+        let failed = BundleOutcome::Failed("lost".to_string());
+        assert_eq!(
+            merge_outcomes(Some(failed.clone()), BundleOutcome::Landed { slot: 9 }),
+            BundleOutcome::Landed { slot: 9 }
+        );
+        assert_eq!(
+            merge_outcomes(Some(BundleOutcome::Pending), failed.clone()),
+            BundleOutcome::Pending
+        );
+        assert_eq!(
+            merge_outcomes(Some(BundleOutcome::Dropped), failed.clone()),
+            failed
+        );
+
+        assert_eq!(
+            region_url("ams").unwrap(),
+            "https://amsterdam.mainnet.block-engine.jito.wtf"
+        );
+        assert_eq!(
+            region_url("https://be.example/").unwrap(),
+            "https://be.example"
+        );
+        assert!(region_url("mars").is_err());
+    }
+}
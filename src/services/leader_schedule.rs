@@ -0,0 +1,206 @@
+use std::{collections::HashMap, env, str::FromStr, sync::Arc, sync::LazyLock, time::Duration};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::{watch, RwLock},
+    time::Instant,
+};
+
+use crate::{common::utils::log_message, services::idle::idle_sleep};
+
+// Configuration constants
+const DEFAULT_POLL_INTERVAL_MS: u64 = 150;
+const DEFAULT_MAX_AGE_MS: u64 = 2_000;
+
+/// Current slot and leader schedule kept up to date by `run_leader_tracker`
+pub static LEADER_SCHEDULE: LazyLock<LeaderSchedule> = LazyLock::new(LeaderSchedule::new);
+
+/// A slot observed by the tracker and when it was first seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotTick {
+    pub slot: u64,
+    pub observed_at: Instant,
+}
+
+/// Slot leaders for one epoch, indexed by slot offset into the epoch
+#[derive(Debug, Clone)]
+pub struct EpochLeaders {
+    pub first_slot: u64,
+    leaders: Vec<Option<Pubkey>>,
+}
+
+impl EpochLeaders {
+    /// Builds the lookup from a `getLeaderSchedule` response (identity to slot offsets)
+    pub fn from_schedule(
+        first_slot: u64,
+        slots_in_epoch: u64,
+        schedule: &HashMap<String, Vec<usize>>,
+    ) -> Self {
+        let mut leaders = vec![None; slots_in_epoch as usize];
+        for (identity, offsets) in schedule {
+            let Ok(leader) = Pubkey::from_str(identity) else {
+                continue;
+            };
+            for &offset in offsets {
+                if let Some(slot) = leaders.get_mut(offset) {
+                    *slot = Some(leader);
+                }
+            }
+        }
+        Self {
+            first_slot,
+            leaders,
+        }
+    }
+
+    pub fn contains(&self, slot: u64) -> bool {
+        slot >= self.first_slot && slot - self.first_slot < self.leaders.len() as u64
+    }
+
+    pub fn leader_at(&self, slot: u64) -> Option<Pubkey> {
+        if !self.contains(slot) {
+            return None;
+        }
+        self.leaders[(slot - self.first_slot) as usize]
+    }
+}
+
+pub struct LeaderSchedule {
+    slot: watch::Sender<Option<SlotTick>>,
+    epoch: RwLock<Option<EpochLeaders>>,
+    max_age: Duration,
+}
+
+impl Default for LeaderSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LeaderSchedule {
+    /// Creates an empty tracker that treats slots older than `SLOT_MAX_AGE_MS` as stale
+    pub fn new() -> Self {
+        let max_age_ms = env::var("SLOT_MAX_AGE_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_MAX_AGE_MS);
+
+        Self {
+            slot: watch::channel(None).0,
+            epoch: RwLock::new(None),
+            max_age: Duration::from_millis(max_age_ms),
+        }
+    }
+
+    /// Notifies on every new slot
+    pub fn subscribe(&self) -> watch::Receiver<Option<SlotTick>> {
+        self.slot.subscribe()
+    }
+
+    /// The current slot, unless the tracker has fallen behind
+    pub fn current_slot(&self) -> Option<u64> {
+        (*self.slot.borrow())
+            .filter(|tick| tick.observed_at.elapsed() <= self.max_age)
+            .map(|tick| tick.slot)
+    }
+
+    /// True if slot updates are fresh enough to time sends against
+    pub fn is_live(&self) -> bool {
+        self.current_slot().is_some()
+    }
+
+    pub async fn leader_at(&self, slot: u64) -> Option<Pubkey> {
+        self.epoch.read().await.as_ref()?.leader_at(slot)
+    }
+
+    async fn needs_schedule(&self, slot: u64) -> bool {
+        !self
+            .epoch
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(|epoch| epoch.contains(slot))
+    }
+
+    fn observe_slot(&self, slot: u64) {
+        self.slot.send_if_modified(|current| {
+            if current.is_some_and(|tick| tick.slot >= slot) {
+                return false;
+            }
+            *current = Some(SlotTick {
+                slot,
+                observed_at: Instant::now(),
+            });
+            true
+        });
+    }
+}
+
+async fn refresh_schedule(client: &RpcClient) -> anyhow::Result<EpochLeaders> {
+    let epoch_info = client.get_epoch_info().await?;
+    let first_slot = epoch_info.absolute_slot - epoch_info.slot_index;
+    let schedule = client
+        .get_leader_schedule(Some(epoch_info.absolute_slot))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No leader schedule for epoch {}", epoch_info.epoch))?;
+    Ok(EpochLeaders::from_schedule(
+        first_slot,
+        epoch_info.slots_in_epoch,
+        &schedule,
+    ))
+}
+
+/// Polls the current slot forever and reloads the leader schedule at each epoch boundary
+pub async fn run_leader_tracker(client: Arc<RpcClient>) {
+    let poll_interval = Duration::from_millis(
+        env::var("SLOT_POLL_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+    );
+
+    loop {
+        match client.get_slot().await {
+            Ok(slot) => {
+                LEADER_SCHEDULE.observe_slot(slot);
+                if LEADER_SCHEDULE.needs_schedule(slot).await {
+                    match refresh_schedule(&client).await {
+                        Ok(epoch) => *LEADER_SCHEDULE.epoch.write().await = Some(epoch),
+                        Err(e) => {
+                            let _ =
+                                log_message(&format!("Leader schedule fetch failed: {}", e)).await;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = log_message(&format!("Slot poll failed: {}", e)).await;
+            }
+        }
+        idle_sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_leaders_lookup() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let schedule = HashMap::from([
+            (a.to_string(), vec![0, 1, 2, 3]),
+            (b.to_string(), vec![4, 5, 6, 7, 99]),
+        ]);
+        let epoch = EpochLeaders::from_schedule(1_000, 8, &schedule);
+
+        assert_eq!(epoch.leader_at(1_000), Some(a));
+        assert_eq!(epoch.leader_at(1_004), Some(b));
+        assert_eq!(epoch.leader_at(1_008), None);
+        assert_eq!(epoch.leader_at(999), None);
+        assert!(epoch.contains(1_007));
+        assert!(!epoch.contains(1_008));
+    }
+}
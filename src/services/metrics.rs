@@ -0,0 +1,238 @@
+use std::{env, sync::LazyLock};
+
+use anyhow::Result;
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::Instant,
+};
+
+use crate::{
+    common::utils::log_message,
+    services::{dashboard::ACTIVITY, leader_schedule::LEADER_SCHEDULE},
+};
+
+// Configuration constants
+pub const NAMESPACE: &str = "copybot";
+const MAX_REQUEST_BYTES: usize = 4_096;
+
+pub static METRICS: LazyLock<Metrics> =
+    LazyLock::new(|| Metrics::new().expect("Failed to register metrics"));
+
+/// Everything the bot exports to Prometheus
+pub struct Metrics {
+    registry: Registry,
+    /// Swaps by venue (`pump`, `raydium`, ...) and outcome
+    pub swaps_attempted: IntCounterVec,
+    pub swaps_succeeded: IntCounterVec,
+    pub swaps_failed: IntCounterVec,
+    /// Slots between the target's transaction and ours, from the slot ours landed in
+    pub copy_latency_slots: Histogram,
+    /// Wall time from seeing the target's transaction to ours confirming
+    pub copy_latency_seconds: Histogram,
+    /// Bundle land rate is `jito_bundles_landed / jito_bundles_sent`
+    pub jito_bundles_sent: IntCounter,
+    pub jito_bundles_landed: IntCounter,
+    /// RPC failures by operation
+    pub rpc_errors: IntCounterVec,
+    pub priority_fees_lamports: IntCounter,
+    /// Net lamports of closed positions, fees included
+    pub realized_pnl_lamports: IntGauge,
+    pub positions_closed: IntCounter,
+    /// Lookups, evictions and size of each bounded cache
+    pub cache_hits: IntCounterVec,
+    pub cache_misses: IntCounterVec,
+    pub cache_evictions: IntCounterVec,
+    pub cache_entries: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+        let swaps = |name: &str, help: &str| -> Result<IntCounterVec> {
+            let counter = IntCounterVec::new(Opts::new(name, help), &["venue"])?;
+            registry.register(Box::new(counter.clone()))?;
+            Ok(counter)
+        };
+        let swaps_attempted = swaps("swaps_attempted_total", "Swaps submitted")?;
+        let swaps_succeeded = swaps("swaps_succeeded_total", "Swaps that landed")?;
+        let swaps_failed = swaps("swaps_failed_total", "Swaps that failed")?;
+        let caches = |name: &str, help: &str| -> Result<IntCounterVec> {
+            let counter = IntCounterVec::new(Opts::new(name, help), &["cache"])?;
+            registry.register(Box::new(counter.clone()))?;
+            Ok(counter)
+        };
+        let cache_hits = caches("cache_hits_total", "Cache lookups that found a live entry")?;
+        let cache_misses = caches("cache_misses_total", "Cache lookups that found nothing")?;
+        let cache_evictions = caches(
+            "cache_evictions_total",
+            "Entries dropped for age or to make room",
+        )?;
+        let cache_entries = IntGaugeVec::new(
+            Opts::new("cache_entries", "Entries currently held"),
+            &["cache"],
+        )?;
+
+        let copy_latency_slots = Histogram::with_opts(
+            HistogramOpts::new(
+                "copy_latency_slots",
+                "Slots from the target's transaction to ours",
+            )
+            .buckets(vec![0.0, 1.0, 2.0, 3.0, 5.0, 8.0, 13.0, 21.0, 50.0]),
+        )?;
+        let copy_latency_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "copy_latency_seconds",
+                "Seconds from seeing the target's transaction to ours confirming",
+            )
+            .buckets(exponential_buckets(0.1, 2.0, 10)?),
+        )?;
+        let jito_bundles_sent = IntCounter::new("jito_bundles_sent_total", "Bundles sent to Jito")?;
+        let jito_bundles_landed =
+            IntCounter::new("jito_bundles_landed_total", "Jito bundles confirmed")?;
+        let rpc_errors = IntCounterVec::new(
+            Opts::new("rpc_errors_total", "RPC calls that failed"),
+            &["operation"],
+        )?;
+        let priority_fees_lamports = IntCounter::new(
+            "priority_fees_lamports_total",
+            "Priority fees attached to sent transactions",
+        )?;
+        let realized_pnl_lamports = IntGauge::new(
+            "realized_pnl_lamports",
+            "Net result of closed positions, fees included",
+        )?;
+        let positions_closed = IntCounter::new("positions_closed_total", "Positions settled")?;
+
+        registry.register(Box::new(copy_latency_slots.clone()))?;
+        registry.register(Box::new(copy_latency_seconds.clone()))?;
+        registry.register(Box::new(jito_bundles_sent.clone()))?;
+        registry.register(Box::new(jito_bundles_landed.clone()))?;
+        registry.register(Box::new(rpc_errors.clone()))?;
+        registry.register(Box::new(priority_fees_lamports.clone()))?;
+        registry.register(Box::new(realized_pnl_lamports.clone()))?;
+        registry.register(Box::new(positions_closed.clone()))?;
+        registry.register(Box::new(cache_entries.clone()))?;
+
+        Ok(Self {
+            registry,
+            swaps_attempted,
+            swaps_succeeded,
+            swaps_failed,
+            copy_latency_slots,
+            copy_latency_seconds,
+            jito_bundles_sent,
+            jito_bundles_landed,
+            rpc_errors,
+            priority_fees_lamports,
+            realized_pnl_lamports,
+            positions_closed,
+            cache_hits,
+            cache_misses,
+            cache_evictions,
+            cache_entries,
+        })
+    }
+
+    /// Counts a finished swap on `venue`
+    pub fn observe_swap<T, E>(&self, venue: &str, result: &Result<T, E>) {
+        self.swaps_attempted.with_label_values(&[venue]).inc();
+        match result {
+            Ok(_) => self.swaps_succeeded.with_label_values(&[venue]).inc(),
+            Err(_) => self.swaps_failed.with_label_values(&[venue]).inc(),
+        }
+    }
+
+    /// Records how long after the target a successful copy confirmed
+    pub fn observe_copy(&self, target_slot: u64, seen_at: Instant) {
+        let seconds = seen_at.elapsed().as_secs_f64();
+        self.copy_latency_seconds.observe(seconds);
+        // The tracked slot when our confirmation came back is where the copy landed, give or
+        // take; good enough for the dashboard until the landed transaction is fetched
+        let slots = LEADER_SCHEDULE
+            .current_slot()
+            .map(|slot| slot.saturating_sub(target_slot));
+        ACTIVITY.landed(slots, seconds);
+    }
+
+    /// Records how many slots behind the target's trade a copy landed
+    pub fn observe_slots_behind(&self, slots: u64) {
+        self.copy_latency_slots.observe(slots as f64);
+    }
+
+    pub fn rpc_error(&self, operation: &str) {
+        self.rpc_errors.with_label_values(&[operation]).inc();
+    }
+
+    /// Books a closed position's net result
+    pub fn record_pnl(&self, pnl_lamports: i64) {
+        self.realized_pnl_lamports.add(pnl_lamports);
+        self.positions_closed.inc();
+    }
+
+    /// Prometheus text exposition of every metric
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+async fn serve(mut stream: TcpStream) -> Result<()> {
+    let mut request = vec![0; MAX_REQUEST_BYTES];
+    let read = stream.read(&mut request).await?;
+    let request_line = String::from_utf8_lossy(&request[..read]);
+    let response = if request_line.starts_with("GET /metrics ") {
+        let body = METRICS.render()?;
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Serves `/metrics` on `METRICS_ADDR` (e.g. `0.0.0.0:9184`) forever; off when unset
+pub async fn run_metrics_server() {
+    let Ok(addr) = env::var("METRICS_ADDR") else {
+        return;
+    };
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            let _ = log_message(&format!("Metrics server failed to bind {}: {}", addr, e)).await;
+            return;
+        }
+    };
+    let _ = log_message(&format!("Serving metrics on http://{}/metrics", addr)).await;
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(async move {
+            let _ = serve(stream).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_swap_counters() {
+        METRICS.observe_swap::<(), ()>("pump", &Ok(()));
+        METRICS.observe_swap::<(), ()>("pump", &Err(()));
+        let text = METRICS.render().unwrap();
+        assert!(text.contains("copybot_swaps_attempted_total{venue=\"pump\"}"));
+        assert!(text.contains("copybot_swaps_failed_total{venue=\"pump\"} 1"));
+    }
+}
@@ -1 +1,23 @@
+pub mod alerts;
+pub mod api;
+pub mod attribution;
+pub mod blockhash;
+pub mod curve_cache;
+pub mod dashboard;
+pub mod discord;
+pub mod grafana;
+pub mod idle;
 pub mod jito;
+pub mod leader_schedule;
+pub mod metrics;
+pub mod notify;
+pub mod price;
+pub mod price_feed;
+pub mod relay;
+pub mod remote_signer;
+#[cfg(feature = "shredstream")]
+pub mod shredstream;
+pub mod slo;
+pub mod snapshot;
+pub mod sol_usd;
+pub mod sources;
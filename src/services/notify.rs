@@ -0,0 +1,703 @@
+use std::{env, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use async_trait::async_trait;
+use jito_json_rpc_client::jsonrpc_client::rpc_client::RpcClient as JitoRpcClient;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::sleep;
+
+use crate::common::utils::{log_message, AppState};
+use crate::engine::dual_control::{act_on_vote, submit, ControlAction, Vote};
+use crate::engine::fees::position_breakeven;
+use crate::engine::panic::fire_panic_exits;
+use crate::engine::position::{load_closed_trades, FillSide};
+use crate::risk::filters::{handle_filter_command, FilterField};
+use crate::risk::pause::set_copying_paused;
+use crate::services::alerts::AlertCondition;
+use crate::services::discord::DiscordNotification;
+use crate::services::snapshot::Snapshot;
+use crate::services::sol_usd::usd_suffix;
+
+// Configuration constants
+const UPDATES_LONG_POLL_SECS: u64 = 30;
+const UPDATES_RETRY_DELAY_SECS: u64 = 5;
+
+/// Something worth telling the operator about
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A registered price alert fired
+    PriceAlert {
+        mint: String,
+        price: f64,
+        message: String,
+    },
+    /// A copy is on hold until the operator approves or rejects it
+    ApprovalRequest {
+        intent_id: u64,
+        mint: String,
+        amount_lamports: u64,
+        timeout_secs: u64,
+    },
+    /// An operator action waiting for more sign-offs
+    ControlRequest {
+        action_id: u64,
+        description: String,
+        approvals: usize,
+        required: usize,
+    },
+    /// A buy was dropped because it would break a position limit
+    LimitBreached {
+        source: String,
+        mint: String,
+        reason: String,
+    },
+    /// One of our swaps filled
+    Trade {
+        side: FillSide,
+        mint: String,
+        lamports: u64,
+        /// Raw token units
+        tokens: u64,
+        /// SOL per token
+        price: f64,
+        signature: String,
+        /// Slots the fill landed after the target's trade, for copies
+        slots_behind: Option<u64>,
+    },
+    /// A position was fully exited
+    PositionClosed {
+        mint: String,
+        invested_lamports: u64,
+        pnl_lamports: i64,
+    },
+    /// Free-form status line
+    Info(String),
+}
+
+/// Solscan page of a transaction
+pub fn solscan_tx(signature: &str) -> String {
+    format!("https://solscan.io/tx/{}", signature)
+}
+
+impl Event {
+    /// Plain-text rendering shared by sinks without rich formatting
+    pub fn text(&self) -> String {
+        match self {
+            Event::PriceAlert {
+                mint,
+                price,
+                message,
+            } => format!("🔔 {} — {} (price {:.10} SOL)", mint, message, price),
+            Event::ApprovalRequest {
+                intent_id,
+                mint,
+                amount_lamports,
+                timeout_secs,
+            } => format!(
+                "✋ Approve copy buy #{} of {:.4} SOL into {}? Dropped in {}s without an answer",
+                intent_id,
+                *amount_lamports as f64 / 1e9,
+                mint,
+                timeout_secs
+            ),
+            Event::ControlRequest {
+                action_id,
+                description,
+                approvals,
+                required,
+            } => format!(
+                "🔐 #{}: {} ({}/{} approvals)",
+                action_id, description, approvals, required
+            ),
+            Event::LimitBreached {
+                source,
+                mint,
+                reason,
+            } => format!("🛑 Skipped {} buy of {}: {}", source, mint, reason),
+            Event::Trade {
+                side,
+                mint,
+                lamports,
+                tokens,
+                price,
+                signature,
+                slots_behind,
+            } => format!(
+                "{} {} {} for {:.4} SOL at {:.10} SOL{} {}",
+                if *side == FillSide::Buy { "🟢 Bought" } else { "🔴 Sold" },
+                tokens,
+                mint,
+                *lamports as f64 / 1e9,
+                price,
+                slots_behind
+                    .map(|slots| format!(", {} slots behind target", slots))
+                    .unwrap_or_default(),
+                solscan_tx(signature)
+            ),
+            Event::PositionClosed {
+                mint,
+                invested_lamports,
+                pnl_lamports,
+            } => format!(
+                "🏁 Closed {}: {:+.4} SOL{} on {:.4} SOL invested",
+                mint,
+                *pnl_lamports as f64 / 1e9,
+                usd_suffix(*pnl_lamports),
+                *invested_lamports as f64 / 1e9
+            ),
+            Event::Info(message) => message.clone(),
+        }
+    }
+}
+
+/// A destination for operator notifications
+#[async_trait]
+pub trait Notification: Send + Sync {
+    async fn send(&self, event: &Event) -> Result<()>;
+}
+
+/// Writes notifications to the bot log
+pub struct LogNotification;
+
+#[async_trait]
+impl Notification for LogNotification {
+    async fn send(&self, event: &Event) -> Result<()> {
+        log_message(&event.text()).await?;
+        Ok(())
+    }
+}
+
+/// Sends notifications to a Telegram chat through the Bot API
+pub struct TelegramNotification {
+    pub bot_token: String,
+    pub chat_id: String,
+    /// Chats whose members may run commands and press buttons
+    pub allowed_chats: Vec<String>,
+    http: reqwest::Client,
+}
+
+impl TelegramNotification {
+    /// Reads `TELEGRAM_BOT_TOKEN` and `TELEGRAM_CHAT_ID`, both required, and
+    /// `TELEGRAM_ALLOWED_CHATS`, comma-separated chat ids defaulting to the notification chat
+    pub fn from_env() -> Option<Self> {
+        let chat_id = env::var("TELEGRAM_CHAT_ID").ok()?;
+        let allowed_chats = env::var("TELEGRAM_ALLOWED_CHATS")
+            .map(|chats| {
+                chats
+                    .split(',')
+                    .map(|chat| chat.trim().to_string())
+                    .filter(|chat| !chat.is_empty())
+                    .collect()
+            })
+            .unwrap_or_else(|_| vec![chat_id.clone()]);
+        Some(Self {
+            bot_token: env::var("TELEGRAM_BOT_TOKEN").ok()?,
+            chat_id,
+            allowed_chats,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    fn method_url(&self, method: &str) -> String {
+        format!("https://api.telegram.org/bot{}/{}", self.bot_token, method)
+    }
+
+    /// Long-polls for button presses and commands, returning `(next offset, update)` pairs
+    async fn updates(&self, offset: i64) -> Result<Vec<(i64, Value)>> {
+        let response: Value = self
+            .http
+            .post(self.method_url("getUpdates"))
+            .json(&json!({
+                "offset": offset,
+                "timeout": UPDATES_LONG_POLL_SECS,
+                "allowed_updates": ["callback_query", "message"],
+            }))
+            .timeout(Duration::from_secs(UPDATES_LONG_POLL_SECS + 10))
+            .send()
+            .await?
+            .error_for_status()
+            .context("Telegram getUpdates failed")?
+            .json()
+            .await?;
+
+        Ok(response["result"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|update| Some((update["update_id"].as_i64()? + 1, update.clone())))
+            .collect())
+    }
+
+    /// Whether a message or callback came from an allowed chat
+    fn from_our_chat(&self, message: &Value) -> bool {
+        let chat = message["chat"]["id"].to_string();
+        self.allowed_chats
+            .iter()
+            .any(|allowed| allowed == chat.trim_matches('"'))
+    }
+
+    /// Answers a command in the chat it came from
+    async fn reply(&self, chat_id: &Value, text: &str) -> Result<()> {
+        self.http
+            .post(self.method_url("sendMessage"))
+            .json(&json!({
+                "chat_id": chat_id,
+                "text": text,
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await?
+            .error_for_status()
+            .context("Telegram sendMessage failed")?;
+        Ok(())
+    }
+
+    async fn answer_callback(&self, callback_id: &str, text: &str) -> Result<()> {
+        self.http
+            .post(self.method_url("answerCallbackQuery"))
+            .json(&json!({ "callback_query_id": callback_id, "text": text }))
+            .send()
+            .await?
+            .error_for_status()
+            .context("Telegram answerCallbackQuery failed")?;
+        Ok(())
+    }
+}
+
+/// Parses inline button data of the form `<action>:<id>`
+fn parse_callback(data: &str) -> Option<(&str, u64)> {
+    let (action, id) = data.split_once(':')?;
+    Some((action, id.parse().ok()?))
+}
+
+/// Answers an inline button: copy approvals (`approve`/`reject`) or dual-control votes
+/// (`confirm`/`veto`), the voter being identified by their Telegram user id
+async fn handle_callback(
+    state: &AppState,
+    callback: &Value,
+    jito_client: Arc<JitoRpcClient>,
+) -> Option<String> {
+    let (action, id) = parse_callback(callback["data"].as_str()?)?;
+    let voter = format!("telegram:{}", callback["from"]["id"]);
+    let reply = match action {
+        "approve" | "reject" => {
+            let approved = action == "approve";
+            match state.approvals.resolve(id, approved).await {
+                true if approved => format!("Copy #{} approved", id),
+                true => format!("Copy #{} rejected", id),
+                false => format!("Copy #{} is no longer pending", id),
+            }
+        }
+        "confirm" => {
+            let vote = state.control.approve(id, &voter).await;
+            act_on_vote(state, id, vote, jito_client).await
+        }
+        "veto" => {
+            let vote = state.control.reject(id, &voter).await;
+            act_on_vote(state, id, vote, jito_client).await
+        }
+        _ => return None,
+    };
+    Some(reply)
+}
+
+const COMMAND_HELP: &str = "/positions, /position <mint>, /pnl, /sell <mint> [pct], /pause, \
+/resume, /blacklist <mint>, /panic, /alerts, /alert <mint> <condition>, /unalert <id>, /filters, \
+/block, /unblock, /allow, /unallow";
+
+const ALERT_USAGE: &str =
+    "Usage: /alert <mint> <condition>, condition above:<price>, below:<price> or move:<pct>:<secs>";
+
+/// A chat command; the token filter commands are parsed by `handle_filter_command`
+#[derive(Debug, Clone, PartialEq)]
+enum ChatCommand {
+    Positions,
+    Position { mint: String },
+    Pnl,
+    Sell { mint: String, pct: f64 },
+    Pause,
+    Resume,
+    Blacklist { mint: String },
+    Panic,
+    Alerts,
+    Alert { mint: String, condition: AlertCondition },
+    Unalert { id: u64 },
+    Help,
+}
+
+impl ChatCommand {
+    /// `None` for anything that isn't one of ours, `Err` with usage for bad arguments
+    fn parse(text: &str) -> Option<Result<Self, &'static str>> {
+        let mut words = text.split_whitespace();
+        // Group chats address commands as `/command@bot`
+        let command = words.next()?.split('@').next()?;
+        let args = words.collect::<Vec<_>>();
+        let command = match (command, args.as_slice()) {
+            ("/positions", []) => ChatCommand::Positions,
+            ("/position", [mint]) => ChatCommand::Position {
+                mint: mint.to_string(),
+            },
+            ("/position", _) => return Some(Err("Usage: /position <mint>")),
+            ("/pnl", []) => ChatCommand::Pnl,
+            ("/pause", []) => ChatCommand::Pause,
+            ("/resume", []) => ChatCommand::Resume,
+            ("/panic", []) => ChatCommand::Panic,
+            ("/help" | "/start", _) => ChatCommand::Help,
+            ("/sell", [mint]) => ChatCommand::Sell {
+                mint: mint.to_string(),
+                pct: 100.0,
+            },
+            ("/sell", [mint, pct]) => match pct.trim_end_matches('%').parse::<f64>() {
+                Ok(pct) if pct > 0.0 && pct <= 100.0 => ChatCommand::Sell {
+                    mint: mint.to_string(),
+                    pct,
+                },
+                _ => return Some(Err("Usage: /sell <mint> [pct], pct in (0, 100]")),
+            },
+            ("/sell", _) => return Some(Err("Usage: /sell <mint> [pct]")),
+            ("/blacklist", [mint]) => ChatCommand::Blacklist {
+                mint: mint.to_string(),
+            },
+            ("/blacklist", _) => return Some(Err("Usage: /blacklist <mint>")),
+            ("/alerts", []) => ChatCommand::Alerts,
+            ("/alert", [mint, condition]) => match AlertCondition::from_str(condition) {
+                Ok(condition) => ChatCommand::Alert {
+                    mint: mint.to_string(),
+                    condition,
+                },
+                Err(_) => return Some(Err(ALERT_USAGE)),
+            },
+            ("/alert", _) => return Some(Err(ALERT_USAGE)),
+            ("/unalert", [id]) => match id.trim_start_matches('#').parse() {
+                Ok(id) => ChatCommand::Unalert { id },
+                Err(_) => return Some(Err("Usage: /unalert <id>")),
+            },
+            ("/unalert", _) => return Some(Err("Usage: /unalert <id>")),
+            _ => return None,
+        };
+        Some(Ok(command))
+    }
+}
+
+async fn positions_reply(state: &AppState) -> String {
+    let mut positions = state.positions.open_positions().await;
+    positions.extend(state.positions.impaired_positions().await);
+    if positions.is_empty() {
+        return "No open positions".to_string();
+    }
+    positions.sort_by_key(|position| position.opened_at);
+    let mut lines = vec![];
+    for position in &positions {
+        let breakeven = match position_breakeven(state, position).await {
+            Ok(price) => format!(", breakeven {:.10} SOL", price),
+            Err(_) => String::new(),
+        };
+        lines.push(format!(
+            "{}: in {:.4} SOL{}, out {:.4} SOL, held {}m{}{}",
+            position.mint,
+            position.sol_invested as f64 / 1e9,
+            usd_suffix(position.sol_invested as i64),
+            position.sol_returned as f64 / 1e9,
+            (Utc::now() - position.opened_at).num_minutes(),
+            breakeven,
+            position
+                .impaired
+                .map_or(String::new(), |impairment| format!(" ({})", impairment))
+        ));
+    }
+    lines.join("\n")
+}
+
+/// One position's exit levels, the signal that opened it and its fills, oldest first
+async fn position_reply(state: &AppState, mint: &str) -> String {
+    let Some(position) = state.positions.get(mint).await else {
+        return format!("No open position in {}", mint);
+    };
+    let stop_loss = state
+        .settings
+        .current()
+        .stop_loss_pct
+        .map_or("off".to_string(), |pct| format!("{}%", pct));
+    let max_hold = position
+        .max_hold_secs
+        .map_or("default".to_string(), |secs| format!("{}s", secs));
+    let breakeven = match position_breakeven(state, &position).await {
+        Ok(price) => format!("{:.10} SOL", price),
+        Err(_) => "unknown".to_string(),
+    };
+    let mut lines = vec![
+        format!(
+            "{}: opened {} copying {} ({})",
+            position.mint,
+            position.opened_at.format("%Y-%m-%d %H:%M:%S"),
+            position.target.as_deref().unwrap_or("unknown"),
+            position.signal.as_deref().unwrap_or("no signal"),
+        ),
+        format!(
+            "Stop loss {}, max hold {}, breakeven {}",
+            stop_loss, max_hold, breakeven
+        ),
+    ];
+    lines.extend(position.fills.iter().map(|fill| {
+        let side = match fill.side {
+            FillSide::Buy => "buy",
+            FillSide::Sell => "sell",
+        };
+        format!(
+            "{} {} {:.4} SOL, fees {:.6} SOL",
+            fill.at.format("%H:%M:%S"),
+            side,
+            fill.lamports as f64 / 1e9,
+            fill.fees as f64 / 1e9,
+        )
+    }));
+    lines.join("\n")
+}
+
+async fn pnl_reply(state: &AppState) -> String {
+    let trades = match load_closed_trades() {
+        Ok(trades) => trades,
+        Err(e) => return format!("Could not read the trade log: {}", e),
+    };
+    let snapshot = Snapshot::build(&trades, 0, Utc::now());
+    let open = state.positions.open_positions().await;
+    let invested = open.iter().map(|p| p.sol_invested).sum::<u64>();
+    format!(
+        "{} trades, {} wins, realized {:+.4} SOL{}\n{} open positions with {:.4} SOL{} invested{}",
+        snapshot.trades,
+        snapshot.wins,
+        snapshot.net_pnl_sol,
+        usd_suffix((snapshot.net_pnl_sol * 1e9) as i64),
+        open.len(),
+        invested as f64 / 1e9,
+        usd_suffix(invested as i64),
+        if state.pause.is_paused() { "\nCopying is paused" } else { "" }
+    )
+}
+
+/// Runs a command from an allowed chat and returns the reply for that chat. Sells go through
+/// dual control; `/panic` doesn't, since it only ever closes exposure
+async fn handle_command(
+    state: &AppState,
+    message: &Value,
+    jito_client: Arc<JitoRpcClient>,
+) -> Option<String> {
+    let text = message["text"].as_str()?;
+    if let Some(reply) = handle_filter_command(state, text).await {
+        return Some(reply);
+    }
+    let command = match ChatCommand::parse(text)? {
+        Ok(command) => command,
+        Err(usage) => return Some(usage.to_string()),
+    };
+    let requester = format!("telegram:{}", message["from"]["id"]);
+    let reply = match command {
+        ChatCommand::Positions => positions_reply(state).await,
+        ChatCommand::Position { mint } => position_reply(state, &mint).await,
+        ChatCommand::Pnl => pnl_reply(state).await,
+        ChatCommand::Pause | ChatCommand::Resume => {
+            let paused = command == ChatCommand::Pause;
+            if !set_copying_paused(state, &requester, paused).await {
+                let now = if paused { "paused" } else { "running" };
+                return Some(format!("Copying is already {}", now));
+            }
+            // The change was announced to every sink
+            return None;
+        }
+        ChatCommand::Blacklist { mint } => {
+            match state.filters.add(false, FilterField::Mint, &mint).await {
+                Ok(true) => format!("Added {} to the blacklist", mint),
+                Ok(false) => format!("{} is already on the blacklist", mint),
+                Err(e) => format!("Invalid mint '{}': {}", mint, e),
+            }
+        }
+        ChatCommand::Sell { mint, pct } => {
+            if state.positions.get(&mint).await.is_none() {
+                return Some(format!("No open position in {}", mint));
+            }
+            let action = ControlAction::ManualSell { mint, pct };
+            let (id, vote) = submit(state, action, Some(&requester)).await;
+            // Pending votes were already announced with buttons by `submit`, and outcomes
+            // are announced by `act_on_vote`
+            if !matches!(vote, Vote::Pending { .. }) {
+                act_on_vote(state, id, vote, jito_client).await;
+            }
+            return None;
+        }
+        ChatCommand::Panic => {
+            let report = fire_panic_exits(state, jito_client).await;
+            let summary = report.summary_as("🚨 Panic exit");
+            let _ = log_message(&summary).await;
+            state.notifier.notify(Event::Info(summary)).await;
+            return None;
+        }
+        ChatCommand::Alerts => {
+            let alerts = state.alerts.list().await;
+            if alerts.is_empty() {
+                "No price alerts".to_string()
+            } else {
+                alerts
+                    .iter()
+                    .map(|alert| format!("#{} {} {}", alert.id, alert.mint, alert.condition))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+        ChatCommand::Alert { mint, condition } => {
+            if Pubkey::from_str(&mint).is_err() {
+                return Some(format!("Invalid mint '{}'", mint));
+            }
+            let id = state.alerts.register(state, &mint, condition).await;
+            format!("Alert #{} set on {}", id, mint)
+        }
+        ChatCommand::Unalert { id } => {
+            if state.alerts.remove(state, id).await {
+                format!("Removed alert #{}", id)
+            } else {
+                format!("No alert #{}", id)
+            }
+        }
+        ChatCommand::Help => COMMAND_HELP.to_string(),
+    };
+    Some(reply)
+}
+
+/// Handles Telegram buttons and commands from the configured chat, forever
+pub async fn run_telegram_control(state: AppState, jito_client: Arc<JitoRpcClient>) {
+    let Some(telegram) = TelegramNotification::from_env() else {
+        return;
+    };
+    let mut offset = 0;
+    loop {
+        let updates = match telegram.updates(offset).await {
+            Ok(updates) => updates,
+            Err(e) => {
+                let _ = log_message(&format!("Telegram poll failed: {}", e)).await;
+                sleep(Duration::from_secs(UPDATES_RETRY_DELAY_SECS)).await;
+                continue;
+            }
+        };
+
+        for (next_offset, update) in updates {
+            offset = offset.max(next_offset);
+            let callback = &update["callback_query"];
+            // Only the configured chat may approve trades or run commands
+            if callback.is_object() && telegram.from_our_chat(&callback["message"]) {
+                let reply = handle_callback(&state, callback, jito_client.clone()).await;
+                if let (Some(reply), Some(callback_id)) = (reply, callback["id"].as_str()) {
+                    let _ = telegram.answer_callback(callback_id, &reply).await;
+                }
+            } else if telegram.from_our_chat(&update["message"]) {
+                let message = &update["message"];
+                if let Some(reply) = handle_command(&state, message, jito_client.clone()).await {
+                    let _ = telegram.reply(&message["chat"]["id"], &reply).await;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Notification for TelegramNotification {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut message = json!({
+            "chat_id": self.chat_id,
+            "text": event.text(),
+            "disable_web_page_preview": true,
+        });
+        let buttons = match event {
+            Event::ApprovalRequest { intent_id, .. } => Some(("approve", "reject", intent_id)),
+            Event::ControlRequest { action_id, .. } => Some(("confirm", "veto", action_id)),
+            _ => None,
+        };
+        if let Some((yes, no, id)) = buttons {
+            message["reply_markup"] = json!({
+                "inline_keyboard": [[
+                    { "text": "✅ Approve", "callback_data": format!("{}:{}", yes, id) },
+                    { "text": "❌ Reject", "callback_data": format!("{}:{}", no, id) },
+                ]]
+            });
+        }
+        self.http
+            .post(self.method_url("sendMessage"))
+            .json(&message)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Telegram sendMessage failed")?;
+        Ok(())
+    }
+}
+
+/// Fans each event out to every configured sink
+pub struct Notifier {
+    sinks: Vec<Arc<dyn Notification>>,
+}
+
+impl Notifier {
+    pub fn new(sinks: Vec<Arc<dyn Notification>>) -> Self {
+        Self { sinks }
+    }
+
+    /// Log sink always, plus Telegram and Discord when configured
+    pub fn from_env() -> Self {
+        let mut sinks: Vec<Arc<dyn Notification>> = vec![Arc::new(LogNotification)];
+        if let Some(telegram) = TelegramNotification::from_env() {
+            sinks.push(Arc::new(telegram));
+        }
+        if let Some(discord) = DiscordNotification::from_env() {
+            sinks.push(Arc::new(discord));
+        }
+        Self::new(sinks)
+    }
+
+    /// Delivers an event to every sink; a failing sink never blocks the others
+    pub async fn notify(&self, event: Event) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.send(&event).await {
+                let _ = log_message(&format!("Notification failed: {}", e)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_chat_commands() {
+        assert_eq!(
+            ChatCommand::parse("/sell@copy_bot mint 25%"),
+            Some(Ok(ChatCommand::Sell {
+                mint: "mint".to_string(),
+                pct: 25.0
+            }))
+        );
+        assert_eq!(
+            ChatCommand::parse("/sell mint"),
+            Some(Ok(ChatCommand::Sell {
+                mint: "mint".to_string(),
+                pct: 100.0
+            }))
+        );
+        assert!(matches!(ChatCommand::parse("/sell mint 150"), Some(Err(_))));
+        assert!(matches!(ChatCommand::parse("/blacklist"), Some(Err(_))));
+        assert_eq!(
+            ChatCommand::parse("/position mint"),
+            Some(Ok(ChatCommand::Position {
+                mint: "mint".to_string()
+            }))
+        );
+        assert_eq!(ChatCommand::parse("/pause"), Some(Ok(ChatCommand::Pause)));
+        assert_eq!(ChatCommand::parse("hello"), None);
+        assert_eq!(
+            ChatCommand::parse("/unalert #4"),
+            Some(Ok(ChatCommand::Unalert { id: 4 }))
+        );
+        assert!(matches!(ChatCommand::parse("/alert mint up:2"), Some(Err(_))));
+        assert_eq!(ChatCommand::parse("/block mint x"), None);
+    }
+}
@@ -0,0 +1,360 @@
+use std::{env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::{
+    sync::Mutex,
+    time::{sleep_until, Instant},
+};
+
+use crate::{common::cache::BoundedCache, dex::jupiter::SOL_MINT};
+
+// Configuration constants
+const BIRDEYE_URL: &str = "https://public-api.birdeye.so/defi/token_overview";
+const DEXSCREENER_URL: &str = "https://api.dexscreener.com/latest/dex/tokens";
+const JUPITER_PRICE_URL: &str = "https://lite-api.jup.ag/price/v2";
+const DEFAULT_PROVIDERS: &str = "dexscreener,jupiter";
+const CACHE_ENTRIES: usize = 2_000;
+const CACHE_TTL_SECS: u64 = 30;
+const REQUEST_TIMEOUT_SECS: u64 = 5;
+
+/// Off-chain market data for one mint
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketData {
+    pub mint: String,
+    pub price_usd: f64,
+    pub market_cap_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    /// Provider the data came from
+    pub source: &'static str,
+}
+
+/// A market data API
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// The provider's data for `mint`, or `None` if it doesn't list it
+    async fn fetch(&self, http: &reqwest::Client, mint: &str) -> Result<Option<MarketData>>;
+}
+
+/// Spaces a provider's requests at least `interval` apart
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Reads `PRICE_<NAME>_RPS`, requests per second allowed to the provider
+    fn from_env(name: &str, default_rps: f64) -> Self {
+        let rps = env::var(format!("PRICE_{}_RPS", name.to_uppercase()))
+            .ok()
+            .and_then(|v| f64::from_str(&v).ok())
+            .filter(|rps| *rps > 0.0)
+            .unwrap_or(default_rps);
+        Self {
+            interval: Duration::from_secs_f64(1.0 / rps),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot and claims it
+    async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|v| f64::from_str(v).ok()))
+}
+
+async fn get_json(request: reqwest::RequestBuilder) -> Result<Value> {
+    Ok(request
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Birdeye's token overview; needs `BIRDEYE_API_KEY`
+pub struct Birdeye {
+    api_key: String,
+    limiter: RateLimiter,
+}
+
+/// Reads a Birdeye `token_overview` response
+pub fn parse_birdeye(mint: &str, response: &Value) -> Option<MarketData> {
+    let data = &response["data"];
+    Some(MarketData {
+        mint: mint.to_string(),
+        price_usd: number(&data["price"]).filter(|price| *price > 0.0)?,
+        market_cap_usd: number(&data["marketCap"]).or_else(|| number(&data["mc"])),
+        liquidity_usd: number(&data["liquidity"]),
+        source: "birdeye",
+    })
+}
+
+#[async_trait]
+impl PriceProvider for Birdeye {
+    fn name(&self) -> &'static str {
+        "birdeye"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, mint: &str) -> Result<Option<MarketData>> {
+        self.limiter.acquire().await;
+        let response = get_json(
+            http.get(BIRDEYE_URL)
+                .query(&[("address", mint)])
+                .header("X-API-KEY", &self.api_key)
+                .header("x-chain", "solana"),
+        )
+        .await?;
+        Ok(parse_birdeye(mint, &response))
+    }
+}
+
+/// DexScreener's public token pairs endpoint
+pub struct DexScreener {
+    limiter: RateLimiter,
+}
+
+/// Reads a DexScreener `tokens` response, from the deepest pair trading `mint` as its base
+pub fn parse_dexscreener(mint: &str, response: &Value) -> Option<MarketData> {
+    let pair = response["pairs"]
+        .as_array()?
+        .iter()
+        .filter(|pair| pair["baseToken"]["address"].as_str() == Some(mint))
+        .max_by(|a, b| {
+            let depth = |pair: &Value| number(&pair["liquidity"]["usd"]).unwrap_or(0.0);
+            depth(a).total_cmp(&depth(b))
+        })?;
+    Some(MarketData {
+        mint: mint.to_string(),
+        price_usd: number(&pair["priceUsd"]).filter(|price| *price > 0.0)?,
+        market_cap_usd: number(&pair["marketCap"]).or_else(|| number(&pair["fdv"])),
+        liquidity_usd: number(&pair["liquidity"]["usd"]),
+        source: "dexscreener",
+    })
+}
+
+#[async_trait]
+impl PriceProvider for DexScreener {
+    fn name(&self) -> &'static str {
+        "dexscreener"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, mint: &str) -> Result<Option<MarketData>> {
+        self.limiter.acquire().await;
+        let response = get_json(http.get(format!("{}/{}", DEXSCREENER_URL, mint))).await?;
+        Ok(parse_dexscreener(mint, &response))
+    }
+}
+
+/// Jupiter's price API; price only
+pub struct JupiterPrice {
+    limiter: RateLimiter,
+}
+
+/// Reads a Jupiter price response (`data.<mint>.price`, string or number)
+pub fn parse_jupiter_price(mint: &str, response: &Value) -> Option<MarketData> {
+    Some(MarketData {
+        mint: mint.to_string(),
+        price_usd: number(&response["data"][mint]["price"]).filter(|price| *price > 0.0)?,
+        market_cap_usd: None,
+        liquidity_usd: None,
+        source: "jupiter",
+    })
+}
+
+#[async_trait]
+impl PriceProvider for JupiterPrice {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, mint: &str) -> Result<Option<MarketData>> {
+        self.limiter.acquire().await;
+        let response = get_json(http.get(JUPITER_PRICE_URL).query(&[("ids", mint)])).await?;
+        Ok(parse_jupiter_price(mint, &response))
+    }
+}
+
+/// Builds the provider called `name`
+fn provider(name: &str) -> Result<Box<dyn PriceProvider>> {
+    match name {
+        "birdeye" => Ok(Box::new(Birdeye {
+            api_key: env::var("BIRDEYE_API_KEY").context("birdeye needs BIRDEYE_API_KEY")?,
+            limiter: RateLimiter::from_env(name, 1.0),
+        })),
+        "dexscreener" => Ok(Box::new(DexScreener {
+            limiter: RateLimiter::from_env(name, 4.0),
+        })),
+        "jupiter" => Ok(Box::new(JupiterPrice {
+            limiter: RateLimiter::from_env(name, 10.0),
+        })),
+        other => Err(anyhow!(
+            "Unknown price provider '{}'. Use birdeye, dexscreener or jupiter",
+            other
+        )),
+    }
+}
+
+/// Off-chain prices, market caps and liquidity from the configured providers, tried in order
+/// and cached, for valuing holdings in USD and checking on-chain quotes against the market
+pub struct PriceService {
+    providers: Vec<Box<dyn PriceProvider>>,
+    http: reqwest::Client,
+    cache: BoundedCache<String, MarketData>,
+    /// Largest gap, in percent, allowed between an on-chain quote and the market price
+    max_deviation_pct: Option<f64>,
+}
+
+impl PriceService {
+    pub fn new(providers: Vec<Box<dyn PriceProvider>>, max_deviation_pct: Option<f64>) -> Self {
+        Self {
+            providers,
+            http: reqwest::Client::new(),
+            cache: BoundedCache::from_env(
+                "prices",
+                CACHE_ENTRIES,
+                Some(Duration::from_secs(CACHE_TTL_SECS)),
+            ),
+            max_deviation_pct,
+        }
+    }
+
+    /// Reads `PRICE_PROVIDERS`, a comma-separated order of birdeye, dexscreener and jupiter
+    /// (default dexscreener,jupiter, with birdeye first when `BIRDEYE_API_KEY` is set), and
+    /// `PRICE_MAX_DEVIATION_PCT`, which turns on checking buys' on-chain quotes
+    pub fn from_env() -> Result<Self> {
+        let names =
+            env::var("PRICE_PROVIDERS").unwrap_or_else(|_| match env::var("BIRDEYE_API_KEY") {
+                Ok(_) => format!("birdeye,{}", DEFAULT_PROVIDERS),
+                Err(_) => DEFAULT_PROVIDERS.to_string(),
+            });
+        let providers = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(provider)
+            .collect::<Result<Vec<_>>>()?;
+        let max_deviation_pct = match env::var("PRICE_MAX_DEVIATION_PCT") {
+            Ok(pct) => Some(f64::from_str(&pct).context("Invalid PRICE_MAX_DEVIATION_PCT")?),
+            Err(_) => None,
+        };
+        Ok(Self::new(providers, max_deviation_pct))
+    }
+
+    /// Forgets every cached price
+    pub async fn clear_cache(&self) {
+        self.cache.clear().await;
+    }
+
+    /// Market data for `mint` from the first provider that has it
+    pub async fn market_data(&self, mint: &str) -> Result<MarketData> {
+        if let Some(data) = self.cache.get(&mint.to_string()).await {
+            return Ok(data);
+        }
+        let mut failures = Vec::new();
+        for provider in &self.providers {
+            match provider.fetch(&self.http, mint).await {
+                Ok(Some(data)) => {
+                    self.cache.insert(mint.to_string(), data.clone()).await;
+                    return Ok(data);
+                }
+                Ok(None) => failures.push(format!("{}: not listed", provider.name())),
+                Err(e) => failures.push(format!("{}: {}", provider.name(), e)),
+            }
+        }
+        Err(anyhow!("No price for {} ({})", mint, failures.join("; ")))
+    }
+
+    /// Whether buys' on-chain quotes are checked against the market
+    pub fn checks_quotes(&self) -> bool {
+        self.max_deviation_pct.is_some()
+    }
+
+    pub async fn sol_price_usd(&self) -> Result<f64> {
+        Ok(self.market_data(SOL_MINT).await?.price_usd)
+    }
+
+    /// Errors if `sol_per_token`, a price quoted on-chain, is further from the market price
+    /// than `PRICE_MAX_DEVIATION_PCT` allows. Passes when the check is off or the providers
+    /// can't price the mint, so an API outage never blocks trading
+    pub async fn check_quote(&self, mint: &str, sol_per_token: f64) -> Result<()> {
+        let Some(max_pct) = self.max_deviation_pct else {
+            return Ok(());
+        };
+        let (Ok(market), Ok(sol_usd)) = (self.market_data(mint).await, self.sol_price_usd().await)
+        else {
+            return Ok(());
+        };
+        let deviation = quote_deviation_pct(sol_per_token * sol_usd, market.price_usd);
+        if deviation > max_pct {
+            return Err(anyhow!(
+                "On-chain price ${:.10} is {:.1}% off {}'s ${:.10}",
+                sol_per_token * sol_usd,
+                deviation,
+                market.source,
+                market.price_usd
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How far `quoted` is from `market`, in percent of `market`
+pub fn quote_deviation_pct(quoted: f64, market: f64) -> f64 {
+    ((quoted - market) / market).abs() * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parses_provider_responses() {
+        let dexscreener = json!({ "pairs": [
+            { "baseToken": { "address": "mint" }, "priceUsd": "0.0012",
+              "liquidity": { "usd": 5000.0 }, "fdv": 1200000.0 },
+            { "baseToken": { "address": "mint" }, "priceUsd": "0.0011",
+              "liquidity": { "usd": 90000.0 }, "marketCap": 1100000.0 },
+            { "baseToken": { "address": "other" }, "priceUsd": "9",
+              "liquidity": { "usd": 1e9 } }
+        ]});
+        let data = parse_dexscreener("mint", &dexscreener).unwrap();
+        assert_eq!(data.price_usd, 0.0011);
+        assert_eq!(data.market_cap_usd, Some(1_100_000.0));
+        assert_eq!(data.liquidity_usd, Some(90_000.0));
+        assert!(parse_dexscreener("missing", &dexscreener).is_none());
+
+        let birdeye = json!({ "data": { "price": 0.5, "mc": 5e8, "liquidity": 2e6 } });
+        let data = parse_birdeye("mint", &birdeye).unwrap();
+        assert_eq!(
+            (data.market_cap_usd, data.liquidity_usd),
+            (Some(5e8), Some(2e6))
+        );
+
+        let jupiter = json!({ "data": { "mint": { "price": "150.25" } } });
+        assert_eq!(
+            parse_jupiter_price("mint", &jupiter).unwrap().price_usd,
+            150.25
+        );
+        assert!(parse_jupiter_price("mint", &json!({ "data": { "mint": null } })).is_none());
+
+        assert_eq!(quote_deviation_pct(1.2, 1.0).round(), 20.0);
+        assert!(provider("coingecko").is_err());
+    }
+}
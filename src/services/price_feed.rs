@@ -0,0 +1,244 @@
+use std::{collections::HashMap, env, str::FromStr, time::Duration};
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::{broadcast, RwLock},
+    time::Instant,
+};
+
+use crate::{
+    common::{cache::BoundedCache, utils::AppState},
+    dex::{
+        pump::{get_bonding_curve_account, Pump, PUMP_PROGRAM_ID},
+        raydium::Raydium,
+        raydium_clmm::RaydiumClmm,
+        raydium_cpmm::RaydiumCpmm,
+    },
+    engine::{router::Venue, swap::SwapDirection},
+    services::idle::idle_sleep,
+};
+
+// Configuration constants
+const DEFAULT_POLL_INTERVAL_MS: u64 = 2_000;
+const CHANNEL_CAPACITY: usize = 1_024;
+const CURVE_CACHE_ENTRIES: usize = 1_000;
+
+/// Latest observed price of a mint, in SOL per token
+#[derive(Debug, Clone)]
+pub struct PriceUpdate {
+    pub mint: String,
+    pub price: f64,
+    pub observed_at: Instant,
+}
+
+/// Virtual reserves of a bonding curve at one poll
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveReserves {
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+/// Net flow through a bonding curve between two polls, from everyone trading it
+#[derive(Debug, Clone)]
+pub struct CurveDelta {
+    pub mint: String,
+    /// Lamports that entered (positive) or left (negative) the curve
+    pub sol_delta: i64,
+    /// Raw tokens that entered (positive) or left (negative) the curve
+    pub token_delta: i64,
+    pub observed_at: Instant,
+}
+
+impl CurveDelta {
+    /// The change from `before` to `after`, or `None` if nothing traded
+    pub fn between(mint: &str, before: CurveReserves, after: CurveReserves) -> Option<Self> {
+        let sol_delta = after.virtual_sol_reserves as i64 - before.virtual_sol_reserves as i64;
+        let token_delta =
+            after.virtual_token_reserves as i64 - before.virtual_token_reserves as i64;
+        if sol_delta == 0 && token_delta == 0 {
+            return None;
+        }
+        Some(Self {
+            mint: mint.to_string(),
+            sol_delta,
+            token_delta,
+            observed_at: Instant::now(),
+        })
+    }
+
+    /// Net side other participants took: SOL flowing in means they bought
+    pub fn direction(&self) -> Option<SwapDirection> {
+        match self.sol_delta {
+            d if d > 0 => Some(SwapDirection::Buy),
+            d if d < 0 => Some(SwapDirection::Sell),
+            _ => None,
+        }
+    }
+
+    /// Size of the implied net trade, in lamports
+    pub fn implied_lamports(&self) -> u64 {
+        self.sol_delta.unsigned_abs()
+    }
+}
+
+/// Polls prices for every watched mint and broadcasts the results
+pub struct PriceFeed {
+    watched: RwLock<HashMap<String, usize>>,
+    sender: broadcast::Sender<PriceUpdate>,
+    curves: BoundedCache<String, CurveReserves>,
+    curve_sender: broadcast::Sender<CurveDelta>,
+    poll_interval: Duration,
+}
+
+impl Default for PriceFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceFeed {
+    /// Creates a feed polling every `PRICE_FEED_INTERVAL_MS`
+    pub fn new() -> Self {
+        let poll_interval_ms = env::var("PRICE_FEED_INTERVAL_MS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_POLL_INTERVAL_MS);
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (curve_sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self {
+            watched: RwLock::new(HashMap::new()),
+            sender,
+            curves: BoundedCache::from_env("curves", CURVE_CACHE_ENTRIES, None),
+            curve_sender,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+        }
+    }
+
+    /// Forgets every cached curve
+    pub async fn clear_cache(&self) {
+        self.curves.clear().await;
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PriceUpdate> {
+        self.sender.subscribe()
+    }
+
+    /// Reserve changes of watched bonding curves; curves are only polled while subscribed
+    pub fn subscribe_curves(&self) -> broadcast::Receiver<CurveDelta> {
+        self.curve_sender.subscribe()
+    }
+
+    /// Starts (or keeps) polling a mint; calls are reference counted
+    pub async fn watch(&self, mint: &str) {
+        *self
+            .watched
+            .write()
+            .await
+            .entry(mint.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// Releases one watch on a mint, stopping the polling once nobody needs it
+    pub async fn unwatch(&self, mint: &str) {
+        let mut watched = self.watched.write().await;
+        if let Some(count) = watched.get_mut(mint) {
+            *count -= 1;
+            if *count == 0 {
+                watched.remove(mint);
+                self.curves.remove(&mint.to_string()).await;
+            }
+        }
+    }
+
+    pub async fn watched_mints(&self) -> Vec<String> {
+        self.watched.read().await.keys().cloned().collect()
+    }
+}
+
+/// Fetches the current price of a mint from whichever venue holds its liquidity
+pub async fn fetch_price(state: &AppState, mint: &str) -> Result<f64> {
+    match state.router.route(state, mint).await? {
+        Venue::BondingCurve => {
+            if let Some(price) = state.curves.price(mint).await {
+                return Ok(price);
+            }
+            let pump = Pump::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            pump.get_token_price(mint).await
+        }
+        Venue::Raydium { pool } => {
+            let raydium = Raydium::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            raydium.get_token_price(mint, Some(&pool.to_string())).await
+        }
+        Venue::RaydiumClmm { pool } => {
+            let clmm = RaydiumClmm::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            clmm.get_token_price(mint, &pool).await
+        }
+        Venue::RaydiumCpmm { pool } => {
+            let cpmm = RaydiumCpmm::new(
+                state.rpc_nonblocking_client.clone(),
+                state.rpc_client.clone(),
+                state.wallet.clone(),
+            );
+            cpmm.get_token_price(mint, &pool).await
+        }
+        venue => Err(anyhow::anyhow!("No on-chain price source for {:?}", venue)),
+    }
+}
+
+/// Reads a bonding curve's reserves and publishes the change since the last poll
+async fn poll_curve(state: &AppState, mint: &str) -> Result<()> {
+    let mint_pubkey = Pubkey::from_str(mint)?;
+    let (_, _, curve) =
+        get_bonding_curve_account(state.rpc_client.clone(), &mint_pubkey, &PUMP_PROGRAM_ID).await?;
+    let reserves = CurveReserves {
+        virtual_sol_reserves: curve.virtual_sol_reserves,
+        virtual_token_reserves: curve.virtual_token_reserves,
+    };
+
+    let feed = &state.price_feed;
+    let previous = feed.curves.insert(mint.to_string(), reserves).await;
+    if let Some(delta) = previous.and_then(|before| CurveDelta::between(mint, before, reserves)) {
+        let _ = feed.curve_sender.send(delta);
+    }
+    Ok(())
+}
+
+/// Polls every watched mint forever, publishing updates to subscribers
+pub async fn run_price_feed(state: AppState) {
+    let feed = state.price_feed.clone();
+    loop {
+        for mint in feed.watched_mints().await {
+            if let Ok(price) = fetch_price(&state, &mint).await {
+                // No subscribers is fine; updates are simply dropped
+                let _ = feed.sender.send(PriceUpdate {
+                    mint: mint.clone(),
+                    price,
+                    observed_at: Instant::now(),
+                });
+            }
+            if feed.curve_sender.receiver_count() > 0
+                && matches!(
+                    state.router.route(&state, &mint).await,
+                    Ok(Venue::BondingCurve)
+                )
+            {
+                let _ = poll_curve(&state, &mint).await;
+            }
+        }
+        idle_sleep(feed.poll_interval).await;
+    }
+}
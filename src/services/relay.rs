@@ -0,0 +1,133 @@
+use std::{env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+
+use crate::core::{
+    rpc_pool::RPC_POOL,
+    tx::{TxSender, TxSigner},
+};
+
+// Configuration constants
+const DEFAULT_BLOXROUTE_URL: &str = "https://ny.solana.dex.blxrbdn.com";
+const DEFAULT_NEXTBLOCK_URL: &str = "https://ny.nextblock.io";
+const BLOXROUTE_TIP_ACCOUNT: &str = "HWEoBxYs7ssKuudEjzjmpfJVX7Dvi7wescFsVx2L5yoY";
+const NEXTBLOCK_TIP_ACCOUNT: &str = "NextbLoCkVtMGcV47JzewQdvBpLqT9TxQFozQkN98pE";
+const DEFAULT_TIP_SOL: f64 = 0.001;
+const CONFIRMATION_TIMEOUT_SECS: u64 = 60;
+
+/// A third-party relay taking base64 transactions over HTTP, paid by a tip inside the transaction
+pub struct RelaySender {
+    name: &'static str,
+    submit_url: String,
+    auth_header: String,
+    tip_account: Pubkey,
+    tip_lamports: u64,
+    http: reqwest::Client,
+}
+
+impl RelaySender {
+    /// bloXroute Trader API; needs `BLOXROUTE_AUTH_HEADER`, see `from_env` for the optional settings
+    pub fn bloxroute() -> Result<Self> {
+        Self::from_env(
+            "bloXroute",
+            "BLOXROUTE",
+            DEFAULT_BLOXROUTE_URL,
+            BLOXROUTE_TIP_ACCOUNT,
+        )
+    }
+
+    /// Nextblock; needs `NEXTBLOCK_AUTH_HEADER`, see `from_env` for the optional settings
+    pub fn nextblock() -> Result<Self> {
+        Self::from_env(
+            "Nextblock",
+            "NEXTBLOCK",
+            DEFAULT_NEXTBLOCK_URL,
+            NEXTBLOCK_TIP_ACCOUNT,
+        )
+    }
+
+    /// Reads `<PREFIX>_AUTH_HEADER` plus optional `_URL`, `_TIP_SOL` and `_TIP_ACCOUNT` overrides
+    fn from_env(
+        name: &'static str,
+        prefix: &str,
+        default_url: &str,
+        tip_account: &str,
+    ) -> Result<Self> {
+        let auth_header = env::var(format!("{}_AUTH_HEADER", prefix))
+            .with_context(|| format!("{}_AUTH_HEADER not set", prefix))?;
+        let base_url = env::var(format!("{}_URL", prefix)).unwrap_or(default_url.to_string());
+        let tip_sol = env::var(format!("{}_TIP_SOL", prefix))
+            .ok()
+            .and_then(|v| f64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_TIP_SOL);
+
+        Ok(Self {
+            name,
+            submit_url: format!("{}/api/v2/submit", base_url.trim_end_matches('/')),
+            auth_header,
+            tip_account: Pubkey::from_str(
+                &env::var(format!("{}_TIP_ACCOUNT", prefix)).unwrap_or(tip_account.to_string()),
+            )?,
+            tip_lamports: sol_to_lamports(tip_sol),
+            http: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl TxSender for RelaySender {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn tip_instruction(&self, payer: &Pubkey) -> Option<Instruction> {
+        Some(system_instruction::transfer(
+            payer,
+            &self.tip_account,
+            self.tip_lamports,
+        ))
+    }
+
+    async fn send(
+        &self,
+        _keypair: &TxSigner,
+        versioned_tx: VersionedTransaction,
+        _recent_blockhash: &Hash,
+    ) -> Result<String> {
+        let content = base64::encode(bincode::serialize(&versioned_tx)?);
+        let response: Value = self
+            .http
+            .post(&self.submit_url)
+            .header("Authorization", &self.auth_header)
+            .json(&json!({
+                "transaction": { "content": content },
+                "frontRunningProtection": false,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let signature = response["signature"]
+            .as_str()
+            .and_then(|s| Signature::from_str(s).ok())
+            .or_else(|| versioned_tx.signatures.first().copied())
+            .ok_or_else(|| anyhow!("{} returned no signature: {}", self.name, response))?;
+        RPC_POOL
+            .confirm_any(&signature, Duration::from_secs(CONFIRMATION_TIMEOUT_SECS))
+            .await?;
+        Ok(signature.to_string())
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use solana_sdk::{
+    compute_budget,
+    message::VersionedMessage,
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, SignerError},
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    common::{utils::log_message, wallet::load_wallet},
+    dex::{
+        jupiter::JUPITER_PROGRAM_ID,
+        pump::{PUMP_AMM_PROGRAM_ID, PUMP_BUY_METHOD, PUMP_PROGRAM_ID},
+        raydium::AMM_PROGRAM_ID,
+        raydium_clmm::{CLMM_PROGRAM_ID, MEMO_PROGRAM_ID},
+        raydium_cpmm::CPMM_PROGRAM_ID,
+    },
+    services::{api::read_request, sources::header},
+};
+
+// Configuration constants
+const DEFAULT_SIGNER_ADDR: &str = "127.0.0.1:7001";
+const SIGN_TIMEOUT_SECS: u64 = 5;
+
+type SignRequest = (
+    Vec<u8>,
+    mpsc::Sender<std::result::Result<Signature, SignerError>>,
+);
+
+/// What the signing service will put its key to: SOL leaving the wallet per transaction and
+/// the programs a transaction may call
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningPolicy {
+    pub max_lamports: Option<u64>,
+    pub allowed_programs: Vec<Pubkey>,
+}
+
+impl SigningPolicy {
+    /// Reads `SIGNER_MAX_SOL_PER_TX` and `SIGNER_ALLOWED_PROGRAMS` (comma-separated, default
+    /// the programs the bot trades through)
+    pub fn from_env() -> Result<Self> {
+        let max_lamports = env::var("SIGNER_MAX_SOL_PER_TX")
+            .ok()
+            .map(|v| f64::from_str(&v).map(sol_to_lamports))
+            .transpose()
+            .context("Invalid SIGNER_MAX_SOL_PER_TX")?;
+        let allowed_programs = match env::var("SIGNER_ALLOWED_PROGRAMS") {
+            Ok(programs) => programs
+                .split(',')
+                .map(str::trim)
+                .filter(|program| !program.is_empty())
+                .map(|program| {
+                    Pubkey::from_str(program).with_context(|| {
+                        format!("Invalid program in SIGNER_ALLOWED_PROGRAMS: {}", program)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Err(_) => Self::default_programs(),
+        };
+        Ok(Self {
+            max_lamports,
+            allowed_programs,
+        })
+    }
+
+    fn default_programs() -> Vec<Pubkey> {
+        vec![
+            system_program::id(),
+            compute_budget::id(),
+            spl_token::ID,
+            spl_token_2022::ID,
+            spl_associated_token_account::ID,
+            PUMP_PROGRAM_ID,
+            PUMP_AMM_PROGRAM_ID,
+            AMM_PROGRAM_ID,
+            CPMM_PROGRAM_ID,
+            CLMM_PROGRAM_ID,
+            MEMO_PROGRAM_ID,
+            JUPITER_PROGRAM_ID,
+        ]
+    }
+
+    /// Lamports `message` can move out of `wallet` through its top-level instructions: system
+    /// transfers and account creations it funds, which covers tips and wrapped SOL, plus the
+    /// `max_sol_cost` of pump.fun buys. Swaps paying from wrapped SOL are bounded by the
+    /// transfer that funded it
+    pub fn spend(message: &VersionedMessage, wallet: &Pubkey) -> u64 {
+        let keys = message.static_account_keys();
+        let key = |index: u8| keys.get(index as usize);
+        let system = system_program::id();
+        message
+            .instructions()
+            .iter()
+            .map(|ix| {
+                let program = key(ix.program_id_index);
+                let funder = ix.accounts.first().and_then(|index| key(*index));
+                if program == Some(&system) && funder == Some(wallet) {
+                    return match bincode::deserialize::<SystemInstruction>(&ix.data) {
+                        Ok(SystemInstruction::Transfer { lamports })
+                        | Ok(SystemInstruction::CreateAccount { lamports, .. })
+                        | Ok(SystemInstruction::CreateAccountWithSeed { lamports, .. })
+                        | Ok(SystemInstruction::TransferWithSeed { lamports, .. }) => lamports,
+                        _ => 0,
+                    };
+                }
+                let is_buy = ix.data.len() >= 24 && ix.data[..8] == PUMP_BUY_METHOD.to_le_bytes();
+                if program == Some(&PUMP_PROGRAM_ID) && is_buy {
+                    return u64::from_le_bytes(ix.data[16..24].try_into().unwrap());
+                }
+                0
+            })
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Why `message` may not be signed for `wallet`, or `None` if it may
+    pub fn rejects(&self, message: &VersionedMessage, wallet: &Pubkey) -> Option<String> {
+        let keys = message.static_account_keys();
+        for ix in message.instructions() {
+            let Some(program) = keys.get(ix.program_id_index as usize) else {
+                return Some("instruction calls an unknown program".to_string());
+            };
+            if !self.allowed_programs.contains(program) {
+                return Some(format!("program {} is not allowed", program));
+            }
+        }
+        let spend = Self::spend(message, wallet);
+        match self.max_lamports {
+            Some(max) if spend > max => Some(format!(
+                "spends {:.4} SOL, over the {:.4} SOL limit",
+                lamports_to_sol(spend),
+                lamports_to_sol(max)
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Signs through a signing service (`wallet serve-signer` on another machine, or anything
+/// speaking its protocol), so the trading box never holds the key. The service applies its own
+/// `SigningPolicy` and refuses transactions outside it.
+///
+/// `Signer` is synchronous, so requests go through a thread of their own with a blocking HTTP
+/// client, and each signature blocks its caller for a round trip to the service. It reports
+/// itself interactive so `tx::signing` takes that wait off the async workers
+pub struct RemoteSigner {
+    pubkey: Pubkey,
+    requests: mpsc::Sender<SignRequest>,
+}
+
+impl RemoteSigner {
+    /// Connects to `WALLET_SIGNER_URL`, authenticating with `WALLET_SIGNER_TOKEN`
+    pub fn from_env() -> Result<Self> {
+        let url = env::var("WALLET_SIGNER_URL").context("WALLET_SIGNER_URL is not set")?;
+        let token = env::var("WALLET_SIGNER_TOKEN").context("WALLET_SIGNER_TOKEN is not set")?;
+        Self::connect(url.trim_end_matches('/').to_string(), token)
+    }
+
+    pub fn connect(url: String, token: String) -> Result<Self> {
+        let (requests, inbox) = mpsc::channel::<SignRequest>();
+        let (ready, connected) = mpsc::channel::<Result<Pubkey>>();
+        thread::Builder::new()
+            .name("remote-signer".to_string())
+            .spawn(move || {
+                let client = match reqwest::blocking::Client::builder()
+                    .timeout(Duration::from_secs(SIGN_TIMEOUT_SECS))
+                    .build()
+                {
+                    Ok(client) => client,
+                    Err(e) => {
+                        let _ = ready.send(Err(e.into()));
+                        return;
+                    }
+                };
+                let call = |request: reqwest::blocking::RequestBuilder| -> Result<Value> {
+                    let response = request.bearer_auth(&token).send()?;
+                    let status = response.status();
+                    let body = response.json::<Value>()?;
+                    if !status.is_success() {
+                        let reason = body["error"].as_str().unwrap_or("no reason given");
+                        return Err(anyhow!("Signer refused ({}): {}", status, reason));
+                    }
+                    Ok(body)
+                };
+                let pubkey = call(client.get(format!("{}/pubkey", url))).and_then(|body| {
+                    let pubkey = body["pubkey"].as_str().unwrap_or_default();
+                    Pubkey::from_str(pubkey).context("Signer sent an invalid pubkey")
+                });
+                let pubkey = match pubkey {
+                    Ok(pubkey) => pubkey,
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(pubkey));
+                for (message, reply) in inbox {
+                    let request = client
+                        .post(format!("{}/sign", url))
+                        .json(&json!({ "message": base64::encode(&message) }));
+                    let signature = call(request)
+                        .and_then(|body| {
+                            let signature = body["signature"].as_str().unwrap_or_default();
+                            Signature::from_str(signature)
+                                .context("Signer sent an invalid signature")
+                        })
+                        .map_err(|e| SignerError::Custom(e.to_string()));
+                    let _ = reply.send(signature);
+                }
+            })?;
+        let pubkey = connected
+            .recv()
+            .map_err(|_| anyhow!("Remote signer thread exited"))?
+            .context("Failed to reach the remote signer")?;
+        Ok(Self { pubkey, requests })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        let lost = || SignerError::Connection("Remote signer thread exited".to_string());
+        let (reply, signed) = mpsc::channel();
+        self.requests
+            .send((message.to_vec(), reply))
+            .map_err(|_| lost())?;
+        let signature = signed.recv().map_err(|_| lost())?;
+        // A forged signature would only fail at the cluster, so it's caught here
+        match signature {
+            Ok(signature) if !signature.verify(self.pubkey.as_ref(), message) => Err(
+                SignerError::Custom("Remote signer returned a bad signature".to_string()),
+            ),
+            signature => signature,
+        }
+    }
+
+    /// Every signature waits on the service, which may also hold it for approval
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+/// Signs one request's message if `policy` allows it
+fn handle_sign(keypair: &Keypair, policy: &SigningPolicy, body: &[u8]) -> (&'static str, Value) {
+    let error = |status, message: String| (status, json!({ "error": message }));
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| base64::decode(body["message"].as_str()?).ok());
+    let Some(bytes) = message else {
+        return error("400 Bad Request", "message must be base64".to_string());
+    };
+    let Ok(message) = bincode::deserialize::<VersionedMessage>(&bytes) else {
+        return error(
+            "400 Bad Request",
+            "message is not a transaction message".to_string(),
+        );
+    };
+    if let Some(reason) = policy.rejects(&message, &keypair.pubkey()) {
+        return error("403 Forbidden", reason);
+    }
+    let signature = keypair.sign_message(&bytes);
+    ("200 OK", json!({ "signature": signature.to_string() }))
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    token: &str,
+    keypair: &Keypair,
+    policy: &SigningPolicy,
+) -> Result<()> {
+    let (status, body) = match read_request(&mut stream).await {
+        Err(status) => (status, json!({ "error": status })),
+        Ok((head, body)) => {
+            let expected = format!("Bearer {}", token);
+            let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+            let route = (request_line.next(), request_line.next());
+            match route {
+                _ if header(&head, "authorization") != Some(expected.as_str()) => (
+                    "401 Unauthorized",
+                    json!({ "error": "Missing or wrong token" }),
+                ),
+                (Some("GET"), Some("/pubkey")) => {
+                    ("200 OK", json!({ "pubkey": keypair.pubkey().to_string() }))
+                }
+                (Some("POST"), Some("/sign")) => {
+                    let (status, response) = handle_sign(keypair, policy, &body);
+                    if let Some(reason) = response["error"].as_str() {
+                        let _ = log_message(&format!("Refused to sign: {}", reason)).await;
+                    }
+                    (status, response)
+                }
+                _ => ("404 Not Found", json!({ "error": "No such endpoint" })),
+            }
+        }
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Runs the signing service for the wallet `load_wallet` finds, on `SIGNER_ADDR` (default
+/// `127.0.0.1:7001`) with token `SIGNER_TOKEN`, under the `SigningPolicy` from the environment.
+/// The protocol is plain HTTP, so expose it over a private network or a TLS tunnel
+pub async fn run_signer_service() -> Result<()> {
+    let addr = env::var("SIGNER_ADDR").unwrap_or_else(|_| DEFAULT_SIGNER_ADDR.to_string());
+    let token = env::var("SIGNER_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| anyhow!("SIGNER_TOKEN must be set"))?;
+    let policy = SigningPolicy::from_env()?;
+    let keypair = Arc::new(load_wallet()?);
+    let listener = TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("Failed to bind {}", addr))?;
+    let limit = policy
+        .max_lamports
+        .map(|max| format!("{:.4} SOL per transaction", lamports_to_sol(max)))
+        .unwrap_or_else(|| "no SOL limit".to_string());
+    let _ = log_message(&format!(
+        "Signing for {} on http://{} ({}, {} programs allowed)",
+        keypair.pubkey(),
+        addr,
+        limit,
+        policy.allowed_programs.len()
+    ))
+    .await;
+    let (token, policy) = (Arc::new(token), Arc::new(policy));
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let (token, keypair, policy) = (token.clone(), keypair.clone(), policy.clone());
+        tokio::spawn(async move {
+            let _ = serve(stream, &token, &keypair, &policy).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{instruction::Instruction, message::Message, system_instruction};
+
+    #[test]
+    fn test_policy() {
+        let wallet = Keypair::new().pubkey();
+        let tip = system_instruction::transfer(&wallet, &Pubkey::new_unique(), 1_000_000);
+        let wrap = system_instruction::transfer(&wallet, &Pubkey::new_unique(), 400_000_000);
+        let message =
+            |ixs: &[Instruction]| VersionedMessage::Legacy(Message::new(ixs, Some(&wallet)));
+        let policy = SigningPolicy {
+            max_lamports: Some(sol_to_lamports(0.5)),
+            allowed_programs: SigningPolicy::default_programs(),
+        };
+
+        let swap = message(&[tip.clone(), wrap.clone()]);
+        assert_eq!(SigningPolicy::spend(&swap, &wallet), 401_000_000);
+        assert_eq!(policy.rejects(&swap, &wallet), None);
+        let too_big = message(&[tip.clone(), wrap.clone(), wrap]);
+        assert_eq!(
+            policy.rejects(&too_big, &wallet).unwrap(),
+            "spends 0.8010 SOL, over the 0.5000 SOL limit"
+        );
+        let unknown = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let program = unknown.program_id;
+        assert_eq!(
+            policy.rejects(&message(&[tip, unknown]), &wallet).unwrap(),
+            format!("program {} is not allowed", program)
+        );
+    }
+}
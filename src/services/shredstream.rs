@@ -0,0 +1,261 @@
+// Jito ShredStream signal source. Only built with the `shredstream` feature, since the proxy
+// needs an approved ShredStream key.
+
+use std::{collections::VecDeque, env};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, transaction::VersionedTransaction};
+use tonic::{
+    codec::{ProstCodec, Streaming},
+    codegen::http::uri::PathAndQuery,
+    transport::Endpoint,
+};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    dex::pump::{PUMP_BUY_METHOD, PUMP_PROGRAM_ID},
+    services::sources::{SignalEvent, SignalSource},
+};
+
+// Configuration constants
+const SUBSCRIBE_ENTRIES: &str = "/shredstream.ShredstreamProxy/SubscribeEntries";
+const PUMP_TOKEN_DECIMALS: u8 = 6;
+// Positions of the mint and buyer in pump.fun's `buy` accounts
+const BUY_MINT_INDEX: usize = 2;
+const BUY_USER_INDEX: usize = 6;
+
+/// Messages of the proxy's `shredstream.proto`
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeEntriesRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Entry {
+        #[prost(uint64, tag = "1")]
+        pub slot: u64,
+        /// bincode `Vec<solana_entry::entry::Entry>`
+        #[prost(bytes = "vec", tag = "2")]
+        pub entries: Vec<u8>,
+    }
+}
+
+/// bincode layout of `solana_entry::entry::Entry`
+#[derive(Deserialize)]
+struct Entry {
+    _num_hashes: u64,
+    _hash: Hash,
+    transactions: Vec<VersionedTransaction>,
+}
+
+/// Transactions rebuilt from shreds by a ShredStream proxy (`SHREDSTREAM_URL`), seen before
+/// their block is produced, let alone confirmed.
+///
+/// Entries carry no execution results, so only a target's direct pump.fun buys are turned into
+/// events, with balances synthesised from the instruction: `max_sol_cost` stands in for the
+/// SOL spent and the transaction may still fail. The RPC feeds deliver the same signature
+/// later and the hub drops it; sells are left to them, as mirroring a partial exit needs the
+/// target's balance. Nothing is replayed, the websocket source backfills the targets.
+pub struct ShredstreamSource {
+    url: String,
+    state: AppState,
+    stream: Option<Streaming<proto::Entry>>,
+    pending: VecDeque<SignalEvent>,
+}
+
+impl ShredstreamSource {
+    pub fn from_env(state: AppState) -> Option<Self> {
+        Some(Self {
+            url: env::var("SHREDSTREAM_URL").ok()?,
+            state,
+            stream: None,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// A pump.fun buy signed by one of `targets`, as a `transactionSubscribe` notification whose
+/// balances show the instruction's token amount and SOL ceiling
+pub fn pump_buy_notification(
+    tx: &VersionedTransaction,
+    slot: u64,
+    targets: &[String],
+) -> Option<Value> {
+    let message = &tx.message;
+    let keys = message.static_account_keys();
+    let signers = message.header().num_required_signatures as usize;
+    // Accounts behind lookup tables can't be resolved here; those buys wait for the RPC feeds
+    let key = |index: u8| keys.get(index as usize).map(|key| key.to_string());
+    message.instructions().iter().find_map(|ix| {
+        if keys.get(ix.program_id_index as usize)? != &PUMP_PROGRAM_ID || ix.data.len() < 24 {
+            return None;
+        }
+        if u64::from_le_bytes(ix.data[..8].try_into().ok()?) != PUMP_BUY_METHOD {
+            return None;
+        }
+        let user_index = *ix.accounts.get(BUY_USER_INDEX)?;
+        let user = key(user_index)?;
+        if (user_index as usize) >= signers || !targets.contains(&user) {
+            return None;
+        }
+        let mint = key(*ix.accounts.get(BUY_MINT_INDEX)?)?;
+        let token_amount = u64::from_le_bytes(ix.data[8..16].try_into().ok()?);
+        let max_sol_cost = u64::from_le_bytes(ix.data[16..24].try_into().ok()?);
+
+        let account_keys = keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| json!({ "pubkey": key.to_string(), "signer": i < signers }))
+            .collect::<Vec<_>>();
+        let mut pre_balances = vec![0u64; keys.len()];
+        pre_balances[user_index as usize] = max_sol_cost;
+        let token_balance = |amount: u64| {
+            json!([{
+                "owner": user,
+                "mint": mint,
+                "uiTokenAmount": {
+                    "amount": amount.to_string(),
+                    "decimals": PUMP_TOKEN_DECIMALS,
+                },
+            }])
+        };
+        let instruction_accounts = ix
+            .accounts
+            .iter()
+            .filter_map(|i| key(*i))
+            .collect::<Vec<_>>();
+        let signature = tx.signatures.first()?.to_string();
+        Some(json!({ "params": { "result": {
+            "signature": signature,
+            "slot": slot,
+            "transaction": {
+                "transaction": {
+                    "signatures": [signature],
+                    "message": {
+                        "accountKeys": account_keys,
+                        "instructions": [{
+                            "programId": PUMP_PROGRAM_ID.to_string(),
+                            "accounts": instruction_accounts,
+                            "data": bs58::encode(&ix.data).into_string(),
+                        }],
+                    },
+                },
+                "meta": {
+                    "err": null,
+                    "fee": 0,
+                    "preBalances": pre_balances,
+                    "postBalances": vec![0u64; keys.len()],
+                    "preTokenBalances": token_balance(0),
+                    "postTokenBalances": token_balance(token_amount),
+                    "logMessages": [],
+                },
+            },
+        }}}))
+    })
+}
+
+#[async_trait]
+impl SignalSource for ShredstreamSource {
+    fn name(&self) -> &'static str {
+        "shredstream"
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let channel = Endpoint::from_shared(self.url.clone())
+            .context("Invalid SHREDSTREAM_URL")?
+            .connect()
+            .await
+            .context("Failed to connect to the ShredStream proxy")?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await?;
+        let stream = grpc
+            .server_streaming(
+                tonic::Request::new(proto::SubscribeEntriesRequest {}),
+                PathAndQuery::from_static(SUBSCRIBE_ENTRIES),
+                ProstCodec::default(),
+            )
+            .await?
+            .into_inner();
+        self.stream = Some(stream);
+        let _ = log_message(&format!(
+            "Subscribed to ShredStream entries at {}",
+            self.url
+        ))
+        .await;
+        Ok(())
+    }
+
+    async fn next(&mut self) -> Result<SignalEvent> {
+        let name = self.name();
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            let stream = self
+                .stream
+                .as_mut()
+                .ok_or_else(|| anyhow!("Not connected"))?;
+            let message = match stream.message().await {
+                Ok(Some(message)) => message,
+                Ok(None) => {
+                    self.stream = None;
+                    return Err(anyhow!("ShredStream closed"));
+                }
+                Err(e) => {
+                    self.stream = None;
+                    return Err(e.into());
+                }
+            };
+            // Entries from a partial or corrupt slot are skipped, not fatal
+            let Ok(entries) = bincode::deserialize::<Vec<Entry>>(&message.entries) else {
+                continue;
+            };
+            let settings = self.state.settings.current();
+            self.pending.extend(
+                entries
+                    .iter()
+                    .flat_map(|entry| &entry.transactions)
+                    .filter_map(|tx| pump_buy_notification(tx, message.slot, &settings.targets))
+                    .filter_map(|json| SignalEvent::from_notification(name, json)),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{signal::parse_trade_signal, swap::SwapDirection};
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        transaction::Transaction,
+    };
+
+    #[test]
+    fn test_target_buys_become_signals() {
+        let (target, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = PUMP_BUY_METHOD.to_le_bytes().to_vec();
+        data.extend(1_000_000_000u64.to_le_bytes());
+        data.extend(250_000_000u64.to_le_bytes());
+        let mut accounts = (0..7)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect::<Vec<_>>();
+        accounts[BUY_MINT_INDEX] = AccountMeta::new_readonly(mint, false);
+        accounts[BUY_USER_INDEX] = AccountMeta::new(target, true);
+        let ix = Instruction::new_with_bytes(PUMP_PROGRAM_ID, &data, accounts);
+        let tx = VersionedTransaction::from(Transaction::new_with_payer(&[ix], Some(&target)));
+
+        let json = pump_buy_notification(&tx, 42, &[target.to_string()]).unwrap();
+        let signal = parse_trade_signal(&json, &target.to_string()).unwrap();
+        assert_eq!(signal.mint, mint.to_string());
+        assert!(matches!(signal.direction, SwapDirection::Buy));
+        assert_eq!(
+            (signal.token_amount, signal.sol_amount, signal.slot),
+            (1_000_000_000, 250_000_000, 42)
+        );
+        assert!(pump_buy_notification(&tx, 42, &[Pubkey::new_unique().to_string()]).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::VecDeque, env, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    common::utils::{log_message, AppState},
+    services::notify::Event,
+};
+
+// Configuration constants
+const DEFAULT_WINDOW_SECS: u64 = 300;
+const DEFAULT_MIN_SAMPLES: usize = 20;
+
+/// Where the time between seeing a signal and our transaction landing went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeLatency {
+    /// Signal seen to transaction submitted: parsing, sizing, approvals and safety checks
+    pub decide: Duration,
+    /// Submitted to confirmed
+    pub land: Duration,
+}
+
+impl TradeLatency {
+    /// Splits a trade at the moment it was handed to the sender
+    pub fn between(detected: Instant, submitted: Instant) -> Self {
+        Self {
+            decide: submitted.saturating_duration_since(detected),
+            land: submitted.elapsed(),
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.decide + self.land
+    }
+}
+
+/// A latency objective: the `percentile`th detection-to-land time stays under `threshold`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySlo {
+    pub percentile: f64,
+    pub threshold: Duration,
+}
+
+impl FromStr for LatencySlo {
+    type Err = anyhow::Error;
+
+    /// Parses `p<percentile>:<threshold ms>`, e.g. `p95:2000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (percentile, threshold_ms) = s
+            .trim()
+            .strip_prefix('p')
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(|| anyhow!("Invalid latency SLO: '{}'. Use p<pct>:<ms>", s))?;
+        let percentile = f64::from_str(percentile)?;
+        if percentile <= 0.0 || percentile > 100.0 {
+            return Err(anyhow!("SLO percentile must be in (0, 100]: '{}'", s));
+        }
+        Ok(Self {
+            percentile,
+            threshold: Duration::from_millis(u64::from_str(threshold_ms)?),
+        })
+    }
+}
+
+impl std::fmt::Display for LatencySlo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "p{} detection-to-land < {:?}",
+            self.percentile, self.threshold
+        )
+    }
+}
+
+/// Nearest-rank percentile of `values`, which must be sorted
+fn percentile(values: &[Duration], pct: f64) -> Duration {
+    if values.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((pct / 100.0) * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+struct SloState {
+    samples: VecDeque<(Instant, TradeLatency)>,
+    /// Whether each SLO was in breach at the last check, so alerts fire on transitions only
+    breached: Vec<bool>,
+}
+
+/// Tracks landed trades over a sliding window and reports SLOs crossing in and out of breach
+pub struct SloMonitor {
+    slos: Vec<LatencySlo>,
+    window: Duration,
+    min_samples: usize,
+    state: Mutex<SloState>,
+}
+
+impl SloMonitor {
+    pub fn new(slos: Vec<LatencySlo>, window: Duration, min_samples: usize) -> Self {
+        let breached = vec![false; slos.len()];
+        Self {
+            slos,
+            window,
+            min_samples: min_samples.max(1),
+            state: Mutex::new(SloState {
+                samples: VecDeque::new(),
+                breached,
+            }),
+        }
+    }
+
+    /// Reads `LATENCY_SLOS` (comma-separated, e.g. `p95:2000,p50:800`; unset disables),
+    /// `LATENCY_SLO_WINDOW_SECS` and `LATENCY_SLO_MIN_SAMPLES`
+    pub fn from_env() -> Result<Self> {
+        let slos = env::var("LATENCY_SLOS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(LatencySlo::from_str)
+            .collect::<Result<Vec<_>>>()?;
+        let window_secs = env::var("LATENCY_SLO_WINDOW_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_WINDOW_SECS);
+        let min_samples = env::var("LATENCY_SLO_MIN_SAMPLES")
+            .ok()
+            .and_then(|v| usize::from_str(&v).ok())
+            .unwrap_or(DEFAULT_MIN_SAMPLES);
+        Ok(Self::new(
+            slos,
+            Duration::from_secs(window_secs),
+            min_samples,
+        ))
+    }
+
+    /// Adds a landed trade, returning a message for every SLO that entered or left breach
+    pub async fn record(&self, latency: TradeLatency) -> Vec<String> {
+        if self.slos.is_empty() {
+            return vec![];
+        }
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+        state.samples.push_back((now, latency));
+        while state
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > self.window)
+        {
+            state.samples.pop_front();
+        }
+        if state.samples.len() < self.min_samples {
+            return vec![];
+        }
+
+        let sorted = |stage: fn(&TradeLatency) -> Duration| {
+            let mut values = state
+                .samples
+                .iter()
+                .map(|(_, latency)| stage(latency))
+                .collect::<Vec<_>>();
+            values.sort();
+            values
+        };
+        let totals = sorted(TradeLatency::total);
+        let decides = sorted(|latency| latency.decide);
+        let lands = sorted(|latency| latency.land);
+
+        let mut messages = vec![];
+        for (i, slo) in self.slos.iter().enumerate() {
+            let observed = percentile(&totals, slo.percentile);
+            let breached = observed >= slo.threshold;
+            if breached == state.breached[i] {
+                continue;
+            }
+            state.breached[i] = breached;
+            messages.push(if breached {
+                format!(
+                    "⏱️ SLO breached: {} (observed {:?} over {} trades in {:?}). \
+                     p{} by stage: decide {:?}, land {:?}",
+                    slo,
+                    observed,
+                    totals.len(),
+                    self.window,
+                    slo.percentile,
+                    percentile(&decides, slo.percentile),
+                    percentile(&lands, slo.percentile),
+                )
+            } else {
+                format!("✅ SLO recovered: {} (observed {:?})", slo, observed)
+            });
+        }
+        messages
+    }
+}
+
+/// Records a landed trade and alerts the operators on any SLO transition
+pub async fn observe_latency(state: &AppState, latency: TradeLatency) {
+    for message in state.slo.record(latency).await {
+        let _ = log_message(&message).await;
+        state.notifier.notify(Event::Info(message)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(decide_ms: u64, land_ms: u64) -> TradeLatency {
+        TradeLatency {
+            decide: Duration::from_millis(decide_ms),
+            land: Duration::from_millis(land_ms),
+        }
+    }
+
+    #[test]
+    fn test_parse_slo() {
+        assert_eq!(
+            LatencySlo::from_str("p95:2000").unwrap(),
+            LatencySlo {
+                percentile: 95.0,
+                threshold: Duration::from_millis(2_000),
+            }
+        );
+        assert!(LatencySlo::from_str("95:2000").is_err());
+        assert!(LatencySlo::from_str("p150:2000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_alerts_on_breach_and_recovery() {
+        let slo = LatencySlo::from_str("p50:1000").unwrap();
+        let monitor = SloMonitor::new(vec![slo], Duration::from_secs(60), 2);
+        assert!(monitor.record(latency(100, 300)).await.is_empty());
+        assert!(monitor.record(latency(100, 300)).await.is_empty());
+
+        monitor.record(latency(200, 1_500)).await;
+        monitor.record(latency(200, 1_500)).await;
+        let messages = monitor.record(latency(200, 1_500)).await;
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("breached"));
+        assert!(messages[0].contains("land 1.5s"));
+        // Still breached: no repeat alert
+        assert!(monitor.record(latency(200, 1_500)).await.is_empty());
+
+        monitor.record(latency(100, 300)).await;
+        let messages = monitor.record(latency(100, 300)).await;
+        assert!(messages[0].contains("recovered"));
+    }
+}
@@ -0,0 +1,317 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+};
+
+use crate::{core::tx::TxSigner, engine::position::ClosedTrade};
+
+// Configuration constants
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 240.0;
+const MINT_EDGE_CHARS: usize = 4;
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SIGNATURE_FILE: &str = "snapshot.json.sig";
+
+/// `AbCd…wXyZ`: enough to tell trades apart without pointing at the exact token
+pub fn truncate_mint(mint: &str) -> String {
+    let chars = mint.chars().collect::<Vec<_>>();
+    if chars.len() <= MINT_EDGE_CHARS * 2 {
+        return "…".to_string();
+    }
+    let head = chars[..MINT_EDGE_CHARS].iter().collect::<String>();
+    let tail = chars[chars.len() - MINT_EDGE_CHARS..]
+        .iter()
+        .collect::<String>();
+    format!("{}…{}", head, tail)
+}
+
+/// Cumulative net PnL after a trade closed
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    pub at: DateTime<Utc>,
+    pub pnl_sol: f64,
+}
+
+/// A closed trade with nothing that identifies the wallet or the exact mint
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicTrade {
+    pub mint: String,
+    pub closed_at: DateTime<Utc>,
+    pub hold_secs: i64,
+    pub pnl_sol: f64,
+    pub return_pct: f64,
+}
+
+/// Shareable performance summary built from the trade log
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub generated_at: DateTime<Utc>,
+    pub trades: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub net_pnl_sol: f64,
+    pub equity: Vec<EquityPoint>,
+    /// Most recent first
+    pub recent: Vec<PublicTrade>,
+}
+
+impl Snapshot {
+    /// Summarises `trades` (oldest first), listing the last `recent` of them
+    pub fn build(trades: &[ClosedTrade], recent: usize, generated_at: DateTime<Utc>) -> Self {
+        let mut trades = trades.to_vec();
+        trades.sort_by_key(|trade| trade.closed_at);
+
+        let mut cumulative = 0;
+        let equity = trades
+            .iter()
+            .map(|trade| {
+                cumulative += trade.pnl();
+                EquityPoint {
+                    at: trade.closed_at,
+                    pnl_sol: lamports_to_signed_sol(cumulative),
+                }
+            })
+            .collect();
+        let wins = trades.iter().filter(|trade| trade.pnl() > 0).count();
+        let recent = trades
+            .iter()
+            .rev()
+            .take(recent)
+            .map(|trade| PublicTrade {
+                mint: truncate_mint(&trade.mint),
+                closed_at: trade.closed_at,
+                hold_secs: (trade.closed_at - trade.opened_at).num_seconds(),
+                pnl_sol: lamports_to_signed_sol(trade.pnl()),
+                return_pct: match trade.sol_invested {
+                    0 => 0.0,
+                    invested => trade.pnl() as f64 / invested as f64 * 100.0,
+                },
+            })
+            .collect();
+
+        Self {
+            generated_at,
+            trades: trades.len(),
+            wins,
+            win_rate: match trades.len() {
+                0 => 0.0,
+                n => wins as f64 / n as f64 * 100.0,
+            },
+            net_pnl_sol: lamports_to_signed_sol(cumulative),
+            equity,
+            recent,
+        }
+    }
+
+    /// Equity curve as SVG polyline points, scaled to the chart
+    fn chart_points(&self) -> String {
+        let values = std::iter::once(0.0)
+            .chain(self.equity.iter().map(|point| point.pnl_sol))
+            .collect::<Vec<_>>();
+        let (min, max) = values
+            .iter()
+            .fold((0.0_f64, 0.0_f64), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+        let range = if max > min { max - min } else { 1.0 };
+        let step = CHART_WIDTH / (values.len().max(2) - 1) as f64;
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let y = CHART_HEIGHT - (v - min) / range * CHART_HEIGHT;
+                format!("{:.1},{:.1}", i as f64 * step, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Self-contained page: no scripts, fonts or requests to anywhere
+    pub fn to_html(&self) -> String {
+        let rows = self
+            .recent
+            .iter()
+            .map(|trade| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}m</td><td class=\"{}\">{:+.4}</td><td>{:+.1}%</td></tr>",
+                    trade.closed_at.format("%Y-%m-%d %H:%M"),
+                    trade.mint,
+                    trade.hold_secs / 60,
+                    if trade.pnl_sol >= 0.0 { "up" } else { "down" },
+                    trade.pnl_sol,
+                    trade.return_pct,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            r##"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>Performance snapshot</title>
+<style>
+body {{ font-family: sans-serif; max-width: 860px; margin: 2em auto; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td, th {{ padding: 4px 8px; border-bottom: 1px solid #ddd; text-align: left; }}
+.up {{ color: #1a7f37; }} .down {{ color: #cf222e; }}
+</style></head><body>
+<h1>Performance snapshot</h1>
+<p>Generated {generated} · {trades} trades · win rate {win_rate:.1}% · net {net:+.4} SOL</p>
+<svg width="{width}" height="{height}" viewBox="0 0 {width} {height}">
+<polyline fill="none" stroke="#0969da" stroke-width="2" points="{points}"/>
+</svg>
+<h2>Recent trades</h2>
+<table><tr><th>Closed (UTC)</th><th>Mint</th><th>Held</th><th>PnL (SOL)</th><th>Return</th></tr>
+{rows}
+</table></body></html>
+"##,
+            generated = self.generated_at.format("%Y-%m-%d %H:%M UTC"),
+            trades = self.trades,
+            win_rate = self.win_rate,
+            net = self.net_pnl_sol,
+            width = CHART_WIDTH,
+            height = CHART_HEIGHT,
+            points = self.chart_points(),
+            rows = rows,
+        )
+    }
+
+    /// Writes `snapshot.json` and `snapshot.html` into `dir`
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        fs::write(dir.join(SNAPSHOT_FILE), serde_json::to_vec_pretty(self)?)?;
+        fs::write(dir.join("snapshot.html"), self.to_html())?;
+        Ok(())
+    }
+}
+
+/// Detached signature over `snapshot.json`. Publishing it names the signing wallet, which is
+/// the point: it shows the report was released by whoever controls that wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportSignature {
+    pub file: String,
+    pub signer: String,
+    pub signature: String,
+}
+
+/// Signs the `snapshot.json` in `dir` with `keypair` and writes `snapshot.json.sig` beside it
+pub fn sign_report(dir: &Path, keypair: &TxSigner) -> Result<ReportSignature> {
+    let report = fs::read(dir.join(SNAPSHOT_FILE))
+        .with_context(|| format!("No {} in {}", SNAPSHOT_FILE, dir.display()))?;
+    let signed = ReportSignature {
+        file: SNAPSHOT_FILE.to_string(),
+        signer: keypair.pubkey().to_string(),
+        signature: keypair.sign_message(&report).to_string(),
+    };
+    fs::write(
+        dir.join(SIGNATURE_FILE),
+        serde_json::to_vec_pretty(&signed)?,
+    )?;
+    Ok(signed)
+}
+
+/// Checks `snapshot.json.sig` against the `snapshot.json` in `dir`, returning the signature if
+/// it holds
+pub fn verify_report(dir: &Path) -> Result<ReportSignature> {
+    let signed: ReportSignature = serde_json::from_slice(
+        &fs::read(dir.join(SIGNATURE_FILE))
+            .with_context(|| format!("No {} in {}", SIGNATURE_FILE, dir.display()))?,
+    )?;
+    let report = fs::read(dir.join(SNAPSHOT_FILE))?;
+    let signer = Pubkey::from_str(&signed.signer).context("Invalid signer in signature file")?;
+    let signature =
+        Signature::from_str(&signed.signature).context("Invalid signature in signature file")?;
+    if !signature.verify(&signer.to_bytes(), &report) {
+        return Err(anyhow!(
+            "{} was not signed by {}",
+            signed.file,
+            signed.signer
+        ));
+    }
+    Ok(signed)
+}
+
+fn lamports_to_signed_sol(lamports: i64) -> f64 {
+    let sol = lamports_to_sol(lamports.unsigned_abs());
+    if lamports < 0 {
+        -sol
+    } else {
+        sol
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::storage::EventTime;
+    use chrono::Duration;
+    use solana_sdk::signature::Keypair;
+
+    fn trade(mint: &str, minutes: i64, invested: u64, returned: u64) -> ClosedTrade {
+        let opened_at = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        ClosedTrade {
+            mint: mint.to_string(),
+            opened_at,
+            closed_at: opened_at + Duration::minutes(minutes),
+            sol_invested: invested,
+            sol_returned: returned,
+            fees_paid: 0,
+            target: None,
+            time: EventTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_stats() {
+        let trades = vec![
+            trade(
+                "MintAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAApump",
+                5,
+                1_000_000_000,
+                1_500_000_000,
+            ),
+            trade(
+                "MintBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBpump",
+                10,
+                1_000_000_000,
+                750_000_000,
+            ),
+        ];
+        let snapshot = Snapshot::build(&trades, 1, Utc::now());
+        assert_eq!(snapshot.trades, 2);
+        assert_eq!(snapshot.win_rate, 50.0);
+        assert_eq!(snapshot.net_pnl_sol, 0.25);
+        assert_eq!(snapshot.equity.len(), 2);
+        assert_eq!(snapshot.recent.len(), 1);
+        assert_eq!(snapshot.recent[0].pnl_sol, -0.25);
+        assert_eq!(snapshot.recent[0].return_pct, -25.0);
+    }
+
+    #[test]
+    fn test_full_mints_never_leave() {
+        let mint = "7GCihgDB8fe6KNjn2MYtkzZcRjQy3t9GHdC8uHYmW2hr";
+        let snapshot = Snapshot::build(&[trade(mint, 1, 100, 200)], 10, Utc::now());
+        assert_eq!(snapshot.recent[0].mint, "7GCi…W2hr");
+        assert!(!snapshot.to_html().contains(mint));
+        assert!(!serde_json::to_string(&snapshot).unwrap().contains(mint));
+    }
+
+    #[test]
+    fn test_signed_report() {
+        let dir = std::env::temp_dir().join(format!("snapshot-{}", std::process::id()));
+        Snapshot::build(&[trade("Mint", 5, 100, 200)], 1, Utc::now())
+            .write(&dir)
+            .unwrap();
+        let keypair = Keypair::new();
+        let signed = sign_report(&dir, &keypair).unwrap();
+        assert_eq!(verify_report(&dir).unwrap(), signed);
+
+        // Any edit to the report breaks the signature
+        fs::write(dir.join(SNAPSHOT_FILE), "{}").unwrap();
+        assert!(verify_report(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
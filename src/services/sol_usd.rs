@@ -0,0 +1,138 @@
+use std::{
+    env,
+    str::FromStr,
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use tokio::time::{sleep, Instant};
+
+use crate::common::utils::{log_message, AppState};
+
+// Configuration constants
+const HERMES_URL: &str = "https://hermes.pyth.network";
+/// Pyth's Crypto.SOL/USD price feed
+const SOL_USD_FEED_ID: &str = "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+const DEFAULT_POLL_SECS: u64 = 5;
+// A price older than this is not used to convert anything
+const MAX_AGE_SECS: u64 = 120;
+
+/// Latest SOL/USD price, shared by everything that sizes, limits or reports in USD
+pub static SOL_USD: LazyLock<SolUsd> = LazyLock::new(SolUsd::default);
+
+#[derive(Debug, Default)]
+pub struct SolUsd {
+    latest: RwLock<Option<(f64, Instant)>>,
+}
+
+impl SolUsd {
+    pub fn set(&self, price: f64) {
+        *self.latest.write().unwrap() = Some((price, Instant::now()));
+    }
+
+    /// USD per SOL, or `None` until the feed has a recent price
+    pub fn price(&self) -> Option<f64> {
+        self.latest
+            .read()
+            .unwrap()
+            .filter(|(_, at)| at.elapsed() < Duration::from_secs(MAX_AGE_SECS))
+            .map(|(price, _)| price)
+    }
+
+    pub fn lamports_to_usd(&self, lamports: i64) -> Option<f64> {
+        Some(lamports as f64 / 1e9 * self.price()?)
+    }
+
+    pub fn usd_to_lamports(&self, usd: f64) -> Option<u64> {
+        Some((usd / self.price()? * 1e9) as u64)
+    }
+}
+
+/// ` ($1.23)` for `lamports` at the current SOL price, or nothing without one
+pub fn usd_suffix(lamports: i64) -> String {
+    SOL_USD
+        .lamports_to_usd(lamports)
+        .map_or(String::new(), |usd| format!(" (${:.2})", usd))
+}
+
+/// Reads the SOL/USD price out of a Hermes `updates/price/latest` response
+pub fn parse_hermes(response: &Value) -> Option<f64> {
+    let price = &response["parsed"][0]["price"];
+    let mantissa = i64::from_str(price["price"].as_str()?).ok()?;
+    let expo = price["expo"].as_i64()?;
+    Some(mantissa as f64 * 10f64.powi(expo as i32)).filter(|price| *price > 0.0)
+}
+
+async fn fetch_pyth(http: &reqwest::Client, url: &str) -> Result<f64> {
+    let response: Value = http
+        .get(format!("{}/v2/updates/price/latest", url))
+        .query(&[("ids[]", SOL_USD_FEED_ID)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    parse_hermes(&response).ok_or_else(|| anyhow!("No SOL/USD price in the Pyth response"))
+}
+
+/// Keeps `SOL_USD` current from Pyth's Hermes service (`PYTH_HERMES_URL`), every
+/// `SOL_USD_POLL_SECS`, falling back to the off-chain price providers when Pyth can't be
+/// reached. Runs forever
+pub async fn run_sol_usd_feed(state: AppState) {
+    let url = env::var("PYTH_HERMES_URL").unwrap_or_else(|_| HERMES_URL.to_string());
+    let interval = Duration::from_secs(
+        env::var("SOL_USD_POLL_SECS")
+            .ok()
+            .and_then(|v| u64::from_str(&v).ok())
+            .unwrap_or(DEFAULT_POLL_SECS)
+            .max(1),
+    );
+    let http = reqwest::Client::new();
+    let mut failing = false;
+    loop {
+        let price = match fetch_pyth(&http, &url).await {
+            Ok(price) => Ok(price),
+            Err(pyth) => state
+                .prices
+                .sol_price_usd()
+                .await
+                .map_err(|fallback| anyhow!("{}; fallback: {}", pyth, fallback)),
+        };
+        match price {
+            Ok(price) => {
+                SOL_USD.set(price);
+                failing = false;
+            }
+            Err(e) if !failing => {
+                let _ = log_message(&format!("SOL/USD price unavailable: {}", e)).await;
+                failing = true;
+            }
+            Err(_) => {}
+        }
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reads_pyth_prices_and_converts() {
+        let response = json!({ "parsed": [{
+            "id": SOL_USD_FEED_ID,
+            "price": { "price": "15025000000", "conf": "7000000", "expo": -8, "publish_time": 1 }
+        }]});
+        assert!((parse_hermes(&response).unwrap() - 150.25).abs() < 1e-9);
+        assert!(parse_hermes(&json!({ "parsed": [] })).is_none());
+
+        let feed = SolUsd::default();
+        assert_eq!(feed.usd_to_lamports(10.0), None);
+        feed.set(200.0);
+        assert_eq!(feed.usd_to_lamports(10.0), Some(50_000_000));
+        assert_eq!(feed.lamports_to_usd(-500_000_000), Some(-100.0));
+    }
+}
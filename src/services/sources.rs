@@ -0,0 +1,727 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    env,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    time::{sleep, Instant},
+};
+use tokio_tungstenite::{
+    connect_async, tungstenite::Message as WsMessage, MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{
+    common::{
+        cache::BoundedCache,
+        storage::{data_path, load_json, save_json},
+        utils::{log_message, AppState},
+    },
+    core::fault,
+    dex::{pump::PUMP_PROGRAM_ID, raydium::AMM_PROGRAM_ID},
+};
+
+// Configuration constants
+const CHECKPOINT_FILE: &str = "signal_checkpoints.json";
+const CHECKPOINT_FLUSH_SECS: u64 = 1;
+const RECONNECT_DELAY_MS: u64 = 1_000;
+const CHANNEL_CAPACITY: usize = 4_096;
+const SEEN_SIGNATURES: usize = 50_000;
+const SEEN_TTL_SECS: u64 = 600;
+// ~2 minutes; copying trades older than this on restart does more harm than missing them
+const DEFAULT_REPLAY_SLOTS: u64 = 300;
+const REPLAY_SIGNATURE_LIMIT: usize = 100;
+const MAX_WEBHOOK_BYTES: usize = 4 * 1024 * 1024;
+
+/// One transaction from a feed, always in the `transactionSubscribe` notification shape
+/// (`params.result.{signature, slot, transaction}`) so the parsers never care where it came from
+#[derive(Debug, Clone)]
+pub struct SignalEvent {
+    pub source: &'static str,
+    pub signature: String,
+    pub slot: u64,
+    pub json: Value,
+}
+
+impl SignalEvent {
+    /// Reads a `transactionSubscribe` notification; `None` for anything else on the socket
+    pub fn from_notification(source: &'static str, json: Value) -> Option<Self> {
+        let result = &json["params"]["result"];
+        let signature = result["signature"].as_str()?.to_string();
+        let slot = result["slot"].as_u64().unwrap_or(0);
+        Some(Self {
+            source,
+            signature,
+            slot,
+            json,
+        })
+    }
+
+    /// Wraps a jsonParsed `getTransaction` result, the shape webhooks and backfills return
+    pub fn from_transaction(source: &'static str, tx: &Value) -> Option<Self> {
+        let signature = tx["transaction"]["signatures"][0].as_str()?;
+        let slot = tx["slot"].as_u64().unwrap_or(0);
+        let json = json!({ "params": { "result": {
+            "signature": signature,
+            "slot": slot,
+            "transaction": { "transaction": tx["transaction"], "meta": tx["meta"] },
+        }}});
+        Self::from_notification(source, json)
+    }
+}
+
+/// Where a source last got to: the newest slot whose events were fully handled
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub signature: String,
+}
+
+/// A feed of copy-trading signals.
+///
+/// Delivery is at-least-once: after any disconnect or restart the hub reconnects and asks the
+/// source to `replay` from its last checkpoint, so an event can arrive more than once (the hub
+/// drops repeats by signature) but none the source can still see is lost. Order is by arrival,
+/// only roughly by slot, and there is no ordering between sources.
+#[async_trait]
+pub trait SignalSource: Send {
+    /// Stable name; checkpoints are stored under it
+    fn name(&self) -> &'static str;
+
+    /// (Re)opens the feed; called before the first `next` and after any error
+    async fn connect(&mut self) -> Result<()>;
+
+    /// Waits for the next event; an error drops the connection
+    async fn next(&mut self) -> Result<SignalEvent>;
+
+    /// Events after `checkpoint` the feed may have missed while down, oldest first
+    async fn replay(&mut self, _checkpoint: &Checkpoint) -> Result<Vec<SignalEvent>> {
+        Ok(vec![])
+    }
+}
+
+/// Events of one source taken up but not finished, and those finished ahead of them
+#[derive(Debug, Default)]
+struct Progress {
+    in_flight: BTreeMap<u64, usize>,
+    done: BTreeMap<u64, String>,
+}
+
+impl Progress {
+    fn begin(&mut self, slot: u64) {
+        *self.in_flight.entry(slot).or_default() += 1;
+    }
+
+    /// Finishes an event; the newest finished one no unfinished event precedes, if any
+    fn finish(&mut self, slot: u64, signature: &str) -> Option<Checkpoint> {
+        if let Some(count) = self.in_flight.get_mut(&slot) {
+            *count -= 1;
+            if *count == 0 {
+                self.in_flight.remove(&slot);
+            }
+        }
+        self.done.insert(slot, signature.to_string());
+        // Replay resumes after the checkpoint's slot, so it stays below anything unfinished
+        let bound = self.in_flight.keys().next().copied().unwrap_or(u64::MAX);
+        let slot = *self.done.range(..bound).next_back()?.0;
+        let later = self.done.split_off(&(slot + 1));
+        let signature = std::mem::replace(&mut self.done, later).remove(&slot)?;
+        Some(Checkpoint { slot, signature })
+    }
+}
+
+struct Checkpoints {
+    saved: HashMap<String, Checkpoint>,
+    progress: HashMap<&'static str, Progress>,
+    last_flush: Instant,
+}
+
+/// Per-source checkpoints, persisted to the data directory at most once a second. A checkpoint
+/// only passes an event once it and every event before it from that source are finished
+pub struct CheckpointStore {
+    path: PathBuf,
+    inner: Mutex<Checkpoints>,
+}
+
+impl CheckpointStore {
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let saved = load_json(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            inner: Mutex::new(Checkpoints {
+                saved,
+                progress: HashMap::new(),
+                last_flush: Instant::now(),
+            }),
+        })
+    }
+
+    pub async fn get(&self, source: &str) -> Option<Checkpoint> {
+        self.inner.lock().unwrap().saved.get(source).cloned()
+    }
+
+    /// Marks `event` taken up; the checkpoint stays before it until it is finished
+    pub fn begin(&self, event: &SignalEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.progress.entry(event.source).or_default().begin(event.slot);
+    }
+
+    /// Marks `event` finished and moves the source's checkpoint up as far as that allows,
+    /// never backwards. Returns the checkpoints to save when a flush is due
+    fn finish(&self, event: &SignalEvent) -> Option<HashMap<String, Checkpoint>> {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        let progress = inner.progress.entry(event.source).or_default();
+        let checkpoint = progress.finish(event.slot, &event.signature)?;
+        let current = inner.saved.entry(event.source.to_string()).or_default();
+        if checkpoint.slot < current.slot {
+            return None;
+        }
+        *current = checkpoint;
+        if inner.last_flush.elapsed() < Duration::from_secs(CHECKPOINT_FLUSH_SECS) {
+            return None;
+        }
+        inner.last_flush = Instant::now();
+        Some(inner.saved.clone())
+    }
+
+    /// Finishes `event`, saving the checkpoints if a flush is due
+    pub async fn advance(&self, event: &SignalEvent) -> Result<()> {
+        match self.finish(event) {
+            Some(checkpoints) => save_json(&self.path, &checkpoints).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Saves every checkpoint now, whenever the last flush was
+    pub async fn flush(&self) -> Result<()> {
+        let checkpoints = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.last_flush = Instant::now();
+            inner.saved.clone()
+        };
+        save_json(&self.path, &checkpoints).await
+    }
+}
+
+/// Holds an event open; it is acked once the last clone is dropped, i.e. once every copy it
+/// started has completed or given up for good
+#[derive(Clone)]
+pub struct EventAck(Arc<PendingAck>);
+
+struct PendingAck {
+    event: SignalEvent,
+    checkpoints: Arc<CheckpointStore>,
+}
+
+impl Drop for PendingAck {
+    fn drop(&mut self) {
+        let Some(checkpoints) = self.checkpoints.finish(&self.event) else {
+            return;
+        };
+        let path = self.checkpoints.path.clone();
+        tokio::spawn(async move {
+            if let Err(e) = save_json(&path, &checkpoints).await {
+                let _ = log_message(&format!("Failed to save signal checkpoint: {}", e)).await;
+            }
+        });
+    }
+}
+
+/// Runs every source into one stream of deduplicated events and tracks what was handled
+pub struct SignalHub {
+    tx: mpsc::Sender<SignalEvent>,
+    rx: mpsc::Receiver<SignalEvent>,
+    checkpoints: Arc<CheckpointStore>,
+    seen: BoundedCache<String, ()>,
+}
+
+impl SignalHub {
+    pub fn new() -> Result<Self> {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        Ok(Self {
+            tx,
+            rx,
+            checkpoints: Arc::new(CheckpointStore::load(data_path(CHECKPOINT_FILE))?),
+            seen: BoundedCache::from_env(
+                "signals",
+                SEEN_SIGNATURES,
+                Some(Duration::from_secs(SEEN_TTL_SECS)),
+            ),
+        })
+    }
+
+    /// Starts pumping `source` into the hub, reconnecting and replaying on every failure
+    pub fn spawn(&self, source: Box<dyn SignalSource>) {
+        tokio::spawn(run_source(
+            source,
+            self.tx.clone(),
+            self.checkpoints.clone(),
+        ));
+    }
+
+    /// Next event not seen before. This is the only dedup: a trade several sources deliver, or
+    /// one replays, reaches the copy path and the target's flow once
+    pub async fn recv(&mut self) -> Option<SignalEvent> {
+        loop {
+            let event = self.rx.recv().await?;
+            if self
+                .seen
+                .insert(event.signature.clone(), ())
+                .await
+                .is_none()
+            {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Takes up `event`; it is acked once the returned token and all its clones are dropped
+    pub fn track(&self, event: &SignalEvent) -> EventAck {
+        self.checkpoints.begin(event);
+        EventAck(Arc::new(PendingAck {
+            event: event.clone(),
+            checkpoints: self.checkpoints.clone(),
+        }))
+    }
+
+    /// Writes out the checkpoints of everything acked so far, before exiting
+    pub async fn flush(&self) -> Result<()> {
+        self.checkpoints.flush().await
+    }
+}
+
+async fn run_source(
+    mut source: Box<dyn SignalSource>,
+    tx: mpsc::Sender<SignalEvent>,
+    checkpoints: Arc<CheckpointStore>,
+) {
+    let name = source.name();
+    loop {
+        if let Err(e) = source.connect().await {
+            let _ = log_message(&format!("Signal source {} failed to connect: {}", name, e)).await;
+            sleep(Duration::from_millis(RECONNECT_DELAY_MS)).await;
+            continue;
+        }
+        // Subscribed first, so anything between the checkpoint and now is replayed or live
+        if let Some(checkpoint) = checkpoints.get(name).await {
+            match source.replay(&checkpoint).await {
+                Ok(events) => {
+                    if !events.is_empty() {
+                        let _ = log_message(&format!(
+                            "Replaying {} events from {} since slot {}",
+                            events.len(),
+                            name,
+                            checkpoint.slot
+                        ))
+                        .await;
+                    }
+                    for event in events {
+                        if tx.send(event).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ =
+                        log_message(&format!("Signal source {} replay failed: {}", name, e)).await;
+                }
+            }
+        }
+        loop {
+            match source.next().await {
+                Ok(event) => {
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    let _ = log_message(&format!("Signal source {} dropped: {}", name, e)).await;
+                    break;
+                }
+            }
+        }
+        sleep(Duration::from_millis(RECONNECT_DELAY_MS)).await;
+    }
+}
+
+/// Whether any account of a jsonParsed transaction is `pubkey`
+fn touches_account(tx: &Value, pubkey: &str) -> bool {
+    tx["transaction"]["message"]["accountKeys"]
+        .as_array()
+        .is_some_and(|keys| {
+            keys.iter()
+                .any(|key| key["pubkey"].as_str() == Some(pubkey))
+        })
+}
+
+/// Copy targets' recent transactions after `checkpoint`, within `max_slots` of the tip
+async fn backfill_targets(
+    state: &AppState,
+    source: &'static str,
+    checkpoint: &Checkpoint,
+    max_slots: u64,
+    exclude: &str,
+) -> Result<Vec<SignalEvent>> {
+    let client = &state.rpc_nonblocking_client;
+    let tip = client.get_slot().await?;
+    let from_slot = checkpoint.slot.max(tip.saturating_sub(max_slots));
+    let mut events = vec![];
+    for target in &state.settings.current().targets {
+        let signatures = client
+            .get_signatures_for_address_with_config(
+                &Pubkey::from_str(target)?,
+                GetConfirmedSignaturesForAddress2Config {
+                    limit: Some(REPLAY_SIGNATURE_LIMIT),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        for status in signatures
+            .iter()
+            .filter(|status| status.slot > from_slot && status.err.is_none())
+        {
+            let tx = client
+                .get_transaction_with_config(
+                    &Signature::from_str(&status.signature)?,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::JsonParsed),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await?;
+            let tx = serde_json::to_value(&tx)?;
+            if touches_account(&tx, exclude) {
+                continue;
+            }
+            events.extend(SignalEvent::from_transaction(source, &tx));
+        }
+    }
+    events.sort_by_key(|event| event.slot);
+    Ok(events)
+}
+
+fn replay_slots() -> u64 {
+    env::var("SIGNAL_REPLAY_SLOTS")
+        .ok()
+        .and_then(|v| u64::from_str(&v).ok())
+        .unwrap_or(DEFAULT_REPLAY_SLOTS)
+}
+
+/// `transactionSubscribe` over the RPC websocket (Helius and compatible providers), covering
+/// every pump.fun and Raydium v4 transaction
+pub struct WsSource {
+    url: String,
+    /// Accounts whose transactions are never delivered (`JUP_PUBKEY`)
+    exclude: String,
+    state: AppState,
+    ws: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+}
+
+impl WsSource {
+    pub fn new(url: String, exclude: String, state: AppState) -> Self {
+        Self {
+            url,
+            exclude,
+            state,
+            ws: None,
+        }
+    }
+}
+
+#[async_trait]
+impl SignalSource for WsSource {
+    fn name(&self) -> &'static str {
+        "ws"
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let (mut ws, _) = connect_async(self.url.as_str())
+            .await
+            .context("Failed to connect to WebSocket server")?;
+        let subscription_message = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "transactionSubscribe",
+            "params": [
+                {
+                    "failed": false,
+                    "accountInclude": [AMM_PROGRAM_ID.to_string(), PUMP_PROGRAM_ID.to_string()],
+                    "accountExclude": [self.exclude],
+                },
+                {
+                    "commitment": "processed",
+                    "encoding": "jsonParsed",
+                    "transactionDetails": "full",
+                    "maxSupportedTransactionVersion": 0
+                }
+            ]
+        });
+        ws.send(subscription_message.to_string().into()).await?;
+        self.ws = Some(ws);
+        Ok(())
+    }
+
+    async fn next(&mut self) -> Result<SignalEvent> {
+        let name = self.name();
+        let ws = self.ws.as_mut().ok_or_else(|| anyhow!("Not connected"))?;
+        loop {
+            match ws.next().await {
+                Some(Ok(WsMessage::Text(text))) => {
+                    if fault::drop_ws_message() {
+                        continue;
+                    }
+                    let Ok(json) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if let Some(event) = SignalEvent::from_notification(name, json) {
+                        return Ok(event);
+                    }
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    self.ws = None;
+                    return Err(e.into());
+                }
+                None => {
+                    self.ws = None;
+                    return Err(anyhow!("WebSocket closed"));
+                }
+            }
+        }
+    }
+
+    async fn replay(&mut self, checkpoint: &Checkpoint) -> Result<Vec<SignalEvent>> {
+        backfill_targets(
+            &self.state,
+            self.name(),
+            checkpoint,
+            replay_slots(),
+            &self.exclude,
+        )
+        .await
+    }
+}
+
+/// Transactions POSTed to `SIGNAL_WEBHOOK_ADDR` as jsonParsed `getTransaction` results, one or
+/// an array per request. Providers retry failed deliveries themselves, so nothing is replayed.
+pub struct WebhookSource {
+    addr: String,
+    /// Required `Authorization` header value, from `SIGNAL_WEBHOOK_TOKEN`
+    token: Option<String>,
+    listener: Option<TcpListener>,
+    pending: VecDeque<SignalEvent>,
+}
+
+impl WebhookSource {
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            addr: env::var("SIGNAL_WEBHOOK_ADDR").ok()?,
+            token: env::var("SIGNAL_WEBHOOK_TOKEN").ok(),
+            listener: None,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Reads one request and returns its events, or an error status for the response
+    async fn receive(&self, stream: &mut TcpStream) -> Result<Vec<SignalEvent>, &'static str> {
+        let mut request = vec![];
+        let mut chunk = vec![0; 64 * 1024];
+        let (head_len, content_length) = loop {
+            let read = stream
+                .read(&mut chunk)
+                .await
+                .map_err(|_| "400 Bad Request")?;
+            if read == 0 {
+                return Err("400 Bad Request");
+            }
+            request.extend_from_slice(&chunk[..read]);
+            if request.len() > MAX_WEBHOOK_BYTES {
+                return Err("413 Payload Too Large");
+            }
+            if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&request[..end]).to_string();
+                if !head.starts_with("POST ") {
+                    return Err("405 Method Not Allowed");
+                }
+                if let Some(token) = &self.token {
+                    if header(&head, "authorization") != Some(token.as_str()) {
+                        return Err("401 Unauthorized");
+                    }
+                }
+                let length = header(&head, "content-length")
+                    .and_then(|v| usize::from_str(v).ok())
+                    .ok_or("411 Length Required")?;
+                break (end + 4, length);
+            }
+        };
+        if content_length > MAX_WEBHOOK_BYTES {
+            return Err("413 Payload Too Large");
+        }
+        while request.len() < head_len + content_length {
+            let read = stream
+                .read(&mut chunk)
+                .await
+                .map_err(|_| "400 Bad Request")?;
+            if read == 0 {
+                return Err("400 Bad Request");
+            }
+            request.extend_from_slice(&chunk[..read]);
+        }
+        let body = serde_json::from_slice::<Value>(&request[head_len..head_len + content_length])
+            .map_err(|_| "400 Bad Request")?;
+        Ok(webhook_events(self.name(), &body))
+    }
+}
+
+pub(crate) fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+/// Events in a webhook body: one transaction or an array of them
+pub fn webhook_events(source: &'static str, body: &Value) -> Vec<SignalEvent> {
+    match body.as_array() {
+        Some(txs) => txs
+            .iter()
+            .filter_map(|tx| SignalEvent::from_transaction(source, tx))
+            .collect(),
+        None => SignalEvent::from_transaction(source, body)
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[async_trait]
+impl SignalSource for WebhookSource {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        if self.listener.is_none() {
+            self.listener = Some(TcpListener::bind(&self.addr).await?);
+            let _ = log_message(&format!("Accepting signal webhooks on {}", self.addr)).await;
+        }
+        Ok(())
+    }
+
+    async fn next(&mut self) -> Result<SignalEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            let listener = self
+                .listener
+                .as_ref()
+                .ok_or_else(|| anyhow!("Not listening"))?;
+            let (mut stream, _) = listener.accept().await?;
+            let status = match self.receive(&mut stream).await {
+                Ok(events) => {
+                    self.pending.extend(events);
+                    "200 OK"
+                }
+                Err(status) => status,
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(slot: u64, signature: &str) -> SignalEvent {
+        SignalEvent {
+            source: "test",
+            signature: signature.to_string(),
+            slot,
+            json: Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_only_moves_forward() {
+        let path = env::temp_dir().join(format!("checkpoints-{}.json", std::process::id()));
+        let store = CheckpointStore::load(path.clone()).unwrap();
+        assert_eq!(store.get("test").await, None);
+        store.advance(&event(10, "b")).await.unwrap();
+        store.advance(&event(9, "a")).await.unwrap();
+        assert_eq!(
+            store.get("test").await,
+            Some(Checkpoint {
+                slot: 10,
+                signature: "b".to_string()
+            })
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_checkpoint_waits_for_earlier_events() {
+        let mut progress = Progress::default();
+        progress.begin(5);
+        progress.begin(7);
+        progress.begin(9);
+        // Later events finishing first leave the checkpoint before the unfinished one
+        assert_eq!(progress.finish(9, "c"), None);
+        assert_eq!(
+            progress.finish(5, "a"),
+            Some(Checkpoint {
+                slot: 5,
+                signature: "a".to_string()
+            })
+        );
+        assert_eq!(
+            progress.finish(7, "b"),
+            Some(Checkpoint {
+                slot: 9,
+                signature: "c".to_string()
+            })
+        );
+        assert!(progress.done.is_empty() && progress.in_flight.is_empty());
+    }
+
+    #[test]
+    fn test_webhook_bodies_become_notifications() {
+        let tx = json!({
+            "slot": 42,
+            "transaction": { "signatures": ["sig"], "message": { "accountKeys": [] } },
+            "meta": { "err": null }
+        });
+        let events = webhook_events("webhook", &json!([tx.clone(), { "slot": 1 }]));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].signature, "sig");
+        assert_eq!(events[0].slot, 42);
+        let result = &events[0].json["params"]["result"];
+        assert_eq!(result["transaction"]["meta"]["err"], Value::Null);
+        assert!(result["transaction"]["transaction"]["message"].is_object());
+        assert_eq!(webhook_events("webhook", &tx).len(), 1);
+    }
+}